@@ -13,9 +13,18 @@ pub const STICKY_CHOICE_MARKER: char = '+';
 /// Marker for a gather point.
 pub const GATHER_MARKER: char = '-';
 
+/// Marker which opens the label of a choice or gather, written directly after its markers.
+pub const LABEL_OPEN_MARKER: char = '(';
+
+/// Marker which closes the label of a choice or gather.
+pub const LABEL_CLOSE_MARKER: char = ')';
+
 /// Marker for a divert to another knot, stitch or label in the story.
 pub const DIVERT_MARKER: &'static str = "->";
 
+/// Marker for a thread which gathers the content and choices of another knot.
+pub const THREAD_MARKER: &str = "<-";
+
 /// Marker for glue which joins separate lines together without a newline character.
 pub const GLUE_MARKER: &'static str = "<>";
 
@@ -25,6 +34,15 @@ pub const GLUE_MARKER: &'static str = "<>";
 /// (or the end of the line) will be a single tag.
 pub const TAG_MARKER: char = '#';
 
+/// Marker which escapes the character after it, so that it is read as text.
+///
+/// Braces, separators, tag and divert markers and others can be written in text by
+/// preceding them with a backslash: `\{`. The backslash is removed from the text.
+pub const ESCAPE_MARKER: char = '\\';
+
+/// Name of the tag which sets the weight of a choice, written as `# weight: 3`.
+pub const CHOICE_WEIGHT_TAG: &str = "weight";
+
 /********************
  * Sequence markers *
  ********************/
@@ -41,6 +59,29 @@ pub const SHUFFLE_MARKER: char = '~';
 /// Marker for sequence item separator.
 pub const SEQUENCE_SEPARATOR: &'static str = "|";
 
+/// Keyword for a multi-line sequence block.
+pub const SEQUENCE_BLOCK_KEYWORD: &str = "stopping";
+
+/// Keyword for a multi-line cycle block.
+pub const CYCLE_BLOCK_KEYWORD: &str = "cycle";
+
+/// Keyword for a multi-line once-only block.
+pub const ONCE_ONLY_BLOCK_KEYWORD: &str = "once";
+
+/// Keyword for a multi-line shuffle block.
+pub const SHUFFLE_BLOCK_KEYWORD: &str = "shuffle";
+
+/// Keyword for the branch of a multi-line conditional block which is used if its
+/// condition is not fulfilled.
+pub const ELSE_BLOCK_KEYWORD: &str = "else";
+
+/// Marker for the start of a multi-line conditional block whose branches each have
+/// a condition.
+pub const BLOCK_START_MARKER: &str = "{";
+
+/// Marker for the end of a multi-line block.
+pub const BLOCK_END_MARKER: &str = "}";
+
 /****************
  * Knot markers *
  ****************/
@@ -51,6 +92,9 @@ pub const KNOT_MARKER: &'static str = "==";
 /// Marker for a stitch belonging to a knot.
 pub const STITCH_MARKER: &'static str = "=";
 
+/// Keyword which marks a knot as a function, which is called from expressions.
+pub const FUNCTION_MARKER: &str = "function";
+
 /************************
  * Comment line markers *
  ************************/
@@ -58,11 +102,9 @@ pub const STITCH_MARKER: &'static str = "=";
 /// Marker for line comments, which will be ignored when parsing a story.
 pub const LINE_COMMENT_MARKER: &'static str = "//";
 
-#[allow(dead_code)]
 /// Marker to begin multiline comments.
 pub const MULTILINE_COMMENT_BEGIN_MARKER: &'static str = "/*";
 
-#[allow(dead_code)]
 /// Marker to end multiline comments.
 pub const MULTILINE_COMMENT_END_MARKER: &'static str = "*/";
 
@@ -95,14 +137,23 @@ pub const CONST_MARKER: &'static str = "CONST";
 /// Marker for global variable.
 pub const VARIABLE_MARKER: &'static str = "VAR";
 
-#[allow(dead_code)]
 /// Marker for lists.
 pub const LIST_MARKER: &'static str = "LIST";
 
-#[allow(dead_code)]
-/// Variable assignment marker.
+/// Marker for logic lines, which assign values to variables.
 pub const ASSIGNMENT_MARKER: char = '~';
 
+/// Keyword for the declaration of a temporary variable in a logic line.
+pub const TEMPORARY_VARIABLE_MARKER: &str = "temp";
+
+/// Keyword for a logic line which returns from a function.
+pub const RETURN_MARKER: &str = "return";
+
+/// Maximum number of nested calls to functions in the story.
+///
+/// Guards against functions which recurse without end.
+pub const MAX_FUNCTION_CALL_DEPTH: usize = 64;
+
 /***********************
  * Meta data variables *
  ***********************/
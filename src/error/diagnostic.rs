@@ -0,0 +1,228 @@
+//! Flat list of problems found in a story, for tools which only need to report them.
+
+use std::fmt::{self, Write};
+
+use crate::{
+    error::{
+        parse::{knot::write_knot_error_kind, validate::ValidationError, ParseError},
+        utils::{write_line_information, MetaData},
+        ReadError,
+    },
+    log::Logger,
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Single problem found while reading and validating a story.
+///
+/// Created by [`validate_story_from_string`][crate::validate_story_from_string], which
+/// collects all errors and warnings from a story into a list of these.
+pub struct Diagnostic {
+    /// Whether the problem prevents the story from being read.
+    pub severity: Severity,
+    /// Description of the problem, including where it was found.
+    pub message: String,
+    /// Information about the origin of the line which caused the problem, if known.
+    pub meta_data: Option<MetaData>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Severity of a `Diagnostic`.
+pub enum Severity {
+    /// The story could not be read because of this problem.
+    Error,
+    /// The story can be read but the problem may cause unexpected behavior.
+    Warning,
+}
+
+impl Diagnostic {
+    /// Assert whether the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Assert whether the diagnostic is a warning.
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+
+    /// Construct an error diagnostic from something that prints its own line information.
+    fn error<T: fmt::Display>(error: &T, meta_data: Option<&MetaData>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: format!("{}", error),
+            meta_data: meta_data.cloned(),
+        }
+    }
+}
+
+/// Get a separate `Diagnostic` for every error in a `ReadError`.
+pub(crate) fn get_diagnostics_from_read_error(error: &ReadError) -> Vec<Diagnostic> {
+    match error {
+        ReadError::Empty | ReadError::InvalidVariableDefault { .. } | ReadError::IoError { .. } => {
+            vec![Diagnostic::error(error, None)]
+        }
+        ReadError::ParseError(err) => get_diagnostics_from_parse_error(err),
+        ReadError::ValidationError(err) => get_diagnostics_from_validation_error(err),
+        ReadError::FlagError(errors) => errors
+            .iter()
+            .map(|err| Diagnostic::error(err, Some(&err.meta_data)))
+            .collect(),
+        ReadError::IncludeError(errors) => errors
+            .iter()
+            .map(|err| Diagnostic::error(err, Some(&err.meta_data)))
+            .collect(),
+        ReadError::UnterminatedComment { meta_data } => {
+            vec![Diagnostic::error(error, Some(meta_data))]
+        }
+    }
+}
+
+/// Get a warning `Diagnostic` for every warning in a `Logger`.
+///
+/// To-do comments are not problems and are not included.
+pub(crate) fn get_diagnostics_from_log(log: &Logger) -> Vec<Diagnostic> {
+    log.warnings
+        .iter()
+        .map(|message| {
+            let mut buffer = String::new();
+
+            write_line_information(&mut buffer, &message.meta_data)
+                .and_then(|_| write!(&mut buffer, "{}", message.message))
+                .unwrap();
+
+            Diagnostic {
+                severity: Severity::Warning,
+                message: buffer,
+                meta_data: Some(message.meta_data.clone()),
+            }
+        })
+        .collect()
+}
+
+fn get_diagnostics_from_parse_error(error: &ParseError) -> Vec<Diagnostic> {
+    let prelude_diagnostics = error
+        .prelude_errors
+        .iter()
+        .map(|err| Diagnostic::error(err, Some(&err.meta_data)));
+
+    let knot_diagnostics = error.knot_errors.iter().flat_map(|knot_error| {
+        knot_error.line_errors.iter().map(move |line_error| {
+            let mut buffer = String::new();
+            write_knot_error_kind(&mut buffer, line_error, &knot_error.knot_meta_data).unwrap();
+
+            let meta_data = line_error
+                .get_meta_data()
+                .unwrap_or(&knot_error.knot_meta_data);
+
+            Diagnostic {
+                severity: Severity::Error,
+                message: buffer,
+                meta_data: Some(meta_data.clone()),
+            }
+        })
+    });
+
+    prelude_diagnostics.chain(knot_diagnostics).collect()
+}
+
+fn get_diagnostics_from_validation_error(error: &ValidationError) -> Vec<Diagnostic> {
+    let address_diagnostics = error
+        .invalid_address_errors
+        .iter()
+        .map(|err| Diagnostic::error(err, Some(&err.meta_data)));
+
+    let name_space_diagnostics = error
+        .name_space_errors
+        .iter()
+        .map(|err| Diagnostic::error(err, Some(&err.from_meta_data)));
+
+    let variable_diagnostics = error
+        .variable_errors
+        .iter()
+        .map(|err| Diagnostic::error(err, Some(&err.meta_data)));
+
+    address_diagnostics
+        .chain(name_space_diagnostics)
+        .chain(variable_diagnostics)
+        .collect()
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "ERROR"),
+            Severity::Warning => write!(f, "WARNING"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::parse::knot::{KnotError, KnotErrorKind};
+
+    #[cfg(feature = "logging")]
+    use crate::log::Warning;
+
+    #[test]
+    fn every_knot_line_error_becomes_a_separate_diagnostic() {
+        let error = ParseError {
+            prelude_errors: Vec::new(),
+            knot_errors: vec![KnotError {
+                knot_meta_data: MetaData::from(4),
+                line_errors: vec![
+                    KnotErrorKind::EmptyKnot,
+                    KnotErrorKind::EmptyStitch {
+                        name: None,
+                        meta_data: MetaData::from(6),
+                    },
+                ],
+            }],
+        };
+
+        let diagnostics = get_diagnostics_from_read_error(&error.into());
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.is_error()));
+
+        assert_eq!(diagnostics[0].meta_data, Some(MetaData::from(4)));
+        assert!(diagnostics[0].message.starts_with("(line 5)"));
+
+        assert_eq!(diagnostics[1].meta_data, Some(MetaData::from(6)));
+        assert!(diagnostics[1].message.starts_with("(line 7)"));
+    }
+
+    #[test]
+    #[cfg(feature = "logging")]
+    fn warnings_in_log_become_warning_diagnostics_but_todo_comments_do_not() {
+        let mut log = Logger::default();
+
+        log.add_todo("TODO: Write more", &MetaData::from(0));
+        log.add_warning(Warning::ShuffleSequenceNoRandom, &MetaData::from(2));
+
+        let diagnostics = get_diagnostics_from_log(&log);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_warning());
+        assert_eq!(diagnostics[0].meta_data, Some(MetaData::from(2)));
+    }
+
+    #[test]
+    fn diagnostics_print_with_severity_marker() {
+        let diagnostics = get_diagnostics_from_read_error(&ReadError::Empty);
+
+        assert!(format!("{}", diagnostics[0]).starts_with("ERROR: "));
+    }
+}
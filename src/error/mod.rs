@@ -2,9 +2,16 @@
 
 #[macro_use]
 pub(crate) mod utils;
+mod diagnostic;
 pub mod parse;
 pub(crate) mod runtime;
+#[cfg(feature = "compressed_saves")]
+mod save;
 
+pub(crate) use diagnostic::{get_diagnostics_from_log, get_diagnostics_from_read_error};
+pub use diagnostic::{Diagnostic, Severity};
 pub use parse::ReadError;
 pub use runtime::{variable, InklingError, InternalError};
+#[cfg(feature = "compressed_saves")]
+pub use save::SaveError;
 pub use utils::MetaData;
@@ -3,7 +3,8 @@
 use std::{error::Error, fmt};
 
 use crate::{
-    error::utils::{write_line_information, MetaData},
+    consts::ROOT_KNOT_NAME,
+    error::utils::{write_line_information, write_suggestions, MetaData},
     knot::Address,
 };
 
@@ -21,16 +22,64 @@ pub struct InvalidAddressError {
 pub enum InvalidAddressErrorKind {
     /// The address is not formatted correctly.
     BadFormat { line: String },
+    /// A divert to a knot was given a different number of arguments than the knot
+    /// has parameters.
+    InvalidDivertArguments {
+        address: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A function in the story was called with a different number of arguments than
+    /// it has parameters.
+    InvalidNumberOfArguments {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
     /// The address does not reference a knot, stitch or variable in the story.
-    UnknownAddress { name: String },
+    UnknownAddress {
+        name: String,
+        /// Names in the story which are close to the given name.
+        suggestions: Vec<String>,
+    },
+    /// The address of an assignment does not reference a variable in the story.
+    UnknownVariable {
+        name: String,
+        /// Variable names in the story which are close to the given name.
+        suggestions: Vec<String>,
+    },
+    /// An external function was declared in the story but not bound by the host.
+    ///
+    /// Only checked if it was requested when reading the story.
+    UnboundExternalFunction { name: String },
+    /// The called function is not a built-in function or written in the story.
+    UnknownFunction {
+        name: String,
+        /// Function names in the story which are close to the given name.
+        suggestions: Vec<String>,
+    },
     /// Tried to validate an address but the given current knot did not exist in the system.
     UnknownCurrentAddress { address: Address },
     /// The address references a `Knot` that is not in the story.
-    UnknownKnot { knot_name: String },
+    UnknownKnot {
+        knot_name: String,
+        /// Knot names in the story which are close to the given name.
+        suggestions: Vec<String>,
+    },
     /// The address references a `Stitch` that is not present in the current `Knot`.
     UnknownStitch {
         knot_name: String,
         stitch_name: String,
+        /// Stitch names in the knot which are close to the given name.
+        suggestions: Vec<String>,
+    },
+    /// The address references a label that is not present in its `Stitch`.
+    UnknownLabel {
+        knot_name: String,
+        stitch_name: String,
+        label_name: String,
+        /// Label names in the stitch which are close to the given name.
+        suggestions: Vec<String>,
     },
     /// Tried to validate an address using an unvalidated current address.
     ValidatedWithUnvalidatedAddress {
@@ -60,28 +109,97 @@ impl fmt::Display for InvalidAddressErrorKind {
 
         match self {
             BadFormat { line } => write!(f, "address was incorrectly formatted ('{}')", line),
-            UnknownAddress { name } => write!(
+            InvalidDivertArguments {
+                address,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{}' takes {} arguments but was diverted to with {}",
+                address, expected, found
+            ),
+            InvalidNumberOfArguments {
+                function,
+                expected,
+                found,
+            } => write!(
                 f,
-                "could not find knot or variable with name '{}' in the story",
-                name
+                "function '{}' takes {} arguments but was called with {}",
+                function, expected, found
             ),
+            UnknownAddress { name, suggestions } => {
+                write!(
+                    f,
+                    "could not find knot or variable with name '{}' in the story",
+                    name
+                )?;
+                write_suggestions(f, suggestions)
+            }
+            UnknownVariable { name, suggestions } => {
+                write!(
+                    f,
+                    "could not find variable with name '{}' in the story",
+                    name
+                )?;
+                write_suggestions(f, suggestions)
+            }
+            UnboundExternalFunction { name } => {
+                write!(f, "external function '{}' is not bound by the host", name)
+            }
+            UnknownFunction { name, suggestions } => {
+                write!(
+                    f,
+                    "could not find function with name '{}' in the story",
+                    name
+                )?;
+                write_suggestions(f, suggestions)
+            }
             UnknownCurrentAddress { address } => write!(
                 f,
                 "during validation an address '{:?}' that is not in the system was used as
                  a current address",
                 address
             ),
-            UnknownKnot { knot_name } => {
-                write!(f, "no knot with name '{}' in the story", knot_name)
+            UnknownKnot {
+                knot_name,
+                suggestions,
+            } => {
+                write!(f, "no knot with name '{}' in the story", knot_name)?;
+                write_suggestions(f, suggestions)
             }
             UnknownStitch {
                 knot_name,
                 stitch_name,
-            } => write!(
-                f,
-                "no stitch with name '{}' in knot '{}'",
-                stitch_name, knot_name
-            ),
+                suggestions,
+            } => {
+                write!(
+                    f,
+                    "no stitch with name '{}' in knot '{}'",
+                    stitch_name, knot_name
+                )?;
+                write_suggestions(f, suggestions)
+            }
+            UnknownLabel {
+                knot_name,
+                stitch_name,
+                label_name,
+                suggestions,
+            } => {
+                if stitch_name == ROOT_KNOT_NAME {
+                    write!(
+                        f,
+                        "no label with name '{}' in knot '{}'",
+                        label_name, knot_name
+                    )?;
+                } else {
+                    write!(
+                        f,
+                        "no label with name '{}' in stitch '{}' of knot '{}'",
+                        label_name, stitch_name, knot_name
+                    )?;
+                }
+                write_suggestions(f, suggestions)
+            }
             ValidatedWithUnvalidatedAddress {
                 needle,
                 current_address,
@@ -1,10 +1,16 @@
 //! Main error type from parsing and validating stories.
 
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io};
 
-use crate::error::parse::{
-    parse::{print_parse_error, ParseError},
-    validate::{print_validation_error, ValidationError},
+use crate::error::{
+    parse::{
+        flag::FlagError,
+        include::IncludeError,
+        parse::{print_parse_error, ParseError},
+        validate::{print_validation_error, ValidationError},
+    },
+    utils::MetaData,
+    InklingError,
 };
 
 #[derive(Clone, Debug)]
@@ -19,6 +25,30 @@ pub enum ReadError {
     ParseError(ParseError),
     /// Encountered one or more errors while validating a successfully parsed story.
     ValidationError(ValidationError),
+    /// Could not set the default value of a global variable, given when reading the story.
+    InvalidVariableDefault {
+        /// Name of the variable.
+        name: String,
+        /// Error from setting the value.
+        error: Box<InklingError>,
+    },
+    /// Found one or more lines which begin, separate or end flagged regions of content
+    /// in the wrong order.
+    FlagError(Vec<FlagError>),
+    /// Could not read one or more files which were included with `INCLUDE` lines.
+    IncludeError(Vec<IncludeError>),
+    /// Found a block comment which is not closed before the content ends.
+    UnterminatedComment {
+        /// Information about the line that the comment begins on.
+        meta_data: MetaData,
+    },
+    /// Could not read the story content from its source.
+    IoError {
+        /// Kind of error that the source reported.
+        kind: io::ErrorKind,
+        /// Description of the error.
+        message: String,
+    },
 }
 
 /// Get a string containing all errors encountered while reading a story.
@@ -35,6 +65,12 @@ pub fn print_read_error(error: &ReadError) -> Result<String, fmt::Error> {
     match &error {
         ReadError::ParseError(parse_error) => print_parse_error(parse_error),
         ReadError::ValidationError(validation_error) => print_validation_error(validation_error),
+        ReadError::FlagError(errors) => {
+            Ok(errors.iter().map(|error| format!("{}\n", error)).collect())
+        }
+        ReadError::IncludeError(errors) => {
+            Ok(errors.iter().map(|error| format!("{}\n", error)).collect())
+        }
         _ => Ok(format!("{}", error)),
     }
 }
@@ -44,6 +80,7 @@ impl Error for ReadError {
         match &self {
             ReadError::ParseError(err) => Some(err),
             ReadError::ValidationError(err) => Some(err),
+            ReadError::InvalidVariableDefault { error, .. } => Some(error.as_ref()),
             _ => None,
         }
     }
@@ -55,12 +92,42 @@ impl fmt::Display for ReadError {
 
         match self {
             Empty => write!(f, "Could not parse story: no content was available"),
+            InvalidVariableDefault { name, error } => write!(
+                f,
+                "Could not set default value of variable '{}': {}",
+                name, error
+            ),
+            FlagError(errors) => write!(
+                f,
+                "Could not parse story: found {} errors in flagged regions.",
+                errors.len()
+            ),
+            IncludeError(errors) => write!(
+                f,
+                "Could not read story: found {} errors in included files.",
+                errors.len()
+            ),
+            IoError { message, .. } => write!(f, "Could not read story content: {}", message),
+            UnterminatedComment { meta_data } => write!(
+                f,
+                "Could not parse story: the block comment which begins at {} is never closed",
+                meta_data
+            ),
             ParseError(err) => write!(f, "{}", err),
             ValidationError(err) => write!(f, "{}", err),
         }
     }
 }
 
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::IoError {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
 impl_from_error![
     ReadError;
     [ParseError, ParseError],
@@ -22,10 +22,18 @@ pub enum ExpressionErrorKind {
     InvalidHead { head: String },
     /// Could not parse variable inside expression.
     InvalidVariable(VariableError),
+    /// A built-in function was called with the wrong number of arguments.
+    InvalidNumberOfArguments {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
     /// Encountered a string in the tail with no leading mathematical operator.
     NoOperator { content: String },
     /// Expression had unmatched parenthesis brackets.
     UnmatchedParenthesis,
+    /// Expression used mathematical operators without the `math` feature enabled.
+    MathDisabled,
 }
 
 impl Error for ExpressionError {
@@ -57,10 +65,23 @@ impl fmt::Display for ExpressionErrorKind {
             Empty => write!(f, "empty string"),
             InvalidHead { head } => write!(f, "no left hand side value before '{}'", head),
             InvalidVariable(err) => write!(f, "invalid variable: {}", err),
+            InvalidNumberOfArguments {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "function '{}' takes {} arguments but was called with {}",
+                function, expected, found
+            ),
             NoOperator { content } => {
                 write!(f, "no mathematical operator before operand '{}'", content)
             }
             UnmatchedParenthesis => write!(f, "found unmatched parenthesis",),
+            MathDisabled => write!(
+                f,
+                "mathematical operators require the `math` feature to be enabled"
+            ),
         }
     }
 }
@@ -0,0 +1,61 @@
+//! Errors from reading regions of content which are included depending on flags.
+
+use std::{error::Error, fmt};
+
+use crate::error::utils::{write_line_information, MetaData};
+
+#[derive(Clone, Debug)]
+/// Error from a line which begins, separates or ends a flagged region of content.
+pub struct FlagError {
+    /// Line that caused the error.
+    pub line: String,
+    /// Kind of error.
+    pub kind: FlagErrorKind,
+    /// Information about the origin of the line that caused this error.
+    pub meta_data: MetaData,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Variant of error from reading flagged regions.
+pub enum FlagErrorKind {
+    /// A second `# else` was found in the same region.
+    DuplicateElse,
+    /// A flag name contained more than one word.
+    InvalidFlagName { name: String },
+    /// An `# ifdef` or `# ifndef` line had no flag name.
+    MissingFlagName,
+    /// An `# else` was found outside of any region.
+    UnmatchedElse,
+    /// An `# endif` was found outside of any region.
+    UnmatchedEndif,
+    /// A region was never ended with `# endif`.
+    UnterminatedRegion,
+}
+
+impl Error for FlagError {}
+
+impl fmt::Display for FlagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_line_information(f, &self.meta_data)?;
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for FlagErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use FlagErrorKind::*;
+
+        match self {
+            DuplicateElse => write!(f, "found a second '# else' in the same flagged region"),
+            InvalidFlagName { name } => write!(
+                f,
+                "invalid flag name '{}': flag names must be a single word",
+                name
+            ),
+            MissingFlagName => write!(f, "no flag name after '# ifdef' or '# ifndef'"),
+            UnmatchedElse => write!(f, "found '# else' outside of a flagged region"),
+            UnmatchedEndif => write!(f, "found '# endif' outside of a flagged region"),
+            UnterminatedRegion => write!(f, "flagged region is never ended with '# endif'"),
+        }
+    }
+}
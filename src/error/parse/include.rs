@@ -0,0 +1,33 @@
+//! Errors from reading files which are included into a story.
+
+use std::{error::Error, fmt, io};
+
+use crate::error::utils::{write_line_information, MetaData};
+
+#[derive(Clone, Debug)]
+/// Error from an `INCLUDE` line whose file could not be read.
+pub struct IncludeError {
+    /// Name of the file as it was written after `INCLUDE`.
+    pub name: String,
+    /// Full name of the file, as it was resolved from the file which included it.
+    pub resolved_name: String,
+    /// Kind of error that the resolver reported.
+    pub kind: io::ErrorKind,
+    /// Description of the error.
+    pub message: String,
+    /// Information about the origin of the `INCLUDE` line.
+    pub meta_data: MetaData,
+}
+
+impl Error for IncludeError {}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_line_information(f, &self.meta_data)?;
+        write!(
+            f,
+            "could not include file '{}' (resolved as '{}'): {}",
+            self.name, self.resolved_name, self.message
+        )
+    }
+}
@@ -26,6 +26,15 @@ pub enum KnotErrorKind {
         /// Information about the origin of the line of the original knot with this name.
         prev_meta_data: MetaData,
     },
+    /// Duplicate label name was found in a stitch.
+    DuplicateLabel {
+        /// Name of duplicate label.
+        name: String,
+        /// Information about the origin of the line that caused this error.
+        meta_data: MetaData,
+        /// Information about the origin of the line of the original label with this name.
+        prev_meta_data: MetaData,
+    },
     /// Duplicate stitch name was found in a knot.
     DuplicateStitchName {
         /// Name of duplicate stitch.
@@ -57,6 +66,11 @@ pub enum KnotErrorKind {
     },
     /// Could not parse a line inside a not.
     LineError(LineError),
+    /// Logic line which returns a value was found in a knot which is not a function.
+    ReturnOutsideFunction {
+        /// Information about the origin of the line that caused this error.
+        meta_data: MetaData,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -90,26 +104,47 @@ impl_from_error![
     [LineError, LineError]
 ];
 
+impl KnotErrorKind {
+    /// Get the `MetaData` of the line that caused the error, if the error carries its own.
+    ///
+    /// Errors which do not carry it originate from the line at which their knot starts.
+    pub(crate) fn get_meta_data(&self) -> Option<&MetaData> {
+        match self {
+            KnotErrorKind::DuplicateKnotName { .. } | KnotErrorKind::EmptyKnot => None,
+            KnotErrorKind::DuplicateLabel { meta_data, .. }
+            | KnotErrorKind::DuplicateStitchName { meta_data, .. }
+            | KnotErrorKind::EmptyStitch { meta_data, .. }
+            | KnotErrorKind::InvalidName { meta_data, .. }
+            | KnotErrorKind::ReturnOutsideFunction { meta_data } => Some(meta_data),
+            KnotErrorKind::LineError(err) => Some(&err.meta_data),
+        }
+    }
+}
+
 /// Get a string with all errors from parsing a `Knot`.
 pub(crate) fn write_knot_error<W: fmt::Write>(buffer: &mut W, error: &KnotError) -> fmt::Result {
     for line_error in &error.line_errors {
-        match line_error {
-            // All error kinds except these carry their own `MetaData` to use
-            KnotErrorKind::EmptyKnot => {
-                write_line_information(buffer, &error.knot_meta_data)?;
-            }
-            KnotErrorKind::DuplicateKnotName { .. } => {
-                write_line_information(buffer, &error.knot_meta_data)?;
-            }
-            _ => (),
-        }
-
-        write!(buffer, "{}\n", line_error)?;
+        write_knot_error_kind(buffer, line_error, &error.knot_meta_data)?;
+        writeln!(buffer)?;
     }
 
     Ok(())
 }
 
+/// Write a single error from parsing a `Knot`, along with information about its origin.
+pub(crate) fn write_knot_error_kind<W: fmt::Write>(
+    buffer: &mut W,
+    line_error: &KnotErrorKind,
+    knot_meta_data: &MetaData,
+) -> fmt::Result {
+    // All error kinds except these carry their own `MetaData` to use
+    if line_error.get_meta_data().is_none() {
+        write_line_information(buffer, knot_meta_data)?;
+    }
+
+    write!(buffer, "{}", line_error)
+}
+
 impl fmt::Display for KnotError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -134,6 +169,18 @@ impl fmt::Display for KnotErrorKind {
                 "encountered another knot with name '{}' in the story (previous at {})",
                 name, prev_meta_data
             ),
+            DuplicateLabel {
+                name,
+                meta_data,
+                prev_meta_data,
+            } => {
+                write_line_information(f, meta_data)?;
+                write!(
+                    f,
+                    "encountered another label with name '{}' in the stitch (previous at {})",
+                    name, prev_meta_data
+                )
+            }
             DuplicateStitchName {
                 name,
                 knot_name,
@@ -169,6 +216,10 @@ impl fmt::Display for KnotErrorKind {
                 write!(f, "could not read knot or stitch name: {}", kind)
             }
             LineError(err) => write!(f, "{}", err),
+            ReturnOutsideFunction { meta_data } => {
+                write_line_information(f, meta_data)?;
+                write!(f, "can only return from functions")
+            }
         }
     }
 }
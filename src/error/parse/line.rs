@@ -3,7 +3,10 @@
 use std::{error::Error, fmt};
 
 use crate::{
-    consts::{CHOICE_MARKER, STICKY_CHOICE_MARKER},
+    consts::{
+        ASSIGNMENT_MARKER, BLOCK_END_MARKER, CHOICE_MARKER, ELSE_BLOCK_KEYWORD, GATHER_MARKER,
+        STICKY_CHOICE_MARKER, TEMPORARY_VARIABLE_MARKER,
+    },
     error::{
         parse::{condition::ConditionError, expression::ExpressionError},
         utils::{write_line_information, MetaData},
@@ -24,24 +27,48 @@ pub struct LineError {
 #[derive(Clone, Debug)]
 /// Variants of line errors.
 pub enum LineErrorKind {
+    /// Found a branch with a condition after the else branch of a multi-line
+    /// conditional block.
+    BlockBranchAfterElse,
+    /// Found a choice, gather or thread inside a multi-line block of alternatives.
+    BranchingInBlock,
     /// Condition was invalid.
     ConditionError(ConditionError),
     /// Found a divert marker but no address.
     EmptyDivert,
+    /// Found more than one else branch in a multi-line conditional block.
+    DuplicateBlockElse,
     /// Found an empty expression (embraced part of line)
     EmptyExpression,
+    /// Found a branch without a condition, or content before the first branch,
+    /// in a multi-line conditional block whose branches have conditions.
+    ExpectedBlockCondition,
+    /// Found content in a multi-line block before its first item.
+    ExpectedBlockItem,
     /// Line did not end after a divert statement.
     ExpectedEndOfLine { tail: String },
     /// Could not read a numerical expression.
     ExpressionError(ExpressionError),
-    /// Found several divert markers which indicates unimplemented tunnels.
+    /// Found several divert markers which indicates unimplemented tunnels, or returns
+    /// from them with `->->`.
     FoundTunnel,
     /// Found an address with invalid characters.
     InvalidAddress { address: String },
+    /// The callback of a custom directive could not parse its content.
+    InvalidDirective { name: String, message: String },
+    /// Found a label of a choice or gather which is not closed or has an invalid name.
+    InvalidLabel { label: String },
+    /// Found a logic line which does not assign a value to a variable.
+    InvalidLogicLine,
     /// A choice has both non-sticky and sticky markers.
     StickyAndNonSticky,
     /// Found unmatched curly braces.
-    UnmatchedBraces,
+    UnmatchedBraces {
+        /// Index of the character in the line of the first brace which is not matched.
+        position: usize,
+    },
+    /// A multi-line block was not closed before the end of its knot or stitch.
+    UnterminatedBlock,
     /// Found unmatched square brackets.
     UnmatchedBrackets,
 }
@@ -80,9 +107,36 @@ impl fmt::Display for LineErrorKind {
         use LineErrorKind::*;
 
         match &self {
+            BlockBranchAfterElse => write!(
+                f,
+                "found a branch with a condition after the '{} {}:' branch in a multi-line \
+                 conditional block",
+                GATHER_MARKER, ELSE_BLOCK_KEYWORD
+            ),
+            BranchingInBlock => write!(
+                f,
+                "choices, gathers and threads cannot be used inside of multi-line blocks of \
+                 alternatives"
+            ),
             ConditionError(err) => write!(f, "could not parse a condition: {}", err),
+            DuplicateBlockElse => write!(
+                f,
+                "found more than one '{} {}:' branch in a multi-line conditional block",
+                GATHER_MARKER, ELSE_BLOCK_KEYWORD
+            ),
             EmptyDivert => write!(f, "encountered a divert statement with no address",),
             EmptyExpression => write!(f, "found an empty embraced expression ('{{}})'"),
+            ExpectedBlockCondition => write!(
+                f,
+                "expected a branch beginning with '{} condition:' in a multi-line conditional \
+                 block",
+                GATHER_MARKER
+            ),
+            ExpectedBlockItem => write!(
+                f,
+                "expected an item beginning with '{}' in a multi-line block",
+                GATHER_MARKER
+            ),
             ExpectedEndOfLine { tail } => write!(
                 f,
                 "expected no more content after a divert statement address but found '{}'",
@@ -101,14 +155,38 @@ impl fmt::Display for LineErrorKind {
                  contains invalid characters",
                 address
             ),
+            InvalidDirective { name, message } => {
+                write!(f, "could not parse directive '{}': {}", name, message)
+            }
+            InvalidLabel { label } => write!(
+                f,
+                "found an invalid label '{}': labels are a name in parenthesis which may only \
+                 contain alphanumeric characters and underscores",
+                label
+            ),
+            InvalidLogicLine => write!(
+                f,
+                "logic lines beginning with '{}' must assign a value to a variable: \
+                 '{} name = value' or '{} {} name = value'",
+                ASSIGNMENT_MARKER, ASSIGNMENT_MARKER, ASSIGNMENT_MARKER, TEMPORARY_VARIABLE_MARKER
+            ),
             StickyAndNonSticky => write!(
                 f,
                 "Encountered a line which has both non-sticky ('{}') and sticky ('{}') \
                  choice markers. This is not allowed.",
                 CHOICE_MARKER, STICKY_CHOICE_MARKER
             ),
-            UnmatchedBraces => write!(f, "line has unmatched curly '{{}}' braces"),
+            UnmatchedBraces { position } => write!(
+                f,
+                "line has unmatched curly '{{}}' braces (at character {})",
+                position + 1
+            ),
             UnmatchedBrackets => write!(f, "choice line has unmatched square '[]' brackets"),
+            UnterminatedBlock => write!(
+                f,
+                "multi-line block is not closed with '{}' before the end of its knot or stitch",
+                BLOCK_END_MARKER
+            ),
         }
     }
 }
@@ -4,6 +4,8 @@ pub mod address;
 pub mod condition;
 mod error;
 pub mod expression;
+pub mod flag;
+pub mod include;
 pub mod knot;
 pub mod line;
 mod parse;
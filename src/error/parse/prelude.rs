@@ -3,7 +3,7 @@
 use std::{error::Error, fmt};
 
 use crate::error::{
-    parse::variable::VariableError,
+    parse::{knot::KnotNameError, variable::VariableError},
     utils::{write_line_information, MetaData},
 };
 
@@ -21,10 +21,20 @@ pub struct PreludeError {
 #[derive(Clone, Debug)]
 /// Variant of error from parsing the prelude.
 pub enum PreludeErrorKind {
+    /// External function with given name was declared multiple times.
+    DuplicateExternalFunction { name: String },
     /// Variable with given name was defined multiple times.
     DuplicateVariable { name: String },
+    /// Variable with given name was defined although the host defines a constant with it.
+    DuplicateHostConstant { name: String },
+    /// Could not parse the name or parameters of an external function.
+    InvalidExternalFunction(KnotNameError),
     /// Could not parse a global variable.
     InvalidVariable(VariableError),
+    /// Could not parse an item in a list definition.
+    InvalidListItem { item: String },
+    /// Item with given name was defined multiple times in a list definition.
+    DuplicateListItem { name: String },
     /// No `=` sign was find in a variable assignment line.
     NoVariableAssignment,
     /// No variable name was found in a variable assignment line.
@@ -40,6 +50,7 @@ impl Error for PreludeError {
 impl Error for PreludeErrorKind {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self {
+            PreludeErrorKind::InvalidExternalFunction(err) => Some(err),
             PreludeErrorKind::InvalidVariable(err) => Some(err),
             _ => None,
         }
@@ -63,10 +74,32 @@ impl fmt::Display for PreludeErrorKind {
         use PreludeErrorKind::*;
 
         match &self {
+            DuplicateExternalFunction { name } => {
+                write!(
+                    f,
+                    "found second declaration of external function '{}'",
+                    name
+                )
+            }
             DuplicateVariable { name } => {
                 write!(f, "found second definition of global variable '{}'", name)
             }
+            DuplicateHostConstant { name } => write!(
+                f,
+                "global variable '{}' is already defined as a constant by the host",
+                name
+            ),
+            InvalidExternalFunction(err) => write!(f, "could not parse external function: {}", err),
             InvalidVariable(err) => write!(f, "could not parse variable: {}", err),
+            InvalidListItem { item } => write!(
+                f,
+                "could not parse list item '{}': items are names which may be marked \
+                 as initial with parenthesis and given values ('(name) = 2')",
+                item
+            ),
+            DuplicateListItem { name } => {
+                write!(f, "found second definition of list item '{}'", name)
+            }
             NoVariableAssignment => write!(f, "no variable assignment ('=') in line"),
             NoVariableName => write!(f, "no variable name in line"),
         }
@@ -83,6 +83,7 @@ pub struct InvalidVariableExpression {
 #[derive(Clone, Debug)]
 /// Kind of encountered invalid expression.
 pub enum ExpressionKind {
+    Assignment,
     Condition,
     Expression,
 }
@@ -94,6 +95,13 @@ pub enum InvalidVariableExpressionError {
     ///
     /// Most if not all invalid errors should be of this type.
     VariableError(VariableError),
+    /// A logic line assigns to a constant variable.
+    AssignedToConst {
+        name: String,
+        /// Information about the origin of the line which declared the constant,
+        /// or `None` if it was given by the host.
+        meta_data: Option<MetaData>,
+    },
     /// Other errors inside the validated item.
     ///
     /// Represents that something that is not a simple variable type or invalid address caused
@@ -152,6 +160,7 @@ impl Error for InvalidVariableExpression {
         match &self.kind {
             InvalidVariableExpressionError::Internal(err) => Some(err),
             InvalidVariableExpressionError::VariableError(err) => Some(err),
+            InvalidVariableExpressionError::AssignedToConst { .. } => None,
         }
     }
 }
@@ -160,6 +169,9 @@ impl From<InklingError> for InvalidVariableExpressionError {
     fn from(err: InklingError) -> Self {
         match err {
             InklingError::VariableError(err) => InvalidVariableExpressionError::VariableError(err),
+            InklingError::AssignedToConst { name, meta_data } => {
+                InvalidVariableExpressionError::AssignedToConst { name, meta_data }
+            }
             _ => InvalidVariableExpressionError::Internal(err),
         }
     }
@@ -186,6 +198,18 @@ impl fmt::Display for InvalidVariableExpression {
             InvalidVariableExpressionError::VariableError(err) => {
                 write!(f, "Invalid {}: {}", &self.expression_kind, err)
             }
+            InvalidVariableExpressionError::AssignedToConst { name, meta_data } => {
+                write!(
+                    f,
+                    "Invalid {}: cannot assign to CONST variable '{}'",
+                    &self.expression_kind, name
+                )?;
+
+                match meta_data {
+                    Some(meta_data) => write!(f, " (declared at {})", meta_data),
+                    None => write!(f, " (given by the host)"),
+                }
+            }
             InvalidVariableExpressionError::Internal(err) => write!(
                 f,
                 "Unknown internal inconsistency in {}: {}\n",
@@ -198,6 +222,7 @@ impl fmt::Display for InvalidVariableExpression {
 impl fmt::Display for ExpressionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
+            ExpressionKind::Assignment => write!(f, "assignment"),
             ExpressionKind::Condition => write!(f, "condition"),
             ExpressionKind::Expression => write!(f, "expression"),
         }
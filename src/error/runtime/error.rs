@@ -3,7 +3,12 @@
 use std::{error::Error, fmt};
 
 use crate::{
-    error::{runtime::internal::StackError, variable::VariableError, InternalError},
+    error::{
+        runtime::internal::{ProcessError, ProcessErrorKind, StackError},
+        utils::MetaData,
+        variable::VariableError,
+        InternalError,
+    },
     line::Variable,
     story::{Choice, Location},
 };
@@ -28,11 +33,22 @@ impl Error for InklingError {}
 /// please open an issue on Github.
 pub enum InklingError {
     /// Tried to assign a new value to a CONST variable.
-    AssignedToConst { name: String },
+    AssignedToConst {
+        /// Name of the constant.
+        name: String,
+        /// Information about the origin of the line which declared the constant.
+        ///
+        /// Is `None` for constants which were given by the host when the story was read.
+        meta_data: Option<MetaData>,
+    },
     /// Internal errors caused by `inkling`.
     Internal(InternalError),
     /// Use of a `Location` which does not exist in the story.
     InvalidAddress { location: Location },
+    /// Use of a label which does not exist in the stitch of its `Location`.
+    InvalidLabel { location: Location, label: String },
+    /// Diverted to a variable which does not hold a divert target.
+    InvalidDivertTarget { name: String, value: Variable },
     /// An invalid choice index was given to resume the story with.
     InvalidChoice {
         /// Choice input by the user to resume the story with.
@@ -40,16 +56,32 @@ pub enum InklingError {
         /// List of choices that were available for the selection
         presented_choices: Vec<Choice>,
     },
+    /// A choice identifier was given which is not in the presented set of choices.
+    InvalidChoiceId {
+        /// Identifier input by the user to resume the story with.
+        id: String,
+        /// List of choices that were available for the selection
+        presented_choices: Vec<Choice>,
+    },
+    /// Called a function which is not present in the story.
+    InvalidFunction { name: String },
     /// Used a variable name that is not present in the story as an input variable.
     InvalidVariable { name: String },
     /// Called `make_choice` when no choice had been requested.
     ///
     /// Likely directly at the start of a story or after a `move_to` call was made.
     MadeChoiceWithoutChoice,
+    /// Called a function while too many calls were already nested, likely because
+    /// it recursed without end.
+    MaxCallDepthReached { name: String },
+    /// Called a function which did not return a value.
+    NoReturnValue { name: String },
     /// No choices or fallback choices were available in a story branch at the given location.
     OutOfChoices { location: Location },
     /// No content was available for the story to continue from.
     OutOfContent,
+    /// Called an external function which the host has not bound.
+    UnboundExternalFunction { name: String },
     /// Tried to print a variable that cannot be printed.
     PrintInvalidVariable { name: String, value: Variable },
     /// Invalid variable assignment or operation.
@@ -62,9 +94,21 @@ impl From<StackError> for InklingError {
     }
 }
 
+impl From<InternalError> for InklingError {
+    fn from(err: InternalError) -> Self {
+        // Errors from the story which were met while processing its lines are not
+        // internal errors, so they are returned as they are
+        match err {
+            InternalError::CouldNotProcess(ProcessError {
+                kind: ProcessErrorKind::InklingError(err),
+            }) => *err,
+            err => InklingError::Internal(err),
+        }
+    }
+}
+
 impl_from_error![
     InklingError;
-    [Internal, InternalError],
     [VariableError, VariableError]
 ];
 
@@ -73,8 +117,13 @@ impl fmt::Display for InklingError {
         use InklingError::*;
 
         match self {
-            AssignedToConst { name } => {
-                write!(f, "Tried to assign a value to CONST variable '{}'", name)
+            AssignedToConst { name, meta_data } => {
+                write!(f, "Tried to assign a value to CONST variable '{}'", name)?;
+
+                match meta_data {
+                    Some(meta_data) => write!(f, " (declared at {})", meta_data),
+                    None => write!(f, " (given by the host)"),
+                }
             }
             Internal(err) => write!(f, "INTERNAL ERROR: {}", err),
             InvalidAddress {
@@ -91,6 +140,15 @@ impl fmt::Display for InklingError {
                     knot
                 ),
             },
+            InvalidLabel { location, label } => write!(
+                f,
+                "Invalid address: '{}' does not contain a label named '{}'",
+                location.stitch.as_ref().map_or_else(
+                    || location.knot.clone(),
+                    |stitch| format!("{}.{}", location.knot, stitch)
+                ),
+                label
+            ),
             InvalidChoice {
                 selection,
                 presented_choices,
@@ -102,6 +160,30 @@ impl fmt::Display for InklingError {
                 presented_choices.len(),
                 presented_choices.len() - 1
             ),
+            InvalidChoiceId {
+                id,
+                presented_choices,
+            } => write!(
+                f,
+                "Invalid selection of choice: no choice with identifier '{}' was presented \
+                 (presented choices: {})",
+                id,
+                presented_choices
+                    .iter()
+                    .map(|choice| format!("'{}'", choice.id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            InvalidFunction { name } => write!(
+                f,
+                "Invalid function: no function with name '{}' exists in the story",
+                name
+            ),
+            InvalidDivertTarget { name, value } => write!(
+                f,
+                "Cannot divert to variable '{}' which has value '{:?}': not a divert target",
+                name, value
+            ),
             InvalidVariable { name } => write!(
                 f,
                 "Invalid variable: no variable with  name '{}' exists in the story",
@@ -112,6 +194,17 @@ impl fmt::Display for InklingError {
                 "Tried to make a choice, but no choice is currently active. Call `resume` \
                  and assert that a branching choice is returned before calling this again."
             ),
+            MaxCallDepthReached { name } => write!(
+                f,
+                "Called function '{}' with too many nested function calls: it may recurse \
+                 without end",
+                name
+            ),
+            NoReturnValue { name } => write!(
+                f,
+                "Function '{}' did not return a value when it was called",
+                name
+            ),
             OutOfChoices {
                 location: Location { knot, stitch },
             } => {
@@ -129,6 +222,11 @@ impl fmt::Display for InklingError {
                 write!(f, ")")
             }
             OutOfContent => write!(f, "Story ran out of content before an end was reached"),
+            UnboundExternalFunction { name } => write!(
+                f,
+                "Called external function '{}' which has not been bound by the host",
+                name
+            ),
             PrintInvalidVariable { name, value } => write!(
                 f,
                 "Cannot print variable '{}' which has value '{:?}': invalid type",
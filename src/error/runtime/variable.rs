@@ -36,6 +36,27 @@ pub enum VariableErrorKind {
         /// Character representation of the operation that caused the error (`/`, `%`).
         operator: char,
     },
+    /// A built-in function was called with a variable of a type that it cannot use.
+    InvalidArgument {
+        /// Name of the function.
+        function: String,
+    },
+    /// A built-in function returned an integer which is out of the range of integers.
+    ///
+    /// The variable is the first argument of the call.
+    IntegerOverflow {
+        /// Name of the function.
+        function: String,
+    },
+    /// A built-in function was called with a lower bound which is higher than its upper bound.
+    ///
+    /// The variable is the lower bound.
+    InvalidBounds {
+        /// Name of the function.
+        function: String,
+        /// Upper bound of the call.
+        max: i32,
+    },
     /// Two variables could not be compared to each other like this.
     InvalidComparison {
         /// Other variable in the comparison.
@@ -43,11 +64,16 @@ pub enum VariableErrorKind {
         /// Type of comparison betweeen `variable` and `other`.
         comparison: Ordering,
     },
+    /// The variable could not be converted into a value of another type.
+    InvalidConversion {
+        /// Name of the type that the variable was converted into.
+        target: &'static str,
+    },
     /// Tried to operate on the variable with an operation that is not allowed for it.
     InvalidOperation {
         /// Other variable in the operation.
         other: Variable,
-        /// Character representation of operation (`+`, `-`, `*`, `/`, `%`).
+        /// Character representation of operation (`+`, `-`, `*`, `/`, `%`, `?`).
         operator: char,
     },
     /// A new variable type was attempted to be assigned to the current variable.
@@ -66,16 +92,45 @@ impl fmt::Display for VariableError {
         match &self.kind {
             DividedByZero { other, operator } => write!(
                 f,
-                "Attempted to divide by 0 in the operation '{} {} {}'",
+                "Attempted to {} by 0 in the operation '{} {} {}'",
+                if *operator == '%' {
+                    "take the remainder of a division"
+                } else {
+                    "divide"
+                },
                 variable.to_error_string(),
                 operator,
                 other.to_error_string()
             ),
+            InvalidArgument { function } => write!(
+                f,
+                "Function '{}' cannot be called with a variable of type '{}' (in: '{}({})')",
+                function,
+                variable.variant_string(),
+                function,
+                variable.to_error_string()
+            ),
+            IntegerOverflow { function } => write!(
+                f,
+                "Function '{}' returned an integer which is out of the range of integers \
+                 (called with: '{}')",
+                function,
+                variable.to_error_string()
+            ),
+            InvalidBounds { function, max } => write!(
+                f,
+                "Function '{}' was called with a lower bound which is higher than \
+                 its upper bound (in: '{}({}, {})')",
+                function,
+                function,
+                variable.to_error_string(),
+                max
+            ),
             InvalidComparison { other, comparison } => {
                 let operator = match comparison {
                     Ordering::Equal => "==",
-                    Ordering::Less => ">",
-                    Ordering::Greater => "<",
+                    Ordering::Less => "<",
+                    Ordering::Greater => ">",
                 };
 
                 write!(
@@ -89,6 +144,13 @@ impl fmt::Display for VariableError {
                     op = operator
                 )
             }
+            InvalidConversion { target } => write!(
+                f,
+                "Cannot convert a variable of type '{}' into '{}' (in: '{}')",
+                variable.variant_string(),
+                target,
+                variable.to_error_string()
+            ),
             InvalidOperation { other, operator } => write!(
                 f,
                 "Operation '{op}' is not allowed between variables of type '{}' and '{}' \
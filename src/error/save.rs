@@ -0,0 +1,60 @@
+//! Errors from saving and loading compressed story states.
+
+use std::{error::Error, fmt};
+
+#[derive(Clone, Debug, PartialEq)]
+/// Errors from writing a story to or reading it from a compressed save.
+pub enum SaveError {
+    /// The story could not be serialized.
+    Serialize {
+        /// Description of the error.
+        message: String,
+    },
+    /// The decompressed state could not be deserialized into a story.
+    Deserialize {
+        /// Description of the error.
+        message: String,
+    },
+    /// The data does not start with the header of a compressed save.
+    InvalidHeader,
+    /// The save was written with a version of the format which is not supported.
+    UnsupportedVersion {
+        /// Version of the format that the save was written with.
+        version: u8,
+    },
+    /// The compressed data is truncated or malformed.
+    CorruptData,
+    /// The hash of the decompressed state does not match the hash in the header.
+    HashMismatch {
+        /// Hash that was written in the header.
+        expected: u64,
+        /// Hash of the decompressed state.
+        found: u64,
+    },
+}
+
+impl Error for SaveError {}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SaveError::*;
+
+        match self {
+            Serialize { message } => write!(f, "Could not serialize story: {}", message),
+            Deserialize { message } => write!(f, "Could not deserialize story: {}", message),
+            InvalidHeader => write!(f, "Could not load story: data is not a compressed save"),
+            UnsupportedVersion { version } => write!(
+                f,
+                "Could not load story: save format version {} is not supported",
+                version
+            ),
+            CorruptData => write!(f, "Could not load story: compressed data is corrupt"),
+            HashMismatch { expected, found } => write!(
+                f,
+                "Could not load story: hash of state ({:016x}) does not match the saved \
+                 hash ({:016x})",
+                found, expected
+            ),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use std::fmt;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Information about the origin of an item.
 ///
@@ -14,11 +14,50 @@ use serde::{Deserialize, Serialize};
 pub struct MetaData {
     /// Which line in the original story the item originated from.
     pub(crate) line_index: u32,
+    /// Name of file which the item was read from, if known.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::story::strings::option"
+        )
+    )]
+    pub(crate) file: Option<String>,
+    /// Name of knot which the item is in, if it is not in the root of the story.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::story::strings::option"
+        )
+    )]
+    pub(crate) knot: Option<String>,
+    /// Name of stitch which the item is in, if it is not at the beginning of its knot.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::story::strings::option"
+        )
+    )]
+    pub(crate) stitch: Option<String>,
 }
 
 impl fmt::Display for MetaData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "line {}", self.line())
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file, self.line())?,
+            None => write!(f, "line {}", self.line())?,
+        }
+
+        match (&self.knot, &self.stitch) {
+            (Some(knot), Some(stitch)) => write!(f, " in '{}.{}'", knot, stitch),
+            (Some(name), None) | (None, Some(name)) => write!(f, " in '{}'", name),
+            (None, None) => Ok(()),
+        }
     }
 }
 
@@ -30,6 +69,27 @@ impl MetaData {
     pub fn line(&self) -> u32 {
         self.line_index + 1
     }
+
+    /// Get the name of the file which the item was read from.
+    ///
+    /// Only set if a file name was given when reading the story.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Get the name of the knot which the item is in.
+    ///
+    /// Items in the root of the story, before the first knot, have no knot name.
+    pub fn knot(&self) -> Option<&str> {
+        self.knot.as_deref()
+    }
+
+    /// Get the name of the stitch which the item is in.
+    ///
+    /// Items at the beginning of a knot, before its first named stitch, have no stitch name.
+    pub fn stitch(&self) -> Option<&str> {
+        self.stitch.as_deref()
+    }
 }
 
 /// Write meta data information for a line or piece of content in a story.
@@ -40,6 +100,23 @@ pub(crate) fn write_line_information<W: fmt::Write>(
     write!(buffer, "({}) ", meta_data)
 }
 
+/// Write a list of suggested names for a misspelled name, if there are any.
+pub(crate) fn write_suggestions<W: fmt::Write>(
+    buffer: &mut W,
+    suggestions: &[String],
+) -> fmt::Result {
+    let quoted = suggestions
+        .iter()
+        .map(|suggestion| format!("'{}'", suggestion))
+        .collect::<Vec<_>>();
+
+    match quoted.split_last() {
+        Some((last, [])) => write!(buffer, " (did you mean {}?)", last),
+        Some((last, head)) => write!(buffer, " (did you mean {} or {}?)", head.join(", "), last),
+        None => Ok(()),
+    }
+}
+
 /// Wrapper to implement From for variants when the variant is simply encapsulated
 /// in the enum.
 ///
@@ -83,6 +160,9 @@ impl From<usize> for MetaData {
     fn from(line_index: usize) -> Self {
         MetaData {
             line_index: line_index as u32,
+            file: None,
+            knot: None,
+            stitch: None,
         }
     }
 }
@@ -90,7 +170,7 @@ impl From<usize> for MetaData {
 #[cfg(test)]
 impl From<()> for MetaData {
     fn from(_: ()) -> Self {
-        MetaData { line_index: 0 }
+        MetaData::from(0)
     }
 }
 
@@ -100,11 +180,64 @@ mod tests {
 
     #[test]
     fn meta_data_from_index_sets_index() {
-        assert_eq!(MetaData::from(6), MetaData { line_index: 6 });
+        assert_eq!(
+            MetaData::from(6),
+            MetaData {
+                line_index: 6,
+                file: None,
+                knot: None,
+                stitch: None
+            }
+        );
     }
 
     #[test]
     fn meta_data_line_number_starts_from_one() {
         assert_eq!(MetaData::from(6).line(), 7);
     }
+
+    #[test]
+    fn meta_data_prints_knot_and_stitch_names_if_present() {
+        let mut meta_data = MetaData::from(6);
+        assert_eq!(format!("{}", meta_data), "line 7");
+
+        meta_data.knot = Some("tripoli".to_string());
+        assert_eq!(format!("{}", meta_data), "line 7 in 'tripoli'");
+
+        meta_data.stitch = Some("cinema".to_string());
+        assert_eq!(format!("{}", meta_data), "line 7 in 'tripoli.cinema'");
+    }
+
+    #[test]
+    fn meta_data_prints_file_and_line_if_file_name_is_present() {
+        let mut meta_data = MetaData::from(6);
+
+        meta_data.file = Some("story.ink".to_string());
+        assert_eq!(format!("{}", meta_data), "story.ink:7");
+
+        meta_data.knot = Some("tripoli".to_string());
+        assert_eq!(format!("{}", meta_data), "story.ink:7 in 'tripoli'");
+    }
+    #[test]
+    fn suggestions_are_written_as_a_list_of_alternatives() {
+        let write = |suggestions: &[&str]| {
+            let suggestions = suggestions
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+
+            let mut buffer = String::new();
+            write_suggestions(&mut buffer, &suggestions).unwrap();
+
+            buffer
+        };
+
+        assert_eq!(&write(&[]), "");
+        assert_eq!(&write(&["one"]), " (did you mean 'one'?)");
+        assert_eq!(&write(&["one", "two"]), " (did you mean 'one' or 'two'?)");
+        assert_eq!(
+            &write(&["one", "two", "three"]),
+            " (did you mean 'one', 'two' or 'three'?)"
+        );
+    }
 }
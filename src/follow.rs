@@ -1,16 +1,28 @@
 //! Results and data that is used or encountered when following, or walking through, a story.
 
 use crate::{
-    error::InklingError,
-    knot::Address,
-    line::InternalChoice,
-    story::{rng::StoryRng, types::VariableSet},
+    consts::ROOT_KNOT_NAME,
+    error::{utils::MetaData, InklingError},
+    knot::{Address, FunctionSet},
+    line::{Directive, InternalChoice, Variable},
+    story::{
+        audit::{DeterminismAudit, NondeterminismKind},
+        coverage::ConditionCoverage,
+        external::{ExternalFunctions, ExternalVariables},
+        journal::VisitJournal,
+        rng::StoryRng,
+        types::{EndingKind, Location, VariableSet},
+        variable_history::{ChangeOrigin, VariableChange, VariableHistory, VariableObservers},
+    },
 };
 
 #[cfg(feature = "serde_support")]
-use serde::{Deserialize, Serialize};
+use crate::story::types::VariableInfo;
 
-use std::collections::HashMap;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{collections::HashMap, sync::Arc};
 
 /// Convenience type for a result of the encountered event and main error type.
 pub type FollowResult = Result<EncounteredEvent, InklingError>;
@@ -27,6 +39,11 @@ pub enum EncounteredEvent {
     Divert(Address),
     /// Finished with the current node or story.
     Done,
+    /// Returned from a function with the given value.
+    Return(Option<Variable>),
+    /// Thread to the given address, which is followed before the story continues
+    /// from the line after it.
+    Thread(Address),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -48,17 +65,334 @@ impl ChoiceInfo {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+/// Set of choices which was gathered from a thread.
+pub struct ThreadChoices {
+    /// Address that the choices were encountered at.
+    pub address: Address,
+    /// Choices which were encountered.
+    pub choices: Vec<ChoiceInfo>,
+    /// Temporary variables of the thread when the choices were encountered.
+    pub temporary_variables: HashMap<String, Variable>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Where a presented choice was encountered, to follow the story from if it is selected.
+pub struct ChoiceOrigin {
+    /// Address that the choice was encountered at.
+    pub address: Address,
+    /// Index of the choice in the set of choices at the address.
+    pub index: usize,
+    /// Temporary variables of the thread or story when the choice was encountered.
+    pub temporary_variables: HashMap<String, Variable>,
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Data used during a follow through knots and nodes.
 pub struct FollowData {
     /// Number of times a knot and stitch address has been visited.
+    ///
+    /// Only knots and stitches which have been visited are saved: the rest are restored
+    /// as not visited when the data is loaded.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(serialize_with = "serialize_visited_counts")
+    )]
     pub knot_visit_counts: HashMap<String, HashMap<String, u32>>,
+    /// Number of times that labeled choices and gathers have been visited.
+    ///
+    /// Labels are keyed by their full address, like `knot.stitch.label`. Labels which
+    /// have not been visited are not included.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub label_visit_counts: HashMap<String, u32>,
     /// Global variables in story.
+    ///
+    /// Only the values of variables which differ from their declaration are saved. The rest
+    /// are rebuilt from the declarations in the story when it is loaded. Transient variables
+    /// are saved with the value that they are reset to.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(
+            serialize_with = "serialize_variables",
+            deserialize_with = "deserialize_variables"
+        )
+    )]
     pub variables: VariableSet,
+    /// Temporary variables which have been declared in the current knot or stitch.
+    ///
+    /// Dropped when the story leaves it.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub temporary_variables: HashMap<String, Variable>,
     /// Random number generator
     pub rng: StoryRng,
+    /// Record of which outcomes conditions have evaluated to.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub condition_coverage: ConditionCoverage,
+    /// Store of variables which are owned by the host.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub external_variables: ExternalVariables,
+    /// Number of choices that have been made.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub turn: u32,
+    /// Turn at which every knot, stitch and label was last visited.
+    ///
+    /// Keyed by their full address, like `knot.stitch`. Addresses which have not been
+    /// visited are not included.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub last_visited_turns: HashMap<String, u32>,
+    /// Journal of visited knots and stitches, if it is being recorded.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub visit_journal: Option<VisitJournal>,
+    /// History of changes to selected variables, if it is being recorded.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub variable_history: Option<VariableHistory>,
+    /// Functions which the host has set to be called when variables change.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub variable_observers: VariableObservers,
+    /// Record of sources of nondeterminism, if they are being audited.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub determinism_audit: Option<DeterminismAudit>,
+    /// How the story ended, if it reached its end when it was last followed.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub ending: Option<EndingKind>,
+    /// Whether lines are written with every variant of their alternatives and conditional
+    /// text, instead of the selected variants.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub proofreading: bool,
+    /// Number of lines after which the story returns at the next divert, if set.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub max_lines_per_resume: Option<usize>,
+    /// Number of diverts after which the story returns, if set.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub max_diverts_per_resume: Option<usize>,
+    /// Functions which are written in the story.
+    ///
+    /// Shared between copies of the data until a function is called in one of them.
+    /// The functions are saved with the state of their alternative sequences.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub functions: Arc<FunctionSet>,
+    /// Number of function calls which are nested at the current point of the story.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub call_depth: usize,
+    /// Arguments which were evaluated for the divert that the story is following.
+    ///
+    /// Set as the parameters of the knot that the divert leads to. Not saved with
+    /// the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub divert_arguments: Vec<Variable>,
+    /// Functions which are declared as `EXTERNAL` in the story, with the functions that
+    /// the host has bound to them.
+    ///
+    /// Only the names of the declared functions are saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub external_functions: ExternalFunctions,
+    /// Sets of choices which have been gathered from threads since choices were last
+    /// presented.
+    ///
+    /// Not saved with the story state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub thread_choices: Vec<ThreadChoices>,
+    /// Where every presented choice was encountered.
+    ///
+    /// Only set if some of the choices were gathered from threads, in which case the selected
+    /// choice is followed from its origin.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub choice_origins: Vec<ChoiceOrigin>,
+    /// Knot, stitch or label whose next visit is not counted.
+    ///
+    /// Set when the host moves the story to an address without counting the visit.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub uncounted_visit: Option<Address>,
+}
+
+impl FollowData {
+    /// Get the current value of a global variable.
+    ///
+    /// Variables which are backed by the external store are read from it.
+    pub fn get_variable(&self, name: &str) -> Option<Variable> {
+        self.variables
+            .get(name)
+            .map(|variable_info| match self.external_variables.get(name) {
+                Some(variable) => {
+                    self.record_nondeterminism(NondeterminismKind::ExternalVariable {
+                        name: name.to_string(),
+                    });
+
+                    variable
+                }
+                None => variable_info.variable.clone(),
+            })
+    }
+
+    /// Assign a value to a global variable from a line in the story.
+    ///
+    /// Variables which are backed by the external store are assigned in it, unless
+    /// they are constant. The change is recorded in the history of variables if the
+    /// variable is watched, and given to the observers of the variable if its value changed.
+    pub fn assign_variable(
+        &mut self,
+        name: &str,
+        value: Variable,
+        meta_data: &MetaData,
+    ) -> Result<(), InklingError> {
+        let variable_info =
+            self.variables
+                .get_mut(name)
+                .ok_or_else(|| InklingError::InvalidVariable {
+                    name: name.to_string(),
+                })?;
+
+        let old = self
+            .external_variables
+            .get(name)
+            .unwrap_or_else(|| variable_info.variable.clone());
+
+        match self.external_variables.get(name) {
+            Some(mut variable) if !variable_info.is_const => {
+                variable.assign(value)?;
+                self.external_variables.set(name, variable);
+            }
+            _ => variable_info.assign(value, name)?,
+        }
+
+        let new = self
+            .external_variables
+            .get(name)
+            .unwrap_or_else(|| variable_info.variable.clone());
+
+        let is_observed = self.variable_observers.is_observed(name) && new != old;
+
+        if self.variable_history.is_some() || is_observed {
+            let location = Location {
+                knot: meta_data
+                    .knot
+                    .clone()
+                    .unwrap_or_else(|| ROOT_KNOT_NAME.to_string()),
+                stitch: meta_data.stitch.clone(),
+            };
+
+            let change = VariableChange {
+                name: name.to_string(),
+                old: Some(old),
+                new,
+                location,
+                origin: ChangeOrigin::Story {
+                    meta_data: meta_data.clone(),
+                },
+                turn: self.turn,
+            };
+
+            if is_observed {
+                self.variable_observers.notify(&change);
+            }
+
+            if let Some(history) = self.variable_history.as_mut() {
+                history.record(change);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a source of nondeterminism at the current line, if they are being audited.
+    pub fn record_nondeterminism(&self, kind: NondeterminismKind) {
+        if let Some(audit) = self.determinism_audit.as_ref() {
+            audit.record(kind, self.condition_coverage.get_current_line());
+        }
+    }
+}
+
+#[cfg(feature = "serde_support")]
+/// Serialize the visit counts of only the knots and stitches which have been visited.
+fn serialize_visited_counts<S: Serializer>(
+    knot_visit_counts: &HashMap<String, HashMap<String, u32>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let visited = knot_visit_counts
+        .iter()
+        .map(|(knot, stitches)| {
+            let visited_stitches = stitches
+                .iter()
+                .filter(|(_, num_visited)| **num_visited > 0)
+                .collect::<HashMap<_, _>>();
+
+            (knot, visited_stitches)
+        })
+        .filter(|(_, stitches)| !stitches.is_empty())
+        .collect::<HashMap<_, _>>();
+
+    visited.serialize(serializer)
+}
+
+#[cfg(feature = "serde_support")]
+/// Serialize the values of only the variables which differ from their declaration.
+///
+/// Transient variables are saved with the value that they are reset to.
+fn serialize_variables<S: Serializer>(
+    variables: &VariableSet,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let changed = variables
+        .iter()
+        .map(|(name, variable_info)| {
+            let variable = variable_info
+                .transient
+                .as_ref()
+                .unwrap_or(&variable_info.variable);
+
+            (name, variable_info, variable)
+        })
+        .filter(|(_, variable_info, variable)| variable_info.declared.as_ref() != Some(variable))
+        .map(|(name, _, variable)| (name, variable))
+        .collect::<HashMap<_, _>>();
+
+    changed.serialize(serializer)
+}
+
+#[cfg(feature = "serde_support")]
+/// Deserialize the values of the variables which differ from their declaration.
+///
+/// The variables are completed from the declarations of the story when it is loaded.
+fn deserialize_variables<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<VariableSet, D::Error> {
+    let changed = HashMap::<String, Variable>::deserialize(deserializer)?;
+
+    Ok(changed
+        .into_iter()
+        .map(|(name, variable)| {
+            let variable_info = VariableInfo {
+                is_const: false,
+                variable,
+                meta_data: MetaData::from(0),
+                declared: None,
+                transient: None,
+                is_host_constant: false,
+                list_items: None,
+            };
+
+            (name, variable_info)
+        })
+        .collect())
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -78,8 +412,26 @@ pub struct LineText {
     pub glue_begin: bool,
     /// Whether or not the line glues to the previous line.
     pub glue_end: bool,
+    /// Whether or not the line was ended by a divert.
+    pub divert_end: bool,
     /// Tags associated with the line.
     pub tags: Vec<String>,
+    /// Custom directive which was encountered instead of a line of text.
+    pub directive: Option<Directive>,
+}
+
+impl LineText {
+    /// Create an item for a custom directive, which has no text.
+    pub fn from_directive(directive: Directive) -> Self {
+        LineText {
+            text: String::new(),
+            glue_begin: false,
+            glue_end: false,
+            divert_end: false,
+            tags: Vec::new(),
+            directive: Some(directive),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -90,6 +442,7 @@ pub struct LineTextBuilder {
     pub text: String,
     pub glue_begin: bool,
     pub glue_end: bool,
+    pub divert_end: bool,
     pub tags: Vec<String>,
 }
 
@@ -100,6 +453,7 @@ impl LineTextBuilder {
             text: content.to_string(),
             glue_begin: false,
             glue_end: false,
+            divert_end: false,
             tags: Vec::new(),
         }
     }
@@ -109,7 +463,9 @@ impl LineTextBuilder {
             text: self.text,
             glue_begin: self.glue_begin,
             glue_end: self.glue_end,
+            divert_end: self.divert_end,
             tags: self.tags,
+            directive: None,
         }
     }
 
@@ -123,6 +479,11 @@ impl LineTextBuilder {
         self
     }
 
+    pub fn with_divert_end(mut self) -> Self {
+        self.divert_end = true;
+        self
+    }
+
     pub fn with_tags(mut self, tags: &[String]) -> Self {
         self.tags = tags.to_vec();
         self
@@ -143,7 +504,7 @@ impl FollowDataBuilder {
         FollowDataBuilder {
             knot_visit_counts: HashMap::new(),
             variables: VariableSet::new(),
-            rng: StoryRng::default(),
+            rng: StoryRng::new(),
         }
     }
 
@@ -165,8 +526,29 @@ impl FollowDataBuilder {
     pub fn build(self) -> FollowData {
         FollowData {
             knot_visit_counts: self.knot_visit_counts,
+            label_visit_counts: HashMap::new(),
             variables: self.variables,
+            temporary_variables: HashMap::new(),
             rng: self.rng,
+            condition_coverage: ConditionCoverage::default(),
+            external_variables: ExternalVariables::default(),
+            turn: 0,
+            last_visited_turns: HashMap::new(),
+            visit_journal: None,
+            variable_history: None,
+            variable_observers: VariableObservers::default(),
+            determinism_audit: None,
+            ending: None,
+            proofreading: false,
+            max_lines_per_resume: None,
+            max_diverts_per_resume: None,
+            functions: Arc::new(FunctionSet::new()),
+            call_depth: 0,
+            divert_arguments: Vec::new(),
+            external_functions: ExternalFunctions::default(),
+            thread_choices: Vec::new(),
+            choice_origins: Vec::new(),
+            uncounted_visit: None,
         }
     }
 }
@@ -16,6 +16,7 @@ use crate::{
     knot::KnotSet,
     log::Logger,
     story::validate::{KnotValidationInfo, ValidateContent, ValidationData},
+    utils::get_close_matches,
 };
 
 use std::collections::HashMap;
@@ -30,16 +31,59 @@ pub enum Address {
     /// An address that has been validated and is guarantueed to resolve.
     Validated(AddressKind),
     /// This string-formatted address has not yet been validated.
-    Raw(String),
+    Raw(#[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))] String),
     /// Divert address to mark that a story is finished.
     End,
+    /// Divert address to mark that the current flow of the story is finished.
+    ///
+    /// Ends the story, except in threads where only the thread is ended.
+    Done,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 pub enum AddressKind {
-    Location { knot: String, stitch: String },
-    GlobalVariable { name: String },
+    Location {
+        #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+        knot: String,
+        #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+        stitch: String,
+    },
+    GlobalVariable {
+        #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+        name: String,
+    },
+    TemporaryVariable {
+        #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+        name: String,
+    },
+    /// Labeled choice or gather in a `Stitch`.
+    ///
+    /// Boxed to keep addresses, which are carried by many errors, small.
+    Label(Box<LabelAddress>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Address of a labeled choice or gather, with the knot and stitch that it is in.
+pub struct LabelAddress {
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+    pub knot: String,
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+    pub stitch: String,
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
+    pub label: String,
+}
+
+impl AddressKind {
+    /// Create the address of a label in a stitch.
+    fn label(knot: String, stitch: String, label: String) -> Self {
+        AddressKind::Label(Box::new(LabelAddress {
+            knot,
+            stitch,
+            label,
+        }))
+    }
 }
 
 impl From<AddressKind> for Address {
@@ -63,6 +107,7 @@ impl Address {
             .get(root_knot_name)
             .ok_or(InvalidAddressErrorKind::UnknownKnot {
                 knot_name: root_knot_name.to_string(),
+                suggestions: Vec::new(),
             })?;
 
         Ok(Address::Validated(AddressKind::Location {
@@ -76,11 +121,13 @@ impl Address {
         location: &Location,
         knots: &KnotSet,
     ) -> Result<Self, InvalidAddressErrorKind> {
-        let knot = knots
-            .get(&location.knot)
-            .ok_or(InvalidAddressErrorKind::UnknownKnot {
-                knot_name: location.knot.to_string(),
-            })?;
+        let knot =
+            knots
+                .get(&location.knot)
+                .ok_or_else(|| InvalidAddressErrorKind::UnknownKnot {
+                    knot_name: location.knot.to_string(),
+                    suggestions: get_close_names(&location.knot, knots.keys()),
+                })?;
 
         let stitch_name = location.stitch.as_ref().unwrap_or(&knot.default_stitch);
 
@@ -93,17 +140,85 @@ impl Address {
             Err(InvalidAddressErrorKind::UnknownStitch {
                 knot_name: location.knot.clone(),
                 stitch_name: stitch_name.clone(),
+                suggestions: get_close_names(stitch_name, knot.stitches.keys()),
             })
         }
     }
 
+    /// Validate that a full address to a knot, stitch or label exists in the knotset and
+    /// create its `Address`.
+    ///
+    /// Addresses are written like in diverts, but always begin with the knot: `knot`,
+    /// `knot.stitch`, `knot.label` for labels in the root stitch of the knot or
+    /// `knot.stitch.label`.
+    pub fn from_full_address(
+        address: &str,
+        knots: &KnotSet,
+    ) -> Result<Self, InvalidAddressErrorKind> {
+        let (knot_name, stitch_name, label_name) = split_address_into_parts(address.trim())?;
+
+        match (stitch_name, label_name) {
+            (None, _) => Address::from_location(&Location::new(knot_name, None), knots),
+            (Some(second), None) => {
+                let knot =
+                    knots
+                        .get(&knot_name)
+                        .ok_or_else(|| InvalidAddressErrorKind::UnknownKnot {
+                            suggestions: get_close_names(&knot_name, knots.keys()),
+                            knot_name: knot_name.clone(),
+                        })?;
+
+                let root_labels = knot
+                    .stitches
+                    .get(ROOT_KNOT_NAME)
+                    .map(|stitch| stitch.labels.keys().collect::<Vec<_>>())
+                    .unwrap_or_default();
+
+                if knot.stitches.contains_key(&second) {
+                    Address::from_location(&Location::with_stitch(knot_name, second), knots)
+                } else if root_labels.contains(&&second) {
+                    Ok(AddressKind::label(knot_name, ROOT_KNOT_NAME.to_string(), second).into())
+                } else {
+                    Err(InvalidAddressErrorKind::UnknownStitch {
+                        suggestions: get_close_names(
+                            &second,
+                            knot.stitches.keys().chain(root_labels),
+                        ),
+                        knot_name,
+                        stitch_name: second,
+                    })
+                }
+            }
+            (Some(stitch_name), Some(label_name)) => {
+                let location = Location::with_stitch(&knot_name, &stitch_name);
+                Address::from_location(&location, knots)?;
+
+                let labels = &knots[&knot_name].stitches[&stitch_name].labels;
+
+                if labels.contains_key(&label_name) {
+                    Ok(AddressKind::label(knot_name, stitch_name, label_name).into())
+                } else {
+                    Err(InvalidAddressErrorKind::UnknownLabel {
+                        suggestions: get_close_names(&label_name, labels.keys()),
+                        knot_name,
+                        stitch_name,
+                        label_name,
+                    })
+                }
+            }
+        }
+    }
+
     /// Get the knot name of a validated address.
+    ///
+    /// The knot and stitch of a label are those that it is in.
     pub fn get_knot(&self) -> Result<&str, InternalError> {
         match self {
             Address::Validated(AddressKind::Location { knot, .. }) => Ok(knot),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
-                Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
-            }
+            Address::Validated(AddressKind::Label(label)) => Ok(&label.knot),
+            Address::Validated(
+                AddressKind::GlobalVariable { name } | AddressKind::TemporaryVariable { name },
+            ) => Err(InternalError::UseOfVariableAsLocation { name: name.clone() }),
             _ => Err(InternalError::UseOfUnvalidatedAddress {
                 address: self.clone(),
             }),
@@ -114,9 +229,10 @@ impl Address {
     pub fn get_stitch(&self) -> Result<&str, InternalError> {
         match self {
             Address::Validated(AddressKind::Location { stitch, .. }) => Ok(stitch),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
-                Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
-            }
+            Address::Validated(AddressKind::Label(label)) => Ok(&label.stitch),
+            Address::Validated(
+                AddressKind::GlobalVariable { name } | AddressKind::TemporaryVariable { name },
+            ) => Err(InternalError::UseOfVariableAsLocation { name: name.clone() }),
             _ => Err(InternalError::UseOfUnvalidatedAddress {
                 address: self.clone(),
             }),
@@ -127,19 +243,33 @@ impl Address {
     pub fn get_knot_and_stitch(&self) -> Result<(&str, &str), InternalError> {
         match self {
             Address::Validated(AddressKind::Location { knot, stitch }) => Ok((knot, stitch)),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
-                Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
-            }
+            Address::Validated(AddressKind::Label(label)) => Ok((&label.knot, &label.stitch)),
+            Address::Validated(
+                AddressKind::GlobalVariable { name } | AddressKind::TemporaryVariable { name },
+            ) => Err(InternalError::UseOfVariableAsLocation { name: name.clone() }),
             _ => Err(InternalError::UseOfUnvalidatedAddress {
                 address: self.clone(),
             }),
         }
     }
 
+    /// Get the address of a label in the knot and stitch of a validated address.
+    pub fn with_label(&self, label: &str) -> Result<Address, InternalError> {
+        let (knot, stitch) = self.get_knot_and_stitch()?;
+
+        Ok(Address::Validated(AddressKind::label(
+            knot.to_string(),
+            stitch.to_string(),
+            label.to_string(),
+        )))
+    }
+
     /// Get a string representation of the address as `Ink` would write it.
     pub fn to_string(&self) -> String {
         match &self {
-            Address::Validated(AddressKind::GlobalVariable { name }) => name.clone(),
+            Address::Validated(
+                AddressKind::GlobalVariable { name } | AddressKind::TemporaryVariable { name },
+            ) => name.clone(),
             Address::Validated(AddressKind::Location { knot, stitch }) => {
                 if stitch.as_str() == ROOT_KNOT_NAME {
                     format!("{}", knot)
@@ -147,8 +277,87 @@ impl Address {
                     format!("{}.{}", knot, stitch)
                 }
             }
+            Address::Validated(AddressKind::Label(label)) => {
+                [&label.knot, &label.stitch, &label.label]
+                    .iter()
+                    .filter(|name| name.as_str() != ROOT_KNOT_NAME)
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            }
             Address::Raw(content) => content.clone(),
             Address::End => "END".to_string(),
+            Address::Done => "DONE".to_string(),
+        }
+    }
+
+    /// Validate a `Raw` address as the variable which a logic line assigns to.
+    ///
+    /// Temporary variables which have been declared in the current stitch take precedence
+    /// over global variables. Assigning to a variable does not mark it as read.
+    pub fn validate_as_variable(
+        &mut self,
+        data: &ValidationData,
+    ) -> Result<(), InvalidAddressErrorKind> {
+        if let Address::Raw(ref name) = self {
+            let name = name.trim().to_string();
+            let temporary_variables = data.temporary_variables.borrow();
+
+            let address = if temporary_variables.contains_key(&name) {
+                AddressKind::TemporaryVariable { name }
+            } else if data.follow_data.variables.contains_key(&name) {
+                AddressKind::GlobalVariable { name }
+            } else {
+                let candidates = data
+                    .follow_data
+                    .variables
+                    .keys()
+                    .chain(temporary_variables.keys());
+
+                return Err(InvalidAddressErrorKind::UnknownVariable {
+                    suggestions: get_close_names(&name, candidates),
+                    name,
+                });
+            };
+
+            drop(temporary_variables);
+            *self = address.into();
+        }
+
+        Ok(())
+    }
+
+    /// Validate that a divert to the address is given an argument for every parameter
+    /// of its knot.
+    ///
+    /// Parameters are set when a knot is entered at its default stitch. Diverts to its
+    /// other stitches or labels, or to the end of the story, take no arguments. Diverts to
+    /// variables are not checked, since their targets are not known until the story is followed.
+    pub fn validate_divert_arguments(
+        &self,
+        num_arguments: usize,
+        data: &ValidationData,
+    ) -> Result<(), InvalidAddressErrorKind> {
+        let expected = match self {
+            Address::Validated(AddressKind::Location { knot, stitch }) => data
+                .knots
+                .get(knot)
+                .filter(|knot_info| &knot_info.default_stitch == stitch)
+                .map(|knot_info| knot_info.parameters.len())
+                .unwrap_or(0),
+            Address::Validated(AddressKind::Label(..)) => 0,
+            Address::Validated(..) => return Ok(()),
+            _ => 0,
+        };
+
+        if num_arguments == expected {
+            Ok(())
+        } else {
+            Err(InvalidAddressErrorKind::InvalidDivertArguments {
+                address: self.to_string(),
+                expected,
+                found: num_arguments,
+            })
         }
     }
 
@@ -159,19 +368,27 @@ impl Address {
         data: &ValidationData,
     ) -> Result<(), InvalidAddressErrorKind> {
         match self {
-            Address::Raw(ref target) if target == DONE_KNOT || target == END_KNOT => {
+            Address::Raw(ref target) if target == END_KNOT => {
                 *self = Address::End;
             }
+            Address::Raw(ref target) if target == DONE_KNOT => {
+                *self = Address::Done;
+            }
             Address::Raw(ref target) => {
                 let address = match split_address_into_parts(target.trim())? {
-                    (knot, Some(stitch)) => get_location_from_parts(knot, stitch, &data.knots)?,
-                    (needle, None) => get_address_from_needle(needle, current_location, data)?,
+                    (knot, Some(stitch), Some(label)) => {
+                        get_label_from_parts(knot, stitch, label, &data.knots)?
+                    }
+                    (first, Some(second), None) => {
+                        get_address_from_two_parts(first, second, current_location, data)?
+                    }
+                    (needle, ..) => get_address_from_needle(needle, current_location, data)?,
                 }
                 .into();
 
                 *self = address;
             }
-            Address::Validated { .. } | Address::End => (),
+            Address::Validated { .. } | Address::End | Address::Done => (),
         }
 
         Ok(())
@@ -187,19 +404,26 @@ impl ValidateContent for Address {
         meta_data: &MetaData,
         data: &ValidationData,
     ) {
-        if let Err(kind) = self.validate_internal(current_location, data) {
-            let err = InvalidAddressError {
-                kind,
-                meta_data: meta_data.clone(),
-            };
-
-            if error
-                .invalid_address_errors
-                .last()
-                .map(|last_err| last_err != &err)
-                .unwrap_or(true)
-            {
-                error.invalid_address_errors.push(err);
+        match self.validate_internal(current_location, data) {
+            Ok(()) => {
+                if let Address::Validated(AddressKind::GlobalVariable { name }) = self {
+                    data.read_variables.borrow_mut().insert(name.clone());
+                }
+            }
+            Err(kind) => {
+                let err = InvalidAddressError {
+                    kind,
+                    meta_data: meta_data.clone(),
+                };
+
+                if error
+                    .invalid_address_errors
+                    .last()
+                    .map(|last_err| last_err != &err)
+                    .unwrap_or(true)
+                {
+                    error.invalid_address_errors.push(err);
+                }
             }
         }
     }
@@ -207,24 +431,28 @@ impl ValidateContent for Address {
 
 /// Split an address into constituent parts if possible.
 ///
-/// The split is done at a dot ('.') marker. If one exists, split at it and return the parts.
-/// Otherwise return the entire string.
+/// The split is done at dot ('.') markers. A full address has at most three parts:
+/// the knot, stitch and label names. If there are no markers the entire string is returned.
 fn split_address_into_parts(
     address: &str,
-) -> Result<(String, Option<String>), InvalidAddressErrorKind> {
-    if let Some(i) = address.find('.') {
-        let knot = address.get(..i).unwrap();
-
-        let stitch = address
-            .get(i + 1..)
-            .ok_or(InvalidAddressErrorKind::BadFormat {
-                line: address.to_string(),
-            })?;
-
-        Ok((knot.to_string(), Some(stitch.to_string())))
-    } else {
-        Ok((address.to_string(), None))
+) -> Result<(String, Option<String>, Option<String>), InvalidAddressErrorKind> {
+    let mut parts = address.splitn(3, '.').map(|part| part.to_string());
+
+    let knot = parts.next().unwrap_or_default();
+    let stitch = parts.next();
+    let label = parts.next();
+
+    if label
+        .as_ref()
+        .map(|label| label.contains('.'))
+        .unwrap_or(false)
+    {
+        return Err(InvalidAddressErrorKind::BadFormat {
+            line: address.to_string(),
+        });
     }
+
+    Ok((knot, stitch, label))
 }
 
 /// Verify and return the full address to a stitch from its parts.
@@ -233,12 +461,13 @@ fn get_location_from_parts(
     stitch_name: String,
     knots: &HashMap<String, KnotValidationInfo>,
 ) -> Result<AddressKind, InvalidAddressErrorKind> {
-    let KnotValidationInfo { stitches, .. } =
-        knots
-            .get(&knot_name)
-            .ok_or(InvalidAddressErrorKind::UnknownKnot {
-                knot_name: knot_name.clone(),
-            })?;
+    let KnotValidationInfo { stitches, .. } = knots
+        .get(&knot_name)
+        .filter(|knot_info| !knot_info.is_function)
+        .ok_or_else(|| InvalidAddressErrorKind::UnknownKnot {
+            knot_name: knot_name.clone(),
+            suggestions: get_close_names(&knot_name, knots.keys()),
+        })?;
 
     if stitches.contains_key(&stitch_name) {
         Ok(AddressKind::Location {
@@ -247,21 +476,130 @@ fn get_location_from_parts(
         })
     } else {
         Err(InvalidAddressErrorKind::UnknownStitch {
-            knot_name: knot_name.clone(),
-            stitch_name: stitch_name.clone(),
+            suggestions: get_close_names(&stitch_name, stitches.keys()),
+            knot_name,
+            stitch_name,
         })
     }
 }
 
+/// Verify the full address to a label from its parts.
+///
+/// The knot and stitch are verified first, so that errors point at the first part of
+/// the address which is missing.
+fn get_label_from_parts(
+    knot_name: String,
+    stitch_name: String,
+    label_name: String,
+    knots: &HashMap<String, KnotValidationInfo>,
+) -> Result<AddressKind, InvalidAddressErrorKind> {
+    get_location_from_parts(knot_name.clone(), stitch_name.clone(), knots)?;
+
+    get_label_in_stitch(knot_name, stitch_name, label_name, knots)
+}
+
+/// Return a validated address from a name with two parts, `first.second`.
+///
+/// If the first name is a knot the second name is one of its stitches, or a label in
+/// the root stitch of the knot. Otherwise the first name is a stitch in the current knot
+/// and the second name is one of its labels.
+fn get_address_from_two_parts(
+    first: String,
+    second: String,
+    current_address: &Address,
+    data: &ValidationData,
+) -> Result<AddressKind, InvalidAddressErrorKind> {
+    let knots = &data.knots;
+
+    if let Some(knot_info) = knots.get(&first).filter(|knot_info| !knot_info.is_function) {
+        let root_labels = get_stitch_labels(&first, ROOT_KNOT_NAME, knots);
+
+        return if knot_info.stitches.contains_key(&second) {
+            Ok(AddressKind::Location {
+                knot: first,
+                stitch: second,
+            })
+        } else if root_labels.contains(&second) {
+            Ok(AddressKind::label(
+                first,
+                ROOT_KNOT_NAME.to_string(),
+                second,
+            ))
+        } else {
+            Err(InvalidAddressErrorKind::UnknownStitch {
+                suggestions: get_close_names(&second, knot_info.stitches.keys().chain(root_labels)),
+                knot_name: first,
+                stitch_name: second,
+            })
+        };
+    }
+
+    let current_knot_name = current_address
+        .get_knot()
+        .ok()
+        .filter(|knot_name| {
+            knots
+                .get(*knot_name)
+                .map(|knot_info| knot_info.stitches.contains_key(&first))
+                .unwrap_or(false)
+        })
+        .map(|knot_name| knot_name.to_string());
+
+    match current_knot_name {
+        Some(knot_name) => get_label_in_stitch(knot_name, first, second, knots),
+        None => Err(InvalidAddressErrorKind::UnknownKnot {
+            suggestions: get_close_names(&first, knots.keys()),
+            knot_name: first,
+        }),
+    }
+}
+
+/// Verify that a label is in a stitch which exists and return its address.
+fn get_label_in_stitch(
+    knot_name: String,
+    stitch_name: String,
+    label_name: String,
+    knots: &HashMap<String, KnotValidationInfo>,
+) -> Result<AddressKind, InvalidAddressErrorKind> {
+    let labels = get_stitch_labels(&knot_name, &stitch_name, knots);
+
+    if labels.contains(&label_name) {
+        Ok(AddressKind::label(knot_name, stitch_name, label_name))
+    } else {
+        Err(InvalidAddressErrorKind::UnknownLabel {
+            suggestions: get_close_names(&label_name, labels),
+            knot_name,
+            stitch_name,
+            label_name,
+        })
+    }
+}
+
+/// Get the names of the labels in a stitch, which are none if the stitch does not exist.
+fn get_stitch_labels<'a>(
+    knot_name: &str,
+    stitch_name: &str,
+    knots: &'a HashMap<String, KnotValidationInfo>,
+) -> &'a [String] {
+    knots
+        .get(knot_name)
+        .and_then(|knot_info| knot_info.stitches.get(stitch_name))
+        .map(|stitch_info| stitch_info.labels.as_slice())
+        .unwrap_or(&[])
+}
+
 /// Return a validated address from a single name.
 ///
-/// Internal addresses are relative to the current knot. If one is found in the current knot,
-/// the knot name and the address is returned. Otherwise the default stitch from a knot
-/// with the name is returned.
+/// Internal addresses are relative to the current stitch and knot, with the nearest scope
+/// first: labels in the current stitch, then stitches in the current knot and labels in
+/// its root stitch. Otherwise the default stitch from a knot with the name is returned.
 ///
 /// If the name is not found in the current knot's stitches, or in the set of knot names,
 /// the variable listing is searched. If a match is found the address will be returned
 /// as a global variable.
+///
+/// Temporary variables which have been declared in the current stitch take precedence
+/// over all other names.
 fn get_address_from_needle(
     needle: String,
     current_address: &Address,
@@ -270,15 +608,41 @@ fn get_address_from_needle(
     let (current_knot_name, current_stitches) =
         get_knot_name_and_stitches(current_address, &data.knots, &needle)?;
 
+    let current_stitch_name = current_address.get_stitch().unwrap_or(ROOT_KNOT_NAME);
+    let stitch_labels = get_stitch_labels(&current_knot_name, current_stitch_name, &data.knots);
+    let knot_labels = get_stitch_labels(&current_knot_name, ROOT_KNOT_NAME, &data.knots);
+
+    let temporary_variables = data.temporary_variables.borrow();
+
+    let matches_temporary_variable = temporary_variables.contains_key(&needle);
+    let matches_label_in_current_stitch = stitch_labels.contains(&needle);
     let matches_stitch_in_current_knot = current_stitches.contains(&needle);
-    let matches_knot = data.knots.get(&needle);
+    let matches_label_in_current_knot = knot_labels.contains(&needle);
+    let matches_knot = data
+        .knots
+        .get(&needle)
+        .filter(|knot_info| !knot_info.is_function);
     let matches_variable = data.follow_data.variables.contains_key(&needle);
 
-    if matches_stitch_in_current_knot {
+    if matches_temporary_variable {
+        Ok(AddressKind::TemporaryVariable { name: needle })
+    } else if matches_label_in_current_stitch {
+        Ok(AddressKind::label(
+            current_knot_name,
+            current_stitch_name.to_string(),
+            needle,
+        ))
+    } else if matches_stitch_in_current_knot {
         Ok(AddressKind::Location {
             knot: current_knot_name.to_string(),
             stitch: needle,
         })
+    } else if matches_label_in_current_knot {
+        Ok(AddressKind::label(
+            current_knot_name,
+            ROOT_KNOT_NAME.to_string(),
+            needle,
+        ))
     } else if let Some(knot_info) = matches_knot {
         Ok(AddressKind::Location {
             knot: needle,
@@ -287,12 +651,37 @@ fn get_address_from_needle(
     } else if matches_variable {
         Ok(AddressKind::GlobalVariable { name: needle })
     } else {
+        let candidates = current_stitches
+            .iter()
+            .chain(stitch_labels)
+            .chain(knot_labels)
+            .chain(data.knots.keys())
+            .chain(data.follow_data.variables.keys())
+            .chain(temporary_variables.keys());
+
         Err(InvalidAddressErrorKind::UnknownAddress {
-            name: needle.clone(),
+            suggestions: get_close_names(&needle, candidates),
+            name: needle,
         })
     }
 }
 
+/// Get names of knots, stitches or variables which are close to the given name.
+///
+/// The name of root knots and stitches is internal and never suggested.
+fn get_close_names<'a, I>(name: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    get_close_matches(
+        name,
+        candidates
+            .into_iter()
+            .map(|candidate| candidate.as_str())
+            .filter(|candidate| *candidate != ROOT_KNOT_NAME),
+    )
+}
+
 /// Get the knot name and stitches from the given address.
 fn get_knot_name_and_stitches(
     address: &Address,
@@ -355,7 +744,7 @@ pub mod tests {
         address: &mut Address,
         current_location: &Address,
         data: &ValidationData,
-    ) -> Result<(), InvalidAddressError> {
+    ) -> Result<(), Box<InvalidAddressError>> {
         let mut error = ValidationError::new();
         let mut log = Logger::default();
 
@@ -364,7 +753,7 @@ pub mod tests {
         if error.is_empty() {
             Ok(())
         } else {
-            Err(error.invalid_address_errors[0].clone())
+            Err(Box::new(error.invalid_address_errors[0].clone()))
         }
     }
 
@@ -403,6 +792,7 @@ pub mod tests {
     #[test]
     fn string_representation_of_end_address_is_end() {
         assert_eq!(&Address::End.to_string(), "END");
+        assert_eq!(&Address::Done.to_string(), "DONE");
     }
 
     #[test]
@@ -633,7 +1023,7 @@ You find yourself in Tripoli, the capital of Libya.
 
         let mut done_address = Address::Raw("DONE".to_string());
         assert!(validate_address(&mut done_address, &current_address, &data).is_ok());
-        assert_eq!(done_address, Address::End);
+        assert_eq!(done_address, Address::Done);
     }
 
     #[test]
@@ -689,6 +1079,55 @@ You find yourself in Addis Ababa, the capital of Ethiopia.
         );
     }
 
+    #[test]
+    fn full_addresses_are_validated_to_knots_stitches_and_labels() {
+        let content = "
+== tripoli
+- (harbour) You see the harbour.
+-> END
+
+= cinema
+- (lobby) You wait in the lobby.
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+
+        assert_eq!(
+            Address::from_full_address("tripoli.cinema", &knots).unwrap(),
+            Address::from_parts_unchecked("tripoli", Some("cinema"))
+        );
+
+        assert_eq!(
+            Address::from_full_address("tripoli.harbour", &knots).unwrap(),
+            AddressKind::label(
+                "tripoli".to_string(),
+                ROOT_KNOT_NAME.to_string(),
+                "harbour".to_string()
+            )
+            .into()
+        );
+
+        assert_eq!(
+            Address::from_full_address(" tripoli.cinema.lobby ", &knots).unwrap(),
+            AddressKind::label(
+                "tripoli".to_string(),
+                "cinema".to_string(),
+                "lobby".to_string()
+            )
+            .into()
+        );
+
+        match Address::from_full_address("tripoli.cinema.lobb", &knots) {
+            Err(InvalidAddressErrorKind::UnknownLabel { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["lobby".to_string()])
+            }
+            other => panic!("expected `UnknownLabel` but got {:?}", other),
+        }
+
+        assert!(Address::from_full_address("tripoli.lobby", &knots).is_err());
+    }
+
     #[test]
     fn address_from_location_uses_default_stitch_if_set() {
         let content = "
@@ -722,4 +1161,384 @@ You find yourself in Cairo, the capital of Egypt.
             })
         );
     }
+    #[test]
+    fn unknown_addresses_suggest_close_knot_and_stitch_names() {
+        let content = "
+== tripoli
+-> END
+
+= with_family
+-> END
+
+== addis_ababa
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_knot("tripoli");
+
+        let error = validate_address(
+            &mut Address::Raw("with_famly".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownAddress {
+                name: "with_famly".to_string(),
+                suggestions: vec!["with_family".to_string()],
+            }
+        );
+
+        let error = validate_address(
+            &mut Address::Raw("adis_abeba".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownAddress {
+                name: "adis_abeba".to_string(),
+                suggestions: vec!["addis_ababa".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_knots_and_stitches_in_full_addresses_suggest_close_names() {
+        let content = "
+== tripoli
+-> END
+
+= with_family
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_knot("tripoli");
+
+        let error = validate_address(
+            &mut Address::Raw("tripli.with_family".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownKnot {
+                knot_name: "tripli".to_string(),
+                suggestions: vec!["tripoli".to_string()],
+            }
+        );
+
+        let error = validate_address(
+            &mut Address::Raw("tripoli.wit_family".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownStitch {
+                knot_name: "tripoli".to_string(),
+                stitch_name: "wit_family".to_string(),
+                suggestions: vec!["with_family".to_string()],
+            }
+        );
+
+        assert!(format!("{}", error).contains("(did you mean 'with_family'?)"));
+    }
+
+    #[test]
+    fn full_label_addresses_yield_errors_at_the_first_missing_part() {
+        let content = "
+== tripoli
+-> END
+
+= with_family
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_knot("tripoli");
+
+        let get_error_kind = |address: &str| {
+            validate_address(
+                &mut Address::Raw(address.to_string()),
+                &current_address,
+                &data,
+            )
+            .unwrap_err()
+            .kind
+        };
+
+        assert_eq!(
+            get_error_kind("tripli.with_family.dinner"),
+            InvalidAddressErrorKind::UnknownKnot {
+                knot_name: "tripli".to_string(),
+                suggestions: vec!["tripoli".to_string()],
+            }
+        );
+
+        assert_eq!(
+            get_error_kind("tripoli.without_family.dinner"),
+            InvalidAddressErrorKind::UnknownStitch {
+                knot_name: "tripoli".to_string(),
+                stitch_name: "without_family".to_string(),
+                suggestions: vec!["with_family".to_string()],
+            }
+        );
+
+        assert_eq!(
+            get_error_kind("tripoli.with_family.dinner"),
+            InvalidAddressErrorKind::UnknownLabel {
+                knot_name: "tripoli".to_string(),
+                stitch_name: "with_family".to_string(),
+                label_name: "dinner".to_string(),
+                suggestions: Vec::new(),
+            }
+        );
+
+        assert_eq!(
+            get_error_kind("tripoli.with_family.dinner.dessert"),
+            InvalidAddressErrorKind::BadFormat {
+                line: "tripoli.with_family.dinner.dessert".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn labels_are_resolved_from_the_nearest_enclosing_scope() {
+        let content = "
+== tripoli
+- (arrival) We arrived in Tripoli.
+-> END
+
+= with_family
+- (dinner) We had dinner.
+- (arrival) We arrived at their home.
+-> END
+
+= alone
+-> END
+
+== cairo
+- (dinner) We had dinner in Cairo.
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let get_address = |address: &str, current_address: &Address| {
+            let mut address = Address::Raw(address.to_string());
+            validate_address(&mut address, current_address, &data).unwrap();
+            address
+        };
+
+        let label = |knot: &str, stitch: &str, label: &str| {
+            Address::Validated(AddressKind::label(
+                knot.to_string(),
+                stitch.to_string(),
+                label.to_string(),
+            ))
+        };
+
+        let in_family = Address::from_parts_unchecked("tripoli", Some("with_family"));
+        let alone = Address::from_parts_unchecked("tripoli", Some("alone"));
+
+        assert_eq!(
+            get_address("arrival", &in_family),
+            label("tripoli", "with_family", "arrival")
+        );
+        assert_eq!(
+            get_address("arrival", &alone),
+            label("tripoli", ROOT_KNOT_NAME, "arrival")
+        );
+        assert_eq!(
+            get_address("with_family.arrival", &alone),
+            label("tripoli", "with_family", "arrival")
+        );
+        assert_eq!(
+            get_address("tripoli.arrival", &in_family),
+            label("tripoli", ROOT_KNOT_NAME, "arrival")
+        );
+        assert_eq!(
+            get_address("cairo.dinner", &in_family),
+            label("cairo", ROOT_KNOT_NAME, "dinner")
+        );
+        assert_eq!(
+            get_address("tripoli.with_family.dinner", &Address::from_knot("cairo")),
+            label("tripoli", "with_family", "dinner")
+        );
+    }
+
+    #[test]
+    fn unknown_labels_suggest_close_label_names() {
+        let content = "
+== tripoli
+- (arrival) We arrived in Tripoli.
+-> END
+
+= with_family
+- (dinner) We had dinner.
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_knot("tripoli");
+
+        let get_error_kind = |address: &str| {
+            validate_address(
+                &mut Address::Raw(address.to_string()),
+                &current_address,
+                &data,
+            )
+            .unwrap_err()
+            .kind
+        };
+
+        assert_eq!(
+            get_error_kind("with_family.diner"),
+            InvalidAddressErrorKind::UnknownLabel {
+                knot_name: "tripoli".to_string(),
+                stitch_name: "with_family".to_string(),
+                label_name: "diner".to_string(),
+                suggestions: vec!["dinner".to_string()],
+            }
+        );
+
+        assert_eq!(
+            get_error_kind("tripoli.arival"),
+            InvalidAddressErrorKind::UnknownStitch {
+                knot_name: "tripoli".to_string(),
+                stitch_name: "arival".to_string(),
+                suggestions: vec!["arrival".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn string_representation_of_labels_omits_root_stitches() {
+        let address = Address::Validated(AddressKind::label(
+            "tripoli".to_string(),
+            ROOT_KNOT_NAME.to_string(),
+            "arrival".to_string(),
+        ));
+
+        assert_eq!(&address.to_string(), "tripoli.arrival");
+    }
+
+    #[test]
+    fn unknown_addresses_without_close_names_have_no_suggestions() {
+        let content = "
+== tripoli
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_knot("tripoli");
+
+        let error = validate_address(
+            &mut Address::Raw("cairo".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownAddress {
+                name: "cairo".to_string(),
+                suggestions: Vec::new(),
+            }
+        );
+
+        assert!(!format!("{}", error).contains("did you mean"));
+    }
+    #[test]
+    fn unknown_addresses_suggest_close_variable_names() {
+        let content = "
+== tripoli
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+
+        let mut variables = HashMap::new();
+        variables.insert(
+            "num_tickets".to_string(),
+            VariableInfo::new(Variable::Int(1), 0),
+        );
+        variables.insert("destination".to_string(), VariableInfo::new(true, 1));
+
+        let data = ValidationData::from_data(&knots, &variables);
+
+        let current_address = Address::from_knot("tripoli");
+
+        let error = validate_address(
+            &mut Address::Raw("num_ticket".to_string()),
+            &current_address,
+            &data,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            InvalidAddressErrorKind::UnknownAddress {
+                name: "num_ticket".to_string(),
+                suggestions: vec!["num_tickets".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn assignment_targets_validate_only_to_variables() {
+        let content = "
+== tripoli
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+
+        let mut variables = HashMap::new();
+        variables.insert(
+            "num_tickets".to_string(),
+            VariableInfo::new(Variable::Int(1), 0),
+        );
+
+        let data = ValidationData::from_data(&knots, &variables);
+
+        let mut address = Address::Raw("num_tickets".to_string());
+        address.validate_as_variable(&data).unwrap();
+
+        assert_eq!(address, Address::variable_unchecked("num_tickets"));
+        assert!(data.read_variables.borrow().is_empty());
+
+        assert_eq!(
+            Address::Raw("tripoli".to_string())
+                .validate_as_variable(&data)
+                .unwrap_err(),
+            InvalidAddressErrorKind::UnknownVariable {
+                name: "tripoli".to_string(),
+                suggestions: Vec::new(),
+            }
+        );
+    }
 }
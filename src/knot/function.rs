@@ -0,0 +1,112 @@
+//! Functions which are written as knots in a story and called from expressions.
+
+use crate::{
+    consts::MAX_FUNCTION_CALL_DEPTH,
+    error::InklingError,
+    follow::{EncounteredEvent, FollowData, LineDataBuffer},
+    knot::{Knot, KnotSet},
+    line::Variable,
+    node::{Follow, RootNode},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, mem, sync::Arc};
+
+/// Convenience type for a set of `InkFunction`s.
+///
+/// The function names are used as keys in the collection.
+pub type FunctionSet = HashMap<String, InkFunction>;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Function which is written as a knot in the story.
+///
+/// Functions are declared with the [function marker][crate::consts::FUNCTION_MARKER]
+/// and return values with `~ return` lines:
+///
+/// ```text
+/// === function max(a, b) ===
+/// { a > b:
+///     ~ return a
+/// }
+/// ~ return b
+/// ```
+///
+/// They are called from expressions, like `{max(x, y)}` in text or `~ temp m = max(x, y)`
+/// in logic lines.
+pub struct InkFunction {
+    /// Names of the parameters, which are temporary variables in the function.
+    pub parameters: Vec<String>,
+    /// Content of the function.
+    pub root: RootNode,
+}
+
+impl InkFunction {
+    /// Create a function from the content of a knot.
+    ///
+    /// Functions are not divided into stitches, so only the default stitch is used.
+    pub fn from_knot(knot: &Knot) -> Option<Self> {
+        knot.stitches
+            .get(&knot.default_stitch)
+            .map(|stitch| InkFunction {
+                parameters: knot.parameters.clone(),
+                root: stitch.root.clone(),
+            })
+    }
+
+    /// Follow the function in the story with evaluated arguments and get the value that
+    /// it returns, if it returns one.
+    ///
+    /// The function is followed in the story state itself: changes that it makes to
+    /// variables and visit counts are kept, and the text that it writes is added to the
+    /// buffer. Its parameters are the only temporary variables while it is followed.
+    ///
+    /// The function is then put back in the set of functions under its name, which keeps
+    /// the state of its alternative sequences for the next time that it is called.
+    pub fn follow_call(
+        mut self,
+        name: &str,
+        arguments: Vec<Variable>,
+        buffer: &mut LineDataBuffer,
+        data: &mut FollowData,
+    ) -> Result<Option<Variable>, InklingError> {
+        if data.call_depth >= MAX_FUNCTION_CALL_DEPTH {
+            return Err(InklingError::MaxCallDepthReached {
+                name: name.to_string(),
+            });
+        }
+
+        let parameters = self.parameters.iter().cloned().zip(arguments).collect();
+        let temporary_variables = mem::replace(&mut data.temporary_variables, parameters);
+        let line_cursor = data.condition_coverage.take_line_cursor();
+
+        data.call_depth += 1;
+
+        let result = self.root.follow(&mut vec![0], buffer, data);
+
+        Arc::make_mut(&mut data.functions).insert(name.to_string(), self);
+
+        data.call_depth -= 1;
+        data.temporary_variables = temporary_variables;
+        data.condition_coverage.set_line_cursor(line_cursor);
+
+        match result? {
+            EncounteredEvent::Return(value) => Ok(value),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Get the functions of a set of knots.
+pub fn get_function_set(knots: &KnotSet) -> FunctionSet {
+    knots
+        .iter()
+        .filter(|(_, knot)| knot.is_function)
+        .filter_map(|(name, knot)| {
+            InkFunction::from_knot(knot).map(|function| (name.clone(), function))
+        })
+        .collect()
+}
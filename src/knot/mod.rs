@@ -1,13 +1,17 @@
 //! Story structure collections: `Knot`s, `Stitch`es and utilities.
 
 mod address;
+mod function;
 mod stitch;
 mod utils;
 
 pub use address::{Address, AddressKind};
+pub use function::{get_function_set, FunctionSet, InkFunction};
 pub use stitch::{
-    parse_stitch_from_lines, read_knot_name, read_stitch_name, Knot, KnotSet, Stitch,
+    parse_stitch_from_lines, read_function_signature, read_knot_name, read_knot_signature,
+    read_stitch_name, Knot, KnotSet, KnotSignature, Stitch,
 };
 pub use utils::{
-    get_empty_knot_counts, get_mut_stitch, get_num_visited, get_stitch, increment_num_visited,
+    detach_choice_texts, get_empty_knot_counts, get_mut_stitch, get_num_visited, get_stitch,
+    increment_num_visited, move_to_label, set_choice_ids, set_num_visited,
 };
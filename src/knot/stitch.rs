@@ -15,20 +15,20 @@
 //! so there can be no collisions.
 
 use crate::{
-    consts::{KNOT_MARKER, RESERVED_KEYWORDS, STITCH_MARKER},
+    consts::{FUNCTION_MARKER, KNOT_MARKER, RESERVED_KEYWORDS, STITCH_MARKER},
     error::{
         parse::knot::{KnotErrorKind, KnotNameError},
         utils::MetaData,
     },
     follow::{EncounteredEvent, FollowData, FollowResult, LineDataBuffer},
-    line::parse_line,
-    node::{parse_root_node, Follow, RootNode, Stack},
+    line::{parse_lines_with_blocks, DirectiveSet},
+    node::{parse_root_node, Follow, NodeItem, RootNode, Stack},
 };
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 
 /// Convenience type for a set of `Knot`s.
 ///
@@ -47,13 +47,33 @@ pub type KnotSet = HashMap<String, Knot>;
 pub struct Knot {
     /// Name of `Stitch` that is used when diverting to the `Knot` without specifying
     /// a `Stitch`.
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))]
     pub default_stitch: String,
     /// Map of `Stitches` belonging to this `Knot`.
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings::map"))]
     pub stitches: HashMap<String, Stitch>,
     /// Tags associated with this knot.
     pub tags: Vec<String>,
     /// Information about the origin of this knot in the story file or text.
     pub meta_data: MetaData,
+    /// Whether the knot is a function, which is called from expressions instead of
+    /// being diverted to.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub is_function: bool,
+    /// Names of the parameters that the knot is called or diverted to with.
+    ///
+    /// The parameters are temporary variables in the knot. Knots which are diverted to
+    /// only set them when they are entered at their default stitch.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub parameters: Vec<String>,
+    /// Whether the knot is a function which is declared as `EXTERNAL`, to be called
+    /// through a function which is bound by the host.
+    ///
+    /// External functions have no content of their own, unless the story also writes
+    /// a function with the same name. That function is called as a fallback while the host
+    /// has not bound the external function.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub is_external: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -65,6 +85,18 @@ pub struct Stitch {
     pub root: RootNode,
     /// Last recorded position inside the `root` graph of content.
     pub stack: Stack,
+    /// Positions of the labeled choices and gathers inside the `root` graph of content.
+    ///
+    /// The position of a label is the `Stack` which the content is followed from.
+    /// For choices it points to the beginning of their branch.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub labels: HashMap<String, Stack>,
+    /// Tags associated with this stitch.
+    ///
+    /// These are the tags written on their own lines directly below the name of the stitch.
+    /// The first stitch of a knot, which has no name, has its tags set to the knot.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub tags: Vec<String>,
     /// Information about the origin of this stitch in the story file or text.
     pub meta_data: MetaData,
 }
@@ -75,8 +107,24 @@ impl Stitch {
         let result = self.root.follow(&mut self.stack, buffer, data)?;
 
         match &result {
-            EncounteredEvent::Done | EncounteredEvent::Divert(..) => self.reset_stack(),
-            EncounteredEvent::BranchingChoice(..) => (),
+            EncounteredEvent::Done
+            | EncounteredEvent::Divert(..)
+            | EncounteredEvent::Return(..) => self.reset_stack(),
+            EncounteredEvent::BranchingChoice(..) | EncounteredEvent::Thread(..) => (),
+        }
+
+        Ok(result)
+    }
+
+    /// Resume following a story from the current position in the stitch, after a thread.
+    pub fn resume(&mut self, buffer: &mut LineDataBuffer, data: &mut FollowData) -> FollowResult {
+        let result = self.root.resume(0, &mut self.stack, buffer, data)?;
+
+        match &result {
+            EncounteredEvent::Done
+            | EncounteredEvent::Divert(..)
+            | EncounteredEvent::Return(..) => self.reset_stack(),
+            EncounteredEvent::BranchingChoice(..) | EncounteredEvent::Thread(..) => (),
         }
 
         Ok(result)
@@ -94,7 +142,9 @@ impl Stitch {
                 .follow_with_choice(choice_index, 0, &mut self.stack, buffer, data)?;
 
         match result {
-            EncounteredEvent::Done | EncounteredEvent::Divert(..) => self.reset_stack(),
+            EncounteredEvent::Done
+            | EncounteredEvent::Divert(..)
+            | EncounteredEvent::Return(..) => self.reset_stack(),
             _ => (),
         }
 
@@ -102,17 +152,20 @@ impl Stitch {
     }
 
     /// Reset the current stack to the first line of the root node.
-    fn reset_stack(&mut self) {
+    pub fn reset_stack(&mut self) {
         self.stack = vec![0];
     }
 }
 
 /// Parse a set of input lines into a `Stitch`.
+///
+/// Lines which begin with a prefix in the set of directives are parsed as custom directives.
 pub fn parse_stitch_from_lines(
     lines: &[(&str, MetaData)],
     knot: &str,
     stitch: &str,
     meta_data: MetaData,
+    directives: &DirectiveSet,
 ) -> Result<Stitch, Vec<KnotErrorKind>> {
     if lines.is_empty() {
         return Err(vec![KnotErrorKind::EmptyStitch {
@@ -121,41 +174,175 @@ pub fn parse_stitch_from_lines(
         }]);
     }
 
-    let mut parsed_lines = Vec::new();
-    let mut line_errors = Vec::new();
-
-    for result in lines
-        .into_iter()
-        .map(|(line, meta_data)| parse_line(line, meta_data))
-    {
-        match result {
-            Ok(parsed_line) => parsed_lines.push(parsed_line),
-            Err(line_error) => line_errors.push(KnotErrorKind::from(line_error)),
-        }
-    }
+    let (parsed_lines, line_errors) = parse_lines_with_blocks(lines, directives);
 
     if line_errors.is_empty() {
         let root = parse_root_node(&parsed_lines, knot, stitch);
+        let labels = get_label_positions(&root)?;
 
         Ok(Stitch {
             root,
             stack: vec![0],
+            labels,
+            tags: Vec::new(),
             meta_data,
         })
     } else {
-        Err(line_errors)
+        Err(line_errors.into_iter().map(KnotErrorKind::from).collect())
+    }
+}
+
+/// Get the positions of all labeled choices and gathers in the tree of content.
+///
+/// Labels must be unique in their stitch.
+fn get_label_positions(root: &RootNode) -> Result<HashMap<String, Stack>, Vec<KnotErrorKind>> {
+    let mut labels = Vec::new();
+    collect_labels(&root.items, &mut Vec::new(), &mut labels);
+
+    let mut positions: HashMap<String, (Stack, &MetaData)> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (name, stack, meta_data) in labels {
+        match positions.entry(name.to_string()) {
+            Entry::Occupied(entry) => errors.push(KnotErrorKind::DuplicateLabel {
+                name: name.to_string(),
+                meta_data: meta_data.clone(),
+                prev_meta_data: entry.get().1.clone(),
+            }),
+            Entry::Vacant(entry) => {
+                entry.insert((stack, meta_data));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(positions
+            .into_iter()
+            .map(|(name, (stack, _))| (name, stack))
+            .collect())
+    } else {
+        Err(errors)
     }
 }
 
+/// Collect the labels of a set of items, with their positions, in the order that they appear.
+///
+/// The `stack` is the position of the node which contains the items. Branches of choices
+/// and conditional blocks are a level of the stack, marked by the index of the branch.
+fn collect_labels<'a>(
+    items: &'a [NodeItem],
+    stack: &mut Stack,
+    labels: &mut Vec<(&'a str, Stack, &'a MetaData)>,
+) {
+    for (index, item) in items.iter().enumerate() {
+        stack.push(index);
+
+        match item {
+            NodeItem::Label(label) => labels.push((&label.name, stack.clone(), &label.meta_data)),
+            NodeItem::BranchingPoint(branches) => {
+                for (branch_index, branch) in branches.iter().enumerate() {
+                    stack.push(branch_index);
+
+                    if let Some(name) = &branch.choice.label {
+                        let mut position = stack.clone();
+                        position.push(0);
+
+                        labels.push((name, position, &branch.choice.meta_data));
+                    }
+
+                    collect_labels(&branch.items, stack, labels);
+                    stack.pop();
+                }
+            }
+            NodeItem::ConditionalBlock(block) => {
+                for (branch_index, branch) in block.branches.iter().enumerate() {
+                    stack.push(branch_index);
+                    collect_labels(&branch.items, stack, labels);
+                    stack.pop();
+                }
+            }
+            _ => (),
+        }
+
+        stack.pop();
+    }
+}
+
+/// Signature of a knot, read from the line which begins it.
+pub struct KnotSignature {
+    /// Name of the knot.
+    pub name: String,
+    /// Whether the knot is marked as a function.
+    pub is_function: bool,
+    /// Names of the parameters of the knot.
+    pub parameters: Vec<String>,
+}
+
 /// Read a knot name from a non-parsed string which contains text markers for a knot.
 ///
 /// The name is validated before returning.
 pub fn read_knot_name(line: &str) -> Result<String, KnotNameError> {
-    if line.trim_start().starts_with(KNOT_MARKER) {
-        read_name_with_marker(line)
-    } else {
-        Err(KnotNameError::Empty)
+    read_knot_signature(line).map(|signature| signature.name)
+}
+
+/// Read the signature of a knot from a non-parsed string which contains text markers for a knot.
+///
+/// Functions are marked with the [function marker][crate::consts::FUNCTION_MARKER] before
+/// their name. Both functions and knots may be given parameters in parenthesis after it:
+///
+/// ```text
+/// === function max(a, b) ===
+/// === meet(who, place) ===
+/// ```
+///
+/// The name and parameters are validated before returning.
+pub fn read_knot_signature(line: &str) -> Result<KnotSignature, KnotNameError> {
+    if !line.trim_start().starts_with(KNOT_MARKER) {
+        return Err(KnotNameError::Empty);
     }
+
+    let head = trim_name_markers(line);
+
+    let (is_function, head) = match head
+        .strip_prefix(FUNCTION_MARKER)
+        .filter(|tail| tail.starts_with(char::is_whitespace))
+    {
+        Some(tail) => (true, tail.trim_start()),
+        None => (false, head),
+    };
+
+    let (name, parameters) = read_function_signature(head)?;
+
+    Ok(KnotSignature {
+        name,
+        is_function,
+        parameters,
+    })
+}
+
+/// Read the name and parameters of a function from a string without markers: `max(a, b)`.
+///
+/// The parenthesis may be left out if there are no parameters. The name and parameters
+/// are validated before returning.
+pub fn read_function_signature(content: &str) -> Result<(String, Vec<String>), KnotNameError> {
+    let content = content.trim();
+
+    let (name, parameters) = match content
+        .strip_suffix(')')
+        .and_then(|content| content.split_once('('))
+    {
+        Some((name, parameters)) if parameters.trim().is_empty() => (name.trim_end(), Vec::new()),
+        Some((name, parameters)) => (
+            name.trim_end(),
+            parameters
+                .split(',')
+                .map(|parameter| validate_name(parameter.trim()))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => (content, Vec::new()),
+    };
+
+    Ok((validate_name(name)?, parameters))
 }
 
 /// Read a stitch name from a non-parsed string which contains text markers for a stitch.
@@ -172,35 +359,38 @@ pub fn read_stitch_name(line: &str) -> Result<String, KnotNameError> {
 /// Read a name beginning with the given knot or stitch marker.
 ///
 /// The name is validated before returning.
+fn read_name_with_marker(line: &str) -> Result<String, KnotNameError> {
+    validate_name(trim_name_markers(line))
+}
+
+/// Trim the knot or stitch markers from both ends of a line.
 ///
 /// # Notes
 ///  *  Uses the [stitch marker][crate::consts::STITCH_MARKER] to trim extraneous markers
-///     from the line before validating the name. Since the stitch marker is a subset
-///     of the knot marker this will trim both types, but any other marker will not be
-///     trimmed from the line.
-fn read_name_with_marker(line: &str) -> Result<String, KnotNameError> {
-    let trimmed_name = line
-        .trim_start_matches(STITCH_MARKER)
+///     from the line. Since the stitch marker is a subset of the knot marker this will trim
+///     both types, but any other marker will not be trimmed from the line.
+fn trim_name_markers(line: &str) -> &str {
+    line.trim_start_matches(STITCH_MARKER)
         .trim_end_matches(STITCH_MARKER)
-        .trim();
+        .trim()
+}
 
-    if let Some(c) = trimmed_name
-        .chars()
-        .find(|&c| !(c.is_alphanumeric() || c == '_'))
-    {
+/// Validate a trimmed name of a knot, stitch or parameter.
+fn validate_name(name: &str) -> Result<String, KnotNameError> {
+    if let Some(c) = name.chars().find(|&c| !(c.is_alphanumeric() || c == '_')) {
         if c.is_whitespace() {
             Err(KnotNameError::ContainsWhitespace)
         } else {
             Err(KnotNameError::ContainsInvalidCharacter(c))
         }
-    } else if trimmed_name.is_empty() {
+    } else if name.is_empty() {
         Err(KnotNameError::Empty)
-    } else if RESERVED_KEYWORDS.contains(&trimmed_name.to_uppercase().as_str()) {
+    } else if RESERVED_KEYWORDS.contains(&name.to_uppercase().as_str()) {
         Err(KnotNameError::ReservedKeyword {
-            keyword: trimmed_name.to_string(),
+            keyword: name.to_string(),
         })
     } else {
-        Ok(trimmed_name.to_string())
+        Ok(name.to_string())
     }
 }
 
@@ -212,7 +402,7 @@ mod tests {
         error::parse::line::LineError,
         follow::FollowDataBuilder,
         knot::{get_num_visited, Address},
-        line::{InternalLine, ParsedLineKind},
+        line::{parse_line, InternalLine, ParsedLineKind},
     };
 
     use std::str::FromStr;
@@ -224,15 +414,19 @@ mod tests {
             let lines = parse_lines(content).unwrap();
             let root = parse_root_node(&lines, "", "");
 
+            let labels = get_label_positions(&root).unwrap();
+
             Ok(Stitch {
                 root,
                 stack: vec![0],
-                meta_data: MetaData { line_index: 0 },
+                labels,
+                tags: Vec::new(),
+                meta_data: MetaData::from(0),
             })
         }
     }
 
-    fn parse_lines(s: &str) -> Result<Vec<ParsedLineKind>, LineError> {
+    fn parse_lines(s: &str) -> Result<Vec<ParsedLineKind>, Box<LineError>> {
         s.lines().map(|line| parse_line(line, &().into())).collect()
     }
 
@@ -252,8 +446,14 @@ mod tests {
 
     #[test]
     fn parsing_stitch_sets_root_node_address() {
-        let stitch =
-            parse_stitch_from_lines(&[("", ().into())], "tripoli", "cinema", ().into()).unwrap();
+        let stitch = parse_stitch_from_lines(
+            &[("", ().into())],
+            "tripoli",
+            "cinema",
+            ().into(),
+            &DirectiveSet::new(),
+        )
+        .unwrap();
 
         assert_eq!(
             stitch.root.address,
@@ -263,18 +463,74 @@ mod tests {
 
     #[test]
     fn parsing_empty_stitch_yields_error() {
-        assert!(parse_stitch_from_lines(&[], "tripoli", "cinema", ().into()).is_err());
+        assert!(
+            parse_stitch_from_lines(&[], "tripoli", "cinema", ().into(), &DirectiveSet::new())
+                .is_err()
+        );
     }
 
     #[test]
     fn parsing_stitch_sets_meta_data_from_given() {
-        let stitch =
-            parse_stitch_from_lines(&[("", ().into())], "tripoli", "cinema", MetaData::from(10))
-                .unwrap();
+        let stitch = parse_stitch_from_lines(
+            &[("", ().into())],
+            "tripoli",
+            "cinema",
+            MetaData::from(10),
+            &DirectiveSet::new(),
+        )
+        .unwrap();
 
         assert_eq!(stitch.meta_data.line_index, 10);
     }
 
+    #[test]
+    fn parsing_stitch_sets_the_positions_of_labeled_choices_and_gathers() {
+        let lines = [
+            ("Hello.", ().into()),
+            ("* (greet) Greet.", ().into()),
+            ("  ** Wave.", ().into()),
+            ("  -- (waved) Waved.", ().into()),
+            ("* Leave.", ().into()),
+            ("- (gathered)", ().into()),
+            ("{ true:", ().into()),
+            ("  - (inside) Inside.", ().into()),
+            ("}", ().into()),
+        ];
+
+        let stitch =
+            parse_stitch_from_lines(&lines, "", "", ().into(), &DirectiveSet::new()).unwrap();
+
+        assert_eq!(stitch.labels.len(), 4);
+        assert_eq!(stitch.labels.get("greet").unwrap(), &vec![1, 0, 0]);
+        assert_eq!(stitch.labels.get("waved").unwrap(), &vec![1, 0, 2]);
+        assert_eq!(stitch.labels.get("gathered").unwrap(), &vec![2]);
+        assert_eq!(stitch.labels.get("inside").unwrap(), &vec![4, 0, 0]);
+
+        match &stitch.root.items[2] {
+            NodeItem::Label(label) => assert_eq!(&label.name, "gathered"),
+            other => panic!("expected `NodeItem::Label` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parsing_stitch_with_duplicate_labels_yields_error() {
+        let lines = [
+            ("* (greet) Greet.", ().into()),
+            ("- (greet) Greeted.", ().into()),
+        ];
+
+        match parse_stitch_from_lines(&lines, "", "", ().into(), &DirectiveSet::new()) {
+            Err(errors) => match &errors[..] {
+                [KnotErrorKind::DuplicateLabel { name, .. }] => assert_eq!(name, "greet"),
+                other => panic!(
+                    "expected `KnotErrorKind::DuplicateLabel` but got {:?}",
+                    other
+                ),
+            },
+            Ok(..) => panic!("expected an error but parsed the stitch"),
+        }
+    }
+
     #[test]
     fn stitch_restarts_from_their_first_line_when_run_again() {
         let text = "Hello, World!";
@@ -675,4 +931,44 @@ Line 6
         assert!(read_knot_name("== else").is_err());
         assert!(read_knot_name("== not").is_err());
     }
+
+    #[test]
+    fn function_knots_are_read_with_their_parameters() {
+        let signature = read_knot_signature("=== function max(a, b) ===").unwrap();
+
+        assert_eq!(&signature.name, "max");
+        assert!(signature.is_function);
+        assert_eq!(signature.parameters, &["a", "b"]);
+
+        let signature = read_knot_signature("== function greet").unwrap();
+
+        assert_eq!(&signature.name, "greet");
+        assert!(signature.is_function);
+        assert!(signature.parameters.is_empty());
+
+        assert_eq!(
+            &read_knot_name("=== function max(a, b) ===").unwrap(),
+            "max"
+        );
+        assert!(!read_knot_signature("== functional").unwrap().is_function);
+    }
+
+    #[test]
+    fn knot_parameters_must_be_valid_names() {
+        match read_knot_signature("== function max(a, b c)") {
+            Err(KnotNameError::ContainsWhitespace) => (),
+            other => panic!(
+                "expected `KnotNameError::ContainsWhitespace` but got {:?}",
+                other.map(|signature| signature.name)
+            ),
+        }
+
+        let signature = read_knot_signature("== meet(who, place)").unwrap();
+        assert!(!signature.is_function);
+        assert_eq!(&signature.parameters, &["who", "place"]);
+
+        assert!(read_knot_signature("== function max(a,, b)").is_err());
+        assert!(read_knot_signature("== function max(a, b").is_err());
+        assert!(read_knot_signature("== function").is_err());
+    }
 }
@@ -1,12 +1,18 @@
 //! Utilities for accessing `Knot` and `Stitch` data.
 
 use crate::{
+    consts::ROOT_KNOT_NAME,
     error::{runtime::internal::StackError, InternalError},
     follow::FollowData,
-    knot::{Address, KnotSet, Stitch},
+    knot::{Address, AddressKind, KnotSet, Stitch},
+    node::NodeItem,
+    story::Location,
 };
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 #[allow(dead_code)]
 /// Return a reference to the `Stitch` at the target address.
@@ -42,7 +48,31 @@ pub fn get_mut_stitch<'a>(
         )
 }
 
+/// Move the stack of the `Stitch` that a label is in to the position of the label.
+///
+/// The stitch is then resumed from the labeled choice or gather.
+pub fn move_to_label(address: &Address, knots: &mut KnotSet) -> Result<(), InternalError> {
+    let stitch = get_mut_stitch(address, knots)?;
+
+    let position = match address {
+        Address::Validated(AddressKind::Label(label)) => stitch.labels.get(&label.label),
+        _ => None,
+    };
+
+    stitch.stack = position.cloned().ok_or(StackError::BadAddress {
+        address: address.clone(),
+    })?;
+
+    Ok(())
+}
+
+/// Return the number of times the knot, stitch or label at the target address has been visited.
 pub fn get_num_visited(address: &Address, data: &FollowData) -> Result<u32, InternalError> {
+    if let Address::Validated(AddressKind::Label(..)) = address {
+        let num_visited = data.label_visit_counts.get(&address.to_string()).copied();
+        return Ok(num_visited.unwrap_or(0));
+    }
+
     let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
 
     data.knot_visit_counts
@@ -56,15 +86,27 @@ pub fn get_num_visited(address: &Address, data: &FollowData) -> Result<u32, Inte
         )
 }
 
-pub fn increment_num_visited(
+/// Set the number of times the knot, stitch or label at the target address has been visited.
+///
+/// Only the count is changed: the turn of the last visit and the visit journal are kept as
+/// they are.
+pub fn set_num_visited(
     address: &Address,
     data: &mut FollowData,
+    num_visited: u32,
 ) -> Result<(), InternalError> {
+    if let Address::Validated(AddressKind::Label(..)) = address {
+        data.label_visit_counts
+            .insert(address.to_string(), num_visited);
+        return Ok(());
+    }
+
     let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
 
     data.knot_visit_counts
         .get_mut(knot_name)
-        .and_then(|knot| knot.get_mut(stitch_name).map(|count| *count += 1))
+        .and_then(|knot| knot.get_mut(stitch_name))
+        .map(|count| *count = num_visited)
         .ok_or(
             StackError::BadAddress {
                 address: address.clone(),
@@ -73,9 +115,108 @@ pub fn increment_num_visited(
         )
 }
 
+/// Increment the number of times the knot, stitch or label at the target address has been visited.
+///
+/// The turn of the visit is recorded for every address, but only knots and stitches are
+/// recorded in the visit journal. Nothing is recorded for the visit if the address is
+/// set as the next uncounted visit, which is then cleared. Calls to functions are not
+/// counted as visits.
+pub fn increment_num_visited(
+    address: &Address,
+    data: &mut FollowData,
+) -> Result<(), InternalError> {
+    if data.uncounted_visit.as_ref() == Some(address) {
+        data.uncounted_visit = None;
+        return Ok(());
+    }
+
+    if let Address::Validated(AddressKind::Location { knot, .. }) = address {
+        if data.functions.contains_key(knot) {
+            return Ok(());
+        }
+    }
+
+    data.last_visited_turns
+        .insert(address.to_string(), data.turn);
+
+    if let Address::Validated(AddressKind::Label(..)) = address {
+        *data
+            .label_visit_counts
+            .entry(address.to_string())
+            .or_insert(0) += 1;
+        return Ok(());
+    }
+
+    let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
+
+    data.knot_visit_counts
+        .get_mut(knot_name)
+        .and_then(|knot| knot.get_mut(stitch_name).map(|count| *count += 1))
+        .ok_or(StackError::BadAddress {
+            address: address.clone(),
+        })?;
+
+    if let Some(journal) = data.visit_journal.as_mut() {
+        if knot_name != ROOT_KNOT_NAME {
+            let location = if stitch_name == ROOT_KNOT_NAME {
+                Location::from(knot_name)
+            } else {
+                Location::with_stitch(knot_name, stitch_name)
+            };
+
+            journal.record(location, data.turn);
+        }
+    }
+
+    Ok(())
+}
+
+/// Give every choice in the story a copy of its selection text of its own.
+///
+/// Cloned knots share the selection texts of their choices with the original, so processing
+/// the choices of a clone would update their alternative sequences in the original too.
+pub fn detach_choice_texts(knots: &mut KnotSet) {
+    for knot in knots.values_mut() {
+        for stitch in knot.stitches.values_mut() {
+            detach_choice_texts_in_items(&mut stitch.root.items);
+        }
+    }
+}
+
+/// Recursively give the choices in a set of node items copies of their selection text.
+fn detach_choice_texts_in_items(items: &mut [NodeItem]) {
+    for item in items.iter_mut() {
+        match item {
+            NodeItem::BranchingPoint(branches) => {
+                for branch in branches.iter_mut() {
+                    let selection_text = branch.choice.selection_text.lock().unwrap().clone();
+                    branch.choice.selection_text = Arc::new(Mutex::new(selection_text));
+
+                    detach_choice_texts_in_items(&mut branch.items);
+                }
+            }
+            NodeItem::ConditionalBlock(block) => {
+                for branch in block.branches.iter_mut() {
+                    detach_choice_texts_in_items(&mut branch.items);
+                }
+            }
+            NodeItem::AlternativeBlock(block) => {
+                for items in block.items.iter_mut() {
+                    detach_choice_texts_in_items(items);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Get the visit counts of all knots and stitches in a story before any have been visited.
+///
+/// Functions are called rather than visited, so they have no counts.
 pub fn get_empty_knot_counts(knots: &KnotSet) -> HashMap<String, HashMap<String, u32>> {
     knots
         .iter()
+        .filter(|(_, knot)| !knot.is_function)
         .map(|(knot_name, knot)| {
             let empty = knot
                 .stitches
@@ -87,3 +228,48 @@ pub fn get_empty_knot_counts(knots: &KnotSet) -> HashMap<String, HashMap<String,
         })
         .collect()
 }
+
+/// Set an identifier to every choice in the story which is unique and stable across runs.
+///
+/// The identifier is the address of the stitch that the choice is in followed by
+/// the ordinal of the choice in it, counted in the order that they are written:
+/// for example `tripoli.cinema#2`. Identifiers of choices in the root of the story
+/// have no address and identifiers of choices in the default stitch of a knot
+/// only have the knot name.
+pub fn set_choice_ids(knots: &mut KnotSet) {
+    for (knot_name, knot) in knots.iter_mut() {
+        for (stitch_name, stitch) in knot.stitches.iter_mut() {
+            let prefix = [knot_name.as_str(), stitch_name.as_str()]
+                .iter()
+                .filter(|name| **name != ROOT_KNOT_NAME)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(".");
+
+            let mut ordinal = 0;
+            set_choice_ids_in_items(&mut stitch.root.items, &prefix, &mut ordinal);
+        }
+    }
+}
+
+/// Recursively set identifiers to the choices in a set of node items.
+fn set_choice_ids_in_items(items: &mut [NodeItem], prefix: &str, ordinal: &mut usize) {
+    for item in items.iter_mut() {
+        match item {
+            NodeItem::BranchingPoint(branches) => {
+                for branch in branches.iter_mut() {
+                    branch.choice.id = format!("{}#{}", prefix, ordinal);
+                    *ordinal += 1;
+
+                    set_choice_ids_in_items(&mut branch.items, prefix, ordinal);
+                }
+            }
+            NodeItem::ConditionalBlock(block) => {
+                for branch in block.branches.iter_mut() {
+                    set_choice_ids_in_items(&mut branch.items, prefix, ordinal);
+                }
+            }
+            _ => (),
+        }
+    }
+}
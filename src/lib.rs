@@ -22,9 +22,29 @@
 //! ## `random`
 //! Proper shuffle sequences using the `{~One|Two|Three}` syntax are enabled with
 //! the `random` feature. This adds `rand` and `rand_chacha` as dependencies.
+//! Without it, shuffle sequences are followed as cycles and `RANDOM(min, max)` always
+//! returns `min`, which is logged as a warning when the story is read.
 //! If combined with `serde_support`, the random number generator state will be
 //! properly saved and restored along with the rest of the data.
 //!
+//! ## `math`, `lints`, `logging` and `includes`
+//! These features are enabled by default and can be disabled to make the crate smaller.
+//! Without `math`, expressions may not use mathematical operators and are limited to single
+//! values. Without `lints`, stories are not checked for style problems and suspicious
+//! content while they are validated. Without `logging`, warnings and to-do comments are
+//! not recorded in the [log][crate::log::Logger] of a story. Without `includes`, stories
+//! cannot be read from paths or resolvers with
+//! [`read_story_from_path`][crate::read_story_from_path()] and `INCLUDE` lines are ignored.
+//!
+//! ## `debug`
+//! Enables recording a timeline of the full story state at every prompt, which the story
+//! can be stepped back and forth through. See `Story::enable_timeline`.
+//!
+//! ## `compressed_saves`
+//! Adds [`save_story_to_bytes`] and [`load_story_from_bytes`], which write the state of
+//! a story as compressed bytes with a hash of the state to detect corrupted saves.
+//! Enables `serde_support` and adds `serde_json` as a dependency.
+//!
 //! # Contributions
 //! I am a complete novice at designing frameworks which will fit into larger schemes.
 //! As such I have no real idea of best practices for interacting with an engine like this.
@@ -47,9 +67,27 @@ mod story;
 mod utils;
 
 pub use error::InklingError;
-pub use line::Variable;
+pub use line::{Directive, DirectiveParser, DirectiveSet, List, ListItem, Variable};
 pub use log::Logger;
 pub use story::{
-    copy_lines_into_string, read_story_from_string, Choice, Line, LineBuffer, Location, Prompt,
-    Story,
+    copy_lines_into_string, explore_story, export_story_to_html, export_story_to_markdown,
+    find_walkthrough, get_knot_metrics, read_story_from_reader, read_story_from_string,
+    read_story_from_string_with_options, validate_story_from_string,
+    validate_story_from_string_with_options, ChangeOrigin, Choice, Comment, CommentKind,
+    ConditionCoverage, ConditionOutcomes, ConditionSite, Ending, EndingKind, ExplorationReport,
+    ExploreOptions, ExternalFunction, ExternalFunctions, ItemBuffer, JournalEntry, KnotMetrics,
+    Line, LineBuffer, LintOptions, Location, Nondeterminism, NondeterminismKind, OutputBlock, Poll,
+    Prompt, ReadOptions, SharedVariableStore, SoftLock, Story, StoryItem, StorySnapshot, Tag,
+    TaggedContent, TaggedContentKind, Transcript, TranscriptEntry, UndeclaredVariablePolicy,
+    VariableChange, VariableHistory, VariableStore, VariableValues, VisitJournal, Walkthrough,
+    WalkthroughOutcome,
 };
+
+#[cfg(feature = "compressed_saves")]
+pub use story::{load_story_from_bytes, save_story_to_bytes};
+
+#[cfg(feature = "debug")]
+pub use story::{Timeline, TimelineEntry};
+
+#[cfg(feature = "includes")]
+pub use story::{read_story_from_path, read_story_with_resolver, IncludeResolver};
@@ -13,7 +13,7 @@ use crate::{
 use crate::log::Warning;
 
 #[cfg(feature = "random")]
-use rand::seq::SliceRandom;
+use crate::story::audit::NondeterminismKind;
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
@@ -40,44 +40,54 @@ pub struct Alternative {
 }
 
 impl Alternative {
-    #[allow(unused_variables)] // `data` only used when the `random` feature is enabled
     /// Get the next item index in the alternative sequence.
     pub fn get_next_index(&mut self, data: &mut FollowData) -> Option<usize> {
-        match self.kind {
-            AlternativeKind::OnceOnly => self.active_inds.pop(),
-            AlternativeKind::Sequence if self.active_inds.len() > 1 => self.active_inds.pop(),
-            AlternativeKind::Sequence => self.active_inds.get(0).cloned(),
-            AlternativeKind::Cycle => {
-                if self.active_inds.is_empty() {
-                    self.reset_active_list()
-                }
+        get_next_alternative_index(self.kind, &mut self.active_inds, self.items.len(), data)
+    }
+}
 
-                self.active_inds.pop()
+#[allow(unused_variables)] // `data` only used when the `random` feature is enabled
+/// Get the next item index in an alternative sequence of the given kind and number of items.
+///
+/// The active list of indices is updated in place. Shared by alternatives in lines and
+/// multi-line blocks of alternatives.
+pub fn get_next_alternative_index(
+    kind: AlternativeKind,
+    active_inds: &mut Vec<usize>,
+    num_items: usize,
+    data: &mut FollowData,
+) -> Option<usize> {
+    let reset_active_list = |active_inds: &mut Vec<usize>| {
+        *active_inds = (0..num_items).rev().collect();
+    };
+
+    match kind {
+        AlternativeKind::OnceOnly => active_inds.pop(),
+        AlternativeKind::Sequence if active_inds.len() > 1 => active_inds.pop(),
+        AlternativeKind::Sequence => active_inds.first().cloned(),
+        AlternativeKind::Cycle => {
+            if active_inds.is_empty() {
+                reset_active_list(active_inds)
             }
-            AlternativeKind::Shuffle => {
-                if self.active_inds.is_empty() {
-                    self.reset_active_list()
-                }
 
-                #[cfg(feature = "random")]
-                if self.is_first_item() {
-                    self.active_inds.shuffle(&mut data.rng.gen);
+            active_inds.pop()
+        }
+        AlternativeKind::Shuffle => {
+            if active_inds.is_empty() {
+                reset_active_list(active_inds)
+            }
+
+            #[cfg(feature = "random")]
+            if active_inds.len() == num_items {
+                if !data.rng.is_seeded() {
+                    data.record_nondeterminism(NondeterminismKind::UnseededRandom);
                 }
 
-                self.active_inds.pop()
+                data.rng.shuffle(active_inds);
             }
-        }
-    }
-
-    #[allow(dead_code)]
-    /// Return whether or not we are at the first item in the sequence.
-    fn is_first_item(&self) -> bool {
-        self.active_inds.len() == self.items.len()
-    }
 
-    /// Reset the active list by remaking the index list and reversing it.
-    fn reset_active_list(&mut self) {
-        self.active_inds = (0..self.items.len()).rev().collect();
+            active_inds.pop()
+        }
     }
 }
 
@@ -307,6 +317,7 @@ mod tests {
     mod not_shuffle {
         use super::*;
 
+        #[cfg(feature = "logging")]
         use std::collections::HashMap;
 
         #[test]
@@ -325,6 +336,7 @@ mod tests {
         }
 
         #[test]
+        #[cfg(feature = "logging")]
         fn shuffle_alternative_yields_warning_during_validation_if_random_is_not_enabled() {
             let validation_data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
             let meta_data = MetaData::from(3);
@@ -367,11 +379,11 @@ mod tests {
         fn alternative_get_next_index_for_shuffle_uses_shuffle_in_place_with_the_generator() {
             let mut alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
 
-            let mut rng = StoryRng::default();
+            let rng = StoryRng::default();
             let mut data = mock_data_with_single_stitch_and_rng("", "", 0, rng.clone());
 
             let mut active_inds = alternative.active_inds.clone();
-            active_inds.shuffle(&mut rng.gen);
+            rng.shuffle(&mut active_inds);
 
             assert_eq!(alternative.get_next_index(&mut data), active_inds.pop());
             assert_eq!(&alternative.active_inds, &active_inds);
@@ -381,8 +393,7 @@ mod tests {
         fn alternative_get_next_index_for_shuffle_resets_list_after_emptying() {
             let mut alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
 
-            let mut rng = StoryRng::default();
-            let mut data = mock_data_with_single_stitch_and_rng("", "", 0, rng.clone());
+            let mut data = mock_data_with_single_stitch_and_rng("", "", 0, StoryRng::default());
 
             // Unshuffled list
             let mut active_inds = alternative.active_inds.clone();
@@ -393,8 +404,8 @@ mod tests {
             }
 
             // Second shuffle will now occur, make corresponding shuffle for comparison
-            rng.gen.set_word_pos(data.rng.gen.get_word_pos());
-            active_inds.shuffle(&mut rng.gen);
+            let rng = data.rng.clone();
+            rng.shuffle(&mut active_inds);
 
             assert_eq!(alternative.get_next_index(&mut data), active_inds.pop());
             assert_eq!(&alternative.active_inds, &active_inds);
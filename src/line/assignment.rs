@@ -0,0 +1,251 @@
+//! Logic lines which assign values to variables, call functions or return values
+//! from functions.
+
+use crate::{
+    error::{
+        parse::{
+            address::InvalidAddressError,
+            validate::{ExpressionKind, InvalidVariableExpression, ValidationError},
+        },
+        utils::MetaData,
+        InklingError, InternalError,
+    },
+    follow::{FollowData, LineDataBuffer},
+    knot::{Address, AddressKind},
+    line::{evaluate_expression, expression::Operator, Expression, FunctionCall, Variable},
+    log::Logger,
+    story::validate::{ValidateContent, ValidationData},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Logic line which assigns the value of an expression to a variable.
+///
+/// The line either declares a temporary variable (`~ temp name = value`) or assigns
+/// to a temporary or global variable which already exists (`~ name = value`, or
+/// the compound `~ name += value` and `~ name -= value`).
+/// Temporary variables can be used in the knot or stitch that they are declared in,
+/// after their declaration. They are dropped when the story leaves it.
+pub struct Assignment {
+    /// Variable which is assigned to.
+    ///
+    /// Validated to a temporary or global variable when the story is read.
+    pub target: Address,
+    /// Whether the line declares a new temporary variable.
+    pub is_declaration: bool,
+    /// Operator of a compound assignment (`+=` or `-=`).
+    ///
+    /// The operation on the variable is already a part of the value expression.
+    pub operator: Option<Operator>,
+    /// Expression which is evaluated into the value of the variable.
+    pub value: Expression,
+    /// Information about the origin of the line.
+    pub meta_data: MetaData,
+}
+
+impl Assignment {
+    /// Evaluate the expression and assign its value to the variable.
+    pub fn assign(&self, data: &mut FollowData) -> Result<(), InklingError> {
+        let value = evaluate_expression(&self.value, data)?;
+
+        match &self.target {
+            Address::Validated(AddressKind::TemporaryVariable { name }) if self.is_declaration => {
+                data.temporary_variables.insert(name.clone(), value);
+            }
+            Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                .temporary_variables
+                .get_mut(name)
+                .ok_or_else(|| InklingError::InvalidVariable { name: name.clone() })?
+                .assign(value)?,
+            Address::Validated(AddressKind::GlobalVariable { name }) => {
+                data.assign_variable(name, value, &self.meta_data)?
+            }
+            other => {
+                return Err(InternalError::UseOfUnvalidatedAddress {
+                    address: other.clone(),
+                }
+                .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the variable can be assigned the value of the validated expression.
+    ///
+    /// Assigning to a constant or changing the type of a variable yields an error.
+    fn validate_assignment(&self, data: &ValidationData) -> Result<(), InklingError> {
+        let mut follow_data = data.get_follow_data().into_owned();
+        let value = evaluate_expression(&self.value, &mut follow_data)?;
+
+        match &self.target {
+            Address::Validated(AddressKind::TemporaryVariable { name }) => follow_data
+                .temporary_variables
+                .get(name)
+                .ok_or_else(|| InklingError::InvalidVariable { name: name.clone() })?
+                .clone()
+                .assign(value)
+                .map_err(InklingError::from),
+            Address::Validated(AddressKind::GlobalVariable { name }) => follow_data
+                .variables
+                .get(name)
+                .ok_or_else(|| InklingError::InvalidVariable { name: name.clone() })?
+                .clone()
+                .assign(value, name),
+            other => Err(InternalError::UseOfUnvalidatedAddress {
+                address: other.clone(),
+            }
+            .into()),
+        }
+    }
+}
+
+impl ValidateContent for Assignment {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        _: &MetaData,
+        data: &ValidationData,
+    ) {
+        let num_errors = error.num_errors();
+        let num_variable_errors = error.variable_errors.len();
+
+        self.value
+            .validate(error, log, current_location, &self.meta_data, data);
+
+        // Errors from the operation of compound assignments are errors in the assignment.
+        if self.operator.is_some() {
+            error
+                .variable_errors
+                .iter_mut()
+                .skip(num_variable_errors)
+                .for_each(|err| err.expression_kind = ExpressionKind::Assignment);
+        }
+
+        if self.is_declaration {
+            self.target = Address::Validated(AddressKind::TemporaryVariable {
+                name: self.target.to_string(),
+            });
+
+            // The value is needed to check the types of expressions which use the variable.
+            // If it could not be evaluated an error has already been recorded.
+            if num_errors == error.num_errors() {
+                let mut follow_data = data.get_follow_data().into_owned();
+
+                if let Ok(value) = evaluate_expression(&self.value, &mut follow_data) {
+                    data.temporary_variables
+                        .borrow_mut()
+                        .insert(self.target.to_string(), value);
+                }
+            }
+        } else if let Err(kind) = self.target.validate_as_variable(data) {
+            error.invalid_address_errors.push(InvalidAddressError {
+                kind,
+                meta_data: self.meta_data.clone(),
+            });
+        } else if num_errors == error.num_errors() {
+            if let Err(err) = self.validate_assignment(data) {
+                error.variable_errors.push(InvalidVariableExpression {
+                    expression_kind: ExpressionKind::Assignment,
+                    kind: err.into(),
+                    meta_data: self.meta_data.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Logic line which returns from a function, with the value of an expression if it is given.
+///
+/// Returns are written as `~ return value` and can only be used in functions.
+pub struct Return {
+    /// Expression which is evaluated into the returned value.
+    pub value: Option<Expression>,
+    /// Information about the origin of the line.
+    pub meta_data: MetaData,
+}
+
+impl Return {
+    /// Evaluate the value which is returned, if there is one.
+    pub fn evaluate(&self, data: &mut FollowData) -> Result<Option<Variable>, InklingError> {
+        self.value
+            .as_ref()
+            .map(|value| evaluate_expression(value, data))
+            .transpose()
+    }
+}
+
+impl ValidateContent for Return {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        _: &MetaData,
+        data: &ValidationData,
+    ) {
+        if let Some(value) = self.value.as_mut() {
+            value.validate(error, log, current_location, &self.meta_data, data);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Logic line which calls a function for what it does, not for the value it returns.
+///
+/// Calls are written as `~ name(arguments)`. Changes that the function makes to the story
+/// state are kept and the text that it writes is added as lines of the story. The value
+/// that it returns, if any, is discarded.
+pub struct LogicCall {
+    /// Call to the function.
+    pub call: FunctionCall,
+    /// Information about the origin of the line.
+    pub meta_data: MetaData,
+}
+
+impl LogicCall {
+    /// Call the function, adding the lines that it writes to the buffer.
+    pub fn follow(
+        &self,
+        buffer: &mut LineDataBuffer,
+        data: &mut FollowData,
+    ) -> Result<(), InklingError> {
+        self.call.follow(buffer, data).map(|_| ())
+    }
+}
+
+impl ValidateContent for LogicCall {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        _: &MetaData,
+        data: &ValidationData,
+    ) {
+        let num_errors = error.num_errors();
+
+        self.call
+            .validate(error, log, current_location, &self.meta_data, data);
+
+        if num_errors == error.num_errors() {
+            let mut follow_data = data.get_follow_data().into_owned();
+
+            if let Err(err) = self.follow(&mut Vec::new(), &mut follow_data) {
+                error.variable_errors.push(InvalidVariableExpression {
+                    expression_kind: ExpressionKind::Expression,
+                    kind: err.into(),
+                    meta_data: self.meta_data.clone(),
+                });
+            }
+        }
+    }
+}
@@ -51,8 +51,20 @@ pub struct InternalChoice {
     pub is_sticky: bool,
     /// Fallback choices are, in order, automatically followed if no other choices are available.
     pub is_fallback: bool,
+    /// Label of the choice, which it can be referenced by in its stitch.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub label: Option<String>,
     /// Information about the origin of this choice in the story file or text.
     pub meta_data: MetaData,
+    /// Identifier of the choice which is unique in the story.
+    ///
+    /// Set after the story has been parsed, see
+    /// [`set_choice_ids`][crate::knot::set_choice_ids].
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub id: String,
+    /// Line of the choice as it was written in the story, including its markers.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub raw_text: String,
 }
 
 impl PartialEq for InternalChoice {
@@ -65,7 +77,10 @@ impl PartialEq for InternalChoice {
             && self.condition == rhs.condition
             && self.is_sticky == rhs.is_sticky
             && self.is_fallback == rhs.is_fallback
+            && self.label == rhs.label
             && self.meta_data == rhs.meta_data
+            && self.id == rhs.id
+            && self.raw_text == rhs.raw_text
     }
 }
 
@@ -156,7 +171,10 @@ impl InternalChoiceBuilder {
             condition: self.condition,
             is_sticky: self.is_sticky,
             is_fallback: self.is_fallback,
+            label: None,
             meta_data,
+            id: String::new(),
+            raw_text: String::new(),
         }
     }
 
@@ -36,7 +36,7 @@ use crate::{
         utils::MetaData,
     },
     knot::Address,
-    line::{Expression, Variable},
+    line::Expression,
     log::Logger,
     process::check_condition,
     story::validate::{ValidateContent, ValidationData},
@@ -103,8 +103,9 @@ pub enum StoryCondition {
     /// method for [`Variable`][crate::line::Variable]), then compare using that.
     ///
     /// Equal-to comparisons (`==`) can be made for all variable types. Less-than (`<`)
-    /// and greater-than (`>`) comparisons are only allowed for `Int` and `Float` variants.
-    /// An error is raised if another variant is used like that.
+    /// and greater-than (`>`) comparisons are only allowed between `Int` and `Float`
+    /// variants, or between two `String` variants which are compared in lexicographic
+    /// order. An error is raised if other variants are compared like that.
     Comparison {
         /// Left hand side variable.
         lhs_variable: Expression,
@@ -117,9 +118,10 @@ pub enum StoryCondition {
         #[cfg_attr(feature = "serde_support", serde(with = "OrderingDerive"))]
         ordering: Ordering,
     },
-    /// Assert that the variable value is "true".
+    /// Assert that the value of an expression is "true".
     ///
-    /// This is evaluated differently for different variable types.
+    /// The expression is usually a single variable, but may also be a call to a function
+    /// which returns a value. The value is evaluated differently for different types.
     ///
     /// *   Boolean variables evaluate directly.
     /// *   Number variables (integers and floats) are `true` if they are non-zero.
@@ -130,7 +132,22 @@ pub enum StoryCondition {
     ///
     /// Variable `Divert` variants will never evaluate to `true` or `false`, but raise
     /// and error. They are not supposed to be used like this.
-    IsTrueLike { variable: Variable },
+    IsTrueLike { expression: Expression },
+    /// Assert that a list contains all items from an `x ? y` statement.
+    ///
+    /// Every item expression must evaluate to a list, which can be a single list item.
+    /// The condition is `true` if none of them is empty and all their items are in the
+    /// list. Conditions with the `!?` marker are negated.
+    ///
+    /// An error is raised if a variable which is not a list is used like this.
+    Contains {
+        /// Expression which evaluates to the list to check.
+        list: Expression,
+        /// Expressions which evaluate to the items that must be in the list.
+        ///
+        /// Several items are given in parenthesis: `x ? (y, z)`.
+        items: Vec<Expression>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -146,9 +163,18 @@ impl Condition {
     ///
     /// This closure will be called on every item in the `Condition` as all parts
     /// are walked through.
-    pub fn evaluate<F, E>(&self, evaluator: &F) -> Result<bool, E>
+    pub fn evaluate<F, E>(&self, mut evaluator: F) -> Result<bool, E>
+    where
+        F: FnMut(&StoryCondition) -> Result<bool, E>,
+        E: Error,
+    {
+        self.evaluate_with(&mut evaluator)
+    }
+
+    /// Evaluate the condition with a borrowed evaluator closure.
+    fn evaluate_with<F, E>(&self, evaluator: &mut F) -> Result<bool, E>
     where
-        F: Fn(&StoryCondition) -> Result<bool, E>,
+        F: FnMut(&StoryCondition) -> Result<bool, E>,
         E: Error,
     {
         self.items
@@ -163,15 +189,15 @@ impl Condition {
 }
 
 /// Match against and evaluate the items.
-fn inner_eval<F, E>(item: &ConditionItem, evaluator: &F) -> Result<bool, E>
+fn inner_eval<F, E>(item: &ConditionItem, evaluator: &mut F) -> Result<bool, E>
 where
-    F: Fn(&StoryCondition) -> Result<bool, E>,
+    F: FnMut(&StoryCondition) -> Result<bool, E>,
     E: Error,
 {
     let mut result = match &item.kind {
         ConditionKind::True => Ok(true),
         ConditionKind::False => Ok(false),
-        ConditionKind::Nested(condition) => condition.evaluate(evaluator),
+        ConditionKind::Nested(condition) => condition.evaluate_with(evaluator),
         ConditionKind::Single(ref kind) => evaluator(kind),
     }?;
 
@@ -255,7 +281,7 @@ impl ValidateContent for Condition {
         });
 
         if num_errors == error.num_errors() {
-            if let Err(err) = check_condition(self, &data.follow_data) {
+            if let Err(err) = check_condition(self, &mut data.get_follow_data().into_owned()) {
                 error.variable_errors.push(InvalidVariableExpression {
                     expression_kind: ExpressionKind::Condition,
                     kind: err.into(),
@@ -305,8 +331,14 @@ impl ValidateContent for StoryCondition {
                 lhs_variable.validate(error, log, current_location, meta_data, data);
                 rhs_variable.validate(error, log, current_location, meta_data, data);
             }
-            StoryCondition::IsTrueLike { variable } => {
-                variable.validate(error, log, current_location, meta_data, data)
+            StoryCondition::IsTrueLike { expression } => {
+                expression.validate(error, log, current_location, meta_data, data)
+            }
+            StoryCondition::Contains { list, items } => {
+                list.validate(error, log, current_location, meta_data, data);
+                items
+                    .iter_mut()
+                    .for_each(|item| item.validate(error, log, current_location, meta_data, data));
             }
         }
     }
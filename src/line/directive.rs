@@ -0,0 +1,31 @@
+//! Custom directives for story content which is not text.
+
+use crate::line::Variable;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+/// Callback which parses the content of a directive line into its arguments.
+///
+/// The callback is given the content of the line after the directive prefix, trimmed
+/// of whitespace. If the content is not valid for the directive, return an error message
+/// describing the problem: it is reported as a parsing error for the line.
+pub type DirectiveParser = fn(&str) -> Result<Vec<Variable>, String>;
+
+/// Registered directive prefixes and the callbacks which parse their content.
+pub type DirectiveSet = HashMap<String, DirectiveParser>;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Custom directive which was parsed from a line of story content.
+///
+/// Lines which begin with a prefix that was registered in the
+/// [`ReadOptions`][crate::ReadOptions] are parsed into directives instead of text.
+pub struct Directive {
+    /// Prefix which marked the line as a directive, for example `@cue`.
+    pub name: String,
+    /// Arguments that were returned by the callback which parsed the directive.
+    pub arguments: Vec<Variable>,
+}
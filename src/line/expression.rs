@@ -6,9 +6,9 @@ use crate::{
         utils::MetaData,
         InklingError,
     },
-    follow::FollowData,
+    follow::{FollowData, LineDataBuffer},
     knot::Address,
-    line::Variable,
+    line::{FunctionCall, Variable},
     log::Logger,
     story::validate::{ValidateContent, ValidationData},
 };
@@ -16,6 +16,8 @@ use crate::{
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
+use std::borrow::Cow;
+
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Single mathematical expression.
@@ -49,6 +51,8 @@ pub enum Operand {
     Nested(Box<Expression>),
     /// Variable with a value.
     Variable(Variable),
+    /// Call to a built-in function, which evaluates to a value.
+    Call(FunctionCall),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -65,30 +69,62 @@ pub enum Operator {
 }
 
 /// Evaluate an expression from start to finish, producing a single `Variable` value.
+///
+/// Story functions that the expression calls are followed in the story state, so the
+/// changes that they make to it are kept. The text that they write is not kept.
 pub fn evaluate_expression(
     expression: &Expression,
-    data: &FollowData,
+    data: &mut FollowData,
 ) -> Result<Variable, InklingError> {
-    expression
-        .tail
-        .iter()
-        .map(|(operation, operand)| (operation, get_value(operand, data)))
-        .fold(
-            get_value(&expression.head, data),
-            |acc, (operation, operand)| {
-                let lhs_variable = acc?;
-                let rhs_variable = operand?;
-
-                match operation {
-                    Operator::Add => lhs_variable.add(&rhs_variable),
-                    Operator::Subtract => lhs_variable.subtract(&rhs_variable),
-                    Operator::Multiply => lhs_variable.multiply(&rhs_variable),
-                    Operator::Divide => lhs_variable.divide(&rhs_variable),
-                    Operator::Remainder => lhs_variable.remainder(&rhs_variable),
-                }
-                .map_err(|err| err.into())
-            },
-        )
+    follow_expression_value(expression, &mut Vec::new(), data)
+}
+
+/// Evaluate an expression in the story, following the story functions that it calls.
+///
+/// Unlike `evaluate_expression` the text that the functions write is added to the buffer
+/// in the order that they are called. An expression which is only a call to a function
+/// that does not return a value evaluates to `None`.
+pub fn follow_expression(
+    expression: &Expression,
+    buffer: &mut LineDataBuffer,
+    data: &mut FollowData,
+) -> Result<Option<Variable>, InklingError> {
+    match &expression.head {
+        Operand::Call(call) if expression.tail.is_empty() => call.follow(buffer, data),
+        _ => follow_expression_value(expression, buffer, data).map(Some),
+    }
+}
+
+/// Follow an expression which has to evaluate to a value.
+pub fn follow_expression_value(
+    expression: &Expression,
+    buffer: &mut LineDataBuffer,
+    data: &mut FollowData,
+) -> Result<Variable, InklingError> {
+    let mut value = follow_value(&expression.head, buffer, data)?;
+
+    for (operation, operand) in expression.tail.iter() {
+        let rhs_variable = follow_value(operand, buffer, data)?;
+        value = apply_operator(*operation, &value, &rhs_variable)?;
+    }
+
+    Ok(value)
+}
+
+/// Apply an operator to two variables.
+fn apply_operator(
+    operation: Operator,
+    lhs_variable: &Variable,
+    rhs_variable: &Variable,
+) -> Result<Variable, InklingError> {
+    match operation {
+        Operator::Add => lhs_variable.add(rhs_variable),
+        Operator::Subtract => lhs_variable.subtract(rhs_variable),
+        Operator::Multiply => lhs_variable.multiply(rhs_variable),
+        Operator::Divide => lhs_variable.divide(rhs_variable),
+        Operator::Remainder => lhs_variable.remainder(rhs_variable),
+    }
+    .map_err(|err| err.into())
 }
 
 /// Nest inner operations based on order of precedence in operations.
@@ -102,6 +138,7 @@ pub fn evaluate_expression(
 /// # Notes
 /// *   This function does *not* recurse into nested expressions to apply order of operations
 ///     for them. This has to be done separately, as those items are created.
+#[cfg(feature = "math")]
 pub fn apply_order_of_operations(expression: &Expression) -> Expression {
     split_expression_into_groups_of_same_precedence(expression)
         .into_iter()
@@ -115,11 +152,21 @@ pub fn apply_order_of_operations(expression: &Expression) -> Expression {
         .unwrap()
 }
 
-/// Evaluate a variable or inner expression to produce a single variable.
-fn get_value(operand: &Operand, data: &FollowData) -> Result<Variable, InklingError> {
+/// Follow a variable, inner expression or function call to produce a single variable.
+fn follow_value(
+    operand: &Operand,
+    buffer: &mut LineDataBuffer,
+    data: &mut FollowData,
+) -> Result<Variable, InklingError> {
     match operand {
-        Operand::Nested(expression) => evaluate_expression(expression, data),
-        Operand::Variable(variable) => variable.as_value(data),
+        Operand::Nested(expression) => follow_expression_value(expression, buffer, data),
+        Operand::Variable(variable) => variable.as_value(&*data),
+        Operand::Call(call) => {
+            call.follow(buffer, data)?
+                .ok_or_else(|| InklingError::NoReturnValue {
+                    name: call.function.name().to_string(),
+                })
+        }
     }
 }
 
@@ -127,6 +174,7 @@ fn get_value(operand: &Operand, data: &FollowData) -> Result<Variable, InklingEr
 ///
 /// This groups multiplied, divided with and remainder or items, while added and subtracted
 /// items remain alone.
+#[cfg(feature = "math")]
 fn split_expression_into_groups_of_same_precedence(
     expression: &Expression,
 ) -> Vec<Vec<(Operator, Operand)>> {
@@ -159,6 +207,7 @@ fn split_expression_into_groups_of_same_precedence(
 ///
 /// If the group contains a single item it will be returned as an `Operand::Variable` object.
 /// If not, a `Operand::Nested` object is constructed from all items.
+#[cfg(feature = "math")]
 fn get_maybe_nested_operand_from_group(group: Vec<(Operator, Operand)>) -> (Operator, Operand) {
     if group.len() == 1 {
         group[0].clone()
@@ -177,8 +226,12 @@ fn get_maybe_nested_operand_from_group(group: Vec<(Operator, Operand)>) -> (Oper
     }
 }
 
-impl ValidateContent for Expression {
-    fn validate(
+impl Expression {
+    /// Validate an expression which is written into the text of a line.
+    ///
+    /// Such expressions are followed like in the story, so they can call functions
+    /// which do not return a value.
+    pub fn validate_in_text(
         &mut self,
         error: &mut ValidationError,
         log: &mut Logger,
@@ -186,6 +239,31 @@ impl ValidateContent for Expression {
         meta_data: &MetaData,
         data: &ValidationData,
     ) {
+        self.validate_with(
+            error,
+            log,
+            current_location,
+            meta_data,
+            data,
+            |expression, follow_data| {
+                follow_expression(expression, &mut Vec::new(), &mut follow_data.into_owned())
+                    .map(|_| ())
+            },
+        )
+    }
+
+    /// Validate the operands of the expression, then check that it can be evaluated.
+    fn validate_with<F>(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+        evaluate: F,
+    ) where
+        F: FnOnce(&Expression, Cow<'_, FollowData>) -> Result<(), InklingError>,
+    {
         let num_errors = error.num_errors();
 
         self.head
@@ -196,7 +274,7 @@ impl ValidateContent for Expression {
         });
 
         if num_errors == error.num_errors() {
-            if let Err(err) = evaluate_expression(self, &data.follow_data) {
+            if let Err(err) = evaluate(self, data.get_follow_data()) {
                 error.variable_errors.push(InvalidVariableExpression {
                     expression_kind: ExpressionKind::Expression,
                     kind: err.into(),
@@ -207,6 +285,28 @@ impl ValidateContent for Expression {
     }
 }
 
+impl ValidateContent for Expression {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        self.validate_with(
+            error,
+            log,
+            current_location,
+            meta_data,
+            data,
+            |expression, follow_data| {
+                evaluate_expression(expression, &mut follow_data.into_owned()).map(|_| ())
+            },
+        )
+    }
+}
+
 impl ValidateContent for Operand {
     fn validate(
         &mut self,
@@ -223,6 +323,9 @@ impl ValidateContent for Operand {
             Operand::Variable(ref mut variable) => {
                 variable.validate(error, log, current_location, meta_data, data)
             }
+            Operand::Call(ref mut call) => {
+                call.validate(error, log, current_location, meta_data, data)
+            }
         }
     }
 }
@@ -300,31 +403,31 @@ mod tests {
 
     #[test]
     fn expression_with_just_head_evaluates_to_head() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
         let expression = get_simple_expression(Variable::Int(5), &[]);
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(5)
         );
     }
 
     #[test]
     fn adding_two_variables_creates_summed_variable() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression =
             get_simple_expression(Variable::Int(1), &[(Operator::Add, Variable::Int(2))]);
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(3)
         );
     }
 
     #[test]
     fn all_operations_work_in_order() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         // 1 + 2 - (-2) * (-3) / 5 = -3
         let expression = get_simple_expression(
@@ -338,26 +441,26 @@ mod tests {
         );
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Float(-3.0)
         );
     }
 
     #[test]
     fn get_value_evaluates_variables_by_following_addresses_if_necessary() {
-        let data = mock_follow_data(&[], &[("counter", 1.into())]);
+        let mut data = mock_follow_data(&[], &[("counter", 1.into())]);
 
         let variable = Variable::Address(Address::variable_unchecked("counter"));
 
         assert_eq!(
-            get_value(&Operand::Variable(variable), &data).unwrap(),
+            follow_value(&Operand::Variable(variable), &mut Vec::new(), &mut data).unwrap(),
             Variable::Int(1)
         );
     }
 
     #[test]
     fn nested_expression_evaluates_into_variable() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let nested_expression = get_simple_expression(
             Variable::Int(1),
@@ -367,18 +470,20 @@ mod tests {
         let nested = Operand::Nested(Box::new(nested_expression.clone()));
 
         assert_eq!(
-            evaluate_expression(&nested_expression, &data).unwrap(),
-            get_value(&nested, &data).unwrap()
+            evaluate_expression(&nested_expression, &mut data).unwrap(),
+            follow_value(&nested, &mut Vec::new(), &mut data).unwrap()
         );
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn order_of_operations_on_expression_with_just_head_is_head() {
         let expression = get_simple_expression(Variable::Int(5), &[]);
         assert_eq!(apply_order_of_operations(&expression), expression);
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn order_of_operations_with_just_add_and_subtract_changes_nothing() {
         // 1 + 1 - 2 - 3 + 4 = 1 + 1 - 2 - 3 + 4
         let expression = get_simple_expression(
@@ -397,6 +502,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn order_of_operations_gathers_multiplied_items_into_nested_groups() {
         // 1 + 1 * 2 * 3 = 1 + (1 * 2 * 3)
         let expression = get_simple_expression(
@@ -424,6 +530,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn multiple_nested_groups_are_separated_by_addition_or_subtraction() {
         // 1 + 1 * 2 + 3 * 4 = 1 + (1 * 2) + (3 * 4)
         let expression = get_simple_expression(
@@ -452,6 +559,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn if_all_items_are_multiply_they_gather_into_one_item_in_head() {
         // 1 * 2 * 3 = (1 * 2 * 3)
         let expression = get_simple_expression(
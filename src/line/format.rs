@@ -0,0 +1,125 @@
+//! Formatting of story logic into text close to how it is written in `Ink`.
+
+use crate::line::{
+    condition::AndOr,
+    expression::{Operand, Operator},
+    Condition, ConditionItem, ConditionKind, Expression, StoryCondition, Variable,
+};
+
+use std::cmp::Ordering;
+
+/// Write a condition in a form close to how it is written in `Ink`.
+///
+/// Comparisons are written as they are stored, so `a >= b` is written as `not a < b`.
+pub(crate) fn format_condition(condition: &Condition) -> String {
+    let mut text = format_condition_item(&condition.root);
+
+    for item in condition.items.iter() {
+        match item {
+            AndOr::And(item) => text.push_str(&format!(" and {}", format_condition_item(item))),
+            AndOr::Or(item) => text.push_str(&format!(" or {}", format_condition_item(item))),
+        }
+    }
+
+    text
+}
+
+/// Write a single item of a condition.
+fn format_condition_item(item: &ConditionItem) -> String {
+    let text = match &item.kind {
+        ConditionKind::True => "true".to_string(),
+        ConditionKind::False => "false".to_string(),
+        ConditionKind::Nested(condition) => format!("({})", format_condition(condition)),
+        ConditionKind::Single(StoryCondition::Comparison {
+            lhs_variable,
+            rhs_variable,
+            ordering,
+        }) => {
+            let operator = match ordering {
+                Ordering::Equal => "==",
+                Ordering::Greater => ">",
+                Ordering::Less => "<",
+            };
+
+            format!(
+                "{} {} {}",
+                format_expression(lhs_variable),
+                operator,
+                format_expression(rhs_variable)
+            )
+        }
+        ConditionKind::Single(StoryCondition::IsTrueLike { expression }) => {
+            format_expression(expression)
+        }
+        ConditionKind::Single(StoryCondition::Contains { list, items }) => {
+            let items = items.iter().map(format_expression).collect::<Vec<_>>();
+
+            match items.as_slice() {
+                [item] => format!("{} ? {}", format_expression(list), item),
+                _ => format!("{} ? ({})", format_expression(list), items.join(", ")),
+            }
+        }
+    };
+
+    if item.negate {
+        format!("not {}", text)
+    } else {
+        text
+    }
+}
+
+/// Write an expression with its operators.
+pub(crate) fn format_expression(expression: &Expression) -> String {
+    let mut text = format_operand(&expression.head);
+
+    for (operator, operand) in expression.tail.iter() {
+        text.push_str(&format!(
+            " {} {}",
+            format_operator(*operator),
+            format_operand(operand)
+        ));
+    }
+
+    text
+}
+
+/// Write the symbol of a mathematical operator.
+pub(crate) fn format_operator(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Add => "+",
+        Operator::Subtract => "-",
+        Operator::Multiply => "*",
+        Operator::Divide => "/",
+        Operator::Remainder => "%",
+    }
+}
+
+/// Write a single term of an expression.
+pub(crate) fn format_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Nested(expression) => format!("({})", format_expression(expression)),
+        Operand::Variable(variable) => format_variable_for_debug(variable),
+        Operand::Call(call) => {
+            let arguments = call
+                .arguments
+                .iter()
+                .map(format_expression)
+                .collect::<Vec<_>>();
+
+            format!("{}({})", call.function.name(), arguments.join(", "))
+        }
+    }
+}
+
+/// Write the value of a variable for a debugging description.
+pub(crate) fn format_variable_for_debug(variable: &Variable) -> String {
+    match variable {
+        Variable::Bool(value) => format!("{}", value),
+        Variable::Float(value) => format!("{}", value),
+        Variable::Int(value) => format!("{}", value),
+        Variable::String(value) => format!("{:?}", value),
+        Variable::List(list) => format!("({})", list),
+        Variable::Divert(address) => format!("-> {}", address.to_string()),
+        Variable::Address(address) => address.to_string(),
+    }
+}
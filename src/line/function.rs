@@ -0,0 +1,818 @@
+//! Built-in and story functions which can be called in expressions.
+
+use crate::{
+    error::{
+        parse::{
+            address::{InvalidAddressError, InvalidAddressErrorKind},
+            validate::ValidationError,
+        },
+        utils::MetaData,
+        variable::{VariableError, VariableErrorKind},
+        InklingError,
+    },
+    follow::{FollowData, LineDataBuffer},
+    knot::Address,
+    line::{expression::follow_expression_value, Expression, List, ListItem, Variable},
+    log::Logger,
+    story::{
+        audit::NondeterminismKind,
+        validate::{ValidateContent, ValidationData},
+    },
+    utils::get_close_matches,
+};
+
+#[cfg(not(feature = "random"))]
+use crate::log::Warning;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Function which is built into the language or written in the story.
+pub enum Function {
+    /// `ABS(x)`: absolute value of a number.
+    ///
+    /// Yields an error for the lowest integer, whose absolute value is not an integer.
+    Abs,
+    /// `CEILING(x)`: number rounded up to the nearest whole number.
+    ///
+    /// Floats stay floats, use `INT` to convert the result to an integer.
+    Ceiling,
+    /// `FLOAT(x)`: number converted to a float.
+    Float,
+    /// `FLOOR(x)`: number rounded down to the nearest whole number.
+    ///
+    /// Floats stay floats, use `INT` to convert the result to an integer.
+    Floor,
+    /// `INT(x)`: number converted to an integer by rounding it towards zero.
+    Int,
+    /// `LIST_ALL(list)`: all items of the list definitions that the list's items come from.
+    ListAll,
+    /// `LIST_COUNT(list)`: number of items in the list.
+    ListCount,
+    /// `LIST_MAX(list)`: item with the highest value in the list.
+    ListMax,
+    /// `LIST_MIN(list)`: item with the lowest value in the list.
+    ListMin,
+    /// `LIST_RANGE(list, min, max)`: items in the list with values from `min` to `max`.
+    ///
+    /// The bounds are numbers or list items.
+    ListRange,
+    /// `MAX(a, b)`: highest of two numbers.
+    ///
+    /// Is an integer if both arguments are integers, otherwise a float.
+    Max,
+    /// `MIN(a, b)`: lowest of two numbers.
+    ///
+    /// Is an integer if both arguments are integers, otherwise a float.
+    Min,
+    /// `POW(base, exponent)`: number raised to a power.
+    ///
+    /// Is an integer if both arguments are integers, otherwise a float. Yields an error
+    /// if an integer result is out of the range of integers.
+    Pow,
+    /// `RANDOM(min, max)`: random number from `min` to `max`, including both.
+    ///
+    /// Numbers are drawn from the generator of the story, which is only random if
+    /// the `random` feature is enabled. Otherwise the number is always `min` and
+    /// a [`RandomFunctionNoRandom`][crate::log::Warning::RandomFunctionNoRandom] warning
+    /// is logged for the call when the story is read. A `min` which is higher than `max`
+    /// yields an [`InvalidBounds`][crate::error::variable::VariableErrorKind::InvalidBounds]
+    /// error.
+    Random,
+    /// `SEED_RANDOM(seed)`: restart the generator of the story from a seed, which makes
+    /// the numbers and shuffles that follow repeatable.
+    ///
+    /// Evaluates to empty text.
+    SeedRandom,
+    /// `TURNS()`: number of turns, or choices made, since the story began.
+    Turns,
+    /// `TURNS_SINCE(-> address)`: number of turns since a knot, stitch or label was last
+    /// visited.
+    ///
+    /// Is 0 if it was visited during the current turn and -1 if it has not been visited.
+    TurnsSince,
+    /// Function with the given name which is written as a knot in the story.
+    Story(String),
+}
+
+impl Function {
+    /// Get the built-in function with a name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ABS" => Some(Function::Abs),
+            "CEILING" => Some(Function::Ceiling),
+            "FLOAT" => Some(Function::Float),
+            "FLOOR" => Some(Function::Floor),
+            "INT" => Some(Function::Int),
+            "LIST_ALL" => Some(Function::ListAll),
+            "LIST_COUNT" => Some(Function::ListCount),
+            "LIST_MAX" => Some(Function::ListMax),
+            "LIST_MIN" => Some(Function::ListMin),
+            "LIST_RANGE" => Some(Function::ListRange),
+            "MAX" => Some(Function::Max),
+            "MIN" => Some(Function::Min),
+            "POW" => Some(Function::Pow),
+            "RANDOM" => Some(Function::Random),
+            "SEED_RANDOM" => Some(Function::SeedRandom),
+            "TURNS" => Some(Function::Turns),
+            "TURNS_SINCE" => Some(Function::TurnsSince),
+            _ => None,
+        }
+    }
+
+    /// Get the name that the function is called with.
+    pub fn name(&self) -> &str {
+        match self {
+            Function::Abs => "ABS",
+            Function::Ceiling => "CEILING",
+            Function::Float => "FLOAT",
+            Function::Floor => "FLOOR",
+            Function::Int => "INT",
+            Function::ListAll => "LIST_ALL",
+            Function::ListCount => "LIST_COUNT",
+            Function::ListMax => "LIST_MAX",
+            Function::ListMin => "LIST_MIN",
+            Function::ListRange => "LIST_RANGE",
+            Function::Max => "MAX",
+            Function::Min => "MIN",
+            Function::Pow => "POW",
+            Function::Random => "RANDOM",
+            Function::SeedRandom => "SEED_RANDOM",
+            Function::Turns => "TURNS",
+            Function::TurnsSince => "TURNS_SINCE",
+            Function::Story(name) => name,
+        }
+    }
+
+    /// Get the number of arguments that a built-in function takes.
+    ///
+    /// Functions in the story are given the number of their parameters, which is checked
+    /// when the story is validated.
+    pub fn num_arguments(&self) -> Option<usize> {
+        match self {
+            Function::Turns => Some(0),
+            Function::Abs
+            | Function::Ceiling
+            | Function::Float
+            | Function::Floor
+            | Function::Int
+            | Function::ListAll
+            | Function::ListCount
+            | Function::ListMax
+            | Function::ListMin
+            | Function::SeedRandom
+            | Function::TurnsSince => Some(1),
+            Function::Max | Function::Min | Function::Pow | Function::Random => Some(2),
+            Function::ListRange => Some(3),
+            Function::Story(..) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Call to a function with expressions as arguments.
+pub struct FunctionCall {
+    /// Function which is called.
+    pub function: Function,
+    /// Expressions which are evaluated into the arguments of the function.
+    ///
+    /// The number of arguments to built-in functions is checked when the call is parsed,
+    /// and to functions in the story when it is validated.
+    pub arguments: Vec<Expression>,
+}
+
+impl FunctionCall {
+    /// Evaluate the arguments and call the function with them.
+    ///
+    /// Functions which are written in the story are followed in the story state, so the
+    /// changes that they make to it are kept, but the text that they write is not.
+    pub fn evaluate(&self, data: &mut FollowData) -> Result<Variable, InklingError> {
+        self.follow(&mut Vec::new(), data)?
+            .ok_or_else(|| InklingError::NoReturnValue {
+                name: self.function.name().to_string(),
+            })
+    }
+
+    /// Follow the arguments and call the function with them in the story.
+    ///
+    /// Functions which are written in the story are followed in the story state, so the
+    /// changes that they make to it are kept and the text that they write is added to
+    /// the buffer. Functions which do not return a value give `None`.
+    pub fn follow(
+        &self,
+        buffer: &mut LineDataBuffer,
+        data: &mut FollowData,
+    ) -> Result<Option<Variable>, InklingError> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| follow_expression_value(argument, buffer, data))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Function::Story(name) = &self.function {
+            if let Some(function) = data.external_functions.get(name) {
+                return Ok(function(&arguments));
+            }
+
+            if let Some(function) = data.functions.get(name).cloned() {
+                return function.follow_call(name, arguments, buffer, data);
+            }
+        }
+
+        self.call_with_arguments(arguments, data).map(Some)
+    }
+
+    /// Call the function with evaluated arguments.
+    fn call_with_arguments(
+        &self,
+        arguments: Vec<Variable>,
+        data: &FollowData,
+    ) -> Result<Variable, InklingError> {
+        if let Function::Story(name) = &self.function {
+            if let Some(function) = data.external_functions.get(name) {
+                return function(&arguments)
+                    .ok_or_else(|| InklingError::NoReturnValue { name: name.clone() });
+            }
+
+            return if data.external_functions.is_declared(name) {
+                Err(InklingError::UnboundExternalFunction { name: name.clone() })
+            } else {
+                Err(InklingError::InvalidFunction { name: name.clone() })
+            };
+        }
+
+        match (&self.function, arguments.as_slice()) {
+            (Function::Abs, [Variable::Int(value)]) => value
+                .checked_abs()
+                .map(Variable::Int)
+                .ok_or_else(|| self.integer_overflow(*value).into()),
+            (Function::Ceiling, [Variable::Int(value)])
+            | (Function::Floor, [Variable::Int(value)])
+            | (Function::Int, [Variable::Int(value)]) => Ok(Variable::Int(*value)),
+            (Function::Int, [Variable::Float(value)]) => Ok(Variable::Int(value.trunc() as i32)),
+            (Function::Abs, [value])
+            | (Function::Ceiling, [value])
+            | (Function::Float, [value])
+            | (Function::Floor, [value]) => {
+                let value = self.get_number(value)?;
+
+                let result = match self.function {
+                    Function::Abs => value.abs(),
+                    Function::Ceiling => value.ceil(),
+                    Function::Floor => value.floor(),
+                    _ => value,
+                };
+
+                Ok(Variable::Float(result))
+            }
+            (Function::Int, [other]) => Err(self.invalid_argument(other).into()),
+            (Function::Max, [Variable::Int(a), Variable::Int(b)]) => Ok(Variable::Int(*a.max(b))),
+            (Function::Min, [Variable::Int(a), Variable::Int(b)]) => Ok(Variable::Int(*a.min(b))),
+            (Function::Max, [a, b]) => Ok(Variable::Float(
+                self.get_number(a)?.max(self.get_number(b)?),
+            )),
+            (Function::Min, [a, b]) => Ok(Variable::Float(
+                self.get_number(a)?.min(self.get_number(b)?),
+            )),
+            (Function::Pow, [Variable::Int(base), Variable::Int(exponent)]) if *exponent >= 0 => {
+                base.checked_pow(*exponent as u32)
+                    .map(Variable::Int)
+                    .ok_or_else(|| self.integer_overflow(*base).into())
+            }
+            // Negative exponents give results between -1 and 1, which cannot overflow
+            (Function::Pow, [Variable::Int(base), Variable::Int(exponent)]) => {
+                Ok(Variable::Int((*base as f64).powf(*exponent as f64) as i32))
+            }
+            (Function::Pow, [base, exponent]) => {
+                let base = self.get_number(base)?;
+                let exponent = self.get_number(exponent)?;
+
+                Ok(Variable::Float(base.powf(exponent)))
+            }
+            (Function::ListAll, [list]) => {
+                let list = self.get_list(list)?;
+
+                Ok(list
+                    .origins()
+                    .filter_map(|origin| data.variables.get(origin))
+                    .filter_map(|variable_info| variable_info.list_items.as_ref())
+                    .fold(List::default(), |all, items| all.union(items))
+                    .into())
+            }
+            (Function::ListCount, [list]) => Ok(Variable::Int(self.get_list(list)?.len() as i32)),
+            (Function::ListMax, [list]) => {
+                let list = self.get_list(list)?;
+                let max = list.items().last().cloned();
+
+                Ok(list.filter(|item| Some(item) == max.as_ref()).into())
+            }
+            (Function::ListMin, [list]) => {
+                let list = self.get_list(list)?;
+                let min = list.items().next().cloned();
+
+                Ok(list.filter(|item| Some(item) == min.as_ref()).into())
+            }
+            (Function::ListRange, [list, min, max]) => {
+                let list = self.get_list(list)?;
+                let min = self.get_bound(min, |bound| bound.items().next())?;
+                let max = self.get_bound(max, |bound| bound.items().last())?;
+
+                Ok(list
+                    .filter(|item| item.value >= min && item.value <= max)
+                    .into())
+            }
+            (Function::Random, [Variable::Int(min), Variable::Int(max)]) if min <= max => {
+                if !data.rng.is_seeded() {
+                    data.record_nondeterminism(NondeterminismKind::UnseededRandom);
+                }
+
+                Ok(Variable::Int(data.rng.gen_range(*min, *max)))
+            }
+            (Function::Random, [Variable::Int(min), Variable::Int(max)]) => {
+                Err(VariableError::from_kind(
+                    *min,
+                    VariableErrorKind::InvalidBounds {
+                        function: self.function.name().to_string(),
+                        max: *max,
+                    },
+                )
+                .into())
+            }
+            (Function::Random, [min, max]) => match min {
+                Variable::Int(..) => Err(self.invalid_argument(max).into()),
+                _ => Err(self.invalid_argument(min).into()),
+            },
+            (Function::SeedRandom, [Variable::Int(seed)]) => {
+                data.rng.reseed(*seed as i64 as u64);
+
+                Ok(Variable::String(String::new()))
+            }
+            (Function::SeedRandom, [other]) => Err(self.invalid_argument(other).into()),
+            (Function::Turns, []) => Ok(Variable::Int(data.turn as i32)),
+            (Function::TurnsSince, [Variable::Divert(address)]) => {
+                let turns_since = data
+                    .last_visited_turns
+                    .get(&address.to_string())
+                    .map(|turn| (data.turn - turn) as i32)
+                    .unwrap_or(-1);
+
+                Ok(Variable::Int(turns_since))
+            }
+            (Function::TurnsSince, [other]) => Err(self.invalid_argument(other).into()),
+            _ => unreachable!("the number of arguments is checked when calls are parsed"),
+        }
+    }
+
+    /// Get the list of an argument, or an error if it is not a list.
+    fn get_list<'a>(&self, argument: &'a Variable) -> Result<&'a List, VariableError> {
+        match argument {
+            Variable::List(list) => Ok(list),
+            other => Err(self.invalid_argument(other)),
+        }
+    }
+
+    /// Get the value of a number as a float, or an error if it is not a number.
+    fn get_number(&self, argument: &Variable) -> Result<f32, VariableError> {
+        match argument {
+            Variable::Int(value) => Ok(*value as f32),
+            Variable::Float(value) => Ok(*value),
+            other => Err(self.invalid_argument(other)),
+        }
+    }
+
+    /// Get the value of a bound, which is either a number or the value of an item in a list.
+    fn get_bound<'a, F>(&self, argument: &'a Variable, get_item: F) -> Result<i32, VariableError>
+    where
+        F: Fn(&'a List) -> Option<&'a ListItem>,
+    {
+        match argument {
+            Variable::Int(value) => Ok(*value),
+            Variable::List(list) => get_item(list)
+                .map(|item| item.value)
+                .ok_or_else(|| self.invalid_argument(argument)),
+            other => Err(self.invalid_argument(other)),
+        }
+    }
+
+    /// Get an error for a call whose integer result overflowed.
+    fn integer_overflow(&self, argument: i32) -> VariableError {
+        VariableError::from_kind(
+            argument,
+            VariableErrorKind::IntegerOverflow {
+                function: self.function.name().to_string(),
+            },
+        )
+    }
+
+    fn invalid_argument(&self, argument: &Variable) -> VariableError {
+        VariableError::from_kind(
+            argument.clone(),
+            VariableErrorKind::InvalidArgument {
+                function: self.function.name().to_string(),
+            },
+        )
+    }
+
+    /// Check that a function with the name is written in the story and takes the number
+    /// of arguments that it is called with.
+    fn validate_story_function(
+        &self,
+        name: &str,
+        data: &ValidationData,
+    ) -> Result<(), InvalidAddressErrorKind> {
+        let function = data
+            .knots
+            .get(name)
+            .filter(|knot| knot.is_function)
+            .ok_or_else(|| {
+                let candidates = data
+                    .knots
+                    .iter()
+                    .filter(|(_, knot)| knot.is_function)
+                    .map(|(name, _)| name.as_str());
+
+                InvalidAddressErrorKind::UnknownFunction {
+                    name: name.to_string(),
+                    suggestions: get_close_matches(name, candidates),
+                }
+            })?;
+
+        if function.parameters.len() == self.arguments.len() {
+            Ok(())
+        } else {
+            Err(InvalidAddressErrorKind::InvalidNumberOfArguments {
+                function: name.to_string(),
+                expected: function.parameters.len(),
+                found: self.arguments.len(),
+            })
+        }
+    }
+}
+
+impl ValidateContent for FunctionCall {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        self.arguments
+            .iter_mut()
+            .for_each(|argument| argument.validate(error, log, current_location, meta_data, data));
+
+        #[cfg(not(feature = "random"))]
+        if self.function == Function::Random {
+            log.add_warning(Warning::RandomFunctionNoRandom, meta_data);
+        }
+
+        if let Function::Story(name) = &self.function {
+            if let Err(kind) = self.validate_story_function(name, data) {
+                error.invalid_address_errors.push(InvalidAddressError {
+                    kind,
+                    meta_data: meta_data.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{follow::FollowDataBuilder, line::expression::Operand, story::types::VariableInfo};
+
+    use std::collections::HashMap;
+
+    fn call(function: Function, arguments: Vec<Variable>) -> Result<Variable, InklingError> {
+        let mut inventory = VariableInfo::new(Variable::List(List::default()), 0);
+        inventory.list_items = Some(
+            vec![
+                ListItem::new("inventory", "sword", 1),
+                ListItem::new("inventory", "torch", 2),
+                ListItem::new("inventory", "key", 3),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let mut variables = HashMap::new();
+        variables.insert("inventory".to_string(), inventory);
+
+        let mut data = FollowDataBuilder::new().with_variables(variables).build();
+
+        FunctionCall {
+            function,
+            arguments: arguments
+                .into_iter()
+                .map(|variable| Expression {
+                    head: Operand::Variable(variable),
+                    tail: Vec::new(),
+                })
+                .collect(),
+        }
+        .evaluate(&mut data)
+    }
+
+    fn list(names: &[(&str, i32)]) -> Variable {
+        Variable::List(
+            names
+                .iter()
+                .map(|(name, value)| ListItem::new("inventory", name, *value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn list_queries_count_and_find_the_lowest_and_highest_items() {
+        let items = list(&[("sword", 1), ("key", 3)]);
+
+        assert_eq!(
+            call(Function::ListCount, vec![items.clone()]).unwrap(),
+            Variable::Int(2)
+        );
+        assert_eq!(
+            call(Function::ListMin, vec![items.clone()]).unwrap(),
+            list(&[("sword", 1)])
+        );
+        assert_eq!(
+            call(Function::ListMax, vec![items.clone()]).unwrap(),
+            list(&[("key", 3)])
+        );
+        assert_eq!(call(Function::ListMax, vec![list(&[])]).unwrap(), list(&[]));
+    }
+
+    #[test]
+    fn list_all_gets_every_item_of_the_origins_of_a_list() {
+        let empty = List::default().with_origin("inventory");
+
+        assert_eq!(
+            call(Function::ListAll, vec![Variable::List(empty)]).unwrap(),
+            list(&[("sword", 1), ("torch", 2), ("key", 3)])
+        );
+    }
+
+    #[test]
+    fn list_range_keeps_items_between_numbers_or_items() {
+        let items = list(&[("sword", 1), ("torch", 2), ("key", 3)]);
+
+        assert_eq!(
+            call(
+                Function::ListRange,
+                vec![items.clone(), Variable::Int(2), Variable::Int(5)]
+            )
+            .unwrap(),
+            list(&[("torch", 2), ("key", 3)])
+        );
+        assert_eq!(
+            call(
+                Function::ListRange,
+                vec![items.clone(), list(&[("sword", 1)]), list(&[("torch", 2)])]
+            )
+            .unwrap(),
+            list(&[("sword", 1), ("torch", 2)])
+        );
+    }
+
+    #[test]
+    fn turns_since_counts_the_turns_from_the_last_visit_of_an_address() {
+        let address = Address::from_parts_unchecked("tripoli", None);
+
+        let mut data = FollowDataBuilder::new().build();
+        data.turn = 5;
+        data.last_visited_turns.insert("tripoli".to_string(), 3);
+
+        let turns_since = |address: &Address, data: &mut FollowData| {
+            FunctionCall {
+                function: Function::TurnsSince,
+                arguments: vec![Expression {
+                    head: Operand::Variable(Variable::Divert(address.clone())),
+                    tail: Vec::new(),
+                }],
+            }
+            .evaluate(data)
+            .unwrap()
+        };
+
+        assert_eq!(turns_since(&address, &mut data), Variable::Int(2));
+
+        let unvisited = Address::from_parts_unchecked("cairo", None);
+        assert_eq!(turns_since(&unvisited, &mut data), Variable::Int(-1));
+
+        let turns = FunctionCall {
+            function: Function::Turns,
+            arguments: Vec::new(),
+        };
+
+        assert_eq!(turns.evaluate(&mut data).unwrap(), Variable::Int(5));
+    }
+
+    #[test]
+    fn numeric_functions_keep_integers_as_integers() {
+        let int = |function, value| call(function, vec![Variable::Int(value)]).unwrap();
+
+        assert_eq!(int(Function::Abs, -3), Variable::Int(3));
+        assert_eq!(int(Function::Ceiling, 3), Variable::Int(3));
+        assert_eq!(int(Function::Floor, -3), Variable::Int(-3));
+        assert_eq!(int(Function::Int, 3), Variable::Int(3));
+        assert_eq!(int(Function::Float, 3), Variable::Float(3.0));
+
+        assert_eq!(
+            call(Function::Pow, vec![Variable::Int(2), Variable::Int(10)]).unwrap(),
+            Variable::Int(1024)
+        );
+    }
+
+    #[test]
+    fn numeric_functions_round_and_convert_floats() {
+        let float = |function, value| call(function, vec![Variable::Float(value)]).unwrap();
+
+        assert_eq!(float(Function::Abs, -2.5), Variable::Float(2.5));
+        assert_eq!(float(Function::Ceiling, 2.5), Variable::Float(3.0));
+        assert_eq!(float(Function::Floor, -2.5), Variable::Float(-3.0));
+        assert_eq!(float(Function::Int, -2.5), Variable::Int(-2));
+        assert_eq!(float(Function::Float, 2.5), Variable::Float(2.5));
+
+        assert_eq!(
+            call(
+                Function::Pow,
+                vec![Variable::Float(4.0), Variable::Float(0.5)]
+            )
+            .unwrap(),
+            Variable::Float(2.0)
+        );
+        assert_eq!(
+            call(Function::Pow, vec![Variable::Int(2), Variable::Float(-1.0)]).unwrap(),
+            Variable::Float(0.5)
+        );
+    }
+
+    #[test]
+    fn min_and_max_of_integers_are_integers_and_of_mixed_numbers_are_floats() {
+        let ints = vec![Variable::Int(2), Variable::Int(-3)];
+        let mixed = vec![Variable::Int(2), Variable::Float(2.5)];
+
+        assert_eq!(call(Function::Max, ints.clone()).unwrap(), Variable::Int(2));
+        assert_eq!(call(Function::Min, ints).unwrap(), Variable::Int(-3));
+        assert_eq!(
+            call(Function::Max, mixed.clone()).unwrap(),
+            Variable::Float(2.5)
+        );
+        assert_eq!(call(Function::Min, mixed).unwrap(), Variable::Float(2.0));
+    }
+
+    #[test]
+    fn numeric_functions_yield_errors_for_other_types() {
+        assert!(call(Function::Abs, vec![Variable::from("five")]).is_err());
+        assert!(call(
+            Function::Max,
+            vec![Variable::Int(2), Variable::from("five")]
+        )
+        .is_err());
+        assert!(call(Function::Int, vec![Variable::Bool(true)]).is_err());
+        assert!(call(Function::Pow, vec![Variable::Int(2), list(&[])]).is_err());
+    }
+
+    #[test]
+    fn abs_and_pow_of_integers_yield_errors_if_the_result_overflows() {
+        for arguments in [
+            vec![Variable::Int(i32::MIN)],
+            vec![Variable::Int(2), Variable::Int(31)],
+            vec![Variable::Int(-10), Variable::Int(13)],
+        ] {
+            let function = if arguments.len() == 1 {
+                Function::Abs
+            } else {
+                Function::Pow
+            };
+
+            match call(function.clone(), arguments.clone()) {
+                Err(InklingError::VariableError(VariableError {
+                    variable,
+                    kind: VariableErrorKind::IntegerOverflow { function: name },
+                })) => {
+                    assert_eq!(name, function.name());
+                    assert_eq!(variable, arguments[0]);
+                }
+                other => panic!(
+                    "expected `VariableErrorKind::IntegerOverflow` but got {:?}",
+                    other
+                ),
+            }
+        }
+
+        assert_eq!(
+            call(Function::Abs, vec![Variable::Int(i32::MIN + 1)]).unwrap(),
+            Variable::Int(i32::MAX)
+        );
+        assert_eq!(
+            call(Function::Pow, vec![Variable::Int(-2), Variable::Int(31)]).unwrap(),
+            Variable::Int(i32::MIN)
+        );
+        assert_eq!(
+            call(Function::Pow, vec![Variable::Int(2), Variable::Int(-1)]).unwrap(),
+            Variable::Int(0)
+        );
+    }
+
+    #[test]
+    fn random_numbers_are_drawn_from_the_inclusive_range_between_the_arguments() {
+        for _ in 0..20 {
+            match call(Function::Random, vec![Variable::Int(1), Variable::Int(3)]).unwrap() {
+                Variable::Int(value) => assert!((1..=3).contains(&value)),
+                other => panic!("expected `Variable::Int` but got {:?}", other),
+            }
+        }
+
+        assert_eq!(
+            call(Function::Random, vec![Variable::Int(2), Variable::Int(2)]).unwrap(),
+            Variable::Int(2)
+        );
+    }
+
+    #[test]
+    fn random_numbers_repeat_after_seeding_the_generator_with_the_same_seed() {
+        let mut data = FollowDataBuilder::new().build();
+
+        let mut call_with = |function: Function, arguments: &[i32]| {
+            FunctionCall {
+                function,
+                arguments: arguments
+                    .iter()
+                    .map(|value| Expression {
+                        head: Operand::Variable(Variable::Int(*value)),
+                        tail: Vec::new(),
+                    })
+                    .collect(),
+            }
+            .evaluate(&mut data)
+            .unwrap()
+        };
+
+        assert_eq!(
+            call_with(Function::SeedRandom, &[12]),
+            Variable::String(String::new())
+        );
+        let first = (0..10)
+            .map(|_| call_with(Function::Random, &[1, 100]))
+            .collect::<Vec<_>>();
+
+        call_with(Function::SeedRandom, &[12]);
+        let second = (0..10)
+            .map(|_| call_with(Function::Random, &[1, 100]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(first, second);
+        assert!(data.rng.is_seeded());
+    }
+
+    #[test]
+    fn random_yields_errors_for_other_types_or_inverted_bounds() {
+        assert!(call(
+            Function::Random,
+            vec![Variable::Float(1.0), Variable::Int(3)]
+        )
+        .is_err());
+        assert!(call(Function::SeedRandom, vec![Variable::from("seed")]).is_err());
+
+        match call(Function::Random, vec![Variable::Int(3), Variable::Int(1)]) {
+            Err(InklingError::VariableError(VariableError {
+                variable,
+                kind: VariableErrorKind::InvalidBounds { function, max },
+            })) => {
+                assert_eq!(&function, "RANDOM");
+                assert_eq!(variable, Variable::Int(3));
+                assert_eq!(max, 1);
+            }
+            other => panic!(
+                "expected `VariableErrorKind::InvalidBounds` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn list_functions_yield_errors_for_other_types() {
+        match call(Function::ListCount, vec![Variable::Int(5)]) {
+            Err(InklingError::VariableError(VariableError {
+                kind: VariableErrorKind::InvalidArgument { function },
+                ..
+            })) => assert_eq!(&function, "LIST_COUNT"),
+            other => panic!(
+                "expected `VariableErrorKind::InvalidArgument` but got {:?}",
+                other
+            ),
+        }
+
+        assert!(call(
+            Function::ListRange,
+            vec![list(&[]), Variable::from("low"), Variable::Int(2)]
+        )
+        .is_err());
+    }
+}
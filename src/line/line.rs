@@ -1,7 +1,10 @@
 //! Structures for representing a single, whole line of `Ink` content.
 
 use crate::{
-    error::{parse::validate::ValidationError, utils::MetaData},
+    error::{
+        parse::{address::InvalidAddressError, validate::ValidationError},
+        utils::MetaData,
+    },
     knot::Address,
     line::{Alternative, Condition, Expression},
     log::Logger,
@@ -23,6 +26,12 @@ pub struct InternalLine {
     /// Tags associated with the line. Will be given to the user along with the processed
     /// line content as the story is followed.
     pub tags: Vec<String>,
+    /// Tags which contain expressions, conditions or alternatives, along with their index
+    /// in `tags`.
+    ///
+    /// These are processed when the line is and replace the tag as it was written.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub dynamic_tags: Vec<(usize, LineChunk)>,
     /// Whether or not the line is glued to the previous line. Glue prohibits new lines
     /// to be added between lines, which is otherwise the default behavior when following
     /// the story.
@@ -66,8 +75,8 @@ pub struct LineChunk {
 pub enum Content {
     /// Content that alternates every time it is visited in the story.
     Alternative(Alternative),
-    /// Divert to a new node in the story.
-    Divert(Address),
+    /// Divert to a new node in the story, with arguments for the parameters of its knot.
+    Divert(Address, Vec<Expression>),
     /// Null content.
     Empty,
     /// Expression to evaluate.
@@ -75,7 +84,7 @@ pub enum Content {
     /// Nested `LineChunk` to evaluate.
     Nested(LineChunk),
     /// String of regular text content in the line.
-    Text(String),
+    Text(#[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings"))] String),
 }
 
 impl InternalLine {
@@ -86,6 +95,7 @@ impl InternalLine {
         InternalLine {
             chunk,
             tags: Vec::new(),
+            dynamic_tags: Vec::new(),
             glue_begin: false,
             glue_end: false,
             meta_data: MetaData::from(0),
@@ -110,6 +120,30 @@ impl InternalLine {
         buffer
     }
 
+    /// Get the processed text of the line if it does not depend on the story state.
+    ///
+    /// Lines with conditions, alternatives, expressions or nested content can change as
+    /// the story is followed and yield `None`. The text is trimmed, just like it is when
+    /// presented to the user.
+    pub fn get_static_text(&self) -> Option<String> {
+        if self.chunk.condition.is_some() {
+            return None;
+        }
+
+        let mut buffer = String::new();
+
+        for item in &self.chunk.items {
+            match item {
+                Content::Text(string) => buffer.push_str(string),
+                Content::Empty => buffer.push(' '),
+                Content::Divert(..) => (),
+                _ => return None,
+            }
+        }
+
+        Some(buffer.trim().to_string())
+    }
+
     #[cfg(test)]
     pub fn from_string(line: &str) -> Self {
         use builders::LineChunkBuilder;
@@ -119,6 +153,33 @@ impl InternalLine {
     }
 }
 
+impl LineChunk {
+    /// Count the number of conditions in the chunk, including those in nested content.
+    pub fn count_conditions(&self) -> usize {
+        let num_own = if self.condition.is_some() { 1 } else { 0 };
+
+        num_own
+            + count_conditions_in_content(&self.items)
+            + count_conditions_in_content(&self.else_items)
+    }
+}
+
+/// Count the number of conditions in a set of content, including those in nested content.
+pub fn count_conditions_in_content(items: &[Content]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            Content::Alternative(alternative) => alternative
+                .items
+                .iter()
+                .map(|chunk| chunk.count_conditions())
+                .sum(),
+            Content::Nested(chunk) => chunk.count_conditions(),
+            _ => 0,
+        })
+        .sum()
+}
+
 impl ValidateContent for InternalLine {
     fn validate(
         &mut self,
@@ -130,6 +191,10 @@ impl ValidateContent for InternalLine {
     ) {
         self.chunk
             .validate(error, log, current_location, &self.meta_data, data);
+
+        self.dynamic_tags.iter_mut().for_each(|(_, chunk)| {
+            chunk.validate(error, log, current_location, &self.meta_data, data)
+        });
     }
 }
 
@@ -166,12 +231,27 @@ impl ValidateContent for Content {
             Content::Alternative(alternative) => {
                 alternative.validate(error, log, current_location, meta_data, data)
             }
-            Content::Divert(address) => {
-                address.validate(error, log, current_location, meta_data, data)
+            Content::Divert(address, arguments) => {
+                let num_errors = error.num_errors();
+
+                address.validate(error, log, current_location, meta_data, data);
+
+                arguments.iter_mut().for_each(|argument| {
+                    argument.validate(error, log, current_location, meta_data, data)
+                });
+
+                if num_errors == error.num_errors() {
+                    if let Err(kind) = address.validate_divert_arguments(arguments.len(), data) {
+                        error.invalid_address_errors.push(InvalidAddressError {
+                            kind,
+                            meta_data: meta_data.clone(),
+                        });
+                    }
+                }
             }
             Content::Empty | Content::Text(..) => (),
             Content::Expression(expression) => {
-                expression.validate(error, log, current_location, meta_data, data)
+                expression.validate_in_text(error, log, current_location, meta_data, data)
             }
             Content::Nested(chunk) => chunk.validate(error, log, current_location, meta_data, data),
         }
@@ -208,9 +288,10 @@ pub mod builders {
             InternalLine {
                 chunk: self.chunk,
                 tags: self.tags,
+                dynamic_tags: Vec::new(),
                 glue_begin: self.glue_begin,
                 glue_end: self.glue_end,
-                meta_data: MetaData { line_index: 0 },
+                meta_data: MetaData::from(0),
             }
         }
     }
@@ -247,7 +328,10 @@ pub mod builders {
         }
 
         pub fn with_divert(self, address: &str) -> Self {
-            self.with_item(Content::Divert(Address::Raw(address.to_string())))
+            self.with_item(Content::Divert(
+                Address::Raw(address.to_string()),
+                Vec::new(),
+            ))
         }
 
         pub fn with_item(mut self, item: Content) -> Self {
@@ -0,0 +1,224 @@
+//! Lists of named items, declared with `LIST` lines in a story.
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{cmp::Ordering, collections::BTreeSet, fmt, iter::FromIterator};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Item in a [`List`], which belongs to the list definition that declared it.
+///
+/// Items are ordered by their value, then by the names of their list and themselves.
+pub struct ListItem {
+    /// Name of the list definition that the item was declared in.
+    pub list: String,
+    /// Name of the item.
+    pub name: String,
+    /// Value of the item in its list definition.
+    ///
+    /// Items are numbered from 1 in the order that they are declared, unless they
+    /// are given values in the declaration.
+    pub value: i32,
+}
+
+impl ListItem {
+    /// Create an item in a list definition.
+    pub fn new(list: &str, name: &str, value: i32) -> Self {
+        ListItem {
+            list: list.to_string(),
+            name: name.to_string(),
+            value,
+        }
+    }
+}
+
+impl Ord for ListItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value
+            .cmp(&other.value)
+            .then_with(|| self.list.cmp(&other.list))
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
+impl PartialOrd for ListItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Set of items from one or more list definitions.
+///
+/// Lists are declared in a story with `LIST` lines, which name all items of the list
+/// and mark the items that the variable starts with in parenthesis:
+///
+/// ```text
+/// LIST inventory = sword, (torch), key
+/// ```
+///
+/// Items can be added to and removed from lists with `+` and `-`. Lists print as
+/// the names of their items, separated by commas and ordered by their values.
+///
+/// Lists also remember the names of the list definitions that their items come from,
+/// even after the items have been removed. Two lists are equal if they have the same items.
+///
+/// # Examples
+/// ```
+/// # use inkling::{List, ListItem};
+/// let sword = ListItem::new("inventory", "sword", 1);
+/// let torch = ListItem::new("inventory", "torch", 2);
+///
+/// let list: List = vec![torch.clone(), sword.clone()].into_iter().collect();
+///
+/// assert!(list.contains(&sword));
+/// assert_eq!(&list.to_string(), "sword, torch");
+/// ```
+pub struct List {
+    items: BTreeSet<ListItem>,
+    /// Names of the list definitions that the items come from.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    origins: BTreeSet<String>,
+}
+
+impl List {
+    /// Get whether the list contains an item.
+    pub fn contains(&self, item: &ListItem) -> bool {
+        self.items.contains(item)
+    }
+
+    /// Get whether the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Get the number of items in the list.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Get the items in the list, ordered by their values.
+    pub fn items(&self) -> impl Iterator<Item = &ListItem> {
+        self.items.iter()
+    }
+
+    /// Get the names of the list definitions that the items of the list come from.
+    pub fn origins(&self) -> impl Iterator<Item = &str> {
+        self.origins.iter().map(|origin| origin.as_str())
+    }
+
+    /// Get a list with the items of both lists.
+    pub fn union(&self, other: &List) -> List {
+        List {
+            items: self.items.union(&other.items).cloned().collect(),
+            origins: self.origins.union(&other.origins).cloned().collect(),
+        }
+    }
+
+    /// Get a list with the items of this list which are not in the other.
+    pub fn difference(&self, other: &List) -> List {
+        List {
+            items: self.items.difference(&other.items).cloned().collect(),
+            origins: self.origins.clone(),
+        }
+    }
+
+    /// Get a list with only the items for which the predicate is true.
+    ///
+    /// The list keeps its origins.
+    pub(crate) fn filter<F: Fn(&ListItem) -> bool>(&self, predicate: F) -> List {
+        List {
+            items: self
+                .items
+                .iter()
+                .filter(|item| predicate(item))
+                .cloned()
+                .collect(),
+            origins: self.origins.clone(),
+        }
+    }
+
+    /// Add a list definition to the origins of the list.
+    pub(crate) fn with_origin(mut self, origin: &str) -> Self {
+        self.origins.insert(origin.to_string());
+        self
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl From<ListItem> for List {
+    fn from(item: ListItem) -> Self {
+        std::iter::once(item).collect()
+    }
+}
+
+impl FromIterator<ListItem> for List {
+    fn from_iter<I: IntoIterator<Item = ListItem>>(iter: I) -> Self {
+        let items = iter.into_iter().collect::<BTreeSet<_>>();
+        let origins = items.iter().map(|item| item.list.clone()).collect();
+
+        List { items, origins }
+    }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self
+            .items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_are_ordered_by_value_before_names() {
+        let list: List = vec![
+            ListItem::new("inventory", "sword", 3),
+            ListItem::new("inventory", "torch", 1),
+            ListItem::new("bag", "apple", 3),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(&list.to_string(), "torch, apple, sword");
+    }
+
+    #[test]
+    fn lists_have_set_semantics() {
+        let sword = ListItem::new("inventory", "sword", 1);
+        let torch = ListItem::new("inventory", "torch", 2);
+
+        let list = List::from(sword.clone());
+        let both = list.union(&List::from(torch.clone())).union(&list);
+
+        assert_eq!(both.len(), 2);
+        assert_eq!(both.difference(&list), List::from(torch.clone()));
+        assert!(list.difference(&list).is_empty());
+    }
+
+    #[test]
+    fn lists_remember_their_origins_after_items_are_removed() {
+        let sword = List::from(ListItem::new("inventory", "sword", 1));
+        let calm = List::from(ListItem::new("moods", "calm", 1));
+
+        let empty = sword.union(&calm).difference(&sword).difference(&calm);
+
+        assert!(empty.is_empty());
+        assert_eq!(empty.origins().collect::<Vec<_>>(), &["inventory", "moods"]);
+        assert_eq!(empty, List::default());
+    }
+}
@@ -17,21 +17,41 @@
 //! is made and can have conditions for when they are presented at all.
 
 mod alternative;
+mod assignment;
 mod choice;
 pub(crate) mod condition;
+mod directive;
 pub mod expression;
+mod format;
+mod function;
 pub(crate) mod line;
+mod list;
 pub(crate) mod parse;
+mod thread;
 mod variable;
 
-pub(crate) use alternative::{Alternative, AlternativeBuilder, AlternativeKind};
+pub(crate) use alternative::{
+    get_next_alternative_index, Alternative, AlternativeBuilder, AlternativeKind,
+};
+pub(crate) use assignment::{Assignment, LogicCall, Return};
 pub(crate) use choice::{InternalChoice, InternalChoiceBuilder};
 pub(crate) use condition::{
     Condition, ConditionBuilder, ConditionItem, ConditionKind, StoryCondition,
 };
-pub(crate) use expression::{evaluate_expression, Expression};
+pub use directive::{Directive, DirectiveParser, DirectiveSet};
+pub(crate) use expression::{evaluate_expression, follow_expression, Expression};
+pub(crate) use format::{
+    format_condition, format_expression, format_operand, format_operator, format_variable_for_debug,
+};
+pub(crate) use function::{Function, FunctionCall};
 #[cfg(test)]
 pub(crate) use line::builders::LineChunkBuilder;
-pub(crate) use line::{Content, InternalLine, LineChunk};
-pub(crate) use parse::{parse_line, parse_variable, ParsedLineKind};
+pub(crate) use line::{count_conditions_in_content, Content, InternalLine, LineChunk};
+pub use list::{List, ListItem};
+#[cfg(test)]
+pub(crate) use parse::parse_line;
+pub(crate) use parse::{
+    is_escaped, is_return_line, parse_lines_with_blocks, parse_variable, ParsedLineKind,
+};
+pub(crate) use thread::Thread;
 pub use variable::Variable;
@@ -0,0 +1,348 @@
+//! Parse logic lines as marked up `ParsedLineKind::Assignment`, `ParsedLineKind::Call` and
+//! `ParsedLineKind::Return` objects.
+
+use crate::{
+    consts::{ASSIGNMENT_MARKER, RETURN_MARKER, TEMPORARY_VARIABLE_MARKER},
+    error::{parse::line::LineErrorKind, utils::MetaData},
+    knot::Address,
+    line::{
+        expression::{Operand, Operator},
+        parse::parse_expression,
+        Assignment, Expression, FunctionCall, LogicCall, ParsedLineKind, Return,
+    },
+};
+
+#[cfg(not(feature = "math"))]
+use crate::error::parse::expression::{ExpressionError, ExpressionErrorKind};
+
+/// Parse a `ParsedLineKind::Assignment` from a line if it is a logic line.
+///
+/// Logic lines begin with the [assignment marker][crate::consts::ASSIGNMENT_MARKER]
+/// and either declare a temporary variable (`~ temp name = value`) or assign to
+/// an existing variable (`~ name = value`). Existing variables can also be assigned
+/// with the compound operators `+=` and `-=`.
+///
+/// Logic lines which begin with the [return marker][crate::consts::RETURN_MARKER]
+/// instead return from a function, with the value of an expression if one is given
+/// (`~ return value`). Logic lines which only call a function (`~ name(arguments)`)
+/// are parsed as calls.
+pub fn parse_assignment(
+    content: &str,
+    meta_data: &MetaData,
+) -> Result<Option<ParsedLineKind>, LineErrorKind> {
+    let logic = match get_logic(content) {
+        Some(logic) => logic,
+        None => return Ok(None),
+    };
+
+    if let Some(value) = get_return_value(logic) {
+        let value = Some(value)
+            .filter(|value| !value.is_empty())
+            .map(parse_expression)
+            .transpose()?;
+
+        return Ok(Some(ParsedLineKind::Return(Return {
+            value,
+            meta_data: meta_data.clone(),
+        })));
+    }
+
+    if let Some(call) = get_function_call(logic) {
+        return Ok(Some(ParsedLineKind::Call(LogicCall {
+            call,
+            meta_data: meta_data.clone(),
+        })));
+    }
+
+    let declaration = logic
+        .strip_prefix(TEMPORARY_VARIABLE_MARKER)
+        .filter(|tail| tail.starts_with(char::is_whitespace));
+
+    let is_declaration = declaration.is_some();
+
+    let (name, value) = declaration
+        .unwrap_or(logic)
+        .split_once('=')
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .ok_or(LineErrorKind::InvalidLogicLine)?;
+
+    let (name, operator) = match name.char_indices().last() {
+        Some((i, '+')) => (name[..i].trim_end(), Some(Operator::Add)),
+        Some((i, '-')) => (name[..i].trim_end(), Some(Operator::Subtract)),
+        _ => (name, None),
+    };
+
+    if is_declaration && operator.is_some() {
+        return Err(LineErrorKind::InvalidLogicLine);
+    }
+
+    if name.is_empty() || name.contains(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        return Err(LineErrorKind::InvalidAddress {
+            address: name.to_string(),
+        });
+    }
+
+    let value = match operator {
+        Some(operator) => parse_compound_value(name, operator, value)?,
+        None => parse_expression(value)?,
+    };
+
+    Ok(Some(ParsedLineKind::Assignment(Assignment {
+        target: Address::Raw(name.to_string()),
+        is_declaration,
+        operator,
+        value,
+        meta_data: meta_data.clone(),
+    })))
+}
+
+/// Get whether a line is a logic line which returns from a function.
+pub fn is_return_line(content: &str) -> bool {
+    get_logic(content).and_then(get_return_value).is_some()
+}
+
+/// Get the trimmed content of a logic line after its marker, if it is one.
+fn get_logic(content: &str) -> Option<&str> {
+    content
+        .trim()
+        .strip_prefix(ASSIGNMENT_MARKER)
+        .map(|logic| logic.trim())
+}
+
+/// Get the trimmed value of a logic line which returns from a function, if it is one.
+fn get_return_value(logic: &str) -> Option<&str> {
+    logic
+        .strip_prefix(RETURN_MARKER)
+        .filter(|tail| tail.is_empty() || tail.starts_with(char::is_whitespace))
+        .map(|value| value.trim())
+}
+
+/// Get the call of a logic line which only calls a function, if it is one.
+///
+/// The line has to begin with the name of the function and end with the parenthesis
+/// around its arguments.
+fn get_function_call(logic: &str) -> Option<FunctionCall> {
+    let name = logic.split('(').next().unwrap_or_default().trim_end();
+
+    if name.is_empty()
+        || name.contains(|c: char| !(c.is_alphanumeric() || c == '_'))
+        || name == TEMPORARY_VARIABLE_MARKER
+        || !logic.ends_with(')')
+    {
+        return None;
+    }
+
+    match parse_expression(logic) {
+        Ok(Expression {
+            head: Operand::Call(call),
+            tail,
+        }) if tail.is_empty() => Some(call),
+        _ => None,
+    }
+}
+
+/// Parse the value of a compound assignment as the operation on the variable itself.
+///
+/// The given value is grouped as a single term, so that `~ gold -= cost + 1` is
+/// evaluated as `gold - (cost + 1)`.
+#[cfg(feature = "math")]
+fn parse_compound_value(
+    name: &str,
+    operator: Operator,
+    value: &str,
+) -> Result<Expression, LineErrorKind> {
+    let operand = parse_expression(value)?;
+
+    let mut expression = parse_expression(name)?;
+    expression
+        .tail
+        .push((operator, Operand::Nested(Box::new(operand))));
+
+    Ok(expression)
+}
+
+/// Return an error for compound assignments, since the `math` feature is disabled.
+#[cfg(not(feature = "math"))]
+fn parse_compound_value(_: &str, _: Operator, value: &str) -> Result<Expression, LineErrorKind> {
+    Err(LineErrorKind::ExpressionError(ExpressionError {
+        content: value.to_string(),
+        kind: ExpressionErrorKind::MathDisabled,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::line::{expression::Operand, function::Function, parse_line, Variable};
+
+    fn parse(content: &str) -> Result<Option<Assignment>, LineErrorKind> {
+        parse_assignment(content, &().into()).map(|parsed| match parsed {
+            Some(ParsedLineKind::Assignment(assignment)) => Some(assignment),
+            None => None,
+            Some(other) => panic!("expected `ParsedLineKind::Assignment` but got {:?}", other),
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn temporary_declarations_are_parsed_with_their_name_and_value() {
+        let assignment = parse("~ temp damage = strength + 2").unwrap().unwrap();
+
+        assert_eq!(assignment.target, Address::Raw("damage".to_string()));
+        assert!(assignment.is_declaration);
+        assert_eq!(
+            assignment.value.head,
+            Operand::Variable(Variable::Address(Address::Raw("strength".to_string())))
+        );
+        assert_eq!(assignment.value.tail.len(), 1);
+
+        let assignment = parse("  ~temp   name=\"Anna\"  ").unwrap().unwrap();
+
+        assert_eq!(assignment.target, Address::Raw("name".to_string()));
+        assert_eq!(
+            assignment.value.head,
+            Operand::Variable(Variable::from("Anna"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn assignments_to_existing_variables_are_not_declarations() {
+        let assignment = parse("~ x = x + 1").unwrap().unwrap();
+
+        assert_eq!(assignment.target, Address::Raw("x".to_string()));
+        assert!(!assignment.is_declaration);
+        assert_eq!(assignment.value.tail.len(), 1);
+
+        let assignment = parse("~ temperature = 5").unwrap().unwrap();
+
+        assert_eq!(assignment.target, Address::Raw("temperature".to_string()));
+        assert!(!assignment.is_declaration);
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn compound_assignments_operate_on_the_variable_with_the_grouped_value() {
+        let assignment = parse("~ gold += 5").unwrap().unwrap();
+
+        assert_eq!(assignment.target, Address::Raw("gold".to_string()));
+        assert_eq!(assignment.operator, Some(Operator::Add));
+        assert_eq!(
+            assignment.value.head,
+            Operand::Variable(Variable::Address(Address::Raw("gold".to_string())))
+        );
+        assert_eq!(assignment.value.tail.len(), 1);
+
+        let assignment = parse("~ gold -= cost + 1").unwrap().unwrap();
+
+        assert_eq!(assignment.operator, Some(Operator::Subtract));
+
+        match &assignment.value.tail[..] {
+            [(Operator::Subtract, Operand::Nested(value))] => assert_eq!(value.tail.len(), 1),
+            other => panic!("expected a single nested subtraction but got {:?}", other),
+        }
+
+        assert_eq!(parse("~ gold = 5").unwrap().unwrap().operator, None);
+    }
+
+    #[test]
+    fn compound_assignments_cannot_declare_temporary_variables() {
+        match parse("~ temp gold += 5") {
+            Err(LineErrorKind::InvalidLogicLine) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidLogicLine` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn lines_without_assignment_marker_are_not_logic_lines() {
+        assert!(parse("The temp worker arrived.").unwrap().is_none());
+        assert!(parse("{~once|twice}").unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_logic_lines_yield_errors() {
+        match parse("~ damage") {
+            Err(LineErrorKind::InvalidLogicLine) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidLogicLine` but got {:?}",
+                other
+            ),
+        }
+
+        match parse("~ temp damage") {
+            Err(LineErrorKind::InvalidLogicLine) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidLogicLine` but got {:?}",
+                other
+            ),
+        }
+
+        match parse("~ temp max damage = 5") {
+            Err(LineErrorKind::InvalidAddress { address }) => assert_eq!(&address, "max damage"),
+            other => panic!(
+                "expected `LineErrorKind::InvalidAddress` but got {:?}",
+                other
+            ),
+        }
+
+        match parse("~ temp damage = ") {
+            Err(LineErrorKind::ExpressionError(..)) => (),
+            other => panic!(
+                "expected `LineErrorKind::ExpressionError` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn return_lines_are_parsed_with_their_optional_value() {
+        match parse_assignment("~ return a + b", &().into()).unwrap() {
+            Some(ParsedLineKind::Return(Return {
+                value: Some(value), ..
+            })) => assert_eq!(value.tail.len(), 1),
+            other => panic!("expected `ParsedLineKind::Return` but got {:?}", other),
+        }
+
+        match parse_assignment("~return", &().into()).unwrap() {
+            Some(ParsedLineKind::Return(Return { value: None, .. })) => (),
+            other => panic!("expected `ParsedLineKind::Return` but got {:?}", other),
+        }
+
+        assert!(is_return_line("  ~ return 5"));
+        assert!(!is_return_line("~ returned = 5"));
+        assert!(!is_return_line("return 5"));
+        assert_eq!(
+            parse("~ returned = 5").unwrap().unwrap().value.tail.len(),
+            0
+        );
+    }
+
+    #[test]
+    fn logic_lines_with_a_lone_function_call_are_parsed_as_calls() {
+        match parse_assignment("~ reward(coins, 2)", &().into()).unwrap() {
+            Some(ParsedLineKind::Call(LogicCall { call, .. })) => {
+                assert_eq!(call.function, Function::Story("reward".to_string()));
+                assert_eq!(call.arguments.len(), 2);
+            }
+            other => panic!("expected `ParsedLineKind::Call` but got {:?}", other),
+        }
+
+        assert!(get_function_call("reward() + 1").is_none());
+        assert!(get_function_call("temp(x)").is_none());
+        assert!(get_function_call("(x)").is_none());
+        assert!(parse("~ x = reward()").unwrap().is_some());
+    }
+
+    #[test]
+    fn logic_lines_are_parsed_before_other_kinds_of_lines() {
+        match parse_line("~ temp level = 1", &().into()).unwrap() {
+            ParsedLineKind::Assignment(..) => (),
+            other => panic!("expected `ParsedLineKind::Assignment` but got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,729 @@
+//! Parse multi-line blocks of content.
+//!
+//! Blocks group several lines under a single item of the node tree. Blocks of alternatives
+//! are opened with a line of a brace and a keyword, followed by items which each begin with
+//! a gather marker and may span several lines, and closed with a line of a single brace:
+//!
+//! ```text
+//! { shuffle:
+//!     -   The rain kept falling.
+//!     -   Thunder rolled in the distance.
+//!         The windows rattled.
+//! }
+//! ```
+//!
+//! Conditional blocks are opened with a condition instead of a keyword. Their content
+//! is regular lines, which may include choices and gathers, and may be split into
+//! a branch which is used if the condition is not fulfilled:
+//!
+//! ```text
+//! { has_lantern:
+//!     The lantern lit up the cave.
+//!     *   [Go deeper] -> depths
+//!     *   [Turn back] -> entrance
+//! - else:
+//!     It was too dark to go on. -> entrance
+//! }
+//! ```
+//!
+//! Blocks which are opened with a single brace are split into branches which each begin
+//! with a condition. If the block is opened with a value, the branches begin with values
+//! which it is compared to. The first branch whose condition is fulfilled is used:
+//!
+//! ```text
+//! {
+//!     - coins > 10: You were rich.
+//!     - coins > 0: You had a few coins.
+//!     - else: You were broke.
+//! }
+//!
+//! { num_guests:
+//!     - 1: You dined alone.
+//!     - 2: You dined with a friend.
+//! }
+//! ```
+//!
+//! Gather markers always begin branches in these blocks, so they cannot contain gathers.
+
+use crate::{
+    consts::{
+        BLOCK_END_MARKER, BLOCK_START_MARKER, CYCLE_BLOCK_KEYWORD, DIVERT_MARKER,
+        ELSE_BLOCK_KEYWORD, GATHER_MARKER, ONCE_ONLY_BLOCK_KEYWORD, SEQUENCE_BLOCK_KEYWORD,
+        SHUFFLE_BLOCK_KEYWORD,
+    },
+    error::{
+        parse::line::{LineError, LineErrorKind},
+        utils::MetaData,
+    },
+    line::{
+        parse::{parse_condition, parse_line_with_directives},
+        AlternativeKind, DirectiveSet, ParsedLineKind,
+    },
+};
+
+/// Parse a set of lines, grouping the lines of multi-line blocks into single items.
+///
+/// Returns the parsed lines along with all errors that were found.
+pub fn parse_lines_with_blocks(
+    lines: &[(&str, MetaData)],
+    directives: &DirectiveSet,
+) -> (Vec<ParsedLineKind>, Vec<LineError>) {
+    let mut parsed_lines = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut index = 0;
+
+    while index < lines.len() {
+        let (line, meta_data) = &lines[index];
+        index += 1;
+
+        if let Some(parsed_line) =
+            parse_line_or_block(line, meta_data, lines, &mut index, directives, &mut errors)
+        {
+            parsed_lines.push(parsed_line);
+        }
+    }
+
+    (parsed_lines, errors)
+}
+
+/// Parse a line, or the entire block if the line opens one.
+///
+/// When the function returns the `index` will point to the line directly after the block.
+fn parse_line_or_block(
+    line: &str,
+    meta_data: &MetaData,
+    lines: &[(&str, MetaData)],
+    index: &mut usize,
+    directives: &DirectiveSet,
+    errors: &mut Vec<LineError>,
+) -> Option<ParsedLineKind> {
+    if let Some(kind) = get_alternative_block_kind(line) {
+        parse_alternative_block(kind, line, meta_data, lines, index, directives, errors)
+    } else if let Some(condition) = get_block_condition(line) {
+        parse_conditional_block(
+            Some(condition),
+            line,
+            meta_data,
+            lines,
+            index,
+            directives,
+            errors,
+        )
+    } else if line.trim() == BLOCK_START_MARKER {
+        parse_conditional_block(None, line, meta_data, lines, index, directives, errors)
+    } else {
+        parse_line_with_directives(line, meta_data, directives)
+            .map_err(|err| errors.push(*err))
+            .ok()
+    }
+}
+
+/// Parse the items of an alternative block until the line which closes it.
+///
+/// Errors in the lines of items are added to the set of errors. Returns `None` if the
+/// block is not closed before the lines run out.
+fn parse_alternative_block(
+    kind: AlternativeKind,
+    head: &str,
+    meta_data: &MetaData,
+    lines: &[(&str, MetaData)],
+    index: &mut usize,
+    directives: &DirectiveSet,
+    errors: &mut Vec<LineError>,
+) -> Option<ParsedLineKind> {
+    let mut items: Vec<Vec<ParsedLineKind>> = Vec::new();
+
+    loop {
+        let (line, line_meta_data) = match lines.get(*index) {
+            Some(line) => line,
+            None => {
+                errors.push(LineError {
+                    line: head.to_string(),
+                    kind: LineErrorKind::UnterminatedBlock,
+                    meta_data: meta_data.clone(),
+                });
+
+                return None;
+            }
+        };
+
+        *index += 1;
+
+        let trimmed = line.trim();
+
+        if trimmed == BLOCK_END_MARKER {
+            break;
+        }
+
+        let content = match trimmed.strip_prefix(GATHER_MARKER) {
+            Some(content) if !trimmed.starts_with(DIVERT_MARKER) => {
+                items.push(Vec::new());
+                content.trim()
+            }
+            _ => trimmed,
+        };
+
+        if content.is_empty() {
+            continue;
+        }
+
+        let item = match items.last_mut() {
+            Some(item) => item,
+            None => {
+                errors.push(LineError {
+                    line: line.to_string(),
+                    kind: LineErrorKind::ExpectedBlockItem,
+                    meta_data: line_meta_data.clone(),
+                });
+
+                continue;
+            }
+        };
+
+        match parse_line_or_block(content, line_meta_data, lines, index, directives, errors) {
+            Some(parsed_line) if has_branching(&parsed_line) => {
+                errors.push(LineError {
+                    line: line.to_string(),
+                    kind: LineErrorKind::BranchingInBlock,
+                    meta_data: line_meta_data.clone(),
+                });
+            }
+            Some(parsed_line) => item.push(parsed_line),
+            None => (),
+        }
+    }
+
+    Some(ParsedLineKind::AlternativeBlock {
+        kind,
+        items,
+        meta_data: meta_data.clone(),
+    })
+}
+
+/// Parse the branches of a conditional block until the line which closes it.
+///
+/// Blocks which are opened with a condition are split into a branch for it and
+/// an else branch. Blocks whose first line begins a branch with a condition are instead
+/// split into several branches: if the block was opened with a value the conditions of
+/// the branches are values which it is compared to, otherwise they are conditions of
+/// their own. The branches are nested into blocks of a single condition and else branch,
+/// which are checked in order.
+///
+/// Errors in the lines of the branches or the conditions are added to the set of errors.
+/// Returns `None` if a condition could not be parsed or the block is not closed before
+/// the lines run out.
+fn parse_conditional_block(
+    value: Option<&str>,
+    head: &str,
+    meta_data: &MetaData,
+    lines: &[(&str, MetaData)],
+    index: &mut usize,
+    directives: &DirectiveSet,
+    errors: &mut Vec<LineError>,
+) -> Option<ParsedLineKind> {
+    let mut branches: Vec<(String, &str, &MetaData, Vec<ParsedLineKind>)> = Vec::new();
+    let mut else_items = None;
+
+    let has_branch_conditions = value.is_none()
+        || lines
+            .get(*index)
+            .and_then(|(line, _)| get_branch_condition(line.trim()))
+            .is_some();
+
+    if let (Some(value), false) = (value, has_branch_conditions) {
+        branches.push((value.to_string(), head, meta_data, Vec::new()));
+    }
+
+    loop {
+        let (line, line_meta_data) = match lines.get(*index) {
+            Some(line) => line,
+            None => {
+                errors.push(LineError {
+                    line: head.to_string(),
+                    kind: LineErrorKind::UnterminatedBlock,
+                    meta_data: meta_data.clone(),
+                });
+
+                return None;
+            }
+        };
+
+        *index += 1;
+
+        let mut content = line.trim();
+
+        if content == BLOCK_END_MARKER {
+            break;
+        }
+
+        if let Some(else_content) = get_else_content(content) {
+            if else_items.is_some() {
+                errors.push(LineError {
+                    line: line.to_string(),
+                    kind: LineErrorKind::DuplicateBlockElse,
+                    meta_data: line_meta_data.clone(),
+                });
+            }
+
+            else_items.get_or_insert_with(Vec::new);
+            content = else_content;
+        } else if has_branch_conditions && is_gather(content) {
+            match get_branch_condition(content) {
+                Some(_) if else_items.is_some() => {
+                    errors.push(LineError {
+                        line: line.to_string(),
+                        kind: LineErrorKind::BlockBranchAfterElse,
+                        meta_data: line_meta_data.clone(),
+                    });
+
+                    continue;
+                }
+                Some((condition, branch_content)) => {
+                    let condition = match value {
+                        Some(value) => format!("{} == {}", value, condition),
+                        None => condition.to_string(),
+                    };
+
+                    branches.push((condition, line, line_meta_data, Vec::new()));
+                    content = branch_content;
+                }
+                None => {
+                    errors.push(LineError {
+                        line: line.to_string(),
+                        kind: LineErrorKind::ExpectedBlockCondition,
+                        meta_data: line_meta_data.clone(),
+                    });
+
+                    continue;
+                }
+            }
+        }
+
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Some(parsed_line) =
+            parse_line_or_block(content, line_meta_data, lines, index, directives, errors)
+        {
+            match (else_items.as_mut(), branches.last_mut()) {
+                (Some(else_items), _) => else_items.push(parsed_line),
+                (None, Some((_, _, _, items))) => items.push(parsed_line),
+                (None, None) => errors.push(LineError {
+                    line: line.to_string(),
+                    kind: LineErrorKind::ExpectedBlockCondition,
+                    meta_data: line_meta_data.clone(),
+                }),
+            }
+        }
+    }
+
+    if branches.is_empty() {
+        errors.push(LineError {
+            line: head.to_string(),
+            kind: LineErrorKind::ExpectedBlockCondition,
+            meta_data: meta_data.clone(),
+        });
+
+        return None;
+    }
+
+    let conditions = branches
+        .iter()
+        .map(|(condition, line, meta_data, _)| {
+            parse_condition(condition)
+                .map_err(|err| {
+                    errors.push(LineError {
+                        line: line.to_string(),
+                        kind: err.into(),
+                        meta_data: (*meta_data).clone(),
+                    })
+                })
+                .ok()
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect::<Option<Vec<_>>>()?;
+
+    branches
+        .into_iter()
+        .zip(conditions)
+        .rev()
+        .fold(
+            else_items.unwrap_or_default(),
+            |else_items, ((_, _, meta_data, items), condition)| {
+                vec![ParsedLineKind::ConditionalBlock {
+                    condition,
+                    items,
+                    else_items,
+                    meta_data: meta_data.clone(),
+                }]
+            },
+        )
+        .pop()
+}
+
+/// Assert whether a parsed line is or contains a choice, gather or thread.
+fn has_branching(line: &ParsedLineKind) -> bool {
+    match line {
+        ParsedLineKind::Choice { .. }
+        | ParsedLineKind::Gather { .. }
+        | ParsedLineKind::Thread(..) => true,
+        ParsedLineKind::ConditionalBlock {
+            items, else_items, ..
+        } => items.iter().chain(else_items.iter()).any(has_branching),
+        ParsedLineKind::AlternativeBlock { .. }
+        | ParsedLineKind::Assignment(..)
+        | ParsedLineKind::Call(..)
+        | ParsedLineKind::Return(..)
+        | ParsedLineKind::Directive(..)
+        | ParsedLineKind::Line(..) => false,
+    }
+}
+
+/// Get the kind of alternative block that a line opens, if it opens one.
+fn get_alternative_block_kind(line: &str) -> Option<AlternativeKind> {
+    let keyword = line.trim().strip_prefix('{')?.strip_suffix(':')?.trim();
+
+    match keyword {
+        SEQUENCE_BLOCK_KEYWORD => Some(AlternativeKind::Sequence),
+        CYCLE_BLOCK_KEYWORD => Some(AlternativeKind::Cycle),
+        ONCE_ONLY_BLOCK_KEYWORD => Some(AlternativeKind::OnceOnly),
+        SHUFFLE_BLOCK_KEYWORD => Some(AlternativeKind::Shuffle),
+        _ => None,
+    }
+}
+
+/// Get the condition of a conditional block that a line opens, if it opens one.
+fn get_block_condition(line: &str) -> Option<&str> {
+    let condition = line.trim().strip_prefix('{')?.strip_suffix(':')?.trim();
+
+    if condition.is_empty() || condition.contains(['{', '}']) {
+        None
+    } else {
+        Some(condition)
+    }
+}
+
+/// Get the condition of a branch in a conditional block and the content after it,
+/// if the line begins one.
+fn get_branch_condition(line: &str) -> Option<(&str, &str)> {
+    if !is_gather(line) {
+        return None;
+    }
+
+    let (condition, content) = line.strip_prefix(GATHER_MARKER)?.split_once(':')?;
+    let condition = condition.trim();
+
+    if condition.is_empty() || condition == ELSE_BLOCK_KEYWORD || condition.contains(['{', '}']) {
+        None
+    } else {
+        Some((condition, content.trim()))
+    }
+}
+
+/// Assert whether a line begins with a gather marker.
+fn is_gather(line: &str) -> bool {
+    line.starts_with(GATHER_MARKER) && !line.starts_with(DIVERT_MARKER)
+}
+
+/// Get the content after the marker of a conditional block's else branch,
+/// if the line begins one.
+fn get_else_content(line: &str) -> Option<&str> {
+    line.strip_prefix(GATHER_MARKER)?
+        .trim_start()
+        .strip_prefix(ELSE_BLOCK_KEYWORD)?
+        .trim_start()
+        .strip_prefix(':')
+        .map(|content| content.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_lines(content: &str) -> (Vec<ParsedLineKind>, Vec<LineError>) {
+        let lines = content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .zip(0..)
+            .map(|(line, i)| (line, MetaData::from(i)))
+            .collect::<Vec<_>>();
+
+        parse_lines_with_blocks(&lines, &DirectiveSet::new())
+    }
+
+    fn get_error_kinds(content: &str) -> Vec<String> {
+        parse_lines(content)
+            .1
+            .into_iter()
+            .map(|err| format!("{:?}", err.kind))
+            .collect()
+    }
+
+    #[test]
+    fn blocks_are_opened_by_a_brace_and_keyword() {
+        assert_eq!(
+            get_alternative_block_kind("{ stopping:"),
+            Some(AlternativeKind::Sequence)
+        );
+        assert_eq!(
+            get_alternative_block_kind("{cycle:"),
+            Some(AlternativeKind::Cycle)
+        );
+        assert_eq!(
+            get_alternative_block_kind("{ once :"),
+            Some(AlternativeKind::OnceOnly)
+        );
+        assert_eq!(
+            get_alternative_block_kind("{ shuffle:"),
+            Some(AlternativeKind::Shuffle)
+        );
+
+        assert!(get_alternative_block_kind("{ shuffle: A | B }").is_none());
+        assert!(get_alternative_block_kind("{ variable:").is_none());
+    }
+
+    #[test]
+    fn items_are_groups_of_lines_until_the_next_item_marker() {
+        let content = "
+Before.
+{ cycle:
+    - One.
+      Still one.
+    -
+      Two.
+      -> three
+}
+After.
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+        assert_eq!(parsed_lines.len(), 3);
+
+        match &parsed_lines[1] {
+            ParsedLineKind::AlternativeBlock {
+                kind,
+                items,
+                meta_data,
+            } => {
+                assert_eq!(kind, &AlternativeKind::Cycle);
+                assert_eq!(meta_data.line(), 2);
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].len(), 2);
+                assert_eq!(items[1].len(), 2);
+            }
+            other => panic!("expected an alternative block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn blocks_can_be_nested_in_items() {
+        let content = "
+{ stopping:
+    - { shuffle:
+        - A.
+        - B.
+      }
+    - C.
+}
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+
+        match &parsed_lines[0] {
+            ParsedLineKind::AlternativeBlock { items, .. } => match &items[0][0] {
+                ParsedLineKind::AlternativeBlock { kind, items, .. } => {
+                    assert_eq!(kind, &AlternativeKind::Shuffle);
+                    assert_eq!(items.len(), 2);
+                }
+                other => panic!("expected a nested block but got {:?}", other),
+            },
+            other => panic!("expected an alternative block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conditional_blocks_are_split_into_branches_at_else() {
+        let content = "
+{ visited_hall and not has_key:
+    The door was locked.
+    * [Knock]
+    - else: The door was open.
+    Light spilled out.
+}
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+
+        match &parsed_lines[0] {
+            ParsedLineKind::ConditionalBlock {
+                items, else_items, ..
+            } => {
+                assert_eq!(items.len(), 2);
+                assert!(matches!(items[1], ParsedLineKind::Choice { .. }));
+                assert_eq!(else_items.len(), 2);
+            }
+            other => panic!("expected a conditional block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branches_with_conditions_are_nested_into_else_branches() {
+        let content = "
+{
+    - coins > 10: You were rich.
+    - coins > 0:
+        You had a few coins.
+        *   [Buy bread]
+    - else: You were broke.
+}
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+        assert_eq!(parsed_lines.len(), 1);
+
+        match &parsed_lines[0] {
+            ParsedLineKind::ConditionalBlock {
+                items, else_items, ..
+            } => {
+                assert_eq!(items.len(), 1);
+
+                match &else_items[..] {
+                    [ParsedLineKind::ConditionalBlock {
+                        items, else_items, ..
+                    }] => {
+                        assert_eq!(items.len(), 2);
+                        assert!(matches!(items[1], ParsedLineKind::Choice { .. }));
+                        assert_eq!(else_items.len(), 1);
+                    }
+                    other => panic!("expected a nested conditional block but got {:?}", other),
+                }
+            }
+            other => panic!("expected a conditional block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branches_of_blocks_opened_with_a_value_are_compared_to_it() {
+        let content = "
+{ num_guests:
+    - 1: You dined alone.
+    - 2: You dined with a friend.
+}
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+
+        match &parsed_lines[0] {
+            ParsedLineKind::ConditionalBlock {
+                condition,
+                else_items,
+                ..
+            } => {
+                assert_eq!(condition, &parse_condition("num_guests == 1").unwrap());
+
+                match &else_items[..] {
+                    [ParsedLineKind::ConditionalBlock {
+                        condition,
+                        else_items,
+                        ..
+                    }] => {
+                        assert_eq!(condition, &parse_condition("num_guests == 2").unwrap());
+                        assert!(else_items.is_empty());
+                    }
+                    other => panic!("expected a nested conditional block but got {:?}", other),
+                }
+            }
+            other => panic!("expected a conditional block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn blocks_opened_with_a_value_have_branches_only_if_their_first_line_begins_one() {
+        let content = "
+{ has_key:
+    You unlocked the door.
+    - Well: the door swung open.
+}
+";
+
+        let (parsed_lines, errors) = parse_lines(content);
+
+        assert!(errors.is_empty());
+
+        match &parsed_lines[0] {
+            ParsedLineKind::ConditionalBlock {
+                items, else_items, ..
+            } => {
+                assert_eq!(items.len(), 2);
+                assert!(matches!(items[1], ParsedLineKind::Gather { .. }));
+                assert!(else_items.is_empty());
+            }
+            other => panic!("expected a conditional block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_with_inline_conditions_do_not_open_blocks() {
+        assert!(get_block_condition("{ has_key: You unlock the door. }").is_none());
+        assert!(get_block_condition("{ has_key: {door}:").is_none());
+        assert!(get_block_condition("{:").is_none());
+
+        assert_eq!(get_block_condition("{ has_key :"), Some("has_key"));
+    }
+
+    #[test]
+    fn malformed_blocks_yield_errors() {
+        assert_eq!(get_error_kinds("{ once:\n- A."), &["UnterminatedBlock"]);
+        assert_eq!(
+            get_error_kinds("{ once:\nA.\n- B.\n}"),
+            &["ExpectedBlockItem"]
+        );
+        assert_eq!(
+            get_error_kinds("{ once:\n- * [Choice]\n}"),
+            &["BranchingInBlock"]
+        );
+        assert_eq!(
+            get_error_kinds("{ once:\n- { has_key:\n* [Choice]\n}\n}"),
+            &["BranchingInBlock"]
+        );
+        assert_eq!(
+            get_error_kinds("{ has_key:\n- else:\n- else:\n}"),
+            &["DuplicateBlockElse"]
+        );
+        assert_eq!(get_error_kinds("{ has_key:\nA."), &["UnterminatedBlock"]);
+        assert_eq!(
+            get_error_kinds("{\n- a: A.\n- B.\n}"),
+            &["ExpectedBlockCondition"]
+        );
+        assert_eq!(
+            get_error_kinds("{\nA.\n- a: B.\n}"),
+            &["ExpectedBlockCondition"]
+        );
+        assert_eq!(get_error_kinds("{\n}"), &["ExpectedBlockCondition"]);
+        assert_eq!(
+            get_error_kinds("{\n- a: A.\n- else: B.\n- b: C.\n}"),
+            &["BlockBranchAfterElse"]
+        );
+        assert!(matches!(
+            &parse_lines("{ value:\n- 1: A.\n- 2 +: B.\n}").1[..],
+            [LineError {
+                kind: LineErrorKind::ConditionError(..),
+                ..
+            }]
+        ));
+    }
+}
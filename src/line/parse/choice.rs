@@ -6,7 +6,7 @@ use crate::{
     line::{
         parse::{
             parse_choice_condition, parse_internal_line, parse_markers_and_text,
-            split_at_divert_marker,
+            split_at_divert_marker, split_label_from_line, utils::is_escaped,
         },
         Content, InternalChoice, InternalChoiceBuilder, InternalLine, ParsedLineKind,
     },
@@ -19,9 +19,13 @@ pub fn parse_choice(
 ) -> Result<Option<ParsedLineKind>, LineErrorKind> {
     parse_choice_markers_and_text(content)?
         .map(|(level, is_sticky, line)| {
+            let (label, line) = split_label_from_line(line)?;
+
             parse_choice_data(line, meta_data)
                 .map(|mut choice_data| {
                     choice_data.is_sticky = is_sticky;
+                    choice_data.label = label;
+                    choice_data.raw_text = content.trim().to_string();
                     (level, choice_data)
                 })
                 .map(|(level, choice_data)| ParsedLineKind::Choice { level, choice_data })
@@ -113,15 +117,14 @@ fn marker_exists_before_text(line: &str, marker: char) -> bool {
 ///
 /// These are demarcated by `[]` brackets. Content before the bracket is both selection
 /// and display text. Content inside the bracket is only for the selection and content
-/// after the bracket only for display.
+/// after the bracket only for display. Brackets which are escaped with a backslash
+/// are kept as text.
 fn parse_choice_line_variants(line: &str) -> Result<(String, String), LineErrorKind> {
-    match (line.find('['), line.find(']')) {
-        (Some(i), Some(j)) if i < j => {
-            // Ensure that we don't have more brackets
-            if line.rfind('[').unwrap() != i || line.rfind(']').unwrap() != j {
-                return Err(LineErrorKind::UnmatchedBrackets);
-            }
+    let open = find_unescaped_indices(line, '[');
+    let close = find_unescaped_indices(line, ']');
 
+    match (open.as_slice(), close.as_slice()) {
+        (&[i], &[j]) if i < j => {
             let head = line.get(..i).unwrap();
             let inside = line.get(i + 1..j).unwrap();
             let tail = line.get(j + 1..).unwrap();
@@ -131,11 +134,19 @@ fn parse_choice_line_variants(line: &str) -> Result<(String, String), LineErrorK
 
             Ok((selection_text, display_text))
         }
-        (None, None) => Ok((line.to_string(), line.to_string())),
+        (&[], &[]) => Ok((line.to_string(), line.to_string())),
         _ => Err(LineErrorKind::UnmatchedBrackets),
     }
 }
 
+/// Find the byte indices of a bracket in a line, skipping those escaped by a backslash.
+fn find_unescaped_indices(line: &str, bracket: char) -> Vec<usize> {
+    line.match_indices(bracket)
+        .map(|(i, _)| i)
+        .filter(|&i| !is_escaped(line, i))
+        .collect()
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
@@ -236,6 +247,28 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn braces_which_are_both_escaped_are_kept_as_text() {
+        let choice = parse_choice_data("\\{One|Two\\}", &().into()).unwrap();
+        assert_eq!(
+            choice.selection_text.lock().unwrap().chunk.items,
+            &[Content::Text("{One|Two}".to_string())]
+        );
+    }
+
+    #[test]
+    fn brackets_with_backslash_are_kept_as_text() {
+        let choice = parse_choice_data("Open \\[the door\\] [now]", &().into()).unwrap();
+        assert_eq!(
+            choice.selection_text.lock().unwrap().chunk.items,
+            &[Content::Text("Open [the door] now".to_string())]
+        );
+        assert_eq!(
+            choice.display_text.chunk.items,
+            &[Content::Text("Open [the door] ".to_string())]
+        );
+    }
+
     #[test]
     fn alternatives_can_be_within_brackets() {
         let choice = parse_choice_data("[{One|Two}]", &().into()).unwrap();
@@ -325,6 +358,30 @@ pub(crate) mod tests {
         assert!(choice.condition.is_some());
     }
 
+    #[test]
+    fn labels_are_parsed_from_before_the_conditions_of_choices() {
+        match parse_choice("* (greet) {knot_name} Hello, World!", &().into()).unwrap() {
+            Some(ParsedLineKind::Choice { choice_data, .. }) => {
+                assert_eq!(choice_data.label, Some("greet".to_string()));
+                assert!(choice_data.condition.is_some());
+                assert_eq!(
+                    choice_data.display_text,
+                    parse_internal_line(" Hello, World!", &().into()).unwrap()
+                );
+            }
+            other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
+        }
+
+        match parse_choice("* Hello, (World)!", &().into()).unwrap() {
+            Some(ParsedLineKind::Choice { choice_data, .. }) => {
+                assert!(choice_data.label.is_none())
+            }
+            other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
+        }
+
+        assert!(parse_choice("* (greet them) Hello, World!", &().into()).is_err());
+    }
+
     #[test]
     fn parsing_choice_line_variants_return_same_line_if_no_brackets_are_present() {
         let (displayed, line) = parse_choice_line_variants("Hello, World!").unwrap();
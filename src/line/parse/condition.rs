@@ -3,11 +3,13 @@
 use std::cmp::Ordering;
 
 use crate::{
+    consts::{CYCLE_MARKER, SEQUENCE_SEPARATOR, SHUFFLE_MARKER},
     error::parse::{
         condition::{ConditionError, ConditionErrorKind},
         line::LineErrorKind,
     },
     line::{
+        expression::Operand,
         parse::{
             parse_expression, parse_variable, split_line_at_separator_braces,
             split_line_at_separator_parenthesis, split_line_into_groups_braces, LinePart,
@@ -61,6 +63,10 @@ pub fn parse_line_condition(line: &str) -> Result<(Condition, &str, Option<&str>
 /// Choices can lead with multiple conditions. Every condition is contained inside
 /// `{}` bracket pairs and may be whitespace separated. This function reads all conditions
 /// until no bracket pairs are left in the leading part of the line.
+///
+/// The conditions are linked with `and`, so all of them have to be fulfilled. Every
+/// condition after the first is nested, so that its own `and` and `or` links only apply
+/// inside of it.
 pub fn parse_choice_condition(line: &mut String) -> Result<Option<Condition>, LineErrorKind> {
     let conditions = split_choice_conditions_off_string(line)?
         .into_iter()
@@ -87,7 +93,7 @@ pub fn parse_choice_condition(line: &mut String) -> Result<Option<Condition>, Li
 /// be `&&` or `||` respectively), since chained conditions need them. These splits will
 /// not be done within enclosed parenthesis: all grouped conditions inside those will be
 /// treated as whole.
-fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
+pub fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
     let mut buffer = content.to_string();
 
     let mut items: Vec<(Link, ConditionItem)> = Vec::new();
@@ -96,7 +102,12 @@ fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
         let mut condition_string = read_next_condition_string(&mut buffer)?;
 
         let link = split_off_condition_link(&mut condition_string);
-        let mut negate = split_off_negation(&mut condition_string);
+
+        let mut negate = false;
+
+        while split_off_negation(&mut condition_string) {
+            negate = !negate;
+        }
 
         let (condition_kind, inner_negate) = parse_condition_kind(&condition_string)?;
 
@@ -142,7 +153,7 @@ fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
 /// `parse_condition` will be called and the item will be returned as a nested condition.
 /// If not `true`, `false` or nested, it will be parsed as a single condition.
 ///
-/// An extra negation comes from conditions with `!=` markers.
+/// An extra negation comes from conditions with `!=` or `!?` markers.
 fn parse_condition_kind(content: &str) -> Result<(ConditionKind, bool), ConditionError> {
     if &content.trim().to_lowercase() == "true" {
         Ok((ConditionKind::True, false))
@@ -206,21 +217,23 @@ fn split_line_condition_content(content: &str) -> Result<(&str, &str, &str), Con
 ///     to parsing conditions for choices. Other conditional lines require separate markup.
 /// *   As soon as text which is not enclosed by braces appear the condition parsing
 ///     ends.
+/// *   Condition parsing also ends at braces which enclose inline conditional content or
+///     alternatives (`{met_anna: Greet Anna|Introduce yourself}`). Those are kept as the
+///     beginning of the choice text.
 /// *   A backslash '\\' can be used in front of a curly brace to denote that it's not
-///     a condition.
+///     a condition. The backslash is removed if the brace is closed by one which is not
+///     escaped, in which case the braces enclose a variable or alternative.
 /// *   The condition strings are returned without the enclosing braces.
-fn split_choice_conditions_off_string(content: &mut String) -> Result<Vec<String>, ConditionError> {
-    let (head, backslash_adjustor) = content
+fn split_choice_conditions_off_string(content: &mut String) -> Result<Vec<String>, LineErrorKind> {
+    let head = content
         .find("\\{")
         .and_then(|i| content.get(..i))
-        .map(|s| (s, 1))
-        .unwrap_or((content.as_str(), 0));
+        .unwrap_or(content.as_str());
 
-    let parts =
-        split_line_into_groups_braces(head).map_err(|_| get_unmatched_error(content.as_str()))?;
+    let parts = split_line_into_groups_braces(head)?;
 
     let iter = parts.into_iter().take_while(|part| match part {
-        LinePart::Embraced(..) => true,
+        LinePart::Embraced(text) => !is_inline_logic(text),
         LinePart::Text(text) => text.chars().all(|c| c.is_whitespace()),
     });
 
@@ -239,11 +252,43 @@ fn split_choice_conditions_off_string(content: &mut String) -> Result<Vec<String
         })
         .collect();
 
-    content.drain(..num_chars + backslash_adjustor);
+    content.drain(..num_chars);
+
+    if begins_with_escaped_variable(content) {
+        content.remove(0);
+    }
 
     Ok(conditions)
 }
 
+/// Check whether embraced content is inline conditional content or alternatives.
+///
+/// Such content has a `:` after a condition or `|` between items, or begins with the
+/// marker of a cycle or shuffle. Conditions never have these outside of nested braces,
+/// except as `||` for `or`.
+fn is_inline_logic(content: &str) -> bool {
+    let has_separator = |content: &str, separator| {
+        split_line_at_separator_braces(content, separator, Some(1))
+            .map(|parts| parts.len() > 1)
+            .unwrap_or(false)
+    };
+
+    content
+        .trim_start()
+        .starts_with(&[CYCLE_MARKER, SHUFFLE_MARKER][..])
+        || has_separator(content, ":")
+        || has_separator(&content.replace("||", ""), SEQUENCE_SEPARATOR)
+}
+
+/// Check whether a line begins with a backslash before a brace which is closed later.
+///
+/// This is how choice text is written to begin with a variable or alternative instead of
+/// a condition: `\{mentor}, your mentor, greets you`. If the closing brace is escaped as
+/// well the braces are text and the backslash is kept for it.
+fn begins_with_escaped_variable(content: &str) -> bool {
+    content.starts_with("\\{") && split_line_into_groups_braces(&content[1..]).is_ok()
+}
+
 /// Split off leading `and`/`or` parts from line and return them as a `Link`.
 ///
 /// # Notes
@@ -270,19 +315,29 @@ fn split_off_condition_link(content: &mut String) -> Link {
     link
 }
 
-/// Split off leading `not` keyword from line and return whether it was present.
+/// Split off leading `not` keyword or `!` marker from line and return whether it was present.
 ///
 /// # Notes
 /// *   Will trim leading whitespace if keyword is found.
+/// *   The keyword may be directly followed by a parenthesis: `not(a and b)`.
+/// *   The `!=` and `!?` markers are not negations of the condition that follows them.
 fn split_off_negation(content: &mut String) -> bool {
-    if content.to_lowercase().trim_start().starts_with("not ") {
-        let index = content.to_lowercase().find("not").unwrap();
-        content.drain(..index + 3);
+    let trimmed = content.trim_start().to_lowercase();
+    let index = content.len() - content.trim_start().len();
 
-        true
+    let size = if trimmed.starts_with("not")
+        && trimmed[3..].starts_with(|c: char| c.is_whitespace() || c == '(')
+    {
+        3
+    } else if trimmed.starts_with('!') && !trimmed[1..].starts_with(['=', '?']) {
+        1
     } else {
-        false
-    }
+        return false;
+    };
+
+    content.drain(..index + size);
+
+    true
 }
 
 /// Split the string corresponding to the next whole condition from the buffer.
@@ -336,34 +391,30 @@ fn get_split_index(content: &str, separator: &str) -> Result<usize, LineErrorKin
 
 /// Parse a `StoryCondition` from a line and return with whether it is negated.
 ///
-/// An extra negation comes from conditions with `!=` markers.
+/// An extra negation comes from conditions with `!=`, `<=`, `>=` or `!?` markers. Since
+/// `<=` and `>=` are parsed as negated `>` and `<` comparisons they work the same for all
+/// variables which can be ordered, not only integers.
 ///
 /// # Notes
 /// *   Assumes that any preceeding `not` has been trimmed from the conditional. The
-///     negation will come purely from a `!=` marker.
+///     negation will come purely from a `!=`, `<=`, `>=` or `!?` marker.
 fn parse_story_condition(line: &str) -> Result<(StoryCondition, bool), ConditionError> {
     let ordering_search = line
         .find("==")
-        .map(|i| (i, Ordering::Equal, 0, 2, false))
-        .or(line.find("!=").map(|i| (i, Ordering::Equal, 0, 2, true)))
-        .or(line.find("<=").map(|i| (i, Ordering::Less, 1, 2, false)))
-        .or(line
-            .find(">=")
-            .map(|i| (i, Ordering::Greater, -1, 2, false)))
-        .or(line.find("<").map(|i| (i, Ordering::Less, 0, 1, false)))
-        .or(line.find(">").map(|i| (i, Ordering::Greater, 0, 1, false)));
+        .map(|i| (i, Ordering::Equal, 2, false))
+        .or(line.find("!=").map(|i| (i, Ordering::Equal, 2, true)))
+        .or(line.find("<=").map(|i| (i, Ordering::Greater, 2, true)))
+        .or(find_greater_than(line, ">=").map(|i| (i, Ordering::Less, 2, true)))
+        .or(line.find("<").map(|i| (i, Ordering::Less, 1, false)))
+        .or(find_greater_than(line, ">").map(|i| (i, Ordering::Greater, 1, false)));
 
     match ordering_search {
-        Some((index, ordering, adjustment, symbol_length, negate)) => {
+        Some((index, ordering, symbol_length, negate)) => {
             let head = line.get(..index).unwrap().trim();
             let tail = line.get(index + symbol_length..).unwrap().trim();
 
             let lhs_variable = parse_comparison_expression(head)?;
-            let mut rhs_variable = parse_comparison_expression(tail)?;
-
-            if adjustment != 0 {
-                rhs_variable.add(Variable::Int(adjustment));
-            }
+            let rhs_variable = parse_comparison_expression(tail)?;
 
             Ok((
                 StoryCondition::Comparison {
@@ -374,10 +425,60 @@ fn parse_story_condition(line: &str) -> Result<(StoryCondition, bool), Condition
                 negate,
             ))
         }
+        None => parse_contains_condition(line),
+    }
+}
+
+/// Find the first greater-than marker in a line which is not part of a divert target (`-> knot`).
+fn find_greater_than(line: &str, marker: &str) -> Option<usize> {
+    line.match_indices(marker)
+        .map(|(i, _)| i)
+        .find(|&i| !line[..i].ends_with('-'))
+}
+
+/// Parse a `StoryCondition` from a line without comparisons.
+///
+/// Lines with `?` or `!?` markers check whether a list contains items, which may be
+/// given together in parenthesis: `list ? (item, other_item)`. Other lines are single
+/// variables or expressions, like calls to functions, which are checked for whether
+/// they are true-like.
+fn parse_contains_condition(line: &str) -> Result<(StoryCondition, bool), ConditionError> {
+    let contains_search = line
+        .find("!?")
+        .map(|i| (i, 2, true))
+        .or(line.find('?').map(|i| (i, 1, false)));
+
+    match contains_search {
+        Some((index, symbol_length, negate)) => {
+            let head = line.get(..index).unwrap().trim();
+            let tail = line.get(index + symbol_length..).unwrap().trim();
+
+            let list = parse_comparison_expression(head)?;
+
+            let items = match tail
+                .strip_prefix('(')
+                .and_then(|inner| inner.strip_suffix(')'))
+                .and_then(|inner| split_line_at_separator_parenthesis(inner, ",", None).ok())
+            {
+                Some(items) => items
+                    .into_iter()
+                    .map(parse_comparison_expression)
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => vec![parse_comparison_expression(tail)?],
+            };
+
+            Ok((StoryCondition::Contains { list, items }, negate))
+        }
         None => {
-            let variable = parse_condition_variable(line)?;
+            let expression = match parse_condition_variable(line) {
+                Ok(variable) => Expression {
+                    head: Operand::Variable(variable),
+                    tail: Vec::new(),
+                },
+                Err(err) => parse_expression(line).map_err(|_| err)?,
+            };
 
-            Ok((StoryCondition::IsTrueLike { variable }, false))
+            Ok((StoryCondition::IsTrueLike { expression }, false))
         }
     }
 }
@@ -435,13 +536,7 @@ fn get_unmatched_error(line: &str) -> ConditionError {
 mod tests {
     use super::*;
 
-    use crate::{
-        knot::Address,
-        line::{
-            condition::AndOr,
-            expression::{Operand, Operator},
-        },
-    };
+    use crate::{knot::Address, line::condition::AndOr};
 
     #[test]
     fn parse_line_condition_returns_condition_if_true_and_else_content() {
@@ -463,7 +558,7 @@ mod tests {
 
     #[test]
     fn parsing_two_conditions_from_a_line() {
-        let condition = parse_condition(&mut "knot > 0 and other_knot > 0").unwrap();
+        let condition = parse_condition("knot > 0 and other_knot > 0").unwrap();
 
         assert_eq!(condition.items.len(), 1);
         assert!(condition.items[0].is_and());
@@ -480,8 +575,8 @@ mod tests {
 
     #[test]
     fn parsing_condition_with_not_sets_reverse_condition() {
-        let mut line = "not knot_name".to_string();
-        let condition = parse_condition(&mut line).unwrap();
+        let line = "not knot_name".to_string();
+        let condition = parse_condition(&line).unwrap();
 
         assert!(condition.root.negate);
     }
@@ -499,9 +594,59 @@ mod tests {
         assert_eq!(inversed_condition_marker, inversed_condition_word);
     }
 
+    #[test]
+    fn exclamation_marks_and_not_keywords_before_parenthesis_negate_conditions() {
+        let condition_word = parse_condition("not knot_name").unwrap();
+
+        assert_eq!(parse_condition("!knot_name").unwrap(), condition_word);
+        assert_eq!(parse_condition("! knot_name").unwrap(), condition_word);
+
+        let nested_word = parse_condition("not (knot and other_knot)").unwrap();
+
+        assert_eq!(
+            parse_condition("not(knot and other_knot)").unwrap(),
+            nested_word
+        );
+        assert_eq!(
+            parse_condition("!(knot and other_knot)").unwrap(),
+            nested_word
+        );
+    }
+
+    #[test]
+    fn repeated_negations_cancel_each_other() {
+        assert_eq!(
+            parse_condition("not not knot_name").unwrap(),
+            parse_condition("knot_name").unwrap()
+        );
+        assert_eq!(
+            parse_condition("!!knot_name").unwrap(),
+            parse_condition("knot_name").unwrap()
+        );
+        assert!(parse_condition("not !not knot_name").unwrap().root.negate);
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn unary_minus_can_be_used_in_comparisons() {
+        let (condition, _) = parse_story_condition("-knot < 2 * -3").unwrap();
+
+        match condition {
+            StoryCondition::Comparison {
+                lhs_variable,
+                rhs_variable,
+                ..
+            } => {
+                assert_eq!(lhs_variable, parse_expression("-knot").unwrap());
+                assert_eq!(rhs_variable, parse_expression("2 * -3").unwrap());
+            }
+            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn and_gives_and_and_vice_versa() {
-        let condition = parse_condition(&mut "knot and other_knot or third_knot").unwrap();
+        let condition = parse_condition("knot and other_knot or third_knot").unwrap();
 
         assert!(condition.items[0].is_and());
         assert!(condition.items[1].is_or());
@@ -557,8 +702,8 @@ mod tests {
 
     #[test]
     fn parsing_single_not_larger_than_condition() {
-        let mut line = "not knot_name > 2".to_string();
-        let condition = parse_condition(&mut line).unwrap();
+        let line = "not knot_name > 2".to_string();
+        let condition = parse_condition(&line).unwrap();
 
         assert!(condition.root.negate);
         assert_eq!(
@@ -567,28 +712,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parsing_story_condition_with_question_mark_returns_contains_type() {
+        match parse_story_condition("inventory ? torch").unwrap() {
+            (StoryCondition::Contains { list, items }, false) => {
+                assert_eq!(list, parse_expression("inventory").unwrap());
+                assert_eq!(items, &[parse_expression("torch").unwrap()]);
+            }
+            other => panic!("expected `StoryCondition::Contains` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parsing_story_condition_with_not_contains_marker_returns_negated_contains_type() {
+        match parse_story_condition("inventory !? torch").unwrap() {
+            (StoryCondition::Contains { items, .. }, true) => assert_eq!(items.len(), 1),
+            other => panic!(
+                "expected negated `StoryCondition::Contains` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn contains_conditions_can_check_several_items_in_parenthesis() {
+        match parse_story_condition("inventory ? (torch, key)").unwrap() {
+            (StoryCondition::Contains { items, .. }, false) => assert_eq!(
+                items,
+                &[
+                    parse_expression("torch").unwrap(),
+                    parse_expression("key").unwrap()
+                ]
+            ),
+            other => panic!("expected `StoryCondition::Contains` but got {:?}", other),
+        }
+
+        match parse_story_condition("inventory ? (torch)").unwrap() {
+            (StoryCondition::Contains { items, .. }, false) => assert_eq!(items.len(), 1),
+            other => panic!("expected `StoryCondition::Contains` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn parsing_story_condition_with_single_word_returns_is_true_like_type() {
-        let mut line = "knot_name".to_string();
+        let line = "knot_name".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
-            StoryCondition::IsTrueLike { variable } => {
+            StoryCondition::IsTrueLike { expression } => {
                 assert_eq!(
-                    variable,
-                    &Variable::Address(Address::Raw("knot_name".to_string()))
+                    expression,
+                    &Variable::Address(Address::Raw("knot_name".to_string())).into()
                 );
             }
             other => panic!("expected `StoryCondition::IsTrueLike` but got {:?}", other),
         }
     }
 
+    #[test]
+    fn parsing_story_condition_with_function_call_returns_is_true_like_type() {
+        let (condition, _) = parse_story_condition("is_open()").unwrap();
+
+        match &condition {
+            StoryCondition::IsTrueLike { expression } => {
+                assert!(expression.tail.is_empty());
+
+                match &expression.head {
+                    Operand::Call(call) => assert_eq!(call.function.name(), "is_open"),
+                    other => panic!("expected `Operand::Call` but got {:?}", other),
+                }
+            }
+            other => panic!("expected `StoryCondition::IsTrueLike` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parsing_story_condition_with_invalid_word_yields_variable_error() {
+        match parse_story_condition("two words").unwrap_err().kind {
+            ConditionErrorKind::InvalidVariable(..) => (),
+            other => panic!(
+                "expected `ConditionErrorKind::InvalidVariable` but got {:?}",
+                other
+            ),
+        }
+    }
+
     #[test]
     fn parsing_story_condition_with_comparison_returns_comparison_type() {
-        let mut line = "knot_name > 2".to_string();
+        let line = "knot_name > 2".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison { .. } => (),
@@ -598,54 +812,75 @@ mod tests {
 
     #[test]
     fn parsing_story_condition_with_comparison_sets_correct_ordering_operator() {
-        let mut line = "knot_name > 2".to_string();
+        let line = "knot_name > 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
+        match parse_story_condition(&line).unwrap().0 {
             StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Greater),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name >= 2".to_string();
+        let line = "knot_name >= 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
-            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Greater),
+        match parse_story_condition(&line).unwrap().0 {
+            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Less),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name < 2".to_string();
+        let line = "knot_name < 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
+        match parse_story_condition(&line).unwrap().0 {
             StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Less),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name <= 2".to_string();
+        let line = "knot_name <= 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
-            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Less),
+        match parse_story_condition(&line).unwrap().0 {
+            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Greater),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name == 2".to_string();
+        let line = "knot_name == 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
+        match parse_story_condition(&line).unwrap().0 {
             StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Equal),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name != 2".to_string();
+        let line = "knot_name != 2".to_string();
 
-        match parse_story_condition(&mut line).unwrap().0 {
+        match parse_story_condition(&line).unwrap().0 {
             StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Equal),
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
     }
 
+    #[test]
+    fn divert_markers_in_comparisons_are_not_greater_than_markers() {
+        match parse_story_condition("TURNS_SINCE(-> knot_name) > 2")
+            .unwrap()
+            .0
+        {
+            StoryCondition::Comparison {
+                lhs_variable,
+                ordering,
+                ..
+            } => {
+                assert_eq!(ordering, Ordering::Greater);
+                assert_eq!(
+                    lhs_variable,
+                    parse_expression("TURNS_SINCE(-> knot_name)").unwrap()
+                );
+            }
+            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn parsing_story_condition_with_address_variables_sets_raw_addresses() {
-        let mut line = "knot_name > other_knot".to_string();
+        let line = "knot_name > other_knot".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison {
@@ -668,9 +903,9 @@ mod tests {
 
     #[test]
     fn parsing_story_condition_with_numbers_sets_numeric_variables() {
-        let mut line = "2 > 3.0".to_string();
+        let line = "2 > 3.0".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison {
@@ -687,9 +922,9 @@ mod tests {
 
     #[test]
     fn parsing_story_condition_with_true_or_false_sets_boolean() {
-        let mut line = "true == false".to_string();
+        let line = "true == false".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison {
@@ -706,9 +941,9 @@ mod tests {
 
     #[test]
     fn parsing_story_condition_with_strings_sets_string_variables() {
-        let mut line = "\"hello\" > \"world\"".to_string();
+        let line = "\"hello\" > \"world\"".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison {
@@ -725,9 +960,9 @@ mod tests {
 
     #[test]
     fn parsing_story_condition_can_have_different_variable_types_on_each_side() {
-        let mut line = "knot > 2".to_string();
+        let line = "knot > 2".to_string();
 
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+        let (condition, _) = parse_story_condition(&line).unwrap();
 
         match &condition {
             StoryCondition::Comparison {
@@ -746,45 +981,30 @@ mod tests {
     }
 
     #[test]
-    fn parsing_story_conditions_with_larger_than_or_equal_condition_adjusts_rhs_value() {
-        let mut line = "knot >= 2".to_string();
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
-
-        let mut equiv_line = "knot > 2 - 1".to_string();
-        let (mut equiv_condition, _) = parse_story_condition(&mut equiv_line).unwrap();
-
-        // The function does `2 + (-1)` for the adjustment so we flip the term here
-        match equiv_condition {
-            StoryCondition::Comparison {
-                ref mut rhs_variable,
-                ..
-            } => {
-                rhs_variable.tail[0] = (Operator::Add, Operand::Variable((-1).into()));
-            }
-            _ => unreachable!(),
-        }
+    fn greater_than_or_equal_story_conditions_are_negated_less_than_conditions() {
+        let (condition, negated) = parse_story_condition("knot >= 2").unwrap();
+        let (equiv_condition, _) = parse_story_condition("knot < 2").unwrap();
 
+        assert!(negated);
         assert_eq!(condition, equiv_condition);
     }
 
     #[test]
-    fn parsing_story_conditions_with_less_than_or_equal_condition_adjusts_rhs_value() {
-        let mut line = "knot <= 2".to_string();
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
-
-        let mut equiv_line = "knot < 2 + 1".to_string();
-        let (equiv_condition, _) = parse_story_condition(&mut equiv_line).unwrap();
+    fn less_than_or_equal_story_conditions_are_negated_greater_than_conditions() {
+        let (condition, negated) = parse_story_condition("knot <= 2").unwrap();
+        let (equiv_condition, _) = parse_story_condition("knot > 2").unwrap();
 
+        assert!(negated);
         assert_eq!(condition, equiv_condition);
     }
 
     #[test]
     fn not_equal_to_story_conditions_return_true_for_negation() {
-        let mut line = "knot_name == 2".to_string();
-        let (condition, not_negated) = parse_story_condition(&mut line).unwrap();
+        let line = "knot_name == 2".to_string();
+        let (condition, not_negated) = parse_story_condition(&line).unwrap();
 
-        let mut line = "knot_name != 2".to_string();
-        let (negated_condition, negated) = parse_story_condition(&mut line).unwrap();
+        let line = "knot_name != 2".to_string();
+        let (negated_condition, negated) = parse_story_condition(&line).unwrap();
 
         assert!(!not_negated);
         assert!(negated);
@@ -807,10 +1027,10 @@ mod tests {
         let condition = parse_choice_condition(&mut line).unwrap().unwrap();
 
         match &condition.story_condition() {
-            StoryCondition::IsTrueLike { variable } => {
+            StoryCondition::IsTrueLike { expression } => {
                 assert_eq!(
-                    variable,
-                    &Variable::Address(Address::Raw("knot_name".to_string()))
+                    expression,
+                    &Variable::Address(Address::Raw("knot_name".to_string())).into()
                 );
             }
             _ => panic!(),
@@ -840,6 +1060,15 @@ mod tests {
         assert!(conditions.is_none());
     }
 
+    #[test]
+    fn backslashes_are_kept_before_braces_which_are_escaped_on_both_sides() {
+        let mut line = "\\{knot_name\\} Hello, World!".to_string();
+        let conditions = parse_choice_condition(&mut line).unwrap();
+
+        assert_eq!(&line, "\\{knot_name\\} Hello, World!");
+        assert!(conditions.is_none());
+    }
+
     #[test]
     fn several_choice_conditions_can_be_parsed_and_will_be_and_nested_variants() {
         let mut line = "{knot_name} {other_knot} {third_knot} Hello, World!".to_string();
@@ -868,6 +1097,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stacked_choice_conditions_are_and_linked_after_the_items_of_the_first() {
+        let mut line = "{torch or lamp} {dark_room > 1} [Light the way]".to_string();
+        let condition = parse_choice_condition(&mut line).unwrap().unwrap();
+
+        assert_eq!(&line, " [Light the way]");
+        assert_eq!(condition.items.len(), 2);
+
+        match (&condition.items[0], &condition.items[1]) {
+            (AndOr::Or(..), AndOr::And(ConditionItem { kind, .. })) => assert_eq!(
+                kind,
+                &ConditionKind::Nested(Box::new(parse_condition("dark_room > 1").unwrap()))
+            ),
+            other => panic!("expected `AndOr::Or` and `AndOr::And` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn single_choice_conditions_can_have_more_than_one_items_inside() {
         let mut line = "{knot_name or other_knot} Hello, World!".to_string();
@@ -992,6 +1238,34 @@ mod tests {
         assert_eq!(&conditions[1], "condition_two");
     }
 
+    #[test]
+    fn splitting_choice_conditions_stops_at_inline_conditions_and_alternatives() {
+        let mut line = "{visited} {visited: Greet|Meet} Anna".to_string();
+        let conditions = split_choice_conditions_off_string(&mut line).unwrap();
+
+        assert_eq!(conditions, &["visited"]);
+        assert_eq!(&line, "{visited: Greet|Meet} Anna");
+
+        let mut line = "{Hi|Hello}!".to_string();
+        assert!(split_choice_conditions_off_string(&mut line)
+            .unwrap()
+            .is_empty());
+
+        let mut line = "{&Hi}!".to_string();
+        assert!(split_choice_conditions_off_string(&mut line)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn or_markers_in_choice_conditions_are_not_taken_as_alternatives() {
+        let mut line = "{one || two} {(three or four) && f(\"a|b\")} Hello".to_string();
+        let conditions = split_choice_conditions_off_string(&mut line).unwrap();
+
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(&line, "{(three or four) && f(\"a|b\")} Hello");
+    }
+
     #[test]
     fn condition_strings_with_just_condition_and_content_splits_at_colon() {
         assert_eq!(
@@ -1081,6 +1355,23 @@ mod tests {
         assert_eq!(&buffer, " rest");
     }
 
+    #[test]
+    fn splitting_off_negation_does_not_remove_words_beginning_with_not_or_comparison_markers() {
+        let mut buffer = "nothing".to_string();
+
+        assert!(!split_off_negation(&mut buffer));
+        assert_eq!(&buffer, "nothing");
+
+        let mut buffer = "!= 2".to_string();
+
+        assert!(!split_off_negation(&mut buffer));
+
+        let mut buffer = " !(rest)".to_string();
+
+        assert!(split_off_negation(&mut buffer));
+        assert_eq!(&buffer, "(rest)");
+    }
+
     #[test]
     fn closest_split_index_works_for_all_variants() {
         assert_eq!(get_closest_split_index("1 and 2 or 3").unwrap(), 2);
@@ -0,0 +1,93 @@
+//! Parse custom directives as marked up `ParsedLineKind::Directive` objects.
+
+use crate::{
+    error::parse::line::LineErrorKind,
+    line::{Directive, DirectiveSet, ParsedLineKind},
+};
+
+/// Parse a `ParsedLineKind::Directive` from a line if it begins with a registered prefix.
+///
+/// The prefix must be the first word of the line. The rest of the line is given
+/// to the callback of the directive to parse.
+pub fn parse_directive(
+    content: &str,
+    directives: &DirectiveSet,
+) -> Result<Option<ParsedLineKind>, LineErrorKind> {
+    let content = content.trim();
+
+    let (prefix, tail) =
+        content.split_at(content.find(char::is_whitespace).unwrap_or(content.len()));
+
+    match directives.get(prefix) {
+        Some(parser) => parser(tail.trim())
+            .map(|arguments| {
+                Some(ParsedLineKind::Directive(Directive {
+                    name: prefix.to_string(),
+                    arguments,
+                }))
+            })
+            .map_err(|message| LineErrorKind::InvalidDirective {
+                name: prefix.to_string(),
+                message,
+            }),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::line::Variable;
+
+    fn parse_wait(content: &str) -> Result<Vec<Variable>, String> {
+        content
+            .parse::<f32>()
+            .map(|seconds| vec![Variable::Float(seconds)])
+            .map_err(|_| format!("'{}' is not a number of seconds", content))
+    }
+
+    fn get_directives() -> DirectiveSet {
+        let mut directives = DirectiveSet::new();
+        directives.insert("@wait".to_string(), parse_wait);
+
+        directives
+    }
+
+    #[test]
+    fn lines_beginning_with_a_registered_prefix_are_parsed_by_its_callback() {
+        match parse_directive("@wait  2.5 ", &get_directives()).unwrap() {
+            Some(ParsedLineKind::Directive(directive)) => {
+                assert_eq!(&directive.name, "@wait");
+                assert_eq!(directive.arguments, vec![Variable::Float(2.5)]);
+            }
+            other => panic!("expected `ParsedLineKind::Directive` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn prefix_must_be_the_whole_first_word_of_the_line() {
+        let directives = get_directives();
+
+        assert!(parse_directive("@waiting 2.5", &directives)
+            .unwrap()
+            .is_none());
+        assert!(parse_directive("I @wait 2.5", &directives)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn errors_from_the_callback_are_returned_with_the_directive_name() {
+        match parse_directive("@wait a while", &get_directives()) {
+            Err(LineErrorKind::InvalidDirective { name, message }) => {
+                assert_eq!(&name, "@wait");
+                assert_eq!(&message, "'a while' is not a number of seconds");
+            }
+            other => panic!(
+                "expected `LineErrorKind::InvalidDirective` but got {:?}",
+                other
+            ),
+        }
+    }
+}
@@ -1,20 +1,27 @@
 //! Parse `Expression` objects.
 
 use crate::{
+    consts::DIVERT_MARKER,
     error::parse::{
         expression::{ExpressionError, ExpressionErrorKind},
         line::LineErrorKind,
     },
     line::{
-        expression::{apply_order_of_operations, Operand, Operator},
+        expression::{Operand, Operator},
         parse::{parse_variable, split_line_at_separator_parenthesis},
-        Expression,
+        Expression, Function, FunctionCall,
     },
 };
 
+#[cfg(feature = "math")]
+use crate::line::expression::apply_order_of_operations;
+
 /// List of valid mathematical operators.
 pub const MATHEMATICAL_OPERATORS: &[char] = &['+', '-', '*', '/', '%'];
 
+/// Keyword which can be written instead of the '%' operator.
+const REMAINDER_KEYWORD: &str = "mod";
+
 /// Parse a mathematical `Expression` from a string.
 ///
 /// The expression may be a numerical expression or string concatenation.
@@ -24,15 +31,99 @@ pub const MATHEMATICAL_OPERATORS: &[char] = &['+', '-', '*', '/', '%'];
 /// of operator precedence is applied to group multiplication, division and remainder
 /// operations together before addition and subtraction.
 ///
-/// String concatenation should only use addition.
+/// String concatenation should only use addition. The remainder operator may also be
+/// written as the keyword `mod`: `a mod b` is the same as `a % b`.
 pub fn parse_expression(content: &str) -> Result<Expression, ExpressionError> {
-    split_line_into_operation_terms(content)
-        .and_then(|operations| parse_expression_from_operation_terms(operations))
-        .map(|expression| apply_order_of_operations(&expression))
-        .map_err(|kind| ExpressionError {
-            content: content.to_string(),
-            kind,
+    let expression = if content.trim_start().starts_with(DIVERT_MARKER) {
+        parse_divert_target(content)
+    } else {
+        split_line_into_operation_terms(&replace_remainder_keywords(content))
+            .and_then(parse_expression_from_operation_terms)
+            .and_then(prepare_operations)
+    };
+
+    expression.map_err(|kind| ExpressionError {
+        content: content.to_string(),
+        kind,
+    })
+}
+
+/// Replace every `mod` keyword which is outside of strings with the '%' operator.
+///
+/// The keyword must be separated from its operands by whitespace or parenthesis,
+/// so that names like `model` are left as they are.
+fn replace_remainder_keywords(content: &str) -> String {
+    content
+        .split('"')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 0 {
+                replace_remainder_keywords_in_part(part)
+            } else {
+                part.to_string()
+            }
         })
+        .collect::<Vec<_>>()
+        .join("\"")
+}
+
+/// Replace the `mod` keywords of a part of an expression which has no strings.
+fn replace_remainder_keywords_in_part(content: &str) -> String {
+    let mut buffer = String::with_capacity(content.len());
+    let mut remaining = content;
+
+    while let Some(i) = remaining.find(REMAINDER_KEYWORD) {
+        let (before, after) = remaining.split_at(i);
+        let after = &after[REMAINDER_KEYWORD.len()..];
+
+        let is_keyword = before.ends_with(|c: char| c.is_whitespace() || c == ')')
+            && after.starts_with(|c: char| c.is_whitespace() || c == '(');
+
+        buffer.push_str(before);
+        buffer.push_str(if is_keyword { "%" } else { REMAINDER_KEYWORD });
+
+        remaining = after;
+    }
+
+    buffer.push_str(remaining);
+
+    buffer
+}
+
+/// Parse an expression which is a single divert target (`-> knot`).
+///
+/// The divert marker would otherwise be read as a subtraction, and divert targets
+/// cannot be operated on.
+fn parse_divert_target(content: &str) -> Result<Expression, ExpressionErrorKind> {
+    let variable = parse_variable(content).map_err(ExpressionErrorKind::InvalidVariable)?;
+
+    Ok(Expression {
+        head: Operand::Variable(variable),
+        tail: Vec::new(),
+    })
+}
+
+/// Apply the order of operations to a parsed expression.
+#[cfg(feature = "math")]
+fn prepare_operations(expression: Expression) -> Result<Expression, ExpressionErrorKind> {
+    Ok(apply_order_of_operations(&expression))
+}
+
+/// Return an error if the expression uses operators, since the `math` feature is disabled.
+///
+/// Nested expressions in parenthesis are checked as well.
+#[cfg(not(feature = "math"))]
+fn prepare_operations(expression: Expression) -> Result<Expression, ExpressionErrorKind> {
+    fn has_operators(expression: &Expression) -> bool {
+        !expression.tail.is_empty()
+            || matches!(&expression.head, Operand::Nested(inner) if has_operators(inner))
+    }
+
+    if has_operators(&expression) {
+        Err(ExpressionErrorKind::MathDisabled)
+    } else {
+        Ok(expression)
+    }
 }
 
 /// Parse a list of operation terms into a single `Expression`.
@@ -135,14 +226,23 @@ fn split_line_into_operation_terms(content: &str) -> Result<Vec<String>, Express
 
 /// Parse the `Operand` from an expression.
 ///
+/// Operands which lead with a sign that is not part of a number, like the `-b` in `a * -b`,
+/// are parsed as nested expressions which negate their value.
+///
 /// Assumes that the given string is trimmed of whitespace from both ends.
 fn parse_operand(content: &str) -> Result<Operand, ExpressionErrorKind> {
-    if content.starts_with('(') && content.ends_with(')') && content.len() > 1 {
+    if content.starts_with(['-', '+']) && parse_variable(content).is_err() {
+        parse_expression(content)
+            .map(|expression| Operand::Nested(Box::new(expression)))
+            .map_err(|err| err.kind)
+    } else if content.starts_with('(') && content.ends_with(')') && content.len() > 1 {
         let inner = content.get(1..content.bytes().len() - 1).unwrap();
 
         parse_expression(inner)
             .map(|expression| Operand::Nested(Box::new(expression)))
             .map_err(|err| err.kind)
+    } else if let Some(call) = parse_function_call(content)? {
+        Ok(Operand::Call(call))
     } else {
         parse_variable(content)
             .map(|variable| Operand::Variable(variable))
@@ -150,6 +250,57 @@ fn parse_operand(content: &str) -> Result<Operand, ExpressionErrorKind> {
     }
 }
 
+/// Parse a call to a function from an operand, if it is one.
+///
+/// Calls are on the form `name(argument, ...)` where every argument is an expression.
+/// Names which are not of built-in functions are calls to functions in the story,
+/// which are checked when the story is validated.
+fn parse_function_call(content: &str) -> Result<Option<FunctionCall>, ExpressionErrorKind> {
+    let (name, arguments) = match content
+        .strip_suffix(')')
+        .and_then(|content| content.split_once('('))
+    {
+        Some((name, arguments)) => (name.trim(), arguments),
+        None => return Ok(None),
+    };
+
+    let function = match Function::from_name(name) {
+        Some(function) => function,
+        None if is_function_name(name) => Function::Story(name.to_string()),
+        None => return Ok(None),
+    };
+
+    let arguments = if arguments.trim().is_empty() {
+        Vec::new()
+    } else {
+        split_line_at_separator_parenthesis(arguments, ",", None)
+            .map_err(|_| ExpressionErrorKind::UnmatchedParenthesis)?
+            .into_iter()
+            .map(|argument| parse_expression(argument).map_err(|err| err.kind))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    if let Some(expected) = function.num_arguments().filter(|n| *n != arguments.len()) {
+        return Err(ExpressionErrorKind::InvalidNumberOfArguments {
+            function: function.name().to_string(),
+            expected,
+            found: arguments.len(),
+        });
+    }
+
+    Ok(Some(FunctionCall {
+        function,
+        arguments,
+    }))
+}
+
+/// Check whether a name can be the name of a function in the story.
+fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
 /// Split off the initial operator and return its type.
 ///
 /// Assumes to be called on lines for which operators were definitely found. This should
@@ -179,9 +330,20 @@ fn split_off_operator(buffer: &mut String) -> Option<Operator> {
 /// For an input buffer of `a + (b * c) - d` this returns `a `, leaving the buffer as
 /// `+ (b * c) - d`. Operating again on the buffer returns `+ (b * c) `, leaving `- d`.
 /// A final operation drains the buffer completely and returns `- d`.
+///
+/// A sign directly after the leading operator belongs to the operand: `* -b` is returned
+/// whole rather than as `* ` and `-b`.
 fn read_next_operation_string(buffer: &mut String) -> Result<String, ExpressionErrorKind> {
     let (head, tail) = split_leading_operator(&buffer);
-    let head_size = head.len();
+
+    let sign_size = if head.is_empty() {
+        0
+    } else {
+        get_unary_sign_size(tail)
+    };
+
+    let tail = tail.get(sign_size..).unwrap();
+    let head_size = head.len() + sign_size;
 
     let mut last_index = 0;
 
@@ -226,6 +388,17 @@ fn split_leading_operator(content: &str) -> (&str, &str) {
     content.split_at(index)
 }
 
+/// Return the number of bytes up to and including a leading `+` or `-` sign, if there is one.
+fn get_unary_sign_size(content: &str) -> usize {
+    let trimmed = content.trim_start();
+
+    if trimmed.starts_with(['-', '+']) {
+        content.len() - trimmed.len() + 1
+    } else {
+        0
+    }
+}
+
 /// Return the lowest index for any mathematical operator in a line.
 fn get_closest_split_index(content: &str) -> Result<usize, LineErrorKind> {
     get_split_index(content, "+")
@@ -245,15 +418,19 @@ fn get_split_index(content: &str, separator: &str) -> Result<usize, LineErrorKin
 mod tests {
     use super::*;
 
+    use crate::{knot::Address, line::Variable};
+
+    #[cfg(feature = "math")]
     use crate::{
         follow::{FollowData, FollowDataBuilder},
-        knot::Address,
-        line::{evaluate_expression, Variable},
+        line::evaluate_expression,
         story::types::VariableInfo,
     };
 
+    #[cfg(feature = "math")]
     use std::collections::HashMap;
 
+    #[cfg(feature = "math")]
     fn mock_follow_data(knots: &[(&str, &str, u32)], variables: &[(&str, Variable)]) -> FollowData {
         let mut knot_visit_counts = HashMap::new();
 
@@ -287,6 +464,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn number_then_operand_then_number_parses_into_addition_expression() {
         let expression = parse_expression("1 + 2").unwrap();
 
@@ -301,67 +479,205 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn many_operations_created_nested_structure_based_on_operator_precedence() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + 2 - 2 * 3 + 1 / 5 + 5").unwrap();
         let equiv_expression = parse_expression("1 + 2 - (2 * 3) + (1 / 5) + 5").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
-            evaluate_expression(&equiv_expression, &data).unwrap()
+            evaluate_expression(&expression, &mut data).unwrap(),
+            evaluate_expression(&equiv_expression, &mut data).unwrap()
         );
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn whitespace_does_not_matter() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + 2 - 2 * 3 + 1 / 5 + 5").unwrap();
         let equiv_expression = parse_expression("1+2-(2*3)+(1/5)+5").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
-            evaluate_expression(&equiv_expression, &data).unwrap()
+            evaluate_expression(&expression, &mut data).unwrap(),
+            evaluate_expression(&equiv_expression, &mut data).unwrap()
         );
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn nested_parenthesis_are_evaluated_correctly() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + ((2 * (4 + 6)) * (3 - 5))").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(-39),
         );
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn parenthesis_can_nest_several_levels_at_once() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("((((1 + 2))))").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(3),
         );
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn strings_can_be_inside_expressions() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("\"str\" + \"ing\"").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::String("string".to_string())
         );
     }
 
+    #[test]
+    #[cfg(feature = "math")]
+    fn signs_after_operators_negate_the_following_operand() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let mut evaluate = |content: &str| {
+            evaluate_expression(&parse_expression(content).unwrap(), &mut data).unwrap()
+        };
+
+        assert_eq!(evaluate("2 * -3"), Variable::Int(-6));
+        assert_eq!(evaluate("2 - -(1 + 2)"), Variable::Int(5));
+        assert_eq!(evaluate("--4 / +2"), Variable::Int(2));
+    }
+
+    #[test]
+    fn signs_after_operators_are_kept_with_their_operand_when_splitting_into_terms() {
+        assert_eq!(
+            split_line_into_operation_terms("a * -b - - c").unwrap(),
+            &["a ", "* -b ", "- - c"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn mod_keyword_is_the_remainder_operator() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let expression = parse_expression("15 mod 4 + (9)mod(5)").unwrap();
+
+        assert_eq!(
+            evaluate_expression(&expression, &mut data).unwrap(),
+            Variable::Int(7)
+        );
+    }
+
+    #[test]
+    fn mod_keyword_is_only_replaced_when_separated_from_names() {
+        assert_eq!(
+            replace_remainder_keywords("model mod modem"),
+            "model % modem"
+        );
+        assert_eq!(replace_remainder_keywords("a_mod mod_b"), "a_mod mod_b");
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn mod_keyword_inside_strings_is_kept_as_text() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let expression = parse_expression("\"a mod b\" + \" mod \"").unwrap();
+
+        assert_eq!(
+            evaluate_expression(&expression, &mut data).unwrap(),
+            Variable::String("a mod b mod ".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn built_in_functions_parse_into_calls_with_expression_arguments() {
+        let expression = parse_expression("LIST_RANGE(inventory, 1 + 1, 3) + 1").unwrap();
+
+        match &expression.head {
+            Operand::Call(FunctionCall {
+                function: Function::ListRange,
+                arguments,
+            }) => {
+                assert_eq!(arguments.len(), 3);
+                assert_eq!(arguments[1].tail.len(), 1);
+            }
+            other => panic!("expected `Operand::Call` but got {:?}", other),
+        }
+
+        assert_eq!(expression.tail.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "math")]
+    fn calls_to_other_names_are_parsed_as_calls_to_story_functions() {
+        match parse_operand("max(a, b + 1)").unwrap() {
+            Operand::Call(FunctionCall {
+                function: Function::Story(name),
+                arguments,
+            }) => {
+                assert_eq!(&name, "max");
+                assert_eq!(arguments.len(), 2);
+            }
+            other => panic!("expected `Operand::Call` but got {:?}", other),
+        }
+
+        assert!(matches!(
+            parse_operand("(inventory)").unwrap(),
+            Operand::Nested(..)
+        ));
+        assert!(parse_operand("inventory").is_ok());
+    }
+
+    #[test]
+    fn divert_targets_parse_into_single_divert_variables() {
+        let expression = parse_expression(" -> farewell").unwrap();
+
+        assert_eq!(
+            expression.head,
+            Operand::Variable(Variable::Divert(Address::Raw("farewell".to_string())))
+        );
+        assert!(expression.tail.is_empty());
+
+        assert!(parse_expression("-> farewell + 1").is_err());
+    }
+
+    #[test]
+    fn calls_with_wrong_number_of_arguments_yield_error() {
+        match parse_expression("LIST_COUNT(inventory, 2)") {
+            Err(ExpressionError {
+                kind:
+                    ExpressionErrorKind::InvalidNumberOfArguments {
+                        function,
+                        expected,
+                        found,
+                    },
+                ..
+            }) => {
+                assert_eq!(&function, "LIST_COUNT");
+                assert_eq!(expected, 1);
+                assert_eq!(found, 2);
+            }
+            other => panic!(
+                "expected `ExpressionErrorKind::InvalidNumberOfArguments` but got {:?}",
+                other
+            ),
+        }
+    }
+
     #[test]
     fn parsing_expression_from_no_terms_yields_empty_error() {
         match parse_expression_from_operation_terms(vec![]) {
@@ -4,7 +4,10 @@ use crate::{
     consts::GATHER_MARKER,
     error::{parse::line::LineErrorKind, utils::MetaData},
     line::{
-        parse::{parse_internal_line, parse_markers_and_text, split_at_divert_marker},
+        parse::{
+            parse_internal_line, parse_markers_and_text, split_at_divert_marker,
+            split_label_from_line,
+        },
         ParsedLineKind,
     },
 };
@@ -17,9 +20,15 @@ pub fn parse_gather(
     let (line_without_divert, line_from_divert) = split_at_divert_marker(content);
 
     parse_markers_and_text(line_without_divert, GATHER_MARKER)
-        .map(|(level, remaining_text)| (level, format!("{}{}", remaining_text, line_from_divert)))
-        .map(|(level, line)| {
-            parse_internal_line(&line, meta_data).map(|line| ParsedLineKind::Gather { level, line })
+        .map(|(level, remaining_text)| {
+            let (label, remaining_text) = split_label_from_line(remaining_text)?;
+            let line = format!("{}{}", remaining_text, line_from_divert);
+
+            parse_internal_line(&line, meta_data).map(|line| ParsedLineKind::Gather {
+                level,
+                label,
+                line,
+            })
         })
         .transpose()
 }
@@ -96,6 +105,28 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn gathers_may_have_labels_before_their_text() {
+        match parse_line("-- (greet) Hello, World!", &().into()).unwrap() {
+            ParsedLineKind::Gather { level, label, line } => {
+                assert_eq!(level, 2);
+                assert_eq!(label, Some("greet".to_string()));
+                assert_eq!(line, InternalLine::from_string(" Hello, World!"));
+            }
+            other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
+        }
+
+        match parse_line("- (greet)", &().into()).unwrap() {
+            ParsedLineKind::Gather { label, line, .. } => {
+                assert_eq!(label, Some("greet".to_string()));
+                assert_eq!(line.chunk.items.len(), 0);
+            }
+            other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
+        }
+
+        assert!(parse_line("- (greet Hello, World!", &().into()).is_err());
+    }
+
     #[test]
     fn diverts_can_come_directly_after_gathers() {
         match parse_line("- -> world", &().into()).unwrap() {
@@ -103,7 +134,7 @@ pub mod tests {
                 assert_eq!(line.chunk.items[0], Content::Empty);
                 assert_eq!(
                     line.chunk.items[1],
-                    Content::Divert(Address::Raw("world".to_string()))
+                    Content::Divert(Address::Raw("world".to_string()), Vec::new())
                 );
             }
             other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
@@ -116,7 +147,7 @@ pub mod tests {
             ParsedLineKind::Line(line) => {
                 assert_eq!(
                     line.chunk.items[1],
-                    Content::Divert(Address::Raw("world".to_string()))
+                    Content::Divert(Address::Raw("world".to_string()), Vec::new())
                 );
             }
             other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
@@ -2,10 +2,17 @@
 
 use crate::{
     consts::DIVERT_MARKER,
-    error::{parse::line::LineError, utils::MetaData},
+    error::{
+        parse::line::{LineError, LineErrorKind},
+        utils::MetaData,
+    },
     line::{
-        parse::{parse_choice, parse_gather, parse_internal_line},
-        InternalChoice, InternalLine,
+        parse::{
+            parse_assignment, parse_choice, parse_directive, parse_gather, parse_internal_line,
+            parse_thread, utils::find_unmatched_brace,
+        },
+        AlternativeKind, Assignment, Condition, Directive, DirectiveSet, InternalChoice,
+        InternalLine, LogicCall, Return, Thread,
     },
 };
 
@@ -26,11 +33,43 @@ pub enum ParsedLineKind {
     Gather {
         /// Nested level of gather.
         level: u32,
+        /// Label of gather point, if it has one.
+        label: Option<String>,
         /// Parsed line of gather point.
         line: InternalLine,
     },
     /// Regular line of content.
     Line(InternalLine),
+    /// Custom directive registered by the user.
+    Directive(Directive),
+    /// Logic line which assigns a value to a variable.
+    Assignment(Assignment),
+    /// Logic line which calls a function.
+    Call(LogicCall),
+    /// Logic line which returns from a function.
+    Return(Return),
+    /// Line which gathers the content and choices of another knot.
+    Thread(Thread),
+    /// Multi-line block of alternatives, every item of which is a group of lines.
+    AlternativeBlock {
+        /// Which kind of alternative the block is.
+        kind: AlternativeKind,
+        /// Parsed lines of every item.
+        items: Vec<Vec<ParsedLineKind>>,
+        /// Information about the origin of the line which opened the block.
+        meta_data: MetaData,
+    },
+    /// Multi-line block of content which depends on a condition.
+    ConditionalBlock {
+        /// Condition for which branch of the block is followed.
+        condition: Condition,
+        /// Parsed lines to follow if the condition is fulfilled.
+        items: Vec<ParsedLineKind>,
+        /// Parsed lines to follow if the condition is not fulfilled.
+        else_items: Vec<ParsedLineKind>,
+        /// Information about the origin of the line which opened the block.
+        meta_data: MetaData,
+    },
 }
 
 #[cfg(test)]
@@ -42,7 +81,11 @@ impl ParsedLineKind {
 
     /// Construct a `ParsedLineKind::Gather` object with given level and line.
     pub fn gather(level: u32, line: InternalLine) -> Self {
-        ParsedLineKind::Gather { level, line }
+        ParsedLineKind::Gather {
+            level,
+            label: None,
+            line,
+        }
     }
 
     /// Construct a `ParsedLineKind::Line` object with given line.
@@ -52,21 +95,60 @@ impl ParsedLineKind {
 }
 
 /// Parse a line into a `ParsedLineKind` object.
-pub fn parse_line(content: &str, meta_data: &MetaData) -> Result<ParsedLineKind, LineError> {
-    if let Some(choice) = parse_choice(content, meta_data).transpose() {
+pub fn parse_line(content: &str, meta_data: &MetaData) -> Result<ParsedLineKind, Box<LineError>> {
+    if let Some(assignment) = parse_assignment(content, meta_data).transpose() {
+        assignment
+    } else if let Some(thread) = parse_thread(content, meta_data).transpose() {
+        thread
+    } else if let Some(choice) = parse_choice(content, meta_data).transpose() {
         choice
     } else if let Some(gather) = parse_gather(content, meta_data).transpose() {
         gather
     } else {
-        parse_internal_line(content, meta_data).map(|line| ParsedLineKind::Line(line))
+        parse_internal_line(content, meta_data).map(ParsedLineKind::Line)
     }
-    .map_err(|kind| LineError {
-        line: content.to_string(),
-        kind,
-        meta_data: meta_data.clone(),
+    .map_err(|kind| {
+        Box::new(LineError {
+            line: content.to_string(),
+            kind: locate_unmatched_braces(kind, content),
+            meta_data: meta_data.clone(),
+        })
     })
 }
 
+/// Set the position of unmatched braces in an error to their position in the full line.
+///
+/// Braces are matched in parts of the line as the line is parsed, which gives positions
+/// relative to those parts.
+fn locate_unmatched_braces(kind: LineErrorKind, content: &str) -> LineErrorKind {
+    match kind {
+        LineErrorKind::UnmatchedBraces { position } => LineErrorKind::UnmatchedBraces {
+            position: find_unmatched_brace(content, '{', '}').unwrap_or(position),
+        },
+        kind => kind,
+    }
+}
+
+/// Parse a line into a `ParsedLineKind` object, first checking for custom directives.
+///
+/// Lines which begin with a prefix in the set of directives are parsed by its callback,
+/// all other lines are parsed as usual.
+pub fn parse_line_with_directives(
+    content: &str,
+    meta_data: &MetaData,
+    directives: &DirectiveSet,
+) -> Result<ParsedLineKind, Box<LineError>> {
+    match parse_directive(content, directives) {
+        Ok(None) => parse_line(content, meta_data),
+        Ok(Some(directive)) => Ok(directive),
+        Err(kind) => Err(Box::new(LineError {
+            line: content.to_string(),
+            kind,
+            meta_data: meta_data.clone(),
+        })),
+    }
+}
+
 /// Count leading markers and return the number and a string without them.
 pub fn parse_markers_and_text(line: &str, marker: char) -> Option<(u32, &str)> {
     if line.trim_start().starts_with(marker) {
@@ -139,4 +221,20 @@ pub mod tests {
             other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
         }
     }
+
+    #[test]
+    fn lines_are_only_parsed_as_directives_if_their_prefix_is_registered() {
+        let mut directives = DirectiveSet::new();
+        directives.insert("@cue".to_string(), |_| Ok(Vec::new()));
+
+        match parse_line_with_directives("@cue lights", &().into(), &directives).unwrap() {
+            ParsedLineKind::Directive(directive) => assert_eq!(&directive.name, "@cue"),
+            other => panic!("expected `ParsedLineKind::Directive` but got {:?}", other),
+        }
+
+        match parse_line_with_directives("@cue lights", &().into(), &DirectiveSet::new()).unwrap() {
+            ParsedLineKind::Line(..) => (),
+            other => panic!("expected `ParsedLineKind::Line` but got {:?}", other),
+        }
+    }
 }
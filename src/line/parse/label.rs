@@ -0,0 +1,75 @@
+//! Parse labels of choices and gathers.
+
+use crate::{
+    consts::{LABEL_CLOSE_MARKER, LABEL_OPEN_MARKER},
+    error::parse::line::LineErrorKind,
+};
+
+/// Split the label from the beginning of a choice or gather line, if it has one.
+///
+/// Labels are names in parenthesis which are written directly after the markers of
+/// the line, before its conditions and text: `* (label) Choice text`. The line should
+/// not contain the markers. Return the label along with the remaining line.
+pub fn split_label_from_line(content: &str) -> Result<(Option<String>, &str), LineErrorKind> {
+    let line = content.trim_start();
+
+    if !line.starts_with(LABEL_OPEN_MARKER) {
+        return Ok((None, content));
+    }
+
+    let (label, remaining) = line
+        .find(LABEL_CLOSE_MARKER)
+        .map(|i| line.split_at(i))
+        .ok_or_else(|| LineErrorKind::InvalidLabel {
+            label: line.to_string(),
+        })?;
+
+    let name = label.trim_start_matches(LABEL_OPEN_MARKER).trim();
+
+    if name.is_empty() || name.contains(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        return Err(LineErrorKind::InvalidLabel {
+            label: format!("{}{}", label, LABEL_CLOSE_MARKER),
+        });
+    }
+
+    Ok((Some(name.to_string()), &remaining[1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_split_from_the_beginning_of_lines() {
+        assert_eq!(
+            split_label_from_line(" (greet) Hello!").unwrap(),
+            (Some("greet".to_string()), " Hello!")
+        );
+
+        assert_eq!(
+            split_label_from_line("( greet )").unwrap(),
+            (Some("greet".to_string()), "")
+        );
+    }
+
+    #[test]
+    fn lines_without_a_leading_parenthesis_have_no_label() {
+        assert_eq!(
+            split_label_from_line("Hello (greet)!").unwrap(),
+            (None, "Hello (greet)!")
+        );
+
+        assert_eq!(
+            split_label_from_line("{visited} (greet)").unwrap(),
+            (None, "{visited} (greet)")
+        );
+    }
+
+    #[test]
+    fn labels_which_are_not_closed_or_have_invalid_names_yield_errors() {
+        assert!(split_label_from_line("(greet Hello!").is_err());
+        assert!(split_label_from_line("() Hello!").is_err());
+        assert!(split_label_from_line("(greet them) Hello!").is_err());
+        assert!(split_label_from_line("(greet.them) Hello!").is_err());
+    }
+}
@@ -7,12 +7,17 @@ use crate::{
     line::{
         parse::{
             parse_alternative, parse_expression, parse_line_condition,
-            utils::{split_line_at_separator_braces, split_line_into_groups_braces, LinePart},
+            utils::{
+                is_escaped, split_line_at_separator_braces, split_line_at_separator_parenthesis,
+                split_line_into_groups_braces, unescape_text, LinePart,
+            },
         },
-        Content, InternalLine, LineChunk,
+        Content, Expression, InternalLine, LineChunk,
     },
 };
 
+use std::iter::once;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 /// Kinds of variable expressions in an `Ink` line of text.
 enum VariableText {
@@ -31,20 +36,26 @@ pub fn parse_internal_line(
 ) -> Result<InternalLine, LineErrorKind> {
     let mut buffer = content.to_string();
 
-    let tags = parse_tags(&mut buffer);
+    let raw_tags = parse_tags(&mut buffer);
+    let dynamic_tags = parse_dynamic_tags(&raw_tags)?;
+    let tags = raw_tags.iter().map(|tag| unescape_text(tag)).collect();
+
     let divert = split_off_end_divert(&mut buffer)?;
 
     let (glue_begin, glue_end) = parse_line_glue(&mut buffer, divert.is_some());
 
     let mut chunk = parse_chunk(&buffer)?;
 
-    if let Some(address) = divert {
-        chunk.items.push(Content::Divert(Address::Raw(address)));
+    if let Some((address, arguments)) = divert {
+        chunk
+            .items
+            .push(Content::Divert(Address::Raw(address), arguments));
     }
 
     Ok(InternalLine {
         chunk,
         tags,
+        dynamic_tags,
         glue_begin,
         glue_end,
         meta_data: meta_data.clone(),
@@ -72,6 +83,8 @@ fn parse_line_content(content: &str) -> Result<Vec<Content>, LineErrorKind> {
 }
 
 /// Parse and add text and divert items to a `LineChunkBuilder`.
+///
+/// Backslashes which escape characters are removed from the text.
 fn get_text_items(content: &str) -> Result<Vec<Content>, LineErrorKind> {
     let mut buffer = content.to_string();
     let mut items = Vec::new();
@@ -79,13 +92,13 @@ fn get_text_items(content: &str) -> Result<Vec<Content>, LineErrorKind> {
     let divert = split_off_end_divert(&mut buffer)?;
 
     if !buffer.trim().is_empty() {
-        items.push(Content::Text(buffer));
+        items.push(Content::Text(unescape_text(&buffer)));
     } else {
         items.push(Content::Empty);
     }
 
-    if let Some(address) = divert {
-        items.push(Content::Divert(Address::Raw(address)));
+    if let Some((address, arguments)) = divert {
+        items.push(Content::Divert(Address::Raw(address), arguments));
     }
 
     Ok(items)
@@ -135,10 +148,11 @@ fn determine_kind(content: &str) -> Result<VariableText, LineErrorKind> {
 /// Parse and remove glue markers from either side.
 ///
 /// Enclosed whitespace within these markers is retained. Markers that are placed further
-/// in are not (currently) removed.
+/// in are not (currently) removed, nor are markers which are escaped with a backslash.
 fn parse_line_glue(line: &mut String, has_divert: bool) -> (bool, bool) {
     let glue_left = line.trim_start().starts_with(GLUE_MARKER);
-    let glue_right = line.trim_end().ends_with(GLUE_MARKER);
+    let glue_right = line.trim_end().ends_with(GLUE_MARKER)
+        && !is_escaped(line.trim_end(), line.trim_end().len() - GLUE_MARKER.len());
 
     if glue_left {
         *line = line
@@ -155,39 +169,107 @@ fn parse_line_glue(line: &mut String, has_divert: bool) -> (bool, bool) {
 }
 
 /// Split any found tags off the given line and return them separately.
+///
+/// Tag markers which are escaped with a backslash are kept in the text. The tags are
+/// trimmed but keep their escape characters, which the caller should remove.
 fn parse_tags(line: &mut String) -> Vec<String> {
-    match line.find(TAG_MARKER) {
-        Some(i) => {
+    let marker_indices = line
+        .match_indices(TAG_MARKER)
+        .map(|(i, _)| i)
+        .filter(|&i| !is_escaped(line, i))
+        .collect::<Vec<_>>();
+
+    match marker_indices.first() {
+        Some(&i) => {
             let part = line.split_off(i);
 
-            part.trim_matches(TAG_MARKER)
-                .split(TAG_MARKER)
-                .map(|tag| tag.trim().to_string())
-                .collect::<Vec<_>>()
+            marker_indices
+                .iter()
+                .map(|j| j - i + 1)
+                .zip(
+                    marker_indices
+                        .iter()
+                        .skip(1)
+                        .map(|j| j - i)
+                        .chain(once(part.len())),
+                )
+                .map(|(start, end)| part[start..end].trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect()
         }
         None => Vec::new(),
     }
 }
 
+/// Parse the content of tags which contain expressions, conditions or alternatives.
+///
+/// Return the parsed chunks along with the index of their tag. Tags without (unescaped)
+/// braces are static and left out.
+fn parse_dynamic_tags(tags: &[String]) -> Result<Vec<(usize, LineChunk)>, LineErrorKind> {
+    tags.iter()
+        .enumerate()
+        .filter(|(_, tag)| tag.match_indices('{').any(|(i, _)| !is_escaped(tag, i)))
+        .map(|(i, tag)| parse_chunk(tag).map(|chunk| (i, chunk)))
+        .collect()
+}
+
 /// Split diverts off the given line and return it separately if found.
-fn split_off_end_divert(line: &mut String) -> Result<Option<String>, LineErrorKind> {
-    let splits = split_line_at_separator_braces(&line, DIVERT_MARKER, None)?;
+///
+/// The divert is returned as its address and the arguments that it is given.
+fn split_off_end_divert(
+    line: &mut String,
+) -> Result<Option<(String, Vec<Expression>)>, LineErrorKind> {
+    let splits = split_line_at_separator_braces(line, DIVERT_MARKER, Some(1))?;
 
     match splits.len() {
         0 | 1 => Ok(None),
-        2 => {
+        _ => {
             let head_length = splits.get(0).unwrap().len();
 
-            let address = validate_address(splits[1].trim())?;
+            // Divert targets may be given as arguments, inside of parenthesis
+            let is_tunnel = split_line_at_separator_braces(splits[1], DIVERT_MARKER, None)?.len()
+                > 1
+                && split_line_at_separator_parenthesis(splits[1], DIVERT_MARKER, None)?.len() > 1;
+
+            if is_tunnel {
+                return Err(LineErrorKind::FoundTunnel);
+            }
+
+            let divert = parse_divert(splits[1].trim())?;
             line.truncate(head_length);
             line.push(' ');
 
-            Ok(Some(address))
+            Ok(Some(divert))
         }
-        _ => Err(LineErrorKind::FoundTunnel),
     }
 }
 
+/// Parse the address of a divert and the arguments that it is given.
+///
+/// Arguments are expressions which are separated by commas inside of parenthesis
+/// after the address: `-> meet(barry, tavern)`. Divert targets are given as arguments
+/// with their own divert marker: `-> greet(-> farewell)`.
+pub fn parse_divert(content: &str) -> Result<(String, Vec<Expression>), LineErrorKind> {
+    let (address, arguments) = match content
+        .strip_suffix(')')
+        .and_then(|content| content.split_once('('))
+    {
+        Some((address, arguments)) => (address.trim(), arguments),
+        None => (content, ""),
+    };
+
+    let arguments = if arguments.trim().is_empty() {
+        Vec::new()
+    } else {
+        split_line_at_separator_parenthesis(arguments, ",", None)?
+            .into_iter()
+            .map(|argument| parse_expression(argument).map_err(LineErrorKind::from))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    Ok((validate_address(address)?, arguments))
+}
+
 /// Validate that an address for a divert or variable can be parsed.
 ///
 /// # Notes
@@ -283,7 +365,7 @@ mod tests {
 
         assert_eq!(
             line.chunk.items.last().unwrap(),
-            &Content::Divert(Address::Raw("world".to_string()))
+            &Content::Divert(Address::Raw("world".to_string()), Vec::new())
         );
     }
 
@@ -294,7 +376,7 @@ mod tests {
 
         assert_eq!(
             chunk.items.last().unwrap(),
-            &Content::Divert(Address::Raw("world".to_string()))
+            &Content::Divert(Address::Raw("world".to_string()), Vec::new())
         );
     }
 
@@ -304,7 +386,7 @@ mod tests {
 
         assert_eq!(
             chunk.items[1],
-            Content::Divert(Address::Raw("world".to_string()))
+            Content::Divert(Address::Raw("world".to_string()), Vec::new())
         );
     }
 
@@ -316,7 +398,7 @@ mod tests {
         assert_eq!(chunk.items[0], Content::Empty);
         assert_eq!(
             chunk.items[1],
-            Content::Divert(Address::Raw("hello_world".to_string()))
+            Content::Divert(Address::Raw("hello_world".to_string()), Vec::new())
         );
     }
 
@@ -325,10 +407,49 @@ mod tests {
         let chunk = parse_chunk("-> hello.world").unwrap();
         assert_eq!(
             chunk.items.last().unwrap(),
-            &Content::Divert(Address::Raw("hello.world".to_string()))
+            &Content::Divert(Address::Raw("hello.world".to_string()), Vec::new())
         );
     }
 
+    #[test]
+    #[cfg(feature = "math")]
+    fn diverts_may_be_given_arguments_in_parenthesis() {
+        let chunk = parse_chunk("-> meet(barry, 2 * level)").unwrap();
+
+        match chunk.items.last().unwrap() {
+            Content::Divert(Address::Raw(address), arguments) => {
+                assert_eq!(address, "meet");
+                assert_eq!(arguments.len(), 2);
+                assert_eq!(arguments[0], parse_expression("barry").unwrap());
+                assert_eq!(arguments[1], parse_expression("2 * level").unwrap());
+            }
+            other => panic!("expected `Content::Divert` but got {:?}", other),
+        }
+
+        match parse_chunk("-> meet()").unwrap().items.last().unwrap() {
+            Content::Divert(_, arguments) => assert!(arguments.is_empty()),
+            other => panic!("expected `Content::Divert` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn divert_targets_may_be_given_as_arguments_to_diverts() {
+        let chunk = parse_chunk("Goodbye -> greet(-> farewell, -> END)").unwrap();
+
+        match chunk.items.last().unwrap() {
+            Content::Divert(_, arguments) => {
+                assert_eq!(arguments[0], parse_expression("-> farewell").unwrap());
+                assert_eq!(arguments[1], parse_expression("-> END").unwrap());
+            }
+            other => panic!("expected `Content::Divert` but got {:?}", other),
+        }
+
+        match parse_chunk("-> greet(-> farewell) -> tunnel") {
+            Err(LineErrorKind::FoundTunnel) => (),
+            other => panic!("expected `LineErrorKind::FoundTunnel` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn divert_marker_adds_whitespace_to_the_left_of_it() {
         let chunk = parse_chunk("hello-> world").unwrap();
@@ -351,6 +472,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tunnel_returns_with_or_without_a_destination_yield_error() {
+        for content in &["->->", "->-> somewhere_else", "Goodbye ->-> somewhere_else"] {
+            match parse_chunk(content) {
+                Err(LineErrorKind::FoundTunnel) => (),
+                other => panic!("expected `LineErrorKind::FoundTunnel` but got {:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn divert_address_must_be_valid() {
         match parse_chunk("-> hello$world") {
@@ -406,7 +537,7 @@ mod tests {
         let line = parse_internal_line("Hello <> -> world", &().into()).unwrap();
         assert_eq!(
             line.chunk.items[1],
-            Content::Divert(Address::Raw("world".to_string()))
+            Content::Divert(Address::Raw("world".to_string()), Vec::new())
         );
     }
 
@@ -440,6 +571,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tags_with_braces_are_parsed_as_dynamic_tags_along_with_their_index() {
+        let line =
+            parse_internal_line("Hello! # mood # portrait_{speaker} # \\{raw\\}", &().into())
+                .unwrap();
+
+        assert_eq!(&line.tags, &["mood", "portrait_{speaker}", "{raw}"]);
+
+        assert_eq!(line.dynamic_tags.len(), 1);
+
+        let (index, chunk) = &line.dynamic_tags[0];
+
+        assert_eq!(*index, 1);
+        assert_eq!(chunk.items.len(), 2);
+        assert_eq!(chunk.items[0], Content::Text("portrait_".to_string()));
+
+        assert!(parse_internal_line("Hello! # portrait_{speaker", &().into()).is_err());
+    }
+
+    #[test]
+    fn escaped_tag_markers_are_kept_in_the_text_of_lines() {
+        let line = parse_internal_line("Room \\#4 # door \\# 2", &().into()).unwrap();
+
+        assert_eq!(&line.tags, &["door # 2"]);
+        assert_eq!(line.chunk.items[0], Content::Text("Room #4 ".to_string()));
+    }
+
+    #[test]
+    fn escaped_braces_diverts_and_glue_are_read_as_text_without_backslashes() {
+        let line = parse_internal_line("\\{x\\} \\| \\-> \\\\ \\<>", &().into()).unwrap();
+
+        assert!(!line.glue_end);
+        assert_eq!(
+            &line.chunk.items,
+            &[Content::Text("{x} | -> \\ <>".to_string())]
+        );
+    }
+
+    #[test]
+    fn escaped_separators_in_alternatives_are_kept_in_their_text() {
+        let mut line = parse_internal_line("{One \\| Two|Three}", &().into()).unwrap();
+
+        assert_eq!(&get_processed_chunk(&mut line.chunk), "One | Two");
+    }
+
     #[test]
     fn parse_embraced_line_as_alternative() {
         match parse_embraced_line("One | Two").unwrap() {
@@ -491,6 +667,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn parse_embraced_line_expression() {
         match parse_embraced_line("2 + 3").unwrap() {
             Content::Expression(expression) => {
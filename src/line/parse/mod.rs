@@ -12,24 +12,39 @@
 //! Thus `ParsedLineKind` is a temporary object, used only while parsing an `Ink` story.
 
 mod alternative;
+mod assignment;
+mod block;
 mod choice;
 mod condition;
-pub(self) mod expression;
+mod directive;
+mod expression;
 mod gather;
 mod kind;
+mod label;
 mod line;
+mod thread;
 mod utils;
 mod variable;
 
-pub(self) use alternative::parse_alternative;
-pub(self) use choice::parse_choice;
-pub(self) use condition::{parse_choice_condition, parse_line_condition};
-pub(self) use expression::parse_expression;
-pub(self) use gather::parse_gather;
-pub use kind::{parse_line, ParsedLineKind};
-pub(self) use kind::{parse_markers_and_text, split_at_divert_marker};
+use alternative::parse_alternative;
+pub use assignment::is_return_line;
+use assignment::parse_assignment;
+pub use block::parse_lines_with_blocks;
+use choice::parse_choice;
+use condition::{parse_choice_condition, parse_condition, parse_line_condition};
+use directive::parse_directive;
+use expression::parse_expression;
+use gather::parse_gather;
+#[cfg(test)]
+pub use kind::parse_line;
+pub use kind::{parse_line_with_directives, ParsedLineKind};
+use kind::{parse_markers_and_text, split_at_divert_marker};
+use label::split_label_from_line;
+use line::parse_divert;
 pub use line::{parse_chunk, parse_internal_line, validate_address};
-pub(self) use utils::{
+use thread::parse_thread;
+pub use utils::is_escaped;
+use utils::{
     split_line_at_separator_braces, split_line_at_separator_parenthesis,
     split_line_into_groups_braces, LinePart,
 };
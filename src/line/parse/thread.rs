@@ -0,0 +1,68 @@
+//! Parse threads as marked up `ParsedLineKind::Thread` objects.
+
+use crate::{
+    consts::THREAD_MARKER,
+    error::{parse::line::LineErrorKind, utils::MetaData},
+    knot::Address,
+    line::{parse::parse_divert, ParsedLineKind, Thread},
+};
+
+/// Parse a `ParsedLineKind::Thread` from a line if it begins with a thread marker.
+///
+/// Threads are written as the [thread marker][crate::consts::THREAD_MARKER] followed
+/// by an address, which may be given arguments like a divert: `<- conversation(barry)`.
+pub fn parse_thread(
+    content: &str,
+    meta_data: &MetaData,
+) -> Result<Option<ParsedLineKind>, LineErrorKind> {
+    let tail = match content.trim().strip_prefix(THREAD_MARKER) {
+        Some(tail) => tail.trim(),
+        None => return Ok(None),
+    };
+
+    let (address, arguments) = parse_divert(tail)?;
+
+    Ok(Some(ParsedLineKind::Thread(Thread {
+        address: Address::Raw(address),
+        arguments,
+        meta_data: meta_data.clone(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::line::parse::parse_expression;
+
+    #[test]
+    fn threads_parse_into_their_address_and_arguments() {
+        match parse_thread("<- conversation(barry, 2)", &().into()).unwrap() {
+            Some(ParsedLineKind::Thread(thread)) => {
+                assert_eq!(thread.address, Address::Raw("conversation".to_string()));
+                assert_eq!(
+                    thread.arguments,
+                    vec![
+                        parse_expression("barry").unwrap(),
+                        parse_expression("2").unwrap()
+                    ]
+                );
+            }
+            other => panic!("expected `ParsedLineKind::Thread` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_without_thread_marker_are_not_threads() {
+        assert!(parse_thread("A line <- with an arrow", &().into())
+            .unwrap()
+            .is_none());
+        assert!(parse_thread("<> glued", &().into()).unwrap().is_none());
+    }
+
+    #[test]
+    fn threads_must_have_valid_addresses() {
+        assert!(parse_thread("<- ", &().into()).is_err());
+        assert!(parse_thread("<- two words", &().into()).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 //! Utilities for parsing of lines.
 
-use crate::error::parse::line::LineErrorKind;
+use crate::{consts::ESCAPE_MARKER, error::parse::line::LineErrorKind};
 
 use std::{iter::once, ops::Range};
 
@@ -56,7 +56,7 @@ pub fn split_line_at_separator_quotes<'a>(
 /// *   Should work for strings with multibyte characters, since we search for braces
 ///     based on their byte indices, not char index position.
 /// *   Will not work if the separator itself includes curly '{}' braces.
-/// *   Separators can be escaped with a leading backslash '\' (has to be removed later).
+/// *   Separators can be escaped with a leading backslash '\' (removed by `unescape_text`).
 fn split_line_at_separator<'a>(
     content: &'a str,
     separator: &str,
@@ -153,19 +153,46 @@ fn get_separator_indices(
     outside_brace_ranges: &[Range<usize>],
     separator: &str,
 ) -> Vec<usize> {
-    let backslash_preceeding_indices = content
-        .match_indices('\\')
-        .map(|(i, _)| i + 1)
-        .collect::<Vec<_>>();
-
     content
         .match_indices(separator)
         .map(|(i, _)| i)
         .filter(|i| outside_brace_ranges.iter().any(|range| range.contains(i)))
-        .filter(|i| !backslash_preceeding_indices.contains(i))
+        .filter(|&i| !is_escaped(content, i))
         .collect::<Vec<_>>()
 }
 
+/// Check whether the character at a byte index is escaped by a backslash.
+///
+/// Backslashes can themselves be escaped, so the character is escaped if an odd number
+/// of backslashes directly precede it.
+pub fn is_escaped(content: &str, index: usize) -> bool {
+    content.as_bytes()[..index]
+        .iter()
+        .rev()
+        .take_while(|&&byte| byte == ESCAPE_MARKER as u8)
+        .count()
+        % 2
+        == 1
+}
+
+/// Remove the backslashes which escape characters in text.
+///
+/// A backslash always escapes the character which follows it, so `\\` gives a single
+/// backslash. A backslash at the end of the text is kept as it is.
+pub fn unescape_text(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE_MARKER => text.push(chars.next().unwrap_or(ESCAPE_MARKER)),
+            _ => text.push(c),
+        }
+    }
+
+    text
+}
+
 /// Find the `Range`s of bytes in a string which are not enclosed by curly braces.
 ///
 /// Any given variant of opening and closing characters can be used.
@@ -227,7 +254,7 @@ fn get_brace_level_zero_ranges(
 ///
 /// # Notes
 /// *   Braces can be preceeded with backslashes ('\') in which case they do not
-///     count as nesting braces.
+///     count as nesting braces. Backslashes can escape each other.
 /// *   Opening and closing characters must be single-byte characters.
 ///
 /// # Example
@@ -242,21 +269,27 @@ fn get_brace_level_of_line(
     open: char,
     close: char,
 ) -> Result<Vec<u8>, LineErrorKind> {
+    let unmatched_error = || LineErrorKind::UnmatchedBraces {
+        position: find_unmatched_brace(content, open, close).unwrap_or(0),
+    };
+
     content
         .bytes()
-        .scan((None, 0), |(prev, brace_level), byte| {
-            if byte == open as u8 && prev.map(|c| c != b'\\').unwrap_or(true) {
+        .scan((false, 0), |(escaped, brace_level), byte| {
+            if *escaped {
+                *escaped = false;
+            } else if byte == ESCAPE_MARKER as u8 {
+                *escaped = true;
+            } else if byte == open as u8 {
                 *brace_level += 1;
-            } else if byte == close as u8 && prev.map(|c| c != b'\\').unwrap_or(true) {
+            } else if byte == close as u8 {
                 if *brace_level > 0 {
                     *brace_level -= 1;
                 } else {
-                    return Some(Err(LineErrorKind::UnmatchedBraces));
+                    return Some(Err(unmatched_error()));
                 }
             }
 
-            prev.replace(byte);
-
             if open == close {
                 *brace_level = *brace_level % 2;
             }
@@ -268,11 +301,35 @@ fn get_brace_level_of_line(
             if brace_levels.last().map(|&v| v == 0).unwrap_or(true) {
                 Ok(brace_levels)
             } else {
-                Err(LineErrorKind::UnmatchedBraces)
+                Err(unmatched_error())
             }
         })
 }
 
+/// Find the character index of the first unmatched brace in a line, if there is one.
+///
+/// This is the first closing brace which has no opening brace before it, or if all
+/// closing braces are matched, the outermost opening brace which is never closed.
+/// Escaped braces are skipped like when the brace levels of a line are found.
+pub fn find_unmatched_brace(content: &str, open: char, close: char) -> Option<usize> {
+    let mut escaped = false;
+    let mut open_positions = Vec::new();
+
+    for (i, c) in content.chars().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if c == ESCAPE_MARKER {
+            escaped = true;
+        } else if c == open && (open != close || open_positions.is_empty()) {
+            open_positions.push(i);
+        } else if c == close && open_positions.pop().is_none() {
+            return Some(i);
+        }
+    }
+
+    open_positions.first().cloned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -508,7 +565,7 @@ mod tests {
 
     #[test]
     fn brace_level_counting_works_for_empty_line() {
-        assert_eq!(get_brace_level_of_line("", '{', '}').unwrap(), &[]);
+        assert_eq!(get_brace_level_of_line("", '{', '}').unwrap(), &[] as &[u8]);
     }
 
     #[test]
@@ -590,6 +647,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn braces_after_escaped_backslashes_change_the_level() {
+        assert_eq!(
+            &get_brace_level_of_line("\\\\{a}", '{', '}').unwrap(),
+            &[0, 0, 1, 1, 0],
+        );
+    }
+
+    #[test]
+    fn characters_are_escaped_by_an_odd_number_of_backslashes() {
+        assert!(!is_escaped("a|", 1));
+        assert!(is_escaped("a\\|", 2));
+        assert!(!is_escaped("a\\\\|", 3));
+        assert!(is_escaped("\\\\\\|", 3));
+    }
+
+    #[test]
+    fn unescaping_text_removes_backslashes_before_characters() {
+        assert_eq!(&unescape_text("\\{One\\|Two\\}"), "{One|Two}");
+        assert_eq!(&unescape_text("\\\\ and \\# and \\->"), "\\ and # and ->");
+        assert_eq!(&unescape_text("end \\"), "end \\");
+    }
+
     #[test]
     fn same_open_and_close_character_simply_toggles_levels_on_and_off() {
         assert_eq!(
@@ -597,4 +677,27 @@ mod tests {
             &[1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0]
         );
     }
+
+    #[test]
+    fn unmatched_braces_are_found_at_their_character_index() {
+        assert_eq!(find_unmatched_brace("Hello, {World}!", '{', '}'), None);
+        assert_eq!(find_unmatched_brace("Hello, World!}", '{', '}'), Some(13));
+        assert_eq!(find_unmatched_brace("{a {b|c}|d", '{', '}'), Some(0));
+        assert_eq!(find_unmatched_brace("{a} {b {c}", '{', '}'), Some(4));
+        assert_eq!(find_unmatched_brace("김{택}용}", '{', '}'), Some(5));
+        assert_eq!(find_unmatched_brace("\\\\{a\\}", '{', '}'), Some(2));
+    }
+
+    #[test]
+    fn unmatched_brace_errors_carry_the_position_of_the_brace() {
+        match get_brace_level_of_line("{a|{b|c}|d", '{', '}') {
+            Err(LineErrorKind::UnmatchedBraces { position }) => assert_eq!(position, 0),
+            other => panic!("expected `UnmatchedBraces` but got {:?}", other),
+        }
+
+        match get_brace_level_of_line("{a}}", '{', '}') {
+            Err(LineErrorKind::UnmatchedBraces { position }) => assert_eq!(position, 3),
+            other => panic!("expected `UnmatchedBraces` but got {:?}", other),
+        }
+    }
 }
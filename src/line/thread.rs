@@ -0,0 +1,64 @@
+//! Threads which gather the content and choices of other knots into the current flow.
+
+use crate::{
+    error::{
+        parse::{address::InvalidAddressError, validate::ValidationError},
+        utils::MetaData,
+    },
+    knot::Address,
+    line::Expression,
+    log::Logger,
+    story::validate::{ValidateContent, ValidationData},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Line which gathers the content and choices of another knot or stitch into the story.
+///
+/// Threads are written as `<- address` on a line of their own and may be given arguments
+/// like diverts. When the thread is encountered its content is followed until it reaches
+/// a set of choices or its end, after which the story continues from the line after it.
+/// The choices of the thread are presented along with the choices of the story.
+pub struct Thread {
+    /// Address of the knot or stitch which is followed.
+    pub address: Address,
+    /// Expressions which are evaluated into the parameters of a knot.
+    pub arguments: Vec<Expression>,
+    /// Information about the origin of the line.
+    pub meta_data: MetaData,
+}
+
+impl ValidateContent for Thread {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        _: &MetaData,
+        data: &ValidationData,
+    ) {
+        let num_errors = error.num_errors();
+
+        self.address
+            .validate(error, log, current_location, &self.meta_data, data);
+
+        self.arguments.iter_mut().for_each(|argument| {
+            argument.validate(error, log, current_location, &self.meta_data, data)
+        });
+
+        if num_errors == error.num_errors() {
+            if let Err(kind) = self
+                .address
+                .validate_divert_arguments(self.arguments.len(), data)
+            {
+                error.invalid_address_errors.push(InvalidAddressError {
+                    kind,
+                    meta_data: self.meta_data.clone(),
+                });
+            }
+        }
+    }
+}
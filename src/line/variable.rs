@@ -9,6 +9,7 @@ use crate::{
     },
     follow::FollowData,
     knot::{get_num_visited, Address, AddressKind},
+    line::{List, ListItem},
     log::Logger,
     story::{
         validate::{ValidateContent, ValidationData},
@@ -72,6 +73,10 @@ pub enum Variable {
     Int(i32),
     /// Text string.
     String(String),
+    /// Set of items from list definitions.
+    ///
+    /// Will print as the names of its items, separated by commas.
+    List(List),
     /// Divert to another address.
     ///
     /// This is fully internal and will never print to the story. If encountered as a variable
@@ -139,6 +144,7 @@ impl Variable {
             Variable::Float(value) => Some(format!("{}", value)),
             Variable::Int(value) => Some(format!("{}", value)),
             Variable::String(string) => Some(format!("{}", string)),
+            Variable::List(list) => Some(format!("{}", list)),
             Variable::Divert(_) | Variable::Address(_) => None,
         }
     }
@@ -231,17 +237,23 @@ impl Variable {
     pub(crate) fn to_string_internal(&self, data: &FollowData) -> Result<String, InklingError> {
         match &self {
             Variable::Address(address) => match address {
-                Address::Validated(AddressKind::Location { .. }) => {
+                Address::Validated(AddressKind::Location { .. } | AddressKind::Label(..)) => {
                     let num_visited = get_num_visited(address, data)?;
                     Ok(format!("{}", num_visited))
                 }
                 Address::Validated(AddressKind::GlobalVariable { name }) => data
-                    .variables
+                    .get_variable(name)
+                    .ok_or(InklingError::InvalidVariable {
+                        name: name.to_string(),
+                    })
+                    .and_then(|variable| variable.to_string_internal(data)),
+                Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                    .temporary_variables
                     .get(name)
                     .ok_or(InklingError::InvalidVariable {
                         name: name.to_string(),
                     })
-                    .and_then(|variable_info| variable_info.variable.to_string_internal(data)),
+                    .and_then(|variable| variable.to_string_internal(data)),
                 other => Err(InternalError::UseOfUnvalidatedAddress {
                     address: other.clone(),
                 }
@@ -255,6 +267,7 @@ impl Variable {
             Variable::Float(value) => Ok(format!("{}", value)),
             Variable::Int(value) => Ok(format!("{}", value)),
             Variable::String(content) => Ok(content.clone()),
+            Variable::List(list) => Ok(format!("{}", list)),
         }
     }
     /// Return a simple string representation of the variable which does not follow addresses.
@@ -269,6 +282,7 @@ impl Variable {
             Variable::Float(value) => format!("{}", value),
             Variable::Int(value) => format!("{}", value),
             Variable::String(string) => format!("\"{}\"", string),
+            Variable::List(list) => format!("({})", list),
             Variable::Divert(address) => format!("-> {}", address.to_string()),
         }
     }
@@ -285,17 +299,23 @@ impl Variable {
     pub(crate) fn as_value(&self, data: &FollowData) -> Result<Variable, InklingError> {
         match &self {
             Variable::Address(address) => match address {
-                Address::Validated(AddressKind::Location { .. }) => {
+                Address::Validated(AddressKind::Location { .. } | AddressKind::Label(..)) => {
                     let num_visited = get_num_visited(address, data)?;
                     Ok(Variable::Int(num_visited as i32))
                 }
                 Address::Validated(AddressKind::GlobalVariable { name }) => data
-                    .variables
+                    .get_variable(name)
+                    .ok_or(InklingError::InvalidVariable {
+                        name: name.to_string(),
+                    })
+                    .and_then(|variable| variable.as_value(data)),
+                Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                    .temporary_variables
                     .get(name)
                     .ok_or(InklingError::InvalidVariable {
                         name: name.to_string(),
                     })
-                    .and_then(|info| info.variable.as_value(&data)),
+                    .and_then(|variable| variable.as_value(data)),
                 other => Err(InternalError::UseOfUnvalidatedAddress {
                     address: other.clone(),
                 }
@@ -357,6 +377,7 @@ impl Variable {
             (Float(..), Float(..)) => (),
             (Int(..), Int(..)) => (),
             (String(..), String(..)) => (),
+            (List(..), List(..)) => (),
             _ => {
                 return Err(VariableError::from_kind(
                     self.clone(),
@@ -374,9 +395,9 @@ impl Variable {
 
     /// Add the value of a variable to that of another.
     ///
-    /// This operation is valid for integer, floating point and string variables.
+    /// This operation is valid for integer, floating point, string and list variables.
     /// Integer and floating point variables simply adds the numbers together. String
-    /// variables concatenate their strings.
+    /// variables concatenate their strings. Lists get the items of both lists.
     ///
     /// Integer and floating point values can be added to one another. If so, the integer
     /// is cast into a floating point number before the operation and the variable is returned
@@ -414,6 +435,18 @@ impl Variable {
     /// );
     /// ```
     ///
+    /// ## Adding items to lists
+    /// ```
+    /// # use inkling::{List, ListItem, Variable};
+    /// let sword = List::from(ListItem::new("inventory", "sword", 1));
+    /// let torch = List::from(ListItem::new("inventory", "torch", 2));
+    ///
+    /// assert_eq!(
+    ///     Variable::List(sword.clone()).add(&Variable::List(torch.clone())).unwrap(),
+    ///     Variable::List(sword.union(&torch))
+    /// );
+    /// ```
+    ///
     /// # Errors
     /// *   [`InvalidOperation`][crate::error::variable::VariableErrorKind::InvalidOperation]:
     ///     if the variables cannot perform this operation.
@@ -426,6 +459,7 @@ impl Variable {
             (Float(val1), Int(val2)) => Ok(Float(val1 + *val2 as f32)),
             (Float(val1), Float(val2)) => Ok(Float(val1 + val2)),
             (String(s1), String(s2)) => Ok(String(format!("{}{}", s1, s2))),
+            (List(list1), List(list2)) => Ok(List(list1.union(list2))),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidOperation {
@@ -438,7 +472,8 @@ impl Variable {
 
     /// Subtract the value of a variable from that of another.
     ///
-    /// This operation is valid for integer and floating point variables.
+    /// This operation is valid for integer, floating point and list variables. Items
+    /// of the other list are removed from lists.
     ///
     /// Integer and floating point values can be subtracted from one another. If so, the integer
     /// is cast into a floating point number before the operation and the variable is returned
@@ -474,6 +509,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok(Float(*val1 as f32 - val2)),
             (Float(val1), Int(val2)) => Ok(Float(val1 - *val2 as f32)),
             (Float(val1), Float(val2)) => Ok(Float(val1 - val2)),
+            (List(list1), List(list2)) => Ok(List(list1.difference(list2))),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidOperation {
@@ -632,7 +668,7 @@ impl Variable {
                 other: other.clone(),
                 operator: '%',
             }),
-            (Int(val1), Int(val2)) => Ok(Int(val1 % val2)),
+            (Int(val1), Int(val2)) => Ok(Int(val1.wrapping_rem(*val2))),
             (Int(val1), Float(val2)) => Ok(Float(*val1 as f32 % val2)),
             (Float(val1), Int(val2)) => Ok(Float(val1 % *val2 as f32)),
             (Float(val1), Float(val2)) => Ok(Float(val1 % val2)),
@@ -644,6 +680,43 @@ impl Variable {
         .map_err(|kind| VariableError::from_kind(self.clone(), kind))
     }
 
+    /// Assert whether a list contains all items of another.
+    ///
+    /// This operation is only valid for `List` variants. An empty list is not contained
+    /// in any list.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{List, ListItem, Variable};
+    /// let sword = ListItem::new("inventory", "sword", 1);
+    /// let torch = ListItem::new("inventory", "torch", 2);
+    ///
+    /// let inventory = Variable::List(List::from(sword.clone()));
+    ///
+    /// assert!(inventory.contains(&Variable::List(List::from(sword))).unwrap());
+    /// assert!(!inventory.contains(&Variable::List(List::from(torch))).unwrap());
+    /// assert!(!inventory.contains(&Variable::List(List::default())).unwrap());
+    /// assert!(inventory.contains(&Variable::Int(1)).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidOperation`][crate::error::variable::VariableErrorKind::InvalidOperation]:
+    ///     if either variable is not a list.
+    pub fn contains(&self, other: &Variable) -> Result<bool, VariableError> {
+        match (&self, &other) {
+            (Variable::List(list), Variable::List(items)) => {
+                Ok(!items.is_empty() && items.items().all(|item| list.contains(item)))
+            }
+            _ => Err(VariableError::from_kind(
+                self.clone(),
+                VariableErrorKind::InvalidOperation {
+                    other: other.clone(),
+                    operator: '?',
+                },
+            )),
+        }
+    }
+
     /// Assert whether a variable is equal to another.
     ///
     /// Different variable variants cannot be compared to each other, with one exception:
@@ -681,6 +754,7 @@ impl Variable {
             (Float(val1), Float(val2)) => Ok(val1.eq(val2)),
             (Variable::String(val1), Variable::String(val2)) => Ok(val1.eq(val2)),
             (Bool(val1), Bool(val2)) => Ok(val1.eq(val2)),
+            (List(val1), List(val2)) => Ok(val1.eq(val2)),
             (Address(val1), Address(val2)) => Ok(val1.eq(val2)),
             (Divert(val1), Divert(val2)) => Ok(val1.eq(val2)),
             _ => Err(VariableError::from_kind(
@@ -693,11 +767,12 @@ impl Variable {
         }
     }
 
-    /// Assert whether a numeric variable value is greater than that of another.
+    /// Assert whether a variable value is greater than that of another.
     ///
-    /// This operation is only valid for `Int` and `Float` variants. Those variants can
+    /// This operation is only valid for `Int`, `Float` and `String` variants. Numbers can
     /// be compared to each other. If an integer is compared to a floating point number
-    /// the integer will be cast to a float, then the comparison is made.
+    /// the integer will be cast to a float, then the comparison is made. Strings are
+    /// compared to other strings in lexicographic order.
     ///
     /// # Examples
     /// ## Valid comparisons between numbers
@@ -709,12 +784,19 @@ impl Variable {
     /// assert!(Variable::Float(5.1).greater_than(&Variable::Int(5)).unwrap());
     /// ```
     ///
-    /// ## Invalid comparisons between non-numbers
+    /// ## Valid comparisons between strings
+    /// ```
+    /// # use inkling::Variable;
+    /// assert!(Variable::from("hiya").greater_than(&Variable::from("hi")).unwrap());
+    /// assert!(!Variable::from("Anna").greater_than(&Variable::from("Bert")).unwrap());
+    /// ```
+    ///
+    /// ## Invalid comparisons between other variables
     /// ```
     /// # use inkling::Variable;
     /// assert!(Variable::Int(1).greater_than(&Variable::Bool(false)).is_err());
     /// assert!(Variable::Bool(true).greater_than(&Variable::Bool(false)).is_err());
-    /// assert!(Variable::from("hiya").greater_than(&Variable::from("hi")).is_err());
+    /// assert!(Variable::from("hi").greater_than(&Variable::Int(1)).is_err());
     /// ```
     ///
     /// # Errors
@@ -728,6 +810,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok((*val1 as f32).gt(val2)),
             (Float(val1), Int(val2)) => Ok(val1.gt(&(*val2 as f32))),
             (Float(val1), Float(val2)) => Ok(val1.gt(val2)),
+            (String(val1), String(val2)) => Ok(val1.gt(val2)),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidComparison {
@@ -738,11 +821,12 @@ impl Variable {
         }
     }
 
-    /// Assert whether a numeric variable value is less than that of another.
+    /// Assert whether a variable value is less than that of another.
     ///
-    /// This operation is only valid for `Int` and `Float` variants. Those variants can
+    /// This operation is only valid for `Int`, `Float` and `String` variants. Numbers can
     /// be compared to each other. If an integer is compared to a floating point number
-    /// the integer will be cast to a float, then the comparison is made.
+    /// the integer will be cast to a float, then the comparison is made. Strings are
+    /// compared to other strings in lexicographic order.
     ///
     /// # Examples
     /// ## Valid comparisons between numbers
@@ -754,12 +838,19 @@ impl Variable {
     /// assert!(Variable::Float(4.9).less_than(&Variable::Int(5)).unwrap());
     /// ```
     ///
-    /// ## Invalid comparisons between non-numbers
+    /// ## Valid comparisons between strings
+    /// ```
+    /// # use inkling::Variable;
+    /// assert!(Variable::from("hi").less_than(&Variable::from("hiya")).unwrap());
+    /// assert!(Variable::from("Anna").less_than(&Variable::from("Bert")).unwrap());
+    /// ```
+    ///
+    /// ## Invalid comparisons between other variables
     /// ```
     /// # use inkling::Variable;
     /// assert!(Variable::Int(0).less_than(&Variable::Bool(true)).is_err());
     /// assert!(Variable::Bool(false).less_than(&Variable::Bool(true)).is_err());
-    /// assert!(Variable::from("hi").less_than(&Variable::from("hiya")).is_err());
+    /// assert!(Variable::from("hi").less_than(&Variable::Float(1.0)).is_err());
     /// ```
     ///
     /// # Errors
@@ -773,6 +864,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok((*val1 as f32).lt(val2)),
             (Float(val1), Int(val2)) => Ok(val1.lt(&(*val2 as f32))),
             (Float(val1), Float(val2)) => Ok(val1.lt(val2)),
+            (String(val1), String(val2)) => Ok(val1.lt(val2)),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidComparison {
@@ -792,6 +884,7 @@ impl Variable {
             Variable::Float(..) => "Float",
             Variable::Int(..) => "Int",
             Variable::String(..) => "String",
+            Variable::List(..) => "List",
         }
     }
 }
@@ -835,6 +928,117 @@ impl From<String> for Variable {
     }
 }
 
+impl From<List> for Variable {
+    fn from(list: List) -> Self {
+        Variable::List(list)
+    }
+}
+
+/// Get the error for a variable which cannot be converted into a type.
+fn conversion_error(variable: Variable, target: &'static str) -> VariableError {
+    VariableError::from_kind(variable, VariableErrorKind::InvalidConversion { target })
+}
+
+/// Convert an `Int` variable into its value.
+///
+/// # Examples
+/// ```
+/// # use inkling::Variable;
+/// # use std::convert::TryFrom;
+/// assert_eq!(i32::try_from(Variable::Int(5)).unwrap(), 5);
+/// assert!(i32::try_from(Variable::Float(5.0)).is_err());
+/// ```
+impl TryFrom<Variable> for i32 {
+    type Error = VariableError;
+
+    fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+        match variable {
+            Variable::Int(value) => Ok(value),
+            other => Err(conversion_error(other, "i32")),
+        }
+    }
+}
+
+/// Convert a `Float` or `Int` variable into its value.
+///
+/// Integers are cast to floating point numbers, like when they are used together with
+/// them in the story.
+///
+/// # Examples
+/// ```
+/// # use inkling::Variable;
+/// # use std::convert::TryFrom;
+/// assert_eq!(f32::try_from(Variable::Float(0.5)).unwrap(), 0.5);
+/// assert_eq!(f32::try_from(Variable::Int(2)).unwrap(), 2.0);
+/// assert!(f32::try_from(Variable::from("0.5")).is_err());
+/// ```
+impl TryFrom<Variable> for f32 {
+    type Error = VariableError;
+
+    fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+        match variable {
+            Variable::Float(value) => Ok(value),
+            Variable::Int(value) => Ok(value as f32),
+            other => Err(conversion_error(other, "f32")),
+        }
+    }
+}
+
+/// Convert a `Bool` variable into its value.
+///
+/// # Examples
+/// ```
+/// # use inkling::Variable;
+/// # use std::convert::TryFrom;
+/// assert!(bool::try_from(Variable::Bool(true)).unwrap());
+/// assert!(bool::try_from(Variable::Int(1)).is_err());
+/// ```
+impl TryFrom<Variable> for bool {
+    type Error = VariableError;
+
+    fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+        match variable {
+            Variable::Bool(value) => Ok(value),
+            other => Err(conversion_error(other, "bool")),
+        }
+    }
+}
+
+/// Convert a `String` variable into its value.
+///
+/// Other variables are not converted into their string representation: use
+/// [`to_string`][Variable::to_string()] for that.
+///
+/// # Examples
+/// ```
+/// # use inkling::Variable;
+/// # use std::convert::TryFrom;
+/// assert_eq!(&String::try_from(Variable::from("Alcazar")).unwrap(), "Alcazar");
+/// assert!(String::try_from(Variable::Int(5)).is_err());
+/// ```
+impl TryFrom<Variable> for String {
+    type Error = VariableError;
+
+    fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+        match variable {
+            Variable::String(value) => Ok(value),
+            other => Err(conversion_error(other, "String")),
+        }
+    }
+}
+
+/// Convert a `List` variable into its value.
+impl TryFrom<Variable> for List {
+    type Error = VariableError;
+
+    fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+        match variable {
+            Variable::List(list) => Ok(list),
+            other => Err(conversion_error(other, "List")),
+        }
+    }
+}
+
 impl ValidateContent for Variable {
     fn validate(
         &mut self,
@@ -845,24 +1049,116 @@ impl ValidateContent for Variable {
         data: &ValidationData,
     ) {
         match self {
-            Variable::Address(address) | Variable::Divert(address) => {
+            Variable::Address(address) => {
+                if let Some(item) = get_list_item(address, data) {
+                    *self = Variable::List(List::from(item));
+                    return;
+                }
+
                 address.validate(error, log, current_location, meta_data, data);
+
+                if let Some(value) = get_constant_value(address, data) {
+                    *self = value;
+                }
             }
-            Variable::Bool(..) | Variable::Float(..) | Variable::Int(..) | Variable::String(..) => {
-                ()
+            Variable::Divert(address) => {
+                address.validate(error, log, current_location, meta_data, data);
             }
+            Variable::Bool(..)
+            | Variable::Float(..)
+            | Variable::Int(..)
+            | Variable::String(..)
+            | Variable::List(..) => (),
         }
     }
 }
 
+/// Get the value of a constant that a validated address refers to.
+///
+/// Constants cannot be assigned to while the story is followed which means that their
+/// values can be substituted when the story is validated. Constants which refer to other
+/// variables or to knots are not substituted.
+fn get_constant_value(address: &Address, data: &ValidationData) -> Option<Variable> {
+    match address {
+        Address::Validated(AddressKind::GlobalVariable { name }) => data
+            .follow_data
+            .variables
+            .get(name)
+            .filter(|info| info.is_const)
+            .map(|info| &info.variable)
+            .filter(|variable| !matches!(variable, Variable::Address(..) | Variable::Divert(..)))
+            .cloned(),
+        _ => None,
+    }
+}
+
+/// Get the item of a list definition that an unvalidated address names.
+///
+/// Items are named by themselves or qualified with their list name (`inventory.torch`).
+/// Names of temporary and global variables take precedence over items. Names which
+/// match items in several lists are not resolved and have to be qualified.
+fn get_list_item(address: &Address, data: &ValidationData) -> Option<ListItem> {
+    let name = match address {
+        Address::Raw(name) => name.trim(),
+        _ => return None,
+    };
+
+    if data.temporary_variables.borrow().contains_key(name)
+        || data.follow_data.variables.contains_key(name)
+    {
+        return None;
+    }
+
+    let (list_name, item_name) = match name.split_once('.') {
+        Some((list_name, item_name)) => (Some(list_name), item_name),
+        None => (None, name),
+    };
+
+    let mut matches = data
+        .follow_data
+        .variables
+        .iter()
+        .filter(|(name, _)| list_name.is_none() || list_name == Some(name.as_str()))
+        .filter_map(|(_, variable_info)| variable_info.list_items.as_ref())
+        .flat_map(|items| items.items())
+        .filter(|item| item.name == item_name);
+
+    match (matches.next(), matches.next()) {
+        (Some(item), None) => Some(item.clone()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::{follow::FollowDataBuilder, story::types::VariableInfo};
+    use crate::{
+        follow::FollowDataBuilder,
+        story::{parse::tests::read_knots_from_string, types::VariableInfo},
+    };
 
     use std::collections::HashMap;
 
+    #[test]
+    fn variables_of_other_types_cannot_be_converted_into_values() {
+        let error = i32::try_from(Variable::Bool(true)).unwrap_err();
+
+        match error.kind {
+            VariableErrorKind::InvalidConversion { target } => assert_eq!(target, "i32"),
+            other => panic!("expected `InvalidConversion` but got {:?}", other),
+        }
+
+        assert_eq!(error.variable, Variable::Bool(true));
+        assert_eq!(
+            error.to_string(),
+            "Cannot convert a variable of type 'Bool' into 'i32' (in: 'true')"
+        );
+
+        assert!(List::try_from(Variable::List(List::default())).is_ok());
+        assert!(List::try_from(Variable::from("items")).is_err());
+    }
+
     fn mock_follow_data(knots: &[(&str, &str, u32)], variables: &[(&str, Variable)]) -> FollowData {
         let mut knot_visit_counts = HashMap::new();
 
@@ -933,6 +1229,47 @@ mod tests {
         assert_eq!(variable_two.as_value(&data).unwrap(), Variable::Int(3));
     }
 
+    #[test]
+    fn validating_addresses_to_constants_substitutes_their_values() {
+        let mut variables = HashMap::new();
+
+        let mut constant = VariableInfo::new(Variable::Int(5), 0);
+        constant.is_const = true;
+
+        variables.insert("max_lives".to_string(), constant);
+        variables.insert("lives".to_string(), VariableInfo::new(Variable::Int(3), 1));
+
+        let knots = read_knots_from_string("== storm\nThe storm has begun.").unwrap();
+        let data = ValidationData::from_data(&knots, &variables);
+
+        let mut error = ValidationError::new();
+        let mut log = Logger::default();
+        let current_location = Address::from_parts_unchecked("storm", None);
+
+        let mut validate = |variable: &mut Variable| {
+            variable.validate(
+                &mut error,
+                &mut log,
+                &current_location,
+                &MetaData::from(2),
+                &data,
+            )
+        };
+
+        let mut constant = Variable::Address(Address::Raw("max_lives".to_string()));
+        let mut variable = Variable::Address(Address::Raw("lives".to_string()));
+
+        validate(&mut constant);
+        validate(&mut variable);
+
+        assert_eq!(constant, Variable::Int(5));
+        assert_eq!(
+            variable,
+            Variable::Address(Address::variable_unchecked("lives"))
+        );
+        assert!(data.read_variables.borrow().contains("max_lives"));
+    }
+
     #[test]
     fn getting_value_from_address_variable_of_global_variable_gets_value_of_that() {
         let data = mock_follow_data(&[], &[("population", Variable::Int(1305))]);
@@ -1241,15 +1578,24 @@ mod tests {
     }
 
     #[test]
-    fn string_variables_can_do_equality_comparison_only() {
+    fn string_variables_compare_to_each_other_in_lexicographic_order() {
         let string1 = Variable::String("Hello, World!".to_string());
         let string2 = Variable::String("Hello!".to_string());
 
         assert!(string1.equal_to(&string1).unwrap());
         assert!(!string1.equal_to(&string2).unwrap());
 
-        assert!(string1.less_than(&string2).is_err());
-        assert!(string1.greater_than(&string2).is_err());
+        assert!(string1.greater_than(&string2).unwrap());
+        assert!(!string1.less_than(&string2).unwrap());
+        assert!(!string1.less_than(&string1).unwrap());
+    }
+
+    #[test]
+    fn string_variables_cannot_be_ordered_against_numbers() {
+        let string = Variable::String("5".to_string());
+
+        assert!(string.less_than(&Variable::Int(6)).is_err());
+        assert!(Variable::Float(4.0).greater_than(&string).is_err());
     }
 
     #[test]
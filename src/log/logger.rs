@@ -1,9 +1,11 @@
 use crate::{
-    consts::TODO_COMMENT_MARKER,
     error::MetaData,
-    log::{LogMessage, MessageKind, Warning},
+    log::{LogMessage, Warning},
 };
 
+#[cfg(feature = "logging")]
+use crate::{consts::TODO_COMMENT_MARKER, log::MessageKind};
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -39,6 +41,7 @@ impl Logger {
         !self.todo_comments.is_empty() || !self.warnings.is_empty()
     }
 
+    #[cfg(feature = "logging")]
     pub(crate) fn add_todo(&mut self, comment: &str, meta_data: &MetaData) {
         let without_marker = comment
             .trim_start()
@@ -51,12 +54,19 @@ impl Logger {
             .push(LogMessage::with_kind(message, meta_data));
     }
 
+    #[cfg(feature = "logging")]
     pub(crate) fn add_warning(&mut self, warning: Warning, meta_data: &MetaData) {
         self.warnings.push(LogMessage::with_kind(
             MessageKind::Warning(warning),
             meta_data,
         ));
     }
+
+    #[cfg(not(feature = "logging"))]
+    pub(crate) fn add_todo(&mut self, _: &str, _: &MetaData) {}
+
+    #[cfg(not(feature = "logging"))]
+    pub(crate) fn add_warning(&mut self, _: Warning, _: &MetaData) {}
 }
 
 /****************************
@@ -164,6 +174,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "logging")]
     fn iterating_through_log_yields_comments_and_warnings_in_line_index_order() {
         let mut logger = Logger::default();
 
@@ -186,6 +197,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "logging")]
     fn into_iter_yields_items_in_same_order_as_iter() {
         let mut logger = Logger::default();
 
@@ -202,6 +214,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "logging")]
     fn logger_has_entries_if_todo_or_warnings_list_has() {
         let mut logger = Logger::default();
         assert!(!logger.has_entries());
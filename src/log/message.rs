@@ -16,6 +16,7 @@ pub struct LogMessage {
 }
 
 impl LogMessage {
+    #[cfg_attr(not(feature = "logging"), allow(dead_code))]
     pub(crate) fn with_kind(message: MessageKind, meta_data: &MetaData) -> Self {
         LogMessage {
             message,
@@ -40,8 +41,45 @@ pub enum MessageKind {
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// A detected non-fatal error or incompatibility.
 pub enum Warning {
+    /// Found a choice with the same selection text as another choice in the same set.
+    ///
+    /// Players will not be able to tell the choices apart.
+    DuplicateChoiceText {
+        /// Selection text of both choices.
+        text: String,
+        /// Information about the origin of the first choice with this text.
+        prev_meta_data: MetaData,
+    },
+    /// The host set a variable which is not declared in the story, which created it.
+    CreatedUndeclaredVariable {
+        /// Name of variable.
+        name: String,
+    },
+    /// Found a line with more characters than the set maximum.
+    LineTooLong {
+        /// Number of characters in the line.
+        length: usize,
+        /// Maximum number of characters set for lines.
+        max: usize,
+    },
+    /// Found a call to `RANDOM` but the `random` feature is not enabled.
+    RandomFunctionNoRandom,
     /// Found a shuffle sequence but the `random` feature is not enabled.
     ShuffleSequenceNoRandom,
+    /// Found a set of choices with more choices than the set maximum.
+    TooManyChoices {
+        /// Number of choices in the set.
+        num_choices: usize,
+        /// Maximum number of choices set for a set.
+        max: usize,
+    },
+    /// Found a global variable which is never read in the story.
+    UnreadVariable {
+        /// Name of variable.
+        name: String,
+        /// Whether the variable is constant.
+        is_const: bool,
+    },
 }
 
 impl fmt::Display for LogMessage {
@@ -71,12 +109,54 @@ impl fmt::Display for Warning {
         use Warning::*;
 
         match self {
+            DuplicateChoiceText {
+                text,
+                prev_meta_data,
+            } => write!(
+                f,
+                "found choice with selection text '{}' which is identical to the choice \
+                 at {} in the same set: players will not be able to tell them apart",
+                text, prev_meta_data
+            ),
+            CreatedUndeclaredVariable { name } => write!(
+                f,
+                "created global variable '{}' which was set by the host but is not declared \
+                 in the story: is it misspelled?",
+                name
+            ),
+            LineTooLong { length, max } => write!(
+                f,
+                "line has {} characters which is more than the maximum of {}",
+                length, max
+            ),
+            RandomFunctionNoRandom => write!(
+                f,
+                "found a call to `RANDOM` but the `random` feature is not enabled: \
+                 it always returns its lower bound (fix: compile `inkling` with the \
+                 `random` feature)"
+            ),
             ShuffleSequenceNoRandom => write!(
                 f,
                 "found a shuffle sequence but the `random` feature is not enabled: \
                  changed it to a cycle sequence (fix: compile `inkling` with the \
                  `random` feature)"
             ),
+            TooManyChoices { num_choices, max } => write!(
+                f,
+                "set of choices has {} choices which is more than the maximum of {}",
+                num_choices, max
+            ),
+            UnreadVariable { name, is_const } => write!(
+                f,
+                "{} '{}' is never read in any condition, expression or text: \
+                 is it misspelled where it should be used?",
+                if *is_const {
+                    "constant"
+                } else {
+                    "global variable"
+                },
+                name
+            ),
         }
     }
 }
@@ -28,6 +28,7 @@
 //! ";
 //!
 //! let story = read_story_from_string(content).unwrap();
+//! # #[cfg(feature = "logging")]
 //! assert_eq!(story.log.todo_comments.len(), 1);
 //!
 //! for comment in story.log.todo_comments.iter() {
@@ -1,11 +1,12 @@
 //! Processing nested story content by following, or walking through, it.
 
 use crate::{
-    error::{runtime::internal::IncorrectNodeStackError, InternalError},
-    follow::{ChoiceInfo, EncounteredEvent, FollowData, FollowResult, LineDataBuffer},
+    error::{runtime::internal::IncorrectNodeStackError, InklingError, InternalError},
+    follow::{ChoiceInfo, EncounteredEvent, FollowData, FollowResult, LineDataBuffer, LineText},
     knot::increment_num_visited,
-    node::{Branch, NodeItem, RootNode},
-    process::process_line,
+    node::{AlternativeBlock, BlockBranch, Branch, ConditionalBlock, NodeItem, RootNode},
+    process::{check_condition, process_line, process_thread},
+    story::ConditionSite,
 };
 
 use std::{fmt, slice::IterMut};
@@ -69,35 +70,65 @@ pub trait Follow: FollowInternal {
         buffer: &mut LineDataBuffer,
         data: &mut FollowData,
     ) -> FollowResult {
-        let at_index = stack
-            .last_mut()
+        let stack_index = stack
+            .len()
+            .checked_sub(1)
             .ok_or(InternalError::from(IncorrectNodeStackError::EmptyStack))?;
 
-        if *at_index > self.get_num_items() {
+        let at_index = stack[stack_index];
+
+        if at_index > self.get_num_items() {
             return Err(InternalError::from(IncorrectNodeStackError::OutOfBounds {
                 stack_index: stack.len() - 1,
                 stack: stack.clone(),
                 num_items: self.get_num_items(),
             })
             .into());
-        } else if *at_index == 0 {
+        } else if at_index == 0 {
             self.increment_num_visited(data)?;
         }
 
-        for item in self.iter_mut_items().skip(*at_index) {
-            *at_index += 1;
+        for item in self.iter_mut_items().skip(at_index) {
+            stack[stack_index] += 1;
 
             match item {
                 NodeItem::Line(line) => {
-                    let result =
-                        process_line(line, buffer, data).map_err(|err| InternalError::from(err))?;
+                    let result = process_line(line, buffer, data).map_err(InternalError::from)?;
 
                     if let EncounteredEvent::Divert(..) = result {
                         return Ok(result);
                     }
                 }
+                NodeItem::Directive(directive) => {
+                    buffer.push(LineText::from_directive(directive.clone()));
+                }
+                NodeItem::Label(label) => increment_num_visited(&label.address, data)?,
+                NodeItem::Assignment(assignment) => assignment.assign(data)?,
+                NodeItem::Call(call) => call.follow(buffer, data)?,
+                NodeItem::Return(return_line) => {
+                    return Ok(EncounteredEvent::Return(return_line.evaluate(data)?));
+                }
+                NodeItem::Thread(thread) => return process_thread(thread, data),
+                NodeItem::AlternativeBlock(block) => {
+                    let result = follow_alternative_block(block, buffer, data)?;
+
+                    if let EncounteredEvent::Divert(..) | EncounteredEvent::Return(..) = result {
+                        return Ok(result);
+                    }
+                }
+                NodeItem::ConditionalBlock(block) => {
+                    let result = follow_conditional_block(block, stack, buffer, data)?;
+
+                    if let EncounteredEvent::Divert(..)
+                    | EncounteredEvent::BranchingChoice(..)
+                    | EncounteredEvent::Return(..)
+                    | EncounteredEvent::Thread(..) = result
+                    {
+                        return Ok(result);
+                    }
+                }
                 NodeItem::BranchingPoint(branches) => {
-                    *at_index -= 1;
+                    stack[stack_index] -= 1;
 
                     let branching_choice_set = get_choices_from_branching_set(branches);
 
@@ -156,10 +187,139 @@ pub trait Follow: FollowInternal {
             other => Ok(other),
         }
     }
+
+    /// Resume the follow of content in the tree from the position in the `Stack`.
+    ///
+    /// Used to continue after a thread, which can be encountered in nested nodes. Like
+    /// `follow_with_choice` the stack is used to advance to the deepest node, which is
+    /// followed from the item that the stack points to. When we return from a deeper level
+    /// due to running out of content in that node, we keep `follow`ing the content in
+    /// the current node until its end.
+    fn resume(
+        &mut self,
+        stack_index: usize,
+        stack: &mut Stack,
+        buffer: &mut LineDataBuffer,
+        data: &mut FollowData,
+    ) -> FollowResult {
+        let result = match self.get_next_level_branch(stack_index, stack)? {
+            Some(next_branch) => next_branch.resume(stack_index + 2, stack, buffer, data),
+            None => return self.follow(stack, buffer, data),
+        }?;
+
+        match result {
+            EncounteredEvent::Done => {
+                stack.truncate(stack_index + 1);
+
+                if let Some(i) = stack.last_mut() {
+                    *i += 1;
+                }
+
+                self.follow(stack, buffer, data)
+            }
+            other => Ok(other),
+        }
+    }
 }
 
 impl Follow for RootNode {}
 impl Follow for Branch {}
+impl Follow for BlockBranch {}
+
+/// Follow the next selected group of content in a multi-line block of alternatives.
+///
+/// Blocks contain no choices, so the group is followed to its end or the first divert.
+fn follow_alternative_block(
+    block: &mut AlternativeBlock,
+    buffer: &mut LineDataBuffer,
+    data: &mut FollowData,
+) -> FollowResult {
+    let items = match block.get_next_index(data) {
+        Some(index) => &mut block.items[index],
+        None => return Ok(EncounteredEvent::Done),
+    };
+
+    for item in items.iter_mut() {
+        let result = match item {
+            NodeItem::Line(line) => {
+                process_line(line, buffer, data).map_err(InternalError::from)?
+            }
+            NodeItem::Directive(directive) => {
+                buffer.push(LineText::from_directive(directive.clone()));
+                EncounteredEvent::Done
+            }
+            NodeItem::Assignment(assignment) => {
+                assignment.assign(data)?;
+                EncounteredEvent::Done
+            }
+            NodeItem::Call(call) => {
+                call.follow(buffer, data)?;
+                EncounteredEvent::Done
+            }
+            NodeItem::Return(return_line) => EncounteredEvent::Return(return_line.evaluate(data)?),
+            NodeItem::AlternativeBlock(block) => follow_alternative_block(block, buffer, data)?,
+            NodeItem::ConditionalBlock(block) => {
+                let branch_index = get_conditional_branch_index(block, data)?;
+
+                // Blocks of alternatives never contain choices, so the branch is always
+                // followed to its end or a divert and its position does not need to be kept
+                block.branches[branch_index].follow(&mut vec![0], buffer, data)?
+            }
+            // Choices, gathers and threads are errors when the lines of blocks are parsed
+            NodeItem::BranchingPoint(..) | NodeItem::Thread(..) | NodeItem::Label(..) => {
+                EncounteredEvent::Done
+            }
+        };
+
+        if let EncounteredEvent::Divert(..) | EncounteredEvent::Return(..) = result {
+            return Ok(result);
+        }
+    }
+
+    Ok(EncounteredEvent::Done)
+}
+
+/// Follow the branch of a multi-line conditional block which its condition selects.
+///
+/// The block is the last item of the stack, which has already been advanced past it.
+/// While the branch is followed the stack points to the block, followed by the index
+/// of the branch and the index in its items. If the branch is followed to its end the stack
+/// is restored to point past the block.
+fn follow_conditional_block(
+    block: &mut ConditionalBlock,
+    stack: &mut Stack,
+    buffer: &mut LineDataBuffer,
+    data: &mut FollowData,
+) -> FollowResult {
+    let branch_index = get_conditional_branch_index(block, data)?;
+
+    let stack_index = stack.len() - 1;
+
+    stack[stack_index] -= 1;
+    stack.extend_from_slice(&[branch_index, 0]);
+
+    let result = block.branches[branch_index].follow(stack, buffer, data)?;
+
+    if let EncounteredEvent::Done = result {
+        stack.truncate(stack_index + 1);
+        stack[stack_index] += 1;
+    }
+
+    Ok(result)
+}
+
+/// Evaluate the condition of a conditional block and get the index of the branch to follow.
+fn get_conditional_branch_index(
+    block: &ConditionalBlock,
+    data: &mut FollowData,
+) -> Result<usize, InklingError> {
+    let fulfilled = check_condition(&block.condition, data)?;
+
+    data.condition_coverage
+        .record(ConditionSite::Block(block.meta_data.clone()), fulfilled);
+
+    Ok(if fulfilled { 0 } else { 1 })
+}
 
 /// Internal utilities required to implement `Follow`.
 ///
@@ -170,8 +330,19 @@ pub trait FollowInternal: fmt::Debug {
         &mut self,
         stack_index: usize,
         stack: &Stack,
-    ) -> Result<Option<&mut Branch>, InternalError> {
+    ) -> Result<Option<&mut dyn Follow>, InternalError> {
         if stack_index < stack.len() - 1 {
+            let is_conditional_block = matches!(
+                stack.get(stack_index).and_then(|i| self.get_item(*i)),
+                Some(NodeItem::ConditionalBlock(..))
+            );
+
+            if is_conditional_block {
+                return self
+                    .get_block_branch_at_stack_index(stack_index, stack)
+                    .map(|branch| Some(branch as &mut dyn Follow));
+            }
+
             self.get_branches_at_stack_index(stack_index, stack)
                 .and_then(|branches| {
                     let branch_index = stack.get(stack_index + 1).ok_or(
@@ -198,11 +369,54 @@ pub trait FollowInternal: fmt::Debug {
                     )
                     .transpose()
                 })
+                .map(|branch| branch.map(|branch| branch as &mut dyn Follow))
         } else {
             Ok(None)
         }
     }
 
+    fn get_block_branch_at_stack_index(
+        &mut self,
+        stack_index: usize,
+        stack: &Stack,
+    ) -> Result<&mut BlockBranch, InternalError> {
+        let num_items = self.get_num_items();
+
+        let branch_index =
+            *stack
+                .get(stack_index + 1)
+                .ok_or(IncorrectNodeStackError::MissingBranchIndex {
+                    stack_index,
+                    stack: stack.clone(),
+                })?;
+
+        match stack.get(stack_index).and_then(|i| self.get_item_mut(*i)) {
+            Some(NodeItem::ConditionalBlock(block)) => {
+                let num_branches = block.branches.len();
+
+                block.branches.get_mut(branch_index).ok_or(
+                    IncorrectNodeStackError::OutOfBounds {
+                        stack_index: stack_index + 1,
+                        stack: stack.clone(),
+                        num_items: num_branches,
+                    }
+                    .into(),
+                )
+            }
+            Some(..) => Err(IncorrectNodeStackError::ExpectedBranchingPoint {
+                stack_index,
+                stack: stack.clone(),
+            }
+            .into()),
+            None => Err(IncorrectNodeStackError::OutOfBounds {
+                stack_index,
+                stack: stack.clone(),
+                num_items,
+            }
+            .into()),
+        }
+    }
+
     fn get_selected_branch(
         &mut self,
         branch_index: usize,
@@ -245,11 +459,21 @@ pub trait FollowInternal: fmt::Debug {
             )
             .and_then(|item| match item {
                 NodeItem::BranchingPoint(branches) => Ok(branches),
-                NodeItem::Line(..) => Err(IncorrectNodeStackError::ExpectedBranchingPoint {
-                    stack_index,
-                    stack: stack.clone(),
+                NodeItem::Line(..)
+                | NodeItem::Directive(..)
+                | NodeItem::Assignment(..)
+                | NodeItem::Call(..)
+                | NodeItem::Return(..)
+                | NodeItem::Thread(..)
+                | NodeItem::Label(..)
+                | NodeItem::AlternativeBlock(..)
+                | NodeItem::ConditionalBlock(..) => {
+                    Err(IncorrectNodeStackError::ExpectedBranchingPoint {
+                        stack_index,
+                        stack: stack.clone(),
+                    }
+                    .into())
                 }
-                .into()),
             })
     }
 
@@ -282,7 +506,7 @@ impl FollowInternal for RootNode {
     }
 }
 
-impl FollowInternal for Branch {
+impl FollowInternal for BlockBranch {
     fn get_item(&self, index: usize) -> Option<&NodeItem> {
         self.items.get(index)
     }
@@ -296,9 +520,34 @@ impl FollowInternal for Branch {
     }
 
     fn increment_num_visited(&mut self, _: &mut FollowData) -> Result<(), InternalError> {
+        Ok(())
+    }
+
+    fn iter_mut_items(&mut self) -> IterMut<'_, NodeItem> {
+        self.items.iter_mut()
+    }
+}
+
+impl FollowInternal for Branch {
+    fn get_item(&self, index: usize) -> Option<&NodeItem> {
+        self.items.get(index)
+    }
+
+    fn get_item_mut(&mut self, index: usize) -> Option<&mut NodeItem> {
+        self.items.get_mut(index)
+    }
+
+    fn get_num_items(&self) -> usize {
+        self.items.len()
+    }
+
+    fn increment_num_visited(&mut self, data: &mut FollowData) -> Result<(), InternalError> {
         self.num_visited += 1;
 
-        Ok(())
+        match &self.label {
+            Some(address) => increment_num_visited(address, data),
+            None => Ok(()),
+        }
     }
 
     fn iter_mut_items(&mut self) -> IterMut<NodeItem> {
@@ -310,7 +559,17 @@ impl FollowInternal for Branch {
 fn get_choices_from_branching_set(branches: &[Branch]) -> Vec<ChoiceInfo> {
     branches
         .iter()
-        .map(|branch| ChoiceInfo::from_choice(&branch.choice, branch.num_visited))
+        .map(|branch| {
+            let mut choice_info = ChoiceInfo::from_choice(&branch.choice, branch.num_visited);
+
+            // The first line of a branch is the display text of its choice, which is processed
+            // when the branch is followed. Use its current state to preview the text.
+            if let Some(NodeItem::Line(line)) = branch.items.first() {
+                choice_info.choice_data.display_text = line.clone();
+            }
+
+            choice_info
+        })
         .collect::<Vec<_>>()
 }
 
@@ -322,7 +581,7 @@ mod tests {
         error::InklingError,
         follow::FollowDataBuilder,
         knot::{get_num_visited, Address},
-        line::{InternalChoice, LineChunkBuilder},
+        line::{InternalChoice, InternalLine, LineChunkBuilder},
         node::builders::{BranchBuilder, BranchingPointBuilder, RootNodeBuilder},
     };
 
@@ -465,6 +724,36 @@ mod tests {
         assert_eq!(get_num_visited(&node.address, &data).unwrap(), 2);
     }
 
+    #[test]
+    fn following_past_a_label_increments_its_number_of_visits() {
+        let mut builder = RootNodeBuilder::from_address("tripoli", "with_family");
+        builder.add_gather(
+            Some("arrival".to_string()),
+            InternalLine::from_string("We arrived."),
+        );
+
+        let mut node = builder.build();
+        let address = node.address.with_label("arrival").unwrap();
+
+        if let NodeItem::Label(label) = &mut node.items[0] {
+            label.address = address.clone();
+        }
+
+        let mut buffer = Vec::new();
+        let mut data = mock_follow_data(&node);
+
+        assert_eq!(get_num_visited(&address, &data).unwrap(), 0);
+
+        node.follow(&mut vec![0], &mut buffer, &mut data).unwrap();
+        node.follow(&mut vec![0], &mut buffer, &mut data).unwrap();
+
+        assert_eq!(get_num_visited(&address, &data).unwrap(), 2);
+        assert_eq!(
+            data.label_visit_counts.get("tripoli.with_family.arrival"),
+            Some(&2)
+        );
+    }
+
     #[test]
     fn following_items_updates_stack() {
         let mut node = RootNodeBuilder::empty()
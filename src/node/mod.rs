@@ -11,5 +11,8 @@ mod parse;
 
 pub use follow::{Follow, Stack};
 pub(self) use node::builders;
-pub use node::{builders::RootNodeBuilder, Branch, NodeItem, RootNode};
+pub use node::{
+    builders::RootNodeBuilder, AlternativeBlock, BlockBranch, Branch, ConditionalBlock, NodeItem,
+    RootNode,
+};
 pub use parse::parse_root_node;
@@ -2,12 +2,19 @@
 
 use crate::{
     error::{parse::validate::ValidationError, utils::MetaData},
+    follow::FollowData,
     knot::Address,
-    line::{InternalChoice, InternalLine},
+    line::{
+        get_next_alternative_index, AlternativeKind, Assignment, Condition, Directive,
+        InternalChoice, InternalLine, LogicCall, Return, Thread,
+    },
     log::Logger,
     story::validate::{ValidateContent, ValidationData},
 };
 
+#[cfg(any(feature = "lints", not(feature = "random")))]
+use crate::log::Warning;
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -36,16 +43,133 @@ pub struct Branch {
     pub items: Vec<NodeItem>,
     /// Number of times the node has been visited in the story.
     pub num_visited: u32,
+    /// Address of the label of the choice, if it has one.
+    ///
+    /// Set to the full address of the label when the branch is validated.
+    pub label: Option<Address>,
 }
 
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
-/// Every item that a `Stitch` contains can be either some text producing asset
-/// or a branching point which the user must select an option from to continue.
+/// Every item that a `Stitch` contains can be either some text producing asset,
+/// a custom directive, a logic line or a branching point which the user must select
+/// an option from to continue.
 pub enum NodeItem {
     Line(InternalLine),
+    Directive(Directive),
+    Assignment(Assignment),
+    Call(LogicCall),
+    Return(Return),
+    Thread(Thread),
+    Label(Label),
     BranchingPoint(Vec<Branch>),
+    AlternativeBlock(AlternativeBlock),
+    ConditionalBlock(ConditionalBlock),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Label of a gather point, which marks its position in the tree of content.
+///
+/// The label is placed directly before the line of the gather. Labels of choices
+/// are kept with the choice of their `Branch`.
+pub struct Label {
+    /// Name of the label.
+    pub name: String,
+    /// Address of the label, used to count its visits.
+    ///
+    /// Set to the full address of the label when it is validated.
+    pub address: Address,
+    /// Information about the origin of the gather line.
+    pub meta_data: MetaData,
+}
+
+impl Label {
+    /// Create a label with its name as an unvalidated address.
+    pub fn new(name: String, meta_data: MetaData) -> Self {
+        Label {
+            address: Address::Raw(name.clone()),
+            name,
+            meta_data,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Multi-line block of alternatives, which selects one group of items when it is followed.
+///
+/// Which group is selected depends on the kind of alternative, like for alternatives
+/// within lines. Groups only contain lines, directives and nested blocks.
+pub struct AlternativeBlock {
+    /// Active list of group indices that will be used to select groups, in reverse order.
+    pub active_inds: Vec<usize>,
+    /// Which kind of alternative this represents.
+    pub kind: AlternativeKind,
+    /// Groups of content which the block will select and follow from.
+    pub items: Vec<Vec<NodeItem>>,
+    /// Information about the origin of the line which opened the block.
+    pub meta_data: MetaData,
+}
+
+impl AlternativeBlock {
+    /// Create a block which has not yet selected any group.
+    pub fn new(kind: AlternativeKind, items: Vec<Vec<NodeItem>>, meta_data: MetaData) -> Self {
+        AlternativeBlock {
+            active_inds: (0..items.len()).rev().collect(),
+            kind,
+            items,
+            meta_data,
+        }
+    }
+
+    /// Get the next group index in the alternative sequence.
+    pub fn get_next_index(&mut self, data: &mut FollowData) -> Option<usize> {
+        get_next_alternative_index(self.kind, &mut self.active_inds, self.items.len(), data)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Multi-line block of content which follows one of two branches depending on a condition.
+///
+/// Unlike blocks of alternatives the branches may contain choices. Like the branches
+/// of choices they are then a level of the `Stack`, marked by the index of the branch.
+pub struct ConditionalBlock {
+    /// Condition for which branch to follow.
+    pub condition: Condition,
+    /// Branches to follow if the condition is fulfilled and if it is not, in that order.
+    pub branches: Vec<BlockBranch>,
+    /// Information about the origin of the line which opened the block.
+    pub meta_data: MetaData,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Branch of content in a `ConditionalBlock`.
+pub struct BlockBranch {
+    /// Content grouped under this branch.
+    pub items: Vec<NodeItem>,
+}
+
+impl ConditionalBlock {
+    /// Create a block from the items of its fulfilled and unfulfilled branches.
+    pub fn new(
+        condition: Condition,
+        items: Vec<NodeItem>,
+        else_items: Vec<NodeItem>,
+        meta_data: MetaData,
+    ) -> Self {
+        ConditionalBlock {
+            condition,
+            branches: vec![BlockBranch { items }, BlockBranch { items: else_items }],
+            meta_data,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -92,6 +216,10 @@ impl ValidateContent for Branch {
     ) {
         let num_errors = error.num_errors();
 
+        if let Some(label) = self.label.as_mut() {
+            validate_label_address(label, current_location);
+        }
+
         self.choice
             .validate(error, log, current_location, meta_data, data);
 
@@ -121,11 +249,97 @@ impl ValidateContent for NodeItem {
         data: &ValidationData,
     ) {
         match self {
-            NodeItem::BranchingPoint(branches) => branches
-                .iter_mut()
-                .for_each(|item| item.validate(error, log, current_location, meta_data, data)),
+            NodeItem::BranchingPoint(branches) => {
+                #[cfg(feature = "lints")]
+                check_for_duplicate_choice_text(branches, log);
+
+                branches
+                    .iter_mut()
+                    .for_each(|item| item.validate(error, log, current_location, meta_data, data))
+            }
             NodeItem::Line(line) => line.validate(error, log, current_location, meta_data, data),
+            NodeItem::Directive(..) => (),
+            NodeItem::Label(label) => validate_label_address(&mut label.address, current_location),
+            NodeItem::Assignment(assignment) => {
+                assignment.validate(error, log, current_location, meta_data, data)
+            }
+            NodeItem::Call(call) => call.validate(error, log, current_location, meta_data, data),
+            NodeItem::Return(return_line) => {
+                return_line.validate(error, log, current_location, meta_data, data)
+            }
+            NodeItem::Thread(thread) => {
+                thread.validate(error, log, current_location, meta_data, data)
+            }
+            NodeItem::AlternativeBlock(block) => {
+                #[cfg(not(feature = "random"))]
+                if block.kind == AlternativeKind::Shuffle {
+                    log.add_warning(Warning::ShuffleSequenceNoRandom, &block.meta_data);
+                }
+
+                block
+                    .items
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|item| item.validate(error, log, current_location, meta_data, data))
+            }
+            NodeItem::ConditionalBlock(block) => {
+                block
+                    .condition
+                    .validate(error, log, current_location, &block.meta_data, data);
+
+                block
+                    .branches
+                    .iter_mut()
+                    .flat_map(|branch| branch.items.iter_mut())
+                    .for_each(|item| item.validate(error, log, current_location, meta_data, data))
+            }
+        };
+    }
+}
+
+/// Log a warning for every choice with the same selection text as another in the set.
+///
+/// Only choices with text that does not depend on the story state are compared. Choices
+/// with different conditions are assumed to not be presented together.
+/// Set the address of a label to its full address in the stitch at the current location.
+///
+/// Labels are always in the stitch that they are validated from, so they need no lookup.
+fn validate_label_address(address: &mut Address, current_location: &Address) {
+    if let Address::Raw(name) = address {
+        if let Ok(label_address) = current_location.with_label(name) {
+            *address = label_address;
+        }
+    }
+}
+
+#[cfg(feature = "lints")]
+fn check_for_duplicate_choice_text(branches: &[Branch], log: &mut Logger) {
+    let mut seen_choices: Vec<(String, &InternalChoice)> = Vec::new();
+
+    for choice in branches
+        .iter()
+        .map(|branch| &branch.choice)
+        .filter(|choice| !choice.is_fallback)
+    {
+        let text = match choice.selection_text.lock().unwrap().get_static_text() {
+            Some(text) => text,
+            None => continue,
         };
+
+        let original = seen_choices
+            .iter()
+            .find(|(other_text, other)| other_text == &text && other.condition == choice.condition);
+
+        match original {
+            Some((_, original)) => log.add_warning(
+                Warning::DuplicateChoiceText {
+                    text,
+                    prev_meta_data: original.meta_data.clone(),
+                },
+                &choice.meta_data,
+            ),
+            None => seen_choices.push((text, choice)),
+        }
     }
 }
 
@@ -136,7 +350,7 @@ pub mod builders {
     //! the `test` profile is activated. These functions are not meant to be used internally
     //! except by tests, since they do not perform any validation of the content.
 
-    use super::{Branch, NodeItem, RootNode};
+    use super::{Branch, Label, NodeItem, RootNode};
 
     use crate::{
         knot::{Address, AddressKind},
@@ -187,6 +401,15 @@ pub mod builders {
             self.add_item(NodeItem::Line(line));
         }
 
+        pub fn add_gather(&mut self, label: Option<String>, line: InternalLine) {
+            if let Some(name) = label {
+                let meta_data = line.meta_data.clone();
+                self.add_item(NodeItem::Label(Label::new(name, meta_data)));
+            }
+
+            self.add_line(line);
+        }
+
         #[cfg(test)]
         pub fn empty() -> Self {
             Self::from_address("", "")
@@ -238,10 +461,13 @@ pub mod builders {
         }
 
         pub fn build(self) -> Branch {
+            let label = self.choice.label.clone().map(Address::Raw);
+
             Branch {
                 choice: self.choice,
                 items: self.items,
                 num_visited: 0,
+                label,
             }
         }
 
@@ -257,6 +483,15 @@ pub mod builders {
             self.add_item(NodeItem::Line(line));
         }
 
+        pub fn add_gather(&mut self, label: Option<String>, line: InternalLine) {
+            if let Some(name) = label {
+                let meta_data = line.meta_data.clone();
+                self.add_item(NodeItem::Label(Label::new(name, meta_data)));
+            }
+
+            self.add_line(line);
+        }
+
         #[cfg(test)]
         pub fn with_item(mut self, item: NodeItem) -> Self {
             self.items.push(item);
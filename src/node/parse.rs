@@ -10,7 +10,7 @@ use crate::{
     line::{InternalLine, ParsedLineKind},
     node::{
         builders::{BranchBuilder, RootNodeBuilder},
-        Branch, RootNode,
+        AlternativeBlock, Branch, ConditionalBlock, NodeItem, RootNode,
     },
 };
 
@@ -20,7 +20,13 @@ use crate::{
 /// of line content from it.
 pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> RootNode {
     let mut builder = RootNodeBuilder::from_address(knot, stitch);
+    add_node_items(&mut builder, lines);
 
+    builder.build()
+}
+
+/// Add the items of a set of lines, with the branching trees of their choices, to a builder.
+fn add_node_items(builder: &mut RootNodeBuilder, lines: &[ParsedLineKind]) {
     let mut index = 0;
 
     while index < lines.len() {
@@ -30,14 +36,32 @@ pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> Ro
             ParsedLineKind::Line(line) => {
                 builder.add_line(line.clone());
             }
+            ParsedLineKind::Directive(directive) => {
+                builder.add_item(NodeItem::Directive(directive.clone()));
+            }
+            ParsedLineKind::Assignment(assignment) => {
+                builder.add_item(NodeItem::Assignment(assignment.clone()));
+            }
+            ParsedLineKind::Call(call) => {
+                builder.add_item(NodeItem::Call(call.clone()));
+            }
+            ParsedLineKind::Return(return_line) => {
+                builder.add_item(NodeItem::Return(return_line.clone()));
+            }
+            ParsedLineKind::Thread(thread) => {
+                builder.add_item(NodeItem::Thread(thread.clone()));
+            }
+            ParsedLineKind::AlternativeBlock { .. } | ParsedLineKind::ConditionalBlock { .. } => {
+                builder.add_item(get_block_item(line));
+            }
             ParsedLineKind::Choice { level, .. } => {
                 let (branches, gather) =
                     parse_branching_choice_set_and_gather(&mut index, *level, lines);
 
                 builder.add_branching_choice(branches);
 
-                if let Some(line) = gather {
-                    builder.add_line(line);
+                if let Some((label, line)) = gather {
+                    builder.add_gather(label, line);
 
                     // `parse_choice_set_with_gather` advances the index to the next line
                     // after this group if a gather was found, but this loop also does that
@@ -45,21 +69,72 @@ pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> Ro
                     index -= 1;
                 }
             }
-            ParsedLineKind::Gather { line, .. } => {
-                builder.add_line(line.clone());
+            ParsedLineKind::Gather { label, line, .. } => {
+                builder.add_gather(label.clone(), line.clone());
             }
         };
 
         index += 1;
     }
+}
 
-    builder.build()
+/// Get the node item of a line which is not part of a branching set.
+///
+/// Blocks are converted along with all of their nested content. Blocks of alternatives
+/// never contain choices or gathers, which are errors when the lines are parsed, but
+/// the branches of conditional blocks are parsed into branching trees like a root node.
+fn get_block_item(line: &ParsedLineKind) -> NodeItem {
+    match line {
+        ParsedLineKind::AlternativeBlock {
+            kind,
+            items,
+            meta_data,
+        } => {
+            let items = items
+                .iter()
+                .map(|lines| lines.iter().map(get_block_item).collect())
+                .collect();
+
+            NodeItem::AlternativeBlock(AlternativeBlock::new(*kind, items, meta_data.clone()))
+        }
+        ParsedLineKind::ConditionalBlock {
+            condition,
+            items,
+            else_items,
+            meta_data,
+        } => {
+            let get_items = |lines: &[ParsedLineKind]| {
+                let mut builder = RootNodeBuilder::from_address("", "");
+                add_node_items(&mut builder, lines);
+
+                builder.build().items
+            };
+
+            NodeItem::ConditionalBlock(ConditionalBlock::new(
+                condition.clone(),
+                get_items(items),
+                get_items(else_items),
+                meta_data.clone(),
+            ))
+        }
+        ParsedLineKind::Directive(directive) => NodeItem::Directive(directive.clone()),
+        ParsedLineKind::Assignment(assignment) => NodeItem::Assignment(assignment.clone()),
+        ParsedLineKind::Call(call) => NodeItem::Call(call.clone()),
+        ParsedLineKind::Return(return_line) => NodeItem::Return(return_line.clone()),
+        ParsedLineKind::Thread(thread) => NodeItem::Thread(thread.clone()),
+        ParsedLineKind::Line(line) | ParsedLineKind::Gather { line, .. } => {
+            NodeItem::Line(line.clone())
+        }
+        ParsedLineKind::Choice { choice_data, .. } => {
+            NodeItem::Line(choice_data.display_text.clone())
+        }
+    }
 }
 
 /// Parse a set of branching points and the gather it ended with.
 ///
 /// After parsing a group of choices, check whether it ended because of a `Gather`.
-/// If so, return the `NodeItem::Line` object from that gather so that it can be appended
+/// If so, return the label and line of that gather so that they can be appended
 /// *after* the node, not inside it.
 ///
 /// When the function returns the `index` will point to the line directly after
@@ -69,13 +144,13 @@ fn parse_branching_choice_set_and_gather(
     index: &mut usize,
     current_level: u32,
     lines: &[ParsedLineKind],
-) -> (Vec<Branch>, Option<InternalLine>) {
+) -> (Vec<Branch>, Option<(Option<String>, InternalLine)>) {
     let node = parse_branching_choice_set(index, current_level, lines);
     let mut gather = None;
 
-    if let Some(ParsedLineKind::Gather { level, line }) = lines.get(*index) {
+    if let Some(ParsedLineKind::Gather { level, label, line }) = lines.get(*index) {
         if *level == current_level {
-            gather.replace(line.clone());
+            gather.replace((label.clone(), line.clone()));
             *index += 1;
         }
     }
@@ -147,6 +222,24 @@ fn parse_branch_at_given_level(
             ParsedLineKind::Line(line) => {
                 builder.add_line(line.clone());
             }
+            ParsedLineKind::Directive(directive) => {
+                builder.add_item(NodeItem::Directive(directive.clone()));
+            }
+            ParsedLineKind::Assignment(assignment) => {
+                builder.add_item(NodeItem::Assignment(assignment.clone()));
+            }
+            ParsedLineKind::Call(call) => {
+                builder.add_item(NodeItem::Call(call.clone()));
+            }
+            ParsedLineKind::Return(return_line) => {
+                builder.add_item(NodeItem::Return(return_line.clone()));
+            }
+            ParsedLineKind::Thread(thread) => {
+                builder.add_item(NodeItem::Thread(thread.clone()));
+            }
+            ParsedLineKind::AlternativeBlock { .. } | ParsedLineKind::ConditionalBlock { .. } => {
+                builder.add_item(get_block_item(line));
+            }
             ParsedLineKind::Choice { level, .. } if *level == current_level => break,
             ParsedLineKind::Choice { level, .. } if *level > current_level => {
                 let (branching_set, gather) =
@@ -154,8 +247,8 @@ fn parse_branch_at_given_level(
 
                 builder.add_branching_choice(branching_set);
 
-                if let Some(line) = gather {
-                    builder.add_line(line);
+                if let Some((label, line)) = gather {
+                    builder.add_gather(label, line);
                 }
 
                 // `parse_branching_choice_set_and_gather` advances the index to the next line
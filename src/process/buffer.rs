@@ -2,26 +2,80 @@
 
 use crate::{
     follow::{LineDataBuffer, LineText},
-    story::{Line, LineBuffer},
+    story::{ItemBuffer, Line, StoryItem},
 };
 
-/// Process internal lines to a user-ready state.
+#[cfg(test)]
+use crate::story::LineBuffer;
+
+#[cfg(test)]
+/// Process internal lines to a user-ready state, discarding custom directives.
 pub fn process_buffer(into_buffer: &mut LineBuffer, from_buffer: LineDataBuffer) {
-    let mut iter = from_buffer
+    let mut item_buffer = Vec::new();
+    process_buffer_into_items(&mut item_buffer, from_buffer, false);
+
+    into_buffer.extend(item_buffer.into_iter().filter_map(|item| match item {
+        StoryItem::Line(line) => Some(line),
+        StoryItem::Directive(..) => None,
+    }));
+}
+
+/// Process internal lines and custom directives to a user-ready state.
+///
+/// Directives do not break glue between the lines of text around them. Tags of lines
+/// without text, such as tags written on their own line, are set to the next line of text.
+///
+/// If the lines are continued by lines which are processed later, the last line keeps
+/// its glue to them.
+pub fn process_buffer_into_items(
+    into_buffer: &mut ItemBuffer,
+    from_buffer: LineDataBuffer,
+    is_continued: bool,
+) {
+    let mut pending_tags = Vec::new();
+
+    let lines = from_buffer
         .into_iter()
-        .filter(|line| !line.text.trim().is_empty())
-        .peekable();
+        .filter_map(|mut line| {
+            if line.directive.is_some() {
+                Some(line)
+            } else if line.text.trim().is_empty() {
+                pending_tags.append(&mut line.tags);
+                None
+            } else {
+                if !pending_tags.is_empty() {
+                    pending_tags.append(&mut line.tags);
+                    line.tags = std::mem::take(&mut pending_tags);
+                }
+
+                Some(line)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(directive) = &line.directive {
+            into_buffer.push(StoryItem::Directive(directive.clone()));
+            continue;
+        }
 
-    while let Some(mut line) = iter.next() {
-        let (glue, whitespace) = check_for_whitespace_and_glue(&line, iter.peek());
+        let next_line = lines[i + 1..].iter().find(|line| line.directive.is_none());
+        let (glue, whitespace) = match next_line {
+            None if is_continued => (line.glue_end, line.glue_end && line.text.ends_with(' ')),
+            _ => check_for_whitespace_and_glue(line, next_line),
+        };
+
+        let mut line = line.clone();
 
         trim_extra_whitespace(&mut line);
         add_line_ending(&mut line, glue, whitespace);
 
-        into_buffer.push(Line {
+        into_buffer.push(StoryItem::Line(Line {
             text: line.text,
             tags: line.tags,
-        });
+            glue_end: glue,
+            divert_end: line.divert_end,
+        }));
     }
 }
 
@@ -70,7 +124,7 @@ fn add_line_ending(line: &mut LineText, glue: bool, whitespace: bool) {
 mod tests {
     use super::*;
 
-    use crate::follow::LineTextBuilder;
+    use crate::{follow::LineTextBuilder, line::Directive};
 
     #[test]
     fn processing_line_buffer_removes_empty_lines() {
@@ -202,6 +256,53 @@ mod tests {
         assert!(!processed[1].text.starts_with(' '));
     }
 
+    #[test]
+    fn processed_lines_are_marked_with_glue_and_divert_endings() {
+        let line1 = LineTextBuilder::from_string("Glued to the next")
+            .with_glue_end()
+            .build();
+        let line2 = LineTextBuilder::from_string("ended by a divert")
+            .with_divert_end()
+            .build();
+        let line3 = LineTextBuilder::from_string("Glued to nothing")
+            .with_glue_end()
+            .build();
+
+        let buffer = vec![line1, line2, line3];
+
+        let mut processed = Vec::new();
+        process_buffer(&mut processed, buffer);
+
+        assert!(processed[0].glue_end);
+        assert!(!processed[0].divert_end);
+        assert!(!processed[1].glue_end);
+        assert!(processed[1].divert_end);
+        assert!(!processed[2].glue_end);
+    }
+
+    #[test]
+    fn tags_of_lines_without_text_are_set_to_the_next_line_of_text() {
+        let tags = vec!["tag 1".to_string(), "tag 2".to_string()];
+
+        let buffer = vec![
+            LineTextBuilder::from_string("")
+                .with_tags(&tags[..1])
+                .build(),
+            LineTextBuilder::from_string(" ").build(),
+            LineTextBuilder::from_string("Hello")
+                .with_tags(&tags[1..])
+                .build(),
+            LineTextBuilder::from_string("World").build(),
+        ];
+
+        let mut processed = Vec::new();
+        process_buffer(&mut processed, buffer);
+
+        assert_eq!(processed.len(), 2);
+        assert_eq!(processed[0].tags, tags);
+        assert!(processed[1].tags.is_empty());
+    }
+
     #[test]
     fn processing_line_buffer_preserves_tags() {
         let text = "Mr. and Mrs. Doubtfire";
@@ -229,4 +330,34 @@ mod tests {
 
         assert_eq!(&processed[0].text, "A line with just enough whitespace\n");
     }
+
+    #[test]
+    fn directives_are_kept_in_order_without_breaking_glue_between_lines() {
+        let directive = Directive {
+            name: "@cue".to_string(),
+            arguments: Vec::new(),
+        };
+
+        let buffer = vec![
+            LineTextBuilder::from_string("Glued")
+                .with_glue_end()
+                .build(),
+            LineText::from_directive(directive.clone()),
+            LineTextBuilder::from_string("together").build(),
+        ];
+
+        let mut processed = Vec::new();
+        process_buffer_into_items(&mut processed, buffer, false);
+
+        assert_eq!(processed.len(), 3);
+        assert_eq!(processed[1], StoryItem::Directive(directive));
+
+        match (&processed[0], &processed[2]) {
+            (StoryItem::Line(first), StoryItem::Line(second)) => {
+                assert_eq!(&first.text, "Glued");
+                assert_eq!(&second.text, "together\n");
+            }
+            other => panic!("expected two `StoryItem::Line` objects but got {:?}", other),
+        }
+    }
 }
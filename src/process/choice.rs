@@ -1,11 +1,12 @@
 //! Process and filter choices to present to the user.
 
 use crate::{
+    consts::CHOICE_WEIGHT_TAG,
     error::{InklingError, InternalError},
     follow::{ChoiceInfo, FollowData},
-    line::InternalLine,
+    line::{InternalChoice, InternalLine},
     process::{check_condition, process_line},
-    story::Choice,
+    story::{variable_history::VariableObservers, Choice, ConditionSite, Tag},
 };
 
 use std::ops::DerefMut;
@@ -79,11 +80,23 @@ fn zip_choices_with_filter_values(
                 process_choice_text_and_tags(Arc::new(Mutex::new(independent_text)), data)
             }?;
 
+            let display_text = if keep {
+                process_choice_display_text(choice_data, data)?
+            } else {
+                String::new()
+            };
+
+            let weight = get_choice_weight(&tags);
+
             Ok((
                 keep,
                 Choice {
                     text,
+                    display_text,
+                    raw_text: choice_data.raw_text.clone(),
                     tags,
+                    id: choice_data.id.clone(),
+                    weight,
                     index: i,
                 },
             ))
@@ -104,18 +117,53 @@ fn process_choice_text_and_tags(
     process_line(&mut line, &mut data_buffer, data).map_err(|err| InternalError::from(err))?;
 
     let mut buffer = String::new();
+    let mut tags = Vec::new();
 
     for data in data_buffer.into_iter() {
         buffer.push_str(&data.text);
+        tags.extend(data.tags);
     }
 
-    Ok((buffer.trim().to_string(), line.tags.clone()))
+    Ok((buffer.trim().to_string(), tags))
+}
+
+/// Get the weight of a choice from its `weight: <number>` tag, or 1 if it has none.
+///
+/// If several weight tags are set the last one is used.
+fn get_choice_weight(tags: &[String]) -> u32 {
+    tags.iter()
+        .filter_map(|tag| {
+            Tag::parse_key_value(tag)
+                .filter(|tag| tag.key == CHOICE_WEIGHT_TAG)
+                .and_then(|tag| tag.value.parse::<u32>().ok())
+        })
+        .next_back()
+        .unwrap_or(1)
+}
+
+/// Process the text that will be added to the buffer if a choice is made.
+///
+/// Both the line and the story data are copies, so that processing the text does not
+/// affect the story. The copy has no observers of variables, since its changes are not kept.
+fn process_choice_display_text(
+    choice_data: &InternalChoice,
+    data: &FollowData,
+) -> Result<String, InklingError> {
+    let display_text = Arc::new(Mutex::new(choice_data.display_text.clone()));
+    let mut independent_data = data.clone();
+    independent_data.variable_observers = VariableObservers::default();
+
+    // Extra whitespace between words is trimmed like when the line is added to the buffer
+    process_choice_text_and_tags(display_text, &mut independent_data)
+        .map(|(text, _)| text.split_whitespace().collect::<Vec<_>>().join(" "))
 }
 
 /// Return a list of whether choices fulfil their conditions.
+///
+/// The outcome of every choice condition is recorded in the condition coverage.
 fn check_choices_for_conditions(
     choices: &[ChoiceInfo],
-    data: &FollowData,
+    data: &mut FollowData,
     keep_only_fallback: bool,
 ) -> Result<Vec<bool>, InklingError> {
     let mut checked_conditions = Vec::new();
@@ -125,11 +173,19 @@ fn check_choices_for_conditions(
         choice_data,
     } in choices.iter()
     {
-        let mut keep = choice_data
-            .condition
-            .as_ref()
-            .map(|condition| check_condition(condition, data).unwrap())
-            .unwrap_or(true);
+        let mut keep = match choice_data.condition.as_ref() {
+            Some(condition) => {
+                let fulfilled = check_condition(condition, data)?;
+
+                data.condition_coverage.record(
+                    ConditionSite::Choice(choice_data.meta_data.clone()),
+                    fulfilled,
+                );
+
+                fulfilled
+            }
+            None => true,
+        };
 
         keep = keep
             && (choice_data.is_sticky || *num_visited == 0)
@@ -214,7 +270,9 @@ mod tests {
     }
 
     fn get_true_like_condition(variable: Variable, negate: bool) -> Condition {
-        let kind = StoryCondition::IsTrueLike { variable };
+        let kind = StoryCondition::IsTrueLike {
+            expression: variable.into(),
+        };
 
         ConditionBuilder::from_kind(&kind.into(), negate).build()
     }
@@ -376,4 +434,18 @@ mod tests {
         assert_eq!(presented_choices.len(), 1);
         assert_eq!(&presented_choices[0].text, "Hello twice!");
     }
+
+    #[test]
+    fn choice_weight_is_read_from_last_weight_tag_or_defaults_to_one() {
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(get_choice_weight(&tags(&[])), 1);
+        assert_eq!(get_choice_weight(&tags(&["weight: 3"])), 3);
+        assert_eq!(
+            get_choice_weight(&tags(&["weight:2", "mood", "weight : 5"])),
+            5
+        );
+        assert_eq!(get_choice_weight(&tags(&["weight: often"])), 1);
+        assert_eq!(get_choice_weight(&tags(&["heavy: 4"])), 1);
+    }
 }
@@ -12,7 +12,10 @@ use crate::{
 use std::cmp::Ordering;
 
 /// Check whether a single condition is fulfilled.
-pub fn check_condition(condition: &Condition, data: &FollowData) -> Result<bool, InklingError> {
+///
+/// Functions which are called in the condition are followed in the story state, so the
+/// changes that they make to it are kept.
+pub fn check_condition(condition: &Condition, data: &mut FollowData) -> Result<bool, InklingError> {
     let evaluator = |kind: &StoryCondition| match kind {
         StoryCondition::Comparison {
             lhs_variable,
@@ -29,24 +32,34 @@ pub fn check_condition(condition: &Condition, data: &FollowData) -> Result<bool,
             }
         }
         .map_err(|err| err.into()),
-        StoryCondition::IsTrueLike { variable } => match variable.as_value(data)? {
+        StoryCondition::IsTrueLike { expression } => match evaluate_expression(expression, data)? {
             Variable::Bool(value) => Ok(value),
             Variable::Float(value) => Ok(value != 0.0),
             Variable::Int(value) => Ok(value != 0),
             Variable::String(s) => Ok(s.len() > 0),
-            Variable::Divert(..) => Err(VariableError::from_kind(
-                variable.clone(),
+            Variable::List(list) => Ok(!list.is_empty()),
+            variable @ Variable::Divert(..) => Err(VariableError::from_kind(
+                variable,
                 VariableErrorKind::InvalidComparison {
                     other: Variable::Bool(true),
                     comparison: Ordering::Equal,
                 },
             )
             .into()),
-            Variable::Address(..) => unreachable!("`as_value` will not return an `Address`"),
+            Variable::Address(..) => unreachable!("expressions will not evaluate to an `Address`"),
         },
+        StoryCondition::Contains { list, items } => {
+            let list = evaluate_expression(list, data)?;
+
+            items.iter().try_fold(true, |contains, item| {
+                let item = evaluate_expression(item, data)?;
+
+                Ok(list.contains(&item)? && contains)
+            })
+        }
     };
 
-    condition.evaluate(&evaluator)
+    condition.evaluate(evaluator)
 }
 
 #[cfg(test)]
@@ -58,7 +71,7 @@ mod tests {
         knot::Address,
         line::{
             expression::{Expression, Operand},
-            ConditionBuilder,
+            ConditionBuilder, List, ListItem,
         },
         story::types::VariableInfo,
     };
@@ -90,7 +103,9 @@ mod tests {
     }
 
     fn get_true_like_condition(variable: Variable, negate: bool) -> Condition {
-        let kind = StoryCondition::IsTrueLike { variable };
+        let kind = StoryCondition::IsTrueLike {
+            expression: variable.into(),
+        };
 
         ConditionBuilder::from_kind(&kind.into(), negate).build()
     }
@@ -118,7 +133,7 @@ mod tests {
 
     #[test]
     fn conditions_can_compare_variable_values() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let integer_condition = get_variable_comparison_condition(
             Variable::from(5),
@@ -134,62 +149,112 @@ mod tests {
             false,
         );
 
-        assert!(check_condition(&integer_condition, &data).unwrap());
-        assert!(!check_condition(&string_condition, &data).unwrap());
+        assert!(check_condition(&integer_condition, &mut data).unwrap());
+        assert!(!check_condition(&string_condition, &mut data).unwrap());
+    }
+
+    #[test]
+    fn contains_conditions_check_that_all_items_are_in_the_list() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let sword = ListItem::new("inventory", "sword", 1);
+        let torch = ListItem::new("inventory", "torch", 2);
+
+        let get_condition = |items: &[&ListItem]| {
+            let kind = StoryCondition::Contains {
+                list: Expression {
+                    head: Operand::Variable(Variable::List(List::from(sword.clone()))),
+                    tail: Vec::new(),
+                },
+                items: items
+                    .iter()
+                    .map(|&item| Expression {
+                        head: Operand::Variable(Variable::List(List::from(item.clone()))),
+                        tail: Vec::new(),
+                    })
+                    .collect(),
+            };
+
+            ConditionBuilder::from_kind(&kind.into(), false).build()
+        };
+
+        assert!(check_condition(&get_condition(&[&sword]), &mut data).unwrap());
+        assert!(!check_condition(&get_condition(&[&torch]), &mut data).unwrap());
+        assert!(!check_condition(&get_condition(&[&sword, &torch]), &mut data).unwrap());
+    }
+
+    #[test]
+    fn contains_conditions_yield_error_if_variables_are_not_lists() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let kind = StoryCondition::Contains {
+            list: Expression {
+                head: Operand::Variable(Variable::from(5)),
+                tail: Vec::new(),
+            },
+            items: vec![Expression {
+                head: Operand::Variable(Variable::from(5)),
+                tail: Vec::new(),
+            }],
+        };
+
+        let condition = ConditionBuilder::from_kind(&kind.into(), false).build();
+
+        assert!(check_condition(&condition, &mut data).is_err());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_boolean_and_true() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let true_condition = get_true_like_condition(Variable::from(true), false);
         let false_condition = get_true_like_condition(Variable::from(false), false);
 
-        assert!(check_condition(&true_condition, &data).unwrap());
-        assert!(!check_condition(&false_condition, &data).unwrap());
+        assert!(check_condition(&true_condition, &mut data).unwrap());
+        assert!(!check_condition(&false_condition, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_numeric_and_non_zero() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let int_equal = get_true_like_condition(Variable::from(0), false);
         let int_greater = get_true_like_condition(Variable::from(1), false);
         let int_less = get_true_like_condition(Variable::from(-1), false);
 
-        assert!(check_condition(&int_greater, &data).unwrap());
-        assert!(check_condition(&int_less, &data).unwrap());
-        assert!(!check_condition(&int_equal, &data).unwrap());
+        assert!(check_condition(&int_greater, &mut data).unwrap());
+        assert!(check_condition(&int_less, &mut data).unwrap());
+        assert!(!check_condition(&int_equal, &mut data).unwrap());
 
         let float_equal = get_true_like_condition(Variable::from(0.0), false);
         let float_greater = get_true_like_condition(Variable::from(0.1), false);
         let float_less = get_true_like_condition(Variable::from(-0.1), false);
 
-        assert!(check_condition(&float_greater, &data).unwrap());
-        assert!(check_condition(&float_less, &data).unwrap());
-        assert!(!check_condition(&float_equal, &data).unwrap());
+        assert!(check_condition(&float_greater, &mut data).unwrap());
+        assert!(check_condition(&float_less, &mut data).unwrap());
+        assert!(!check_condition(&float_equal, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_string_with_non_zero_length() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let string_word = get_true_like_condition(Variable::from("non-empty"), false);
         let string_char = get_true_like_condition(Variable::from("c"), false);
         let string_empty = get_true_like_condition(Variable::from(""), false);
 
-        assert!(check_condition(&string_word, &data).unwrap());
-        assert!(check_condition(&string_char, &data).unwrap());
-        assert!(!check_condition(&string_empty, &data).unwrap());
+        assert!(check_condition(&string_word, &mut data).unwrap());
+        assert!(check_condition(&string_char, &mut data).unwrap());
+        assert!(!check_condition(&string_empty, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_condition_yields_error_if_variable_is_divert() {
-        let data = mock_follow_data(&[("tripoli", "cinema", 1)], &[]);
+        let mut data = mock_follow_data(&[("tripoli", "cinema", 1)], &[]);
 
         let variable = Variable::Divert(Address::from_parts_unchecked("tripoli", Some("cinema")));
         let divert = get_true_like_condition(variable, false);
 
-        assert!(check_condition(&divert, &data).is_err());
+        assert!(check_condition(&divert, &mut data).is_err());
     }
 }
@@ -1,9 +1,17 @@
 //! Processing of nested line chunks into text content.
 
 use crate::{
-    error::runtime::internal::{ProcessError, ProcessErrorKind},
+    error::{
+        runtime::internal::{ProcessError, ProcessErrorKind},
+        InklingError,
+    },
     follow::{EncounteredEvent, FollowData, LineDataBuffer, LineText},
-    line::{evaluate_expression, Alternative, Content, InternalLine, LineChunk},
+    knot::{Address, AddressKind},
+    line::{
+        count_conditions_in_content, evaluate_expression, follow_expression, format_condition,
+        format_expression, Alternative, AlternativeKind, Content, InternalLine, LineChunk, Thread,
+        Variable,
+    },
     process::check_condition,
 };
 
@@ -15,13 +23,22 @@ pub fn process_line(
 ) -> Result<EncounteredEvent, ProcessError> {
     let mut text_buffer = String::new();
 
+    data.condition_coverage.begin_line(&line.meta_data);
     let result = process_chunk(&mut line.chunk, &mut text_buffer, data);
+    let tags = process_tags(line, data)?;
+
+    if data.proofreading {
+        text_buffer.clear();
+        write_all_variants(&line.chunk, &mut text_buffer, data);
+    }
 
     let line_text = LineText {
         text: text_buffer,
         glue_begin: line.glue_begin,
         glue_end: line.glue_end,
-        tags: line.tags.clone(),
+        divert_end: matches!(result, Ok(EncounteredEvent::Divert(..))),
+        tags,
+        directive: None,
     };
 
     buffer.push(line_text);
@@ -29,21 +46,79 @@ pub fn process_line(
     result
 }
 
+/// Get the tags of a line, with its dynamic tags processed into text.
+///
+/// Dynamic tags which are processed into empty text are removed.
+fn process_tags(
+    line: &mut InternalLine,
+    data: &mut FollowData,
+) -> Result<Vec<String>, ProcessError> {
+    if line.dynamic_tags.is_empty() {
+        return Ok(line.tags.clone());
+    }
+
+    let mut tags = line.tags.clone();
+
+    for (index, chunk) in line.dynamic_tags.iter_mut() {
+        let mut buffer = String::new();
+        process_chunk(chunk, &mut buffer, data)?;
+
+        tags[*index] = buffer.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+
+    tags.retain(|tag| !tag.is_empty());
+
+    Ok(tags)
+}
+
+/// Evaluate the address and arguments of a thread for the story to follow.
+///
+/// The arguments are set as the parameters of the knot that the thread leads to,
+/// like for diverts.
+pub fn process_thread(
+    thread: &Thread,
+    data: &mut FollowData,
+) -> Result<EncounteredEvent, InklingError> {
+    data.divert_arguments = thread
+        .arguments
+        .iter()
+        .map(|argument| evaluate_expression(argument, data))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EncounteredEvent::Thread(get_divert_target(
+        &thread.address,
+        data,
+    )?))
+}
+
 /// Process and add the content of a `LineChunk` to a string buffer.
 ///
 /// If a condition is set to the chunk, it will be evaluated. If it evaluates to true,
 /// the items in the `items` field will be processed. If not, the items in the `else_items`
 /// field will be.
+///
+/// The outcome of the condition is recorded in the condition coverage. Conditions in
+/// the content that is not processed are skipped over to keep the numbering of conditions
+/// in the line consistent.
 fn process_chunk(
     chunk: &mut LineChunk,
     buffer: &mut String,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
+    let mut num_skipped_after = 0;
+
     let items = match &chunk.condition {
         Some(ref condition) => {
-            if check_condition(condition, data)? {
+            let fulfilled = check_condition(condition, data)?;
+            data.condition_coverage.record_in_line(fulfilled);
+
+            if fulfilled {
+                num_skipped_after = count_conditions_in_content(&chunk.else_items);
                 chunk.items.iter_mut()
             } else {
+                let num_skipped = count_conditions_in_content(&chunk.items);
+                data.condition_coverage.skip_in_line(num_skipped);
+
                 chunk.else_items.iter_mut()
             }
         }
@@ -58,6 +133,8 @@ fn process_chunk(
         }
     }
 
+    data.condition_coverage.skip_in_line(num_skipped_after);
+
     Ok(EncounteredEvent::Done)
 }
 
@@ -69,14 +146,28 @@ fn process_content(
 ) -> Result<EncounteredEvent, ProcessError> {
     match item {
         Content::Alternative(alternative) => process_alternative(alternative, buffer, data),
-        Content::Divert(address) => Ok(EncounteredEvent::Divert(address.clone())),
+        Content::Divert(address, arguments) => {
+            data.divert_arguments = arguments
+                .iter()
+                .map(|argument| evaluate_expression(argument, data))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(EncounteredEvent::Divert(get_divert_target(address, data)?))
+        }
         Content::Empty => {
             buffer.push(' ');
             Ok(EncounteredEvent::Done)
         }
         Content::Expression(expression) => {
-            let variable = evaluate_expression(&expression, data)?;
-            buffer.push_str(&variable.to_string_internal(data)?);
+            let mut function_lines = Vec::new();
+            let variable = follow_expression(expression, &mut function_lines, data)?;
+
+            write_function_text(&function_lines, buffer);
+
+            if let Some(variable) = variable {
+                buffer.push_str(&variable.to_string_internal(data)?);
+            }
+
             Ok(EncounteredEvent::Done)
         }
         Content::Nested(chunk) => process_chunk(chunk, buffer, data),
@@ -87,24 +178,135 @@ fn process_content(
     }
 }
 
+/// Add the text that functions wrote while they were called in a line to a string buffer.
+///
+/// The lines of the functions are written into the line, separated by spaces.
+fn write_function_text(function_lines: &[LineText], buffer: &mut String) {
+    let text = function_lines
+        .iter()
+        .map(|line| line.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    buffer.push_str(&text);
+}
+
+/// Get the address that a divert leads to.
+///
+/// Diverts to variables lead to the divert targets that the variables hold.
+fn get_divert_target(address: &Address, data: &FollowData) -> Result<Address, InklingError> {
+    match address {
+        Address::Validated(
+            AddressKind::GlobalVariable { name } | AddressKind::TemporaryVariable { name },
+        ) => match Variable::Address(address.clone()).as_value(data)? {
+            Variable::Divert(target) => Ok(target),
+            value => Err(InklingError::InvalidDivertTarget {
+                name: name.clone(),
+                value,
+            }),
+        },
+        _ => Ok(address.clone()),
+    }
+}
+
 /// Process and add the content of an `Alternative` to a string buffer.
 fn process_alternative(
     alternative: &mut Alternative,
     buffer: &mut String,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
+    let count_conditions =
+        |items: &[LineChunk]| -> usize { items.iter().map(|item| item.count_conditions()).sum() };
+
     match alternative.get_next_index(data) {
-        Some(index) => {
-            let item = alternative
-                .items
-                .get_mut(index)
-                .ok_or_else(|| ProcessError {
-                    kind: ProcessErrorKind::InvalidAlternativeIndex,
-                })?;
-
-            process_chunk(item, buffer, data)
+        Some(index) if index < alternative.items.len() => {
+            let (before, after) = alternative.items.split_at_mut(index);
+            let (item, after) = after.split_first_mut().unwrap();
+
+            data.condition_coverage
+                .skip_in_line(count_conditions(before));
+            let result = process_chunk(item, buffer, data)?;
+            data.condition_coverage
+                .skip_in_line(count_conditions(after));
+
+            Ok(result)
+        }
+        Some(..) => Err(ProcessError {
+            kind: ProcessErrorKind::InvalidAlternativeIndex,
+        }),
+        None => {
+            let num_skipped = count_conditions(&alternative.items);
+            data.condition_coverage.skip_in_line(num_skipped);
+
+            Ok(EncounteredEvent::Done)
+        }
+    }
+}
+
+/// Write every variant of the content of a `LineChunk` to a string buffer, marked up.
+///
+/// Used in proofreading mode, after the chunk has been processed as usual to select
+/// the variants and diverts that the story follows. Conditional content is written as
+/// `{if condition: items | else: else_items}` and alternatives as `{cycle: a | b}`,
+/// with the kind of alternative. Diverts are not written.
+///
+/// Expressions are written with their current value, or as the expression itself if
+/// it cannot be evaluated.
+fn write_all_variants(chunk: &LineChunk, buffer: &mut String, data: &FollowData) {
+    match &chunk.condition {
+        Some(condition) => {
+            buffer.push_str(&format!("{{if {}: ", format_condition(condition)));
+            write_all_content_variants(&chunk.items, buffer, data);
+
+            if !chunk.else_items.is_empty() {
+                buffer.push_str(" | else: ");
+                write_all_content_variants(&chunk.else_items, buffer, data);
+            }
+
+            buffer.push('}');
+        }
+        None => write_all_content_variants(&chunk.items, buffer, data),
+    }
+}
+
+/// Write every variant of a set of `Content` items to a string buffer, marked up.
+fn write_all_content_variants(items: &[Content], buffer: &mut String, data: &FollowData) {
+    for item in items {
+        match item {
+            Content::Alternative(alternative) => {
+                let kind = match alternative.kind {
+                    AlternativeKind::Cycle => "cycle",
+                    AlternativeKind::OnceOnly => "once",
+                    AlternativeKind::Sequence => "sequence",
+                    AlternativeKind::Shuffle => "shuffle",
+                };
+
+                buffer.push_str(&format!("{{{}: ", kind));
+
+                for (i, item) in alternative.items.iter().enumerate() {
+                    if i > 0 {
+                        buffer.push_str(" | ");
+                    }
+
+                    write_all_variants(item, buffer, data);
+                }
+
+                buffer.push('}');
+            }
+            Content::Divert(..) => (),
+            Content::Empty => buffer.push(' '),
+            Content::Expression(expression) => {
+                // Functions which are called in the expression must not change the story
+                let text = evaluate_expression(expression, &mut data.clone())
+                    .and_then(|variable| variable.to_string_internal(data))
+                    .unwrap_or_else(|_| format!("{{{}}}", format_expression(expression)));
+
+                buffer.push_str(&text);
+            }
+            Content::Nested(chunk) => write_all_variants(chunk, buffer, data),
+            Content::Text(string) => buffer.push_str(string),
         }
-        None => Ok(EncounteredEvent::Done),
     }
 }
 
@@ -169,6 +371,42 @@ pub mod tests {
         assert!(result.glue_end);
     }
 
+    #[test]
+    fn proofreading_writes_all_variants_but_returns_the_selected_divert() {
+        let mut line =
+            parse_internal_line("{&Up|Down}{true: -> knot|, twice}.", &().into()).unwrap();
+
+        let mut buffer = Vec::new();
+        let mut data = mock_data_with_single_stitch("", "", 0);
+        data.proofreading = true;
+
+        let result = process_line(&mut line, &mut buffer, &mut data).unwrap();
+
+        assert_eq!(
+            result,
+            EncounteredEvent::Divert(Address::Raw("knot".to_string()))
+        );
+        assert_eq!(
+            buffer[0].text,
+            "{cycle: Up | Down} {if true:   | else: , twice}."
+        );
+    }
+
+    #[test]
+    fn full_line_processing_marks_lines_which_end_in_a_divert() {
+        let mut line = parse_internal_line("A test string -> knot", &().into()).unwrap();
+        let mut other = parse_internal_line("A test string", &().into()).unwrap();
+
+        let mut buffer = Vec::new();
+        let mut data = mock_data_with_single_stitch("", "", 0);
+
+        process_line(&mut line, &mut buffer, &mut data).unwrap();
+        process_line(&mut other, &mut buffer, &mut data).unwrap();
+
+        assert!(buffer[0].divert_end);
+        assert!(!buffer[1].divert_end);
+    }
+
     #[test]
     fn full_line_processing_retains_tags() {
         let mut line = parse_internal_line("A test string", &().into()).unwrap();
@@ -5,7 +5,9 @@ pub(crate) mod choice;
 mod condition;
 pub(crate) mod line;
 
+#[cfg(test)]
 pub use buffer::process_buffer;
+pub use buffer::process_buffer_into_items;
 pub use choice::{get_fallback_choices, prepare_choices_for_user};
 pub use condition::check_condition;
-pub use line::process_line;
+pub use line::{process_line, process_thread};
@@ -0,0 +1,99 @@
+//! Detection of sources of nondeterminism while following a story.
+
+use crate::error::utils::MetaData;
+
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, PartialEq)]
+/// Kind of content which can make a story produce different output from the same choices.
+pub enum NondeterminismKind {
+    /// Shuffled content or a call to `RANDOM` used a random number generator which was
+    /// seeded from entropy.
+    ///
+    /// Set a seed with [`set_seed`][crate::story::Story::set_seed()] or `SEED_RANDOM` in
+    /// the story to make them repeatable. Generators which were restored from a saved state are seeded.
+    UnseededRandom,
+    /// Value of a variable was read from the host through its
+    /// [`VariableStore`][crate::VariableStore], which the story cannot replay.
+    ExternalVariable {
+        /// Name of the variable.
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Source of nondeterminism which was encountered while following a story.
+pub struct Nondeterminism {
+    /// Kind of nondeterminism.
+    pub kind: NondeterminismKind,
+    /// Line which was being processed when the source was encountered, if any.
+    pub meta_data: Option<MetaData>,
+}
+
+#[derive(Debug, Default)]
+/// Record of every unique source of nondeterminism which has been encountered.
+///
+/// Sources are recorded through shared references since variables are read through them,
+/// which is why the record is kept behind a lock.
+pub struct DeterminismAudit {
+    found: Mutex<Vec<Nondeterminism>>,
+}
+
+impl DeterminismAudit {
+    /// Get all recorded sources in the order that they were first encountered.
+    pub fn get_found(&self) -> Vec<Nondeterminism> {
+        self.lock().clone()
+    }
+
+    /// Record a source of nondeterminism, unless it has already been recorded.
+    pub fn record(&self, kind: NondeterminismKind, meta_data: Option<&MetaData>) {
+        let item = Nondeterminism {
+            kind,
+            meta_data: meta_data.cloned(),
+        };
+
+        let mut found = self.lock();
+
+        if !found.contains(&item) {
+            found.push(item);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Nondeterminism>> {
+        self.found.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Clone for DeterminismAudit {
+    fn clone(&self) -> Self {
+        DeterminismAudit {
+            found: Mutex::new(self.get_found()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for DeterminismAudit {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_found() == other.get_found()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_are_recorded_once_per_kind_and_line() {
+        let audit = DeterminismAudit::default();
+
+        audit.record(NondeterminismKind::UnseededRandom, Some(&MetaData::from(2)));
+        audit.record(NondeterminismKind::UnseededRandom, Some(&MetaData::from(2)));
+        audit.record(NondeterminismKind::UnseededRandom, Some(&MetaData::from(4)));
+
+        let found = audit.get_found();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].meta_data, Some(MetaData::from(4)));
+    }
+}
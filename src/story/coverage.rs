@@ -0,0 +1,261 @@
+//! Tracking of which outcomes the conditions in a story have evaluated to.
+
+use crate::error::utils::MetaData;
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Location of a condition in the story.
+pub enum ConditionSite {
+    /// Condition for whether a choice is presented to the user.
+    Choice(MetaData),
+    /// Condition inside a line of content.
+    ///
+    /// Conditions on the same line are numbered from 0 in the order they are written,
+    /// with nested conditions numbered after the condition that contains them.
+    Line(MetaData, usize),
+    /// Condition for which branch of a multi-line conditional block is followed.
+    Block(MetaData),
+}
+
+impl ConditionSite {
+    /// Get the information about the line which the condition is on.
+    pub fn meta_data(&self) -> &MetaData {
+        match self {
+            ConditionSite::Choice(meta_data)
+            | ConditionSite::Line(meta_data, _)
+            | ConditionSite::Block(meta_data) => meta_data,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Number of times that a condition has evaluated to either outcome.
+pub struct ConditionOutcomes {
+    /// Number of times the condition evaluated to `true`.
+    pub num_true: u32,
+    /// Number of times the condition evaluated to `false`.
+    pub num_false: u32,
+}
+
+impl ConditionOutcomes {
+    /// Assert whether the condition has been evaluated but only ever to one outcome.
+    pub fn is_stuck(&self) -> bool {
+        (self.num_true == 0) != (self.num_false == 0)
+    }
+}
+
+/// Line which conditions are numbered in and the number of the next condition in it.
+pub(crate) type LineCursor = (MetaData, usize);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Record of the outcomes of every condition evaluated while following a story.
+///
+/// Conditions which gate content but in practice always evaluate to the same outcome
+/// are usually mistakes in the story logic. Collect the coverage from several playthroughs
+/// with [`merge`][ConditionCoverage::merge()], then find such conditions with
+/// [`get_stuck_conditions`][ConditionCoverage::get_stuck_conditions()].
+///
+/// Conditions which were never evaluated are not in the record at all.
+///
+/// # Example
+/// ```
+/// # use inkling::read_story_from_string;
+/// let content = "\
+/// VAR has_map = false
+///
+/// {has_map: You unfold the map.|You are lost.}
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// let mut line_buffer = Vec::new();
+///
+/// story.resume(&mut line_buffer).unwrap();
+///
+/// let stuck = story.get_condition_coverage().get_stuck_conditions();
+///
+/// assert_eq!(stuck.len(), 1);
+/// assert_eq!(stuck[0].0.meta_data().line(), 3);
+/// assert_eq!(stuck[0].1, false);
+/// ```
+pub struct ConditionCoverage {
+    /// Outcomes of every condition that has been evaluated.
+    sites: HashMap<ConditionSite, ConditionOutcomes>,
+    /// Line which is currently being processed and the number of the next condition in it.
+    line_cursor: Option<LineCursor>,
+}
+
+impl ConditionCoverage {
+    /// Get the recorded outcomes of a condition, if it has been evaluated.
+    pub fn get(&self, site: &ConditionSite) -> Option<&ConditionOutcomes> {
+        self.sites.get(site)
+    }
+
+    /// Iterate over all evaluated conditions and their outcomes, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&ConditionSite, &ConditionOutcomes)> {
+        self.sites.iter()
+    }
+
+    /// Add the outcomes from another record to this one.
+    ///
+    /// Used to collect the coverage from several playthroughs of the same story.
+    pub fn merge(&mut self, other: &ConditionCoverage) {
+        for (site, outcomes) in other.sites.iter() {
+            let entry = self.sites.entry(site.clone()).or_default();
+
+            entry.num_true += outcomes.num_true;
+            entry.num_false += outcomes.num_false;
+        }
+    }
+
+    /// Get all conditions which have only ever evaluated to one outcome, along with that outcome.
+    ///
+    /// The conditions are sorted by where they occur in the story.
+    pub fn get_stuck_conditions(&self) -> Vec<(&ConditionSite, bool)> {
+        let mut stuck = self
+            .sites
+            .iter()
+            .filter(|(_, outcomes)| outcomes.is_stuck())
+            .map(|(site, outcomes)| (site, outcomes.num_true > 0))
+            .collect::<Vec<_>>();
+
+        stuck.sort_by_key(|(site, _)| get_sort_key(site));
+
+        stuck
+    }
+
+    /// Record the outcome of a condition.
+    pub(crate) fn record(&mut self, site: ConditionSite, outcome: bool) {
+        let entry = self.sites.entry(site).or_default();
+
+        if outcome {
+            entry.num_true += 1;
+        } else {
+            entry.num_false += 1;
+        }
+    }
+
+    /// Get the line which is currently being processed, if any.
+    pub(crate) fn get_current_line(&self) -> Option<&MetaData> {
+        self.line_cursor.as_ref().map(|(meta_data, _)| meta_data)
+    }
+
+    /// Stop numbering the conditions in the current line and get how far it has come.
+    ///
+    /// Used while functions are followed from the middle of a line, after which the
+    /// numbering continues with `set_line_cursor`.
+    pub(crate) fn take_line_cursor(&mut self) -> Option<LineCursor> {
+        self.line_cursor.take()
+    }
+
+    /// Continue numbering the conditions in a line from where it was taken.
+    pub(crate) fn set_line_cursor(&mut self, line_cursor: Option<LineCursor>) {
+        self.line_cursor = line_cursor;
+    }
+
+    /// Begin numbering the conditions in a new line.
+    pub(crate) fn begin_line(&mut self, meta_data: &MetaData) {
+        self.line_cursor.replace((meta_data.clone(), 0));
+    }
+
+    /// Record the outcome of the next condition in the current line.
+    ///
+    /// Does nothing if no line has been begun.
+    pub(crate) fn record_in_line(&mut self, outcome: bool) {
+        if let Some((meta_data, index)) = self.line_cursor.as_mut() {
+            let site = ConditionSite::Line(meta_data.clone(), *index);
+            *index += 1;
+
+            self.record(site, outcome);
+        }
+    }
+
+    /// Skip numbering conditions in the current line which will not be evaluated.
+    pub(crate) fn skip_in_line(&mut self, num_conditions: usize) {
+        if let Some((_, index)) = self.line_cursor.as_mut() {
+            *index += num_conditions;
+        }
+    }
+}
+
+/// Get a key to sort condition sites by where they occur in the story.
+fn get_sort_key(site: &ConditionSite) -> (Option<String>, u32, usize) {
+    let meta_data = site.meta_data();
+
+    let index = match site {
+        ConditionSite::Choice(..) | ConditionSite::Block(..) => 0,
+        ConditionSite::Line(_, index) => index + 1,
+    };
+
+    (meta_data.file.clone(), meta_data.line_index, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_are_stuck_if_evaluated_only_to_a_single_outcome() {
+        let mut coverage = ConditionCoverage::default();
+
+        let choice = ConditionSite::Choice(MetaData::from(3));
+        let line = ConditionSite::Line(MetaData::from(1), 0);
+
+        coverage.record(choice.clone(), true);
+        coverage.record(choice.clone(), true);
+        coverage.record(line.clone(), false);
+
+        assert_eq!(
+            coverage.get_stuck_conditions(),
+            vec![(&line, false), (&choice, true)]
+        );
+
+        coverage.record(line.clone(), true);
+
+        assert_eq!(coverage.get_stuck_conditions(), vec![(&choice, true)]);
+    }
+
+    #[test]
+    fn conditions_in_lines_are_numbered_in_order_after_skipped_conditions() {
+        let mut coverage = ConditionCoverage::default();
+
+        coverage.record_in_line(true);
+        assert!(coverage.iter().next().is_none());
+
+        coverage.begin_line(&MetaData::from(2));
+        coverage.record_in_line(true);
+        coverage.skip_in_line(2);
+        coverage.record_in_line(false);
+
+        assert!(coverage
+            .get(&ConditionSite::Line(MetaData::from(2), 0))
+            .is_some());
+        assert!(coverage
+            .get(&ConditionSite::Line(MetaData::from(2), 3))
+            .is_some());
+        assert_eq!(coverage.iter().count(), 2);
+    }
+
+    #[test]
+    fn merging_coverage_adds_the_outcomes_of_both() {
+        let site = ConditionSite::Choice(MetaData::from(0));
+
+        let mut coverage = ConditionCoverage::default();
+        coverage.record(site.clone(), true);
+
+        let mut other = ConditionCoverage::default();
+        other.record(site.clone(), false);
+        other.record(site.clone(), false);
+
+        coverage.merge(&other);
+
+        assert_eq!(
+            coverage.get(&site),
+            Some(&ConditionOutcomes {
+                num_true: 1,
+                num_false: 2
+            })
+        );
+        assert!(coverage.get_stuck_conditions().is_empty());
+    }
+}
@@ -0,0 +1,711 @@
+//! Bounded exploration of the states that a story can reach.
+
+use crate::{
+    error::{utils::MetaData, InklingError},
+    line::Variable,
+    story::{
+        types::{EndingKind, Location, Prompt},
+        Story,
+    },
+};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Values to start a story with: variable names paired with their values.
+pub type VariableValues = Vec<(String, Variable)>;
+
+#[derive(Clone, Debug)]
+/// Bounds for exploring the states of a story with [`explore_story`][crate::explore_story].
+pub struct ExploreOptions {
+    /// Values to try for global variables.
+    ///
+    /// The story is explored once with the declared values of all variables and then once
+    /// for every combination of the values given here. Variables which are not listed keep
+    /// their declared value.
+    pub variable_bounds: Vec<(String, Vec<Variable>)>,
+    /// Maximum number of choices to make from the starting state along a single path.
+    pub max_depth: usize,
+    /// Maximum number of states to visit for each set of variable values.
+    pub max_states: usize,
+}
+
+impl Default for ExploreOptions {
+    fn default() -> Self {
+        ExploreOptions {
+            variable_bounds: Vec::new(),
+            max_depth: 20,
+            max_states: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Path through a story that ended in an error instead of the end of the story.
+pub struct SoftLock {
+    /// Variable values that the story was started with.
+    pub values: VariableValues,
+    /// Indices of the choices that were made from the starting state.
+    pub choices: Vec<usize>,
+    /// Location in the story where the error occurred.
+    ///
+    /// If the error does not contain a location this is the last location which the story
+    /// moved to before the error.
+    pub location: Location,
+    /// Error that the story stopped with.
+    pub error: InklingError,
+}
+
+#[derive(Clone, Debug)]
+/// End of the story which was reached while exploring it.
+pub struct Ending {
+    /// Whether the story diverted to `END` or `DONE`, or ran out of content.
+    pub kind: EndingKind,
+    /// Knot or stitch that the story ended in.
+    pub location: Location,
+    /// Line which the story ended at, if known.
+    ///
+    /// For diverts to `END` or `DONE` this is the line with the divert. For dead ends it is the last
+    /// line of content before the story ran out.
+    pub meta_data: Option<MetaData>,
+    /// Variable values that the story was started with to reach the ending.
+    pub values: VariableValues,
+    /// Indices of the choices that were made from the starting state to reach the ending.
+    ///
+    /// If the ending was reached along several paths this is the shortest one.
+    pub choices: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+/// Sequence of choices which leads a story to a target knot or stitch.
+pub struct Walkthrough {
+    /// Variable values that the story has to be started with to reach the target.
+    pub values: VariableValues,
+    /// Indices of the choices to make from the starting state, in order.
+    pub choices: Vec<usize>,
+    /// Text of the choices to make, in order.
+    pub choice_texts: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Result of searching for a walkthrough with [`find_walkthrough`][crate::find_walkthrough].
+pub enum WalkthroughOutcome {
+    /// The target was reached by following the walkthrough.
+    Found(Walkthrough),
+    /// Every path was followed to its end without reaching the target.
+    Unreachable,
+    /// The target was not reached before the depth or state limits were reached.
+    ///
+    /// It may still be reachable in longer playthroughs.
+    LimitReached,
+}
+
+#[derive(Clone, Debug, Default)]
+/// Result of exploring a story.
+pub struct ExplorationReport {
+    /// Knots and stitches that were reached with the declared values of all variables.
+    pub reached: Vec<Location>,
+    /// Knots and stitches that were only reached with other variable values, along with
+    /// the first set of values which reached them.
+    pub unlocked: Vec<(Location, VariableValues)>,
+    /// Knots and stitches that were not reached with any variable values.
+    pub unreached: Vec<Location>,
+    /// Paths through the story which ended in an error, such as running out of choices.
+    pub soft_locks: Vec<SoftLock>,
+    /// Every unique end of the story that was reached, sorted by where it is in the story.
+    pub endings: Vec<Ending>,
+    /// Whether every path was followed to its end without reaching the depth or state limits.
+    ///
+    /// If this is `false` the unreached content may still be reachable in longer playthroughs.
+    pub is_complete: bool,
+}
+
+/// Explore the states that a story can reach by trying every choice.
+///
+/// Starting from the current state of the story, every choice is made in turn and followed
+/// until the story ends, runs into an error or a limit set in the options is reached.
+/// This is repeated for every combination of variable values in the options, to find
+/// content which is only unlocked by some values and paths which lead to soft-locks.
+///
+/// The given story is not modified. Since every path is explored separately the number
+/// of states grows quickly with the number of choices: use the limits to bound the search.
+///
+/// # Examples
+/// ```
+/// # use inkling::{explore_story, read_story_from_string, ExploreOptions, Location, Variable};
+/// let content = "\
+/// VAR has_key = false
+///
+/// *   {has_key} [Unlock the door] -> vault
+/// *   [Leave] -> END
+///
+/// === vault ===
+/// The vault was empty.
+/// -> END
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+///
+/// let options = ExploreOptions {
+///     variable_bounds: vec![("has_key".to_string(), vec![Variable::Bool(true)])],
+///     ..Default::default()
+/// };
+///
+/// let report = explore_story(&story, &options).unwrap();
+///
+/// assert!(report.is_complete);
+/// assert_eq!(report.unlocked[0].0, Location::from("vault"));
+/// ```
+///
+/// The report also lists the endings of the story, along with a path which reaches them:
+/// ```
+/// # use inkling::{explore_story, read_story_from_string, EndingKind, ExploreOptions};
+/// let content = "\
+/// *   [Board the train] -> END
+/// *   [Stay on the platform]
+///     The train left without her.
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+/// let report = explore_story(&story, &ExploreOptions::default()).unwrap();
+///
+/// assert_eq!(report.endings.len(), 2);
+/// assert_eq!(report.endings[0].kind, EndingKind::End);
+/// assert_eq!(report.endings[1].kind, EndingKind::DeadEnd);
+/// assert_eq!(report.endings[1].choices, vec![1]);
+/// ```
+///
+/// # Errors
+/// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if a variable
+///     in the bounds does not exist in the story.
+/// *   [`VariableError`][crate::error::InklingError::VariableError]: if a value in the
+///     bounds has a different type to its variable.
+pub fn explore_story(
+    story: &Story,
+    options: &ExploreOptions,
+) -> Result<ExplorationReport, InklingError> {
+    let mut report = ExplorationReport {
+        is_complete: true,
+        ..Default::default()
+    };
+
+    let mut first_reached_with: HashMap<Location, VariableValues> = HashMap::new();
+    let mut default_reached = HashSet::new();
+
+    for (i, values) in get_value_combinations(&options.variable_bounds)
+        .into_iter()
+        .enumerate()
+    {
+        let mut start = story.clone();
+
+        for (name, value) in values.iter() {
+            start.set_variable(name, value.clone())?;
+        }
+
+        let reached = explore_from_state(start, &values, options, &mut report);
+
+        for location in reached {
+            if i == 0 {
+                default_reached.insert(location.clone());
+            }
+
+            first_reached_with
+                .entry(location)
+                .or_insert_with(|| values.clone());
+        }
+    }
+
+    for (location, _) in story.get_locations_with_num_visited() {
+        if default_reached.contains(&location) {
+            report.reached.push(location);
+        } else if let Some(values) = first_reached_with.remove(&location) {
+            report.unlocked.push((location, values));
+        } else {
+            report.unreached.push(location);
+        }
+    }
+
+    report.reached.sort_by(compare_locations);
+    report
+        .unlocked
+        .sort_by(|(lhs, _), (rhs, _)| compare_locations(lhs, rhs));
+    report.unreached.sort_by(compare_locations);
+    report.endings.sort_by(|lhs, rhs| {
+        compare_locations(&lhs.location, &rhs.location)
+            .then_with(|| get_line(lhs).cmp(&get_line(rhs)))
+    });
+
+    Ok(report)
+}
+
+/// Find the shortest sequence of choices which leads a story to a target knot or stitch.
+///
+/// Starting from the current state of the story, the choices are searched in order of how
+/// many have been made, so the returned walkthrough makes as few choices as possible.
+/// The target is reached once it has been visited one more time than in the current state,
+/// even if the story only passes through it on the way to the next choice. All conditions
+/// in the story are evaluated as the search goes.
+///
+/// The story is first searched with the declared values of all variables, then with every
+/// combination of the values in the variable bounds of the options. The depth and state
+/// limits are applied to every set of values.
+///
+/// # Examples
+/// ```
+/// # use inkling::{find_walkthrough, read_story_from_string, ExploreOptions, Location,
+/// #     WalkthroughOutcome};
+/// let content = "\
+/// *   [Take the stairs] -> stairs
+/// *   [Take the lift] -> lift
+///
+/// === stairs ===
+/// *   [Keep climbing] -> roof
+/// *   [Give up] -> END
+///
+/// === lift ===
+/// The lift was out of order.
+/// -> END
+///
+/// === roof ===
+/// The city lay beneath her.
+/// -> END
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+/// let target = Location::from("roof");
+///
+/// match find_walkthrough(&story, &target, &ExploreOptions::default()).unwrap() {
+///     WalkthroughOutcome::Found(walkthrough) => {
+///         assert_eq!(walkthrough.choices, vec![0, 0]);
+///         assert_eq!(&walkthrough.choice_texts[1], "Keep climbing");
+///     }
+///     other => panic!("expected a walkthrough but got {:?}", other),
+/// }
+/// ```
+///
+/// # Errors
+/// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the target
+///     does not exist in the story.
+/// *   Returns the same errors as [`explore_story`][crate::explore_story()] for invalid
+///     variable bounds.
+pub fn find_walkthrough(
+    story: &Story,
+    target: &Location,
+    options: &ExploreOptions,
+) -> Result<WalkthroughOutcome, InklingError> {
+    let initial_visits =
+        story
+            .get_num_visited(target)
+            .ok_or_else(|| InklingError::InvalidAddress {
+                location: target.clone(),
+            })?;
+
+    let mut is_complete = true;
+
+    for values in get_value_combinations(&options.variable_bounds) {
+        let mut start = story.clone();
+
+        for (name, value) in values.iter() {
+            start.set_variable(name, value.clone())?;
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back((start, Vec::new(), Vec::new()));
+
+        let mut num_states = 0;
+
+        while let Some((mut story, choices, choice_texts)) = queue.pop_front() {
+            if num_states >= options.max_states {
+                is_complete = false;
+                break;
+            }
+
+            num_states += 1;
+
+            let mut line_buffer = Vec::new();
+            let result = story.resume(&mut line_buffer);
+
+            if story.get_num_visited(target).unwrap_or(0) > initial_visits {
+                return Ok(WalkthroughOutcome::Found(Walkthrough {
+                    values,
+                    choices,
+                    choice_texts,
+                }));
+            }
+
+            match result {
+                Ok(Prompt::Choice(..)) if choices.len() >= options.max_depth => {
+                    is_complete = false;
+                }
+                Ok(Prompt::Choice(choice_set)) => {
+                    for (index, choice) in choice_set.iter().enumerate() {
+                        let mut next = story.clone();
+
+                        if next.make_choice(index).is_ok() {
+                            let mut path = choices.clone();
+                            path.push(index);
+
+                            let mut texts = choice_texts.clone();
+                            texts.push(choice.text.clone());
+
+                            queue.push_back((next, path, texts));
+                        }
+                    }
+                }
+                Ok(Prompt::Continue) => queue.push_front((story, choices, choice_texts)),
+                Ok(Prompt::Done(..)) | Err(..) => (),
+            }
+        }
+    }
+
+    if is_complete {
+        Ok(WalkthroughOutcome::Unreachable)
+    } else {
+        Ok(WalkthroughOutcome::LimitReached)
+    }
+}
+
+/// Follow every path from a state and return the locations that were visited.
+///
+/// Soft-locks are added to the report, which is also marked as incomplete if a limit
+/// was reached.
+fn explore_from_state(
+    start: Story,
+    values: &VariableValues,
+    options: &ExploreOptions,
+    report: &mut ExplorationReport,
+) -> HashSet<Location> {
+    let mut reached = HashSet::new();
+    let mut num_states = 0;
+
+    let mut stack = vec![(start, Vec::new())];
+
+    while let Some((mut story, choices)) = stack.pop() {
+        if num_states >= options.max_states {
+            report.is_complete = false;
+            break;
+        }
+
+        num_states += 1;
+
+        let mut line_buffer = Vec::new();
+        let result = story.resume(&mut line_buffer);
+
+        reached.extend(
+            story
+                .get_locations_with_num_visited()
+                .into_iter()
+                .filter(|(_, num_visited)| *num_visited > 0)
+                .map(|(location, _)| location),
+        );
+
+        match result {
+            Ok(Prompt::Done(kind)) => add_ending(&story, kind, values, choices, report),
+            Ok(Prompt::Choice(..)) if choices.len() >= options.max_depth => {
+                report.is_complete = false;
+            }
+            Ok(Prompt::Continue) => stack.push((story, choices)),
+            Ok(Prompt::Choice(choice_set)) => {
+                for index in (0..choice_set.len()).rev() {
+                    let mut next = story.clone();
+
+                    if next.make_choice(index).is_ok() {
+                        let mut path = choices.clone();
+                        path.push(index);
+
+                        stack.push((next, path));
+                    }
+                }
+            }
+            Err(error) => {
+                let location = match &error {
+                    InklingError::OutOfChoices { location } => location.clone(),
+                    _ => story.get_current_location(),
+                };
+
+                report.soft_locks.push(SoftLock {
+                    values: values.clone(),
+                    choices,
+                    location,
+                    error,
+                });
+            }
+        }
+    }
+
+    reached
+}
+
+/// Add the ending that a story reached to the report, or a shorter path to a known ending.
+fn add_ending(
+    story: &Story,
+    kind: EndingKind,
+    values: &VariableValues,
+    choices: Vec<usize>,
+    report: &mut ExplorationReport,
+) {
+    let ending = Ending {
+        kind,
+        location: story.get_current_location(),
+        meta_data: story.get_last_processed_line().cloned(),
+        values: values.clone(),
+        choices,
+    };
+
+    let existing = report.endings.iter_mut().find(|other| {
+        other.kind == ending.kind
+            && other.location == ending.location
+            && other.meta_data == ending.meta_data
+    });
+
+    match existing {
+        Some(other) if ending.choices.len() < other.choices.len() => *other = ending,
+        Some(..) => (),
+        None => report.endings.push(ending),
+    }
+}
+
+/// Get the line number of an ending, with unknown lines ordered first.
+fn get_line(ending: &Ending) -> Option<u32> {
+    ending.meta_data.as_ref().map(|meta_data| meta_data.line())
+}
+
+/// Get every combination of variable values to explore.
+///
+/// The first combination is empty, which starts the story with the declared values.
+fn get_value_combinations(bounds: &[(String, Vec<Variable>)]) -> Vec<VariableValues> {
+    let mut combinations: Vec<VariableValues> = vec![Vec::new()];
+
+    for (name, values) in bounds.iter().filter(|(_, values)| !values.is_empty()) {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push((name.clone(), value.clone()));
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    if !combinations[0].is_empty() {
+        combinations.insert(0, Vec::new());
+    }
+
+    combinations
+}
+
+/// Order locations by knot name and then stitch name.
+fn compare_locations(lhs: &Location, rhs: &Location) -> std::cmp::Ordering {
+    (&lhs.knot, &lhs.stitch).cmp(&(&rhs.knot, &rhs.stitch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::story::read_story_from_string;
+
+    #[test]
+    fn value_combinations_start_with_the_declared_values() {
+        let bounds = vec![
+            ("a".to_string(), vec![Variable::Int(1), Variable::Int(2)]),
+            ("b".to_string(), vec![Variable::Bool(true)]),
+        ];
+
+        let combinations = get_value_combinations(&bounds);
+
+        assert_eq!(combinations.len(), 3);
+        assert!(combinations[0].is_empty());
+        assert_eq!(
+            combinations[1],
+            vec![
+                ("a".to_string(), Variable::Int(1)),
+                ("b".to_string(), Variable::Bool(true))
+            ]
+        );
+        assert_eq!(combinations[2][0], ("a".to_string(), Variable::Int(2)));
+    }
+
+    #[test]
+    fn exploring_story_finds_content_reached_by_every_choice() {
+        let content = "\
+*   [Left] -> left
+*   [Right] -> right
+
+=== left ===
+-> END
+
+=== right ===
+-> END
+
+=== nowhere ===
+-> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let report = explore_story(&story, &ExploreOptions::default()).unwrap();
+
+        assert!(report.is_complete);
+        assert_eq!(
+            report.reached,
+            vec![Location::from("left"), Location::from("right")]
+        );
+        assert!(report.unlocked.is_empty());
+        assert_eq!(report.unreached, vec![Location::from("nowhere")]);
+    }
+
+    #[test]
+    fn paths_which_run_out_of_choices_are_soft_locks() {
+        let content = "\
+VAR has_key = false
+*   [Go down] -> cellar
+
+=== cellar ===
+*   {has_key} [Unlock the door] -> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let report = explore_story(&story, &ExploreOptions::default()).unwrap();
+
+        assert_eq!(report.soft_locks.len(), 1);
+        assert_eq!(report.soft_locks[0].choices, vec![0]);
+        assert_eq!(report.soft_locks[0].location, Location::from("cellar"));
+    }
+
+    #[test]
+    fn exploration_is_incomplete_if_a_limit_is_reached() {
+        let content = "\
+-> loop
+
+=== loop ===
++   [Again] -> loop
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        let options = ExploreOptions {
+            max_depth: 5,
+            ..Default::default()
+        };
+
+        let report = explore_story(&story, &options).unwrap();
+
+        assert!(!report.is_complete);
+        assert_eq!(report.reached, vec![Location::from("loop")]);
+    }
+
+    #[test]
+    fn endings_are_listed_once_with_their_line_and_shortest_path() {
+        let content = "\
+*   [Wait] -> waiting_room
+*   [Leave] -> exit
+
+=== waiting_room ===
+*   [Wait some more] -> exit
+*   [Fall asleep]
+    The night passed.
+
+=== exit ===
+The doors closed behind her.
+-> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let report = explore_story(&story, &ExploreOptions::default()).unwrap();
+
+        assert_eq!(report.endings.len(), 2);
+
+        let exit = &report.endings[0];
+        assert_eq!(exit.kind, EndingKind::End);
+        assert_eq!(exit.location, Location::from("exit"));
+        assert_eq!(exit.meta_data.as_ref().unwrap().line(), 11);
+        assert_eq!(exit.choices, vec![1]);
+
+        let asleep = &report.endings[1];
+        assert_eq!(asleep.kind, EndingKind::DeadEnd);
+        assert_eq!(asleep.location, Location::from("waiting_room"));
+        assert_eq!(asleep.meta_data.as_ref().unwrap().line(), 7);
+        assert_eq!(asleep.choices, vec![0, 1]);
+    }
+
+    #[test]
+    fn walkthrough_uses_variable_bounds_to_pass_conditions() {
+        let content = "\
+VAR has_key = false
+*   {has_key} [Unlock the door] -> vault
+*   [Leave] -> END
+
+=== vault ===
+The vault was empty.
+-> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let target = Location::from("vault");
+
+        match find_walkthrough(&story, &target, &ExploreOptions::default()).unwrap() {
+            WalkthroughOutcome::Unreachable => (),
+            other => panic!("expected the vault to be unreachable but got {:?}", other),
+        }
+
+        let options = ExploreOptions {
+            variable_bounds: vec![("has_key".to_string(), vec![Variable::Bool(true)])],
+            ..Default::default()
+        };
+
+        match find_walkthrough(&story, &target, &options).unwrap() {
+            WalkthroughOutcome::Found(walkthrough) => {
+                assert_eq!(
+                    walkthrough.values,
+                    vec![("has_key".to_string(), Variable::Bool(true))]
+                );
+                assert_eq!(walkthrough.choices, vec![0]);
+            }
+            other => panic!("expected a walkthrough but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn walkthrough_search_reports_when_a_limit_is_reached() {
+        let content = "\
+-> loop
+
+=== loop ===
++   [Again] -> loop
+*   [Leave] -> exit
+
+=== exit ===
+-> END
+
+=== nowhere ===
+-> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        let options = ExploreOptions {
+            max_depth: 3,
+            ..Default::default()
+        };
+
+        match find_walkthrough(&story, &Location::from("nowhere"), &options).unwrap() {
+            WalkthroughOutcome::LimitReached => (),
+            other => panic!("expected the search to reach a limit but got {:?}", other),
+        }
+
+        assert!(find_walkthrough(&story, &Location::from("unknown"), &options).is_err());
+    }
+
+    #[test]
+    fn variables_in_bounds_must_exist_in_the_story() {
+        let story = read_story_from_string("Line.").unwrap();
+
+        let options = ExploreOptions {
+            variable_bounds: vec![("unknown".to_string(), vec![Variable::Int(0)])],
+            ..Default::default()
+        };
+
+        assert!(explore_story(&story, &options).is_err());
+    }
+}
@@ -0,0 +1,692 @@
+//! Static export of the full content of a story for proofreading.
+//!
+//! Unlike a [transcript][crate::Transcript] of a single playthrough, the export walks
+//! every knot, stitch and choice branch of the story and writes out all its content:
+//! every variant of alternatives and both branches of conditional text. Conditions,
+//! diverts and alternatives are written as annotations beside the content they affect.
+
+use crate::{
+    consts::{RETURN_MARKER, ROOT_KNOT_NAME, TEMPORARY_VARIABLE_MARKER, THREAD_MARKER},
+    line::{
+        expression::Operand, format_condition, format_expression, format_operand, format_operator,
+        format_variable_for_debug, AlternativeKind, Content, InternalLine, LineChunk,
+    },
+    node::NodeItem,
+    story::Story,
+};
+
+use std::cmp::Ordering;
+
+/// Export the full content of a story as a Markdown document.
+///
+/// Knots are written as level two headings and stitches as level three headings, in the
+/// order that they appear in the story. Content in choice branches is written as block
+/// quotes nested under their choice. Annotations are written as inline code.
+///
+/// # Example
+/// ```
+/// # use inkling::{export_story_to_markdown, read_story_from_string};
+/// let content = "\
+/// VAR has_ticket = false
+/// -> platform
+///
+/// === platform ===
+/// The {&first|second} train arrived.
+/// *   {has_ticket} [Board it]
+///     She found a seat by the window.
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+/// let markdown = export_story_to_markdown(&story);
+///
+/// assert!(markdown.contains("## platform"));
+/// assert!(markdown.contains("The `cycle:` first `|` second `end` train arrived."));
+/// assert!(markdown.contains("* `choice` `if has_ticket` Board it"));
+/// assert!(markdown.contains("> She found a seat by the window."));
+/// ```
+pub fn export_story_to_markdown(story: &Story) -> String {
+    let blocks = get_story_blocks(story);
+
+    let lines = blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading { level, text } => format!("{} {}", "#".repeat(*level), text),
+            Block::Paragraph {
+                depth,
+                is_choice,
+                spans,
+            } => {
+                let quote = "> ".repeat(*depth);
+                let marker = if *is_choice { "* " } else { "" };
+                let text = join_spans(spans, |text| format!("`{}`", text), |text| text.into());
+
+                format!("{}{}{}", quote, marker, text)
+            }
+        })
+        .map(|line| format!("{}\n", line))
+        .collect::<Vec<_>>();
+
+    lines.join("\n")
+}
+
+/// Export the full content of a story as an HTML fragment.
+///
+/// Knots are written as `<h2>` elements and stitches as `<h3>` elements, in the order that
+/// they appear in the story. Content in choice branches is nested in `<blockquote>` elements
+/// under their choice, which is a paragraph with the `choice` class. Annotations are written
+/// as `<span class="annotation">` elements. All text is escaped.
+pub fn export_story_to_html(story: &Story) -> String {
+    let blocks = get_story_blocks(story);
+
+    let mut lines = Vec::new();
+    let mut current_depth = 0;
+
+    for block in blocks.iter() {
+        let depth = match block {
+            Block::Heading { .. } => 0,
+            Block::Paragraph { depth, .. } => *depth,
+        };
+
+        while current_depth < depth {
+            lines.push("<blockquote>".to_string());
+            current_depth += 1;
+        }
+
+        while current_depth > depth {
+            lines.push("</blockquote>".to_string());
+            current_depth -= 1;
+        }
+
+        let line = match block {
+            Block::Heading { level, text } => {
+                format!("<h{0}>{1}</h{0}>", level, escape_html(text))
+            }
+            Block::Paragraph {
+                is_choice, spans, ..
+            } => {
+                let class = if *is_choice { " class=\"choice\"" } else { "" };
+                let text = join_spans(
+                    spans,
+                    |text| format!("<span class=\"annotation\">{}</span>", escape_html(text)),
+                    escape_html,
+                );
+
+                format!("<p{}>{}</p>", class, text)
+            }
+        };
+
+        lines.push(line);
+    }
+
+    while current_depth > 0 {
+        lines.push("</blockquote>".to_string());
+        current_depth -= 1;
+    }
+
+    lines
+        .into_iter()
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Part of the text in a paragraph.
+enum Span {
+    /// Text content of the story.
+    Text(String),
+    /// Description of logic in the story which affects the text.
+    Annotation(String),
+}
+
+/// Block of content in the exported document.
+enum Block {
+    /// Heading for a knot or stitch.
+    Heading { level: usize, text: String },
+    /// Paragraph of text, nested in `depth` levels of choice branches.
+    Paragraph {
+        depth: usize,
+        is_choice: bool,
+        spans: Vec<Span>,
+    },
+}
+
+/// Walk through all content of the story and collect it into blocks of the document.
+fn get_story_blocks(story: &Story) -> Vec<Block> {
+    let mut blocks = Vec::new();
+
+    let mut knots = story.get_knots().iter().collect::<Vec<_>>();
+    knots.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+        compare_content(
+            lhs_name,
+            lhs.meta_data.line(),
+            rhs_name,
+            rhs.meta_data.line(),
+        )
+    });
+
+    for (knot_name, knot) in knots {
+        if knot_name != ROOT_KNOT_NAME {
+            blocks.push(Block::Heading {
+                level: 2,
+                text: knot_name.clone(),
+            });
+        }
+
+        if !knot.tags.is_empty() {
+            blocks.push(Block::Paragraph {
+                depth: 0,
+                is_choice: false,
+                spans: vec![format_tags(&knot.tags)],
+            });
+        }
+
+        let mut stitches = knot.stitches.iter().collect::<Vec<_>>();
+        stitches.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+            compare_content(
+                lhs_name,
+                lhs.meta_data.line(),
+                rhs_name,
+                rhs.meta_data.line(),
+            )
+        });
+
+        for (stitch_name, stitch) in stitches {
+            if stitch_name != ROOT_KNOT_NAME {
+                blocks.push(Block::Heading {
+                    level: 3,
+                    text: format!("{}.{}", knot_name, stitch_name),
+                });
+            }
+
+            if !stitch.tags.is_empty() {
+                blocks.push(Block::Paragraph {
+                    depth: 0,
+                    is_choice: false,
+                    spans: vec![format_tags(&stitch.tags)],
+                });
+            }
+
+            add_node_items(&stitch.root.items, 0, &mut blocks);
+        }
+    }
+
+    blocks
+}
+
+/// Order content by the line it begins on, with the root of the story first.
+pub(crate) fn compare_content(
+    lhs_name: &str,
+    lhs_line: u32,
+    rhs_name: &str,
+    rhs_line: u32,
+) -> Ordering {
+    let is_not_root = |name: &str| name != ROOT_KNOT_NAME;
+
+    (is_not_root(lhs_name), lhs_line, lhs_name).cmp(&(is_not_root(rhs_name), rhs_line, rhs_name))
+}
+
+/// Add blocks for every item of a node, and recursively for the branches in it.
+fn add_node_items(items: &[NodeItem], depth: usize, blocks: &mut Vec<Block>) {
+    for item in items {
+        match item {
+            NodeItem::Line(line) => add_line(line, depth, blocks),
+            NodeItem::Directive(directive) => {
+                let arguments = directive
+                    .arguments
+                    .iter()
+                    .map(format_variable_for_debug)
+                    .collect::<Vec<_>>();
+
+                let text = std::iter::once(directive.name.clone())
+                    .chain(arguments)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                blocks.push(Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text)],
+                });
+            }
+            NodeItem::Assignment(assignment) => {
+                let target = assignment.target.to_string();
+                let value = format_expression(&assignment.value);
+
+                let text = match (assignment.is_declaration, assignment.operator) {
+                    (true, _) => format!("{} {} = {}", TEMPORARY_VARIABLE_MARKER, target, value),
+                    (false, Some(operator)) => format!(
+                        "{} {}= {}",
+                        target,
+                        format_operator(operator),
+                        assignment
+                            .value
+                            .tail
+                            .last()
+                            .map(|(_, operand)| match operand {
+                                Operand::Nested(expression) => format_expression(expression),
+                                operand => format_operand(operand),
+                            })
+                            .unwrap_or_default()
+                    ),
+                    (false, None) => format!("{} = {}", target, value),
+                };
+
+                blocks.push(Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text)],
+                });
+            }
+            NodeItem::Call(call) => blocks.push(Block::Paragraph {
+                depth,
+                is_choice: false,
+                spans: vec![Span::Annotation(format_operand(&Operand::Call(
+                    call.call.clone(),
+                )))],
+            }),
+            NodeItem::Return(return_line) => {
+                let text = match &return_line.value {
+                    Some(value) => format!("{} {}", RETURN_MARKER, format_expression(value)),
+                    None => RETURN_MARKER.to_string(),
+                };
+
+                blocks.push(Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text)],
+                });
+            }
+            NodeItem::Thread(thread) => {
+                let text = if thread.arguments.is_empty() {
+                    format!("{} {}", THREAD_MARKER, thread.address.to_string())
+                } else {
+                    let arguments = thread
+                        .arguments
+                        .iter()
+                        .map(format_expression)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    format!(
+                        "{} {}({})",
+                        THREAD_MARKER,
+                        thread.address.to_string(),
+                        arguments
+                    )
+                };
+
+                blocks.push(Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text)],
+                });
+            }
+            NodeItem::Label(label) => blocks.push(Block::Paragraph {
+                depth,
+                is_choice: false,
+                spans: vec![Span::Annotation(format!("label {}", label.name))],
+            }),
+            NodeItem::BranchingPoint(branches) => {
+                for branch in branches {
+                    let choice = &branch.choice;
+
+                    let kind = match (choice.is_fallback, choice.is_sticky) {
+                        (true, _) => "fallback choice",
+                        (false, true) => "sticky choice",
+                        (false, false) => "choice",
+                    };
+
+                    let mut spans = vec![Span::Annotation(kind.to_string())];
+
+                    if let Some(label) = &choice.label {
+                        spans.push(Span::Annotation(format!("label {}", label)));
+                    }
+
+                    if let Some(condition) = &choice.condition {
+                        spans.push(Span::Annotation(format!(
+                            "if {}",
+                            format_condition(condition)
+                        )));
+                    }
+
+                    let selection_text = choice
+                        .selection_text
+                        .lock()
+                        .map(|line| line.clone())
+                        .unwrap_or_else(|err| err.into_inner().clone());
+
+                    add_chunk_spans(&selection_text.chunk, &mut spans);
+
+                    if !selection_text.tags.is_empty() {
+                        spans.push(format_tags(&selection_text.tags));
+                    }
+
+                    blocks.push(Block::Paragraph {
+                        depth,
+                        is_choice: true,
+                        spans,
+                    });
+
+                    add_node_items(&branch.items, depth + 1, blocks);
+                }
+            }
+            NodeItem::AlternativeBlock(block) => {
+                let annotation = |text: &str| Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text.to_string())],
+                };
+
+                blocks.push(annotation(format_alternative_kind(&block.kind)));
+
+                for (i, items) in block.items.iter().enumerate() {
+                    if i > 0 {
+                        blocks.push(annotation("|"));
+                    }
+
+                    add_node_items(items, depth + 1, blocks);
+                }
+
+                blocks.push(annotation("end"));
+            }
+            NodeItem::ConditionalBlock(block) => {
+                let annotation = |text: String| Block::Paragraph {
+                    depth,
+                    is_choice: false,
+                    spans: vec![Span::Annotation(text)],
+                };
+
+                blocks.push(annotation(format!(
+                    "if {}:",
+                    format_condition(&block.condition)
+                )));
+
+                let (items, else_items) = (&block.branches[0].items, &block.branches[1].items);
+
+                add_node_items(items, depth + 1, blocks);
+
+                if !else_items.is_empty() {
+                    blocks.push(annotation("else:".to_string()));
+                    add_node_items(else_items, depth + 1, blocks);
+                }
+
+                blocks.push(annotation("end".to_string()));
+            }
+        }
+    }
+}
+
+/// Add a paragraph for a line, unless it has no content.
+fn add_line(line: &InternalLine, depth: usize, blocks: &mut Vec<Block>) {
+    let mut spans = Vec::new();
+    add_chunk_spans(&line.chunk, &mut spans);
+
+    if !line.tags.is_empty() {
+        spans.push(format_tags(&line.tags));
+    }
+
+    let has_content = spans.iter().any(|span| match span {
+        Span::Text(text) => !text.trim().is_empty(),
+        Span::Annotation(..) => true,
+    });
+
+    if has_content {
+        blocks.push(Block::Paragraph {
+            depth,
+            is_choice: false,
+            spans,
+        });
+    }
+}
+
+/// Add the content of a chunk to a paragraph, with all of its variants and branches.
+fn add_chunk_spans(chunk: &LineChunk, spans: &mut Vec<Span>) {
+    match &chunk.condition {
+        Some(condition) => {
+            spans.push(Span::Annotation(format!(
+                "if {}:",
+                format_condition(condition)
+            )));
+            add_content_spans(&chunk.items, spans);
+
+            if !chunk.else_items.is_empty() {
+                spans.push(Span::Annotation("else:".to_string()));
+                add_content_spans(&chunk.else_items, spans);
+            }
+
+            spans.push(Span::Annotation("end".to_string()));
+        }
+        None => add_content_spans(&chunk.items, spans),
+    }
+}
+
+/// Add content items to a paragraph.
+fn add_content_spans(items: &[Content], spans: &mut Vec<Span>) {
+    for item in items {
+        match item {
+            Content::Alternative(alternative) => {
+                spans.push(Span::Annotation(
+                    format_alternative_kind(&alternative.kind).to_string(),
+                ));
+
+                for (i, item) in alternative.items.iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::Annotation("|".to_string()));
+                    }
+
+                    add_chunk_spans(item, spans);
+                }
+
+                spans.push(Span::Annotation("end".to_string()));
+            }
+            Content::Divert(address, arguments) if arguments.is_empty() => {
+                spans.push(Span::Annotation(format!("-> {}", address.to_string())))
+            }
+            Content::Divert(address, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(format_expression)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                spans.push(Span::Annotation(format!(
+                    "-> {}({})",
+                    address.to_string(),
+                    arguments
+                )))
+            }
+            Content::Empty => (),
+            Content::Expression(expression) => spans.push(Span::Annotation(format!(
+                "{{{}}}",
+                format_expression(expression)
+            ))),
+            Content::Nested(chunk) => add_chunk_spans(chunk, spans),
+            Content::Text(text) => spans.push(Span::Text(text.clone())),
+        }
+    }
+}
+
+/// Get the annotation which begins a set of alternatives.
+fn format_alternative_kind(kind: &AlternativeKind) -> &str {
+    match kind {
+        AlternativeKind::Cycle => "cycle:",
+        AlternativeKind::OnceOnly => "once:",
+        AlternativeKind::Sequence => "sequence:",
+        AlternativeKind::Shuffle => "shuffle:",
+    }
+}
+
+/// Write tags as a single annotation.
+fn format_tags(tags: &[String]) -> Span {
+    let tags = tags
+        .iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>();
+
+    Span::Annotation(tags.join(" "))
+}
+
+/// Join the spans of a paragraph into a string with the given writers for each kind.
+///
+/// A space is put between spans unless there is whitespace between them already. Runs of
+/// whitespace are collapsed into single spaces.
+fn join_spans<A, T>(spans: &[Span], write_annotation: A, write_text: T) -> String
+where
+    A: Fn(&str) -> String,
+    T: Fn(&str) -> String,
+{
+    let mut text = String::new();
+    let mut previous_ends_with_space = true;
+
+    for span in spans {
+        let (content, starts_with_space, ends_with_space) = match span {
+            Span::Annotation(annotation) => (write_annotation(annotation), false, false),
+            Span::Text(content) => (
+                write_text(content),
+                content.starts_with(char::is_whitespace),
+                content.ends_with(char::is_whitespace),
+            ),
+        };
+
+        if !text.is_empty() && !previous_ends_with_space && !starts_with_space {
+            text.push(' ');
+        }
+
+        text.push_str(&content);
+        previous_ends_with_space = ends_with_space;
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Escape the characters of a string which have a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::story::read_story_from_string;
+
+    #[test]
+    fn conditional_text_is_annotated_with_both_branches() {
+        let content = "\
+VAR visits = 0
+{visits > 1: Welcome back.|Welcome.}
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert_eq!(
+            export_story_to_markdown(&story),
+            "`if visits > 1:` Welcome back. `else:` Welcome. `end`\n"
+        );
+    }
+
+    #[test]
+    fn content_is_ordered_as_in_the_story_with_nested_branches() {
+        let content = "\
+-> station
+
+=== station ===
+# location: north
+*   [Wait]
+    *   *   [Wait longer]
+            Nobody came.
+            -> END
+
+= ticket_office
+Closed. -> END
+
+=== airport ===
++   [Fly] -> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let markdown = export_story_to_markdown(&story);
+
+        let lines = markdown
+            .lines()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                "`-> station`",
+                "## station",
+                "`#location: north`",
+                "* `choice` Wait",
+                "> * `choice` Wait longer",
+                "> > Nobody came.",
+                "> > `-> END`",
+                "### station.ticket_office",
+                "Closed. `-> END`",
+                "## airport",
+                "* `sticky choice` Fly",
+                "> `-> END`",
+            ]
+        );
+    }
+
+    #[test]
+    fn display_text_of_choices_is_written_once_at_the_start_of_their_branch() {
+        let content = "\
+*   Hello [there] world.
+    They waved back.
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert_eq!(
+            export_story_to_markdown(&story),
+            "* `choice` Hello there\n\n> Hello world.\n\n> They waved back.\n"
+        );
+    }
+
+    #[test]
+    fn html_export_nests_branches_in_block_quotes_and_escapes_text() {
+        let content = "\
+*   [Fish & chips]
+    Delicious.
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert_eq!(
+            export_story_to_html(&story),
+            "<p class=\"choice\"><span class=\"annotation\">choice</span> Fish &amp; chips</p>\n\
+             <blockquote>\n\
+             <p>Delicious.</p>\n\
+             </blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn negated_and_linked_conditions_are_written_with_keywords() {
+        let content = "\
+VAR a = 1
+VAR b = true
+{a != 2 and (b or not b): Yes.}
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert!(export_story_to_markdown(&story)
+            .contains("`if not a == 2 and (b or not b):` Yes. `end`"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Global variables and functions which are owned by the host instead of the story.
+
+use crate::line::Variable;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Store of global variables which are owned by the host application.
+///
+/// Variables must still be declared in the story with `VAR` since the story is validated
+/// against the declarations. Once a store is set with
+/// [`set_variable_store`][crate::story::Story::set_variable_store()], the story asks the store
+/// for the value of a variable before using its own. If the store returns a value, that value
+/// is used and assignments to the variable from the host are given to the store instead of
+/// being kept in the story.
+///
+/// Values in the store are not saved along with the story state.
+///
+/// # Examples
+/// ```
+/// # use inkling::{Variable, VariableStore};
+/// struct Inventory {
+///     gold: i32,
+/// }
+///
+/// impl VariableStore for Inventory {
+///     fn get_variable(&self, name: &str) -> Option<Variable> {
+///         match name {
+///             "gold" => Some(Variable::Int(self.gold)),
+///             _ => None,
+///         }
+///     }
+///
+///     fn set_variable(&mut self, name: &str, value: Variable) {
+///         if let ("gold", Variable::Int(gold)) = (name, value) {
+///             self.gold = gold;
+///         }
+///     }
+/// }
+/// ```
+pub trait VariableStore {
+    /// Get the value of a variable if it is backed by this store.
+    ///
+    /// Return `None` for variables which should be kept in the story.
+    fn get_variable(&self, name: &str) -> Option<Variable>;
+
+    /// Set the value of a variable which is backed by this store.
+    ///
+    /// Only called for variables which the store returned a value for. The value has been type
+    /// checked against the value returned by the store.
+    fn set_variable(&mut self, name: &str, value: Variable);
+}
+
+/// Variable store which is shared between the host and the story.
+pub type SharedVariableStore = Arc<Mutex<dyn VariableStore + Send>>;
+
+#[derive(Clone, Default)]
+/// Optional variable store used by the story while it is followed.
+pub struct ExternalVariables(Option<SharedVariableStore>);
+
+impl ExternalVariables {
+    /// Create the container with a store.
+    pub fn new(store: SharedVariableStore) -> Self {
+        ExternalVariables(Some(store))
+    }
+
+    /// Get the value of a variable from the store, if it is backed by it.
+    pub fn get(&self, name: &str) -> Option<Variable> {
+        self.0
+            .as_ref()
+            .and_then(|store| lock_store(store).get_variable(name))
+    }
+
+    /// Set the value of a variable in the store.
+    pub fn set(&self, name: &str, value: Variable) {
+        if let Some(store) = self.0.as_ref() {
+            lock_store(store).set_variable(name, value);
+        }
+    }
+}
+
+/// Lock the store, ignoring whether another user of it panicked while holding the lock.
+fn lock_store(
+    store: &SharedVariableStore,
+) -> std::sync::MutexGuard<'_, dyn VariableStore + Send + 'static> {
+    store.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+impl fmt::Debug for ExternalVariables {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(..) => write!(f, "ExternalVariables(Some(..))"),
+            None => write!(f, "ExternalVariables(None)"),
+        }
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for ExternalVariables {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(lhs), Some(rhs)) => Arc::ptr_eq(lhs, rhs),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Function which is declared as `EXTERNAL` in the story and bound by the host.
+///
+/// Called with the evaluated arguments from the story. Return `None` if the function
+/// has no value to return, which is an error if the call is used in an expression.
+pub type ExternalFunction = Arc<dyn Fn(&[Variable]) -> Option<Variable> + Send + Sync>;
+
+#[derive(Clone, Default)]
+/// Set of external functions which are declared in the story, with the functions that
+/// the host has bound to them.
+///
+/// Functions are bound to the story with
+/// [`bind_external_function`][crate::story::Story::bind_external_function()], or before
+/// it is read with [`ReadOptions`][crate::ReadOptions]. Only the names of the declared
+/// functions are saved with the story state: functions must be bound again after it is loaded.
+///
+/// # Examples
+/// ```
+/// # use inkling::{ExternalFunctions, Variable};
+/// let mut functions = ExternalFunctions::default();
+///
+/// functions.bind("double", |arguments: &[Variable]| match arguments {
+///     [Variable::Int(value)] => Some(Variable::Int(2 * value)),
+///     _ => None,
+/// });
+/// ```
+pub struct ExternalFunctions {
+    functions: HashMap<String, Option<ExternalFunction>>,
+}
+
+impl ExternalFunctions {
+    /// Bind a function to a name, replacing any function which was bound to it before.
+    pub fn bind<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Variable]) -> Option<Variable> + Send + Sync + 'static,
+    {
+        self.functions
+            .insert(name.to_string(), Some(Arc::new(function)));
+    }
+
+    /// Declare that a function with the name is external, without binding it.
+    pub(crate) fn declare(&mut self, name: &str) {
+        self.functions.entry(name.to_string()).or_insert(None);
+    }
+
+    /// Get the function which is bound to a name, if there is one.
+    pub fn get(&self, name: &str) -> Option<&ExternalFunction> {
+        self.functions
+            .get(name)
+            .and_then(|function| function.as_ref())
+    }
+
+    /// Whether a function with the name has been declared or bound.
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+
+impl fmt::Debug for ExternalFunctions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = self.functions.keys().collect::<Vec<_>>();
+        names.sort();
+
+        write!(f, "ExternalFunctions({:?})", names)
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for ExternalFunctions {
+    fn eq(&self, other: &Self) -> bool {
+        self.functions.len() == other.functions.len()
+            && self
+                .functions
+                .iter()
+                .all(|(name, lhs)| match (lhs, other.functions.get(name)) {
+                    (Some(lhs), Some(Some(rhs))) => Arc::ptr_eq(lhs, rhs),
+                    (None, Some(None)) => true,
+                    _ => false,
+                })
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl Serialize for ExternalFunctions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut names = self.functions.keys().collect::<Vec<_>>();
+        names.sort();
+
+        names.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for ExternalFunctions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+
+        Ok(ExternalFunctions {
+            functions: names.into_iter().map(|name| (name, None)).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Inventory {
+        gold: i32,
+    }
+
+    impl VariableStore for Inventory {
+        fn get_variable(&self, name: &str) -> Option<Variable> {
+            match name {
+                "gold" => Some(Variable::Int(self.gold)),
+                _ => None,
+            }
+        }
+
+        fn set_variable(&mut self, name: &str, value: Variable) {
+            if let ("gold", Variable::Int(gold)) = (name, value) {
+                self.gold = gold;
+            }
+        }
+    }
+
+    #[test]
+    fn external_variables_read_and_write_through_the_shared_store() {
+        let inventory = Arc::new(Mutex::new(Inventory { gold: 5 }));
+        let external = ExternalVariables::new(inventory.clone());
+
+        assert_eq!(external.get("gold"), Some(Variable::Int(5)));
+        assert_eq!(external.get("silver"), None);
+
+        external.set("gold", Variable::Int(10));
+
+        assert_eq!(inventory.lock().unwrap().gold, 10);
+    }
+
+    #[test]
+    fn empty_external_variables_back_no_variables() {
+        let external = ExternalVariables::default();
+
+        assert_eq!(external.get("gold"), None);
+        external.set("gold", Variable::Int(10));
+    }
+
+    #[test]
+    fn external_functions_are_declared_without_binding_and_bound_by_name() {
+        let mut functions = ExternalFunctions::default();
+        functions.declare("play_sound");
+
+        assert!(functions.is_declared("play_sound"));
+        assert!(functions.get("play_sound").is_none());
+        assert!(!functions.is_declared("stop_sound"));
+
+        functions.bind("play_sound", |_: &[Variable]| Some(Variable::Bool(true)));
+        functions.declare("play_sound");
+
+        let function = functions.get("play_sound").unwrap();
+        assert_eq!(function(&[]), Some(Variable::Bool(true)));
+    }
+}
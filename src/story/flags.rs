@@ -0,0 +1,283 @@
+//! Exclusion of flagged regions of content when a story is read.
+//!
+//! Regions begin with `# ifdef FLAG` or `# ifndef FLAG`, can be split with `# else`
+//! and end with `# endif`. Regions may be nested. Content in a region is only read if
+//! its flag is set (or not set, for `# ifndef`) in the [options][crate::ReadOptions::flags]
+//! of the story. Regions are checked for balance even if they are excluded.
+
+use crate::{
+    consts::TAG_MARKER,
+    error::{
+        parse::flag::{FlagError, FlagErrorKind},
+        utils::MetaData,
+        ReadError,
+    },
+};
+
+use std::collections::HashSet;
+
+/// Line which begins, separates or ends a flagged region.
+enum FlagLine<'a> {
+    If { flag: &'a str, negated: bool },
+    Else,
+    EndIf,
+}
+
+/// Region of content which is included depending on a flag.
+struct Region {
+    /// Whether the region is included before any `# else`.
+    condition: bool,
+    /// Whether an `# else` has been read in the region.
+    in_else: bool,
+    /// Information about the origin of the line which began the region.
+    meta_data: MetaData,
+    /// Line which began the region.
+    line: String,
+}
+
+impl Region {
+    fn is_included(&self) -> bool {
+        self.condition != self.in_else
+    }
+}
+
+/// Filter which tracks flagged regions through the lines of a story.
+pub(crate) struct FlagFilter<'a> {
+    flags: &'a HashSet<String>,
+    regions: Vec<Region>,
+    errors: Vec<FlagError>,
+}
+
+impl<'a> FlagFilter<'a> {
+    pub fn new(flags: &'a HashSet<String>) -> Self {
+        FlagFilter {
+            flags,
+            regions: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Read a trimmed line and return whether it is content which should be kept.
+    ///
+    /// Lines which begin, separate or end regions are never kept.
+    pub fn keep_line(&mut self, line: &str, meta_data: &MetaData) -> bool {
+        let flag_line = match parse_flag_line(line) {
+            Some(Ok(flag_line)) => flag_line,
+            Some(Err(kind)) => {
+                self.add_error(kind, line, meta_data);
+                return false;
+            }
+            None => return self.is_included(),
+        };
+
+        match flag_line {
+            FlagLine::If { flag, negated } => self.regions.push(Region {
+                condition: self.flags.contains(flag) != negated,
+                in_else: false,
+                meta_data: meta_data.clone(),
+                line: line.to_string(),
+            }),
+            FlagLine::Else => match self.regions.last_mut() {
+                Some(region) if region.in_else => {
+                    self.add_error(FlagErrorKind::DuplicateElse, line, meta_data)
+                }
+                Some(region) => region.in_else = true,
+                None => self.add_error(FlagErrorKind::UnmatchedElse, line, meta_data),
+            },
+            FlagLine::EndIf => {
+                if self.regions.pop().is_none() {
+                    self.add_error(FlagErrorKind::UnmatchedEndif, line, meta_data);
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Check that all regions were ended and return all errors that were found.
+    pub fn finish(mut self) -> Result<(), ReadError> {
+        for region in std::mem::take(&mut self.regions) {
+            self.add_error(
+                FlagErrorKind::UnterminatedRegion,
+                &region.line,
+                &region.meta_data,
+            );
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ReadError::FlagError(self.errors))
+        }
+    }
+
+    fn is_included(&self) -> bool {
+        self.regions.iter().all(Region::is_included)
+    }
+
+    fn add_error(&mut self, kind: FlagErrorKind, line: &str, meta_data: &MetaData) {
+        self.errors.push(FlagError {
+            line: line.to_string(),
+            kind,
+            meta_data: meta_data.clone(),
+        });
+    }
+}
+
+/// Parse a trimmed line as a line which begins, separates or ends a region, if it is one.
+///
+/// Other lines which begin with a tag marker are tags.
+fn parse_flag_line(line: &str) -> Option<Result<FlagLine<'_>, FlagErrorKind>> {
+    let content = line.strip_prefix(TAG_MARKER)?.trim();
+
+    let (keyword, rest) = match content.find(char::is_whitespace) {
+        Some(i) => (&content[..i], content[i..].trim()),
+        None => (content, ""),
+    };
+
+    let negated = match keyword {
+        "ifdef" => false,
+        "ifndef" => true,
+        "else" if rest.is_empty() => return Some(Ok(FlagLine::Else)),
+        "endif" if rest.is_empty() => return Some(Ok(FlagLine::EndIf)),
+        _ => return None,
+    };
+
+    if rest.is_empty() {
+        Some(Err(FlagErrorKind::MissingFlagName))
+    } else if rest.contains(char::is_whitespace) {
+        Some(Err(FlagErrorKind::InvalidFlagName {
+            name: rest.to_string(),
+        }))
+    } else {
+        Some(Ok(FlagLine::If {
+            flag: rest,
+            negated,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_kept_lines(content: &str, flags: &[&str]) -> Result<Vec<String>, ReadError> {
+        let flags = flags.iter().map(|flag| flag.to_string()).collect();
+
+        let mut filter = FlagFilter::new(&flags);
+
+        let lines = content
+            .lines()
+            .zip(0..)
+            .filter(|(line, i)| filter.keep_line(line.trim(), &MetaData::from(*i)))
+            .map(|(line, _)| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        filter.finish()?;
+
+        Ok(lines)
+    }
+
+    fn get_error_kinds(content: &str) -> Vec<FlagErrorKind> {
+        match get_kept_lines(content, &[]) {
+            Err(ReadError::FlagError(errors)) => {
+                errors.into_iter().map(|error| error.kind).collect()
+            }
+            other => panic!("expected flag errors but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn regions_are_kept_if_their_flag_is_set() {
+        let content = "\
+Before.
+# ifdef DEMO
+Demo.
+# else
+Full.
+# endif
+# ifndef DEMO
+Not demo.
+# endif
+After.
+";
+
+        assert_eq!(
+            get_kept_lines(content, &["DEMO"]).unwrap(),
+            &["Before.", "Demo.", "After."]
+        );
+        assert_eq!(
+            get_kept_lines(content, &[]).unwrap(),
+            &["Before.", "Full.", "Not demo.", "After."]
+        );
+    }
+
+    #[test]
+    fn nested_regions_are_only_kept_if_all_outer_regions_are() {
+        let content = "\
+#ifdef A
+A.
+#ifdef B
+A and B.
+#else
+A but not B.
+#endif
+#endif
+";
+
+        assert_eq!(
+            get_kept_lines(content, &["B"]).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            get_kept_lines(content, &["A"]).unwrap(),
+            &["A.", "A but not B."]
+        );
+    }
+
+    #[test]
+    fn tags_which_are_not_flag_keywords_are_kept() {
+        let content = "\
+# author: Anonymous
+# ifdefined
+# else if
+";
+
+        assert_eq!(
+            get_kept_lines(content, &[]).unwrap(),
+            &["# author: Anonymous", "# ifdefined", "# else if"]
+        );
+    }
+
+    #[test]
+    fn unbalanced_regions_yield_errors_even_if_excluded() {
+        let content = "\
+# ifdef FULL_GAME
+# ifdef FULL_GAME
+# else
+# else
+# endif
+";
+
+        assert_eq!(
+            get_error_kinds(content),
+            &[
+                FlagErrorKind::DuplicateElse,
+                FlagErrorKind::UnterminatedRegion
+            ]
+        );
+
+        assert_eq!(
+            get_error_kinds("# endif\n# else\n# ifdef\n# ifndef A B"),
+            &[
+                FlagErrorKind::UnmatchedEndif,
+                FlagErrorKind::UnmatchedElse,
+                FlagErrorKind::MissingFlagName,
+                FlagErrorKind::InvalidFlagName {
+                    name: "A B".to_string()
+                },
+            ]
+        );
+    }
+}
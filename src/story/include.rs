@@ -0,0 +1,352 @@
+//! Reading of stories which include the content of other files with `INCLUDE` lines.
+//!
+//! Included files are read through an [`IncludeResolver`], which finds the content of a file
+//! from its name. The lines of an included file before its first knot are inserted where it
+//! is included. Its knots are added after the knots of the file which included it, so that
+//! they do not swallow the content that follows the `INCLUDE` line.
+//!
+//! Every file is only included once. Later includes of the same file are skipped, which also
+//! keeps files which include each other from being read without end.
+
+use crate::{
+    consts::{INCLUDE_MARKER, KNOT_MARKER},
+    error::{parse::include::IncludeError, utils::MetaData, ReadError},
+    log::Logger,
+    story::parse::process_file_content_into_lines_and_metadata,
+};
+
+use std::{collections::HashSet, fs, io, path::Path};
+
+/// Source of the content of files which are included into a story.
+///
+/// Implement this to read stories from archives, bundles or other places than the file
+/// system, and read the story with
+/// [`read_story_with_resolver`][crate::read_story_with_resolver()]. Functions and closures
+/// which read the content of a file from its name are resolvers.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_with_resolver, ReadOptions};
+/// use std::{collections::HashMap, io};
+///
+/// let mut files = HashMap::new();
+/// files.insert("main.ink", "INCLUDE chapters/one.ink\n-> one");
+/// files.insert("chapters/one.ink", "=== one ===\nThe journey begins.");
+///
+/// let resolver = |name: &str| {
+///     files
+///         .get(name)
+///         .map(|content| content.to_string())
+///         .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+/// };
+///
+/// let mut story = read_story_with_resolver("main.ink", &resolver, &ReadOptions::default())
+///     .unwrap();
+///
+/// let mut line_buffer = Vec::new();
+/// story.resume(&mut line_buffer).unwrap();
+///
+/// assert_eq!(&line_buffer[0].text, "The journey begins.\n");
+/// ```
+pub trait IncludeResolver {
+    /// Get the full name of a file from the name that it is included with and the full name
+    /// of the file which includes it.
+    ///
+    /// By default names are relative to the directory of the including file, with
+    /// directories separated by `/`.
+    fn resolve_name(&self, name: &str, parent: &str) -> String {
+        match parent.rfind('/') {
+            Some(i) => format!("{}/{}", &parent[..i], name),
+            None => name.to_string(),
+        }
+    }
+
+    /// Read the content of a file from its full name.
+    fn read_file(&self, name: &str) -> io::Result<String>;
+}
+
+impl<F> IncludeResolver for F
+where
+    F: Fn(&str) -> io::Result<String>,
+{
+    fn read_file(&self, name: &str) -> io::Result<String> {
+        self(name)
+    }
+}
+
+/// Resolver which reads files from the file system.
+pub(crate) struct FileResolver;
+
+impl IncludeResolver for FileResolver {
+    fn resolve_name(&self, name: &str, parent: &str) -> String {
+        Path::new(parent)
+            .parent()
+            .map(|directory| directory.join(name))
+            .unwrap_or_else(|| name.into())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn read_file(&self, name: &str) -> io::Result<String> {
+        fs::read_to_string(name)
+    }
+}
+
+/// Lines of a file which come before its first knot, and the lines of its knots.
+type FileLines = (Vec<(String, MetaData)>, Vec<(String, MetaData)>);
+
+/// Names and content of files which have been read.
+type FileContents = Vec<(String, String)>;
+
+/// Read the lines of a file and every file that it includes.
+///
+/// The lines are trimmed and have their `MetaData` set with the name of the file they
+/// come from. Flagged regions are excluded from every file on its own before its includes
+/// are read, so includes can be flagged. The names and content of all read files are
+/// returned along with the lines.
+pub(crate) fn read_lines_with_includes(
+    name: &str,
+    resolver: &dyn IncludeResolver,
+    flags: &HashSet<String>,
+    log: &mut Logger,
+) -> Result<(Vec<(String, MetaData)>, FileContents), ReadError> {
+    let content = resolver.read_file(name)?;
+
+    let mut reader = IncludeReader {
+        resolver,
+        flags,
+        included: Some(name.to_string()).into_iter().collect(),
+        files: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let (mut lines, knot_lines) = reader.read_file(name, content, log)?;
+    lines.extend(knot_lines);
+
+    if reader.errors.is_empty() {
+        Ok((lines, reader.files))
+    } else {
+        Err(ReadError::IncludeError(reader.errors))
+    }
+}
+
+/// Reader which follows the includes of files.
+struct IncludeReader<'a> {
+    resolver: &'a dyn IncludeResolver,
+    flags: &'a HashSet<String>,
+    /// Full names of every file which has been included.
+    included: HashSet<String>,
+    /// Names and content of every file which has been read.
+    files: FileContents,
+    errors: Vec<IncludeError>,
+}
+
+impl<'a> IncludeReader<'a> {
+    /// Read the lines of a file, with the lines of its includes in place.
+    fn read_file(
+        &mut self,
+        name: &str,
+        content: String,
+        log: &mut Logger,
+    ) -> Result<FileLines, ReadError> {
+        let file_lines =
+            process_file_content_into_lines_and_metadata(&content, Some(name), self.flags, log)?;
+
+        let first_knot = file_lines
+            .iter()
+            .position(|(line, _)| line.starts_with(KNOT_MARKER))
+            .unwrap_or(file_lines.len());
+
+        let mut head = Vec::new();
+        let mut knots = Vec::new();
+        let mut included_knots = Vec::new();
+
+        for (i, (line, meta_data)) in file_lines.into_iter().enumerate() {
+            let lines = if i < first_knot {
+                &mut head
+            } else {
+                &mut knots
+            };
+
+            match get_included_file_name(&line) {
+                Some(included_name) => {
+                    let (included_head, knot_lines) =
+                        self.include(included_name, name, meta_data, log)?;
+
+                    lines.extend(included_head);
+                    included_knots.extend(knot_lines);
+                }
+                None => lines.push((line, meta_data)),
+            }
+        }
+
+        knots.extend(included_knots);
+        self.files.push((name.to_string(), content));
+
+        Ok((head, knots))
+    }
+
+    /// Read the lines of an included file, unless it has already been included.
+    ///
+    /// Files which cannot be read are recorded as errors and give no lines.
+    fn include(
+        &mut self,
+        name: &str,
+        parent: &str,
+        meta_data: MetaData,
+        log: &mut Logger,
+    ) -> Result<FileLines, ReadError> {
+        let resolved_name = self.resolver.resolve_name(name, parent);
+
+        if !self.included.insert(resolved_name.clone()) {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        match self.resolver.read_file(&resolved_name) {
+            Ok(content) => self.read_file(&resolved_name, content, log),
+            Err(err) => {
+                self.errors.push(IncludeError {
+                    name: name.to_string(),
+                    resolved_name,
+                    kind: err.kind(),
+                    message: err.to_string(),
+                    meta_data,
+                });
+
+                Ok((Vec::new(), Vec::new()))
+            }
+        }
+    }
+}
+
+/// Get the name of the included file from a trimmed line, if it is an `INCLUDE` line.
+fn get_included_file_name(line: &str) -> Option<&str> {
+    line.strip_prefix(INCLUDE_MARKER)
+        .filter(|name| name.starts_with(char::is_whitespace))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    fn read_lines(files: &[(&str, &str)]) -> Result<Vec<(String, MetaData)>, ReadError> {
+        let files = files.iter().cloned().collect::<HashMap<_, _>>();
+
+        let resolver = |name: &str| {
+            files
+                .get(name)
+                .map(|content| content.to_string())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        };
+
+        read_lines_with_includes(
+            "main.ink",
+            &resolver,
+            &HashSet::new(),
+            &mut Logger::default(),
+        )
+        .map(|(lines, _)| lines)
+    }
+
+    fn get_text(lines: &[(String, MetaData)]) -> Vec<&str> {
+        lines
+            .iter()
+            .map(|(line, _)| line.as_str())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn include_lines_are_replaced_by_the_content_before_the_first_knot_of_the_file() {
+        let lines = read_lines(&[
+            ("main.ink", "INCLUDE vars.ink\nHello!\n=== knot ===\nKnot"),
+            ("vars.ink", "VAR x = 1\n=== other ===\nOther"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            get_text(&lines),
+            &[
+                "VAR x = 1",
+                "Hello!",
+                "=== knot ===",
+                "Knot",
+                "=== other ===",
+                "Other"
+            ]
+        );
+    }
+
+    #[test]
+    fn included_lines_have_the_file_name_and_line_index_of_their_file() {
+        let lines = read_lines(&[
+            ("main.ink", "Hello!\nINCLUDE one.ink"),
+            ("one.ink", "\nFrom one"),
+        ])
+        .unwrap();
+
+        let (_, meta_data) = lines.iter().find(|(line, _)| line == "From one").unwrap();
+
+        assert_eq!(meta_data.file.as_deref(), Some("one.ink"));
+        assert_eq!(meta_data.line_index, 1);
+    }
+
+    #[test]
+    fn included_names_are_resolved_relative_to_the_including_file() {
+        let lines = read_lines(&[
+            ("main.ink", "INCLUDE chapters/one.ink"),
+            ("chapters/one.ink", "INCLUDE two.ink\nOne"),
+            ("chapters/two.ink", "Two"),
+        ])
+        .unwrap();
+
+        assert_eq!(get_text(&lines), &["Two", "One"]);
+    }
+
+    #[test]
+    fn files_are_only_included_once() {
+        let lines = read_lines(&[
+            (
+                "main.ink",
+                "INCLUDE one.ink\nINCLUDE one.ink\nINCLUDE main.ink",
+            ),
+            ("one.ink", "INCLUDE main.ink\nOne"),
+        ])
+        .unwrap();
+
+        assert_eq!(get_text(&lines), &["One"]);
+    }
+
+    #[test]
+    fn files_which_cannot_be_read_yield_errors_for_their_include_lines() {
+        match read_lines(&[("main.ink", "INCLUDE one.ink\nINCLUDE two.ink")]) {
+            Err(ReadError::IncludeError(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert_eq!(&errors[0].name, "one.ink");
+                assert_eq!(errors[0].kind, io::ErrorKind::NotFound);
+                assert_eq!(errors[1].meta_data.line_index, 1);
+            }
+            other => panic!("expected `ReadError::IncludeError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn include_lines_need_a_file_name_after_the_keyword() {
+        assert_eq!(get_included_file_name("INCLUDE one.ink"), Some("one.ink"));
+        assert_eq!(get_included_file_name("INCLUDE"), None);
+        assert_eq!(get_included_file_name("INCLUDES one.ink"), None);
+    }
+
+    #[test]
+    fn file_resolver_joins_names_to_the_directory_of_the_including_file() {
+        assert_eq!(FileResolver.resolve_name("one.ink", "main.ink"), "one.ink");
+        assert_eq!(
+            Path::new(&FileResolver.resolve_name("one.ink", "stories/main.ink")),
+            Path::new("stories").join("one.ink")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Ordered record of the knots and stitches that were entered while following a story.
+
+use crate::story::types::Location;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Single visit to a knot or stitch.
+pub struct JournalEntry {
+    /// Knot or stitch which was entered.
+    pub location: Location,
+    /// Turn that the location was entered on.
+    ///
+    /// Turns are counted as the number of choices that had been made when the visit happened.
+    pub turn: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Journal of every knot and stitch that was entered, in order.
+///
+/// The journal is only recorded after it has been enabled with
+/// [`enable_visit_journal`][crate::story::Story::enable_visit_journal()] and is saved
+/// along with the story state.
+///
+/// # Example
+/// ```
+/// # use inkling::{read_story_from_string, Location};
+/// let content = "\
+/// -> square
+///
+/// === square ===
+/// *   [Enter the chapel] -> chapel
+///
+/// === chapel ===
+/// *   [Leave] -> square
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// story.enable_visit_journal();
+///
+/// let mut line_buffer = Vec::new();
+///
+/// story.resume(&mut line_buffer).unwrap();
+/// story.make_choice(0).unwrap();
+/// story.resume(&mut line_buffer).unwrap();
+///
+/// let journal = story.get_visit_journal().unwrap();
+/// let chapel = Location::from("chapel");
+///
+/// assert_eq!(journal.get_last_visit(&chapel).unwrap().turn, 1);
+/// assert_eq!(journal.get_turns_since(&chapel, story.get_turn()), Some(0));
+/// ```
+pub struct VisitJournal {
+    /// Visits in the order that they happened.
+    entries: Vec<JournalEntry>,
+}
+
+impl VisitJournal {
+    /// Get all visits in the order that they happened.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Get all visits to a location in the order that they happened.
+    pub fn get_visits<'a>(
+        &'a self,
+        location: &'a Location,
+    ) -> impl Iterator<Item = &'a JournalEntry> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| &entry.location == location)
+    }
+
+    /// Get the most recent visit to a location, if it has been visited.
+    pub fn get_last_visit(&self, location: &Location) -> Option<&JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| &entry.location == location)
+    }
+
+    /// Get the number of turns since a location was last visited, if it has been visited.
+    pub fn get_turns_since(&self, location: &Location, current_turn: u32) -> Option<u32> {
+        self.get_last_visit(location)
+            .map(|entry| current_turn.saturating_sub(entry.turn))
+    }
+
+    /// Add a visit to the journal.
+    pub(crate) fn record(&mut self, location: Location, turn: u32) {
+        self.entries.push(JournalEntry { location, turn });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_visit_is_the_most_recent_entry_for_the_location() {
+        let mut journal = VisitJournal::default();
+
+        journal.record(Location::from("square"), 0);
+        journal.record(Location::from("chapel"), 1);
+        journal.record(Location::from("square"), 2);
+
+        let square = Location::from("square");
+
+        assert_eq!(journal.get_visits(&square).count(), 2);
+        assert_eq!(journal.get_last_visit(&square).unwrap().turn, 2);
+        assert_eq!(journal.get_turns_since(&square, 5), Some(3));
+        assert_eq!(journal.get_turns_since(&Location::from("crypt"), 5), None);
+    }
+}
@@ -0,0 +1,347 @@
+//! Metrics of the structure of knots, for finding sections which branch too much or too little.
+
+use crate::{
+    knot::{Address, AddressKind, Knot},
+    line::{Content, LineChunk, Thread},
+    node::NodeItem,
+    story::{export::compare_content, Story},
+};
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Metrics of the structure of a single knot.
+pub struct KnotMetrics {
+    /// Name of the knot.
+    pub knot: String,
+    /// Number of diverts in other knots which lead to this knot or its stitches.
+    pub incoming_diverts: usize,
+    /// Number of diverts in this knot which lead to other knots or their stitches.
+    ///
+    /// Diverts to `END` and `DONE` and diverts between the stitches of the knot are
+    /// not counted.
+    pub outgoing_diverts: usize,
+    /// Number of choices in the knot.
+    pub choices: usize,
+    /// Maximum number of nested sets of choices in the knot.
+    ///
+    /// A knot without choices has a depth of 0. A knot with choices but no choices
+    /// nested in their branches has a depth of 1.
+    pub max_weave_depth: usize,
+    /// Longest number of lines in a row without a set of choices between them.
+    ///
+    /// Runs are counted separately for every stitch and every branch of a choice.
+    pub longest_linear_run: usize,
+}
+
+/// Compute metrics of the structure of every knot in a story.
+///
+/// Knots are returned in the order that they appear in the story. Content at the
+/// start of the story, before any knot, is returned under the name `$ROOT$`.
+///
+/// # Example
+/// ```
+/// # use inkling::{get_knot_metrics, read_story_from_string};
+/// let content = "\
+/// -> cellar
+///
+/// === cellar ===
+/// The cellar was damp.
+/// Water dripped from the ceiling.
+/// *   [Go up] -> hall
+/// *   [Look around]
+///     *   *   [Open the barrel] -> hall
+///
+/// === hall ===
+/// The hall was bright. -> END
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+/// let metrics = get_knot_metrics(&story);
+///
+/// let cellar = &metrics[1];
+///
+/// assert_eq!(cellar.knot, "cellar");
+/// assert_eq!(cellar.incoming_diverts, 1);
+/// assert_eq!(cellar.outgoing_diverts, 2);
+/// assert_eq!(cellar.choices, 3);
+/// assert_eq!(cellar.max_weave_depth, 2);
+/// assert_eq!(cellar.longest_linear_run, 2);
+///
+/// assert_eq!(metrics[2].incoming_diverts, 2);
+/// ```
+pub fn get_knot_metrics(story: &Story) -> Vec<KnotMetrics> {
+    let mut knots = story.get_knots().iter().collect::<Vec<_>>();
+    knots.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+        compare_content(
+            lhs_name,
+            lhs.meta_data.line(),
+            rhs_name,
+            rhs.meta_data.line(),
+        )
+    });
+
+    let mut metrics = knots
+        .iter()
+        .map(|(name, knot)| get_metrics_of_knot(name, knot))
+        .collect::<Vec<_>>();
+
+    let mut incoming: HashMap<String, usize> = HashMap::new();
+
+    for (name, knot) in knots.iter() {
+        let targets = get_divert_targets(knot);
+
+        for target in targets.into_iter().filter(|target| target != *name) {
+            *incoming.entry(target).or_insert(0) += 1;
+        }
+    }
+
+    for knot_metrics in metrics.iter_mut() {
+        knot_metrics.incoming_diverts = incoming.get(&knot_metrics.knot).copied().unwrap_or(0);
+    }
+
+    metrics
+}
+
+/// Compute all metrics of a knot except for its number of incoming diverts.
+fn get_metrics_of_knot(name: &str, knot: &Knot) -> KnotMetrics {
+    let mut metrics = KnotMetrics {
+        knot: name.to_string(),
+        outgoing_diverts: get_divert_targets(knot)
+            .iter()
+            .filter(|target| *target != name)
+            .count(),
+        ..Default::default()
+    };
+
+    for stitch in knot.stitches.values() {
+        let items = &stitch.root.items;
+
+        metrics.choices += count_choices(items);
+        metrics.max_weave_depth = metrics.max_weave_depth.max(get_weave_depth(items));
+        metrics.longest_linear_run = metrics.longest_linear_run.max(get_longest_run(items));
+    }
+
+    metrics
+}
+
+/// Get the knot names of every divert in a knot which leads to a knot or stitch.
+///
+/// Diverts in the text of choices are counted in the branch that they lead from.
+fn get_divert_targets(knot: &Knot) -> Vec<String> {
+    let mut targets = Vec::new();
+
+    for stitch in knot.stitches.values() {
+        add_divert_targets_of_items(&stitch.root.items, &mut targets);
+    }
+
+    targets
+}
+
+fn add_divert_targets_of_items(items: &[NodeItem], targets: &mut Vec<String>) {
+    for item in items {
+        match item {
+            NodeItem::Line(line) => add_divert_targets_of_chunk(&line.chunk, targets),
+            NodeItem::Thread(Thread {
+                address:
+                    address @ Address::Validated(AddressKind::Location { .. } | AddressKind::Label(..)),
+                ..
+            }) => targets.extend(address.get_knot().ok().map(str::to_string)),
+            NodeItem::Directive(..)
+            | NodeItem::Assignment(..)
+            | NodeItem::Call(..)
+            | NodeItem::Return(..)
+            | NodeItem::Thread(..)
+            | NodeItem::Label(..) => (),
+            NodeItem::BranchingPoint(branches) => {
+                for branch in branches {
+                    add_divert_targets_of_items(&branch.items, targets);
+                }
+            }
+            NodeItem::AlternativeBlock(block) => {
+                for items in &block.items {
+                    add_divert_targets_of_items(items, targets);
+                }
+            }
+            NodeItem::ConditionalBlock(block) => {
+                for branch in &block.branches {
+                    add_divert_targets_of_items(&branch.items, targets);
+                }
+            }
+        }
+    }
+}
+
+fn add_divert_targets_of_chunk(chunk: &LineChunk, targets: &mut Vec<String>) {
+    for item in chunk.items.iter().chain(chunk.else_items.iter()) {
+        match item {
+            Content::Alternative(alternative) => {
+                for chunk in alternative.items.iter() {
+                    add_divert_targets_of_chunk(chunk, targets);
+                }
+            }
+            Content::Divert(
+                address @ Address::Validated(AddressKind::Location { .. } | AddressKind::Label(..)),
+                _,
+            ) => targets.extend(address.get_knot().ok().map(str::to_string)),
+            Content::Nested(chunk) => add_divert_targets_of_chunk(chunk, targets),
+            _ => (),
+        }
+    }
+}
+
+/// Count the choices in a set of items, including those nested in branches.
+fn count_choices(items: &[NodeItem]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            NodeItem::BranchingPoint(branches) => branches
+                .iter()
+                .map(|branch| 1 + count_choices(&branch.items))
+                .sum(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Get the maximum number of nested sets of choices in a set of items.
+fn get_weave_depth(items: &[NodeItem]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            NodeItem::BranchingPoint(branches) => {
+                1 + branches
+                    .iter()
+                    .map(|branch| get_weave_depth(&branch.items))
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Get the longest number of lines in a row without a set of choices in a set of items.
+///
+/// The first line of a branch is the display text of its choice and is not counted.
+fn get_longest_run(items: &[NodeItem]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+
+    for item in items {
+        match item {
+            NodeItem::Line(..) => {
+                current += 1;
+                longest = longest.max(current);
+            }
+            NodeItem::Directive(..)
+            | NodeItem::Assignment(..)
+            | NodeItem::Call(..)
+            | NodeItem::Return(..)
+            | NodeItem::Thread(..)
+            | NodeItem::Label(..) => (),
+            NodeItem::AlternativeBlock(block) => {
+                current += block
+                    .items
+                    .iter()
+                    .map(|items| get_longest_run(items))
+                    .max()
+                    .unwrap_or(0);
+                longest = longest.max(current);
+            }
+            NodeItem::ConditionalBlock(block) => {
+                current += block
+                    .branches
+                    .iter()
+                    .map(|branch| get_longest_run(&branch.items))
+                    .max()
+                    .unwrap_or(0);
+                longest = longest.max(current);
+            }
+            NodeItem::BranchingPoint(branches) => {
+                current = 0;
+
+                for branch in branches {
+                    let run = get_longest_run(branch.items.get(1..).unwrap_or(&[]));
+                    longest = longest.max(run);
+                }
+            }
+        }
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::story::read_story_from_string;
+
+    fn get_metrics(content: &str, knot: &str) -> KnotMetrics {
+        let story = read_story_from_string(content).unwrap();
+
+        get_knot_metrics(&story)
+            .into_iter()
+            .find(|metrics| metrics.knot == knot)
+            .unwrap()
+    }
+
+    #[test]
+    fn diverts_between_stitches_of_a_knot_and_to_the_end_are_not_counted() {
+        let content = "\
+=== tavern ===
+= bar
+-> cellar
+= cellar
+{&Empty.|Quiet. -> street}
+-> END
+
+=== street ===
+-> tavern.bar
+";
+
+        let tavern = get_metrics(content, "tavern");
+
+        assert_eq!(tavern.outgoing_diverts, 1);
+        assert_eq!(tavern.incoming_diverts, 1);
+    }
+
+    #[test]
+    fn longest_run_is_broken_by_choices_and_counted_in_branches() {
+        let content = "\
+=== garden ===
+One.
+Two.
+*   [Sit down]
+    Three.
+    Four.
+    Five.
+-   Six.
+";
+
+        let garden = get_metrics(content, "garden");
+
+        assert_eq!(garden.longest_linear_run, 3);
+        assert_eq!(garden.choices, 1);
+        assert_eq!(garden.max_weave_depth, 1);
+    }
+
+    #[test]
+    fn knots_without_choices_have_no_weave_depth() {
+        let content = "\
+=== road ===
+The road went on.
+";
+
+        assert_eq!(
+            get_metrics(content, "road"),
+            KnotMetrics {
+                knot: "road".to_string(),
+                longest_linear_run: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
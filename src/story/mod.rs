@@ -11,14 +11,62 @@
 //! Most of the rest of this module deals with processing internal data into a form
 //! presented to the user, or validating the content of the story as it is being accessed.
 
+pub(crate) mod audit;
+pub(crate) mod coverage;
+mod explore;
+mod export;
+pub(crate) mod external;
+pub(crate) mod flags;
+#[cfg(feature = "includes")]
+mod include;
+pub(crate) mod journal;
+mod metrics;
+mod options;
 pub(crate) mod parse;
 pub(crate) mod rng;
+#[cfg(feature = "compressed_saves")]
+mod save;
 mod story;
+#[cfg(feature = "serde_support")]
+pub(crate) mod strings;
+mod tags;
+#[cfg(feature = "debug")]
+mod timeline;
+mod transcript;
 pub(crate) mod types;
 mod utils;
 pub(crate) mod validate;
+pub(crate) mod variable_history;
 
-pub use parse::read_story_content_from_string;
-pub use story::{read_story_from_string, Story};
-pub use types::{Choice, Line, LineBuffer, Location, Prompt};
+pub use audit::{Nondeterminism, NondeterminismKind};
+pub use coverage::{ConditionCoverage, ConditionOutcomes, ConditionSite};
+pub use explore::{
+    explore_story, find_walkthrough, Ending, ExplorationReport, ExploreOptions, SoftLock,
+    VariableValues, Walkthrough, WalkthroughOutcome,
+};
+pub use export::{export_story_to_html, export_story_to_markdown};
+pub use external::{ExternalFunction, ExternalFunctions, SharedVariableStore, VariableStore};
+#[cfg(feature = "includes")]
+pub use include::IncludeResolver;
+pub use journal::{JournalEntry, VisitJournal};
+pub use metrics::{get_knot_metrics, KnotMetrics};
+pub use options::ReadOptions;
+#[cfg(feature = "compressed_saves")]
+pub use save::{load_story_from_bytes, save_story_to_bytes};
+#[cfg(feature = "includes")]
+pub use story::{read_story_from_path, read_story_with_resolver};
+pub use story::{
+    read_story_from_reader, read_story_from_string, read_story_from_string_with_options,
+    validate_story_from_string, validate_story_from_string_with_options, Story,
+};
+pub use tags::{Tag, TaggedContent, TaggedContentKind};
+#[cfg(feature = "debug")]
+pub use timeline::{Timeline, TimelineEntry};
+pub use transcript::{Transcript, TranscriptEntry};
+pub use types::{
+    Choice, Comment, CommentKind, EndingKind, ItemBuffer, Line, LineBuffer, Location, OutputBlock,
+    Poll, Prompt, StoryItem, StorySnapshot, UndeclaredVariablePolicy,
+};
 pub use utils::copy_lines_into_string;
+pub use validate::LintOptions;
+pub use variable_history::{ChangeOrigin, VariableChange, VariableHistory};
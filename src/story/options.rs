@@ -0,0 +1,159 @@
+//! Options for reading a story.
+
+use crate::{
+    line::{DirectiveSet, Variable},
+    story::{external::ExternalFunctions, validate::LintOptions},
+};
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, Default)]
+/// Options for how a story is read and validated.
+///
+/// Used with [`read_story_from_string_with_options`][crate::read_story_from_string_with_options]
+/// and [`validate_story_from_string_with_options`][crate::validate_story_from_string_with_options].
+/// The default options are used by the regular functions for reading stories.
+///
+/// # Example
+/// Register a custom `@wait` directive which takes a number of seconds:
+/// ```
+/// # use inkling::{read_story_from_string_with_options, ReadOptions, Variable};
+/// fn parse_wait(content: &str) -> Result<Vec<Variable>, String> {
+///     content
+///         .parse::<f32>()
+///         .map(|seconds| vec![Variable::Float(seconds)])
+///         .map_err(|_| format!("expected a number of seconds but got '{}'", content))
+/// }
+///
+/// let mut options = ReadOptions::default();
+/// options.directives.insert("@wait".to_string(), parse_wait);
+///
+/// assert!(read_story_from_string_with_options("@wait 2.5", &options).is_ok());
+/// assert!(read_story_from_string_with_options("@wait a while", &options).is_err());
+/// ```
+pub struct ReadOptions {
+    /// Name of the file which the story is read from.
+    ///
+    /// If set, errors and warnings print their location as `file:line` instead of
+    /// only the line number.
+    pub file_name: Option<String>,
+    /// Optional style lints to check the story for.
+    pub lints: LintOptions,
+    /// Keep the comments of the story, for tools which rewrite or document its content.
+    ///
+    /// The comments are available from [`get_comments`][crate::story::Story::get_comments()].
+    pub preserve_comments: bool,
+    /// Custom directives, as line prefixes along with the callbacks which parse them.
+    ///
+    /// Lines which begin with a registered prefix, such as `@cue`, are parsed by its callback
+    /// into a [`Directive`][crate::Directive] instead of being read as text. The prefix must
+    /// be the first word of the line.
+    pub directives: DirectiveSet,
+    /// Constants defined by the host, such as the platform or build of the game.
+    ///
+    /// The constants are added to the story as if they were declared with `CONST` in its
+    /// prelude, and are validated the same way. Since no text is added to the story, line
+    /// numbers in errors and warnings are not affected. Declaring a global variable with
+    /// the same name as a constant is an error. Constants are not warned about if they are
+    /// never read.
+    ///
+    /// # Example
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, ReadOptions, Variable};
+    /// let content = "\
+    /// {DEMO_BUILD: The full game continues from here.}
+    /// ";
+    ///
+    /// let mut options = ReadOptions::default();
+    /// options.constants.insert("DEMO_BUILD".to_string(), Variable::Bool(true));
+    ///
+    /// assert!(read_story_from_string_with_options(content, &options).is_ok());
+    /// ```
+    pub constants: HashMap<String, Variable>,
+    /// Initial values of global variables, which replace the values they are declared with.
+    ///
+    /// Use this to set up presets or variants of a story before it is started. Every variable
+    /// must be declared with `VAR` in the story and the value must have the same type as
+    /// the declared value. The story is validated with the given values.
+    ///
+    /// # Example
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, ReadOptions, Variable};
+    /// let content = "\
+    /// VAR lives = 3
+    /// You have {lives} lives left.
+    /// ";
+    ///
+    /// let mut options = ReadOptions::default();
+    /// options.variable_defaults.insert("lives".to_string(), Variable::Int(5));
+    ///
+    /// let story = read_story_from_string_with_options(content, &options).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("lives"), Some(Variable::Int(5)));
+    /// ```
+    pub variable_defaults: HashMap<String, Variable>,
+    /// Flags which select the regions of content that are read.
+    ///
+    /// Content between `# ifdef FLAG` and `# endif` is only read if the flag is set, and
+    /// content between `# ifndef FLAG` and `# endif` only if it is not. Either region can be
+    /// split with `# else`, after which content is read in the opposite case. Regions can be
+    /// nested and span any number of lines, knots and stitches. Use this to keep the content
+    /// of several builds of a game in the same story. Regions are balanced even if they
+    /// are excluded: beginning, separating or ending them out of order is an error.
+    ///
+    /// # Example
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, ReadOptions};
+    /// let content = "\
+    /// The ferry arrived at the island.
+    /// ## ifdef DEMO
+    /// Thank you for playing the demo!
+    /// ## else
+    /// -> island
+    ///
+    /// === island ===
+    /// The harbour was quiet.
+    /// ## endif
+    /// ";
+    ///
+    /// let mut options = ReadOptions::default();
+    /// options.flags.insert("DEMO".to_string());
+    ///
+    /// let story = read_story_from_string_with_options(content, &options).unwrap();
+    ///
+    /// assert!(story.parse_location("island").is_err());
+    /// ```
+    pub flags: HashSet<String>,
+    /// Functions which are bound to the `EXTERNAL` functions of the story before it is read.
+    ///
+    /// Functions can also be bound after the story has been read with
+    /// [`bind_external_function`][crate::story::Story::bind_external_function()].
+    ///
+    /// # Example
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, ReadOptions, Variable};
+    /// let content = "\
+    /// EXTERNAL double(x)
+    /// Twice three is {double(3)}.
+    /// ";
+    ///
+    /// let mut options = ReadOptions::default();
+    /// options.external_functions.bind("double", |arguments: &[Variable]| match arguments {
+    ///     [Variable::Int(value)] => Some(Variable::Int(2 * value)),
+    ///     _ => None,
+    /// });
+    ///
+    /// let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "Twice three is 6.\n");
+    /// ```
+    pub external_functions: ExternalFunctions,
+    /// Report every `EXTERNAL` function which is not bound in
+    /// [`external_functions`][ReadOptions::external_functions] as a validation error.
+    ///
+    /// Otherwise unbound functions are reported when they are first called.
+    pub check_external_functions: bool,
+}
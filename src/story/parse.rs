@@ -8,7 +8,8 @@
 use crate::{
     consts::{
         CONST_MARKER, EXTERNAL_FUNCTION_MARKER, INCLUDE_MARKER, KNOT_MARKER, LINE_COMMENT_MARKER,
-        ROOT_KNOT_NAME, STITCH_MARKER, TAG_MARKER, TODO_COMMENT_MARKER, VARIABLE_MARKER,
+        LIST_MARKER, MULTILINE_COMMENT_BEGIN_MARKER, MULTILINE_COMMENT_END_MARKER, ROOT_KNOT_NAME,
+        STITCH_MARKER, TAG_MARKER, TODO_COMMENT_MARKER, VARIABLE_MARKER,
     },
     error::{
         parse::{
@@ -19,27 +20,227 @@ use crate::{
         utils::MetaData,
         ReadError,
     },
-    knot::{parse_stitch_from_lines, read_knot_name, read_stitch_name, Knot, KnotSet, Stitch},
-    line::parse_variable,
+    knot::{
+        parse_stitch_from_lines, read_function_signature, read_knot_name, read_knot_signature,
+        read_stitch_name, Knot, KnotSet, KnotSignature, Stitch,
+    },
+    line::{is_escaped, is_return_line, parse_variable, DirectiveSet, List, ListItem, Variable},
     log::Logger,
-    story::types::{VariableInfo, VariableSet},
+    story::{
+        flags::FlagFilter,
+        types::{Comment, CommentKind, VariableInfo, VariableSet},
+    },
 };
 
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io::BufRead,
+    ops::Range,
+};
 
-/// Read an Ink story from a string and return knots along with the metadata.
+#[cfg(test)]
+/// Read an Ink story from a string without a file name and return knots along with the metadata.
 pub fn read_story_content_from_string(
     content: &str,
     log: &mut Logger,
 ) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
-    let mut content_lines = process_file_content_into_lines_and_metadata(content, log);
+    read_story_content_from_file_string(
+        content,
+        None,
+        &DirectiveSet::new(),
+        &HashMap::new(),
+        &HashSet::new(),
+        log,
+    )
+}
+
+/// Read an Ink story from the string content of a file and return knots along with
+/// the metadata.
+///
+/// If a file name is given it is set in the `MetaData` of all content, to print with
+/// errors and warnings. Lines which begin with a prefix in the set of directives
+/// are parsed as custom directives. The given constants are added to the global variables
+/// of the story. Regions of content which are excluded by the given flags are removed.
+pub(crate) fn read_story_content_from_file_string(
+    content: &str,
+    file_name: Option<&str>,
+    directives: &DirectiveSet,
+    constants: &HashMap<String, Variable>,
+    flags: &HashSet<String>,
+    log: &mut Logger,
+) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
+    let content_lines =
+        process_file_content_into_lines_and_metadata(content, file_name, flags, log)?;
+
+    read_story_content_from_lines(as_line_refs(&content_lines), directives, constants)
+}
+
+/// Read an Ink story from lines of content and return knots along with the metadata.
+///
+/// The lines are trimmed and have their `MetaData` set, like the lines from
+/// [`process_file_content_into_lines_and_metadata`][process_file_content_into_lines_and_metadata()],
+/// and flagged regions are already excluded. This is used to read the combined lines
+/// of stories which include other files.
+pub(crate) fn read_story_content_from_lines(
+    mut content_lines: Vec<(&str, MetaData)>,
+    directives: &DirectiveSet,
+    constants: &HashMap<String, Variable>,
+) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
     prune_empty_lines(&mut content_lines);
+    add_knot_and_stitch_names_to_meta_data(&mut content_lines);
+
+    let (root_knot, mut variables, mut external_functions, tags, mut prelude_errors) =
+        split_off_and_parse_prelude(&mut content_lines, directives, constants)?;
+
+    let declaration_lines = split_off_declaration_lines(&mut content_lines);
+    prelude_errors.extend(add_global_variables(&mut variables, &declaration_lines));
+    prelude_errors.extend(add_external_functions(
+        &mut external_functions,
+        &declaration_lines,
+    ));
+
+    let (knots, knot_errors) = parse_knots_from_lines(content_lines, directives);
+
+    collect_story_content(
+        root_knot,
+        knots,
+        external_functions,
+        variables,
+        tags,
+        knot_errors,
+        prelude_errors,
+    )
+}
+
+/// Read an Ink story line by line from a reader and return knots along with the metadata.
+///
+/// The result is identical to that of
+/// [`read_story_content_from_file_string`][read_story_content_from_file_string()], but only
+/// the lines of a single knot are kept in memory at a time. Each knot is parsed as soon as
+/// the next one begins. If a list of comments is given, comments are read into it as they
+/// are encountered.
+pub(crate) fn read_story_content_from_reader<R: BufRead>(
+    reader: R,
+    file_name: Option<&str>,
+    directives: &DirectiveSet,
+    constants: &HashMap<String, Variable>,
+    flags: &HashSet<String>,
+    comments: Option<&mut Vec<Comment>>,
+    log: &mut Logger,
+) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
+    let mut preprocessor = LinePreprocessor::new(file_name, flags, comments.is_some());
+    let mut knot_parser = KnotParser::default();
+
+    let mut prelude = None;
+    let mut knot_lines: Vec<(String, MetaData)> = Vec::new();
+    let mut declaration_lines: Vec<(String, MetaData)> = Vec::new();
+
+    for line in reader.lines() {
+        let (trimmed_line, meta_data) = preprocessor.process_line(&line?, log);
+
+        if trimmed_line.is_empty() {
+            continue;
+        }
+
+        // Declarations before the first knot are parsed with the prelude
+        if prelude.is_some() && is_declaration_line(&trimmed_line) {
+            declaration_lines.push((trimmed_line, meta_data));
+            continue;
+        }
+
+        if trimmed_line.starts_with(KNOT_MARKER) {
+            let lines = std::mem::take(&mut knot_lines);
+
+            if prelude.is_none() {
+                let head = (trimmed_line.as_str(), meta_data.clone());
+                prelude.replace(parse_prelude_from_owned_lines(
+                    lines,
+                    Some(head),
+                    directives,
+                    constants,
+                )?);
+            } else {
+                knot_parser.add_knot(as_line_refs(&lines), directives);
+            }
+        }
+
+        knot_lines.push((trimmed_line, meta_data));
+    }
+
+    let read_comments = preprocessor.finish()?;
+
+    if let Some(comments) = comments {
+        comments.extend(read_comments);
+    }
+
+    let (root_knot, mut variables, mut external_functions, tags, mut prelude_errors) = match prelude
+    {
+        Some(prelude) => {
+            knot_parser.add_knot(as_line_refs(&knot_lines), directives);
+            prelude
+        }
+        None => parse_prelude_from_owned_lines(knot_lines, None, directives, constants)?,
+    };
+
+    let declaration_lines = as_line_refs(&declaration_lines);
+    prelude_errors.extend(add_global_variables(&mut variables, &declaration_lines));
+    prelude_errors.extend(add_external_functions(
+        &mut external_functions,
+        &declaration_lines,
+    ));
+
+    let (knots, knot_errors) = knot_parser.finish();
+
+    collect_story_content(
+        root_knot,
+        knots,
+        external_functions,
+        variables,
+        tags,
+        knot_errors,
+        prelude_errors,
+    )
+}
 
-    let (root_knot, variables, tags, prelude_errors) =
-        split_off_and_parse_prelude(&mut content_lines)?;
+/// Borrow a set of owned lines as the line slices which are used for parsing.
+fn as_line_refs(lines: &[(String, MetaData)]) -> Vec<(&str, MetaData)> {
+    lines
+        .iter()
+        .map(|(line, meta_data)| (line.as_str(), meta_data.clone()))
+        .collect()
+}
 
-    let (mut knots, mut knot_errors) = parse_knots_from_lines(content_lines);
+/// Parse the prelude and root knot from the lines before the first named knot.
+///
+/// The head line of the first knot is used for the root knot metadata if the root is empty.
+fn parse_prelude_from_owned_lines(
+    lines: Vec<(String, MetaData)>,
+    first_knot_head: Option<(&str, MetaData)>,
+    directives: &DirectiveSet,
+    constants: &HashMap<String, Variable>,
+) -> Result<ParsedPrelude, ReadError> {
+    let mut lines = as_line_refs(&lines);
+    lines.extend(first_knot_head);
+
+    split_off_and_parse_prelude(&mut lines, directives, constants)
+}
 
+/// Collect the parsed root knot, named knots and external functions into the story content,
+/// or all errors.
+///
+/// External functions are added as knots. A function with the same name as an external
+/// function is its fallback, which is called if the host has not bound the external function.
+/// Any other knot with the same name is an error.
+fn collect_story_content(
+    root_knot: Result<Knot, KnotError>,
+    mut knots: KnotSet,
+    external_functions: KnotSet,
+    variables: VariableSet,
+    tags: Vec<String>,
+    mut knot_errors: Vec<KnotError>,
+    prelude_errors: Vec<PreludeError>,
+) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
     match root_knot {
         Ok(knot) => {
             knots.insert(ROOT_KNOT_NAME.to_string(), knot);
@@ -47,6 +248,22 @@ pub fn read_story_content_from_string(
         Err(knot_error) => knot_errors.insert(0, knot_error),
     }
 
+    for (name, function) in external_functions {
+        match knots.get_mut(&name) {
+            Some(knot) if knot.is_function => knot.is_external = true,
+            Some(knot) => knot_errors.push(KnotError {
+                knot_meta_data: knot.meta_data.clone(),
+                line_errors: vec![KnotErrorKind::DuplicateKnotName {
+                    name,
+                    prev_meta_data: function.meta_data,
+                }],
+            }),
+            None => {
+                knots.insert(name, function);
+            }
+        }
+    }
+
     if knot_errors.is_empty() && prelude_errors.is_empty() {
         Ok((knots, variables, tags))
     } else {
@@ -58,22 +275,304 @@ pub fn read_story_content_from_string(
     }
 }
 
+/// Read all comments from the string content of a file.
+///
+/// Comments are found with the same rules as when they are trimmed from the content
+/// before parsing it, and are attached to the line of content they belong to.
+pub(crate) fn read_comments_from_file_string(
+    content: &str,
+    file_name: Option<&str>,
+    flags: &HashSet<String>,
+) -> Vec<Comment> {
+    let mut log = Logger::default();
+    let mut preprocessor = LinePreprocessor::new(file_name, flags, true);
+
+    for line in content.lines() {
+        preprocessor.process_line(line, &mut log);
+    }
+
+    preprocessor.finish().unwrap_or_default()
+}
+
+/// Find the kind and starting byte index of a comment in a line, if one is present.
+///
+/// Comment markers which are escaped with a backslash do not begin comments.
+fn find_comment(line: &str) -> Option<(CommentKind, usize)> {
+    if let Some(i) = find_unescaped(line, LINE_COMMENT_MARKER) {
+        Some((CommentKind::Line, i))
+    } else if line.trim_start().starts_with(TODO_COMMENT_MARKER) {
+        Some((CommentKind::Todo, line.find(TODO_COMMENT_MARKER).unwrap()))
+    } else {
+        None
+    }
+}
+
+/// Find the byte index of the first marker in a line which is not escaped by a backslash.
+fn find_unescaped(line: &str, marker: &str) -> Option<usize> {
+    line.match_indices(marker)
+        .map(|(i, _)| i)
+        .find(|&i| !is_escaped(line, i))
+}
+
+/// Preprocessor of the lines of a file before they are parsed as story content.
+///
+/// Lines are read one at a time, which is the same for the full content of a file
+/// and for a story which is read line by line. Block comments, line comments and content
+/// which is excluded by flags are removed from the lines, which are trimmed and have
+/// their `MetaData` set. If comments are kept they are collected as they are read.
+pub(crate) struct LinePreprocessor<'a> {
+    file_name: Option<&'a str>,
+    line_index: usize,
+    block_comments: BlockCommentFilter,
+    flag_filter: FlagFilter<'a>,
+    locations: LocationTracker,
+    /// Information about the line that the block comment which is currently read began on.
+    open_comment: Option<MetaData>,
+    /// Comments which have been read, if they are kept.
+    comments: Option<Vec<Comment>>,
+    /// Number of read comments which belong to a line of content.
+    ///
+    /// The other comments belong to the next line of content which is read.
+    num_placed_comments: usize,
+}
+
+impl<'a> LinePreprocessor<'a> {
+    pub fn new(
+        file_name: Option<&'a str>,
+        flags: &'a HashSet<String>,
+        keep_comments: bool,
+    ) -> Self {
+        LinePreprocessor {
+            file_name,
+            line_index: 0,
+            block_comments: BlockCommentFilter::default(),
+            flag_filter: FlagFilter::new(flags),
+            locations: LocationTracker::default(),
+            open_comment: None,
+            comments: Some(Vec::new()).filter(|_| keep_comments),
+            num_placed_comments: 0,
+        }
+    }
+
+    /// Read the next line of the file and return its trimmed content with its `MetaData`.
+    ///
+    /// Lines which have no content are returned as empty lines, to keep the line index
+    /// of those which follow them.
+    pub fn process_line(&mut self, line: &str, log: &mut Logger) -> (String, MetaData) {
+        let mut meta_data = MetaData {
+            file: self.file_name.map(|name| name.to_string()),
+            ..MetaData::from(self.line_index)
+        };
+
+        self.line_index += 1;
+
+        let continues_comment = self.block_comments.in_comment;
+        let (stripped_line, comment_spans) = self.block_comments.strip(line);
+
+        let mut trimmed_line = trim_comment(&stripped_line, log, &meta_data).trim();
+
+        if !self.flag_filter.keep_line(trimmed_line, &meta_data) {
+            trimmed_line = "";
+        }
+
+        self.locations.update(trimmed_line, &mut meta_data);
+
+        if !self.block_comments.in_comment {
+            self.open_comment = None;
+        } else if comment_spans.len() > usize::from(continues_comment) {
+            self.open_comment.replace(meta_data.clone());
+        }
+
+        if let Some(comments) = self.comments.as_mut() {
+            for (i, span) in comment_spans.into_iter().enumerate() {
+                match comments.last_mut() {
+                    Some(comment) if i == 0 && continues_comment => {
+                        comment.text.push('\n');
+                        comment.text.push_str(&line[span]);
+                    }
+                    _ => comments.push(Comment {
+                        kind: CommentKind::Block,
+                        text: line[span.clone()].to_string(),
+                        meta_data: meta_data.clone(),
+                        span,
+                        element: None,
+                    }),
+                }
+            }
+
+            // Line comments are in the part of the line which follows all block comments
+            if let Some((kind, start)) = find_comment(&stripped_line) {
+                let start = start + line.len() - stripped_line.len();
+
+                comments.push(Comment {
+                    kind,
+                    text: line[start..].to_string(),
+                    meta_data: meta_data.clone(),
+                    span: start..line.len(),
+                    element: None,
+                });
+            }
+
+            if !trimmed_line.is_empty() {
+                for comment in comments[self.num_placed_comments..].iter_mut() {
+                    comment.element.replace(meta_data.clone());
+                }
+
+                self.num_placed_comments = comments.len();
+            }
+        }
+
+        (trimmed_line.to_string(), meta_data)
+    }
+
+    /// Check that all block comments and flagged regions were ended and return the comments
+    /// which were read.
+    pub fn finish(self) -> Result<Vec<Comment>, ReadError> {
+        if let Some(meta_data) = self.open_comment {
+            return Err(ReadError::UnterminatedComment { meta_data });
+        }
+
+        self.flag_filter.finish()?;
+
+        Ok(self.comments.unwrap_or_default())
+    }
+}
+
+#[derive(Default)]
+/// Remover of block comments from lines which are read one at a time.
+///
+/// Block comments begin with `/*` and end with `*/`, and may span several lines.
+struct BlockCommentFilter {
+    /// Whether the lines which are read are currently inside of a block comment.
+    in_comment: bool,
+}
+
+impl BlockCommentFilter {
+    /// Remove the parts of a line which are inside of block comments.
+    ///
+    /// Returns the line without the parts along with the byte range of every part,
+    /// including their markers. Block comments cannot begin inside of a line comment,
+    /// which is left in the line, or with a marker which is escaped by a backslash.
+    fn strip<'a>(&mut self, line: &'a str) -> (Cow<'a, str>, Vec<Range<usize>>) {
+        if !self.in_comment && !line.contains(MULTILINE_COMMENT_BEGIN_MARKER) {
+            return (Cow::Borrowed(line), Vec::new());
+        }
+
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        let mut start = 0;
+        let mut position = 0;
+
+        loop {
+            let remaining = &line[position..];
+
+            if self.in_comment {
+                match remaining.find(MULTILINE_COMMENT_END_MARKER) {
+                    Some(i) => {
+                        position += i + MULTILINE_COMMENT_END_MARKER.len();
+                        spans.push(start..position);
+                        self.in_comment = false;
+                    }
+                    None => {
+                        spans.push(start..line.len());
+                        break;
+                    }
+                }
+            } else {
+                let begin = find_unescaped(remaining, MULTILINE_COMMENT_BEGIN_MARKER);
+                let line_comment = find_unescaped(remaining, LINE_COMMENT_MARKER);
+
+                match (begin, line_comment) {
+                    (Some(i), Some(j)) if j < i => {
+                        text.push_str(remaining);
+                        break;
+                    }
+                    (Some(i), _) => {
+                        text.push_str(&remaining[..i]);
+                        start = position + i;
+                        position = start + MULTILINE_COMMENT_BEGIN_MARKER.len();
+                        self.in_comment = true;
+                    }
+                    (None, _) => {
+                        text.push_str(remaining);
+                        break;
+                    }
+                }
+            }
+        }
+
+        (Cow::Owned(text), spans)
+    }
+}
+
 /// Split the content from a `.ink` file into lines, trim them and add MetaData.
 ///
-/// This also removes comments from the lines, leaving only the actual content that will
-/// be used into story.
-fn process_file_content_into_lines_and_metadata<'a>(
-    content: &'a str,
+/// This also removes comments and content which is excluded by the given flags from
+/// the lines, leaving only the actual content that will be used in the story. The lines
+/// are preprocessed in the same way as stories which are read line by line.
+pub(crate) fn process_file_content_into_lines_and_metadata(
+    content: &str,
+    file_name: Option<&str>,
+    flags: &HashSet<String>,
     log: &mut Logger,
-) -> Vec<(&'a str, MetaData)> {
-    content
+) -> Result<Vec<(String, MetaData)>, ReadError> {
+    let mut preprocessor = LinePreprocessor::new(file_name, flags, false);
+
+    let lines = content
         .lines()
-        .zip(0..)
-        .map(|(line, line_index)| (line, MetaData::from(line_index)))
-        .map(|(line, meta_data)| (trim_comment(line, log, &meta_data).trim(), meta_data))
-        .collect()
+        .map(|line| preprocessor.process_line(line, log))
+        .collect();
+
+    preprocessor.finish()?;
+
+    Ok(lines)
+}
+
+/// Mark the `MetaData` of every line with the knot and stitch it is in.
+///
+/// Lines in the root of the story have no knot name and lines before the first stitch
+/// in a knot have no stitch name. Neither is set after a knot or stitch with an invalid
+/// name, since that content cannot be addressed by name anyway.
+fn add_knot_and_stitch_names_to_meta_data(lines: &mut [(&str, MetaData)]) {
+    let mut locations = LocationTracker::default();
+
+    for (line, meta_data) in lines.iter_mut() {
+        locations.update(line, meta_data);
+    }
+}
+
+#[derive(Default)]
+/// Knot and stitch that the lines which are currently read belong to.
+struct LocationTracker {
+    knot: Option<String>,
+    stitch: Option<String>,
+}
+
+impl LocationTracker {
+    /// Update the location with a trimmed line and mark its `MetaData` with it.
+    fn update(&mut self, line: &str, meta_data: &mut MetaData) {
+        if line.starts_with(KNOT_MARKER) {
+            self.knot = read_knot_name(line).ok();
+            self.stitch = None;
+        } else if line.starts_with(STITCH_MARKER) {
+            self.stitch = read_stitch_name(line).ok();
+        }
+
+        meta_data.knot = self.knot.clone();
+        meta_data.stitch = self.stitch.clone();
+    }
 }
 
+/// Parsed root knot, global variables, external functions and global tags along with
+/// all prelude errors.
+type ParsedPrelude = (
+    Result<Knot, KnotError>,
+    VariableSet,
+    KnotSet,
+    Vec<String>,
+    Vec<PreludeError>,
+);
+
 /// Split off lines until the first named knot then parse its content and root knot.
 ///
 /// After this function has been called, the given set of lines starts at the first named
@@ -85,17 +584,14 @@ fn process_file_content_into_lines_and_metadata<'a>(
 /// encountered errors from parsing the story at once, not just the first.
 fn split_off_and_parse_prelude(
     lines: &mut Vec<(&str, MetaData)>,
-) -> Result<
-    (
-        Result<Knot, KnotError>,
-        VariableSet,
-        Vec<String>,
-        Vec<PreludeError>,
-    ),
-    ReadError,
-> {
+    directives: &DirectiveSet,
+    constants: &HashMap<String, Variable>,
+) -> Result<ParsedPrelude, ReadError> {
     let prelude_and_root = split_off_prelude_lines(lines);
-    let (prelude_lines, root_lines) = split_prelude_into_metadata_and_text(&prelude_and_root);
+    let (mut prelude_lines, mut root_lines) =
+        split_prelude_into_metadata_and_text(&prelude_and_root);
+
+    prelude_lines.extend(split_off_declaration_lines(&mut root_lines));
 
     let root_meta_data = root_lines
         .first()
@@ -105,49 +601,106 @@ fn split_off_and_parse_prelude(
         .ok_or(ReadError::Empty)?;
 
     let tags = parse_global_tags(&prelude_lines);
-    let (variables, prelude_errors) = parse_global_variables(&prelude_lines);
-    let root_knot = parse_root_knot_from_lines(root_lines, root_meta_data);
-
-    Ok((root_knot, variables, tags, prelude_errors))
+    let (mut variables, mut prelude_errors) = parse_global_variables(&prelude_lines);
+    prelude_errors.extend(add_host_constants(
+        &mut variables,
+        constants,
+        &prelude_lines,
+    ));
+    let mut external_functions = HashMap::new();
+    prelude_errors.extend(add_external_functions(
+        &mut external_functions,
+        &prelude_lines,
+    ));
+    let root_knot = parse_root_knot_from_lines(root_lines, root_meta_data, directives);
+
+    Ok((
+        root_knot,
+        variables,
+        external_functions,
+        tags,
+        prelude_errors,
+    ))
 }
 
 /// Parse all knots from a set of lines and return along with any encountered errors.
-fn parse_knots_from_lines(lines: Vec<(&str, MetaData)>) -> (KnotSet, Vec<KnotError>) {
-    let knot_line_sets = divide_lines_at_marker(lines, KNOT_MARKER);
-
-    let mut knots = HashMap::new();
-    let mut knot_errors = Vec::new();
-
-    for lines in knot_line_sets.into_iter().filter(|lines| !lines.is_empty()) {
-        match get_knot_from_lines(lines) {
-            Ok((knot_name, knot_data)) => {
-                if !knots.contains_key(&knot_name) {
-                    knots.insert(knot_name, knot_data);
-                } else {
-                    let prev_meta_data = knots.get(&knot_name).unwrap().meta_data.clone();
-
-                    knot_errors.push(KnotError {
-                        knot_meta_data: knot_data.meta_data.clone(),
-                        line_errors: vec![KnotErrorKind::DuplicateKnotName {
-                            name: knot_name,
-                            prev_meta_data,
-                        }],
-                    });
+fn parse_knots_from_lines(
+    lines: Vec<(&str, MetaData)>,
+    directives: &DirectiveSet,
+) -> (KnotSet, Vec<KnotError>) {
+    let mut knot_parser = KnotParser::default();
+
+    for lines in divide_lines_at_marker(lines, KNOT_MARKER) {
+        knot_parser.add_knot(lines, directives);
+    }
+
+    knot_parser.finish()
+}
+
+#[derive(Default)]
+/// Parsed knots and errors from knots which are parsed one at a time.
+struct KnotParser {
+    knots: KnotSet,
+    knot_errors: Vec<KnotError>,
+    // Knot names are recorded before parsing their content, to also catch duplicates
+    // of knots which fail to parse and would otherwise not be present in the final set
+    knot_locations: HashMap<String, MetaData>,
+}
+
+impl KnotParser {
+    /// Parse a knot from its lines, starting with its head line, and add it to the set.
+    ///
+    /// Empty sets of lines are ignored.
+    fn add_knot(&mut self, lines: Vec<(&str, MetaData)>, directives: &DirectiveSet) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let (head_line, knot_meta_data) = lines[0].clone();
+
+        let duplicate_error = read_knot_name(head_line).ok().and_then(|name| {
+            get_duplicate_name_error(&mut self.knot_locations, name, &knot_meta_data).map(
+                |(name, prev_meta_data)| KnotErrorKind::DuplicateKnotName {
+                    name,
+                    prev_meta_data,
+                },
+            )
+        });
+
+        match (get_knot_from_lines(lines, directives), duplicate_error) {
+            (Ok((knot_name, knot_data)), None) => {
+                self.knots.insert(knot_name, knot_data);
+            }
+            (Ok(..), Some(error)) => self.knot_errors.push(KnotError {
+                knot_meta_data,
+                line_errors: vec![error],
+            }),
+            (Err(mut error), duplicate_error) => {
+                if let Some(duplicate_error) = duplicate_error {
+                    error.line_errors.insert(0, duplicate_error);
                 }
+
+                self.knot_errors.push(error);
             }
-            Err(error) => knot_errors.push(error),
         }
     }
 
-    (knots, knot_errors)
+    /// Return all parsed knots along with the encountered errors.
+    fn finish(self) -> (KnotSet, Vec<KnotError>) {
+        (self.knots, self.knot_errors)
+    }
 }
 
 /// Parse the root knot from a set of lines.
 fn parse_root_knot_from_lines(
     lines: Vec<(&str, MetaData)>,
     meta_data: MetaData,
+    directives: &DirectiveSet,
 ) -> Result<Knot, KnotError> {
-    let (_, stitches, line_errors) = get_stitches_from_lines(lines, ROOT_KNOT_NAME);
+    let mut line_errors = get_return_outside_function_errors(&lines);
+
+    let (_, stitches, stitch_errors) = get_stitches_from_lines(lines, ROOT_KNOT_NAME, directives);
+    line_errors.extend(stitch_errors);
 
     if line_errors.is_empty() {
         Ok(Knot {
@@ -155,6 +708,9 @@ fn parse_root_knot_from_lines(
             stitches,
             tags: Vec::new(),
             meta_data,
+            is_function: false,
+            parameters: Vec::new(),
+            is_external: false,
         })
     } else {
         Err(KnotError {
@@ -169,7 +725,10 @@ fn parse_root_knot_from_lines(
 /// Creates `Stitch`es and their node tree of branching content. Returns the knot and its name.
 ///
 /// Assumes that the set of lines is non-empty, which we assert before calling this function.
-fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), KnotError> {
+fn get_knot_from_lines(
+    lines: Vec<(&str, MetaData)>,
+    directives: &DirectiveSet,
+) -> Result<(String, Knot), KnotError> {
     let (head, mut tail) = lines
         .split_first()
         .map(|(head, tail)| (head, tail.to_vec()))
@@ -179,14 +738,22 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
 
     let mut line_errors = Vec::new();
 
-    let knot_name = match read_knot_name(head_line) {
-        Ok(name) => name,
+    let KnotSignature {
+        name: knot_name,
+        is_function,
+        parameters,
+    } = match read_knot_signature(head_line) {
+        Ok(signature) => signature,
         Err(kind) => {
             let (invalid_name, error) = get_invalid_name_error(head_line, kind, &knot_meta_data);
 
             line_errors.push(error);
 
-            invalid_name
+            KnotSignature {
+                name: invalid_name,
+                is_function: false,
+                parameters: Vec::new(),
+            }
         }
     };
 
@@ -196,7 +763,12 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
         line_errors.push(KnotErrorKind::EmptyKnot);
     }
 
-    let (default_stitch, stitches, stitch_errors) = get_stitches_from_lines(tail, &knot_name);
+    if !is_function {
+        line_errors.extend(get_return_outside_function_errors(&tail));
+    }
+
+    let (default_stitch, stitches, stitch_errors) =
+        get_stitches_from_lines(tail, &knot_name, directives);
     line_errors.extend(stitch_errors);
 
     if default_stitch.is_some() && line_errors.is_empty() {
@@ -207,6 +779,9 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
                 stitches,
                 tags,
                 meta_data: knot_meta_data.clone(),
+                is_function,
+                parameters,
+                is_external: false,
             },
         ))
     } else {
@@ -217,10 +792,23 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
     }
 }
 
+/// Get an error for every logic line which returns from a function in lines of a knot
+/// which is not one.
+fn get_return_outside_function_errors(lines: &[(&str, MetaData)]) -> Vec<KnotErrorKind> {
+    lines
+        .iter()
+        .filter(|(line, _)| is_return_line(line))
+        .map(|(_, meta_data)| KnotErrorKind::ReturnOutsideFunction {
+            meta_data: meta_data.clone(),
+        })
+        .collect()
+}
+
 /// Parse all stitches from a set of lines and return along with encountered errors.
 fn get_stitches_from_lines(
     lines: Vec<(&str, MetaData)>,
     knot_name: &str,
+    directives: &DirectiveSet,
 ) -> (Option<String>, HashMap<String, Stitch>, Vec<KnotErrorKind>) {
     let knot_stitch_sets = divide_lines_at_marker(lines, STITCH_MARKER);
 
@@ -228,29 +816,39 @@ fn get_stitches_from_lines(
     let mut stitches = HashMap::new();
     let mut line_errors = Vec::new();
 
+    let mut stitch_locations: HashMap<String, MetaData> = HashMap::new();
+
     for (stitch_index, lines) in knot_stitch_sets
         .into_iter()
         .enumerate()
         .filter(|(_, lines)| !lines.is_empty())
     {
-        match get_stitch_from_lines(lines, stitch_index, knot_name) {
+        let (first_line, meta_data) = lines[0].clone();
+
+        let duplicate_error = get_stitch_name(first_line, &meta_data)
+            .ok()
+            .map(|name| get_stitch_identifier(name, stitch_index))
+            .and_then(|name| get_duplicate_name_error(&mut stitch_locations, name, &meta_data))
+            .map(
+                |(name, prev_meta_data)| KnotErrorKind::DuplicateStitchName {
+                    name,
+                    knot_name: knot_name.to_string(),
+                    meta_data: meta_data.clone(),
+                    prev_meta_data,
+                },
+            );
+
+        if let Some(error) = duplicate_error {
+            line_errors.push(error);
+        }
+
+        match get_stitch_from_lines(lines, stitch_index, knot_name, directives) {
             Ok((name, stitch)) => {
                 if default_stitch.is_none() {
                     default_stitch.replace(name.clone());
                 }
 
-                if !stitches.contains_key(&name) {
-                    stitches.insert(name, stitch);
-                } else {
-                    let prev_meta_data = stitches.get(&name).unwrap().meta_data.clone();
-
-                    line_errors.push(KnotErrorKind::DuplicateStitchName {
-                        name: name,
-                        knot_name: knot_name.to_string(),
-                        meta_data: stitch.meta_data.clone(),
-                        prev_meta_data,
-                    });
-                }
+                stitches.entry(name).or_insert(stitch);
             }
             Err(errors) => line_errors.extend(errors),
         }
@@ -272,6 +870,7 @@ fn get_stitch_from_lines(
     mut lines: Vec<(&str, MetaData)>,
     stitch_index: usize,
     knot_name: &str,
+    directives: &DirectiveSet,
 ) -> Result<(String, Stitch), Vec<KnotErrorKind>> {
     let mut line_errors = Vec::new();
 
@@ -291,8 +890,12 @@ fn get_stitch_from_lines(
         }
     };
 
-    match parse_stitch_from_lines(&lines, knot_name, &stitch_name, meta_data) {
-        Ok(stitch) => {
+    let tags = get_knot_tags(&mut lines);
+
+    match parse_stitch_from_lines(&lines, knot_name, &stitch_name, meta_data, directives) {
+        Ok(mut stitch) => {
+            stitch.tags = tags;
+
             if line_errors.is_empty() {
                 Ok((stitch_name, stitch))
             } else {
@@ -325,6 +928,24 @@ fn get_stitch_name(
     }
 }
 
+/// Record the location of a knot or stitch name and check whether it is a duplicate.
+///
+/// If the name has already been recorded, return it along with the `MetaData` of where it
+/// was first defined. The first location is kept: a duplicate never shadows the original.
+fn get_duplicate_name_error(
+    locations: &mut HashMap<String, MetaData>,
+    name: String,
+    meta_data: &MetaData,
+) -> Option<(String, MetaData)> {
+    match locations.get(&name) {
+        Some(prev_meta_data) => Some((name, prev_meta_data.clone())),
+        None => {
+            locations.insert(name, meta_data.clone());
+            None
+        }
+    }
+}
+
 /// Get an invalid knot name error and a default to use while checking remaining content.
 fn get_invalid_name_error(
     line: &str,
@@ -358,7 +979,7 @@ fn get_stitch_identifier(name: Option<String>, stitch_index: usize) -> String {
     }
 }
 
-/// Parse knot tags from lines until the first line with content.
+/// Parse knot or stitch tags from lines until the first line with content.
 ///
 /// The lines which contain tags are split off of the input list.
 ///
@@ -410,13 +1031,13 @@ fn parse_tag_from_line(line: &str) -> String {
 
 /// Trim TODO and line comments from a line.
 fn trim_comment<'a>(line: &'a str, log: &mut Logger, meta_data: &MetaData) -> &'a str {
-    if let Some(i) = line.find(LINE_COMMENT_MARKER) {
-        line.get(..i).unwrap()
-    } else if line.trim_start().starts_with(TODO_COMMENT_MARKER) {
-        log.add_todo(line, meta_data);
-        ""
-    } else {
-        line
+    match find_comment(line) {
+        Some((CommentKind::Todo, _)) => {
+            log.add_todo(line, meta_data);
+            ""
+        }
+        Some((_, i)) => line.get(..i).unwrap(),
+        None => line,
     }
 }
 
@@ -446,6 +1067,7 @@ fn split_prelude_into_metadata_and_text<'a>(
         format!("{} ", CONST_MARKER),
         format!("{} ", EXTERNAL_FUNCTION_MARKER),
         format!("{} ", INCLUDE_MARKER),
+        format!("{} ", LIST_MARKER),
         format!("{} ", VARIABLE_MARKER),
         format!("{} ", TODO_COMMENT_MARKER),
         format!("{}", LINE_COMMENT_MARKER),
@@ -482,6 +1104,20 @@ fn parse_global_tags(lines: &[(&str, MetaData)]) -> Vec<String> {
 /// Parse global variables from a set of metadata lines in the prelude.
 fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<PreludeError>) {
     let mut variables = HashMap::new();
+    let errors = add_global_variables(&mut variables, lines);
+
+    (variables, errors)
+}
+
+/// Parse global variables from a set of lines and add them to a set of variables.
+///
+/// Variables can be declared anywhere in a story, not only in the prelude. An error
+/// is returned for every variable which has already been declared, in which case
+/// constants from the host are kept.
+fn add_global_variables(
+    variables: &mut VariableSet,
+    lines: &[(&str, MetaData)],
+) -> Vec<PreludeError> {
     let mut errors = Vec::new();
 
     for (line, meta_data) in lines
@@ -492,6 +1128,10 @@ fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<Prelu
         if let Err(kind) =
             parse_variable_info_from_line(line, &meta_data).and_then(|(name, variable_info)| {
                 match variables.insert(name.clone(), variable_info) {
+                    Some(prev_info) if prev_info.is_host_constant => {
+                        variables.insert(name.clone(), prev_info);
+                        Err(PreludeErrorKind::DuplicateHostConstant { name })
+                    }
                     Some(_) => Err(PreludeErrorKind::DuplicateVariable { name }),
                     None => Ok(()),
                 }
@@ -505,18 +1145,118 @@ fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<Prelu
         }
     }
 
-    (variables, errors)
+    errors
+}
+
+/// Parse external function declarations from a set of lines and add them to a set.
+///
+/// Declarations are on the form `EXTERNAL name(a, b)`. Every function is added as a function
+/// knot without content, which is called through the function that the host binds to it.
+/// An error is returned for every function which has already been declared.
+fn add_external_functions(
+    functions: &mut KnotSet,
+    lines: &[(&str, MetaData)],
+) -> Vec<PreludeError> {
+    let mut errors = Vec::new();
+
+    for (line, signature, meta_data) in lines.iter().filter_map(|(line, meta_data)| {
+        let line = line.trim();
+
+        line.strip_prefix(EXTERNAL_FUNCTION_MARKER)
+            .filter(|signature| signature.starts_with(char::is_whitespace))
+            .map(|signature| (line, signature, meta_data))
+    }) {
+        let result = read_function_signature(signature)
+            .map_err(PreludeErrorKind::InvalidExternalFunction)
+            .and_then(|(name, parameters)| {
+                let function = Knot {
+                    default_stitch: ROOT_KNOT_NAME.to_string(),
+                    stitches: HashMap::new(),
+                    tags: Vec::new(),
+                    meta_data: meta_data.clone(),
+                    is_function: true,
+                    parameters,
+                    is_external: true,
+                };
+
+                match functions.entry(name) {
+                    Entry::Occupied(entry) => Err(PreludeErrorKind::DuplicateExternalFunction {
+                        name: entry.key().clone(),
+                    }),
+                    Entry::Vacant(entry) => {
+                        entry.insert(function);
+                        Ok(())
+                    }
+                }
+            });
+
+        if let Err(kind) = result {
+            errors.push(PreludeError {
+                line: line.to_string(),
+                kind,
+                meta_data: meta_data.clone(),
+            });
+        }
+    }
+
+    errors
+}
+
+/// Add constants which were defined by the host to the set of global variables.
+///
+/// The constants are added as if they were declared with `CONST` in the prelude, but have
+/// no line of their own. An error is returned for every variable in the prelude which has
+/// the same name as a constant.
+fn add_host_constants(
+    variables: &mut VariableSet,
+    constants: &HashMap<String, Variable>,
+    prelude_lines: &[(&str, MetaData)],
+) -> Vec<PreludeError> {
+    let mut errors = Vec::new();
+
+    for (name, variable) in constants {
+        let variable_info = VariableInfo {
+            is_const: true,
+            variable: variable.clone(),
+            meta_data: MetaData::from(0),
+            declared: Some(variable.clone()),
+            transient: None,
+            is_host_constant: true,
+            list_items: None,
+        };
+
+        if let Some(prev_info) = variables.insert(name.clone(), variable_info) {
+            let line = prelude_lines
+                .iter()
+                .find(|(_, meta_data)| meta_data == &prev_info.meta_data)
+                .map(|(line, _)| line.trim().to_string())
+                .unwrap_or_default();
+
+            errors.push(PreludeError {
+                line,
+                kind: PreludeErrorKind::DuplicateHostConstant { name: name.clone() },
+                meta_data: prev_info.meta_data,
+            });
+        }
+    }
+
+    errors.sort_by_key(|error| error.meta_data.line());
+
+    errors
 }
 
 /// Parse a single variable line into the variable name, initial value and whether it is constant.
 ///
 /// Variable lines are on the form `VAR variable_name = initial_value` and constant variables
-/// on the form `CONST variable_name = constant_value`.
+/// on the form `CONST variable_name = constant_value`. List variables are declared on the form
+/// `LIST list_name = item, (initial_item), ...`.
 fn parse_variable_info_from_line(
     line: &str,
     meta_data: &MetaData,
 ) -> Result<(String, VariableInfo), PreludeErrorKind> {
-    if let Some(i) = line.find('=') {
+    if line.starts_with(LIST_MARKER) {
+        parse_list_info_from_line(line, meta_data)
+    } else if let Some(i) = line.find('=') {
         let (lhs, rhs) = line.split_at(i);
 
         let is_const = lhs.starts_with(CONST_MARKER);
@@ -527,8 +1267,12 @@ fn parse_variable_info_from_line(
             name,
             VariableInfo {
                 is_const,
+                declared: Some(variable.clone()),
                 variable,
                 meta_data: meta_data.clone(),
+                transient: None,
+                is_host_constant: false,
+                list_items: None,
             },
         ))
     } else {
@@ -536,11 +1280,145 @@ fn parse_variable_info_from_line(
     }
 }
 
+/// Parse a list declaration into the list name and its variable with all items of the list.
+///
+/// Items are numbered from 1 in the order that they are declared, unless they are given
+/// values with `item = value`. Items in parenthesis are in the initial value of the variable.
+fn parse_list_info_from_line(
+    line: &str,
+    meta_data: &MetaData,
+) -> Result<(String, VariableInfo), PreludeErrorKind> {
+    let (lhs, rhs) = line
+        .split_once('=')
+        .ok_or(PreludeErrorKind::NoVariableAssignment)?;
+
+    let name = lhs
+        .get(LIST_MARKER.len()..)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or(PreludeErrorKind::NoVariableName)?;
+
+    let mut items = Vec::new();
+    let mut initial_items = Vec::new();
+    let mut value = 0;
+
+    for item in rhs.split(',').map(|item| item.trim()) {
+        let (item_name, item_value, is_initial) = parse_list_item(item)?;
+        value = item_value.unwrap_or(value + 1);
+
+        if items.iter().any(|other: &ListItem| other.name == item_name) {
+            return Err(PreludeErrorKind::DuplicateListItem {
+                name: item_name.to_string(),
+            });
+        }
+
+        let list_item = ListItem::new(&name, item_name, value);
+
+        if is_initial {
+            initial_items.push(list_item.clone());
+        }
+
+        items.push(list_item);
+    }
+
+    let initial_list = initial_items
+        .into_iter()
+        .collect::<List>()
+        .with_origin(&name);
+
+    Ok((
+        name,
+        VariableInfo {
+            is_const: false,
+            variable: Variable::List(initial_list.clone()),
+            meta_data: meta_data.clone(),
+            declared: Some(Variable::List(initial_list)),
+            transient: None,
+            is_host_constant: false,
+            list_items: Some(items.into_iter().collect::<List>()),
+        },
+    ))
+}
+
+/// Parse an item of a list definition into its name, value if given and whether it is initial.
+///
+/// Initial items are in parenthesis, with their value inside or outside of them:
+/// `(item)`, `(item = 2)` or `(item) = 2`.
+fn parse_list_item(item: &str) -> Result<(&str, Option<i32>, bool), PreludeErrorKind> {
+    let invalid_item = || PreludeErrorKind::InvalidListItem {
+        item: item.to_string(),
+    };
+
+    let is_initial = item.starts_with('(');
+    let num_parenthesis = item.matches(['(', ')']).count();
+
+    if (is_initial && (num_parenthesis != 2 || !item.contains(')')))
+        || (!is_initial && num_parenthesis > 0)
+    {
+        return Err(invalid_item());
+    }
+
+    let (name, value) = match item.trim_matches(['(', ')']).split_once('=') {
+        Some((name, value)) => {
+            let value = value
+                .trim()
+                .trim_end_matches(')')
+                .trim()
+                .parse::<i32>()
+                .map_err(|_| invalid_item())?;
+
+            (name.trim().trim_end_matches(')').trim(), Some(value))
+        }
+        None => (item.trim_matches(['(', ')']).trim(), None),
+    };
+
+    if name.is_empty() || name.contains(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        return Err(invalid_item());
+    }
+
+    Ok((name, value, is_initial))
+}
+
 /// Check whether or not a line is a variable.
 ///
 /// Assumes that the line has been trimmed from both ends.
 fn is_variable_line(line: &str) -> bool {
-    line.starts_with(VARIABLE_MARKER) || line.starts_with(CONST_MARKER)
+    line.starts_with(VARIABLE_MARKER)
+        || line.starts_with(CONST_MARKER)
+        || line.starts_with(LIST_MARKER)
+}
+
+/// Check whether or not a line in the content after the prelude declares a variable
+/// or an external function.
+///
+/// Unlike in the prelude the keyword must be followed by whitespace, since lines of text
+/// may begin with words like `VARIOUS`. Assumes that the line has been trimmed from both ends.
+fn is_declaration_line(line: &str) -> bool {
+    [
+        VARIABLE_MARKER,
+        CONST_MARKER,
+        LIST_MARKER,
+        EXTERNAL_FUNCTION_MARKER,
+    ]
+    .iter()
+    .any(|marker| {
+        line.strip_prefix(marker)
+            .map(|rest| rest.starts_with(char::is_whitespace))
+            .unwrap_or(false)
+    })
+}
+
+/// Remove all lines which declare variables from a set of lines and return them.
+fn split_off_declaration_lines<'a>(
+    lines: &mut Vec<(&'a str, MetaData)>,
+) -> Vec<(&'a str, MetaData)> {
+    let (declarations, other) = lines
+        .drain(..)
+        .partition(|(line, _)| is_declaration_line(line.trim()));
+
+    *lines = other;
+
+    declarations
 }
 
 /// Parse the name from a variable string and assert that it is non-empty.
@@ -561,7 +1439,10 @@ fn parse_variable_name(lhs: &str, is_const: bool) -> Result<String, PreludeError
 pub mod tests {
     use super::*;
 
-    use crate::{knot::Address, line::Variable, log::MessageKind};
+    use crate::{error::parse::print_read_error, knot::Address, line::Variable};
+
+    #[cfg(feature = "logging")]
+    use crate::log::MessageKind;
 
     pub fn read_knots_from_string(content: &str) -> Result<KnotSet, Vec<KnotError>> {
         let lines = content
@@ -571,7 +1452,7 @@ pub mod tests {
             .map(|(i, line)| (line, MetaData::from(i)))
             .collect();
 
-        let (knots, knot_errors) = parse_knots_from_lines(lines);
+        let (knots, knot_errors) = parse_knots_from_lines(lines, &DirectiveSet::new());
 
         if knot_errors.is_empty() {
             Ok(knots)
@@ -763,6 +1644,68 @@ pub mod tests {
         assert!(parse_variable_info_from_line("VAR", &MetaData::from(0)).is_err());
     }
 
+    #[test]
+    fn list_declarations_number_their_items_and_set_initial_items() {
+        let (name, list_info) = parse_variable_info_from_line(
+            "LIST inventory = sword, (torch), key = 5, (lamp)",
+            &MetaData::from(0),
+        )
+        .unwrap();
+
+        assert_eq!(&name, "inventory");
+        assert!(!list_info.is_const);
+
+        let all_items = list_info.list_items.unwrap();
+        let values = all_items
+            .items()
+            .map(|item| (item.name.as_str(), item.value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            &[("sword", 1), ("torch", 2), ("key", 5), ("lamp", 6)]
+        );
+        assert_eq!(
+            list_info.variable,
+            Variable::List(
+                vec![
+                    ListItem::new("inventory", "torch", 2),
+                    ListItem::new("inventory", "lamp", 6)
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn list_items_must_be_names_in_balanced_parenthesis() {
+        for item in &[
+            "(torch",
+            "torch)",
+            "((torch))",
+            "big torch",
+            "key = five",
+            "",
+        ] {
+            let line = format!("LIST inventory = sword, {}", item);
+
+            match parse_variable_info_from_line(&line, &MetaData::from(0)) {
+                Err(PreludeErrorKind::InvalidListItem { .. }) => (),
+                other => panic!(
+                    "expected `PreludeErrorKind::InvalidListItem` for '{}' but got {:?}",
+                    item, other
+                ),
+            }
+        }
+
+        assert!(parse_variable_info_from_line(
+            "LIST inventory = (torch = 2), (key) = 3",
+            &MetaData::from(0)
+        )
+        .is_ok());
+    }
+
     #[test]
     fn variables_can_be_const_or_not() {
         let lines = &["VAR float = 1.0", "CONST string = \"two words\""];
@@ -824,6 +1767,55 @@ pub mod tests {
         assert_eq!(errors.len(), 5);
     }
 
+    #[test]
+    fn external_functions_are_parsed_into_function_knots_without_content() {
+        let lines = &[
+            "EXTERNAL play_sound(name, volume)",
+            "EXTERNAL stop_sound()",
+            "EXTERNAL play_sound(name)",
+            "EXTERNAL play sound",
+        ];
+
+        let mut functions = HashMap::new();
+        let errors = add_external_functions(&mut functions, &enumerate(lines));
+
+        assert_eq!(functions.len(), 2);
+        assert_eq!(errors.len(), 2);
+
+        let play_sound = functions.get("play_sound").unwrap();
+
+        assert!(play_sound.is_function && play_sound.is_external);
+        assert!(play_sound.stitches.is_empty());
+        assert_eq!(&play_sound.parameters, &["name", "volume"]);
+
+        match &errors[0].kind {
+            PreludeErrorKind::DuplicateExternalFunction { name } => assert_eq!(name, "play_sound"),
+            other => panic!("expected `DuplicateExternalFunction` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn declarations_are_split_off_from_lines_of_text() {
+        let mut lines = enumerate(&[
+            "Text.",
+            "VAR coins = 3",
+            "VARIOUS stalls.",
+            "  CONST KEEPER = \"Hilda\"",
+            "CONSTANT rain.",
+        ]);
+
+        let declarations = split_off_declaration_lines(&mut lines);
+
+        assert_eq!(
+            &denumerate(declarations),
+            &["VAR coins = 3", "  CONST KEEPER = \"Hilda\""]
+        );
+        assert_eq!(
+            &denumerate(lines),
+            &["Text.", "VARIOUS stalls.", "CONSTANT rain."]
+        );
+    }
+
     #[test]
     fn regular_lines_can_start_with_variable_divert_or_text() {
         let lines = &["# Tag", "Regular line."];
@@ -937,13 +1929,15 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines =
+            process_file_content_into_lines_and_metadata(&content, None, &HashSet::new(), &mut log)
+                .unwrap();
 
         assert_eq!(lines.len(), 4);
-        assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
-        assert_eq!(lines[1], ("", MetaData::from(1)));
-        assert_eq!(lines[2], ("", MetaData::from(2)));
-        assert_eq!(lines[3], (content_lines[3], MetaData::from(3)));
+        assert_eq!(lines[0], (content_lines[0].to_string(), MetaData::from(0)));
+        assert_eq!(lines[1], ("".to_string(), MetaData::from(1)));
+        assert_eq!(lines[2], ("".to_string(), MetaData::from(2)));
+        assert_eq!(lines[3], (content_lines[3].to_string(), MetaData::from(3)));
     }
 
     #[test]
@@ -957,12 +1951,85 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines =
+            process_file_content_into_lines_and_metadata(&content, None, &HashSet::new(), &mut log)
+                .unwrap();
 
         assert_eq!(lines.len(), 3);
-        assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
-        assert_eq!(lines[1], ("Line before comment marker", MetaData::from(1)));
-        assert_eq!(lines[2], (content_lines[2], MetaData::from(2)));
+        assert_eq!(lines[0], (content_lines[0].to_string(), MetaData::from(0)));
+        assert_eq!(
+            lines[1],
+            ("Line before comment marker".to_string(), MetaData::from(1))
+        );
+        assert_eq!(lines[2], (content_lines[2].to_string(), MetaData::from(2)));
+    }
+
+    #[test]
+    fn block_comments_are_removed_while_keeping_the_line_breaks_inside_them() {
+        let content = "One /* removed */ two\nThree /* removed\nremoved */ four\nFive";
+
+        let mut filter = BlockCommentFilter::default();
+
+        let lines = content
+            .lines()
+            .map(|line| filter.strip(line).0.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(&lines, &["One  two", "Three ", " four", "Five"]);
+    }
+
+    #[test]
+    fn block_comments_do_not_begin_inside_of_line_comments() {
+        let mut filter = BlockCommentFilter::default();
+
+        assert_eq!(
+            filter.strip("Line // comment /* not block").0,
+            "Line // comment /* not block"
+        );
+        assert_eq!(filter.strip("Next line").0, "Next line");
+        assert_eq!(filter.strip("Block /* // */ text").0, "Block  text");
+    }
+
+    #[test]
+    fn block_comment_filter_gives_the_spans_of_comments_in_lines() {
+        let mut filter = BlockCommentFilter::default();
+
+        assert_eq!(filter.strip("A /* b */ c /* d").1, &[2..9, 12..16]);
+        assert_eq!(filter.strip("still d").1.first(), Some(&(0..7)));
+        assert_eq!(filter.strip("d */ e").1.first(), Some(&(0..4)));
+        assert!(filter.strip("f").1.is_empty());
+    }
+
+    #[test]
+    fn unterminated_block_comments_yield_an_error_with_the_line_they_begin_on() {
+        let content = "Line\n/* Opened */ /* Never\nclosed\nLine";
+
+        let mut log = Logger::default();
+
+        match process_file_content_into_lines_and_metadata(content, None, &HashSet::new(), &mut log)
+        {
+            Err(ReadError::UnterminatedComment { meta_data }) => {
+                assert_eq!(meta_data, MetaData::from(1))
+            }
+            other => panic!(
+                "expected a `ReadError::UnterminatedComment` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn lines_after_block_comments_keep_their_line_index() {
+        let content = "/* Comment\nover\nthree lines */\nLine";
+
+        let mut log = Logger::default();
+        let lines =
+            process_file_content_into_lines_and_metadata(content, None, &HashSet::new(), &mut log)
+                .unwrap();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], ("".to_string(), MetaData::from(2)));
+        assert_eq!(lines[3], ("Line".to_string(), MetaData::from(3)));
     }
 
     #[test]
@@ -977,12 +2044,14 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines =
+            process_file_content_into_lines_and_metadata(&content, None, &HashSet::new(), &mut log)
+                .unwrap();
 
-        assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
-        assert_eq!(lines[1], ("", MetaData::from(1)));
-        assert_eq!(lines[2], ("", MetaData::from(2)));
-        assert_eq!(lines[3], (content_lines[3], MetaData::from(3)));
+        assert_eq!(lines[0], (content_lines[0].to_string(), MetaData::from(0)));
+        assert_eq!(lines[1], ("".to_string(), MetaData::from(1)));
+        assert_eq!(lines[2], ("".to_string(), MetaData::from(2)));
+        assert_eq!(lines[3], (content_lines[3].to_string(), MetaData::from(3)));
     }
 
     #[test]
@@ -999,15 +2068,17 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines =
+            process_file_content_into_lines_and_metadata(&content, None, &HashSet::new(), &mut log)
+                .unwrap();
 
         assert_eq!(lines.len(), 6);
-        assert_eq!(lines[0], ("Initial", MetaData::from(0)));
-        assert_eq!(lines[1], ("End", MetaData::from(1)));
-        assert_eq!(lines[2], ("", MetaData::from(2)));
-        assert_eq!(lines[3], ("After comment", MetaData::from(3)));
-        assert_eq!(lines[4], ("", MetaData::from(4)));
-        assert_eq!(lines[5], ("Both", MetaData::from(5)));
+        assert_eq!(lines[0], ("Initial".to_string(), MetaData::from(0)));
+        assert_eq!(lines[1], ("End".to_string(), MetaData::from(1)));
+        assert_eq!(lines[2], ("".to_string(), MetaData::from(2)));
+        assert_eq!(lines[3], ("After comment".to_string(), MetaData::from(3)));
+        assert_eq!(lines[4], ("".to_string(), MetaData::from(4)));
+        assert_eq!(lines[5], ("Both".to_string(), MetaData::from(5)));
     }
 
     #[test]
@@ -1017,21 +2088,23 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines =
+            process_file_content_into_lines_and_metadata(&content, None, &HashSet::new(), &mut log)
+                .unwrap();
 
         assert_eq!(lines.len(), 5);
-        assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
-        assert_eq!(lines[1], (content_lines[1], MetaData::from(1)));
-        assert_eq!(lines[2], (content_lines[2], MetaData::from(2)));
-        assert_eq!(lines[3], ("", MetaData::from(3)));
-        assert_eq!(lines[4], (content_lines[4], MetaData::from(4)));
+        assert_eq!(lines[0], (content_lines[0].to_string(), MetaData::from(0)));
+        assert_eq!(lines[1], (content_lines[1].to_string(), MetaData::from(1)));
+        assert_eq!(lines[2], (content_lines[2].to_string(), MetaData::from(2)));
+        assert_eq!(lines[3], ("".to_string(), MetaData::from(3)));
+        assert_eq!(lines[4], (content_lines[4].to_string(), MetaData::from(4)));
     }
 
     #[test]
     fn parsing_knot_from_lines_gets_name() {
         let content = enumerate(&["== Knot_name ==", "Line 1", "Line 2"]);
 
-        let (name, _) = get_knot_from_lines(content).unwrap();
+        let (name, _) = get_knot_from_lines(content, &DirectiveSet::new()).unwrap();
         assert_eq!(&name, "Knot_name");
     }
 
@@ -1039,7 +2112,7 @@ Second line.
     fn parsing_knot_from_lines_without_stitches_sets_content_in_default_named_stitch() {
         let content = enumerate(&["== Knot_name ==", "Line 1", "Line 2"]);
 
-        let (_, knot) = get_knot_from_lines(content).unwrap();
+        let (_, knot) = get_knot_from_lines(content, &DirectiveSet::new()).unwrap();
 
         assert_eq!(&knot.default_stitch, ROOT_KNOT_NAME);
         assert_eq!(
@@ -1050,25 +2123,38 @@ Second line.
 
     #[test]
     fn parsing_a_stitch_gets_name_if_present_else_default_root_name_if_index_is_zero() {
-        let (name, _) =
-            get_stitch_from_lines(enumerate(&["= stitch_name =", "Line 1"]), 0, "").unwrap();
+        let (name, _) = get_stitch_from_lines(
+            enumerate(&["= stitch_name =", "Line 1"]),
+            0,
+            "",
+            &DirectiveSet::new(),
+        )
+        .unwrap();
         assert_eq!(name, "stitch_name".to_string());
 
-        let (name, _) = get_stitch_from_lines(enumerate(&["Line 1"]), 0, "").unwrap();
+        let (name, _) =
+            get_stitch_from_lines(enumerate(&["Line 1"]), 0, "", &DirectiveSet::new()).unwrap();
         assert_eq!(name, ROOT_KNOT_NAME);
     }
 
     #[test]
     fn parsing_stitch_from_lines_sets_address_in_root_node() {
-        let (_, stitch) =
-            get_stitch_from_lines(enumerate(&["= cinema", "Line 1"]), 0, "tripoli").unwrap();
+        let (_, stitch) = get_stitch_from_lines(
+            enumerate(&["= cinema", "Line 1"]),
+            0,
+            "tripoli",
+            &DirectiveSet::new(),
+        )
+        .unwrap();
 
         assert_eq!(
             stitch.root.address,
             Address::from_parts_unchecked("tripoli", Some("cinema"))
         );
 
-        let (_, stitch) = get_stitch_from_lines(enumerate(&["Line 1"]), 0, "tripoli").unwrap();
+        let (_, stitch) =
+            get_stitch_from_lines(enumerate(&["Line 1"]), 0, "tripoli", &DirectiveSet::new())
+                .unwrap();
 
         assert_eq!(
             stitch.root.address,
@@ -1078,11 +2164,17 @@ Second line.
 
     #[test]
     fn parsing_a_stitch_gets_all_content_regardless_of_whether_name_is_present() {
-        let (_, content) =
-            get_stitch_from_lines(enumerate(&["= stitch_name =", "Line 1"]), 0, "").unwrap();
+        let (_, content) = get_stitch_from_lines(
+            enumerate(&["= stitch_name =", "Line 1"]),
+            0,
+            "",
+            &DirectiveSet::new(),
+        )
+        .unwrap();
         assert_eq!(content.root.items.len(), 1);
 
-        let (_, content) = get_stitch_from_lines(enumerate(&["Line 1"]), 0, "").unwrap();
+        let (_, content) =
+            get_stitch_from_lines(enumerate(&["Line 1"]), 0, "", &DirectiveSet::new()).unwrap();
         assert_eq!(content.root.items.len(), 1);
     }
 
@@ -1096,7 +2188,7 @@ Second line.
             "Line two",
         ]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
 
         assert_eq!(knot.stitches.len(), 2);
         assert!(knot.stitches.get("stitch_one").is_some());
@@ -1114,7 +2206,7 @@ Second line.
             "Line 3",
         ]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
         assert_eq!(&knot.default_stitch, ROOT_KNOT_NAME);
     }
 
@@ -1122,10 +2214,17 @@ Second line.
     fn root_knot_parses_stitch_without_a_name() {
         let lines = enumerate(&["Line 1", "Line 2"]);
 
-        let root = parse_root_knot_from_lines(lines.clone(), ().into()).unwrap();
+        let root =
+            parse_root_knot_from_lines(lines.clone(), ().into(), &DirectiveSet::new()).unwrap();
 
-        let comparison =
-            parse_stitch_from_lines(&lines, ROOT_KNOT_NAME, ROOT_KNOT_NAME, ().into()).unwrap();
+        let comparison = parse_stitch_from_lines(
+            &lines,
+            ROOT_KNOT_NAME,
+            ROOT_KNOT_NAME,
+            ().into(),
+            &DirectiveSet::new(),
+        )
+        .unwrap();
 
         assert_eq!(
             format!("{:?}", root.stitches.get(ROOT_KNOT_NAME).unwrap()),
@@ -1137,7 +2236,7 @@ Second line.
     fn root_knot_may_have_stitches() {
         let lines = enumerate(&["Line 1", "= Stitch", "Line 2"]);
 
-        let root = parse_root_knot_from_lines(lines, ().into()).unwrap();
+        let root = parse_root_knot_from_lines(lines, ().into(), &DirectiveSet::new()).unwrap();
 
         assert_eq!(root.stitches.len(), 2);
     }
@@ -1152,7 +2251,7 @@ Second line.
             "Line 2",
         ]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
         assert_eq!(&knot.default_stitch, "stitch_one");
     }
 
@@ -1160,7 +2259,7 @@ Second line.
     fn knot_parses_tags_from_name_until_first_line_without_octothorpe() {
         let lines = enumerate(&["== knot_name", "# Tag one", "# Tag two", "Line 1"]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
         assert_eq!(&knot.tags, &["Tag one".to_string(), "Tag two".to_string()]);
     }
 
@@ -1168,15 +2267,39 @@ Second line.
     fn knot_tags_ignore_empty_lines() {
         let lines = enumerate(&["== knot_name", "", "# Tag one", "", "# Tag two", "Line 1"]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
         assert_eq!(&knot.tags, &["Tag one".to_string(), "Tag two".to_string()]);
     }
 
+    #[test]
+    fn stitches_parse_tags_from_their_name_until_first_line_without_octothorpe() {
+        let lines = enumerate(&[
+            "== knot_name",
+            "# Knot tag",
+            "Line 1",
+            "= stitch",
+            "# Tag one",
+            "",
+            "# Tag two",
+            "Line 2",
+        ]);
+
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
+        assert_eq!(&knot.tags, &["Knot tag".to_string()]);
+
+        let stitch = knot.stitches.get("stitch").unwrap();
+        assert_eq!(
+            &stitch.tags,
+            &["Tag one".to_string(), "Tag two".to_string()]
+        );
+        assert!(knot.stitches.get(ROOT_KNOT_NAME).unwrap().tags.is_empty());
+    }
+
     #[test]
     fn if_no_tags_are_set_the_tags_are_empty() {
         let lines = enumerate(&["== knot_name", "Line 1"]);
 
-        let (_, knot) = get_knot_from_lines(lines).unwrap();
+        let (_, knot) = get_knot_from_lines(lines, &DirectiveSet::new()).unwrap();
         assert!(knot.tags.is_empty());
     }
 
@@ -1188,8 +2311,9 @@ Second line.
             ("Line 1", MetaData::from(4)),
         ];
 
-        let (_, knot_tags) = get_knot_from_lines(lines_with_tags).unwrap();
-        let (_, knot_no_tags) = get_knot_from_lines(lines_without_tags).unwrap();
+        let (_, knot_tags) = get_knot_from_lines(lines_with_tags, &DirectiveSet::new()).unwrap();
+        let (_, knot_no_tags) =
+            get_knot_from_lines(lines_without_tags, &DirectiveSet::new()).unwrap();
 
         assert_eq!(
             format!("{:?}", knot_tags.stitches),
@@ -1201,7 +2325,7 @@ Second line.
     fn empty_knot_yields_error() {
         let lines = enumerate(&["== knot_name"]);
 
-        match get_knot_from_lines(lines) {
+        match get_knot_from_lines(lines, &DirectiveSet::new()) {
             Err(KnotError { line_errors, .. }) => match &line_errors[0] {
                 KnotErrorKind::EmptyKnot => (),
                 other => panic!("expected `KnotErrorKind::EmptyKnot` but got `{:?}`", other),
@@ -1214,7 +2338,7 @@ Second line.
     fn empty_knot_with_tags_yields_error() {
         let lines = enumerate(&["== knot_name", "# Tag", "# Tag 2"]);
 
-        match get_knot_from_lines(lines) {
+        match get_knot_from_lines(lines, &DirectiveSet::new()) {
             Err(KnotError { line_errors, .. }) => match &line_errors[0] {
                 KnotErrorKind::EmptyKnot => (),
                 other => panic!("expected `KnotErrorKind::EmptyKnot` but got `{:?}`", other),
@@ -1244,7 +2368,7 @@ VAR hazardous = true
     }
 
     #[test]
-    fn variables_after_first_line_of_text_are_ignored() {
+    fn variables_after_first_line_of_text_are_declared_as_globals() {
         let content = "
 VAR counter = 0
 
@@ -1253,10 +2377,14 @@ VAR hazardous = true
 ";
 
         let mut log = Logger::default();
-        let (_, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
 
-        assert_eq!(variables.len(), 1);
+        assert_eq!(variables.len(), 2);
         assert!(variables.contains_key("counter"));
+        assert!(variables.contains_key("hazardous"));
+
+        let root = &knots.get(ROOT_KNOT_NAME).unwrap().stitches[ROOT_KNOT_NAME].root;
+        assert_eq!(root.items.len(), 1);
     }
 
     #[test]
@@ -1428,6 +2556,167 @@ Line two.
         }
     }
 
+    #[test]
+    fn duplicate_knot_names_are_found_even_if_the_original_knot_has_errors() {
+        let content = "\
+== knot
+{Unmatched brace
+== knot
+Line two.
+";
+
+        let mut log = Logger::default();
+        match read_story_content_from_string(content, &mut log) {
+            Err(ReadError::ParseError(err)) => {
+                assert_eq!(err.knot_errors.len(), 2);
+
+                match &err.knot_errors[1].line_errors[0] {
+                    KnotErrorKind::DuplicateKnotName { prev_meta_data, .. } => {
+                        assert_eq!(prev_meta_data.line(), 1);
+                        assert_eq!(err.knot_errors[1].knot_meta_data.line(), 3);
+                    }
+                    other => panic!(
+                        "expected `KnotErrorKind::DuplicateKnotName` but got {:?}",
+                        other
+                    ),
+                }
+            }
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_knot_names_are_found_even_if_the_duplicate_knot_has_errors() {
+        let content = "\
+== knot
+Line one.
+== knot
+{Unmatched brace
+";
+
+        let mut log = Logger::default();
+        match read_story_content_from_string(content, &mut log) {
+            Err(ReadError::ParseError(err)) => match &err.knot_errors[0].line_errors[0] {
+                KnotErrorKind::DuplicateKnotName { prev_meta_data, .. } => {
+                    assert_eq!(prev_meta_data.line(), 1);
+                    assert_eq!(err.knot_errors[0].line_errors.len(), 2);
+                }
+                other => panic!(
+                    "expected `KnotErrorKind::DuplicateKnotName` but got {:?}",
+                    other
+                ),
+            },
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_stitch_names_are_found_even_if_the_original_stitch_has_errors() {
+        let content = "\
+== knot
+= stitch
+{Unmatched brace
+= stitch
+Line two.
+";
+
+        let mut log = Logger::default();
+        match read_story_content_from_string(content, &mut log) {
+            Err(ReadError::ParseError(err)) => {
+                let errors = &err.knot_errors[0].line_errors;
+
+                assert!(errors.iter().any(|error| match error {
+                    KnotErrorKind::DuplicateStitchName {
+                        meta_data,
+                        prev_meta_data,
+                        ..
+                    } => meta_data.line() == 4 && prev_meta_data.line() == 2,
+                    _ => false,
+                }));
+            }
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn meta_data_of_lines_is_marked_with_knot_and_stitch_names() {
+        let content = "\
+Root line.
+== tripoli
+Knot line.
+= cinema
+Stitch line.
+== mesopotamia
+Other knot line.
+";
+
+        let mut log = Logger::default();
+        let (knots, _, _) = read_story_content_from_string(content, &mut log).unwrap();
+
+        let root = &knots.get(ROOT_KNOT_NAME).unwrap().stitches[ROOT_KNOT_NAME];
+        assert_eq!(root.meta_data.knot(), None);
+
+        let tripoli = knots.get("tripoli").unwrap();
+        assert_eq!(tripoli.meta_data.knot(), Some("tripoli"));
+        assert_eq!(tripoli.meta_data.stitch(), None);
+
+        let cinema = &tripoli.stitches["cinema"];
+        assert_eq!(cinema.meta_data.knot(), Some("tripoli"));
+        assert_eq!(cinema.meta_data.stitch(), Some("cinema"));
+
+        let mesopotamia = &knots.get("mesopotamia").unwrap().stitches[ROOT_KNOT_NAME];
+        assert_eq!(mesopotamia.meta_data.knot(), Some("mesopotamia"));
+        assert_eq!(mesopotamia.meta_data.stitch(), None);
+    }
+
+    #[test]
+    fn file_name_is_set_in_meta_data_of_all_lines_if_given() {
+        let content = "\
+Root line.
+== tripoli
+Knot line.
+";
+
+        let mut log = Logger::default();
+        let (knots, _, _) = read_story_content_from_file_string(
+            content,
+            Some("story.ink"),
+            &DirectiveSet::new(),
+            &HashMap::new(),
+            &HashSet::new(),
+            &mut log,
+        )
+        .unwrap();
+
+        let root = &knots.get(ROOT_KNOT_NAME).unwrap().stitches[ROOT_KNOT_NAME];
+        assert_eq!(root.meta_data.file(), Some("story.ink"));
+
+        let tripoli = knots.get("tripoli").unwrap();
+        assert_eq!(tripoli.meta_data.file(), Some("story.ink"));
+        assert_eq!(format!("{}", tripoli.meta_data), "story.ink:2 in 'tripoli'");
+    }
+
+    #[test]
+    fn duplicate_name_errors_print_both_locations() {
+        let content = "\
+== knot
+Line one.
+== knot
+Line two.
+";
+
+        let mut log = Logger::default();
+        match read_story_content_from_string(content, &mut log) {
+            Err(error) => {
+                let message = print_read_error(&error).unwrap();
+
+                assert!(message.contains("(line 3 in 'knot')"));
+                assert!(message.contains("previous at line 1 in 'knot'"));
+            }
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn reading_story_content_trims_leading_whitespace_off_of_identifiers_and_tags() {
         let content_whitespace = "\
@@ -1463,6 +2752,7 @@ Line two.
     }
 
     #[test]
+    #[cfg(feature = "logging")]
     fn todo_comments_logs_comment_with_line_number() {
         let content = "\
 == knot
@@ -1479,4 +2769,245 @@ Placeholder text.
         );
         assert_eq!(log.todo_comments[0].meta_data.line(), 2);
     }
+
+    #[test]
+    fn comments_are_read_with_their_spans_and_the_line_they_belong_to() {
+        let content = "\
+== knot
+= stitch
+    TODO: Write this scene.
+Placeholder text. // Replace
+// Fin
+";
+
+        let comments = read_comments_from_file_string(content, None, &HashSet::new());
+
+        assert_eq!(comments.len(), 3);
+
+        assert_eq!(comments[0].kind, CommentKind::Todo);
+        assert_eq!(&comments[0].text, "TODO: Write this scene.");
+        assert_eq!(comments[0].span, 4..27);
+        assert_eq!(comments[0].meta_data.knot(), Some("knot"));
+        assert_eq!(comments[0].meta_data.stitch(), Some("stitch"));
+        assert_eq!(comments[0].element.as_ref().unwrap().line(), 4);
+
+        assert_eq!(comments[1].kind, CommentKind::Line);
+        assert_eq!(&comments[1].text, "// Replace");
+        assert_eq!(comments[1].element.as_ref().unwrap().line(), 4);
+
+        assert!(comments[2].element.is_none());
+    }
+
+    #[test]
+    fn block_comments_are_read_from_the_line_they_begin_on() {
+        let content = "\
+Text. /* Short */
+/* Over
+   two lines */ More text. // Line
+";
+
+        let comments = read_comments_from_file_string(content, None, &HashSet::new());
+
+        assert_eq!(comments.len(), 3);
+
+        assert_eq!(comments[0].kind, CommentKind::Block);
+        assert_eq!(&comments[0].text, "/* Short */");
+        assert_eq!(comments[0].span, 6..17);
+        assert_eq!(comments[0].element.as_ref().unwrap().line(), 1);
+
+        assert_eq!(comments[1].kind, CommentKind::Block);
+        assert_eq!(&comments[1].text, "/* Over\n   two lines */");
+        assert_eq!(comments[1].meta_data.line(), 2);
+        assert_eq!(comments[1].span, 0..7);
+        assert_eq!(comments[1].element.as_ref().unwrap().line(), 3);
+
+        assert_eq!(comments[2].kind, CommentKind::Line);
+        assert_eq!(&comments[2].text, "// Line");
+        assert_eq!(comments[2].span, 27..34);
+    }
+
+    fn read_story_content_from_str_reader(
+        content: &str,
+        log: &mut Logger,
+    ) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
+        read_story_content_from_reader(
+            content.as_bytes(),
+            None,
+            &DirectiveSet::new(),
+            &HashMap::new(),
+            &HashSet::new(),
+            None,
+            log,
+        )
+    }
+
+    #[test]
+    fn reading_story_content_from_reader_gives_same_content_as_reading_from_string() {
+        let content = "\
+# title: The Station
+VAR platform = 2
+
+He drifted off. // Slept
+-> train
+
+== train
+# carriage
+Outside the fields went past.
+= window
+TODO: Describe the fields.
+*   [Look out] -> station
+
+== station ==
+The train stopped.
+";
+
+        let mut log_string = Logger::default();
+        let mut log_reader = Logger::default();
+
+        let from_string = read_story_content_from_string(content, &mut log_string).unwrap();
+        let from_reader = read_story_content_from_str_reader(content, &mut log_reader).unwrap();
+
+        assert_eq!(from_reader, from_string);
+        assert_eq!(log_reader.todo_comments, log_string.todo_comments);
+    }
+
+    #[test]
+    fn reading_story_content_from_reader_finds_duplicate_knots_across_the_story() {
+        let content = "\
+== knot
+Line one.
+== other
+Line two.
+== knot
+Line three.
+";
+
+        let mut log = Logger::default();
+        match read_story_content_from_str_reader(content, &mut log) {
+            Err(ReadError::ParseError(err)) => match &err.knot_errors[0].line_errors[0] {
+                KnotErrorKind::DuplicateKnotName { prev_meta_data, .. } => {
+                    assert_eq!(err.knot_errors[0].knot_meta_data.line(), 5);
+                    assert_eq!(prev_meta_data.line(), 1);
+                }
+                other => panic!(
+                    "expected `KnotErrorKind::DuplicateKnotName` but got {:?}",
+                    other
+                ),
+            },
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reading_empty_story_content_from_reader_yields_empty_error() {
+        let mut log = Logger::default();
+
+        match read_story_content_from_str_reader("// Nothing here\n\n", &mut log) {
+            Err(ReadError::Empty) => (),
+            other => panic!("expected `ReadError::Empty` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn comments_read_from_reader_are_same_as_comments_read_from_string() {
+        let content = "\
+== knot
+= stitch
+    TODO: Write this scene.
+Placeholder text. // Replace
+/* Block
+   comment */ More text. /* Another */ // Line
+// Fin
+";
+
+        let mut log = Logger::default();
+        let mut comments = Vec::new();
+
+        read_story_content_from_reader(
+            content.as_bytes(),
+            None,
+            &DirectiveSet::new(),
+            &HashMap::new(),
+            &HashSet::new(),
+            Some(&mut comments),
+            &mut log,
+        )
+        .unwrap();
+
+        assert_eq!(
+            comments,
+            read_comments_from_file_string(content, None, &HashSet::new())
+        );
+    }
+
+    #[test]
+    fn host_constants_are_added_to_global_variables_as_constants() {
+        let content = "\
+VAR lives = 3
+Line.
+";
+
+        let mut constants = HashMap::new();
+        constants.insert("DEMO_BUILD".to_string(), Variable::Bool(true));
+
+        let mut log = Logger::default();
+        let (_, variables, _) = read_story_content_from_file_string(
+            content,
+            None,
+            &DirectiveSet::new(),
+            &constants,
+            &HashSet::new(),
+            &mut log,
+        )
+        .unwrap();
+
+        let demo_build = variables.get("DEMO_BUILD").unwrap();
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(demo_build.variable, Variable::Bool(true));
+        assert!(demo_build.is_const);
+        assert!(demo_build.is_host_constant);
+        assert!(!variables.get("lives").unwrap().is_host_constant);
+    }
+
+    #[test]
+    fn declaring_variable_with_name_of_host_constant_yields_error_at_declaration() {
+        let content = "\
+VAR lives = 3
+CONST DEMO_BUILD = false
+Line.
+";
+
+        let mut constants = HashMap::new();
+        constants.insert("DEMO_BUILD".to_string(), Variable::Bool(true));
+
+        let mut log = Logger::default();
+        match read_story_content_from_reader(
+            content.as_bytes(),
+            None,
+            &DirectiveSet::new(),
+            &constants,
+            &HashSet::new(),
+            None,
+            &mut log,
+        ) {
+            Err(ReadError::ParseError(err)) => {
+                let error = &err.prelude_errors[0];
+
+                assert_eq!(error.meta_data.line(), 2);
+                assert_eq!(&error.line, "CONST DEMO_BUILD = false");
+
+                match &error.kind {
+                    PreludeErrorKind::DuplicateHostConstant { name } => {
+                        assert_eq!(name, "DEMO_BUILD")
+                    }
+                    other => panic!(
+                        "expected `PreludeErrorKind::DuplicateHostConstant` but got {:?}",
+                        other
+                    ),
+                }
+            }
+            other => panic!("expected `ReadError::ParseError` but got {:?}", other),
+        }
+    }
 }
@@ -8,7 +8,11 @@
 //! This means that regardless of whether or not we need the generator, we have
 //! and object that we can pass through the system and won't have to make a lot
 //! of conditionals in the rest of the code. Only when the generator will be needed,
-//! such as for generating the alternative shuffle sequences.
+//! such as for generating the alternative shuffle sequences or random numbers
+//! in expressions.
+//!
+//! Random numbers are drawn while expressions are evaluated, which only have shared
+//! access to the story data. The generator is kept behind a lock for this reason.
 
 // For scope simplicity we create a private modules depending on whether or not
 // the random generator will be needed. We then export the `StoryRng` object
@@ -32,20 +36,54 @@ mod feature_wrapper {
     /// If you are reading this text, the `random` feature is **not**
     /// currently enabled.
     pub struct StoryRng;
+
+    impl StoryRng {
+        /// Create the dummy generator.
+        pub fn new() -> Self {
+            StoryRng
+        }
+
+        /// Create the dummy generator, which has no use for the seed.
+        pub fn with_seed(_seed: u64) -> Self {
+            StoryRng
+        }
+
+        /// Get the seed of the generator, which does not exist without the `random` feature.
+        pub fn get_seed(&self) -> Option<u64> {
+            None
+        }
+
+        /// Assert that the generator is seeded, which the dummy generator always is
+        /// since it produces no numbers.
+        pub fn is_seeded(&self) -> bool {
+            true
+        }
+
+        /// Get a number from the range, which is always its lowest value since the
+        /// dummy generator produces no numbers.
+        pub fn gen_range(&self, min: i32, _max: i32) -> i32 {
+            min
+        }
+
+        /// Set a new seed, which the dummy generator has no use for.
+        pub fn reseed(&self, _seed: u64) {}
+    }
 }
 
 #[cfg(feature = "random")]
 mod feature_wrapper {
-    use rand::{RngCore, SeedableRng};
+    use rand::{seq::SliceRandom, Rng, RngCore, SeedableRng};
     use rand_chacha::ChaCha8Rng;
 
+    use std::sync::{Mutex, MutexGuard};
+
     #[cfg(feature = "serde_support")]
     use serde::{
         de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
         ser::{Serialize, SerializeStruct, Serializer},
     };
 
-    #[derive(Clone, Debug)]
+    #[derive(Debug)]
     /// Random number generator for the [`Story`][crate::story::Story].
     ///
     /// We use `ChaChaRng` due to it being seedable and with the ability to get and set
@@ -55,35 +93,108 @@ mod feature_wrapper {
     /// and `Serialize` below. This is due to the generator itself not having either
     /// derived.
     pub struct StoryRng {
+        /// Generator along with its seed.
+        state: Mutex<RngState>,
+    }
+
+    #[derive(Clone, Debug)]
+    /// Generator and the seed that it was initiated with.
+    struct RngState {
         /// Random number generator.
-        pub gen: ChaCha8Rng,
+        gen: ChaCha8Rng,
         /// Seed for the generator.
         seed: u64,
+        /// Whether the seed was set instead of drawn from entropy.
+        ///
+        /// Generators which are restored from a saved state count as seeded, since they
+        /// continue from the saved seed and position.
+        is_seeded: bool,
+    }
+
+    impl RngState {
+        fn with_seed(seed: u64) -> Self {
+            let mut gen = ChaCha8Rng::seed_from_u64(seed);
+
+            // `get_word_pos()` will panic unless we set the stream to 0
+            gen.set_word_pos(0);
+
+            RngState {
+                gen,
+                seed,
+                is_seeded: true,
+            }
+        }
     }
 
     impl Default for StoryRng {
         fn default() -> Self {
             let seed = ChaCha8Rng::from_entropy().next_u64();
-            StoryRng::with_seed(seed)
+
+            StoryRng::from_state(RngState {
+                is_seeded: false,
+                ..RngState::with_seed(seed)
+            })
+        }
+    }
+
+    impl Clone for StoryRng {
+        fn clone(&self) -> Self {
+            StoryRng::from_state(self.lock().clone())
         }
     }
 
     impl StoryRng {
+        /// Initiate the random number generator with a seed drawn from entropy.
+        pub fn new() -> Self {
+            StoryRng::default()
+        }
+
         /// Initiate the random number generator with a seed.
-        fn with_seed(seed: u64) -> Self {
-            let mut gen = ChaCha8Rng::seed_from_u64(seed);
+        pub fn with_seed(seed: u64) -> Self {
+            StoryRng::from_state(RngState::with_seed(seed))
+        }
 
-            // `get_word_pos()` will panic unless we set the stream to 0
-            gen.set_word_pos(0);
+        /// Get the seed that the generator was initiated with.
+        pub fn get_seed(&self) -> Option<u64> {
+            Some(self.lock().seed)
+        }
+
+        /// Assert whether the seed was set rather than drawn from entropy.
+        pub fn is_seeded(&self) -> bool {
+            self.lock().is_seeded
+        }
 
-            StoryRng { gen, seed }
+        /// Get a number from the range, which includes both bounds.
+        pub fn gen_range(&self, min: i32, max: i32) -> i32 {
+            self.lock().gen.gen_range(min as i64, max as i64 + 1) as i32
+        }
+
+        /// Restart the generator from a new seed.
+        pub fn reseed(&self, seed: u64) {
+            *self.lock() = RngState::with_seed(seed);
+        }
+
+        /// Shuffle a set of items in place.
+        pub fn shuffle<T>(&self, items: &mut [T]) {
+            items.shuffle(&mut self.lock().gen);
+        }
+
+        fn from_state(state: RngState) -> Self {
+            StoryRng {
+                state: Mutex::new(state),
+            }
+        }
+
+        /// Lock the state, which cannot be left inconsistent by a panicking thread.
+        fn lock(&self) -> MutexGuard<'_, RngState> {
+            self.state.lock().unwrap_or_else(|err| err.into_inner())
         }
 
         #[cfg(feature = "serde_support")]
         /// Initiate the random number generator with a seed and word position.
         fn with_seed_and_position(seed: u64, position: u128) -> Self {
-            let mut rng = Self::with_seed(seed);
-            rng.gen.set_word_pos(position);
+            let rng = Self::with_seed(seed);
+            rng.lock().gen.set_word_pos(position);
 
             rng
         }
@@ -97,11 +208,12 @@ mod feature_wrapper {
         {
             // The word position for `ChaCha8Rng` is u128 but `serde` can only serialize
             // up to u64. Ensure that we de/serialize as that.
-            let position = self.gen.get_word_pos() as u64;
+            let rng_state = self.lock();
+            let position = rng_state.gen.get_word_pos() as u64;
 
             let mut state = serializer.serialize_struct("StoryRng", 2)?;
             state.skip_field("gen")?;
-            state.serialize_field("seed", &self.seed)?;
+            state.serialize_field("seed", &rng_state.seed)?;
             state.serialize_field("position", &position)?;
             state.end()
         }
@@ -220,7 +332,8 @@ mod feature_wrapper {
     // Implementation for `PartialEq` to satisfy bounds on `serde_test` functions
     impl PartialEq for StoryRng {
         fn eq(&self, other: &Self) -> bool {
-            self.seed == other.seed && self.gen.get_word_pos() == other.gen.get_word_pos()
+            let (state, other) = (self.lock(), other.lock());
+            state.seed == other.seed && state.gen.get_word_pos() == other.gen.get_word_pos()
         }
     }
 
@@ -234,7 +347,7 @@ mod feature_wrapper {
             let seed = 30;
             let rng = StoryRng::with_seed(seed);
 
-            let position = rng.gen.get_word_pos() as u64;
+            let position = rng.lock().gen.get_word_pos() as u64;
 
             assert_tokens(
                 &rng,
@@ -255,13 +368,13 @@ mod feature_wrapper {
         #[test]
         fn story_rng_serializes_with_correct_word_position() {
             let seed = 30;
-            let mut rng = StoryRng::with_seed(seed);
+            let rng = StoryRng::with_seed(seed);
 
             let mut buffer = vec![0; 64];
-            rng.gen.fill_bytes(&mut buffer);
+            rng.lock().gen.fill_bytes(&mut buffer);
 
             // Get and sanity check current position, it should not be zero right now
-            let position = rng.gen.get_word_pos() as u64;
+            let position = rng.lock().gen.get_word_pos() as u64;
             assert!(position > 0);
 
             assert_tokens(
@@ -279,5 +392,39 @@ mod feature_wrapper {
                 ],
             );
         }
+
+        #[test]
+        fn reseeded_story_rng_serializes_with_the_new_seed_from_the_start() {
+            let rng = StoryRng::with_seed(30);
+            rng.gen_range(0, 10);
+
+            rng.reseed(5);
+
+            assert_tokens(
+                &rng,
+                &[
+                    Token::Struct {
+                        name: "StoryRng",
+                        len: 2,
+                    },
+                    Token::Str("seed"),
+                    Token::U64(5),
+                    Token::Str("position"),
+                    Token::U64(0),
+                    Token::StructEnd,
+                ],
+            );
+        }
+
+        #[test]
+        fn story_rng_generates_numbers_in_the_inclusive_range() {
+            let rng = StoryRng::with_seed(30);
+
+            let numbers = (0..100).map(|_| rng.gen_range(1, 3)).collect::<Vec<_>>();
+
+            assert!(numbers.iter().all(|n| (1..=3).contains(n)));
+            assert!(numbers.contains(&1));
+            assert!(numbers.contains(&3));
+        }
     }
 }
@@ -0,0 +1,468 @@
+//! Compressed saves of story states.
+//!
+//! A save starts with a header which contains a marker, the version of the format, a hash
+//! of the uncompressed state and its length. The state is the story serialized as JSON
+//! and compressed with a small LZ77 compressor, which JSON with its repeated keys and
+//! names compresses well with.
+//!
+//! Saves which are truncated, corrupted or written with another version of the format
+//! are rejected when they are loaded, before their state is deserialized.
+
+use crate::{error::SaveError, story::Story};
+
+/// Marker which every compressed save begins with.
+const SAVE_MARKER: &[u8; 4] = b"INKS";
+
+/// Version of the save format which is written.
+const SAVE_VERSION: u8 = 1;
+
+/// Number of bytes in the header: marker, version, hash and uncompressed length.
+const HEADER_LENGTH: usize = 4 + 1 + 8 + 8;
+
+/// Shortest sequence of repeated bytes which is written as a match.
+const MIN_MATCH: usize = 3;
+
+/// Longest sequence of repeated bytes which is written as a match.
+const MAX_MATCH: usize = MIN_MATCH + u8::MAX as usize;
+
+/// Greatest distance back to a repeated sequence.
+const WINDOW_SIZE: usize = u16::MAX as usize;
+
+/// Number of bits in hashes of sequences which are used to find matches.
+const HASH_BITS: u32 = 15;
+
+/// Number of earlier positions with the same hash to check for the longest match.
+const MAX_CHAIN_LENGTH: usize = 64;
+
+/// Serialize a story into a compressed save.
+///
+/// The save contains the full state of the story, like its serialized form, along
+/// with a hash which is checked when it is loaded. Requires the `compressed_saves` feature.
+///
+/// # Examples
+/// ```
+/// # use inkling::{load_story_from_bytes, read_story_from_string, save_story_to_bytes};
+/// let content = "\
+/// The lighthouse keeper climbed the stairs once more.
+/// *   [Light the lamp] The beam swept across the bay.
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// story.resume(&mut Vec::new()).unwrap();
+///
+/// let bytes = save_story_to_bytes(&story).unwrap();
+/// let mut loaded = load_story_from_bytes(&bytes).unwrap();
+///
+/// loaded.make_choice(0).unwrap();
+///
+/// let mut line_buffer = Vec::new();
+/// loaded.resume(&mut line_buffer).unwrap();
+///
+/// assert_eq!(&line_buffer[0].text, "The beam swept across the bay.\n");
+/// ```
+///
+/// # Errors
+/// *   [`Serialize`][crate::error::SaveError::Serialize]: if the story could not
+///     be serialized.
+pub fn save_story_to_bytes(story: &Story) -> Result<Vec<u8>, SaveError> {
+    let state = serde_json::to_vec(story).map_err(|err| SaveError::Serialize {
+        message: err.to_string(),
+    })?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + state.len() / 4);
+
+    bytes.extend_from_slice(SAVE_MARKER);
+    bytes.push(SAVE_VERSION);
+    bytes.extend_from_slice(&get_hash(&state).to_le_bytes());
+    bytes.extend_from_slice(&(state.len() as u64).to_le_bytes());
+
+    compress(&state, &mut bytes);
+
+    Ok(bytes)
+}
+
+/// Load a story from a save which was written by
+/// [`save_story_to_bytes`][crate::save_story_to_bytes()].
+///
+/// Requires the `compressed_saves` feature.
+///
+/// # Errors
+/// *   [`InvalidHeader`][crate::error::SaveError::InvalidHeader]: if the data is not
+///     a compressed save.
+/// *   [`UnsupportedVersion`][crate::error::SaveError::UnsupportedVersion]: if the save
+///     was written with another version of the format.
+/// *   [`CorruptData`][crate::error::SaveError::CorruptData]: if the compressed state
+///     is truncated or malformed.
+/// *   [`HashMismatch`][crate::error::SaveError::HashMismatch]: if the decompressed state
+///     does not have the hash that was saved with it.
+/// *   [`Deserialize`][crate::error::SaveError::Deserialize]: if the state could not be
+///     deserialized into a story.
+pub fn load_story_from_bytes(bytes: &[u8]) -> Result<Story, SaveError> {
+    if bytes.len() < HEADER_LENGTH || &bytes[..4] != SAVE_MARKER {
+        return Err(SaveError::InvalidHeader);
+    }
+
+    let version = bytes[4];
+
+    if version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion { version });
+    }
+
+    let expected = read_u64(&bytes[5..13]);
+    let length = usize::try_from(read_u64(&bytes[13..21])).map_err(|_| SaveError::CorruptData)?;
+
+    let state = decompress(&bytes[HEADER_LENGTH..], length)?;
+    let found = get_hash(&state);
+
+    if found != expected {
+        return Err(SaveError::HashMismatch { expected, found });
+    }
+
+    serde_json::from_slice(&state).map_err(|err| SaveError::Deserialize {
+        message: err.to_string(),
+    })
+}
+
+/// Read a little-endian integer from eight bytes.
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buffer = [0; 8];
+    buffer.copy_from_slice(bytes);
+
+    u64::from_le_bytes(buffer)
+}
+
+/// Get the 64 bit FNV-1a hash of some data.
+///
+/// The hash is stable across platforms and versions, unlike the hashers of the standard library.
+fn get_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/*********************
+ * LZ77 compression *
+ *********************/
+
+// Compressed data is a sequence of groups of up to eight tokens, each preceded by a byte
+// with one bit per token. A cleared bit is a literal byte which is copied as-is. A set bit
+// is a match of three bytes: the distance back to the repeated sequence as a little-endian
+// `u16` and the length of the sequence minus `MIN_MATCH`.
+
+/// Compress data and write it to the output.
+fn compress(input: &[u8], output: &mut Vec<u8>) {
+    let mut matcher = Matcher::new(input);
+
+    let mut flag_index = 0;
+    let mut num_tokens = 8;
+
+    let mut i = 0;
+
+    while i < input.len() {
+        if num_tokens == 8 {
+            flag_index = output.len();
+            output.push(0);
+            num_tokens = 0;
+        }
+
+        match matcher.find_longest_match(i) {
+            Some((distance, length)) => {
+                output[flag_index] |= 1 << num_tokens;
+                output.extend_from_slice(&(distance as u16).to_le_bytes());
+                output.push((length - MIN_MATCH) as u8);
+
+                for position in i..(i + length) {
+                    matcher.insert(position);
+                }
+
+                i += length;
+            }
+            None => {
+                output.push(input[i]);
+                matcher.insert(i);
+
+                i += 1;
+            }
+        }
+
+        num_tokens += 1;
+    }
+}
+
+/// Decompress data into the given number of bytes.
+///
+/// The length is read from the save and is checked against the most that the input could
+/// decompress into before any memory is set aside for it.
+fn decompress(input: &[u8], length: usize) -> Result<Vec<u8>, SaveError> {
+    // No byte of compressed data writes more than a full match
+    if length / MAX_MATCH > input.len() {
+        return Err(SaveError::CorruptData);
+    }
+
+    let mut output = Vec::with_capacity(length.min(input.len()));
+    let mut bytes = input.iter().copied();
+
+    while output.len() < length {
+        let flags = bytes.next().ok_or(SaveError::CorruptData)?;
+
+        for bit in 0..8 {
+            if output.len() == length {
+                break;
+            }
+
+            if flags & (1 << bit) == 0 {
+                output.push(bytes.next().ok_or(SaveError::CorruptData)?);
+                continue;
+            }
+
+            let mut next = || bytes.next().ok_or(SaveError::CorruptData);
+
+            let distance = u16::from_le_bytes([next()?, next()?]) as usize;
+            let match_length = next()? as usize + MIN_MATCH;
+
+            if distance == 0 || distance > output.len() {
+                return Err(SaveError::CorruptData);
+            }
+
+            let start = output.len() - distance;
+
+            // Matches may overlap the bytes that they write, so copy one byte at a time
+            for index in start..(start + match_length) {
+                output.push(output[index]);
+            }
+        }
+    }
+
+    if output.len() != length || bytes.next().is_some() {
+        return Err(SaveError::CorruptData);
+    }
+
+    Ok(output)
+}
+
+/// Finder of earlier sequences which match the data at a position.
+///
+/// Positions are kept in chains of earlier positions whose first bytes have the same hash.
+struct Matcher<'a> {
+    input: &'a [u8],
+    /// Last inserted position for every hash.
+    head: Vec<Option<usize>>,
+    /// Previously inserted position with the same hash, for positions in the window.
+    previous: Vec<Option<usize>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Matcher {
+            input,
+            head: vec![None; 1 << HASH_BITS],
+            previous: vec![None; WINDOW_SIZE + 1],
+        }
+    }
+
+    /// Get the hash of the sequence at a position, if there are enough bytes left for a match.
+    fn get_hash(&self, position: usize) -> Option<usize> {
+        let bytes = self.input.get(position..(position + MIN_MATCH))?;
+
+        let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+
+        Some((value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize)
+    }
+
+    /// Add a position to the chain of its hash.
+    fn insert(&mut self, position: usize) {
+        if let Some(hash) = self.get_hash(position) {
+            let index = position % self.previous.len();
+
+            self.previous[index] = self.head[hash];
+            self.head[hash] = Some(position);
+        }
+    }
+
+    /// Find the longest earlier sequence in the window which matches the data at a position.
+    ///
+    /// Returns its distance back from the position and its length.
+    fn find_longest_match(&self, position: usize) -> Option<(usize, usize)> {
+        let hash = self.get_hash(position)?;
+        let max_length = MAX_MATCH.min(self.input.len() - position);
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.head[hash];
+
+        for _ in 0..MAX_CHAIN_LENGTH {
+            let start = match candidate {
+                Some(start) if position - start <= WINDOW_SIZE => start,
+                _ => break,
+            };
+
+            let length = self.input[start..]
+                .iter()
+                .zip(&self.input[position..(position + max_length)])
+                .take_while(|(lhs, rhs)| lhs == rhs)
+                .count();
+
+            if length >= MIN_MATCH && best.map(|(_, best)| length > best).unwrap_or(true) {
+                best = Some((position - start, length));
+
+                if length == max_length {
+                    break;
+                }
+            }
+
+            candidate = self.previous[start % self.previous.len()];
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{line::Variable, story::read_story_from_string};
+
+    fn get_story_and_save() -> (Story, Vec<u8>) {
+        let content = "\
+VAR coins = 0
+The market was busy.
+*   [Buy bread]
+    ~ coins = coins - 1
+    You bought some bread.
+*   [Leave] -> END
+- -> END
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        story.resume(&mut Vec::new()).unwrap();
+        story.make_choice(0).unwrap();
+        story.resume(&mut Vec::new()).unwrap();
+
+        let bytes = save_story_to_bytes(&story).unwrap();
+
+        (story, bytes)
+    }
+
+    fn get_state(story: &Story) -> serde_json::Value {
+        serde_json::to_value(story).unwrap()
+    }
+
+    fn compress_and_decompress(input: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        compress(input, &mut compressed);
+
+        decompress(&compressed, input.len()).unwrap()
+    }
+
+    #[test]
+    fn compressed_data_decompresses_to_the_original() {
+        let repeated = "{\"name\":\"knot\",\"visits\":0},".repeat(200);
+
+        let mut mixed = Vec::new();
+        for i in 0..5000u32 {
+            mixed.extend_from_slice(&(i.wrapping_mul(2_654_435_761) >> 20).to_le_bytes());
+        }
+
+        for input in &[b"".to_vec(), b"ab".to_vec(), repeated.into_bytes(), mixed] {
+            assert_eq!(&compress_and_decompress(input), input);
+        }
+    }
+
+    #[test]
+    fn repetitive_data_is_compressed_to_a_fraction_of_its_size() {
+        let input = "{\"knot\":\"hallway\",\"stitch\":null,\"count\":1}".repeat(100);
+
+        let mut compressed = Vec::new();
+        compress(input.as_bytes(), &mut compressed);
+
+        assert!(compressed.len() * 10 < input.len());
+    }
+
+    #[test]
+    fn overlapping_matches_repeat_their_bytes() {
+        let input = "a".repeat(1000);
+
+        assert_eq!(compress_and_decompress(input.as_bytes()), input.as_bytes());
+    }
+
+    #[test]
+    fn truncated_or_malformed_data_yields_errors() {
+        let input = "the same words, the same words, the same words".as_bytes();
+
+        let mut compressed = Vec::new();
+        compress(input, &mut compressed);
+
+        assert_eq!(
+            decompress(&compressed[..compressed.len() - 1], input.len()),
+            Err(SaveError::CorruptData)
+        );
+
+        assert_eq!(
+            decompress(&[0b0000_0001, 5, 0, 0], 3),
+            Err(SaveError::CorruptData)
+        );
+    }
+
+    #[test]
+    fn lengths_which_the_data_cannot_decompress_into_yield_errors() {
+        assert_eq!(decompress(&[0, 1], 1_000_000), Err(SaveError::CorruptData));
+
+        let mut bytes = SAVE_MARKER.to_vec();
+        bytes.push(SAVE_VERSION);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+
+        assert_eq!(bytes.len(), HEADER_LENGTH);
+        assert!(matches!(
+            load_story_from_bytes(&bytes),
+            Err(SaveError::CorruptData)
+        ));
+    }
+
+    #[test]
+    fn saves_are_loaded_into_the_story_that_they_were_written_from() {
+        let (story, bytes) = get_story_and_save();
+        let loaded = load_story_from_bytes(&bytes).unwrap();
+
+        assert_eq!(loaded.get_variable("coins"), Some(Variable::Int(-1)));
+        assert_eq!(get_state(&loaded), get_state(&story));
+    }
+
+    #[test]
+    fn truncated_saves_yield_errors_at_every_length() {
+        let (_, bytes) = get_story_and_save();
+
+        for length in 0..bytes.len() {
+            assert!(load_story_from_bytes(&bytes[..length]).is_err());
+        }
+    }
+
+    #[test]
+    fn saves_with_any_corrupted_byte_yield_errors_or_the_same_story() {
+        let (story, bytes) = get_story_and_save();
+
+        for index in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[index] ^= 0b0101_0101;
+
+            // A corrupted match may point to an earlier sequence with the same bytes
+            if let Ok(loaded) = load_story_from_bytes(&corrupted) {
+                assert_eq!(get_state(&loaded), get_state(&story));
+            }
+        }
+    }
+
+    #[test]
+    fn saves_with_another_version_of_the_format_yield_errors() {
+        let (_, mut bytes) = get_story_and_save();
+
+        for version in [0, SAVE_VERSION + 1] {
+            bytes[4] = version;
+
+            assert_eq!(
+                load_story_from_bytes(&bytes).err(),
+                Some(SaveError::UnsupportedVersion { version })
+            );
+        }
+    }
+}
@@ -2,32 +2,98 @@
 
 use crate::{
     consts::ROOT_KNOT_NAME,
-    error::{InklingError, ReadError},
-    follow::{ChoiceInfo, EncounteredEvent, FollowData, LineDataBuffer},
-    knot::{get_empty_knot_counts, get_mut_stitch, get_num_visited, Address, KnotSet},
-    line::Variable,
-    log::Logger,
-    process::{get_fallback_choices, prepare_choices_for_user, process_buffer},
+    error::{
+        get_diagnostics_from_log, get_diagnostics_from_read_error,
+        parse::{
+            address::{InvalidAddressError, InvalidAddressErrorKind},
+            validate::ValidationError,
+        },
+        utils::MetaData,
+        variable::VariableError,
+        Diagnostic, InklingError, ReadError,
+    },
+    follow::{
+        ChoiceInfo, ChoiceOrigin, EncounteredEvent, FollowData, LineDataBuffer, ThreadChoices,
+    },
+    knot::{
+        detach_choice_texts, get_empty_knot_counts, get_function_set, get_mut_stitch,
+        get_num_visited, move_to_label, set_choice_ids, set_num_visited, Address, AddressKind,
+        FunctionSet, KnotSet,
+    },
+    line::{format_variable_for_debug, Variable},
+    log::{Logger, Warning},
+    process::{get_fallback_choices, prepare_choices_for_user, process_buffer_into_items},
     story::{
-        parse::read_story_content_from_string,
+        audit::Nondeterminism,
+        coverage::ConditionCoverage,
+        external::{ExternalFunctions, ExternalVariables, SharedVariableStore},
+        journal::VisitJournal,
+        parse::{
+            read_comments_from_file_string, read_story_content_from_file_string,
+            read_story_content_from_reader,
+        },
         rng::StoryRng,
-        types::{Choice, LineBuffer, Location, Prompt},
+        tags::{search_tags, Tag, TaggedContent},
+        types::{
+            Choice, Comment, EndingKind, ItemBuffer, Line, LineBuffer, Location, OutputBlock, Poll,
+            Prompt, StoryItem, StorySnapshot, UndeclaredVariablePolicy, VariableInfo, VariableSet,
+        },
         validate::validate_story_content,
+        variable_history::{ChangeOrigin, VariableChange, VariableHistory, VariableObservers},
+        ReadOptions,
     },
 };
 
+#[cfg(feature = "lints")]
+use crate::story::validate::lint_story_content;
+
+#[cfg(feature = "debug")]
+use crate::story::timeline::{Timeline, TimelineEntry, TimelineState};
+
+#[cfg(feature = "includes")]
+use crate::story::{
+    include::{read_lines_with_includes, FileResolver, IncludeResolver},
+    parse::read_story_content_from_lines,
+};
+
+#[cfg(feature = "includes")]
+use std::path::Path;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::BufRead,
+    sync::Arc,
+};
+
+#[cfg(feature = "serde_support")]
+use crate::story::strings::{deserialize_deduplicated, serialize_deduplicated};
+
 #[cfg(feature = "serde_support")]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
-#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(Deserialize, Serialize),
+    serde(remote = "Self")
+)]
 /// Story with knots, diverts, choices and possibly lots of text.
+///
+/// When the `serde_support` feature is enabled the story is serialized with a table
+/// of the names, addresses and text that it contains, which are then referred to by
+/// their index in the table. This keeps large stories from repeating the same strings.
 pub struct Story {
     /// Current address in the story.
     current_address: Address,
     /// Collection of `Knot`s which make up the story.
+    #[cfg_attr(feature = "serde_support", serde(with = "crate::story::strings::map"))]
     knots: KnotSet,
+    /// Global variables with the values that they were declared with.
+    ///
+    /// Saved with the content of the story. Variables whose value does not differ from
+    /// their declaration are rebuilt from these when the story is loaded.
+    declarations: VariableSet,
     /// History of visited addresses.
     history: Vec<Address>,
     /// Internal data for the story.
@@ -38,11 +104,104 @@ pub struct Story {
     last_choices: Option<Vec<Choice>>,
     /// Choice that has been set to resume the story with.
     selected_choice: Option<usize>,
+    /// Lines which have been produced but not yet returned by
+    /// [`resume_step`][crate::story::Story::resume_step()].
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    stepped_lines: VecDeque<Line>,
     /// Log of warnings and to-do comments encountered when parsing the `Story` from the script.
     pub log: Logger,
+    /// Comments in the script, if they were preserved when reading it.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    comments: Vec<Comment>,
+    /// How to handle variables which are set by the host but not declared in the story.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    undeclared_variable_policy: UndeclaredVariablePolicy,
+    /// Timeline of the most recent prompts, if it is being recorded.
+    #[cfg(feature = "debug")]
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    timeline: Option<Timeline>,
+}
+
+#[cfg(feature = "serde_support")]
+/// Wrapper which serializes the fields of a story with their derived implementation.
+struct StoryFields<'a>(&'a Story);
+
+#[cfg(feature = "serde_support")]
+impl Serialize for StoryFields<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Story::serialize(self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+/// Wrapper which deserializes the fields of a story with their derived implementation.
+struct OwnedStoryFields(Story);
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for OwnedStoryFields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Story::deserialize(deserializer).map(OwnedStoryFields)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl Serialize for Story {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_deduplicated(&StoryFields(self), serializer)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for Story {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_deduplicated::<OwnedStoryFields, D>(deserializer).map(
+            |OwnedStoryFields(mut story)| {
+                story.restore_unvisited_counts();
+                story.restore_unchanged_variables();
+                story
+            },
+        )
+    }
 }
 
 impl Story {
+    #[cfg(feature = "serde_support")]
+    /// Add the visit counts of knots and stitches which have not been visited.
+    ///
+    /// Counts of zero are not saved, so they are restored when the story is loaded.
+    fn restore_unvisited_counts(&mut self) {
+        for (knot_name, stitches) in get_empty_knot_counts(&self.knots) {
+            let counts = self.data.knot_visit_counts.entry(knot_name).or_default();
+
+            for (stitch_name, num_visited) in stitches {
+                counts.entry(stitch_name).or_insert(num_visited);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde_support")]
+    /// Rebuild the global variables from their declarations and set the saved values to them.
+    ///
+    /// Only the values of variables which differ from their declaration are saved.
+    fn restore_unchanged_variables(&mut self) {
+        let mut changed = std::mem::take(&mut self.data.variables);
+
+        self.data.variables = self
+            .declarations
+            .iter()
+            .map(|(name, declaration)| {
+                let mut variable_info = declaration.clone();
+                variable_info.declared = Some(declaration.variable.clone());
+
+                if let Some(saved_info) = changed.remove(name) {
+                    variable_info.variable = saved_info.variable;
+                }
+
+                (name.clone(), variable_info)
+            })
+            .collect();
+    }
+
     /// Resume the story text flow while reading all encountered lines into the supplied buffer.
     ///
     /// Should be called to start the flow through the story or to resume it
@@ -136,6 +295,146 @@ impl Story {
     /// assert_eq!(&line_buffer[0].text, "Miranda was waiting in her office.\n");
     /// ```
     pub fn resume(&mut self, line_buffer: &mut LineBuffer) -> Result<Prompt, InklingError> {
+        let mut item_buffer = Vec::new();
+        let prompt = self.resume_with_directives(&mut item_buffer)?;
+
+        line_buffer.extend(get_lines_from_items(item_buffer));
+
+        Ok(prompt)
+    }
+
+    /// Resume the story flow by a single line, for hosts which show lines one at a time.
+    ///
+    /// Adds the next line of text to the buffer and returns
+    /// [`Prompt::Continue`][crate::Prompt::Continue] if the story has more to show. When
+    /// the last line before a set of choices or the end of the story is returned, the prompt
+    /// is the choices or end. Calling it when the story is waiting for a choice returns the
+    /// choices again, without adding a line.
+    ///
+    /// The story is followed until the first divert after a line, like with
+    /// [`set_max_lines_per_resume`][crate::story::Story::set_max_lines_per_resume()],
+    /// rather than until the next choice. Any further lines which were produced before
+    /// the divert are kept in the story and returned by the next calls, which also saves
+    /// them with the story state. [`resume`][crate::story::Story::resume()] returns the kept
+    /// lines first and only continues the story if it did not stop after them.
+    ///
+    /// Variables are assigned while the story is followed, so their
+    /// [observers][crate::story::Story::observe_variable()] are notified of every change that
+    /// is made before the divert when the first of those lines is returned, not when the lines
+    /// after the change are.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Prompt};
+    /// let content = "\
+    /// The lights dimmed.
+    /// A voice spoke from the dark.
+    /// *   [Answer] -> END
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// assert_eq!(story.resume_step(&mut line_buffer).unwrap(), Prompt::Continue);
+    /// assert_eq!(&line_buffer[0].text, "The lights dimmed.\n");
+    ///
+    /// let prompt = story.resume_step(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[1].text, "A voice spoke from the dark.\n");
+    /// assert!(prompt.get_choices().is_some());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the same errors as [`resume`][crate::story::Story::resume()].
+    pub fn resume_step(&mut self, line_buffer: &mut LineBuffer) -> Result<Prompt, InklingError> {
+        if self.stepped_lines.is_empty() {
+            let max_lines = self.data.max_lines_per_resume.replace(1);
+
+            let mut lines = Vec::new();
+            let result = self.resume(&mut lines);
+
+            self.data.max_lines_per_resume = max_lines;
+
+            let prompt = result?;
+
+            if lines.is_empty() {
+                return Ok(prompt);
+            }
+
+            self.stepped_lines.extend(lines);
+        }
+
+        line_buffer.extend(self.stepped_lines.pop_front());
+
+        if !self.stepped_lines.is_empty() {
+            return Ok(Prompt::Continue);
+        }
+
+        Ok(self.get_stopped_prompt().unwrap_or(Prompt::Continue))
+    }
+
+    /// Get the prompt of a story which is waiting for a choice or has reached its end.
+    fn get_stopped_prompt(&self) -> Option<Prompt> {
+        match (self.current_choices(), self.data.ending) {
+            (Some(choices), _) => Some(Prompt::Choice(choices.to_vec())),
+            (None, Some(ending)) => Some(Prompt::Done(ending)),
+            (None, None) => None,
+        }
+    }
+
+    /// Resume the story flow while also returning encountered custom directives.
+    ///
+    /// Works like [`resume`][crate::story::Story::resume()] but adds both lines of text and
+    /// [custom directives][crate::Directive] to the buffer, in the order that they appear
+    /// in the story. Directives are registered in the [`ReadOptions`][crate::ReadOptions]
+    /// when reading the story. The regular `resume` method skips them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, ReadOptions, StoryItem, Variable};
+    /// let content = "\
+    /// The lights dimmed.
+    /// @wait 2
+    /// A voice spoke from the dark.
+    /// ";
+    ///
+    /// let mut options = ReadOptions::default();
+    /// options.directives.insert("@wait".to_string(), |content| {
+    ///     content
+    ///         .parse::<i32>()
+    ///         .map(|seconds| vec![Variable::Int(seconds)])
+    ///         .map_err(|err| err.to_string())
+    /// });
+    ///
+    /// let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    /// let mut buffer = Vec::new();
+    ///
+    /// story.resume_with_directives(&mut buffer).unwrap();
+    ///
+    /// match &buffer[1] {
+    ///     StoryItem::Directive(directive) => {
+    ///         assert_eq!(&directive.name, "@wait");
+    ///         assert_eq!(&directive.arguments, &[Variable::Int(2)]);
+    ///     }
+    ///     other => panic!("expected a directive but got {:?}", other),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the same errors as [`resume`][crate::story::Story::resume()].
+    pub fn resume_with_directives(
+        &mut self,
+        item_buffer: &mut ItemBuffer,
+    ) -> Result<Prompt, InklingError> {
+        // Return the lines which are left from stepping before continuing the story
+        if !self.stepped_lines.is_empty() {
+            item_buffer.extend(self.stepped_lines.drain(..).map(StoryItem::Line));
+
+            if let Some(prompt) = self.get_stopped_prompt() {
+                return Ok(prompt);
+            }
+        }
+
         // Break early if we are at a choice but no choice has yet been made
         match (self.selected_choice, self.last_choices.as_ref()) {
             (None, Some(choices)) => return Ok(Prompt::Choice(choices.clone())),
@@ -144,7 +443,287 @@ impl Story {
 
         let selection = self.selected_choice.take();
 
-        self.follow_story_wrapper(selection, line_buffer)
+        self.follow_story_wrapper(selection, item_buffer)
+    }
+
+    /// Resume the story flow and return the produced lines as a block of their own.
+    ///
+    /// Works like [`resume`][crate::story::Story::resume()] but instead of adding the lines
+    /// to an existing buffer, returns the lines that were produced since the last prompt
+    /// along with their tags, the locations that the story was resumed from and stopped at,
+    /// and the prompt itself. There is no need to keep track of which lines in a buffer
+    /// are new.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location, Prompt};
+    /// let content = "\
+    /// The train pulled into the station. # arrival
+    /// *   [Step off] -> platform
+    ///
+    /// === platform ===
+    /// The platform was deserted.
+    /// -> END
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let block = story.resume_block().unwrap();
+    ///
+    /// assert_eq!(block.lines.len(), 1);
+    /// assert_eq!(&block.tags, &["arrival".to_string()]);
+    /// assert!(block.prompt.get_choices().is_some());
+    ///
+    /// story.make_choice(0).unwrap();
+    ///
+    /// let block = story.resume_block().unwrap();
+    ///
+    /// assert_eq!(&block.lines[0].text, "The platform was deserted.\n");
+    /// assert_eq!(block.end, Location::from("platform"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the same errors as [`resume`][crate::story::Story::resume()].
+    pub fn resume_block(&mut self) -> Result<OutputBlock, InklingError> {
+        let start = self.get_current_location();
+
+        let mut lines = Vec::new();
+        let prompt = self.resume(&mut lines)?;
+
+        let tags = lines
+            .iter()
+            .flat_map(|line| line.tags.iter().cloned())
+            .collect();
+
+        Ok(OutputBlock {
+            lines,
+            tags,
+            start,
+            end: self.get_current_location(),
+            prompt,
+        })
+    }
+
+    /// Advance the story by a bounded amount of work, for hosts which run in a fixed time step.
+    ///
+    /// Every call follows the story through at most one knot or stitch before returning,
+    /// so the work that it does is limited by the amount of content in it, even if the story
+    /// diverts between knots many times before it reaches a choice. Call it once per frame
+    /// until it returns lines to show, choices to present or that the story is done:
+    ///
+    /// *   [`Pending`][crate::Poll::Pending]: the story moved on without producing lines.
+    /// *   [`Lines`][crate::Poll::Lines]: the story produced a block of lines. If the
+    ///     story stopped at a choice or its end, the next call returns that.
+    /// *   [`Choices`][crate::Poll::Choices]: the story is waiting for a choice to be made
+    ///     with [`make_choice`][crate::story::Story::make_choice()].
+    /// *   [`Done`][crate::Poll::Done]: the story has reached its end. Move it to a new
+    ///     location with [`move_to`][crate::story::Story::move_to()] to continue it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Poll};
+    /// let content = "\
+    /// -> corridor
+    ///
+    /// === corridor ===
+    /// -> door
+    ///
+    /// === door ===
+    /// A door blocked the corridor.
+    /// *   [Knock] Nobody answered.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// assert_eq!(story.poll().unwrap(), Poll::Pending);
+    /// assert_eq!(story.poll().unwrap(), Poll::Pending);
+    ///
+    /// match story.poll().unwrap() {
+    ///     Poll::Lines(block) => assert_eq!(&block.lines[0].text, "A door blocked the corridor.\n"),
+    ///     other => panic!("expected lines but got {:?}", other),
+    /// }
+    ///
+    /// assert!(matches!(story.poll().unwrap(), Poll::Choices(..)));
+    ///
+    /// story.make_choice(0).unwrap();
+    ///
+    /// assert!(matches!(story.poll().unwrap(), Poll::Lines(..)));
+    /// assert_eq!(story.poll().unwrap(), Poll::Done);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the same errors as [`resume`][crate::story::Story::resume()].
+    pub fn poll(&mut self) -> Result<Poll, InklingError> {
+        if self.selected_choice.is_none() {
+            if let Some(choices) = &self.last_choices {
+                return Ok(Poll::Choices(choices.clone()));
+            }
+
+            if self.data.ending.is_some() {
+                return Ok(Poll::Done);
+            }
+        }
+
+        let max_diverts = self.data.max_diverts_per_resume.replace(1);
+        let result = self.resume_block();
+        self.data.max_diverts_per_resume = max_diverts;
+
+        let block = result?;
+
+        if !block.lines.is_empty() {
+            return Ok(Poll::Lines(block));
+        }
+
+        match block.prompt {
+            Prompt::Continue => Ok(Poll::Pending),
+            Prompt::Choice(choices) => Ok(Poll::Choices(choices)),
+            Prompt::Done(..) => Ok(Poll::Done),
+        }
+    }
+
+    /// Get whether the story can be resumed to continue its text flow.
+    ///
+    /// This is the case unless the story is waiting for a choice to be made or has reached
+    /// its end, and has no lines left for [`resume_step`][crate::story::Story::resume_step()]
+    /// to return. Does not change the state of the story, so it can be used to check a story
+    /// which was just loaded from a save before calling
+    /// [`resume`][crate::story::Story::resume()].
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// The guard blocked the gate.
+    /// *   [Bribe him] He let you pass.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// assert!(story.can_continue());
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert!(!story.can_continue());
+    ///
+    /// story.make_choice(0).unwrap();
+    /// assert!(story.can_continue());
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert!(!story.can_continue());
+    /// ```
+    pub fn can_continue(&self) -> bool {
+        !self.stepped_lines.is_empty()
+            || (self.current_choices().is_none() && self.data.ending.is_none())
+    }
+
+    /// Get the choices that the story is waiting for a selection from, if any.
+    ///
+    /// These are the choices that [`resume`][crate::story::Story::resume()] returns again
+    /// until one of them is made. Does not change the state of the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// The guard blocked the gate.
+    /// *   [Bribe him] He let you pass.
+    /// *   [Turn back] You walked away.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// assert!(story.current_choices().is_none());
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// let choices = story.current_choices().unwrap();
+    ///
+    /// assert_eq!(choices.len(), 2);
+    /// assert_eq!(&choices[1].text, "Turn back");
+    /// ```
+    pub fn current_choices(&self) -> Option<&[Choice]> {
+        match (self.selected_choice, self.last_choices.as_ref()) {
+            (None, Some(choices)) => Some(choices),
+            _ => None,
+        }
+    }
+
+    /// Get the next line of text that the story will produce, without advancing it.
+    ///
+    /// The line is processed from a copy of the story, so visit counts, alternative
+    /// sequences, variables and the random number generator of the story are left as they
+    /// are and the line is produced again when the story is resumed. Observers of variables
+    /// are not called and variables in the [variable store][crate::VariableStore] are not
+    /// assigned to. Functions bound to `EXTERNAL` functions of the story are called if they
+    /// are used, since their effects cannot be undone.
+    ///
+    /// Returns `None` if the story is waiting for a choice to be made, has reached its end
+    /// or reaches a choice or its end without producing another line.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// {&The bell rang.|The bell rang again.}
+    /// *   [Wait] -> END
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let line = story.peek_line().unwrap().unwrap();
+    /// assert_eq!(&line.text, "The bell rang.\n");
+    /// assert_eq!(story.peek_line().unwrap().unwrap(), line);
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(line_buffer[0], line);
+    /// assert!(story.peek_line().unwrap().is_none());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the same errors as [`resume`][crate::story::Story::resume()].
+    pub fn peek_line(&self) -> Result<Option<Line>, InklingError> {
+        if let Some(line) = self.stepped_lines.front() {
+            return Ok(Some(line.clone()));
+        }
+
+        if !self.can_continue() {
+            return Ok(None);
+        }
+
+        let mut story = self.get_detached_copy();
+        story.data.max_lines_per_resume = Some(1);
+
+        let mut line_buffer = Vec::new();
+        story.resume(&mut line_buffer)?;
+
+        Ok(line_buffer.into_iter().next())
+    }
+
+    /// Get a copy of the story which can be followed without affecting it or the host.
+    ///
+    /// The copy has its own choice texts, no observers of variables and the current values
+    /// of variables which are backed by the variable store instead of the store itself.
+    fn get_detached_copy(&self) -> Story {
+        let mut story = self.clone();
+
+        detach_choice_texts(&mut story.knots);
+
+        for (name, info) in story.data.variables.iter_mut() {
+            if let Some(variable) = self.data.external_variables.get(name) {
+                info.variable = variable;
+            }
+        }
+
+        story.data.external_variables = ExternalVariables::default();
+        story.data.variable_observers = VariableObservers::default();
+
+        #[cfg(feature = "debug")]
+        {
+            story.timeline = None;
+        }
+
+        story
     }
 
     /// Make a choice from a given set of options.
@@ -204,27 +783,79 @@ impl Story {
 
         self.selected_choice.replace(index);
         self.last_choices = None;
+        self.data.turn += 1;
 
         Ok(())
     }
 
-    /// Move the story to another knot or stitch.
-    ///
-    /// A move can be performed at any time, before or after starting the story. It
-    /// simply updates the current internal address in the story to the given address.
-    /// If no stitch name is given the default stitch from the root will be selected.
+    /// Make a choice from the presented set of options by its identifier.
     ///
-    /// After moving to a new location, call [`resume`][crate::story::Story::resume()]
-    /// to continue the text flow from that point.
+    /// Unlike the index of a choice in the presented set, its [identifier][crate::Choice::id]
+    /// does not depend on which other choices are available. This makes it useful
+    /// for recording and replaying the choices of a playthrough.
     ///
     /// # Examples
-    /// ## Using `Location` to move
     /// ```
-    /// // From ‘Purge’ by Sofi Oksanen
-    /// # use inkling::{read_story_from_string, Location};
+    /// # use inkling::read_story_from_string;
     /// let content = "\
-    /// May, 1949
-    /// For the free Estonia!
+    /// === harbor ===
+    /// *   [Board the ship] The ship set sail at dawn.
+    /// *   [Stay ashore] The ship left without her.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// # story.move_to(&"harbor".into()).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// let choices = story.resume(&mut line_buffer).unwrap().get_choices().unwrap();
+    /// assert_eq!(&choices[1].id, "harbor#1");
+    ///
+    /// story.make_choice_by_id("harbor#1").unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "The ship left without her.\n");
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`MadeChoiceWithoutChoice`][crate::error::InklingError::MadeChoiceWithoutChoice]:
+    ///     if the story is not currently at a branching point.
+    /// *   [`InvalidChoiceId`][crate::error::InklingError::InvalidChoiceId]:
+    ///     if no presented choice has the identifier.
+    pub fn make_choice_by_id(&mut self, id: &str) -> Result<(), InklingError> {
+        let selection = self
+            .last_choices
+            .as_ref()
+            .ok_or(InklingError::MadeChoiceWithoutChoice)
+            .and_then(|last_choices| {
+                last_choices
+                    .iter()
+                    .position(|choice| choice.id == id)
+                    .ok_or(InklingError::InvalidChoiceId {
+                        id: id.to_string(),
+                        presented_choices: last_choices.clone(),
+                    })
+            })?;
+
+        self.make_choice(selection)
+    }
+
+    /// Move the story to another knot or stitch.
+    ///
+    /// A move can be performed at any time, before or after starting the story. It
+    /// simply updates the current internal address in the story to the given address.
+    /// If no stitch name is given the default stitch from the root will be selected.
+    ///
+    /// After moving to a new location, call [`resume`][crate::story::Story::resume()]
+    /// to continue the text flow from that point.
+    ///
+    /// # Examples
+    /// ## Using `Location` to move
+    /// ```
+    /// // From ‘Purge’ by Sofi Oksanen
+    /// # use inkling::{read_story_from_string, Location};
+    /// let content = "\
+    /// May, 1949
+    /// For the free Estonia!
     ///
     /// === chapter_one ===
     /// 1992, western Estonia
@@ -271,12 +902,159 @@ impl Story {
             }
         })?;
 
+        self.move_to_validated_address(to_address);
+
+        Ok(())
+    }
+
+    /// Move the story to a knot, stitch or labeled choice or gather from its full address.
+    ///
+    /// Addresses are written like diverts in the story, but always begin with the knot:
+    /// `knot`, `knot.stitch`, `knot.label` for labels in the knot before its first stitch
+    /// or `knot.stitch.label`. Call [`resume`][crate::story::Story::resume()] to continue
+    /// the text flow from that point. Like with diverts, knots and stitches are followed
+    /// from their beginning and labels from their choice or gather.
+    ///
+    /// If `count_visit` is `false` the visit which the story makes to the address when it
+    /// is resumed is not counted, which keeps the number of visits and `seen` state
+    /// of the story unchanged by the move. This is useful for debugging tools which
+    /// jump around in a story. Other visits, including later visits to the same address,
+    /// are counted as usual.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// === harbour ===
+    /// = pier
+    /// The boats rocked in the swell.
+    /// - (gulls) Gulls circled above. Seen {gulls} times.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.move_to_address("harbour.pier.gulls", false).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "Gulls circled above. Seen 0 times.\n");
+    ///
+    /// line_buffer.clear();
+    ///
+    /// story.move_to_address("harbour.pier.gulls", true).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "Gulls circled above. Seen 1 times.\n");
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the knot or
+    ///     stitch does not exist in the story.
+    /// *   [`InvalidLabel`][crate::error::InklingError::InvalidLabel]: if the label does
+    ///     not exist in its stitch.
+    pub fn move_to_address(
+        &mut self,
+        address: &str,
+        count_visit: bool,
+    ) -> Result<(), InklingError> {
+        let to_address = self.validate_full_address(address)?;
+
+        match to_address {
+            Address::Validated(AddressKind::Label(..)) => {
+                move_to_label(&to_address, &mut self.knots)?
+            }
+            _ => get_mut_stitch(&to_address, &mut self.knots)?.reset_stack(),
+        }
+
+        self.move_to_validated_address(to_address.clone());
+
+        if !count_visit {
+            self.data.uncounted_visit.replace(to_address);
+        }
+
+        Ok(())
+    }
+
+    /// Validate a full address to a knot, stitch or label in the story.
+    ///
+    /// Unknown labels yield `InvalidLabel` for their stitch and other invalid addresses
+    /// yield `InvalidAddress`.
+    fn validate_full_address(&self, address: &str) -> Result<Address, InklingError> {
+        Address::from_full_address(address, &self.knots).map_err(|kind| match kind {
+            InvalidAddressErrorKind::UnknownLabel {
+                knot_name,
+                stitch_name,
+                label_name,
+                ..
+            } => InklingError::InvalidLabel {
+                location: if stitch_name == ROOT_KNOT_NAME {
+                    Location::new(knot_name, None)
+                } else {
+                    Location::with_stitch(knot_name, stitch_name)
+                },
+                label: label_name,
+            },
+            _ => InklingError::InvalidAddress {
+                location: Location::from(address.trim()),
+            },
+        })
+    }
+
+    /// Set the current address of the story and drop the state of the previous location.
+    fn move_to_validated_address(&mut self, to_address: Address) {
         self.update_last_stack(&to_address);
 
         self.last_choices = None;
         self.selected_choice = None;
+        self.stepped_lines.clear();
+        self.data.ending = None;
+        self.data.temporary_variables.clear();
+        self.data.thread_choices.clear();
+        self.data.choice_origins.clear();
+        self.data.uncounted_visit = None;
+    }
 
-        Ok(())
+    /// Parse a location in `knot.stitch` format and check that it exists in the story.
+    ///
+    /// Hosts can use this to check addresses from configuration files, save slots or
+    /// debug consoles when they are given, instead of when a later call to
+    /// [`move_to`][crate::story::Story::move_to()] or
+    /// [`get_num_visited`][crate::story::Story::get_num_visited()] fails. Whitespace around
+    /// the knot and stitch names is removed from the returned location.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location};
+    /// let content = "\
+    /// == harbour
+    /// = pier
+    /// The boats rocked in the swell.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let location = story.parse_location(" harbour.pier ").unwrap();
+    /// assert_eq!(location, Location::with_stitch("harbour", "pier"));
+    ///
+    /// story.move_to(&location).unwrap();
+    ///
+    /// assert!(story.parse_location("harbour.lighthouse").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the knot or
+    ///     stitch does not exist in the story.
+    pub fn parse_location(&self, address: &str) -> Result<Location, InklingError> {
+        let location = Location::from(address.trim());
+
+        let location = Location {
+            knot: location.knot.trim().to_string(),
+            stitch: location.stitch.map(|stitch| stitch.trim().to_string()),
+        };
+
+        Address::from_location(&location, &self.knots)
+            .map(|_| location.clone())
+            .map_err(|_| InklingError::InvalidAddress { location })
     }
 
     #[deprecated(since = "0.12.6", note = "will be removed in 1.0.0")]
@@ -348,6 +1126,107 @@ impl Story {
         self.knots.get(knot_name).map(|knot| knot.tags.clone())
     }
 
+    /// Get the tags associated with the given stitch of a knot.
+    ///
+    /// Returns `None` if the knot or stitch does not exist in the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// === tokyo ===
+    /// = university
+    /// ## sound: cicadas
+    /// The pond was quiet in the heat.
+    /// ";
+    ///
+    /// let story = read_story_from_string(content).unwrap();
+    /// let tags = story.get_stitch_tags("tokyo", "university").unwrap();
+    ///
+    /// assert_eq!(&tags[0], "sound: cicadas");
+    /// ```
+    pub fn get_stitch_tags(&self, knot_name: &str, stitch_name: &str) -> Option<Vec<String>> {
+        self.knots
+            .get(knot_name)
+            .and_then(|knot| knot.stitches.get(stitch_name))
+            .map(|stitch| stitch.tags.clone())
+    }
+
+    /// Find every tag in the story which matches a predicate.
+    ///
+    /// Global tags, knot and stitch tags and the tags of every line and choice are searched,
+    /// whether or not the content has been visited. The tags are returned in the order
+    /// that they are written in the story, starting with the global tags, along with
+    /// the location and kind of the content that they are set to.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location, TaggedContentKind};
+    /// let content = "\
+    /// ## music: rain
+    /// -> tokyo
+    ///
+    /// === tokyo ===
+    /// ## music: crowds
+    /// Tokyo was full of things that startled Sanshirō.
+    /// *   [Walk to the university] -> university
+    ///
+    /// = university
+    /// He sat by the pond. # music: cicadas
+    /// -> END
+    /// ";
+    ///
+    /// let story = read_story_from_string(content).unwrap();
+    /// let music = story.search_tags(|tag| tag.starts_with("music:"));
+    ///
+    /// assert_eq!(music.len(), 3);
+    /// assert_eq!(music[0].kind, TaggedContentKind::Story);
+    /// assert_eq!(music[1].kind, TaggedContentKind::Knot);
+    ///
+    /// assert_eq!(&music[2].tag, "music: cicadas");
+    /// assert_eq!(music[2].kind, TaggedContentKind::Line);
+    /// assert_eq!(music[2].location, Location::with_stitch("tokyo", "university"));
+    /// ```
+    pub fn search_tags<F>(&self, predicate: F) -> Vec<TaggedContent>
+    where
+        F: Fn(&str) -> bool,
+    {
+        search_tags(&self.tags, &self.knots, predicate)
+    }
+
+    /// Get the comments in the story script, in the order they were written.
+    ///
+    /// Comments are only kept if [`preserve_comments`][crate::ReadOptions::preserve_comments]
+    /// was set when reading the story. Otherwise this is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string_with_options, CommentKind, ReadOptions};
+    /// let content = "\
+    /// // Opening scene
+    /// The rain had not stopped for days. // Set the mood
+    /// ";
+    ///
+    /// let options = ReadOptions {
+    ///     preserve_comments: true,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let story = read_story_from_string_with_options(content, &options).unwrap();
+    /// let comments = story.get_comments();
+    ///
+    /// assert_eq!(comments.len(), 2);
+    /// assert_eq!(comments[0].kind, CommentKind::Line);
+    /// assert_eq!(&comments[1].text, "// Set the mood");
+    ///
+    /// // Both comments belong to the line of text
+    /// assert_eq!(comments[0].element.as_ref().unwrap().line(), 2);
+    /// assert_eq!(comments[1].element.as_ref().unwrap().line(), 2);
+    /// ```
+    pub fn get_comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     /// Get the number of times a knot or stitch has been visited so far.
     ///
     /// Returns `None` if the given knot and stitch does not exist in the `Story`.
@@ -381,6 +1260,128 @@ impl Story {
         get_num_visited(&address, &self.data).ok()
     }
 
+    /// Get the number of times a knot, stitch or labeled choice or gather has been visited
+    /// from its full address.
+    ///
+    /// Addresses are written like for [`move_to_address`][crate::story::Story::move_to_address()]:
+    /// `knot`, `knot.stitch`, `knot.label` or `knot.stitch.label`. Labeled choices are
+    /// visited every time they are selected.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// -> harbour
+    ///
+    /// === harbour ===
+    /// = pier
+    /// - (gulls) Gulls circled above.
+    /// *   (boat) [Board the boat] -> END
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(story.times_visited("harbour.pier").unwrap(), 1);
+    /// assert_eq!(story.times_visited("harbour.pier.gulls").unwrap(), 1);
+    /// assert_eq!(story.times_visited("harbour.pier.boat").unwrap(), 0);
+    ///
+    /// assert!(story.times_visited("harbour.lighthouse").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the knot or
+    ///     stitch does not exist in the story.
+    /// *   [`InvalidLabel`][crate::error::InklingError::InvalidLabel]: if the label does
+    ///     not exist in its stitch.
+    pub fn times_visited(&self, address: &str) -> Result<u32, InklingError> {
+        let address = self.validate_full_address(address)?;
+
+        get_num_visited(&address, &self.data).map_err(InklingError::from)
+    }
+
+    /// Get whether a knot, stitch or labeled choice or gather has been visited from its
+    /// full address.
+    ///
+    /// See [`times_visited`][crate::story::Story::times_visited()] for how addresses are
+    /// written.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// === harbour ===
+    /// The boats rocked in the swell.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// assert!(!story.has_visited("harbour").unwrap());
+    ///
+    /// story.move_to_address("harbour", true).unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert!(story.has_visited("harbour").unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the knot or
+    ///     stitch does not exist in the story.
+    /// *   [`InvalidLabel`][crate::error::InklingError::InvalidLabel]: if the label does
+    ///     not exist in its stitch.
+    pub fn has_visited(&self, address: &str) -> Result<bool, InklingError> {
+        self.times_visited(address)
+            .map(|num_visited| num_visited > 0)
+    }
+
+    /// Set the number of times a knot or stitch has been visited so far.
+    ///
+    /// Conditions and text which read the visit count use the new number, and later visits
+    /// count up from it. The turn that the knot or stitch was last visited is not changed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location};
+    /// let content = "\
+    /// === depths ===
+    /// {depths > 3: You know these tunnels well.}
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let location = Location::from("depths");
+    ///
+    /// story.set_num_visited(&location, 3).unwrap();
+    /// story.move_to(&location).unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "You know these tunnels well.\n");
+    /// assert_eq!(story.get_num_visited(&location), Some(4));
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the given
+    ///     location does not exist in the story.
+    pub fn set_num_visited(
+        &mut self,
+        location: &Location,
+        num_visited: u32,
+    ) -> Result<(), InklingError> {
+        let address = Address::from_location(location, &self.knots).map_err(|_| {
+            InklingError::InvalidAddress {
+                location: location.clone(),
+            }
+        })?;
+
+        set_num_visited(&address, &mut self.data, num_visited)?;
+
+        Ok(())
+    }
+
     /// Retrieve the global tags associated with the story.
     ///
     /// # Example
@@ -401,59 +1402,517 @@ impl Story {
         self.tags.clone()
     }
 
-    /// Retrieve the value of a global variable.
+    /// Get the global tags of the story which are written as `key: value` pairs, by their key.
     ///
-    /// Returns `None` if no variable with the given name exists in the `Story`.
+    /// Tags are parsed with [`Tag::parse_key_value`][crate::story::Tag::parse_key_value()].
+    /// Tags which are not `key: value` pairs are left out. If several tags have the same key
+    /// the last one is used.
     ///
     /// # Examples
     /// ```
-    /// # use inkling::{read_story_from_string, Variable};
+    /// # use inkling::read_story_from_string;
     /// let content = "\
-    /// VAR books_in_library = 3
-    /// VAR title = \"A Momentuous Spectacle\"
+    /// ## title: Sanshirō
+    /// ## author: Natsume Sōseki
+    /// ## serialized
     /// ";
     ///
     /// let story = read_story_from_string(content).unwrap();
+    /// let tags = story.get_story_tag_map();
     ///
-    /// assert_eq!(story.get_variable("books_in_library").unwrap(), Variable::Int(3));
+    /// assert_eq!(tags.len(), 2);
+    /// assert_eq!(&tags["title"], "Sanshirō");
+    /// assert_eq!(&tags["author"], "Natsume Sōseki");
     /// ```
-    pub fn get_variable(&self, name: &str) -> Option<Variable> {
-        self.data
-            .variables
-            .get(name)
-            .map(|variable_info| variable_info.variable.clone())
+    pub fn get_story_tag_map(&self) -> HashMap<String, String> {
+        self.tags
+            .iter()
+            .filter_map(|tag| Tag::parse_key_value(tag))
+            .map(|tag| (tag.key, tag.value))
+            .collect()
     }
 
-    /// Set the value of an existing global variable.
-    ///
-    /// New variables cannot be created using this method. They have to be defined in the Ink
-    /// script file. Constant variables cannot be modified. An error is returned if the given
-    /// name corresponds to a constant variable.
+    /// Get the number of turns that have passed in the story.
     ///
-    /// The assignment is type checked: a variable of integer type cannot be changed to
-    /// contain a decimal number, a string, or anything else. An error will be returned
-    /// if this is attempted.
+    /// A turn passes every time that a choice is made.
+    pub fn get_turn(&self) -> u32 {
+        self.data.turn
+    }
+
+    /// Get how the story ended, if it reached its end the last time it was resumed.
     ///
-    /// Note that this method accepts values which implement `Into<Variable>`. This is implemented
-    /// for integers, floating point numbers, booleans and string representations, so those
-    /// can be used without a lot of typing.
+    /// Moving the story to a new location with [`move_to`][crate::story::Story::move_to()]
+    /// clears the ending.
     ///
-    /// # Examples
-    /// ## Fully specifying variable type
+    /// # Example
     /// ```
-    /// # use inkling::{read_story_from_string, Variable};
+    /// # use inkling::{read_story_from_string, EndingKind};
     /// let content = "\
-    /// VAR hunted_by_police = false
-    /// VAR num_passengers = 0
-    /// CONST price_of_ticket = 7.50
+    /// *   [Board the train] -> END
+    /// *   [Stay on the platform]
+    ///     The train left without her.
     /// ";
     ///
     /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
     ///
-    /// assert!(story.set_variable("num_passengers", Variable::Int(5)).is_ok());
-    /// ```
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert_eq!(story.get_ending(), None);
     ///
-    /// ## Inferring type from input
+    /// story.make_choice(1).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(story.get_ending(), Some(EndingKind::DeadEnd));
+    /// ```
+    pub fn get_ending(&self) -> Option<EndingKind> {
+        self.data.ending
+    }
+
+    /// Begin recording a journal of every knot and stitch that is entered.
+    ///
+    /// Does nothing if the journal is already being recorded. See
+    /// [`VisitJournal`][crate::VisitJournal] for how to query it.
+    pub fn enable_visit_journal(&mut self) {
+        self.data.visit_journal.get_or_insert_with(Default::default);
+    }
+
+    /// Get the journal of entered knots and stitches, if it is being recorded.
+    pub fn get_visit_journal(&self) -> Option<&VisitJournal> {
+        self.data.visit_journal.as_ref()
+    }
+
+    /// Get the path of knots and stitches which the story has entered, in order, if the
+    /// [visit journal][crate::story::Story::enable_visit_journal()] is being recorded.
+    ///
+    /// Locations which were entered several times are included once for every visit.
+    /// Use [`get_visit_journal`][crate::story::Story::get_visit_journal()] to also get
+    /// the turn of every visit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location};
+    /// let content = "\
+    /// -> square
+    ///
+    /// === square ===
+    /// *   [Enter the chapel] -> chapel
+    /// *   [Leave] -> END
+    ///
+    /// === chapel ===
+    /// = nave
+    /// +   [Leave] -> square
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.enable_visit_journal();
+    ///
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(
+    ///     story.path_history().unwrap(),
+    ///     vec![
+    ///         Location::from("square"),
+    ///         Location::with_stitch("chapel", "nave"),
+    ///         Location::from("square"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn path_history(&self) -> Option<Vec<Location>> {
+        self.data.visit_journal.as_ref().map(|journal| {
+            journal
+                .entries()
+                .iter()
+                .map(|entry| entry.location.clone())
+                .collect()
+        })
+    }
+
+    /// Begin recording every change to the given variables.
+    ///
+    /// Can be called again to record changes to more variables. Names do not have to be
+    /// declared in the story, since the host may create them. See
+    /// [`VariableHistory`][crate::VariableHistory] for how to query it.
+    pub fn enable_variable_history(&mut self, names: &[&str]) {
+        let history = self
+            .data
+            .variable_history
+            .get_or_insert_with(Default::default);
+
+        for name in names {
+            history.watch(name);
+        }
+    }
+
+    /// Get the history of changes to variables, if it is being recorded.
+    pub fn get_variable_history(&self) -> Option<&VariableHistory> {
+        self.data.variable_history.as_ref()
+    }
+
+    /// Seed the random number generator which is used for shuffled content.
+    ///
+    /// Stories are otherwise seeded from entropy when they are read, so two playthroughs
+    /// with the same choices can produce different text. Does nothing if the `random`
+    /// feature is not enabled, since shuffles then always have the same order.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.data.rng = StoryRng::with_seed(seed);
+    }
+
+    /// Set whether lines are written with every variant of their text for proofreading.
+    ///
+    /// In proofreading mode, the text of every line and choice contains all variants of its
+    /// alternatives and conditional content instead of the variants that were selected.
+    /// Conditional content is marked up as `{if condition: text | else: other}` and
+    /// alternatives as `{cycle: one | two}`, with the kind of alternative. The story is
+    /// otherwise followed as usual: alternatives advance, conditions select which diverts
+    /// are followed and variables are used for the values of expressions.
+    ///
+    /// The mode is not saved with the story state. See
+    /// [`export_story_to_markdown`][crate::export_story_to_markdown()] for a document
+    /// with all content of the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR visits = 0
+    /// {visits > 0: Welcome back|Welcome}, said the {~porter|guard}.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.set_proofreading(true);
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(
+    ///     line_buffer[0].text,
+    ///     "{if visits > 0: Welcome back | else: Welcome}, said the {shuffle: porter | guard}.\n"
+    /// );
+    /// ```
+    pub fn set_proofreading(&mut self, enabled: bool) {
+        self.data.proofreading = enabled;
+    }
+
+    /// Set the number of lines after which a call to resume the story returns early.
+    ///
+    /// Content which produces a very large number of lines before reaching a choice, such as
+    /// a knot which diverts to itself many times, otherwise has to be processed in full before
+    /// any of it can be shown. With a limit set, the story returns
+    /// [`Prompt::Continue`][crate::Prompt::Continue] at the first divert after at least
+    /// `max_lines` lines have been produced. Resuming the story again continues from the
+    /// target of the divert, so the host can show the lines as they are produced.
+    ///
+    /// Lines are only split at diverts, so a call can produce more lines than the limit.
+    /// The story does not return early if the last line is glued to the next with `<>`.
+    /// Lines which end in a divert are glued to the text that follows it, but the story
+    /// can return after them. The last line then keeps its
+    /// [`glue_end`][crate::Line::glue_end] and should be displayed together with the first
+    /// line of the next call. Set the limit to `None` to always resume until the next
+    /// choice or the end of the story, which is the default. The limit is not saved with
+    /// the story state.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Prompt};
+    /// let content = "\
+    /// -> countdown
+    ///
+    /// === countdown ===
+    /// {countdown < 5: Tick. -> countdown|Boom! -> END}
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.set_max_lines_per_resume(Some(2));
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// let mut num_calls = 1;
+    ///
+    /// while let Prompt::Continue = story.resume(&mut line_buffer).unwrap() {
+    ///     // Show the lines so far to the player
+    ///     num_calls += 1;
+    /// }
+    ///
+    /// assert_eq!(line_buffer.len(), 5);
+    /// assert_eq!(num_calls, 3);
+    /// ```
+    pub fn set_max_lines_per_resume(&mut self, max_lines: Option<usize>) {
+        self.data.max_lines_per_resume = max_lines;
+    }
+
+    /// Begin auditing the story for content which makes its output nondeterministic.
+    ///
+    /// While the audit is enabled, every use of a random number generator which was not
+    /// seeded and every read of a variable from the host's
+    /// [`VariableStore`][crate::VariableStore] is recorded. Replays of the same choices
+    /// with such content can differ from the original playthrough. The output of a story
+    /// does not otherwise depend on anything but its state and the choices made.
+    ///
+    /// The audit is not saved with the story state. Does nothing if the audit is
+    /// already enabled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR weather = \"rain\"
+    /// The forecast promised {weather}.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.enable_determinism_audit();
+    ///
+    /// story.resume(&mut Vec::new()).unwrap();
+    ///
+    /// assert!(story.get_nondeterminism().is_empty());
+    /// ```
+    pub fn enable_determinism_audit(&mut self) {
+        self.data
+            .determinism_audit
+            .get_or_insert_with(Default::default);
+    }
+
+    /// Get every source of nondeterminism found since the audit was enabled.
+    ///
+    /// Every kind of source is returned once for every line that it was found on, in the order
+    /// that they were first found. Returns an empty list if the audit is not enabled.
+    pub fn get_nondeterminism(&self) -> Vec<Nondeterminism> {
+        self.data
+            .determinism_audit
+            .as_ref()
+            .map(|audit| audit.get_found())
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "debug")]
+    /// Begin recording a timeline of the full story state at every prompt.
+    ///
+    /// Every call which resumes the story records the lines that it produced, the prompt that
+    /// it returned and the full state of the story, up to the `capacity` most recent prompts.
+    /// The story can then be stepped back and forth between the recorded prompts with
+    /// [`step_back`][crate::story::Story::step_back()],
+    /// [`step_forward`][crate::story::Story::step_forward()] and
+    /// [`step_to`][crate::story::Story::step_to()], and the state at every prompt inspected
+    /// through [`get_timeline`][crate::story::Story::get_timeline()].
+    ///
+    /// Making a choice and resuming the story after stepping back discards all later
+    /// prompts from the timeline. Every entry holds a copy of the whole story, which makes
+    /// the timeline a tool for debugging rather than for saving progress. It requires the
+    /// `debug` feature and is not saved with the story state. Does nothing if the timeline
+    /// is already being recorded.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR courage = 0
+    /// The cave mouth yawned before them.
+    /// *   [Enter] ~ courage = 1
+    ///     They stepped inside.
+    /// *   [Leave] They turned back.
+    /// -   The wind picked up.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.enable_timeline(10);
+    ///
+    /// story.resume(&mut Vec::new()).unwrap();
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut Vec::new()).unwrap();
+    ///
+    /// let entry = story.step_back().unwrap();
+    /// assert_eq!(&entry.lines[0].text, "The cave mouth yawned before them.\n");
+    ///
+    /// story.make_choice(1).unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "They turned back.\n");
+    /// assert_eq!(story.get_timeline().unwrap().len(), 2);
+    /// ```
+    pub fn enable_timeline(&mut self, capacity: usize) {
+        self.timeline
+            .get_or_insert_with(|| Timeline::with_capacity(capacity));
+    }
+
+    #[cfg(feature = "debug")]
+    /// Get the timeline of recorded prompts, if it is being recorded.
+    pub fn get_timeline(&self) -> Option<&Timeline> {
+        self.timeline.as_ref()
+    }
+
+    #[cfg(feature = "debug")]
+    /// Restore the story to its state at the previous prompt in the timeline.
+    ///
+    /// Returns the entry of the prompt, or `None` if the story is at the oldest recorded
+    /// prompt or the timeline is not enabled. After stepping, resuming the story returns
+    /// the same prompt again.
+    pub fn step_back(&mut self) -> Option<&TimelineEntry> {
+        let index = self.get_timeline()?.position()?.checked_sub(1)?;
+        self.step_to(index)
+    }
+
+    #[cfg(feature = "debug")]
+    /// Restore the story to its state at the next prompt in the timeline.
+    ///
+    /// Returns the entry of the prompt, or `None` if the story is at the newest recorded
+    /// prompt or the timeline is not enabled.
+    pub fn step_forward(&mut self) -> Option<&TimelineEntry> {
+        let index = self.get_timeline()?.position()? + 1;
+        self.step_to(index)
+    }
+
+    #[cfg(feature = "debug")]
+    /// Restore the story to its state at the prompt with the given index in the timeline.
+    ///
+    /// Index 0 is the oldest recorded prompt. Returns the entry of the prompt, or `None`
+    /// if there is no entry with the index or the timeline is not enabled.
+    pub fn step_to(&mut self, index: usize) -> Option<&TimelineEntry> {
+        let state = self.timeline.as_mut()?.seek(index)?.clone();
+
+        self.current_address = state.current_address;
+        self.knots = state.knots;
+        self.data = state.data;
+        self.last_choices = state.last_choices;
+        self.selected_choice = state.selected_choice;
+        self.stepped_lines.clear();
+
+        self.get_timeline().and_then(Timeline::current)
+    }
+
+    /// Get the record of which outcomes conditions have evaluated to as the story was followed.
+    ///
+    /// See [`ConditionCoverage`][crate::ConditionCoverage] for how to find conditions
+    /// which never change. The record is not saved along with the story state.
+    pub fn get_condition_coverage(&self) -> &ConditionCoverage {
+        &self.data.condition_coverage
+    }
+
+    /// Retrieve the value of a global variable.
+    ///
+    /// Returns `None` if no variable with the given name exists in the `Story`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// VAR books_in_library = 3
+    /// VAR title = \"A Momentuous Spectacle\"
+    /// ";
+    ///
+    /// let story = read_story_from_string(content).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("books_in_library").unwrap(), Variable::Int(3));
+    /// ```
+    pub fn get_variable(&self, name: &str) -> Option<Variable> {
+        self.data.get_variable(name)
+    }
+
+    /// Get the value of a global variable converted into a type.
+    ///
+    /// Variables can be converted into `i32`, `f32`, `bool`, `String` and `List` if they
+    /// are of that type. `Int` variables can also be converted into `f32`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR books_in_library = 3
+    /// VAR is_open = true
+    /// ";
+    ///
+    /// let story = read_story_from_string(content).unwrap();
+    ///
+    /// assert_eq!(story.get_variable_as::<i32>("books_in_library").unwrap(), 3);
+    /// assert!(story.get_variable_as::<bool>("is_open").unwrap());
+    /// assert!(story.get_variable_as::<String>("is_open").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
+    ///     does not refer to a global variable that exists in the story.
+    /// *   [`VariableError`][crate::error::InklingError::VariableError]: if the variable
+    ///     cannot be converted into the type.
+    pub fn get_variable_as<T>(&self, name: &str) -> Result<T, InklingError>
+    where
+        T: TryFrom<Variable, Error = VariableError>,
+    {
+        let variable = self
+            .get_variable(name)
+            .ok_or_else(|| InklingError::InvalidVariable {
+                name: name.to_string(),
+            })?;
+
+        T::try_from(variable).map_err(InklingError::from)
+    }
+
+    /// Iterate over the names and values of all global variables, in arbitrary order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// VAR books_in_library = 3
+    /// CONST title = \"A Momentuous Spectacle\"
+    /// ";
+    ///
+    /// let story = read_story_from_string(content).unwrap();
+    ///
+    /// let mut variables = story.variables().collect::<Vec<_>>();
+    /// variables.sort_by_key(|(name, _)| *name);
+    ///
+    /// assert_eq!(variables[0], ("books_in_library", Variable::Int(3)));
+    /// assert_eq!(story.num_variables(), 2);
+    /// ```
+    pub fn variables(&self) -> impl Iterator<Item = (&str, Variable)> + '_ {
+        self.data.variables.keys().filter_map(move |name| {
+            self.data
+                .get_variable(name)
+                .map(|variable| (name.as_str(), variable))
+        })
+    }
+
+    /// Get the number of global variables in the story, including constants.
+    pub fn num_variables(&self) -> usize {
+        self.data.variables.len()
+    }
+
+    /// Set the value of an existing global variable.
+    ///
+    /// New variables cannot be created using this method. They have to be defined in the Ink
+    /// script file. Constant variables cannot be modified. An error is returned if the given
+    /// name corresponds to a constant variable.
+    ///
+    /// The assignment is type checked: a variable of integer type cannot be changed to
+    /// contain a decimal number, a string, or anything else. An error will be returned
+    /// if this is attempted.
+    ///
+    /// Note that this method accepts values which implement `Into<Variable>`. This is implemented
+    /// for integers, floating point numbers, booleans and string representations, so those
+    /// can be used without a lot of typing.
+    ///
+    /// # Examples
+    /// ## Fully specifying variable type
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// VAR hunted_by_police = false
+    /// VAR num_passengers = 0
+    /// CONST price_of_ticket = 7.50
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// assert!(story.set_variable("num_passengers", Variable::Int(5)).is_ok());
+    /// ```
+    ///
+    /// ## Inferring type from input
     /// ```
     /// # use inkling::{read_story_from_string, Variable};
     /// # let content = "\
@@ -494,7 +1953,9 @@ impl Story {
     /// *   [`AssignedToConst`][crate::error::InklingError::AssignedToConst]: if the name
     ///     refers to a constant variable.
     /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
-    ///     does not refer to a global variable that exists in the story.
+    ///     does not refer to a global variable that exists in the story, unless the
+    ///     [policy for undeclared variables][crate::story::Story::set_undeclared_variable_policy()]
+    ///     is to create them.
     /// *   [`VariableError`][crate::error::InklingError::VariableError]: if
     ///     the existing variable has a different type to the input variable.
     pub fn set_variable<T: Into<Variable>>(
@@ -502,808 +1963,2699 @@ impl Story {
         name: &str,
         value: T,
     ) -> Result<(), InklingError> {
-        self.data
-            .variables
-            .get_mut(name)
-            .ok_or(InklingError::InvalidVariable {
-                name: name.to_string(),
-            })
-            .and_then(|variable_info| variable_info.assign(value.into(), name))
-    }
-
-    /// Wrapper for calling `follow_story` with a prepared internal buffer.
-    ///
-    /// Updates the stack to the last visited address and the last presented set of choices
-    /// if encountered.
-    fn follow_story_wrapper(
-        &mut self,
-        selection: Option<usize>,
-        line_buffer: &mut LineBuffer,
-    ) -> Result<Prompt, InklingError> {
-        let mut internal_buffer = Vec::new();
-
-        let (result, last_address) = follow_story(
-            &self.current_address,
-            &mut internal_buffer,
-            selection,
-            &mut self.knots,
-            &mut self.data,
-        )?;
-
-        process_buffer(line_buffer, internal_buffer);
-
-        self.update_last_stack(&last_address);
+        let is_watched = self
+            .data
+            .variable_history
+            .as_ref()
+            .map(|history| history.is_watched(name))
+            .unwrap_or(false)
+            || self.data.variable_observers.is_observed(name);
 
-        match result {
-            Prompt::Choice(choices) => {
-                self.last_choices.replace(choices.clone());
+        let old = if is_watched {
+            self.peek_variable(name)
+        } else {
+            None
+        };
 
-                Ok(Prompt::Choice(choices))
+        if !self.data.variables.contains_key(name) {
+            self.create_undeclared_variable(name, value.into())?;
+        } else {
+            let variable_info = self.data.variables.get_mut(name).unwrap();
+
+            match self.data.external_variables.get(name) {
+                Some(mut variable) if !variable_info.is_const => {
+                    variable.assign(value)?;
+                    self.data.external_variables.set(name, variable);
+                }
+                _ => variable_info.assign(value.into(), name)?,
             }
-            other => Ok(other),
         }
-    }
 
-    /// Set the given address as active on the stack.
+        if is_watched {
+            self.record_host_variable_change(name, old);
+        }
+
+        Ok(())
+    }
+
+    /// Set the values of several global variables at once, like a snapshot of
+    /// [`variables`][crate::story::Story::variables()] from an earlier state.
+    ///
+    /// Every value is checked before any is set, so if one of them cannot be set the story
+    /// is left unchanged. Constants are skipped if their value is the same as in the story,
+    /// which lets snapshots that include them be set. Values are otherwise set like with
+    /// [`set_variable`][crate::story::Story::set_variable()], in the order that they are given.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// # use std::collections::HashMap;
+    /// let content = "\
+    /// VAR coins = 3
+    /// VAR location = \"harbour\"
+    /// CONST max_coins = 99
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let snapshot = story
+    ///     .variables()
+    ///     .map(|(name, variable)| (name.to_string(), variable))
+    ///     .collect::<HashMap<_, _>>();
+    ///
+    /// story.set_variable("coins", 10).unwrap();
+    /// story.set_variables(snapshot).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("coins"), Some(Variable::Int(3)));
+    /// ```
+    ///
+    /// # Errors
+    /// Yields the same errors as [`set_variable`][crate::story::Story::set_variable()] for
+    /// the first value which cannot be set.
+    pub fn set_variables<I, S>(&mut self, variables: I) -> Result<(), InklingError>
+    where
+        I: IntoIterator<Item = (S, Variable)>,
+        S: AsRef<str>,
+    {
+        let mut assignments = Vec::new();
+
+        for (name, value) in variables {
+            let name = name.as_ref();
+
+            match self.data.variables.get(name) {
+                Some(variable_info) => {
+                    let mut current = self.peek_variable(name).unwrap();
+
+                    if variable_info.is_const {
+                        if current != value {
+                            variable_info.clone().assign(value, name)?;
+                        }
+
+                        continue;
+                    }
+
+                    current.assign(value.clone())?;
+                }
+                None if self.undeclared_variable_policy == UndeclaredVariablePolicy::Error => {
+                    return Err(InklingError::InvalidVariable {
+                        name: name.to_string(),
+                    });
+                }
+                None => (),
+            }
+
+            assignments.push((name.to_string(), value));
+        }
+
+        for (name, value) in assignments {
+            self.set_variable(&name, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the value of a variable without recording it as a read by the story.
+    fn peek_variable(&self, name: &str) -> Option<Variable> {
+        self.data.variables.get(name).map(|variable_info| {
+            self.data
+                .external_variables
+                .get(name)
+                .unwrap_or_else(|| variable_info.variable.clone())
+        })
+    }
+
+    /// Record that the host changed a variable in the history of changes and give the change
+    /// to the observers of the variable if its value changed.
+    fn record_host_variable_change(&mut self, name: &str, old: Option<Variable>) {
+        if let Some(new) = self.peek_variable(name) {
+            let change = VariableChange {
+                name: name.to_string(),
+                old,
+                new,
+                location: self.get_current_location(),
+                origin: ChangeOrigin::Host,
+                turn: self.data.turn,
+            };
+
+            if change.old.as_ref() != Some(&change.new) {
+                self.data.variable_observers.notify(&change);
+            }
+
+            if let Some(history) = self.data.variable_history.as_mut() {
+                history.record(change);
+            }
+        }
+    }
+
+    /// Call a function whenever the value of a global variable changes.
+    ///
+    /// The function is called with the change right after it is made, both when a line in
+    /// the story assigns to the variable and when the host sets it with
+    /// [`set_variable`][crate::story::Story::set_variable()]. Assignments which leave the
+    /// variable with the value it had are skipped. A variable can be observed by several
+    /// functions, which are called in the order that they were added.
+    ///
+    /// Observers are not saved with the story state and must be added again after the story
+    /// is loaded.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, ChangeOrigin, Variable};
+    /// # use std::sync::{Arc, Mutex};
+    /// let content = "\
+    /// VAR gold = 0
+    /// You find a purse of ten coins.
+    /// ~ gold = 10
+    /// ";
+    ///
+    /// let meter = Arc::new(Mutex::new(Vec::new()));
+    /// let observed = meter.clone();
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// story
+    ///     .observe_variable("gold", move |change| {
+    ///         observed.lock().unwrap().push((change.new.clone(), change.origin.clone()))
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// story.set_variable("gold", 4).unwrap();
+    ///
+    /// let meter = meter.lock().unwrap();
+    ///
+    /// assert_eq!(meter.len(), 2);
+    /// assert_eq!(meter[0].0, Variable::Int(10));
+    /// assert_eq!(meter[1], (Variable::Int(4), ChangeOrigin::Host));
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
+    ///     does not refer to a global variable that exists in the story.
+    pub fn observe_variable<F>(&mut self, name: &str, observer: F) -> Result<(), InklingError>
+    where
+        F: Fn(&VariableChange) + Send + Sync + 'static,
+    {
+        if !self.data.variables.contains_key(name) {
+            return Err(InklingError::InvalidVariable {
+                name: name.to_string(),
+            });
+        }
+
+        self.data.variable_observers.add(name, observer);
+
+        Ok(())
+    }
+
+    /// Set how [`set_variable`][crate::story::Story::set_variable()] handles names which
+    /// are not declared in the story.
+    ///
+    /// By default setting an undeclared variable is an error, which catches misspelled names
+    /// in the host. Stories which are given extra variables by the host can instead create
+    /// them, with or without a warning in the [log][crate::story::Story::log]. Conditions and
+    /// text in the story can only use declared variables, so created variables can only be
+    /// read by the host. The policy is not saved with the story state.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, UndeclaredVariablePolicy, Variable};
+    /// let content = "\
+    /// VAR score = 0
+    /// You have {score} points.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// assert!(story.set_variable("scroe", 10).is_err());
+    ///
+    /// story.set_undeclared_variable_policy(UndeclaredVariablePolicy::CreateWithWarning);
+    /// story.set_variable("scroe", 10).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("scroe"), Some(Variable::Int(10)));
+    /// # #[cfg(feature = "logging")]
+    /// assert_eq!(story.log.warnings.len(), 1);
+    /// ```
+    pub fn set_undeclared_variable_policy(&mut self, policy: UndeclaredVariablePolicy) {
+        self.undeclared_variable_policy = policy;
+    }
+
+    /// Create a variable which was set by the host but is not declared, if the policy allows it.
+    ///
+    /// Created variables are given the origin of the last processed line, if any.
+    fn create_undeclared_variable(
+        &mut self,
+        name: &str,
+        variable: Variable,
+    ) -> Result<(), InklingError> {
+        if self.undeclared_variable_policy == UndeclaredVariablePolicy::Error {
+            return Err(InklingError::InvalidVariable {
+                name: name.to_string(),
+            });
+        }
+
+        let meta_data = self
+            .get_last_processed_line()
+            .cloned()
+            .unwrap_or_else(|| MetaData::from(0));
+
+        if self.undeclared_variable_policy == UndeclaredVariablePolicy::CreateWithWarning {
+            self.log.add_warning(
+                Warning::CreatedUndeclaredVariable {
+                    name: name.to_string(),
+                },
+                &meta_data,
+            );
+        }
+
+        let variable_info = VariableInfo {
+            is_const: false,
+            declared: Some(variable.clone()),
+            variable,
+            meta_data,
+            transient: None,
+            is_host_constant: false,
+            list_items: None,
+        };
+
+        self.declarations
+            .insert(name.to_string(), variable_info.clone());
+        self.data.variables.insert(name.to_string(), variable_info);
+
+        Ok(())
+    }
+
+    /// Set a store which backs global variables with values owned by the host.
+    ///
+    /// Variables that the store returns a value for are read from it instead of the story,
+    /// and values set with [`set_variable`][crate::story::Story::set_variable()] are given
+    /// to it. See [`VariableStore`][crate::VariableStore] for more information.
+    ///
+    /// The store is shared with clones of the story but not saved with its state. It has
+    /// to be set again after loading a story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable, VariableStore};
+    /// # use std::sync::{Arc, Mutex};
+    /// struct Inventory {
+    ///     gold: i32,
+    /// }
+    ///
+    /// impl VariableStore for Inventory {
+    ///     fn get_variable(&self, name: &str) -> Option<Variable> {
+    ///         match name {
+    ///             "gold" => Some(Variable::Int(self.gold)),
+    ///             _ => None,
+    ///         }
+    ///     }
+    ///
+    ///     fn set_variable(&mut self, name: &str, value: Variable) {
+    ///         if let ("gold", Variable::Int(gold)) = (name, value) {
+    ///             self.gold = gold;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let content = "\
+    /// VAR gold = 0
+    /// You have {gold} gold coins.
+    /// ";
+    ///
+    /// let inventory = Arc::new(Mutex::new(Inventory { gold: 12 }));
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// story.set_variable_store(inventory.clone());
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "You have 12 gold coins.\n");
+    ///
+    /// story.set_variable("gold", 20).unwrap();
+    /// assert_eq!(inventory.lock().unwrap().gold, 20);
+    /// ```
+    pub fn set_variable_store(&mut self, store: SharedVariableStore) {
+        self.data.external_variables = ExternalVariables::new(store);
+    }
+
+    /// Bind a function to an `EXTERNAL` function of the story, which calls it from its
+    /// text and logic.
+    ///
+    /// The function is called with the evaluated arguments of the call. It returns the value
+    /// of the call, or `None` if it has no value, which is an error if the call is used
+    /// in an expression. Binding a function to a name replaces the function which was bound
+    /// to it before. Calling an external function which has not been bound calls the function
+    /// with the same name in the story as a fallback, or yields an error if there is none.
+    ///
+    /// Bound functions are not saved with the story state and must be bound again after
+    /// the story is loaded.
+    ///
+    /// # Errors
+    /// *   [`InvalidFunction`][crate::error::InklingError::InvalidFunction]:
+    ///     if no external function with the name is declared in the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// EXTERNAL roll_die(sides)
+    /// You rolled a {roll_die(6)}.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// story
+    ///     .bind_external_function("roll_die", |_: &[Variable]| Some(Variable::Int(4)))
+    ///     .unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "You rolled a 4.\n");
+    /// ```
+    pub fn bind_external_function<F>(&mut self, name: &str, function: F) -> Result<(), InklingError>
+    where
+        F: Fn(&[Variable]) -> Option<Variable> + Send + Sync + 'static,
+    {
+        if !self.data.external_functions.is_declared(name) {
+            return Err(InklingError::InvalidFunction {
+                name: name.to_string(),
+            });
+        }
+
+        self.data.external_functions.bind(name, function);
+
+        Ok(())
+    }
+
+    /// Mark a global variable as transient, which excludes its value from saved story states.
+    ///
+    /// Transient variables are meant for values which belong to the current session
+    /// rather than to the player, like debug modes or platform flags. When the story
+    /// is serialized the variable is saved with the value that it has when it is marked,
+    /// so a loaded story always starts with that value. The variable remains transient
+    /// in the loaded story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// VAR debug_mode = false
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// story.mark_variable_as_transient("debug_mode").unwrap();
+    /// story.set_variable("debug_mode", true).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("debug_mode"), Some(Variable::Bool(true)));
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
+    ///     does not refer to a global variable that exists in the story.
+    pub fn mark_variable_as_transient(&mut self, name: &str) -> Result<(), InklingError> {
+        let variable = self
+            .data
+            .variables
+            .get_mut(name)
+            .ok_or(InklingError::InvalidVariable {
+                name: name.to_string(),
+            })
+            .map(|variable_info| {
+                variable_info
+                    .transient
+                    .replace(variable_info.variable.clone());
+
+                variable_info.variable.clone()
+            })?;
+
+        if let Some(declaration) = self.declarations.get_mut(name) {
+            declaration.transient.replace(variable);
+        }
+
+        Ok(())
+    }
+
+    /// Get a read-only copy of the current location, variables and visit counts.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location, Variable};
+    /// let content = "\
+    /// VAR coins = 3
+    /// -> market
+    ///
+    /// === market ===
+    /// The stalls were already closing.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// let snapshot = story.snapshot();
+    ///
+    /// assert_eq!(snapshot.location, Location::from("market"));
+    /// assert_eq!(snapshot.variables["coins"], Variable::Int(3));
+    /// assert_eq!(snapshot.visit_counts[&Location::from("market")], 1);
+    /// ```
+    pub fn snapshot(&self) -> StorySnapshot {
+        let variables = self
+            .data
+            .variables
+            .keys()
+            .filter_map(|name| {
+                self.data
+                    .get_variable(name)
+                    .map(|variable| (name.clone(), variable))
+            })
+            .collect();
+
+        StorySnapshot {
+            location: self.get_current_location(),
+            variables,
+            visit_counts: self.get_locations_with_num_visited().into_iter().collect(),
+        }
+    }
+
+    /// Get a human readable description of the current state of the story, for debugging.
+    ///
+    /// Contains the current location, the choices waiting for a selection (or the branch
+    /// that was selected to resume the story with), all variables,
+    /// the visit counts of every visited knot and stitch and the seed of the random number
+    /// generator. The format is meant to be read by people and may change between versions:
+    /// do not parse it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR lanterns = 2
+    ///
+    /// The tunnel forked ahead of them.
+    /// *   Left
+    /// *   Right
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// let state = story.debug_state_string();
+    ///
+    /// assert!(state.contains("lanterns = 2"));
+    /// assert!(state.contains("1: Right"));
+    /// ```
+    pub fn debug_state_string(&self) -> String {
+        let mut lines = vec![format!("location: {}", self.current_address.to_string())];
+
+        match &self.last_choices {
+            Some(choices) => {
+                lines.push("choices:".to_string());
+
+                for (i, choice) in choices.iter().enumerate() {
+                    lines.push(format!("    {}: {}", i, choice.text));
+                }
+            }
+            None => lines.push("choices: none".to_string()),
+        }
+
+        if let Some(index) = self.selected_choice {
+            lines.push(format!("selected branch: {}", index));
+        }
+
+        let mut variables = self.data.variables.iter().collect::<Vec<_>>();
+        variables.sort_by_key(|(name, _)| name.as_str());
+
+        lines.push("variables:".to_string());
+
+        for (name, info) in variables {
+            let marker = if info.is_const { " (const)" } else { "" };
+            let variable = self
+                .data
+                .external_variables
+                .get(name)
+                .unwrap_or_else(|| info.variable.clone());
+
+            lines.push(format!(
+                "    {} = {}{}",
+                name,
+                format_variable_for_debug(&variable),
+                marker
+            ));
+        }
+
+        let mut visited = self
+            .get_locations_with_num_visited()
+            .into_iter()
+            .filter(|(_, num_visited)| *num_visited > 0)
+            .map(|(location, num_visited)| (format_location(&location), num_visited))
+            .collect::<Vec<_>>();
+
+        visited.sort();
+
+        lines.push("visit counts:".to_string());
+
+        for (location, num_visited) in visited {
+            lines.push(format!("    {}: {}", location, num_visited));
+        }
+
+        match self.data.rng.get_seed() {
+            Some(seed) => lines.push(format!("rng seed: {}", seed)),
+            None => lines.push("rng seed: none".to_string()),
+        }
+
+        lines.join("\n")
+    }
+
+    /// Get every knot and stitch in the story with the number of times it has been visited.
+    ///
+    /// The root of the story is not included.
+    pub(crate) fn get_locations_with_num_visited(&self) -> Vec<(Location, u32)> {
+        self.knots
+            .iter()
+            .filter(|(knot, _)| knot.as_str() != ROOT_KNOT_NAME)
+            .flat_map(|(knot_name, knot)| {
+                knot.stitches.keys().map(move |stitch_name| {
+                    let num_visited = self
+                        .data
+                        .knot_visit_counts
+                        .get(knot_name)
+                        .and_then(|stitches| stitches.get(stitch_name).copied())
+                        .unwrap_or(0);
+
+                    let location = if stitch_name == ROOT_KNOT_NAME {
+                        Location::from(knot_name.as_str())
+                    } else {
+                        Location::with_stitch(knot_name, stitch_name)
+                    };
+
+                    (location, num_visited)
+                })
+            })
+            .collect()
+    }
+
+    /// Get all knots of the story.
+    pub(crate) fn get_knots(&self) -> &KnotSet {
+        &self.knots
+    }
+
+    /// Get the line which was processed last while following the story, if any.
+    pub(crate) fn get_last_processed_line(&self) -> Option<&MetaData> {
+        self.data.condition_coverage.get_current_line()
+    }
+
+    /// Wrapper for calling `follow_story` with a prepared internal buffer.
+    ///
+    /// Updates the stack to the last visited address and the last presented set of choices
+    /// if encountered.
+    fn follow_story_wrapper(
+        &mut self,
+        selection: Option<usize>,
+        item_buffer: &mut ItemBuffer,
+    ) -> Result<Prompt, InklingError> {
+        let mut internal_buffer = Vec::new();
+        self.data.ending = None;
+
+        let (result, last_address) = follow_story(
+            &self.current_address,
+            &mut internal_buffer,
+            selection,
+            &mut self.knots,
+            &mut self.data,
+        )?;
+
+        #[cfg(feature = "debug")]
+        let num_previous_items = item_buffer.len();
+
+        let is_continued = matches!(result, Prompt::Continue);
+        process_buffer_into_items(item_buffer, internal_buffer, is_continued);
+
+        self.update_last_stack(&last_address);
+
+        if let Prompt::Choice(choices) = &result {
+            self.last_choices.replace(choices.clone());
+        }
+
+        #[cfg(feature = "debug")]
+        self.record_timeline_entry(&item_buffer[num_previous_items..], &result);
+
+        Ok(result)
+    }
+
+    #[cfg(feature = "debug")]
+    /// Record the current state in the timeline along with the lines and prompt that led to it.
+    ///
+    /// Does nothing if the timeline is not enabled.
+    fn record_timeline_entry(&mut self, items: &[StoryItem], prompt: &Prompt) {
+        if self.timeline.is_none() {
+            return;
+        }
+
+        let entry = TimelineEntry {
+            lines: get_lines_from_items(items.to_vec()).collect(),
+            prompt: prompt.clone(),
+            snapshot: self.snapshot(),
+            state: TimelineState {
+                current_address: self.current_address.clone(),
+                knots: self.knots.clone(),
+                data: self.data.clone(),
+                last_choices: self.last_choices.clone(),
+                selected_choice: self.selected_choice,
+            },
+        };
+
+        if let Some(timeline) = self.timeline.as_mut() {
+            timeline.record(entry);
+        }
+    }
+
+    /// Set the given address as active on the stack.
     fn update_last_stack(&mut self, address: &Address) {
         self.current_address = address.clone();
     }
-}
+}
+
+/// Read a `Story` by parsing an input string.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_from_string, Story};
+/// let content = "\
+/// He drifted off, and when he opened his eyes the woman was still there.
+/// Now she was talking to the old man seated next to her—the farmer from two stations back.
+/// ";
+///
+/// let story: Story = read_story_from_string(content).unwrap();
+/// ```
+pub fn read_story_from_string(string: &str) -> Result<Story, ReadError> {
+    read_story_from_string_with_options(string, &ReadOptions::default())
+}
+
+/// Read a `Story` by parsing an input string with the given options.
+///
+/// See [`ReadOptions`][crate::ReadOptions] for which options are available.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_from_string_with_options, LintOptions, ReadOptions};
+/// let content = "\
+/// He drifted off, and when he opened his eyes the woman was still there.
+/// Now she was talking to the old man seated next to her—the farmer from two stations back.
+/// ";
+///
+/// let options = ReadOptions {
+///     lints: LintOptions {
+///         max_line_length: Some(80),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+///
+/// let story = read_story_from_string_with_options(content, &options).unwrap();
+/// # #[cfg(all(feature = "lints", feature = "logging"))]
+/// assert_eq!(story.log.warnings.len(), 1);
+/// ```
+pub fn read_story_from_string_with_options(
+    string: &str,
+    options: &ReadOptions,
+) -> Result<Story, ReadError> {
+    let mut log = Logger::default();
+    let (knots, data, tags) = read_and_validate_story_content(string, options, &mut log)?;
+
+    let comments = if options.preserve_comments {
+        read_comments_from_file_string(string, options.file_name.as_deref(), &options.flags)
+    } else {
+        Vec::new()
+    };
+
+    Ok(create_story(knots, data, tags, comments, log))
+}
+
+/// Read a `Story` by parsing its content line by line from a reader with the given options.
+///
+/// The story is identical to one that is read from the full content with
+/// [`read_story_from_string_with_options`][crate::read_story_from_string_with_options()],
+/// but the full content is never kept in memory. Only the lines of the knot which is currently
+/// being read are held while parsing, which keeps the memory use down when reading large
+/// stories from files.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_from_reader, ReadOptions};
+/// use std::io::Cursor;
+///
+/// let content = "\
+/// He drifted off, and when he opened his eyes the woman was still there.
+/// -> platform
+///
+/// === platform ===
+/// Now she was talking to the old man seated next to her.
+/// ";
+///
+/// let story = read_story_from_reader(Cursor::new(content), &ReadOptions::default()).unwrap();
+/// ```
+pub fn read_story_from_reader<R: BufRead>(
+    reader: R,
+    options: &ReadOptions,
+) -> Result<Story, ReadError> {
+    let mut log = Logger::default();
+    let mut comments = Vec::new();
+
+    let (knots, variables, tags) = read_story_content_from_reader(
+        reader,
+        options.file_name.as_deref(),
+        &options.directives,
+        &options.constants,
+        &options.flags,
+        Some(&mut comments).filter(|_| options.preserve_comments),
+        &mut log,
+    )?;
+
+    let (knots, data) = validate_story_content_with_data(knots, variables, options, &mut log)?;
+
+    Ok(create_story(knots, data, tags, comments, log))
+}
+
+#[cfg(feature = "includes")]
+/// Read a `Story` from a file, along with every file that it includes with `INCLUDE` lines.
+///
+/// Included files are found relative to the directory of the file which includes them.
+/// See [`read_story_with_resolver`][crate::read_story_with_resolver()] for how their content
+/// is combined. Errors and warnings print the name of the file that they occur in, which
+/// replaces the `file_name` of the options. Requires the `includes` feature.
+///
+/// # Examples
+/// ```no_run
+/// # use inkling::{read_story_from_path, ReadOptions};
+/// let story = read_story_from_path("stories/main.ink", &ReadOptions::default()).unwrap();
+/// ```
+pub fn read_story_from_path<P: AsRef<Path>>(
+    path: P,
+    options: &ReadOptions,
+) -> Result<Story, ReadError> {
+    read_story_with_resolver(&path.as_ref().to_string_lossy(), &FileResolver, options)
+}
+
+#[cfg(feature = "includes")]
+/// Read a `Story` from a file whose content and includes are read through a resolver.
+///
+/// The content of the file with the given name is read from the resolver, as is the content
+/// of every file that is included with an `INCLUDE file.ink` line. The lines of an included
+/// file before its first knot are inserted where it is included and its knots are added to
+/// the story. Every file is included at most once.
+///
+/// See [`IncludeResolver`][crate::IncludeResolver] for an example. Errors and warnings print
+/// the name of the file that they occur in. Requires the `includes` feature.
+pub fn read_story_with_resolver(
+    name: &str,
+    resolver: &dyn IncludeResolver,
+    options: &ReadOptions,
+) -> Result<Story, ReadError> {
+    let mut log = Logger::default();
+
+    let (lines, files) = read_lines_with_includes(name, resolver, &options.flags, &mut log)?;
+
+    let line_refs = lines
+        .iter()
+        .map(|(line, meta_data)| (line.as_str(), meta_data.clone()))
+        .collect();
+
+    let (knots, variables, tags) =
+        read_story_content_from_lines(line_refs, &options.directives, &options.constants)?;
+    let (knots, data) = validate_story_content_with_data(knots, variables, options, &mut log)?;
+
+    let comments = if options.preserve_comments {
+        files
+            .iter()
+            .flat_map(|(name, content)| {
+                read_comments_from_file_string(content, Some(name), &options.flags)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(create_story(knots, data, tags, comments, log))
+}
+
+/// Create a `Story` from its validated content, starting from the root knot.
+fn create_story(
+    knots: KnotSet,
+    data: FollowData,
+    tags: Vec<String>,
+    comments: Vec<Comment>,
+    log: Logger,
+) -> Story {
+    let root_address = Address::from_root_knot(ROOT_KNOT_NAME, &knots).expect(
+        "After successfully creating all knots, the root knot name that was returned from \
+         `read_knots_from_string` is not present in the set of created knots. \
+         This simply should not be possible",
+    );
+
+    Story {
+        current_address: root_address,
+        knots,
+        declarations: data.variables.clone(),
+        history: Vec::new(),
+        data,
+        tags,
+        last_choices: None,
+        selected_choice: None,
+        stepped_lines: VecDeque::new(),
+        log,
+        comments,
+        undeclared_variable_policy: UndeclaredVariablePolicy::default(),
+        #[cfg(feature = "debug")]
+        timeline: None,
+    }
+}
+
+/// Read and validate a story from an input string and return all found problems.
+///
+/// This runs the same parsing and validation as
+/// [`read_story_from_string`][crate::read_story_from_string] but does not construct
+/// a `Story`. Instead every encountered error and logged warning is returned as a separate
+/// [`Diagnostic`][crate::error::Diagnostic]. If the returned list contains no errors,
+/// the story can be read.
+///
+/// Note that validation is only performed if the story could be parsed. A story with parsing
+/// errors may contain additional problems that will be discovered once these are fixed.
+///
+/// # Examples
+/// ```
+/// # use inkling::validate_story_from_string;
+/// let content = "\
+/// === mirandas_den ===
+/// -> to_the_threshold
+/// ";
+///
+/// let diagnostics = validate_story_from_string(content);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert!(diagnostics[0].is_error());
+/// assert_eq!(diagnostics[0].meta_data.as_ref().unwrap().line(), 2);
+/// ```
+pub fn validate_story_from_string(string: &str) -> Vec<Diagnostic> {
+    validate_story_from_string_with_options(string, &ReadOptions::default())
+}
+
+/// Read and validate a story from an input string with the given options and return all
+/// found problems.
+///
+/// See [`validate_story_from_string`][crate::validate_story_from_string] for more information.
+pub fn validate_story_from_string_with_options(
+    string: &str,
+    options: &ReadOptions,
+) -> Vec<Diagnostic> {
+    let mut log = Logger::default();
+
+    let mut diagnostics = match read_and_validate_story_content(string, options, &mut log) {
+        Ok(..) => Vec::new(),
+        Err(error) => get_diagnostics_from_read_error(&error),
+    };
+
+    diagnostics.extend(get_diagnostics_from_log(&log));
+
+    diagnostics
+}
+
+/// Parse and validate the content of a story and return it along with its initial data.
+fn read_and_validate_story_content(
+    string: &str,
+    options: &ReadOptions,
+    log: &mut Logger,
+) -> Result<(KnotSet, FollowData, Vec<String>), ReadError> {
+    let (knots, variables, tags) = read_story_content_from_file_string(
+        string,
+        options.file_name.as_deref(),
+        &options.directives,
+        &options.constants,
+        &options.flags,
+        log,
+    )?;
+
+    let (knots, data) = validate_story_content_with_data(knots, variables, options, log)?;
+
+    Ok((knots, data, tags))
+}
+
+/// Create the initial data for parsed story content and validate the content against it.
+fn validate_story_content_with_data(
+    mut knots: KnotSet,
+    mut variables: VariableSet,
+    options: &ReadOptions,
+    log: &mut Logger,
+) -> Result<(KnotSet, FollowData), ReadError> {
+    set_variable_defaults(&mut variables, &options.variable_defaults)?;
+
+    let mut data = FollowData {
+        knot_visit_counts: get_empty_knot_counts(&knots),
+        label_visit_counts: HashMap::new(),
+        variables,
+        temporary_variables: HashMap::new(),
+        rng: StoryRng::new(),
+        condition_coverage: ConditionCoverage::default(),
+        external_variables: ExternalVariables::default(),
+        turn: 0,
+        last_visited_turns: HashMap::new(),
+        visit_journal: None,
+        variable_history: None,
+        variable_observers: VariableObservers::default(),
+        determinism_audit: None,
+        ending: None,
+        proofreading: false,
+        max_lines_per_resume: None,
+        max_diverts_per_resume: None,
+        functions: Arc::new(FunctionSet::new()),
+        call_depth: 0,
+        divert_arguments: Vec::new(),
+        external_functions: get_external_functions(&knots, &options.external_functions),
+        thread_choices: Vec::new(),
+        choice_origins: Vec::new(),
+        uncounted_visit: None,
+    };
+
+    let mut error = match validate_story_content(&mut knots, &data, log) {
+        Ok(()) => ValidationError::new(),
+        Err(error) => error,
+    };
+
+    if options.check_external_functions {
+        error
+            .invalid_address_errors
+            .extend(check_external_functions(&knots, &data.external_functions));
+    }
+
+    if !error.is_empty() {
+        return Err(error.into());
+    }
+
+    #[cfg(feature = "lints")]
+    lint_story_content(&knots, &options.lints, log);
+
+    set_choice_ids(&mut knots);
+
+    // Functions are called from expressions instead of followed, so they are kept apart
+    data.functions = Arc::new(get_function_set(&knots));
+    knots.retain(|_, knot| !knot.is_function);
+
+    Ok((knots, data))
+}
+
+/// Declare the external functions of the story, along with the functions which the host
+/// bound to them before the story was read.
+fn get_external_functions(knots: &KnotSet, bound: &ExternalFunctions) -> ExternalFunctions {
+    let mut external_functions = bound.clone();
+
+    knots
+        .iter()
+        .filter(|(_, knot)| knot.is_external)
+        .for_each(|(name, _)| external_functions.declare(name));
+
+    external_functions
+}
+
+/// Get an error for every external function in the story which is not bound by the host.
+///
+/// Functions with a fallback in the story, which are the ones with content, are not errors.
+fn check_external_functions(
+    knots: &KnotSet,
+    external_functions: &ExternalFunctions,
+) -> Vec<InvalidAddressError> {
+    let mut errors = knots
+        .iter()
+        .filter(|(_, knot)| knot.is_external && knot.stitches.is_empty())
+        .filter(|(name, _)| external_functions.get(name).is_none())
+        .map(|(name, knot)| InvalidAddressError {
+            kind: InvalidAddressErrorKind::UnboundExternalFunction { name: name.clone() },
+            meta_data: knot.meta_data.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    errors.sort_by_key(|error| error.meta_data.line_index);
+
+    errors
+}
+
+/// Replace the initial values of global variables with defaults given by the host.
+fn set_variable_defaults(
+    variables: &mut VariableSet,
+    defaults: &HashMap<String, Variable>,
+) -> Result<(), ReadError> {
+    let mut names = defaults.keys().collect::<Vec<_>>();
+    names.sort();
+
+    for name in names {
+        variables
+            .get_mut(name)
+            .ok_or_else(|| InklingError::InvalidVariable {
+                name: name.to_string(),
+            })
+            .and_then(|info| info.assign(defaults[name].clone(), name))
+            .map_err(|error| ReadError::InvalidVariableDefault {
+                name: name.to_string(),
+                error: Box::new(error),
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Get the lines of text from a buffer of items, discarding custom directives.
+fn get_lines_from_items(item_buffer: ItemBuffer) -> impl Iterator<Item = Line> {
+    item_buffer.into_iter().filter_map(|item| match item {
+        StoryItem::Line(line) => Some(line),
+        StoryItem::Directive(..) => None,
+    })
+}
+
+/// Write a `Location` as `Ink` would write its address.
+pub(crate) fn format_location(location: &Location) -> String {
+    match &location.stitch {
+        Some(stitch) => format!("{}.{}", location.knot, stitch),
+        None => location.knot.clone(),
+    }
+}
+
+/// Follow the nodes in a story with selected branch index if supplied.
+///
+/// When an event that triggers a `Prompt` is encountered it will be returned along with
+/// the last visited address. Lines that are followed in the story will be processed
+/// and added to the input buffer.
+///
+/// Choices which were gathered from threads are presented before the choices of the story.
+/// If a choice from a thread is selected the story continues from that thread.
+fn follow_story(
+    current_address: &Address,
+    internal_buffer: &mut LineDataBuffer,
+    selection: Option<usize>,
+    knots: &mut KnotSet,
+    data: &mut FollowData,
+) -> Result<(Prompt, Address), InklingError> {
+    let (address, selection) = get_choice_origin(current_address, selection, knots, data);
+
+    let (last_address, event) = follow_knot(&address, internal_buffer, selection, knots, data)?;
+    let event = add_thread_choices(&last_address, event, data);
+
+    match event {
+        EncounteredEvent::BranchingChoice(choice_set) => {
+            let user_choice_lines =
+                prepare_choices_from_origins(&choice_set, data, prepare_choices_for_user)?;
+            if !user_choice_lines.is_empty() {
+                Ok((Prompt::Choice(user_choice_lines), last_address))
+            } else {
+                let choice = get_fallback_choice(&choice_set, &last_address, data)?;
+
+                follow_story(
+                    &last_address,
+                    internal_buffer,
+                    Some(choice.index),
+                    knots,
+                    data,
+                )
+            }
+        }
+        // Returns are only encountered in functions, which are never followed as knots,
+        // and threads are followed by `follow_knot`
+        EncounteredEvent::Done | EncounteredEvent::Return(..) | EncounteredEvent::Thread(..) => {
+            let ending = *data.ending.get_or_insert(EndingKind::DeadEnd);
+
+            Ok((Prompt::Done(ending), last_address))
+        }
+        // Diverts are only returned from `follow_knot` when the line or divert limit is reached
+        EncounteredEvent::Divert(..) => Ok((Prompt::Continue, last_address)),
+    }
+}
+
+/// Follow a knot through diverts.
+///
+/// Will [follow][crate::node::Follow] through the story starting from the input address
+/// and return all encountered lines. Diverts will be automatically moved to.
+///
+/// The function returns when either a branching point is encountered or there is no
+/// content left to follow. When it returns it will return with the last visited address.
+fn follow_knot(
+    address: &Address,
+    internal_buffer: &mut LineDataBuffer,
+    mut selection: Option<usize>,
+    knots: &mut KnotSet,
+    data: &mut FollowData,
+) -> Result<(Address, EncounteredEvent), InklingError> {
+    let mut current_address = address.clone();
+    let mut num_diverts = 0;
+    let mut line_count = LineCount::default();
+    // Labels are followed from their position inside the stitch
+    let mut is_resuming = matches!(address, Address::Validated(AddressKind::Label(..)));
+
+    let event = loop {
+        let current_stitch = get_mut_stitch(&current_address, knots)?;
+
+        let result = match selection.take() {
+            Some(i) => current_stitch.follow_with_choice(i, internal_buffer, data),
+            None if is_resuming => current_stitch.resume(internal_buffer, data),
+            None => current_stitch.follow(internal_buffer, data),
+        }?;
+
+        is_resuming = false;
+
+        match result {
+            EncounteredEvent::Divert(Address::End) => {
+                data.ending.replace(EndingKind::End);
+
+                break EncounteredEvent::Done;
+            }
+            EncounteredEvent::Divert(Address::Done) => {
+                data.ending.replace(EndingKind::Done);
+
+                break EncounteredEvent::Done;
+            }
+            EncounteredEvent::Divert(to_address) => {
+                // Temporary variables are dropped as the story leaves their knot or stitch
+                if to_address.get_knot_and_stitch()? != current_address.get_knot_and_stitch()? {
+                    data.temporary_variables.clear();
+                }
+
+                set_knot_parameters(&to_address, knots, data)?;
+
+                // Labels are resumed from inside their stitch
+                if let Address::Validated(AddressKind::Label(..)) = to_address {
+                    move_to_label(&to_address, knots)?;
+                    is_resuming = true;
+                }
+
+                current_address = to_address;
+                num_diverts += 1;
+
+                let has_reached_divert_limit = data
+                    .max_diverts_per_resume
+                    .map(|max_diverts| num_diverts >= max_diverts)
+                    .unwrap_or(false);
+
+                if has_reached_divert_limit || line_count.has_reached_limit(internal_buffer, data) {
+                    break EncounteredEvent::Divert(current_address.clone());
+                }
+            }
+            EncounteredEvent::Thread(thread_address) => {
+                follow_thread(&thread_address, internal_buffer, knots, data)?;
+
+                // Threads which divert to `END` end the story
+                if data.ending == Some(EndingKind::End) {
+                    break EncounteredEvent::Done;
+                }
+
+                // Continue from the line after the thread
+                is_resuming = true;
+            }
+            _ => break result,
+        }
+    };
+
+    Ok((current_address, event))
+}
+
+/// Follow a thread until it reaches a set of choices or its end.
+///
+/// The choices are kept to be presented along with the choices of the story. Threads are
+/// always followed from their beginning, with only their parameters as temporary variables.
+/// The temporary variables of the story are restored after the thread. A thread which
+/// diverts to `DONE` or runs out of content only ends the thread, while one which diverts
+/// to `END` ends the story.
+fn follow_thread(
+    address: &Address,
+    internal_buffer: &mut LineDataBuffer,
+    knots: &mut KnotSet,
+    data: &mut FollowData,
+) -> Result<(), InklingError> {
+    let temporary_variables = std::mem::take(&mut data.temporary_variables);
+    let ending = data.ending;
+
+    let result = set_knot_parameters(address, knots, data).and_then(|_| {
+        match address {
+            Address::Validated(AddressKind::Label(..)) => move_to_label(address, knots)?,
+            _ => get_mut_stitch(address, knots)?.reset_stack(),
+        }
+
+        let mut followed = follow_knot(address, internal_buffer, None, knots, data)?;
+
+        // Diverts are only returned when the divert or line limit is reached
+        while let (last_address, EncounteredEvent::Divert(..)) = &followed {
+            followed = follow_knot(&last_address.clone(), internal_buffer, None, knots, data)?;
+        }
+
+        Ok(followed)
+    });
+
+    let thread_variables = std::mem::replace(&mut data.temporary_variables, temporary_variables);
+
+    if data.ending != Some(EndingKind::End) {
+        data.ending = ending;
+    }
+
+    if let (address, EncounteredEvent::BranchingChoice(choices)) = result? {
+        data.thread_choices.push(ThreadChoices {
+            address,
+            choices,
+            temporary_variables: thread_variables,
+        });
+    }
+
+    Ok(())
+}
+
+/// Add the choices which were gathered from threads before the choices that the story
+/// encountered.
+///
+/// The address and index of every choice is recorded to follow the selected choice from.
+/// Choices are presented if the story ran out of content, but not if it reached its end.
+fn add_thread_choices(
+    last_address: &Address,
+    event: EncounteredEvent,
+    data: &mut FollowData,
+) -> EncounteredEvent {
+    if data.thread_choices.is_empty() {
+        return event;
+    }
+
+    let choices = match event {
+        EncounteredEvent::BranchingChoice(choices) => choices,
+        EncounteredEvent::Done if data.ending != Some(EndingKind::End) => Vec::new(),
+        EncounteredEvent::Done => {
+            data.thread_choices.clear();
+            return event;
+        }
+        _ => return event,
+    };
+
+    let story_choices = ThreadChoices {
+        address: last_address.clone(),
+        choices,
+        temporary_variables: data.temporary_variables.clone(),
+    };
+
+    let (origins, choice_set) = std::mem::take(&mut data.thread_choices)
+        .into_iter()
+        .chain(std::iter::once(story_choices))
+        .flat_map(|thread| {
+            let ThreadChoices {
+                address,
+                choices,
+                temporary_variables,
+            } = thread;
+
+            choices.into_iter().enumerate().map(move |(index, choice)| {
+                let origin = ChoiceOrigin {
+                    address: address.clone(),
+                    index,
+                    temporary_variables: temporary_variables.clone(),
+                };
+
+                (origin, choice)
+            })
+        })
+        .unzip();
+
+    data.choice_origins = origins;
+
+    EncounteredEvent::BranchingChoice(choice_set)
+}
+
+/// Get the address to follow a selected choice from and its index in the set of choices there.
+///
+/// If the presented choices were gathered from threads the choice is followed from where
+/// it was encountered, with the temporary variables that it was encountered with. The stitches
+/// of the other choices are reset, to be followed from their beginning the next time that
+/// they are entered.
+fn get_choice_origin(
+    current_address: &Address,
+    selection: Option<usize>,
+    knots: &mut KnotSet,
+    data: &mut FollowData,
+) -> (Address, Option<usize>) {
+    let mut origins = std::mem::take(&mut data.choice_origins);
+
+    match selection.filter(|&index| index < origins.len()) {
+        Some(selection) => {
+            let origin = origins.swap_remove(selection);
+
+            for other in origins
+                .iter()
+                .filter(|other| other.address != origin.address)
+            {
+                if let Ok(stitch) = get_mut_stitch(&other.address, knots) {
+                    stitch.reset_stack();
+                }
+            }
+
+            data.temporary_variables = origin.temporary_variables;
+
+            (origin.address, Some(origin.index))
+        }
+        None => (current_address.clone(), selection),
+    }
+}
+
+/// Prepare a set of choices with the temporary variables that each was encountered with.
+///
+/// The choices are prepared by the given function from `process`. Choices which were not
+/// gathered from threads all share the temporary variables of the story.
+fn prepare_choices_from_origins(
+    choice_set: &[ChoiceInfo],
+    data: &mut FollowData,
+    prepare: fn(&[ChoiceInfo], &mut FollowData) -> Result<Vec<Choice>, InklingError>,
+) -> Result<Vec<Choice>, InklingError> {
+    if data.choice_origins.is_empty() {
+        return prepare(choice_set, data);
+    }
+
+    let origins = data.choice_origins.clone();
+    let temporary_variables = std::mem::take(&mut data.temporary_variables);
+
+    let result = choice_set
+        .iter()
+        .zip(origins)
+        .enumerate()
+        .map(|(index, (choice, origin))| {
+            data.temporary_variables = origin.temporary_variables;
+
+            prepare(std::slice::from_ref(choice), data).map(|choices| {
+                choices
+                    .into_iter()
+                    .map(|choice| Choice { index, ..choice })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|choices| choices.into_iter().flatten().collect());
+
+    data.temporary_variables = temporary_variables;
+
+    result
+}
+
+/// Set the arguments of a divert as the parameters of the knot that it leads to.
+///
+/// Parameters are temporary variables which are only set when the knot is entered
+/// at its default stitch.
+fn set_knot_parameters(
+    to_address: &Address,
+    knots: &KnotSet,
+    data: &mut FollowData,
+) -> Result<(), InklingError> {
+    let arguments = std::mem::take(&mut data.divert_arguments);
+    let (knot_name, stitch_name) = to_address.get_knot_and_stitch()?;
+
+    if let Some(knot) = knots
+        .get(knot_name)
+        .filter(|knot| knot.default_stitch == stitch_name)
+    {
+        data.temporary_variables
+            .extend(knot.parameters.iter().cloned().zip(arguments));
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+/// Running count of the lines in a buffer, to check the line limit at diverts.
+///
+/// Only the lines which were added since the last check are counted.
+struct LineCount {
+    /// Number of items in the buffer which have been counted.
+    num_counted: usize,
+    /// Number of lines with text and custom directives among the counted items.
+    num_lines: usize,
+    /// Whether the story can return after the last line.
+    is_split_point: bool,
+}
+
+impl LineCount {
+    /// Check whether enough lines have been produced to return early at a divert.
+    ///
+    /// Lines which are glued to the next are not split from it, unless the glue comes
+    /// from a divert at the end of the line. The returned line then keeps its glue.
+    fn has_reached_limit(&mut self, internal_buffer: &LineDataBuffer, data: &FollowData) -> bool {
+        let max_lines = match data.max_lines_per_resume {
+            Some(max_lines) => max_lines,
+            None => return false,
+        };
+
+        let new_lines = internal_buffer
+            .get(self.num_counted..)
+            .unwrap_or_default()
+            .iter()
+            .filter(|line| line.directive.is_some() || !line.text.trim().is_empty());
+
+        for line in new_lines {
+            self.num_lines += 1;
+            self.is_split_point = !line.glue_end || line.divert_end;
+        }
+
+        self.num_counted = internal_buffer.len();
+
+        self.num_lines >= max_lines && self.is_split_point
+    }
+}
+
+/// Return the first available fallback choice from the given set of choices.
+///
+/// Choices are filtered as usual by conditions and visits.
+fn get_fallback_choice(
+    choice_set: &[ChoiceInfo],
+    current_address: &Address,
+    data: &mut FollowData,
+) -> Result<Choice, InklingError> {
+    prepare_choices_from_origins(choice_set, data, get_fallback_choices).and_then(|choices| {
+        choices.first().cloned().ok_or(InklingError::OutOfChoices {
+            location: Location::from(current_address.to_string().as_ref()),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        error::utils::MetaData,
+        follow::FollowDataBuilder,
+        knot::{get_num_visited, increment_num_visited},
+        process::process_buffer,
+        story::parse::tests::read_knots_from_string,
+        story::ConditionSite,
+    };
+
+    fn mock_last_choices(choices: &[(&str, usize)]) -> Vec<Choice> {
+        choices
+            .iter()
+            .map(|(text, index)| Choice {
+                text: text.to_string(),
+                display_text: text.to_string(),
+                raw_text: String::new(),
+                tags: Vec::new(),
+                id: String::new(),
+                weight: 1,
+                index: *index,
+            })
+            .collect()
+    }
+
+    fn mock_follow_data(knots: &KnotSet) -> FollowData {
+        FollowDataBuilder::new()
+            .with_knots(get_empty_knot_counts(knots))
+            .build()
+    }
+
+    #[test]
+    fn follow_knot_diverts_to_new_knots_when_encountered() {
+        let content = "
+== back_in_london
+We arrived into London at 9.45pm exactly.
+-> hurry_home
+
+== hurry_home
+We hurried home to Savile Row as fast as we could.
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+
+        assert_eq!(
+            &buffer.last().unwrap().text,
+            "We hurried home to Savile Row as fast as we could."
+        );
+    }
+
+    #[test]
+    fn follow_knot_does_not_return_divert_event_if_divert_is_encountered() {
+        let content = "
+== back_in_london
+We arrived into London at 9.45pm exactly.
+-> hurry_home
+
+== hurry_home
+We hurried home to Savile Row as fast as we could.
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        let (_, event) =
+            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+
+        match event {
+            EncounteredEvent::Done => (),
+            other => panic!("expected `EncounteredEvent::Done` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn follow_knot_returns_choices_when_encountered() {
+        let content = "
+== select_destination
+*   Tripoli
+*   Addis Ababa
+*   Rabat
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let root_address = Address::from_root_knot("select_destination", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        let (_, event) =
+            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+
+        match event {
+            EncounteredEvent::BranchingChoice(ref choices) => {
+                assert_eq!(choices.len(), 3);
+            }
+            other => panic!(
+                "expected `EncounteredEvent::BranchingChoice` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn follow_knot_returns_the_last_active_knot() {
+        let content = "
+== back_in_london
+We arrived into London at 9.45pm exactly.
+-> hurry_home
+
+== hurry_home
+We hurried home to Savile Row as fast as we could.
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        let (last_address, _) =
+            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+
+        assert_eq!(
+            last_address,
+            Address::from_root_knot("hurry_home", &knots).unwrap()
+        );
+    }
+
+    #[test]
+    fn divert_to_done_or_end_constant_knots_ends_story() {
+        let content = "
+== knot_done
+-> DONE
+
+== knot_end
+-> END
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let done_address = Address::from_root_knot("knot_done", &knots).unwrap();
+        let end_address = Address::from_root_knot("knot_end", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        match follow_knot(&done_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
+            (_, EncounteredEvent::Done) => (),
+            _ => panic!("story should be done when diverting to DONE knot"),
+        }
+
+        match follow_knot(&end_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
+            (_, EncounteredEvent::Done) => (),
+            _ => panic!("story should be done when diverting to END knot"),
+        }
+    }
+
+    #[test]
+    fn divert_to_knot_increments_its_visit_count() {
+        let content = "
+== addis_ababa
+-> tripoli
+
+== tripoli
+-> DONE
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
+        let divert_address = Address::from_root_knot("tripoli", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        follow_knot(&current_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+
+        assert_eq!(get_num_visited(&divert_address, &data).unwrap(), 1);
+    }
+
+    #[test]
+    fn knots_do_not_get_their_number_of_visits_incremented_when_resuming_a_choice() {
+        let content = "
+== tripoli
+
+*   Cinema -> END
+*   Visit family -> END
+";
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
+
+        let mut buffer = Vec::new();
+
+        follow_knot(
+            &current_address,
+            &mut buffer,
+            Some(1),
+            &mut knots,
+            &mut data,
+        )
+        .unwrap();
+
+        assert_eq!(get_num_visited(&current_address, &data).unwrap(), 0);
+    }
+
+    #[test]
+    fn follow_story_returns_last_visited_address_when_reaching_end() {
+        let content = "
+== addis_ababa
+-> tripoli.cinema
+
+== tripoli
+
+= cinema
+-> END
+
+= visit_family
+-> END
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
+
+        let mut line_buffer = Vec::new();
+
+        let (_, last_address) = follow_story(
+            &current_address,
+            &mut line_buffer,
+            None,
+            &mut knots,
+            &mut data,
+        )
+        .unwrap();
+
+        assert_eq!(
+            last_address,
+            Address::from_parts_unchecked("tripoli", Some("cinema"))
+        );
+    }
+
+    #[test]
+    fn follow_story_returns_last_visited_address_when_encountering_choices() {
+        let content = "
+== addis_ababa
+-> tripoli
+
+== tripoli
+
+*   Cinema -> END
+*   Visit family -> END
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
+
+        let mut line_buffer = Vec::new();
+
+        let (_, last_address) = follow_story(
+            &current_address,
+            &mut line_buffer,
+            None,
+            &mut knots,
+            &mut data,
+        )
+        .unwrap();
+
+        assert_eq!(last_address, Address::from_parts_unchecked("tripoli", None));
+    }
+
+    /***********************
+     * `Story` API testing *
+     ***********************/
+
+    #[test]
+    fn make_choice_sets_the_choice_index_from_the_last_choices_set() {
+        let mut story = read_story_from_string("Content.").unwrap();
+        story
+            .last_choices
+            .replace(mock_last_choices(&[("", 2), ("", 4)]));
+
+        story.make_choice(1).unwrap();
+
+        assert_eq!(story.selected_choice, Some(4));
+    }
+
+    #[test]
+    fn make_choice_resets_last_choices_vector() {
+        let mut story = read_story_from_string("Content.").unwrap();
+        story.last_choices.replace(mock_last_choices(&[("", 0)]));
+
+        story.make_choice(0).unwrap();
+
+        assert!(story.last_choices.is_none());
+    }
+
+    #[test]
+    fn make_choice_yields_an_error_if_a_choice_has_not_been_prompted() {
+        let mut story = read_story_from_string("Content.").unwrap();
+
+        match story.make_choice(0) {
+            Err(InklingError::MadeChoiceWithoutChoice) => (),
+            other => panic!(
+                "expected `InklingError::MadeChoiceWithoutChoice` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn make_choice_yields_an_error_if_choice_index_is_not_in_last_choices_set() {
+        let mut story = read_story_from_string("Content.").unwrap();
+
+        let last_choices = mock_last_choices(&[("Choice 1", 0), ("Choice 2", 2)]);
+        story.last_choices.replace(last_choices.clone());
+
+        match story.make_choice(2) {
+            Err(InklingError::InvalidChoice {
+                selection,
+                presented_choices,
+            }) => {
+                assert_eq!(selection, 2);
+                assert_eq!(presented_choices, last_choices);
+            }
+            other => panic!("expected `InklingError::InvalidChoice` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn calling_resume_continues_the_text_flow_with_the_choice_that_was_made() {
+        let content = "
+\"To be, or not to be ...\"
+*   [To be]
+*   [Not to be]
+    \"Not to be.\" – Jack Slater
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        story.make_choice(1).unwrap();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[1].text, "\"Not to be.\" – Jack Slater\n");
+    }
+
+    #[test]
+    fn following_story_wrapper_updates_current_address_to_last_address() {
+        let content = "
+== addis_ababa
+-> tripoli.cinema
+
+== tripoli
+
+= cinema
+-> END
+
+= visit_family
+-> END
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"addis_ababa".into()).unwrap();
+
+        let mut line_buffer = Vec::new();
+
+        story.follow_story_wrapper(None, &mut line_buffer).unwrap();
+
+        let address = Address::from_parts_unchecked("tripoli", Some("cinema"));
+
+        assert_eq!(story.current_address, address);
+    }
 
-/// Read a `Story` by parsing an input string.
-///
-/// # Examples
-/// ```
-/// # use inkling::{read_story_from_string, Story};
-/// let content = "\
-/// He drifted off, and when he opened his eyes the woman was still there.
-/// Now she was talking to the old man seated next to her—the farmer from two stations back.
-/// ";
-///
-/// let story: Story = read_story_from_string(content).unwrap();
-/// ```
-pub fn read_story_from_string(string: &str) -> Result<Story, ReadError> {
-    let mut log = Logger::default();
-    let (mut knots, variables, tags) = read_story_content_from_string(string, &mut log)?;
+    #[test]
+    fn if_choice_list_returned_to_user_is_empty_follow_fallback_choice() {
+        let content = "
+== knot
+*   Non-sticky choice -> knot
+*   ->
+    Fallback choice
+";
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"knot".into()).unwrap();
 
-    let data = FollowData {
-        knot_visit_counts: get_empty_knot_counts(&knots),
-        variables,
-        rng: StoryRng::default(),
-    };
+        let mut line_buffer = Vec::new();
 
-    validate_story_content(&mut knots, &data, &mut log)?;
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
 
-    let root_address = Address::from_root_knot(ROOT_KNOT_NAME, &knots).expect(
-        "After successfully creating all knots, the root knot name that was returned from \
-         `read_knots_from_string` is not present in the set of created knots. \
-         This simply should not be possible",
-    );
+        assert_eq!(choices.len(), 1);
 
-    Ok(Story {
-        current_address: root_address,
-        knots,
-        history: Vec::new(),
-        data,
-        tags,
-        last_choices: None,
-        selected_choice: None,
-        log,
-    })
-}
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
 
-/// Follow the nodes in a story with selected branch index if supplied.
-///
-/// When an event that triggers a `Prompt` is encountered it will be returned along with
-/// the last visited address. Lines that are followed in the story will be processed
-/// and added to the input buffer.
-fn follow_story(
-    current_address: &Address,
-    internal_buffer: &mut LineDataBuffer,
-    selection: Option<usize>,
-    knots: &mut KnotSet,
-    data: &mut FollowData,
-) -> Result<(Prompt, Address), InklingError> {
-    let (last_address, event) =
-        follow_knot(current_address, internal_buffer, selection, knots, data)?;
+        assert_eq!(&line_buffer[1].text, "Fallback choice\n");
+    }
 
-    match event {
-        EncounteredEvent::BranchingChoice(choice_set) => {
-            let user_choice_lines = prepare_choices_for_user(&choice_set, data)?;
-            if !user_choice_lines.is_empty() {
-                Ok((Prompt::Choice(user_choice_lines), last_address))
-            } else {
-                let choice = get_fallback_choice(&choice_set, &last_address, data)?;
+    #[test]
+    fn fallback_choices_resume_from_the_knot_they_are_encountered_in() {
+        let content = "
+== first
+-> second
 
-                follow_story(
-                    &last_address,
-                    internal_buffer,
-                    Some(choice.index),
-                    knots,
-                    data,
-                )
-            }
-        }
-        EncounteredEvent::Done => Ok((Prompt::Done, last_address)),
-        EncounteredEvent::Divert(..) => unreachable!("diverts are treated in `follow_knot`"),
+== second
++   ->
+    Fallback choice
+";
+
+        let mut knots = read_knots_from_string(content).unwrap();
+
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+
+        let current_address = Address::from_root_knot("first", &knots).unwrap();
+
+        let mut line_buffer = Vec::new();
+        let mut internal_buffer = Vec::new();
+
+        follow_story(
+            &current_address,
+            &mut internal_buffer,
+            None,
+            &mut knots,
+            &mut data,
+        )
+        .unwrap();
+        process_buffer(&mut line_buffer, internal_buffer);
+
+        assert_eq!(&line_buffer[0].text, "Fallback choice\n");
     }
-}
 
-/// Follow a knot through diverts.
-///
-/// Will [follow][crate::node::Follow] through the story starting from the input address
-/// and return all encountered lines. Diverts will be automatically moved to.
-///
-/// The function returns when either a branching point is encountered or there is no
-/// content left to follow. When it returns it will return with the last visited address.
-fn follow_knot(
-    address: &Address,
-    internal_buffer: &mut LineDataBuffer,
-    mut selection: Option<usize>,
-    knots: &mut KnotSet,
-    data: &mut FollowData,
-) -> Result<(Address, EncounteredEvent), InklingError> {
-    let mut current_address = address.clone();
+    #[test]
+    fn glue_is_followed_over_fallback_choices() {
+        let content = "
+== tripoli
+We decided to go to the <>
+*   [] Cinema.
+";
 
-    let event = loop {
-        let current_stitch = get_mut_stitch(&current_address, knots)?;
+        let mut knots = read_knots_from_string(content).unwrap();
 
-        let result = match selection.take() {
-            Some(i) => current_stitch.follow_with_choice(i, internal_buffer, data),
-            None => current_stitch.follow(internal_buffer, data),
-        }?;
+        let mut data = mock_follow_data(&knots);
+        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
 
-        match result {
-            EncounteredEvent::Divert(Address::End) => break EncounteredEvent::Done,
-            EncounteredEvent::Divert(to_address) => {
-                current_address = to_address;
-            }
-            _ => break result,
-        }
-    };
+        let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
 
-    Ok((current_address, event))
-}
+        let mut line_buffer = Vec::new();
+        let mut internal_buffer = Vec::new();
 
-/// Return the first available fallback choice from the given set of choices.
-///
-/// Choices are filtered as usual by conditions and visits.
-fn get_fallback_choice(
-    choice_set: &[ChoiceInfo],
-    current_address: &Address,
-    data: &mut FollowData,
-) -> Result<Choice, InklingError> {
-    get_fallback_choices(choice_set, data).and_then(|choices| {
-        choices.first().cloned().ok_or(InklingError::OutOfChoices {
-            location: Location::from(current_address.to_string().as_ref()),
-        })
-    })
-}
+        follow_story(
+            &current_address,
+            &mut internal_buffer,
+            None,
+            &mut knots,
+            &mut data,
+        )
+        .unwrap();
+        process_buffer(&mut line_buffer, internal_buffer);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(&line_buffer[0].text, "We decided to go to the ");
+        assert_eq!(&line_buffer[1].text, "Cinema.\n");
+    }
 
-    use crate::{
-        follow::FollowDataBuilder,
-        knot::{get_num_visited, increment_num_visited},
-        story::parse::tests::read_knots_from_string,
-    };
+    #[test]
+    fn if_no_fallback_choices_are_available_raise_error() {
+        let content = "
+== knot
+*   Non-sticky choice -> knot
+";
 
-    fn mock_last_choices(choices: &[(&str, usize)]) -> Vec<Choice> {
-        choices
-            .iter()
-            .map(|(text, index)| Choice {
-                text: text.to_string(),
-                tags: Vec::new(),
-                index: *index,
-            })
-            .collect()
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"knot".into()).unwrap();
+
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        story.make_choice(0).unwrap();
+
+        match story.resume(&mut line_buffer) {
+            Err(InklingError::OutOfChoices { .. }) => (),
+            Err(err) => panic!("expected `OutOfChoices` error but got {:?}", err),
+            Ok(_) => panic!("expected an error but got an Ok"),
+        }
     }
 
-    fn mock_follow_data(knots: &KnotSet) -> FollowData {
-        FollowDataBuilder::new()
-            .with_knots(get_empty_knot_counts(knots))
-            .build()
+    #[test]
+    fn last_set_of_presented_choices_are_stored() {
+        let content = "
+== knot
+*   Choice 1
+*   Choice 2
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        assert!(story.last_choices.is_none());
+
+        story.move_to(&"knot".into()).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+
+        let last_choices = story.last_choices.as_ref().unwrap();
+
+        assert_eq!(last_choices.len(), 2);
+        assert_eq!(&last_choices[0].text, "Choice 1");
+        assert_eq!(&last_choices[1].text, "Choice 2");
     }
 
     #[test]
-    fn follow_knot_diverts_to_new_knots_when_encountered() {
+    fn when_a_knot_is_returned_to_the_text_starts_from_the_beginning() {
         let content = "
-== back_in_london
-We arrived into London at 9.45pm exactly.
+== back_in_almaty
+We arrived into Almaty at 9.45pm exactly.
 -> hurry_home
 
 == hurry_home
-We hurried home to Savile Row as fast as we could.
+*   We hurried home as fast as we could. -> END
+*   But we decided our trip wasn't done and immediately left.
+    After a few days me returned again.
+    -> back_in_almaty
 ";
 
-        let mut knots = read_knots_from_string(content).unwrap();
-
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"back_in_almaty".into()).unwrap();
 
-        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+        let mut item_buffer = Vec::new();
 
-        let mut buffer = Vec::new();
+        story.follow_story_wrapper(None, &mut item_buffer).unwrap();
+        story
+            .follow_story_wrapper(Some(1), &mut item_buffer)
+            .unwrap();
 
-        follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let line_buffer = get_lines_from_items(item_buffer).collect::<Vec<_>>();
 
         assert_eq!(
-            &buffer.last().unwrap().text,
-            "We hurried home to Savile Row as fast as we could."
+            &line_buffer[0].text,
+            "We arrived into Almaty at 9.45pm exactly.\n"
+        );
+        assert_eq!(
+            &line_buffer[3].text,
+            "We arrived into Almaty at 9.45pm exactly.\n"
         );
     }
 
     #[test]
-    fn follow_knot_does_not_return_divert_event_if_divert_is_encountered() {
+    fn when_the_story_begins_the_first_knot_gets_its_number_of_visits_set_to_one() {
         let content = "
-== back_in_london
-We arrived into London at 9.45pm exactly.
--> hurry_home
-
-== hurry_home
-We hurried home to Savile Row as fast as we could.
+Hello, World!
 ";
 
-        let mut knots = read_knots_from_string(content).unwrap();
-
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
-
-        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let mut buffer = Vec::new();
+        story.resume(&mut line_buffer).unwrap();
 
-        let (_, event) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let address = Address::from_root_knot("$ROOT$", &story.knots).unwrap();
 
-        match event {
-            EncounteredEvent::Done => (),
-            other => panic!("expected `EncounteredEvent::Done` but got {:?}", other),
-        }
+        assert_eq!(get_num_visited(&address, &story.data).unwrap(), 1);
     }
 
     #[test]
-    fn follow_knot_returns_choices_when_encountered() {
+    fn reading_story_from_string_initializes_all_knot_visit_counts_to_zero() {
         let content = "
-== select_destination
-*   Tripoli
-*   Addis Ababa
-*   Rabat
-";
 
-        let mut knots = read_knots_from_string(content).unwrap();
+== back_in_almaty
+-> END
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+== hurry_home
+-> END
+= at_home
+-> END
 
-        let root_address = Address::from_root_knot("select_destination", &knots).unwrap();
+";
 
-        let mut buffer = Vec::new();
+        let story = read_story_from_string(content).unwrap();
 
-        let (_, event) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let back_in_almaty = Address::from_parts_unchecked("back_in_almaty", None);
+        let hurry_home = Address::from_parts_unchecked("hurry_home", None);
+        let at_home = Address::from_parts_unchecked("hurry_home", Some("at_home"));
 
-        match event {
-            EncounteredEvent::BranchingChoice(ref choices) => {
-                assert_eq!(choices.len(), 3);
-            }
-            other => panic!(
-                "expected `EncounteredEvent::BranchingChoice` but got {:?}",
-                other
-            ),
-        }
+        assert_eq!(get_num_visited(&back_in_almaty, &story.data).unwrap(), 0);
+        assert_eq!(get_num_visited(&hurry_home, &story.data).unwrap(), 0);
+        assert_eq!(get_num_visited(&at_home, &story.data).unwrap(), 0);
     }
 
     #[test]
-    fn follow_knot_returns_the_last_active_knot() {
+    fn reading_story_from_string_sets_global_tags() {
         let content = "
-== back_in_london
-We arrived into London at 9.45pm exactly.
--> hurry_home
 
-== hurry_home
-We hurried home to Savile Row as fast as we could.
+# title: inkling
+# author: Petter Johansson
+
 ";
+        let story = read_story_from_string(content).unwrap();
 
-        let mut knots = read_knots_from_string(content).unwrap();
+        assert_eq!(
+            &story.tags,
+            &[
+                "title: inkling".to_string(),
+                "author: Petter Johansson".to_string()
+            ]
+        );
+    }
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+    #[test]
+    fn reading_story_from_string_sets_global_variables() {
+        let content = "
 
-        let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
+VAR counter = 0
+VAR hazardous = true
+VAR warning_message = \"ADVARSEL\"
 
-        let mut buffer = Vec::new();
+";
 
-        let (last_address, _) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let story = read_story_from_string(content).unwrap();
+
+        let variables = &story.data.variables;
+        assert_eq!(variables.len(), 3);
 
+        assert_eq!(variables.get("counter").unwrap().variable, Variable::Int(0));
         assert_eq!(
-            last_address,
-            Address::from_root_knot("hurry_home", &knots).unwrap()
+            variables.get("hazardous").unwrap().variable,
+            Variable::Bool(true)
+        );
+
+        assert_eq!(
+            variables.get("warning_message").unwrap().variable,
+            Variable::String("ADVARSEL".to_string())
         );
     }
 
     #[test]
-    fn divert_to_done_or_end_constant_knots_ends_story() {
+    fn knots_with_non_default_root_stitch_gets_validated_addresses_that_point_to_them() {
         let content = "
-== knot_done
--> DONE
+-> almaty
 
-== knot_end
+== almaty
+= back
+We arrived into Almaty at 9.45pm exactly.
 -> END
 ";
 
-        let mut knots = read_knots_from_string(content).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        story.resume(&mut line_buffer).unwrap();
 
-        let done_address = Address::from_root_knot("knot_done", &knots).unwrap();
-        let end_address = Address::from_root_knot("knot_end", &knots).unwrap();
+        assert_eq!(
+            &line_buffer[0].text,
+            "We arrived into Almaty at 9.45pm exactly.\n"
+        );
+    }
 
-        let mut buffer = Vec::new();
+    #[test]
+    fn number_of_visits_in_a_story_is_consistent() {
+        let content = "
+One
+-> root
 
-        match follow_knot(&done_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
-            (_, EncounteredEvent::Done) => (),
-            _ => panic!("story should be done when diverting to DONE knot"),
-        }
+== root
 
-        match follow_knot(&end_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
-            (_, EncounteredEvent::Done) => (),
-            _ => panic!("story should be done when diverting to END knot"),
-        }
++   {visit_twice < 2} -> visit_twice
++   {visit_twice >= 2} {visit_thrice < 3} -> visit_thrice
+*   [] -> END
+
+== visit_twice
+Two
+-> root
+
+== visit_thrice
+Three
+-> root
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let knots = &story.knots;
+
+        let address_root = Address::from_root_knot("root", &knots).unwrap();
+        let address_twice = Address::from_root_knot("visit_twice", &knots).unwrap();
+        let address_thrice = Address::from_root_knot("visit_thrice", &knots).unwrap();
+
+        assert_eq!(get_num_visited(&address_twice, &story.data).unwrap(), 2);
+        assert_eq!(get_num_visited(&address_thrice, &story.data).unwrap(), 3);
+        assert_eq!(get_num_visited(&address_root, &story.data).unwrap(), 6);
     }
 
     #[test]
-    fn divert_to_knot_increments_its_visit_count() {
+    fn calling_resume_on_a_story_at_a_choice_returns_the_choice_again() {
         let content = "
-== addis_ababa
--> tripoli
 
-== tripoli
--> DONE
-";
+== back_in_almaty
 
-        let mut knots = read_knots_from_string(content).unwrap();
+After an arduous journey we arrived back in Almaty.
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+*   We hurried home as fast as we could.
+    -> END
+*   But we decided our trip wasn't done yet.
+    We immediately left the city.
 
-        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
-        let divert_address = Address::from_root_knot("tripoli", &knots).unwrap();
+";
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"back_in_almaty".into()).unwrap();
 
-        let mut buffer = Vec::new();
+        let mut line_buffer = Vec::new();
 
-        follow_knot(&current_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
 
-        assert_eq!(get_num_visited(&divert_address, &data).unwrap(), 1);
+        line_buffer.clear();
+        let resume_choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
+
+        assert_eq!(choices, resume_choices);
+        assert!(line_buffer.is_empty());
     }
 
     #[test]
-    fn knots_do_not_get_their_number_of_visits_incremented_when_resuming_a_choice() {
+    fn calling_resume_on_a_story_at_a_choice_without_making_a_choice_does_not_update_the_story() {
         let content = "
-== tripoli
 
-*   Cinema -> END
-*   Visit family -> END
-";
-        let mut knots = read_knots_from_string(content).unwrap();
+== back_in_almaty
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
+
+*   We hurried home {as fast as we could|slowly}.
+    -> END
+*   But we decided our trip wasn't done yet.
+    We immediately left the city.
+
+";
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"back_in_almaty".into()).unwrap();
 
-        let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let mut buffer = Vec::new();
+        story.resume(&mut line_buffer).unwrap();
 
-        follow_knot(
-            &current_address,
-            &mut buffer,
-            Some(1),
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        let story_backup = story.clone();
+        story.resume(&mut line_buffer).unwrap();
 
-        assert_eq!(get_num_visited(&current_address, &data).unwrap(), 0);
+        assert_eq!(story, story_backup);
     }
 
     #[test]
-    fn follow_story_returns_last_visited_address_when_reaching_end() {
+    fn story_can_be_moved_to_another_address() {
         let content = "
-== addis_ababa
--> tripoli.cinema
-
-== tripoli
 
-= cinema
+We arrived into Almaty at 9.45pm exactly.
 -> END
 
-= visit_family
+== hurry_home
+We hurried home as fast as we could.
 -> END
-";
 
-        let mut knots = read_knots_from_string(content).unwrap();
+";
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
+        story.move_to(&"hurry_home".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        let address = story.current_address.clone();
+        assert_eq!(address.get_knot().unwrap(), "hurry_home");
+        assert_eq!(address.get_stitch().unwrap(), ROOT_KNOT_NAME);
 
-        let (_, last_address) = follow_story(
-            &current_address,
-            &mut line_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        line_buffer.clear();
+        story.resume(&mut line_buffer).unwrap();
 
         assert_eq!(
-            last_address,
-            Address::from_parts_unchecked("tripoli", Some("cinema"))
+            &line_buffer[0].text,
+            "We hurried home as fast as we could.\n"
         );
     }
 
     #[test]
-    fn follow_story_returns_last_visited_address_when_encountering_choices() {
+    fn moving_to_a_new_location_resets_current_choice_list() {
         let content = "
-== addis_ababa
--> tripoli
-
-== tripoli
 
-*   Cinema -> END
-*   Visit family -> END
-";
+== back_in_almaty
 
-        let mut knots = read_knots_from_string(content).unwrap();
+After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+*   We hurried home {as fast as we could|slowly}.
+    -> END
+*   But we decided our trip wasn't done yet.
+    We immediately left the city.
 
-        let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
+== hurry_home
+We hurried home as fast as we could.
+-> END
+";
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"back_in_almaty".into()).unwrap();
 
         let mut line_buffer = Vec::new();
+        story.resume(&mut line_buffer).unwrap();
 
-        let (_, last_address) = follow_story(
-            &current_address,
-            &mut line_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        story.move_to(&"hurry_home".into()).unwrap();
 
-        assert_eq!(last_address, Address::from_parts_unchecked("tripoli", None));
+        assert!(story.last_choices.is_none());
     }
 
-    /***********************
-     * `Story` API testing *
-     ***********************/
-
     #[test]
-    fn make_choice_sets_the_choice_index_from_the_last_choices_set() {
-        let mut story = read_story_from_string("Content.").unwrap();
-        story
-            .last_choices
-            .replace(mock_last_choices(&[("", 2), ("", 4)]));
+    fn moving_to_a_new_location_resets_selected_choice() {
+        let content = "
 
-        story.make_choice(1).unwrap();
+== back_in_almaty
 
-        assert_eq!(story.selected_choice, Some(4));
-    }
+After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
 
-    #[test]
-    fn make_choice_resets_last_choices_vector() {
-        let mut story = read_story_from_string("Content.").unwrap();
-        story.last_choices.replace(mock_last_choices(&[("", 0)]));
+*   We hurried home {as fast as we could|slowly}.
+    -> END
+*   But we decided our trip wasn't done yet.
+    We immediately left the city.
 
-        story.make_choice(0).unwrap();
+== hurry_home
+We hurried home as fast as we could.
+-> END
+";
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"back_in_almaty".into()).unwrap();
 
-        assert!(story.last_choices.is_none());
-    }
+        let mut line_buffer = Vec::new();
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
 
-    #[test]
-    fn make_choice_yields_an_error_if_a_choice_has_not_been_prompted() {
-        let mut story = read_story_from_string("Content.").unwrap();
+        story.move_to(&"hurry_home".into()).unwrap();
 
-        match story.make_choice(0) {
-            Err(InklingError::MadeChoiceWithoutChoice) => (),
-            other => panic!(
-                "expected `InklingError::MadeChoiceWithoutChoice` but got {:?}",
-                other
-            ),
-        }
+        assert!(story.selected_choice.is_none());
     }
 
     #[test]
-    fn make_choice_yields_an_error_if_choice_index_is_not_in_last_choices_set() {
-        let mut story = read_story_from_string("Content.").unwrap();
+    fn move_to_addresses_can_include_stitches() {
+        let content = "
 
-        let last_choices = mock_last_choices(&[("Choice 1", 0), ("Choice 2", 2)]);
-        story.last_choices.replace(last_choices.clone());
+We arrived into Almaty at 9.45pm exactly.
+-> END
 
-        match story.make_choice(2) {
-            Err(InklingError::InvalidChoice {
-                selection,
-                presented_choices,
-            }) => {
-                assert_eq!(selection, 2);
-                assert_eq!(presented_choices, last_choices);
-            }
-            other => panic!("expected `InklingError::InvalidChoice` but got {:?}", other),
-        }
-    }
+== hurry_home
+We hurried home as fast as we could.
+-> END
+
+= at_home
+Once back home we feasted on cheese.
+-> END
 
-    #[test]
-    fn calling_resume_continues_the_text_flow_with_the_choice_that_was_made() {
-        let content = "
-\"To be, or not to be ...\"
-*   [To be]
-*   [Not to be]
-    \"Not to be.\" – Jack Slater
 ";
 
         let mut story = read_story_from_string(content).unwrap();
         let mut line_buffer = Vec::new();
 
-        story.resume(&mut line_buffer).unwrap();
+        let location = Location::with_stitch("hurry_home", "at_home");
+        story.move_to(&location).unwrap();
 
-        story.make_choice(1).unwrap();
+        let address = story.current_address.clone();
+        assert_eq!(address.get_knot().unwrap(), "hurry_home");
+        assert_eq!(address.get_stitch().unwrap(), "at_home");
 
+        line_buffer.clear();
         story.resume(&mut line_buffer).unwrap();
 
-        assert_eq!(&line_buffer[1].text, "\"Not to be.\" – Jack Slater\n");
+        assert_eq!(
+            &line_buffer[0].text,
+            "Once back home we feasted on cheese.\n"
+        );
     }
 
     #[test]
-    fn following_story_wrapper_updates_current_address_to_last_address() {
+    fn move_to_yields_error_if_knot_or_stitch_name_is_invalid() {
         let content = "
-== addis_ababa
--> tripoli.cinema
 
-== tripoli
+We arrived into Almaty at 9.45pm exactly.
+-> END
 
-= cinema
+== hurry_home
+We hurried home as fast as we could.
 -> END
 
-= visit_family
+= at_home
+Once back home we feasted on cheese.
 -> END
+
 ";
 
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"addis_ababa".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        assert!(story.move_to(&"fin".into()).is_err());
 
-        story.follow_story_wrapper(None, &mut line_buffer).unwrap();
+        let location = Location::with_stitch("hurry_home", "not_at_home");
+        assert!(story.move_to(&location).is_err());
+    }
 
-        let address = Address::from_parts_unchecked("tripoli", Some("cinema"));
+    #[test]
+    fn get_knot_tags_from_knot_name() {
+        let content = "
 
-        assert_eq!(story.current_address, address);
+== tripoli
+# country: Libya
+# capital
+-> END
+
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert_eq!(
+            &story.get_knot_tags("tripoli").unwrap(),
+            &["country: Libya".to_string(), "capital".to_string()]
+        );
     }
 
     #[test]
-    fn if_choice_list_returned_to_user_is_empty_follow_fallback_choice() {
+    fn getting_knot_tags_with_invalid_name_yields_none() {
         let content = "
-== knot
-*   Non-sticky choice -> knot
-*   ->
-    Fallback choice
+
+== tripoli
+# country: Libya
+# capital
+-> END
+
 ";
-        let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"knot".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        let story = read_story_from_string(content).unwrap();
+        assert!(story.get_knot_tags("addis_ababa").is_none());
+    }
+
+    #[test]
+    fn current_location_in_story_is_the_current_address() {
+        let content = "
+
+We arrived into Almaty at 9.45pm exactly.
+-> END
+
+== hurry_home
+= at_home
+We hurried home as fast as we could.
+-> END
+
+";
 
-        let choices = story
-            .resume(&mut line_buffer)
-            .unwrap()
-            .get_choices()
-            .unwrap();
+        let mut story = read_story_from_string(content).unwrap();
 
-        assert_eq!(choices.len(), 1);
+        assert_eq!(
+            story.get_current_location(),
+            Location::from(ROOT_KNOT_NAME),
+            // (ROOT_KNOT_NAME.to_string(), None)
+        );
 
-        story.make_choice(0).unwrap();
-        story.resume(&mut line_buffer).unwrap();
+        story.move_to(&"hurry_home".into()).unwrap();
 
-        assert_eq!(&line_buffer[1].text, "Fallback choice\n");
+        assert_eq!(
+            story.get_current_location(),
+            Location::with_stitch("hurry_home", "at_home"),
+            // ("hurry_home".to_string(), Some("at_home".to_string()))
+        );
     }
 
     #[test]
-    fn fallback_choices_resume_from_the_knot_they_are_encountered_in() {
+    fn getting_number_of_visits_uses_data() {
         let content = "
-== first
--> second
-
-== second
-+   ->
-    Fallback choice
-";
+== hurry_home
+We hurried home as fast as we could.
+-> END
 
-        let mut knots = read_knots_from_string(content).unwrap();
+= at_home
+Once back home we feasted on cheese.
+-> END
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+";
 
-        let current_address = Address::from_root_knot("first", &knots).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
 
-        let mut line_buffer = Vec::new();
-        let mut internal_buffer = Vec::new();
+        let location = Location::with_stitch("hurry_home", "at_home");
+        let address = Address::from_location(&location, &story.knots).unwrap();
 
-        follow_story(
-            &current_address,
-            &mut internal_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
-        process_buffer(&mut line_buffer, internal_buffer);
+        increment_num_visited(&address, &mut story.data).unwrap();
+        increment_num_visited(&address, &mut story.data).unwrap();
 
-        assert_eq!(&line_buffer[0].text, "Fallback choice\n");
+        assert_eq!(story.get_num_visited(&"hurry_home".into()).unwrap(), 0);
+        assert_eq!(story.get_num_visited(&location).unwrap(), 2);
     }
 
     #[test]
-    fn glue_is_followed_over_fallback_choices() {
+    fn getting_number_of_visits_yields_none_if_knot_or_stitch_name_is_invalid() {
         let content = "
-== tripoli
-We decided to go to the <>
-*   [] Cinema.
-";
 
-        let mut knots = read_knots_from_string(content).unwrap();
+We arrived into Almaty at 9.45pm exactly.
+-> END
 
-        let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+== hurry_home
+We hurried home as fast as we could.
+-> END
 
-        let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
+= at_home
+Once back home we feasted on cheese.
+-> END
 
-        let mut line_buffer = Vec::new();
-        let mut internal_buffer = Vec::new();
+";
 
-        follow_story(
-            &current_address,
-            &mut internal_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
-        process_buffer(&mut line_buffer, internal_buffer);
+        let story = read_story_from_string(content).unwrap();
 
-        assert_eq!(&line_buffer[0].text, "We decided to go to the ");
-        assert_eq!(&line_buffer[1].text, "Cinema.\n");
+        assert!(story.get_num_visited(&"fin".into()).is_none());
+        assert!(story
+            .get_num_visited(&Location::with_stitch("hurry_home", "fin"))
+            .is_none());
     }
 
     #[test]
-    fn if_no_fallback_choices_are_available_raise_error() {
+    fn setting_number_of_visits_replaces_the_count_in_data() {
         let content = "
-== knot
-*   Non-sticky choice -> knot
+== hurry_home
+We hurried home as fast as we could.
+-> END
+
+= at_home
+Once back home we feasted on cheese.
+-> END
+
 ";
 
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"knot".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        let location = Location::with_stitch("hurry_home", "at_home");
+        let address = Address::from_location(&location, &story.knots).unwrap();
 
-        story.resume(&mut line_buffer).unwrap();
+        story.set_num_visited(&location, 5).unwrap();
+        increment_num_visited(&address, &mut story.data).unwrap();
 
-        story.make_choice(0).unwrap();
+        assert_eq!(story.get_num_visited(&"hurry_home".into()).unwrap(), 0);
+        assert_eq!(story.get_num_visited(&location).unwrap(), 6);
 
-        match story.resume(&mut line_buffer) {
-            Err(InklingError::OutOfChoices { .. }) => (),
-            Err(err) => panic!("expected `OutOfChoices` error but got {:?}", err),
-            Ok(_) => panic!("expected an error but got an Ok"),
+        match story.set_num_visited(&Location::with_stitch("hurry_home", "fin"), 1) {
+            Err(InklingError::InvalidAddress { location }) => {
+                assert_eq!(location, Location::with_stitch("hurry_home", "fin"))
+            }
+            other => panic!(
+                "expected `InklingError::InvalidAddress` but got {:?}",
+                other
+            ),
         }
     }
 
     #[test]
-    fn last_set_of_presented_choices_are_stored() {
-        let content = "
-== knot
-*   Choice 1
-*   Choice 2
-";
+    fn visit_counts_of_addresses_which_are_not_in_the_data_yield_errors() {
+        let mut story = read_story_from_string("== hurry_home\n-> END").unwrap();
 
-        let mut story = read_story_from_string(content).unwrap();
-        let mut line_buffer = Vec::new();
+        let address = Address::Validated(AddressKind::Location {
+            knot: "back_in_almaty".to_string(),
+            stitch: ROOT_KNOT_NAME.to_string(),
+        });
 
-        assert!(story.last_choices.is_none());
+        assert!(get_num_visited(&address, &story.data).is_err());
+        assert!(increment_num_visited(&address, &mut story.data).is_err());
+        assert!(set_num_visited(&address, &mut story.data, 1).is_err());
+    }
 
-        story.move_to(&"knot".into()).unwrap();
-        story.resume(&mut line_buffer).unwrap();
+    #[test]
+    fn getting_variable_returns_cloned() {
+        let content = "
 
-        let last_choices = story.last_choices.as_ref().unwrap();
+VAR hazardous = true
 
-        assert_eq!(last_choices.len(), 2);
-        assert_eq!(&last_choices[0].text, "Choice 1");
-        assert_eq!(&last_choices[1].text, "Choice 2");
+";
+
+        let story = read_story_from_string(content).unwrap();
+
+        assert_eq!(
+            story.get_variable("hazardous").unwrap(),
+            Variable::Bool(true)
+        );
     }
 
     #[test]
-    fn when_a_knot_is_returned_to_the_text_starts_from_the_beginning() {
+    fn setting_variable_is_only_allowed_without_changing_type() {
         let content = "
-== back_in_almaty
-We arrived into Almaty at 9.45pm exactly.
--> hurry_home
 
-== hurry_home
-*   We hurried home as fast as we could. -> END
-*   But we decided our trip wasn't done and immediately left.
-    After a few days me returned again.
-    -> back_in_almaty
+VAR counter = 3
+
 ";
 
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"back_in_almaty".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
-
-        story.follow_story_wrapper(None, &mut line_buffer).unwrap();
-        story
-            .follow_story_wrapper(Some(1), &mut line_buffer)
-            .unwrap();
-
-        assert_eq!(
-            &line_buffer[0].text,
-            "We arrived into Almaty at 9.45pm exactly.\n"
-        );
+        story.set_variable("counter", Variable::Int(5)).unwrap();
         assert_eq!(
-            &line_buffer[3].text,
-            "We arrived into Almaty at 9.45pm exactly.\n"
+            story.data.variables.get("counter").unwrap().variable,
+            Variable::Int(5)
         );
+
+        assert!(story.set_variable("counter", Variable::Float(5.0)).is_err());
+        assert!(story.set_variable("counter", Variable::Bool(true)).is_err());
     }
 
     #[test]
-    fn when_the_story_begins_the_first_knot_gets_its_number_of_visits_set_to_one() {
+    fn setting_variable_is_only_allowed_for_non_const_variables() {
         let content = "
-Hello, World!
+
+VAR non_const_variable = 3
+CONST const_variable = 3
+
 ";
 
         let mut story = read_story_from_string(content).unwrap();
-        let mut line_buffer = Vec::new();
 
-        story.resume(&mut line_buffer).unwrap();
+        assert!(story
+            .set_variable("non_const_variable", Variable::Int(5))
+            .is_ok());
 
-        let address = Address::from_root_knot("$ROOT$", &story.knots).unwrap();
+        let err = story
+            .set_variable("const_variable", Variable::Int(5))
+            .unwrap_err();
+        let expected_err = InklingError::AssignedToConst {
+            name: "const_variable".to_string(),
+            meta_data: Some(MetaData::from(3)),
+        };
 
-        assert_eq!(get_num_visited(&address, &story.data).unwrap(), 1);
+        assert_eq!(format!("{:?}", err), format!("{:?}", expected_err));
     }
 
     #[test]
-    fn reading_story_from_string_initializes_all_knot_visit_counts_to_zero() {
+    fn setting_variable_can_infer_number_boolean_and_string_types() {
         let content = "
 
-== back_in_almaty
--> END
-
-== hurry_home
--> END
-= at_home
--> END
+VAR hazardous = false
+VAR counter = 3
+VAR precision = 1.23
+VAR message = \"boring text\"
 
 ";
 
-        let story = read_story_from_string(content).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
 
-        let back_in_almaty = Address::from_parts_unchecked("back_in_almaty", None);
-        let hurry_home = Address::from_parts_unchecked("hurry_home", None);
-        let at_home = Address::from_parts_unchecked("hurry_home", Some("at_home"));
+        assert!(story.set_variable("hazardous", true).is_ok());
+        assert!(story.set_variable("counter", -10).is_ok());
+        assert!(story.set_variable("precision", 5.45).is_ok());
+        assert!(story
+            .set_variable("message", "What a pleasure to see you!")
+            .is_ok());
 
-        assert_eq!(get_num_visited(&back_in_almaty, &story.data).unwrap(), 0);
-        assert_eq!(get_num_visited(&hurry_home, &story.data).unwrap(), 0);
-        assert_eq!(get_num_visited(&at_home, &story.data).unwrap(), 0);
+        assert_eq!(
+            story.data.variables.get("counter").unwrap().variable,
+            Variable::Int(-10)
+        );
+
+        assert_eq!(
+            story.data.variables.get("hazardous").unwrap().variable,
+            Variable::Bool(true)
+        );
+
+        assert_eq!(
+            story.data.variables.get("precision").unwrap().variable,
+            Variable::Float(5.45)
+        );
+
+        assert_eq!(
+            story.data.variables.get("message").unwrap().variable,
+            Variable::String("What a pleasure to see you!".to_string())
+        );
     }
 
     #[test]
-    fn reading_story_from_string_sets_global_tags() {
+    fn global_tags_can_be_retrieved() {
         let content = "
 
 # title: inkling
@@ -1313,7 +4665,7 @@ Hello, World!
         let story = read_story_from_string(content).unwrap();
 
         assert_eq!(
-            &story.tags,
+            &story.get_story_tags(),
             &[
                 "title: inkling".to_string(),
                 "author: Petter Johansson".to_string()
@@ -1322,40 +4674,67 @@ Hello, World!
     }
 
     #[test]
-    fn reading_story_from_string_sets_global_variables() {
-        let content = "
+    fn conditions_in_lines_are_recorded_in_the_order_they_are_written() {
+        let content = "\
+VAR a = true
+VAR b = false
+{a: {b: One|Two}|Three} {b: Four}
+{b: {a: Five}|Six} {a: Seven}
+";
 
-VAR counter = 0
-VAR hazardous = true
-VAR warning_message = \"ADVARSEL\"
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let coverage = story.get_condition_coverage();
+
+        let get_outcomes = |line_index: usize, index: usize| {
+            coverage
+                .get(&ConditionSite::Line(MetaData::from(line_index), index))
+                .map(|outcomes| (outcomes.num_true, outcomes.num_false))
+        };
+
+        assert_eq!(get_outcomes(2, 0), Some((1, 0)));
+        assert_eq!(get_outcomes(2, 1), Some((0, 1)));
+        assert_eq!(get_outcomes(2, 2), Some((0, 1)));
+
+        assert_eq!(get_outcomes(3, 0), Some((0, 1)));
+        assert_eq!(get_outcomes(3, 1), None);
+        assert_eq!(get_outcomes(3, 2), Some((1, 0)));
+    }
 
+    #[test]
+    fn choice_conditions_are_recorded_when_choices_are_presented() {
+        let content = "\
+VAR a = true
+*   {a} Choice
+*   Other choice
 ";
 
-        let story = read_story_from_string(content).unwrap();
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let variables = &story.data.variables;
-        assert_eq!(variables.len(), 3);
+        story.resume(&mut line_buffer).unwrap();
 
-        assert_eq!(variables.get("counter").unwrap().variable, Variable::Int(0));
-        assert_eq!(
-            variables.get("hazardous").unwrap().variable,
-            Variable::Bool(true)
-        );
+        let site = ConditionSite::Choice(MetaData::from(1));
+        let outcomes = story.get_condition_coverage().get(&site).unwrap();
 
-        assert_eq!(
-            variables.get("warning_message").unwrap().variable,
-            Variable::String("ADVARSEL".to_string())
-        );
+        assert_eq!(outcomes.num_true, 1);
+        assert_eq!(outcomes.num_false, 0);
     }
 
     #[test]
-    fn knots_with_non_default_root_stitch_gets_validated_addresses_that_point_to_them() {
-        let content = "
--> almaty
+    fn debug_state_string_lists_visited_knots_and_selected_branch() {
+        let content = "\
+CONST name = \"Sam\"
+-> hallway
 
-== almaty
-= back
-We arrived into Almaty at 9.45pm exactly.
+== hallway ==
+*   Open the door
+*   Wait
+
+== unvisited ==
 -> END
 ";
 
@@ -1363,67 +4742,130 @@ We arrived into Almaty at 9.45pm exactly.
         let mut line_buffer = Vec::new();
 
         story.resume(&mut line_buffer).unwrap();
+        story.make_choice(1).unwrap();
 
-        assert_eq!(
-            &line_buffer[0].text,
-            "We arrived into Almaty at 9.45pm exactly.\n"
-        );
+        let state = story.debug_state_string();
+
+        assert!(state.contains("location: hallway"));
+        assert!(state.contains("choices: none"));
+        assert!(state.contains("selected branch: 1"));
+        assert!(state.contains("    name = \"Sam\" (const)"));
+        assert!(state.contains("    hallway: 1"));
+        assert!(!state.contains("unvisited"));
+        assert!(state.contains("rng seed: "));
     }
 
     #[test]
-    fn number_of_visits_in_a_story_is_consistent() {
-        let content = "
-One
--> root
+    fn snapshot_contains_visit_counts_of_all_knots_and_stitches() {
+        let content = "\
+-> hallway
 
-== root
+== hallway ==
+-> END
 
-+   {visit_twice < 2} -> visit_twice
-+   {visit_twice >= 2} {visit_thrice < 3} -> visit_thrice
-*   [] -> END
+== cellar ==
+= stairs
+-> END
+";
 
-== visit_twice
-Two
--> root
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-== visit_thrice
-Three
--> root
+        story.resume(&mut line_buffer).unwrap();
+
+        let snapshot = story.snapshot();
+
+        assert_eq!(snapshot.visit_counts.len(), 2);
+        assert_eq!(snapshot.visit_counts[&Location::from("hallway")], 1);
+        assert_eq!(snapshot.visit_counts[&Location::from("cellar.stairs")], 0);
+    }
+
+    #[test]
+    fn choice_ids_are_numbered_in_written_order_within_their_stitch() {
+        let content = "
+*   Root choice -> tripoli.cinema
 
+== tripoli
+= cinema
+*   First
+    **  Nested -> END
+*   Second
+*   Third -> END
 ";
 
         let mut story = read_story_from_string(content).unwrap();
         let mut line_buffer = Vec::new();
 
-        story.resume(&mut line_buffer).unwrap();
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
+        assert_eq!(&choices[0].id, "#0");
 
-        let knots = &story.knots;
+        story.make_choice(0).unwrap();
 
-        let address_root = Address::from_root_knot("root", &knots).unwrap();
-        let address_twice = Address::from_root_knot("visit_twice", &knots).unwrap();
-        let address_thrice = Address::from_root_knot("visit_thrice", &knots).unwrap();
+        let ids = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap()
+            .into_iter()
+            .map(|choice| choice.id)
+            .collect::<Vec<_>>();
 
-        assert_eq!(get_num_visited(&address_twice, &story.data).unwrap(), 2);
-        assert_eq!(get_num_visited(&address_thrice, &story.data).unwrap(), 3);
-        assert_eq!(get_num_visited(&address_root, &story.data).unwrap(), 6);
+        assert_eq!(
+            ids,
+            &["tripoli.cinema#0", "tripoli.cinema#2", "tripoli.cinema#3"]
+        );
+
+        story.make_choice_by_id("tripoli.cinema#0").unwrap();
+
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
+        assert_eq!(&choices[0].id, "tripoli.cinema#1");
+
+        match story.make_choice_by_id("tripoli.cinema#3") {
+            Err(InklingError::InvalidChoiceId { id, .. }) => assert_eq!(&id, "tripoli.cinema#3"),
+            other => panic!(
+                "expected `InklingError::InvalidChoiceId` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
-    fn calling_resume_on_a_story_at_a_choice_returns_the_choice_again() {
+    fn choices_have_selection_display_and_raw_text() {
         let content = "
+*   Hello [back] right back at you!
+";
 
-== back_in_almaty
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-After an arduous journey we arrived back in Almaty.
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
 
-*   We hurried home as fast as we could.
-    -> END
-*   But we decided our trip wasn't done yet.
-    We immediately left the city.
+        assert_eq!(&choices[0].text, "Hello back");
+        assert_eq!(&choices[0].display_text, "Hello right back at you!");
+        assert_eq!(&choices[0].raw_text, "*   Hello [back] right back at you!");
+    }
 
+    #[test]
+    fn previewing_choice_display_text_does_not_advance_sequences() {
+        let content = "
+== greeting
++   [Wave] {Hi|Hello} there. -> greeting
 ";
+
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"back_in_almaty".into()).unwrap();
+        story.move_to(&"greeting".into()).unwrap();
 
         let mut line_buffer = Vec::new();
 
@@ -1432,427 +4874,674 @@ After an arduous journey we arrived back in Almaty.
             .unwrap()
             .get_choices()
             .unwrap();
+        assert_eq!(&choices[0].display_text, "Hi there.");
 
-        line_buffer.clear();
-        let resume_choices = story
+        let choices = story
             .resume(&mut line_buffer)
             .unwrap()
             .get_choices()
             .unwrap();
+        assert_eq!(&choices[0].display_text, "Hi there.");
 
-        assert_eq!(choices, resume_choices);
-        assert!(line_buffer.is_empty());
+        story.make_choice(0).unwrap();
+
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
+
+        assert_eq!(&line_buffer[0].text, "Hi there.\n");
+        assert_eq!(&choices[0].display_text, "Hello there.");
     }
 
     #[test]
-    fn calling_resume_on_a_story_at_a_choice_without_making_a_choice_does_not_update_the_story() {
-        let content = "
+    fn resuming_in_blocks_returns_only_the_lines_since_the_last_prompt() {
+        let content = "\
+=== hall ===
+The hall was cold. # cold
+*   [Go upstairs] -> attic
+
+=== attic ===
+The attic was warm. # warm
+Dust hung in the air.
+*   [Go back] -> hall
+";
 
-== back_in_almaty
+        let mut story = read_story_from_string(content).unwrap();
+        story.move_to(&"hall".into()).unwrap();
 
-After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
+        let block = story.resume_block().unwrap();
 
-*   We hurried home {as fast as we could|slowly}.
-    -> END
-*   But we decided our trip wasn't done yet.
-    We immediately left the city.
+        assert_eq!(block.lines.len(), 1);
+        assert_eq!(block.start, Location::from("hall"));
+        assert_eq!(block.end, Location::from("hall"));
+
+        story.make_choice(0).unwrap();
+
+        let block = story.resume_block().unwrap();
 
+        assert_eq!(block.lines.len(), 2);
+        assert_eq!(&block.lines[0].text, "The attic was warm.\n");
+        assert_eq!(&block.tags, &["warm".to_string()]);
+        assert_eq!(block.start, Location::from("hall"));
+        assert_eq!(block.end, Location::from("attic"));
+        assert_eq!(block.prompt.get_choices().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn all_variables_can_be_iterated_over_with_their_current_values() {
+        let content = "\
+VAR a = 1
+VAR b = \"two\"
+CONST c = true
 ";
+
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"back_in_almaty".into()).unwrap();
+        story.set_variable("a", 5).unwrap();
+
+        let mut variables = story.variables().collect::<Vec<_>>();
+        variables.sort_by_key(|(name, _)| *name);
+
+        assert_eq!(story.num_variables(), 3);
+        assert_eq!(
+            variables,
+            vec![
+                ("a", Variable::Int(5)),
+                ("b", Variable::String("two".to_string())),
+                ("c", Variable::Bool(true))
+            ]
+        );
+    }
+
+    #[test]
+    fn visit_journal_records_every_entered_location_with_its_turn() {
+        let content = "\
+-> square
+
+=== square ===
++   [Enter the chapel] -> chapel
+
+=== chapel ===
+= nave
++   [Leave] -> square
+";
 
+        let mut story = read_story_from_string(content).unwrap();
         let mut line_buffer = Vec::new();
 
         story.resume(&mut line_buffer).unwrap();
+        assert!(story.get_visit_journal().is_none());
 
-        let story_backup = story.clone();
-        story.resume(&mut line_buffer).unwrap();
+        story.enable_visit_journal();
 
-        assert_eq!(story, story_backup);
-    }
+        for _ in 0..3 {
+            story.make_choice(0).unwrap();
+            story.resume(&mut line_buffer).unwrap();
+        }
 
-    #[test]
-    fn story_can_be_moved_to_another_address() {
-        let content = "
+        let entries = story
+            .get_visit_journal()
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|entry| (entry.location.clone(), entry.turn))
+            .collect::<Vec<_>>();
 
-We arrived into Almaty at 9.45pm exactly.
--> END
+        assert_eq!(story.get_turn(), 3);
+        assert_eq!(
+            entries,
+            vec![
+                (Location::from("chapel.nave"), 1),
+                (Location::from("square"), 2),
+                (Location::from("chapel.nave"), 3)
+            ]
+        );
+    }
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+    #[test]
+    fn path_history_is_the_locations_of_the_visit_journal() {
+        let content = "\
+=== square ===
++   [Enter the chapel] -> chapel
 
+=== chapel ===
++   [Leave] -> square
 ";
 
         let mut story = read_story_from_string(content).unwrap();
         let mut line_buffer = Vec::new();
 
-        story.move_to(&"hurry_home".into()).unwrap();
+        assert!(story.path_history().is_none());
 
-        let address = story.current_address.clone();
-        assert_eq!(address.get_knot().unwrap(), "hurry_home");
-        assert_eq!(address.get_stitch().unwrap(), ROOT_KNOT_NAME);
+        story.enable_visit_journal();
+        assert_eq!(story.path_history(), Some(Vec::new()));
 
-        line_buffer.clear();
+        story.move_to(&"square".into()).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
         story.resume(&mut line_buffer).unwrap();
 
         assert_eq!(
-            &line_buffer[0].text,
-            "We hurried home as fast as we could.\n"
+            story.path_history().unwrap(),
+            vec![Location::from("square"), Location::from("chapel")]
         );
     }
 
+    #[cfg(feature = "random")]
     #[test]
-    fn moving_to_a_new_location_resets_current_choice_list() {
-        let content = "
+    fn determinism_audit_finds_shuffles_which_use_an_unseeded_generator() {
+        use crate::story::audit::NondeterminismKind;
 
-== back_in_almaty
+        let content = "\
+The die showed {~one|two|three}.
+";
 
-After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
+        let mut story = read_story_from_string(content).unwrap();
+        story.enable_determinism_audit();
 
-*   We hurried home {as fast as we could|slowly}.
-    -> END
-*   But we decided our trip wasn't done yet.
-    We immediately left the city.
+        let mut seeded = story.clone();
+        seeded.set_seed(13);
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+        story.resume(&mut Vec::new()).unwrap();
+        seeded.resume(&mut Vec::new()).unwrap();
+
+        let found = story.get_nondeterminism();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, NondeterminismKind::UnseededRandom);
+        assert_eq!(found[0].meta_data.as_ref().unwrap().line(), 1);
+
+        assert!(seeded.get_nondeterminism().is_empty());
+    }
+
+    #[test]
+    fn determinism_audit_finds_variables_read_from_the_host() {
+        use crate::story::{audit::NondeterminismKind, external::VariableStore};
+        use std::sync::{Arc, Mutex};
+
+        struct Clock;
+
+        impl VariableStore for Clock {
+            fn get_variable(&self, name: &str) -> Option<Variable> {
+                match name {
+                    "hour" => Some(Variable::Int(23)),
+                    _ => None,
+                }
+            }
+
+            fn set_variable(&mut self, _: &str, _: Variable) {}
+        }
+
+        let content = "\
+VAR hour = 12
+VAR day = 1
+It was {hour} o'clock on day {day}.
 ";
+
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"back_in_almaty".into()).unwrap();
+        story.set_variable_store(Arc::new(Mutex::new(Clock)));
+        story.enable_determinism_audit();
 
         let mut line_buffer = Vec::new();
         story.resume(&mut line_buffer).unwrap();
 
-        story.move_to(&"hurry_home".into()).unwrap();
-
-        assert!(story.last_choices.is_none());
+        assert_eq!(&line_buffer[0].text, "It was 23 o'clock on day 1.\n");
+        assert_eq!(
+            story
+                .get_nondeterminism()
+                .into_iter()
+                .map(|item| item.kind)
+                .collect::<Vec<_>>(),
+            vec![NondeterminismKind::ExternalVariable {
+                name: "hour".to_string()
+            }]
+        );
     }
 
     #[test]
-    fn moving_to_a_new_location_resets_selected_choice() {
-        let content = "
+    fn story_returns_early_at_diverts_after_the_line_limit_and_continues_from_their_target() {
+        let content = "\
+-> corridor
+
+=== corridor ===
+Door one.
+Door two.
+-> hall
+
+=== hall ===
+Door three.
+*   [Open a door] -> END
+";
 
-== back_in_almaty
+        let mut story = read_story_from_string(content).unwrap();
+        story.set_max_lines_per_resume(Some(2));
 
-After an arduous journey we arrived back in {Almaty|Addis Ababa|Tripoli}.
+        let mut line_buffer = Vec::new();
 
-*   We hurried home {as fast as we could|slowly}.
-    -> END
-*   But we decided our trip wasn't done yet.
-    We immediately left the city.
+        assert_eq!(story.resume(&mut line_buffer).unwrap(), Prompt::Continue);
+        assert_eq!(line_buffer.len(), 2);
+        assert_eq!(story.get_current_location(), Location::from("hall"));
+        assert_eq!(story.get_num_visited(&Location::from("hall")), Some(0));
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+        let prompt = story.resume(&mut line_buffer).unwrap();
+
+        assert!(prompt.get_choices().is_some());
+        assert_eq!(&line_buffer[2].text, "Door three.\n");
+    }
+
+    #[test]
+    fn story_does_not_return_early_if_the_last_line_is_glued_to_the_next() {
+        let content = "\
+Hello <>
+-> world
+
+=== world ===
+world!
 ";
+
         let mut story = read_story_from_string(content).unwrap();
-        story.move_to(&"back_in_almaty".into()).unwrap();
+        story.set_max_lines_per_resume(Some(1));
 
         let mut line_buffer = Vec::new();
-        story.resume(&mut line_buffer).unwrap();
-        story.make_choice(0).unwrap();
-
-        story.move_to(&"hurry_home".into()).unwrap();
 
-        assert!(story.selected_choice.is_none());
+        assert_eq!(
+            story.resume(&mut line_buffer).unwrap(),
+            Prompt::Done(EndingKind::DeadEnd)
+        );
+        assert_eq!(&line_buffer[0].text, "Hello ");
+        assert_eq!(&line_buffer[1].text, "world!\n");
     }
 
     #[test]
-    fn move_to_addresses_can_include_stitches() {
-        let content = "
-
-We arrived into Almaty at 9.45pm exactly.
--> END
+    fn story_returns_early_at_lines_which_end_in_diverts_and_keeps_their_glue() {
+        let content = "\
+-> corridor
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+=== corridor ===
+You walk down the corridor -> corridor_end
 
-= at_home
-Once back home we feasted on cheese.
--> END
+=== corridor_end ===
+and reach a door. -> hall
 
+=== hall ===
+*   [Open the door] -> END
 ";
 
         let mut story = read_story_from_string(content).unwrap();
+        story.set_max_lines_per_resume(Some(1));
+
         let mut line_buffer = Vec::new();
 
-        let location = Location::with_stitch("hurry_home", "at_home");
-        story.move_to(&location).unwrap();
+        assert_eq!(story.resume(&mut line_buffer).unwrap(), Prompt::Continue);
+        assert_eq!(line_buffer.len(), 1);
+        assert_eq!(&line_buffer[0].text, "You walk down the corridor ");
+        assert!(line_buffer[0].glue_end);
+        assert_eq!(story.get_current_location(), Location::from("corridor_end"));
 
-        let address = story.current_address.clone();
-        assert_eq!(address.get_knot().unwrap(), "hurry_home");
-        assert_eq!(address.get_stitch().unwrap(), "at_home");
+        assert_eq!(story.resume(&mut line_buffer).unwrap(), Prompt::Continue);
+        assert_eq!(line_buffer.len(), 2);
+        assert_eq!(&line_buffer[1].text, "and reach a door. ");
+        assert!(line_buffer[1].glue_end);
 
-        line_buffer.clear();
-        story.resume(&mut line_buffer).unwrap();
+        let prompt = story.resume(&mut line_buffer).unwrap();
 
-        assert_eq!(
-            &line_buffer[0].text,
-            "Once back home we feasted on cheese.\n"
-        );
+        assert!(prompt.get_choices().is_some());
+        assert_eq!(line_buffer.len(), 2);
     }
 
     #[test]
-    fn move_to_yields_error_if_knot_or_stitch_name_is_invalid() {
-        let content = "
+    fn lines_which_divert_to_themselves_are_returned_in_parts_of_the_line_limit() {
+        let content = "\
+-> tick
 
-We arrived into Almaty at 9.45pm exactly.
--> END
+=== tick ===
+{tick > 10: -> END}
+Tick -> tick
+";
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+        let mut story = read_story_from_string(content).unwrap();
+        story.set_max_lines_per_resume(Some(4));
 
-= at_home
-Once back home we feasted on cheese.
--> END
+        let mut line_buffer = Vec::new();
+        let mut num_lines_per_call = Vec::new();
+
+        while let Prompt::Continue = story.resume(&mut line_buffer).unwrap() {
+            num_lines_per_call.push(line_buffer.len());
+        }
+
+        assert_eq!(&num_lines_per_call, &[4, 8]);
+        assert_eq!(line_buffer.len(), 10);
+        assert!(line_buffer.iter().all(|line| line.text.starts_with("Tick")));
+    }
 
+    #[test]
+    fn stepping_through_the_story_returns_a_single_line_per_call() {
+        let content = "\
+One.
+Two.
+-> next
+
+=== next ===
+Three.
+*   Choice -> END
 ";
 
         let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        assert!(story.move_to(&"fin".into()).is_err());
+        assert_eq!(
+            story.resume_step(&mut line_buffer).unwrap(),
+            Prompt::Continue
+        );
+        assert_eq!(line_buffer.len(), 1);
+        assert_eq!(story.stepped_lines.len(), 1);
 
-        let location = Location::with_stitch("hurry_home", "not_at_home");
-        assert!(story.move_to(&location).is_err());
-    }
+        assert_eq!(
+            story.resume_step(&mut line_buffer).unwrap(),
+            Prompt::Continue
+        );
+        assert_eq!(line_buffer.len(), 2);
+        assert!(story.stepped_lines.is_empty());
 
-    #[test]
-    fn get_knot_tags_from_knot_name() {
-        let content = "
+        let choices = story
+            .resume_step(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
 
-== tripoli
-# country: Libya
-# capital
--> END
+        assert_eq!(line_buffer.len(), 3);
+        assert_eq!(&line_buffer[2].text, "Three.\n");
+        assert_eq!(&choices[0].text, "Choice");
+        assert!(!story.can_continue());
 
-";
+        let prompt = story.resume_step(&mut line_buffer).unwrap();
 
-        let story = read_story_from_string(content).unwrap();
+        assert_eq!(line_buffer.len(), 3);
+        assert_eq!(prompt.get_choices().unwrap(), choices);
+
+        story.make_choice(0).unwrap();
 
         assert_eq!(
-            &story.get_knot_tags("tripoli").unwrap(),
-            &["country: Libya".to_string(), "capital".to_string()]
+            story.resume_step(&mut line_buffer).unwrap(),
+            Prompt::Done(EndingKind::End)
         );
+        assert_eq!(&line_buffer[3].text, "Choice\n");
     }
 
     #[test]
-    fn getting_knot_tags_with_invalid_name_yields_none() {
-        let content = "
-
-== tripoli
-# country: Libya
-# capital
--> END
+    fn stepping_does_not_change_the_max_lines_per_resume_of_the_story() {
+        let mut story = read_story_from_string("One.\nTwo.").unwrap();
+        story.set_max_lines_per_resume(Some(5));
 
-";
+        story.resume_step(&mut Vec::new()).unwrap();
 
-        let story = read_story_from_string(content).unwrap();
-        assert!(story.get_knot_tags("addis_ababa").is_none());
+        assert_eq!(story.data.max_lines_per_resume, Some(5));
     }
 
     #[test]
-    fn current_location_in_story_is_the_current_address() {
-        let content = "
+    fn lines_which_are_left_from_stepping_are_returned_first_by_resume_and_peek() {
+        let content = "\
+One.
+Two.
+Three.
+";
 
-We arrived into Almaty at 9.45pm exactly.
--> END
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-== hurry_home
-= at_home
-We hurried home as fast as we could.
--> END
+        story.resume_step(&mut line_buffer).unwrap();
 
-";
+        assert!(story.can_continue());
+        assert_eq!(&story.peek_line().unwrap().unwrap().text, "Two.\n");
 
-        let mut story = read_story_from_string(content).unwrap();
+        line_buffer.clear();
 
         assert_eq!(
-            story.get_current_location(),
-            Location::from(ROOT_KNOT_NAME),
-            // (ROOT_KNOT_NAME.to_string(), None)
+            story.resume(&mut line_buffer).unwrap(),
+            Prompt::Done(EndingKind::DeadEnd)
         );
+        assert_eq!(line_buffer.len(), 2);
+        assert_eq!(&line_buffer[0].text, "Two.\n");
+        assert_eq!(&line_buffer[1].text, "Three.\n");
+        assert!(story.stepped_lines.is_empty());
+    }
 
-        story.move_to(&"hurry_home".into()).unwrap();
+    #[test]
+    fn stepping_notifies_observers_of_changes_up_to_the_next_divert_with_the_first_line() {
+        use std::sync::Mutex;
+
+        let content = "\
+VAR gold = 0
+You open the chest.
+~ gold = 10
+You count the coins.
+-> counted
+
+=== counted ===
+~ gold = 20
+You find some more.
+-> END
+";
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let observed = changes.clone();
+
+        let mut story = read_story_from_string(content).unwrap();
+        story
+            .observe_variable("gold", move |change| {
+                observed.lock().unwrap().push(change.new.clone())
+            })
+            .unwrap();
+
+        let mut line_buffer = Vec::new();
+        let mut num_changes_per_step = Vec::new();
+
+        while story.resume_step(&mut line_buffer).unwrap() == Prompt::Continue {
+            num_changes_per_step.push(changes.lock().unwrap().len());
+        }
+
+        num_changes_per_step.push(changes.lock().unwrap().len());
 
+        assert_eq!(line_buffer.len(), 3);
+        assert_eq!(&line_buffer[1].text, "You count the coins.\n");
+        assert_eq!(&num_changes_per_step, &[1, 1, 2]);
         assert_eq!(
-            story.get_current_location(),
-            Location::with_stitch("hurry_home", "at_home"),
-            // ("hurry_home".to_string(), Some("at_home".to_string()))
+            changes.lock().unwrap().as_slice(),
+            &[Variable::Int(10), Variable::Int(20)]
         );
     }
 
     #[test]
-    fn getting_number_of_visits_uses_data() {
-        let content = "
-== hurry_home
-We hurried home as fast as we could.
+    fn moving_the_story_drops_lines_which_are_left_from_stepping() {
+        let content = "\
+One.
+Two.
 -> END
 
-= at_home
-Once back home we feasted on cheese.
+=== knot ===
+Knot.
 -> END
-
 ";
 
         let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
 
-        let location = Location::with_stitch("hurry_home", "at_home");
-        let address = Address::from_location(&location, &story.knots).unwrap();
+        story.resume_step(&mut line_buffer).unwrap();
+        story.move_to(&"knot".into()).unwrap();
 
-        increment_num_visited(&address, &mut story.data).unwrap();
-        increment_num_visited(&address, &mut story.data).unwrap();
+        line_buffer.clear();
+        story.resume_step(&mut line_buffer).unwrap();
 
-        assert_eq!(story.get_num_visited(&"hurry_home".into()).unwrap(), 0);
-        assert_eq!(story.get_num_visited(&location).unwrap(), 2);
+        assert_eq!(&line_buffer[0].text, "Knot.\n");
     }
 
     #[test]
-    fn getting_number_of_visits_yields_none_if_knot_or_stitch_name_is_invalid() {
-        let content = "
+    #[cfg(feature = "math")]
+    fn peeking_lines_does_not_change_the_state_of_the_story() {
+        let content = "\
+VAR gold = 0
+~ gold = gold + 5
+You have {gold} gold.
+-> hall
+
+=== hall ===
++   [{&Knock|Bang} on the door] -> hall
+";
 
-We arrived into Almaty at 9.45pm exactly.
--> END
+        let mut story = read_story_from_string(content).unwrap();
+        let original = story.clone();
 
-== hurry_home
-We hurried home as fast as we could.
--> END
+        let line = story.peek_line().unwrap().unwrap();
 
-= at_home
-Once back home we feasted on cheese.
--> END
+        assert_eq!(&line.text, "You have 5 gold.\n");
+        assert_eq!(story, original);
 
-";
+        let mut line_buffer = Vec::new();
+        story.resume(&mut line_buffer).unwrap();
 
-        let story = read_story_from_string(content).unwrap();
+        assert_eq!(line_buffer, vec![line]);
 
-        assert!(story.get_num_visited(&"fin".into()).is_none());
-        assert!(story
-            .get_num_visited(&Location::with_stitch("hurry_home", "fin"))
-            .is_none());
+        story.make_choice(0).unwrap();
+        assert!(story.peek_line().unwrap().is_none());
+
+        let choices = story
+            .resume(&mut line_buffer)
+            .unwrap()
+            .get_choices()
+            .unwrap();
+
+        assert_eq!(&choices[0].text, "Bang on the door");
+        assert_eq!(story.get_variable("gold"), Some(Variable::Int(5)));
     }
 
     #[test]
-    fn getting_variable_returns_cloned() {
-        let content = "
+    #[cfg(feature = "math")]
+    fn peeking_lines_does_not_assign_to_the_variable_store() {
+        use crate::story::external::VariableStore;
+        use std::sync::Mutex;
 
-VAR hazardous = true
+        struct Purse(i32);
 
+        impl VariableStore for Purse {
+            fn get_variable(&self, _: &str) -> Option<Variable> {
+                Some(Variable::Int(self.0))
+            }
+
+            fn set_variable(&mut self, _: &str, value: Variable) {
+                if let Variable::Int(gold) = value {
+                    self.0 = gold;
+                }
+            }
+        }
+
+        let content = "\
+VAR gold = 0
+~ gold = gold + 5
+You have {gold} gold.
 ";
 
-        let story = read_story_from_string(content).unwrap();
+        let purse = Arc::new(Mutex::new(Purse(10)));
 
-        assert_eq!(
-            story.get_variable("hazardous").unwrap(),
-            Variable::Bool(true)
-        );
+        let mut story = read_story_from_string(content).unwrap();
+        story.set_variable_store(purse.clone());
+
+        let line = story.peek_line().unwrap().unwrap();
+
+        assert_eq!(&line.text, "You have 15 gold.\n");
+        assert_eq!(purse.lock().unwrap().0, 10);
     }
 
     #[test]
-    fn setting_variable_is_only_allowed_without_changing_type() {
-        let content = "
-
-VAR counter = 3
+    fn polling_a_story_which_diverts_forever_returns_after_every_divert() {
+        let content = "\
+-> spin
 
+=== spin ===
+-> spin
 ";
 
         let mut story = read_story_from_string(content).unwrap();
 
-        story.set_variable("counter", Variable::Int(5)).unwrap();
-        assert_eq!(
-            story.data.variables.get("counter").unwrap().variable,
-            Variable::Int(5)
-        );
+        for _ in 0..100 {
+            assert_eq!(story.poll().unwrap(), Poll::Pending);
+        }
 
-        assert!(story.set_variable("counter", Variable::Float(5.0)).is_err());
-        assert!(story.set_variable("counter", Variable::Bool(true)).is_err());
+        // The first call follows the root knot into `spin`
+        assert_eq!(story.get_num_visited(&Location::from("spin")), Some(99));
     }
 
     #[test]
-    fn setting_variable_is_only_allowed_for_non_const_variables() {
-        let content = "
-
-VAR non_const_variable = 3
-CONST const_variable = 3
+    fn polling_after_the_end_returns_done_until_the_story_is_moved() {
+        let content = "\
+The end.
 
+=== epilogue ===
+After the end.
 ";
 
         let mut story = read_story_from_string(content).unwrap();
 
-        assert!(story
-            .set_variable("non_const_variable", Variable::Int(5))
-            .is_ok());
+        assert!(matches!(story.poll().unwrap(), Poll::Lines(..)));
+        assert_eq!(story.poll().unwrap(), Poll::Done);
+        assert_eq!(story.poll().unwrap(), Poll::Done);
 
-        let err = story
-            .set_variable("const_variable", Variable::Int(5))
-            .unwrap_err();
-        let expected_err = InklingError::AssignedToConst {
-            name: "const_variable".to_string(),
-        };
+        story.move_to(&Location::from("epilogue")).unwrap();
 
-        assert_eq!(format!("{:?}", err), format!("{:?}", expected_err));
+        match story.poll().unwrap() {
+            Poll::Lines(block) => {
+                assert_eq!(&block.lines[0].text, "After the end.\n");
+                assert_eq!(block.prompt, Prompt::Done(EndingKind::DeadEnd));
+            }
+            other => panic!("expected lines but got {:?}", other),
+        }
     }
 
     #[test]
-    fn setting_variable_can_infer_number_boolean_and_string_types() {
-        let content = "
-
-VAR hazardous = false
-VAR counter = 3
-VAR precision = 1.23
-VAR message = \"boring text\"
-
+    #[cfg(feature = "debug")]
+    fn stepping_through_timeline_restores_visit_counts() {
+        let content = "\
+A coin lay on the floor.
+*   [Take it] -> purse
+
+=== purse ===
+Clink.
+-> END
 ";
 
         let mut story = read_story_from_string(content).unwrap();
+        story.enable_timeline(5);
 
-        assert!(story.set_variable("hazardous", true).is_ok());
-        assert!(story.set_variable("counter", -10).is_ok());
-        assert!(story.set_variable("precision", 5.45).is_ok());
-        assert!(story
-            .set_variable("message", "What a pleasure to see you!")
-            .is_ok());
+        let mut line_buffer = Vec::new();
 
-        assert_eq!(
-            story.data.variables.get("counter").unwrap().variable,
-            Variable::Int(-10)
-        );
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
 
-        assert_eq!(
-            story.data.variables.get("hazardous").unwrap().variable,
-            Variable::Bool(true)
-        );
+        let purse = Location::from("purse");
+        assert_eq!(story.get_num_visited(&purse), Some(1));
 
-        assert_eq!(
-            story.data.variables.get("precision").unwrap().variable,
-            Variable::Float(5.45)
-        );
+        let entry = story.step_back().unwrap();
 
-        assert_eq!(
-            story.data.variables.get("message").unwrap().variable,
-            Variable::String("What a pleasure to see you!".to_string())
-        );
-    }
+        assert_eq!(entry.snapshot.visit_counts[&purse], 0);
+        assert!(entry.prompt.get_choices().is_some());
+        assert_eq!(story.get_num_visited(&purse), Some(0));
+        assert!(story.step_back().is_none());
 
-    #[test]
-    fn global_tags_can_be_retrieved() {
-        let content = "
+        let entry = story.step_forward().unwrap();
 
-# title: inkling
-# author: Petter Johansson
+        assert_eq!(entry.prompt, Prompt::Done(EndingKind::End));
+        assert_eq!(story.get_num_visited(&purse), Some(1));
+        assert!(story.step_forward().is_none());
 
-";
-        let story = read_story_from_string(content).unwrap();
+        story.step_to(0).unwrap();
+        story.make_choice(0).unwrap();
 
-        assert_eq!(
-            &story.get_story_tags(),
-            &[
-                "title: inkling".to_string(),
-                "author: Petter Johansson".to_string()
-            ]
-        );
+        line_buffer.clear();
+        story.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[0].text, "Clink.\n");
+        assert_eq!(story.get_num_visited(&purse), Some(1));
+        assert_eq!(story.get_timeline().unwrap().len(), 2);
     }
 }
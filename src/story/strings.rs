@@ -0,0 +1,592 @@
+//! Deduplication of strings when a story is serialized.
+//!
+//! Names of knots and stitches, addresses and file names are repeated many times in
+//! the content of a story. When a full `Story` is serialized we first collect all such
+//! strings into a table, which is written before the rest of the data. The strings
+//! themselves are then written as indices into that table.
+//!
+//! The table is kept in a thread local variable while the story is de/serialized, since
+//! the fields which hold the strings are nested deep inside of the story. Fields marked
+//! to be deduplicated use the functions of this module through `#[serde(with)]`. When
+//! no table is active, for example when only part of a story is serialized, the strings
+//! are written as usual.
+
+use serde::{
+    de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{self, Serialize, SerializeStruct, Serializer},
+};
+
+use std::{cell::RefCell, collections::HashMap, fmt, hash::Hash};
+
+/// Table of strings which is active while a value is being de/serialized.
+enum StringTable {
+    /// Strings are added to the table as they are seen.
+    Collecting {
+        indices: HashMap<String, u32>,
+        strings: Vec<String>,
+    },
+    /// Finished table which strings are looked up in.
+    Writing { indices: HashMap<String, u32> },
+    /// Table which strings are read from.
+    Reading { strings: Vec<String> },
+}
+
+thread_local! {
+    static TABLE: RefCell<Option<StringTable>> = const { RefCell::new(None) };
+}
+
+/// Guard which restores the previously active table when dropped.
+struct TableGuard(Option<StringTable>);
+
+impl Drop for TableGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        TABLE.with(|table| *table.borrow_mut() = previous);
+    }
+}
+
+/// Activate a table until the returned guard is dropped.
+fn set_table(table: StringTable) -> TableGuard {
+    TableGuard(TABLE.with(|active| active.replace(Some(table))))
+}
+
+/// Take the collected strings from the active table.
+fn take_collected_strings() -> Vec<String> {
+    TABLE.with(|table| match table.borrow_mut().as_mut() {
+        Some(StringTable::Collecting { strings, .. }) => std::mem::take(strings),
+        _ => Vec::new(),
+    })
+}
+
+/// Serialize a value as a table of its deduplicated strings followed by the value itself.
+///
+/// The value is serialized twice: once to collect the strings and once to write it.
+pub fn serialize_deduplicated<V: Serialize, S: Serializer>(
+    value: &V,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let strings = {
+        let _guard = set_table(StringTable::Collecting {
+            indices: HashMap::new(),
+            strings: Vec::new(),
+        });
+
+        value.serialize(Discard).map_err(ser::Error::custom)?;
+
+        take_collected_strings()
+    };
+
+    let indices = strings
+        .iter()
+        .enumerate()
+        .map(|(i, string)| (string.clone(), i as u32))
+        .collect();
+
+    let mut state = serializer.serialize_struct("Deduplicated", 2)?;
+    state.serialize_field("strings", &strings)?;
+
+    let _guard = set_table(StringTable::Writing { indices });
+    state.serialize_field("content", value)?;
+
+    state.end()
+}
+
+/// Deserialize a value which was serialized with [`serialize_deduplicated`].
+pub fn deserialize_deduplicated<'de, V, D>(deserializer: D) -> Result<V, D::Error>
+where
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    const FIELDS: &[&str] = &["strings", "content"];
+
+    struct DeduplicatedVisitor<V>(std::marker::PhantomData<V>);
+
+    impl<'de, V: Deserialize<'de>> Visitor<'de> for DeduplicatedVisitor<V> {
+        type Value = V;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a table of strings followed by the content")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<V, A::Error> {
+            let strings = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+            let _guard = set_table(StringTable::Reading { strings });
+
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<V, A::Error> {
+            let mut guard = None;
+            let mut content = None;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "strings" if guard.is_none() => {
+                        let strings = map.next_value()?;
+                        guard.replace(set_table(StringTable::Reading { strings }));
+                    }
+                    "strings" => return Err(de::Error::duplicate_field("strings")),
+                    "content" if guard.is_none() => {
+                        return Err(de::Error::custom(
+                            "the table of strings must come before the content",
+                        ))
+                    }
+                    "content" => {
+                        content.replace(map.next_value()?);
+                    }
+                    other => return Err(de::Error::unknown_field(other, FIELDS)),
+                }
+            }
+
+            content.ok_or_else(|| de::Error::missing_field("content"))
+        }
+    }
+
+    deserializer.deserialize_struct(
+        "Deduplicated",
+        FIELDS,
+        DeduplicatedVisitor(std::marker::PhantomData),
+    )
+}
+
+/// Serialize a string as an index into the active table, or as itself if no table is active.
+pub fn serialize<S: Serializer>(string: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    let index = TABLE.with(|table| match table.borrow_mut().as_mut() {
+        Some(StringTable::Collecting { indices, strings }) => {
+            let next_index = strings.len() as u32;
+
+            let index = *indices.entry(string.to_string()).or_insert_with(|| {
+                strings.push(string.to_string());
+                next_index
+            });
+
+            Ok(Some(index))
+        }
+        Some(StringTable::Writing { indices }) => indices
+            .get(string)
+            .copied()
+            .map(Some)
+            .ok_or_else(|| format!("string '{}' is missing from the table", string)),
+        Some(StringTable::Reading { .. }) | None => Ok(None),
+    });
+
+    match index.map_err(ser::Error::custom)? {
+        Some(index) => serializer.serialize_u32(index),
+        None => serializer.serialize_str(string),
+    }
+}
+
+/// Deserialize a string from an index into the active table, or as itself if no table is active.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let is_reading =
+        TABLE.with(|table| matches!(table.borrow().as_ref(), Some(StringTable::Reading { .. })));
+
+    if !is_reading {
+        return String::deserialize(deserializer);
+    }
+
+    let index = u32::deserialize(deserializer)?;
+
+    TABLE
+        .with(|table| match table.borrow().as_ref() {
+            Some(StringTable::Reading { strings }) => strings.get(index as usize).cloned(),
+            _ => None,
+        })
+        .ok_or_else(|| de::Error::custom(format!("string index {} is not in the table", index)))
+}
+
+/// Wrapper to serialize a string through the table.
+struct Interned<'a>(&'a str);
+
+impl Serialize for Interned<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+/// Wrapper to deserialize a string through the table.
+struct InternedString(String);
+
+impl<'de> Deserialize<'de> for InternedString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(InternedString)
+    }
+}
+
+/// Deduplicate optional strings.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        string: &Option<String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match string {
+            Some(string) => serializer.serialize_some(&Interned(string)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        Option::<InternedString>::deserialize(deserializer)
+            .map(|string| string.map(|InternedString(string)| string))
+    }
+}
+
+/// Deduplicate the keys of maps.
+pub mod map {
+    use super::*;
+
+    pub fn serialize<V: Serialize, S: Serializer>(
+        map: &HashMap<String, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (Interned(key), value)))
+    }
+
+    pub fn deserialize<'de, V, D>(deserializer: D) -> Result<HashMap<String, V>, D::Error>
+    where
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        HashMap::<InternedString, V>::deserialize(deserializer).map(|map| {
+            map.into_iter()
+                .map(|(InternedString(key), value)| (key, value))
+                .collect()
+        })
+    }
+}
+
+#[derive(Debug)]
+/// Error from the serializer which discards its output.
+struct DiscardError(String);
+
+impl fmt::Display for DiscardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for DiscardError {}
+
+impl ser::Error for DiscardError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DiscardError(msg.to_string())
+    }
+}
+
+/// Serializer which visits all data but discards it, used to collect the strings.
+struct Discard;
+
+macro_rules! discard_values {
+    ($($method:ident: $ty:ty),* $(,)?) => {
+        $(
+            fn $method(self, _: $ty) -> Result<(), DiscardError> {
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl Serializer for Discard {
+    type Ok = ();
+    type Error = DiscardError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    discard_values!(
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str,
+        serialize_bytes: &[u8],
+        serialize_unit_struct: &'static str,
+    );
+
+    fn serialize_none(self) -> Result<(), DiscardError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), DiscardError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), DiscardError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), DiscardError> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), DiscardError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), DiscardError> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, DiscardError> {
+        Ok(self)
+    }
+}
+
+macro_rules! discard_compound {
+    ($($trait:ident: $method:ident),* $(,)?) => {
+        $(
+            impl ser::$trait for Discard {
+                type Ok = ();
+                type Error = DiscardError;
+
+                fn $method<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), DiscardError> {
+                    value.serialize(Discard)
+                }
+
+                fn end(self) -> Result<(), DiscardError> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+discard_compound!(
+    SerializeSeq: serialize_element,
+    SerializeTuple: serialize_element,
+    SerializeTupleStruct: serialize_field,
+    SerializeTupleVariant: serialize_field,
+);
+
+impl ser::SerializeMap for Discard {
+    type Ok = ();
+    type Error = DiscardError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), DiscardError> {
+        key.serialize(Discard)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), DiscardError> {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> Result<(), DiscardError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Discard {
+    type Ok = ();
+    type Error = DiscardError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), DiscardError> {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> Result<(), DiscardError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for Discard {
+    type Ok = ();
+    type Error = DiscardError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), DiscardError> {
+        value.serialize(Discard)
+    }
+
+    fn end(self) -> Result<(), DiscardError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::{Deserialize, Serialize};
+    use serde_test::{assert_tokens, Token};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Names {
+        #[serde(with = "super")]
+        first: String,
+        #[serde(with = "super")]
+        second: String,
+        #[serde(with = "super::option")]
+        third: Option<String>,
+        #[serde(with = "super::map")]
+        counts: HashMap<String, u32>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Deduplicated(Names);
+
+    impl Serialize for Deduplicated {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_deduplicated(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Deduplicated {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_deduplicated(deserializer).map(Deduplicated)
+        }
+    }
+
+    fn get_names() -> Names {
+        let mut counts = HashMap::new();
+        counts.insert("repeated".to_string(), 3);
+
+        Names {
+            first: "repeated".to_string(),
+            second: "unique".to_string(),
+            third: Some("repeated".to_string()),
+            counts,
+        }
+    }
+
+    #[test]
+    fn repeated_strings_are_written_once_in_the_table_and_referred_to_by_index() {
+        assert_tokens(
+            &Deduplicated(get_names()),
+            &[
+                Token::Struct {
+                    name: "Deduplicated",
+                    len: 2,
+                },
+                Token::Str("strings"),
+                Token::Seq { len: Some(2) },
+                Token::Str("repeated"),
+                Token::Str("unique"),
+                Token::SeqEnd,
+                Token::Str("content"),
+                Token::Struct {
+                    name: "Names",
+                    len: 4,
+                },
+                Token::Str("first"),
+                Token::U32(0),
+                Token::Str("second"),
+                Token::U32(1),
+                Token::Str("third"),
+                Token::Some,
+                Token::U32(0),
+                Token::Str("counts"),
+                Token::Map { len: Some(1) },
+                Token::U32(0),
+                Token::U32(3),
+                Token::MapEnd,
+                Token::StructEnd,
+                Token::StructEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn strings_are_written_as_is_without_an_active_table() {
+        assert_tokens(
+            &get_names(),
+            &[
+                Token::Struct {
+                    name: "Names",
+                    len: 4,
+                },
+                Token::Str("first"),
+                Token::Str("repeated"),
+                Token::Str("second"),
+                Token::Str("unique"),
+                Token::Str("third"),
+                Token::Some,
+                Token::Str("repeated"),
+                Token::Str("counts"),
+                Token::Map { len: Some(1) },
+                Token::Str("repeated"),
+                Token::U32(3),
+                Token::MapEnd,
+                Token::StructEnd,
+            ],
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Parsing of tags and search of the tags which are set to content in a story.
+
+use crate::{
+    consts::ROOT_KNOT_NAME,
+    error::utils::MetaData,
+    knot::KnotSet,
+    line::InternalLine,
+    node::NodeItem,
+    story::{export::compare_content, types::Location},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Tag which is written as a `key: value` pair.
+///
+/// Tags are plain text in `Ink`, but are commonly used as metadata by writing them
+/// as a key and a value, like `# author: Natsume Sōseki`. Parse them with
+/// [`parse_key_value`][Tag::parse_key_value()].
+pub struct Tag {
+    /// Key of the tag, before the colon.
+    pub key: String,
+    /// Value of the tag, after the colon.
+    pub value: String,
+}
+
+impl Tag {
+    /// Parse a tag which is written as `key: value`.
+    ///
+    /// The tag is split at its first colon and both parts are trimmed of whitespace. The value
+    /// may be empty. Returns `None` if the tag has no colon or nothing before it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::Tag;
+    /// let tag = Tag::parse_key_value("time: 10:45 ").unwrap();
+    ///
+    /// assert_eq!(&tag.key, "time");
+    /// assert_eq!(&tag.value, "10:45");
+    ///
+    /// assert!(Tag::parse_key_value("sunny").is_none());
+    /// ```
+    pub fn parse_key_value(tag: &str) -> Option<Tag> {
+        let (key, value) = tag.split_once(':')?;
+        let key = key.trim();
+
+        if key.is_empty() {
+            None
+        } else {
+            Some(Tag {
+                key: key.to_string(),
+                value: value.trim().to_string(),
+            })
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Tag in a story along with the content that it is set to.
+///
+/// Found with [`search_tags`][crate::story::Story::search_tags()].
+pub struct TaggedContent {
+    /// Tag as it was written, without its marker.
+    ///
+    /// Tags with expressions are not evaluated.
+    pub tag: String,
+    /// Kind of content that the tag is set to.
+    pub kind: TaggedContentKind,
+    /// Knot and stitch that the content is in.
+    ///
+    /// Global tags of the story are in the root knot.
+    pub location: Location,
+    /// Information about the origin of the content in the story file or text.
+    ///
+    /// For knots and stitches this is where their name is written. Global tags of the story
+    /// are set to the story as a whole and have no origin.
+    pub meta_data: Option<MetaData>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Kinds of content that tags are set to.
+pub enum TaggedContentKind {
+    /// Global tag at the top of the story.
+    Story,
+    /// Tag below the name of a knot.
+    Knot,
+    /// Tag below the name of a stitch.
+    Stitch,
+    /// Tag of a line of text.
+    ///
+    /// This includes the lines which are added to the line buffer when choices are made.
+    Line,
+    /// Tag of the selection text of a choice, which is set to the presented `Choice`.
+    Choice,
+}
+
+/// Get every tag in the story which matches a predicate, in the order they are written.
+pub(crate) fn search_tags<F>(
+    story_tags: &[String],
+    knots: &KnotSet,
+    predicate: F,
+) -> Vec<TaggedContent>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut search = TagSearch {
+        predicate,
+        found: Vec::new(),
+    };
+
+    let root = Location::from(ROOT_KNOT_NAME);
+    search.add(story_tags, TaggedContentKind::Story, &root, None);
+
+    let mut knots = knots.iter().collect::<Vec<_>>();
+    knots.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+        compare_content(
+            lhs_name,
+            lhs.meta_data.line(),
+            rhs_name,
+            rhs.meta_data.line(),
+        )
+    });
+
+    for (knot_name, knot) in knots {
+        let location = Location::from(knot_name.as_str());
+        search.add(
+            &knot.tags,
+            TaggedContentKind::Knot,
+            &location,
+            Some(&knot.meta_data),
+        );
+
+        let mut stitches = knot.stitches.iter().collect::<Vec<_>>();
+        stitches.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+            compare_content(
+                lhs_name,
+                lhs.meta_data.line(),
+                rhs_name,
+                rhs.meta_data.line(),
+            )
+        });
+
+        for (stitch_name, stitch) in stitches {
+            let location = if stitch_name == ROOT_KNOT_NAME {
+                Location::from(knot_name.as_str())
+            } else {
+                Location::with_stitch(knot_name, stitch_name)
+            };
+
+            search.add(
+                &stitch.tags,
+                TaggedContentKind::Stitch,
+                &location,
+                Some(&stitch.meta_data),
+            );
+            search.add_node_items(&stitch.root.items, &location);
+        }
+    }
+
+    search.found
+}
+
+/// Collector of tags which match a predicate.
+struct TagSearch<F> {
+    predicate: F,
+    found: Vec<TaggedContent>,
+}
+
+impl<F> TagSearch<F>
+where
+    F: Fn(&str) -> bool,
+{
+    fn add(
+        &mut self,
+        tags: &[String],
+        kind: TaggedContentKind,
+        location: &Location,
+        meta_data: Option<&MetaData>,
+    ) {
+        let found = tags
+            .iter()
+            .filter(|tag| (self.predicate)(tag))
+            .map(|tag| TaggedContent {
+                tag: tag.clone(),
+                kind,
+                location: location.clone(),
+                meta_data: meta_data.cloned(),
+            });
+
+        self.found.extend(found);
+    }
+
+    fn add_line(&mut self, line: &InternalLine, kind: TaggedContentKind, location: &Location) {
+        self.add(&line.tags, kind, location, Some(&line.meta_data));
+    }
+
+    /// Add the tags of every line and choice in a node, and recursively in its branches.
+    fn add_node_items(&mut self, items: &[NodeItem], location: &Location) {
+        for item in items {
+            match item {
+                NodeItem::Line(line) => self.add_line(line, TaggedContentKind::Line, location),
+                NodeItem::BranchingPoint(branches) => {
+                    for branch in branches {
+                        let selection_text = branch.choice.selection_text.lock().unwrap();
+                        self.add_line(&selection_text, TaggedContentKind::Choice, location);
+
+                        self.add_node_items(&branch.items, location);
+                    }
+                }
+                NodeItem::AlternativeBlock(block) => {
+                    for items in &block.items {
+                        self.add_node_items(items, location);
+                    }
+                }
+                NodeItem::ConditionalBlock(block) => {
+                    for branch in &block.branches {
+                        self.add_node_items(&branch.items, location);
+                    }
+                }
+                NodeItem::Directive(..)
+                | NodeItem::Assignment(..)
+                | NodeItem::Call(..)
+                | NodeItem::Return(..)
+                | NodeItem::Thread(..)
+                | NodeItem::Label(..) => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::story::read_story_from_string;
+
+    #[test]
+    fn key_value_tags_are_split_at_their_first_colon_and_trimmed() {
+        let tag = Tag::parse_key_value(" url : https://example.com ").unwrap();
+
+        assert_eq!(&tag.key, "url");
+        assert_eq!(&tag.value, "https://example.com");
+
+        assert_eq!(&Tag::parse_key_value("draft:").unwrap().value, "");
+        assert!(Tag::parse_key_value(": value").is_none());
+        assert!(Tag::parse_key_value("no value").is_none());
+    }
+
+    fn search(content: &str) -> Vec<(String, TaggedContentKind, Location)> {
+        let story = read_story_from_string(content).unwrap();
+
+        story
+            .search_tags(|_| true)
+            .into_iter()
+            .map(|found| (found.tag, found.kind, found.location))
+            .collect()
+    }
+
+    #[test]
+    fn tags_are_found_in_the_order_they_are_written_with_their_location() {
+        let found = search(
+            "\
+# title
+-> one
+
+=== two ===
+# second
+Two. # line two
+-> END
+
+=== one ===
+# first
+One. # line one
+-> two
+",
+        );
+
+        let knot = |name| Location::from(name);
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "title".to_string(),
+                    TaggedContentKind::Story,
+                    knot(ROOT_KNOT_NAME)
+                ),
+                ("second".to_string(), TaggedContentKind::Knot, knot("two")),
+                ("line two".to_string(), TaggedContentKind::Line, knot("two")),
+                ("first".to_string(), TaggedContentKind::Knot, knot("one")),
+                ("line one".to_string(), TaggedContentKind::Line, knot("one")),
+            ]
+        );
+    }
+
+    #[test]
+    fn tags_of_choices_and_nested_content_are_found_in_their_stitch() {
+        let found = search(
+            "\
+=== knot ===
+= stitch
+# stitch tag
+*   Open [the door # choice] now # opened
+    { true:
+        Nested. # nested
+    }
+-> END
+",
+        );
+
+        let location = Location::with_stitch("knot", "stitch");
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "stitch tag".to_string(),
+                    TaggedContentKind::Stitch,
+                    location.clone()
+                ),
+                (
+                    "choice".to_string(),
+                    TaggedContentKind::Choice,
+                    location.clone()
+                ),
+                (
+                    "opened".to_string(),
+                    TaggedContentKind::Line,
+                    location.clone()
+                ),
+                ("nested".to_string(), TaggedContentKind::Line, location),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_tags_which_match_the_predicate_are_found() {
+        let story = read_story_from_string("Hello! # mood: happy # portrait: smile").unwrap();
+        let found = story.search_tags(|tag| tag.starts_with("mood"));
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(&found[0].tag, "mood: happy");
+        assert_eq!(found[0].meta_data.as_ref().unwrap().line(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+//! Bounded timeline of story states for stepping back and forth through a playthrough.
+
+use crate::{
+    follow::FollowData,
+    knot::{Address, KnotSet},
+    story::types::{Choice, LineBuffer, Prompt, StorySnapshot},
+};
+
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Full internal state of a story at a point in the timeline, which it can be restored to.
+pub(crate) struct TimelineState {
+    pub current_address: Address,
+    pub knots: KnotSet,
+    pub data: FollowData,
+    pub last_choices: Option<Vec<Choice>>,
+    pub selected_choice: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Point in the timeline, recorded when the story returned a prompt.
+pub struct TimelineEntry {
+    /// Lines of text which were produced before the prompt.
+    pub lines: LineBuffer,
+    /// Prompt which the story returned.
+    pub prompt: Prompt,
+    /// Location, variables and visit counts of the story at the prompt.
+    pub snapshot: StorySnapshot,
+    /// State to restore the story to when stepping to this point.
+    pub(crate) state: TimelineState,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Record of the most recent prompts of a story, which the story can step back and forth
+/// between.
+///
+/// Enabled with [`enable_timeline`][crate::story::Story::enable_timeline()], which requires
+/// the `debug` feature. Every call which resumes the story records the full state of the
+/// story at the prompt that it returns. Only the most recent entries are kept, up to the
+/// capacity of the timeline. Stepping to an entry restores the story to its state at that
+/// prompt. Resuming the story from an earlier entry discards all entries after it.
+pub struct Timeline {
+    /// Recorded entries, from oldest to newest.
+    entries: VecDeque<TimelineEntry>,
+    /// Index of the entry that the story is currently at, if any have been recorded.
+    position: Option<usize>,
+    /// Maximum number of entries to keep.
+    capacity: usize,
+}
+
+impl Timeline {
+    /// Create an empty timeline which keeps at most `capacity` entries.
+    ///
+    /// The capacity is at least one.
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Timeline {
+            entries: VecDeque::with_capacity(capacity),
+            position: None,
+            capacity,
+        }
+    }
+
+    /// Get the number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Get whether any entries have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the maximum number of entries which are kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the index of the entry that the story is currently at, if any.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Get the entry at the given index, where index 0 is the oldest kept entry.
+    pub fn get(&self, index: usize) -> Option<&TimelineEntry> {
+        self.entries.get(index)
+    }
+
+    /// Get the entry that the story is currently at, if any.
+    pub fn current(&self) -> Option<&TimelineEntry> {
+        self.position.and_then(|index| self.get(index))
+    }
+
+    /// Iterate over all entries from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &TimelineEntry> {
+        self.entries.iter()
+    }
+
+    /// Record a new entry after the current one.
+    ///
+    /// Entries after the current position are discarded, as is the oldest entry
+    /// if the timeline is full.
+    pub(crate) fn record(&mut self, entry: TimelineEntry) {
+        if let Some(index) = self.position {
+            self.entries.truncate(index + 1);
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+        self.position = Some(self.entries.len() - 1);
+    }
+
+    /// Move the position to the given index and return the state of the entry.
+    pub(crate) fn seek(&mut self, index: usize) -> Option<&TimelineState> {
+        let entry = self.entries.get(index)?;
+        self.position = Some(index);
+
+        Some(&entry.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        consts::ROOT_KNOT_NAME,
+        follow::FollowDataBuilder,
+        story::types::{EndingKind, Line, Location},
+    };
+
+    use std::collections::HashMap;
+
+    fn mock_entry(text: &str) -> TimelineEntry {
+        TimelineEntry {
+            lines: vec![Line {
+                text: text.to_string(),
+                tags: Vec::new(),
+                glue_end: false,
+                divert_end: false,
+            }],
+            prompt: Prompt::Done(EndingKind::End),
+            snapshot: StorySnapshot {
+                location: Location::from(ROOT_KNOT_NAME),
+                variables: HashMap::new(),
+                visit_counts: HashMap::new(),
+            },
+            state: TimelineState {
+                current_address: Address::End,
+                knots: KnotSet::new(),
+                data: FollowDataBuilder::new().build(),
+                last_choices: None,
+                selected_choice: None,
+            },
+        }
+    }
+
+    fn get_texts(timeline: &Timeline) -> Vec<&str> {
+        timeline
+            .iter()
+            .map(|entry| entry.lines[0].text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn recording_past_capacity_drops_oldest_entries() {
+        let mut timeline = Timeline::with_capacity(2);
+
+        timeline.record(mock_entry("one"));
+        timeline.record(mock_entry("two"));
+        timeline.record(mock_entry("three"));
+
+        assert_eq!(get_texts(&timeline), &["two", "three"]);
+        assert_eq!(timeline.position(), Some(1));
+    }
+
+    #[test]
+    fn recording_after_seeking_back_discards_later_entries() {
+        let mut timeline = Timeline::with_capacity(5);
+
+        timeline.record(mock_entry("one"));
+        timeline.record(mock_entry("two"));
+        timeline.record(mock_entry("three"));
+
+        assert!(timeline.seek(0).is_some());
+        timeline.record(mock_entry("four"));
+
+        assert_eq!(get_texts(&timeline), &["one", "four"]);
+        assert_eq!(timeline.position(), Some(1));
+    }
+
+    #[test]
+    fn seeking_outside_of_timeline_does_not_move_position() {
+        let mut timeline = Timeline::with_capacity(5);
+
+        timeline.record(mock_entry("one"));
+
+        assert!(timeline.seek(1).is_none());
+        assert_eq!(timeline.position(), Some(0));
+    }
+}
@@ -0,0 +1,322 @@
+//! Recorded playthroughs of a story which can be exported for review.
+
+use crate::{
+    consts::ROOT_KNOT_NAME,
+    story::{
+        story::format_location,
+        types::{Choice, Line, LineBuffer, Location, OutputBlock},
+    },
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Single step of a recorded playthrough.
+pub enum TranscriptEntry {
+    /// Lines of text that were produced when the story was resumed.
+    Lines {
+        /// Knot or stitch that the story stopped in after producing the lines.
+        location: Location,
+        /// Lines of text with their tags.
+        lines: LineBuffer,
+    },
+    /// Choice that was made by the player.
+    Choice(Choice),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Record of the text and choices of a playthrough, which can be exported as a document.
+///
+/// Record the output of every call to [`resume_block`][crate::story::Story::resume_block()]
+/// and every choice that is made, then export the transcript as Markdown or HTML. Lines
+/// are placed under a heading for the knot or stitch that the story stopped in after
+/// producing them. Lines in the root of the story have no heading.
+///
+/// # Example
+/// ```
+/// # use inkling::{read_story_from_string, Transcript};
+/// let content = "\
+/// The train pulled into the station. # arrival
+/// *   [Step off] -> platform
+///
+/// === platform ===
+/// The platform was deserted.
+/// -> END
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// let mut transcript = Transcript::default();
+///
+/// let block = story.resume_block().unwrap();
+/// transcript.record_block(&block);
+///
+/// let choice = &block.prompt.get_choices().unwrap()[0];
+/// transcript.record_choice(choice);
+/// story.make_choice(0).unwrap();
+///
+/// transcript.record_block(&story.resume_block().unwrap());
+///
+/// let markdown = transcript.to_markdown();
+/// let lines = markdown.lines().collect::<Vec<_>>();
+///
+/// assert_eq!(lines[0], "The train pulled into the station. `#arrival`");
+/// assert_eq!(lines[2], "> **Step off**");
+/// assert_eq!(lines[4], "## platform");
+/// assert_eq!(lines[6], "The platform was deserted.");
+/// ```
+pub struct Transcript {
+    /// Steps of the playthrough in the order that they happened.
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// Get all steps of the playthrough in the order that they happened.
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Record the lines of a block of output.
+    ///
+    /// Blocks without any lines are not recorded.
+    pub fn record_block(&mut self, block: &OutputBlock) {
+        self.record_lines(&block.lines, &block.end);
+    }
+
+    /// Record lines of text that the story stopped in the given location after producing.
+    ///
+    /// Empty sets of lines are not recorded.
+    pub fn record_lines(&mut self, lines: &[Line], location: &Location) {
+        if !lines.is_empty() {
+            self.entries.push(TranscriptEntry::Lines {
+                location: location.clone(),
+                lines: lines.to_vec(),
+            });
+        }
+    }
+
+    /// Record a choice that was made.
+    pub fn record_choice(&mut self, choice: &Choice) {
+        self.entries.push(TranscriptEntry::Choice(choice.clone()));
+    }
+
+    /// Export the transcript as a Markdown document.
+    ///
+    /// Headings are written as level two headings, tags as inline code after the text
+    /// of their line and choices as block quotes.
+    pub fn to_markdown(&self) -> String {
+        let mut blocks = Vec::new();
+
+        for (heading, entry) in self.get_entries_with_headings() {
+            if let Some(heading) = heading {
+                blocks.push(format!("## {}", heading));
+            }
+
+            match entry {
+                TranscriptEntry::Lines { lines, .. } => {
+                    for (text, tags) in get_paragraphs(lines) {
+                        let tags = tags.iter().map(|tag| format!(" `#{}`", tag));
+                        blocks.push(std::iter::once(text).chain(tags).collect());
+                    }
+                }
+                TranscriptEntry::Choice(choice) => {
+                    let tags = choice.tags.iter().map(|tag| format!(" `#{}`", tag));
+                    let text = format!("> **{}**", choice.text.trim());
+
+                    blocks.push(std::iter::once(text).chain(tags).collect());
+                }
+            }
+        }
+
+        join_blocks(blocks)
+    }
+
+    /// Export the transcript as an HTML fragment.
+    ///
+    /// Headings are written as `<h2>` elements and paragraphs of text as `<p>` elements.
+    /// Tags are written as `<span class="tag">` elements after the text of their line
+    /// and choices as paragraphs with the `choice` class. All text is escaped.
+    pub fn to_html(&self) -> String {
+        let mut blocks = Vec::new();
+
+        for (heading, entry) in self.get_entries_with_headings() {
+            if let Some(heading) = heading {
+                blocks.push(format!("<h2>{}</h2>", escape_html(&heading)));
+            }
+
+            match entry {
+                TranscriptEntry::Lines { lines, .. } => {
+                    for (text, tags) in get_paragraphs(lines) {
+                        blocks.push(format!(
+                            "<p>{}{}</p>",
+                            escape_html(&text),
+                            format_html_tags(&tags)
+                        ));
+                    }
+                }
+                TranscriptEntry::Choice(choice) => blocks.push(format!(
+                    "<p class=\"choice\">{}{}</p>",
+                    escape_html(choice.text.trim()),
+                    format_html_tags(&choice.tags)
+                )),
+            }
+        }
+
+        join_blocks(blocks)
+    }
+
+    /// Pair every entry with the heading to write before it, if the location changed.
+    fn get_entries_with_headings(&self) -> Vec<(Option<String>, &TranscriptEntry)> {
+        let mut current = None;
+
+        self.entries
+            .iter()
+            .map(|entry| {
+                let heading = match entry {
+                    TranscriptEntry::Lines { location, .. }
+                        if current.as_ref() != Some(location) =>
+                    {
+                        current.replace(location.clone());
+                        Some(location).filter(|location| location.knot != ROOT_KNOT_NAME)
+                    }
+                    _ => None,
+                };
+
+                (heading.map(format_location), entry)
+            })
+            .collect()
+    }
+}
+
+/// Join lines which are glued together into paragraphs of text, along with all their tags.
+fn get_paragraphs(lines: &[Line]) -> Vec<(String, Vec<String>)> {
+    let mut paragraphs = Vec::new();
+
+    let mut text = String::new();
+    let mut tags = Vec::new();
+
+    for line in lines {
+        text.push_str(&line.text);
+        tags.extend(line.tags.iter().cloned());
+
+        if !line.glue_end {
+            paragraphs.push((text.trim().to_string(), tags));
+
+            text = String::new();
+            tags = Vec::new();
+        }
+    }
+
+    if !text.trim().is_empty() || !tags.is_empty() {
+        paragraphs.push((text.trim().to_string(), tags));
+    }
+
+    paragraphs
+        .into_iter()
+        .filter(|(text, tags)| !text.is_empty() || !tags.is_empty())
+        .collect()
+}
+
+/// Join blocks of a document with empty lines between them.
+fn join_blocks(blocks: Vec<String>) -> String {
+    blocks
+        .into_iter()
+        .map(|block| format!("{}\n", block))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Write tags as HTML elements, with a leading space.
+fn format_html_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!(" <span class=\"tag\">#{}</span>", escape_html(tag)))
+        .collect()
+}
+
+/// Escape the characters of a string which have a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str, tags: &[&str], glue_end: bool) -> Line {
+        Line {
+            text: text.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            glue_end,
+            divert_end: false,
+        }
+    }
+
+    #[test]
+    fn glued_lines_are_joined_into_one_paragraph_with_all_tags() {
+        let lines = vec![
+            line("The lights ", &["dim"], true),
+            line("went out.\n", &["dark"], false),
+            line("Silence.\n", &[], false),
+        ];
+
+        let paragraphs = get_paragraphs(&lines);
+
+        assert_eq!(
+            paragraphs,
+            vec![
+                (
+                    "The lights went out.".to_string(),
+                    vec!["dim".to_string(), "dark".to_string()]
+                ),
+                ("Silence.".to_string(), Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn headings_are_only_written_when_the_location_changes() {
+        let mut transcript = Transcript::default();
+
+        let cinema = Location::with_stitch("tripoli", "cinema");
+
+        transcript.record_lines(&[line("The film began.\n", &[], false)], &cinema);
+        transcript.record_lines(&[line("It was a comedy.\n", &[], false)], &cinema);
+        transcript.record_lines(&[], &Location::from("cairo"));
+
+        assert_eq!(
+            transcript.to_markdown(),
+            "## tripoli.cinema\n\nThe film began.\n\nIt was a comedy.\n"
+        );
+    }
+
+    #[test]
+    fn html_export_escapes_text_and_tags() {
+        let mut transcript = Transcript::default();
+
+        transcript.record_lines(
+            &[line("Fish & <chips>\n", &["menu \"a\""], false)],
+            &Location::from("diner"),
+        );
+
+        assert_eq!(
+            transcript.to_html(),
+            "<h2>diner</h2>\n\n\
+             <p>Fish &amp; &lt;chips&gt; <span class=\"tag\">#menu &quot;a&quot;</span></p>\n"
+        );
+    }
+}
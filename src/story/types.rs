@@ -2,15 +2,15 @@
 
 use crate::{
     error::{utils::MetaData, InklingError},
-    line::Variable,
+    line::{Directive, List, Variable},
 };
 
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Single line of text in a story, ready to display.
 pub struct Line {
@@ -21,25 +21,64 @@ pub struct Line {
     /// at the end of the line unless the line was glued to the next.
     pub text: String,
     /// Tags set to the line.
+    ///
+    /// Includes the tags written after the text of the line and those written on their
+    /// own lines above it.
     pub tags: Vec<String>,
+    /// Whether the line is glued to the next line.
+    ///
+    /// Glued lines have no newline character at the end of their text and should be
+    /// displayed together with the next line as a single paragraph.
+    pub glue_end: bool,
+    /// Whether the line was ended by a divert to another location in the story.
+    pub divert_end: bool,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Choice presented to the user.
 pub struct Choice {
     /// Line of text to represent the choice with.
     ///
-    /// The text is ready to be printed as-is. It is trimmed of whitespace from both ends
-    /// and contains no newline character at the end.
+    /// This is the selection text of the choice, which includes text inside
+    /// of square brackets but not text after them. The text is ready to be printed as-is.
+    /// It is trimmed of whitespace from both ends and contains no newline character
+    /// at the end.
     pub text: String,
+    /// Text which will be added to the line buffer if the choice is made.
+    ///
+    /// This excludes text inside of square brackets but includes text after them.
+    /// It is processed like `text` but with a copy of the story state, so creating it
+    /// does not advance sequences or random numbers in the story.
+    pub display_text: String,
+    /// Line of the choice as it was written in the story, including its markers.
+    pub raw_text: String,
     /// Tags associated with the choice.
+    ///
+    /// These are the tags of the selection text, just like `text`: tags inside of square
+    /// brackets or in a choice without them. Tags after the brackets are only set
+    /// to the line which is added to the line buffer when the choice is made.
     pub tags: Vec<String>,
+    /// Identifier of the choice which is unique in the story.
+    ///
+    /// The identifier is stable across runs and saves, and does not change when the story
+    /// is edited unless the choice is moved or choices are added or removed before it
+    /// in its knot or stitch. Use it to make the choice with
+    /// [`make_choice_by_id`][crate::story::Story::make_choice_by_id()].
+    pub id: String,
+    /// Relative likelihood of the choice being picked by players, for simulations.
+    ///
+    /// Set by the author with a `# weight: 3` tag on the choice. Choices without the tag,
+    /// or with a weight which is not a whole number, have a weight of 1. The tag is still
+    /// included in the `tags` of the choice. The weight does not affect how the story is
+    /// followed and is meant for tools which simulate many playthroughs, such as to
+    /// estimate how often content is seen.
+    pub weight: u32,
     /// Internal index of choice in set.
     pub(crate) index: usize,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Result from following a `Story`.
 ///
@@ -62,14 +101,51 @@ pub struct Choice {
 ///             println!("{}. {}", i + 1, choice.text);
 ///         }
 ///     },
-///     Prompt::Done => { /* the story reached its end */ },
+///     Prompt::Done(..) => { /* the story reached its end */ },
+///     Prompt::Continue => { /* more lines follow when the story is resumed */ },
 /// }
 /// ```
 pub enum Prompt {
-    /// The story reached an end.
-    Done,
+    /// The story reached an end, of the given kind.
+    Done(EndingKind),
     /// A choice was encountered.
     Choice(Vec<Choice>),
+    /// The story produced the maximum number of lines for a single call to resume it.
+    ///
+    /// Resume the story again to get the lines that follow. Only returned if a limit has
+    /// been set with [`set_max_lines_per_resume`][crate::Story::set_max_lines_per_resume()].
+    Continue,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How to handle variables which are set by the host but not declared in the story.
+///
+/// Set with [`set_undeclared_variable_policy`][crate::Story::set_undeclared_variable_policy()].
+pub enum UndeclaredVariablePolicy {
+    /// Return an [`InvalidVariable`][crate::InklingError::InvalidVariable] error. This is
+    /// the default, since a misspelled name would otherwise create a variable which
+    /// the story never reads.
+    #[default]
+    Error,
+    /// Create a new global variable with the name and value.
+    Create,
+    /// Create a new global variable and add a
+    /// [`CreatedUndeclaredVariable`][crate::log::Warning::CreatedUndeclaredVariable] warning
+    /// to the log of the story.
+    CreateWithWarning,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// How a story reached its end.
+pub enum EndingKind {
+    /// The story diverted to `END`.
+    End,
+    /// The story diverted to `DONE`, which ended its current flow without ending it
+    /// for good.
+    Done,
+    /// The story ran out of content without diverting anywhere.
+    DeadEnd,
 }
 
 impl Prompt {
@@ -99,7 +175,43 @@ impl Prompt {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Lines of text produced by a single call to resume the story, with the prompt it stopped at.
+///
+/// Returned by [`resume_block`][crate::story::Story::resume_block()], which collects the
+/// output of one passage into a block of its own instead of adding it to a shared buffer.
+pub struct OutputBlock {
+    /// Lines of text that were produced since the previous prompt.
+    pub lines: LineBuffer,
+    /// Tags of all lines in the block, in the order that they appear.
+    pub tags: Vec<String>,
+    /// Location that the story was resumed from.
+    pub start: Location,
+    /// Location that the story was at when it stopped.
+    pub end: Location,
+    /// Prompt that the story stopped at.
+    pub prompt: Prompt,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Result of a single call to [`poll`][crate::story::Story::poll()] a story.
+pub enum Poll {
+    /// The story did some work but produced no lines. Poll again to continue.
+    Pending,
+    /// The story produced lines of text.
+    ///
+    /// The prompt of the block is [`Continue`][Prompt::Continue] if the story has more
+    /// content to follow, or the set of choices or end that it stopped at.
+    Lines(OutputBlock),
+    /// The story is waiting for a choice to be made.
+    Choices(Vec<Choice>),
+    /// The story has reached its end.
+    Done,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Knot and (possible) stitch location in the story.
 ///
@@ -217,9 +329,77 @@ impl Location {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Comment written by the author of a story.
+///
+/// Comments are not part of the story content and are only kept if
+/// [`preserve_comments`][crate::ReadOptions::preserve_comments] is set when reading the story.
+/// Retrieve them with [`get_comments`][crate::story::Story::get_comments()].
+pub struct Comment {
+    /// Kind of comment.
+    pub kind: CommentKind,
+    /// Text of the comment as written, including its marker.
+    pub text: String,
+    /// Information about the line that the comment is written on.
+    pub meta_data: MetaData,
+    /// Byte range of the comment in its line.
+    pub span: Range<usize>,
+    /// Line of content that the comment belongs to, if any.
+    ///
+    /// A comment which follows content on the same line belongs to that line. A comment
+    /// on a line of its own belongs to the next line of content. Comments at the end
+    /// of a story belong to no line.
+    pub element: Option<MetaData>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Kinds of comments in a story.
+pub enum CommentKind {
+    /// Comment to the end of the line, beginning with `//`.
+    Line,
+    /// Reminder to the author, on a line which begins with `TODO:`.
+    Todo,
+    /// Comment between `/*` and `*/`, which may span several lines.
+    ///
+    /// Its span is the range of the comment in the line that it begins on.
+    Block,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Read-only copy of the runtime state of a story.
+///
+/// Created by [`Story::snapshot`][crate::story::Story::snapshot()]. Changing the snapshot
+/// does not affect the story.
+pub struct StorySnapshot {
+    /// Location that the story is currently at.
+    pub location: Location,
+    /// Current values of all global variables, including constants.
+    pub variables: HashMap<String, Variable>,
+    /// Number of times every knot and stitch in the story has been visited.
+    pub visit_counts: HashMap<Location, u32>,
+}
+
 /// Convenience type to indicate when a buffer of `Line` objects is being manipulated.
 pub type LineBuffer = Vec<Line>;
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Item of content encountered when following a story.
+///
+/// Returned by [`resume_with_directives`][crate::Story::resume_with_directives()], which
+/// delivers custom directives in the order they appear between the lines of text.
+pub enum StoryItem {
+    /// Line of text to display.
+    Line(Line),
+    /// Custom directive for the host to act on.
+    Directive(Directive),
+}
+
+/// Convenience type to indicate when a buffer of `StoryItem` objects is being manipulated.
+pub type ItemBuffer = Vec<StoryItem>;
+
 /// Convenience type for a set of global variables.
 pub type VariableSet = HashMap<String, VariableInfo>;
 
@@ -234,16 +414,40 @@ pub struct VariableInfo {
     pub variable: Variable,
     /// Information about the origin of the variable in the story file or text.
     pub meta_data: MetaData,
+    /// Value that the variable was declared with.
+    ///
+    /// Saved states only hold the values of variables which have changed from it.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub declared: Option<Variable>,
+    /// Value to reset the variable to when the story is saved, if it is transient.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub transient: Option<Variable>,
+    /// Whether the variable is a constant which was given by the host when the story was read.
+    ///
+    /// See [`ReadOptions::constants`][crate::ReadOptions::constants].
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub is_host_constant: bool,
+    /// All items of the list definition, if the variable was declared with `LIST`.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub list_items: Option<List>,
 }
 
 impl VariableInfo {
     /// Assign a new value to the variable.
     ///
-    /// Asserts that the variable is non-constant, returns an error if it is.
+    /// Asserts that the variable is non-constant, returns an error which points to
+    /// its declaration if it is.
     pub fn assign(&mut self, variable: Variable, name: &str) -> Result<(), InklingError> {
         if self.is_const {
+            let meta_data = if self.is_host_constant {
+                None
+            } else {
+                Some(self.meta_data.clone())
+            };
+
             Err(InklingError::AssignedToConst {
                 name: name.to_string(),
+                meta_data,
             })
         } else {
             self.variable.assign(variable).map_err(|err| err.into())
@@ -252,10 +456,16 @@ impl VariableInfo {
 
     #[cfg(test)]
     pub fn new<T: Into<Variable>>(variable: T, line_index: usize) -> Self {
+        let variable = variable.into();
+
         VariableInfo {
             is_const: false,
-            variable: variable.into(),
+            declared: Some(variable.clone()),
+            variable,
             meta_data: line_index.into(),
+            transient: None,
+            is_host_constant: false,
+            list_items: None,
         }
     }
 }
@@ -284,6 +494,7 @@ mod tests {
             .unwrap_err();
         let expected_err = InklingError::AssignedToConst {
             name: "variable".to_string(),
+            meta_data: Some(MetaData::from(0)),
         };
 
         assert_eq!(format!("{:?}", err), format!("{:?}", expected_err));
@@ -42,10 +42,14 @@ mod tests {
             Line {
                 text: "Start of line, ".to_string(),
                 tags: Vec::new(),
+                glue_end: true,
+                divert_end: false,
             },
             Line {
                 text: "end of line without new lines".to_string(),
                 tags: Vec::new(),
+                glue_end: false,
+                divert_end: false,
             },
         ];
 
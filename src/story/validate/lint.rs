@@ -0,0 +1,222 @@
+//! Optional style lints for story content.
+
+#[cfg(feature = "lints")]
+use crate::{
+    knot::KnotSet,
+    log::{Logger, Warning},
+    node::{Branch, NodeItem},
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Optional style lints to check a story for while reading it.
+///
+/// All lints are disabled by default. Any problems found are added as warnings
+/// to the [story log][crate::log::Logger]. Lints are only checked if the `lints`
+/// feature is enabled.
+///
+/// # Example
+/// ```
+/// # use inkling::{read_story_from_string_with_options, LintOptions, ReadOptions};
+/// let content = "\
+/// *   Choice one
+/// *   Choice two
+/// *   Choice three
+/// ";
+///
+/// let options = ReadOptions {
+///     lints: LintOptions {
+///         max_choices: Some(2),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+///
+/// let story = read_story_from_string_with_options(content, &options).unwrap();
+/// # #[cfg(all(feature = "lints", feature = "logging"))]
+/// assert_eq!(story.log.warnings.len(), 1);
+/// ```
+pub struct LintOptions {
+    /// Warn about lines of text which are longer than this number of characters.
+    ///
+    /// Only lines whose text does not depend on the story state are checked.
+    pub max_line_length: Option<usize>,
+    /// Warn about sets of choices with more than this number of choices.
+    ///
+    /// Fallback choices are not counted.
+    pub max_choices: Option<usize>,
+}
+
+/// Check all content in a story for the enabled style lints.
+#[cfg(feature = "lints")]
+pub fn lint_story_content(knots: &KnotSet, options: &LintOptions, log: &mut Logger) {
+    if options == &LintOptions::default() {
+        return;
+    }
+
+    let mut stitches = knots
+        .values()
+        .flat_map(|knot| knot.stitches.values())
+        .collect::<Vec<_>>();
+
+    stitches.sort_by_key(|stitch| stitch.meta_data.line());
+
+    for stitch in stitches {
+        lint_items(&stitch.root.items, options, log);
+    }
+}
+
+/// Recursively check a set of node items.
+#[cfg(feature = "lints")]
+fn lint_items(items: &[NodeItem], options: &LintOptions, log: &mut Logger) {
+    for item in items {
+        match item {
+            NodeItem::Line(line) => {
+                if let (Some(max), Some(text)) = (options.max_line_length, line.get_static_text()) {
+                    let length = text.chars().count();
+
+                    if length > max {
+                        log.add_warning(Warning::LineTooLong { length, max }, &line.meta_data);
+                    }
+                }
+            }
+            NodeItem::Directive(..)
+            | NodeItem::Assignment(..)
+            | NodeItem::Call(..)
+            | NodeItem::Return(..)
+            | NodeItem::Thread(..)
+            | NodeItem::Label(..) => (),
+            NodeItem::AlternativeBlock(block) => {
+                for items in &block.items {
+                    lint_items(items, options, log);
+                }
+            }
+            NodeItem::ConditionalBlock(block) => {
+                for branch in &block.branches {
+                    lint_items(&branch.items, options, log);
+                }
+            }
+            NodeItem::BranchingPoint(branches) => {
+                lint_branching_point(branches, options, log);
+
+                for branch in branches {
+                    lint_items(&branch.items, options, log);
+                }
+            }
+        }
+    }
+}
+
+/// Check the number of choices in a branching point.
+#[cfg(feature = "lints")]
+fn lint_branching_point(branches: &[Branch], options: &LintOptions, log: &mut Logger) {
+    if let Some(max) = options.max_choices {
+        let num_choices = branches
+            .iter()
+            .filter(|branch| !branch.choice.is_fallback)
+            .count();
+
+        if num_choices > max {
+            log.add_warning(
+                Warning::TooManyChoices { num_choices, max },
+                &branches[0].choice.meta_data,
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "lints", feature = "logging"))]
+mod tests {
+    use super::*;
+
+    use crate::{log::MessageKind, story::parse::read_story_content_from_string};
+
+    fn get_lint_log(content: &str, options: &LintOptions) -> Logger {
+        let mut log = Logger::default();
+        let (knots, _, _) = read_story_content_from_string(content, &mut log).unwrap();
+
+        lint_story_content(&knots, options, &mut log);
+
+        log
+    }
+
+    #[test]
+    fn lints_are_disabled_by_default() {
+        let content = "
+
+A line which is quite long for a line.
+
+*   One
+*   Two
+
+";
+
+        let log = get_lint_log(content, &LintOptions::default());
+
+        assert!(log.warnings.is_empty());
+    }
+
+    #[test]
+    fn lines_longer_than_the_maximum_length_yield_warnings() {
+        let content = "
+
+Short line.
+A line which is longer.
+A line which is {~even|much} longer.
+
+*   [One] Choice text that is long.
+
+";
+
+        let options = LintOptions {
+            max_line_length: Some(12),
+            ..Default::default()
+        };
+
+        let log = get_lint_log(content, &options);
+
+        assert_eq!(log.warnings.len(), 2);
+
+        match &log.warnings[0].message {
+            MessageKind::Warning(Warning::LineTooLong { length, max }) => {
+                assert_eq!(*length, 23);
+                assert_eq!(*max, 12);
+                assert_eq!(log.warnings[0].meta_data.line(), 4);
+            }
+            other => panic!("expected `Warning::LineTooLong` but got {:?}", other),
+        }
+
+        assert_eq!(log.warnings[1].meta_data.line(), 7);
+    }
+
+    #[test]
+    fn sets_with_more_than_the_maximum_number_of_choices_yield_warnings() {
+        let content = "
+
+*   One
+*   Two
+    **  Three
+    **  Four
+    **  Five
+*   ->
+
+";
+
+        let options = LintOptions {
+            max_choices: Some(2),
+            ..Default::default()
+        };
+
+        let log = get_lint_log(content, &options);
+
+        assert_eq!(log.warnings.len(), 1);
+
+        match &log.warnings[0].message {
+            MessageKind::Warning(Warning::TooManyChoices { num_choices, max }) => {
+                assert_eq!(*num_choices, 3);
+                assert_eq!(*max, 2);
+                assert_eq!(log.warnings[0].meta_data.line(), 5);
+            }
+            other => panic!("expected `Warning::TooManyChoices` but got {:?}", other),
+        }
+    }
+}
@@ -1,6 +1,10 @@
 //! Validate story and variable names, addresses, expressions, and conditions.
 
+mod lint;
 pub(self) mod namespace;
 pub(self) mod validate;
 
+#[cfg(feature = "lints")]
+pub use lint::lint_story_content;
+pub use lint::LintOptions;
 pub use validate::{validate_story_content, KnotValidationInfo, ValidateContent, ValidationData};
@@ -3,12 +3,32 @@
 use crate::{
     error::{parse::validate::ValidationError, utils::MetaData},
     follow::FollowData,
-    knot::{get_empty_knot_counts, Address, AddressKind, KnotSet},
+    knot::{
+        get_empty_knot_counts, get_function_set, Address, AddressKind, FunctionSet, InkFunction,
+        Knot, KnotSet,
+    },
+    line::{expression::Operand, Expression, Return, Variable},
     log::Logger,
-    story::{rng::StoryRng, types::VariableSet, validate::namespace::validate_story_name_spaces},
+    node::{NodeItem, RootNode},
+    story::{
+        coverage::ConditionCoverage,
+        external::{ExternalFunctions, ExternalVariables},
+        rng::StoryRng,
+        types::VariableSet,
+        validate::namespace::validate_story_name_spaces,
+        variable_history::VariableObservers,
+    },
 };
 
-use std::collections::HashMap;
+#[cfg(feature = "lints")]
+use crate::log::Warning;
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 pub struct ValidationData {
     /// Data required to evaluate expressions.
@@ -19,6 +39,16 @@ pub struct ValidationData {
     pub follow_data: FollowData,
     /// Structure corresponding to knots with their default stitch, stitches and meta data.
     pub knots: HashMap<String, KnotValidationInfo>,
+    /// Names of global variables which are read from somewhere in the story.
+    ///
+    /// Filled in as addresses to variables are validated, which is done through a shared
+    /// reference to this object.
+    pub read_variables: RefCell<HashSet<String>>,
+    /// Temporary variables which have been declared in the stitch that is being validated,
+    /// with the values that their declarations evaluate to.
+    ///
+    /// Filled in as declarations are validated and cleared before every stitch.
+    pub temporary_variables: RefCell<HashMap<String, Variable>>,
 }
 
 /// Basic information about a knot, required to validate its content.
@@ -31,12 +61,18 @@ pub struct KnotValidationInfo {
     pub stitches: HashMap<String, StitchValidationInfo>,
     /// Information about the origin of this knot.
     pub meta_data: MetaData,
+    /// Whether the knot is a function, which can only be called and not diverted to.
+    pub is_function: bool,
+    /// Names of the parameters of the knot.
+    pub parameters: Vec<String>,
 }
 
 /// Basic information about a stitch, required to validate its content.
 pub struct StitchValidationInfo {
     /// Information about the origin of this stitch.
     pub meta_data: MetaData,
+    /// Names of the labeled choices and gathers in the stitch.
+    pub labels: Vec<String>,
 }
 
 impl ValidationData {
@@ -53,6 +89,7 @@ impl ValidationData {
                             stitch_name.to_string(),
                             StitchValidationInfo {
                                 meta_data: stitch_data.meta_data.clone(),
+                                labels: stitch_data.labels.keys().cloned().collect(),
                             },
                         )
                     })
@@ -62,6 +99,8 @@ impl ValidationData {
                     default_stitch: knot.default_stitch.clone(),
                     stitches,
                     meta_data: knot.meta_data.clone(),
+                    is_function: knot.is_function,
+                    parameters: knot.parameters.clone(),
                 };
 
                 (knot_name.clone(), info)
@@ -70,13 +109,51 @@ impl ValidationData {
 
         let follow_data = FollowData {
             knot_visit_counts: get_empty_knot_counts(knots),
+            label_visit_counts: HashMap::new(),
             variables: variables.clone(),
-            rng: StoryRng::default(),
+            temporary_variables: HashMap::new(),
+            rng: StoryRng::new(),
+            condition_coverage: ConditionCoverage::default(),
+            external_variables: ExternalVariables::default(),
+            turn: 0,
+            last_visited_turns: HashMap::new(),
+            visit_journal: None,
+            variable_history: None,
+            variable_observers: VariableObservers::default(),
+            determinism_audit: None,
+            ending: None,
+            proofreading: false,
+            max_lines_per_resume: None,
+            max_diverts_per_resume: None,
+            functions: Arc::new(FunctionSet::new()),
+            call_depth: 0,
+            divert_arguments: Vec::new(),
+            external_functions: ExternalFunctions::default(),
+            thread_choices: Vec::new(),
+            choice_origins: Vec::new(),
+            uncounted_visit: None,
         };
 
         ValidationData {
             follow_data,
             knots: knot_info,
+            read_variables: RefCell::new(HashSet::new()),
+            temporary_variables: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Get the data to evaluate expressions with, which includes the temporary variables
+    /// that have been declared.
+    pub fn get_follow_data(&self) -> Cow<'_, FollowData> {
+        let temporary_variables = self.temporary_variables.borrow();
+
+        if temporary_variables.is_empty() {
+            Cow::Borrowed(&self.follow_data)
+        } else {
+            let mut follow_data = self.follow_data.clone();
+            follow_data.temporary_variables = temporary_variables.clone();
+
+            Cow::Owned(follow_data)
         }
     }
 }
@@ -127,37 +204,62 @@ pub trait ValidateContent {
 /// uses the `ValidateContent` trait to nest through its content. Additionally it checks for
 /// name space collisions between variables, knots and stitches.
 ///
+/// Functions are validated before other knots, after which the calls to them are evaluated
+/// with the validated functions. Since the types of their parameters are not known until
+/// they are called, errors from the types of variables inside functions are found through
+/// their calls.
+///
 /// If any error is encountered this will yield the set of all found errors.
 pub fn validate_story_content(
     knots: &mut KnotSet,
     follow_data: &FollowData,
     log: &mut Logger,
 ) -> Result<(), ValidationError> {
-    let validation_data = ValidationData::from_data(knots, &follow_data.variables);
+    let mut validation_data = ValidationData::from_data(knots, &follow_data.variables);
 
     let mut error = ValidationError::new();
 
-    knots.iter_mut().for_each(|(knot_name, knot)| {
-        knot.stitches.iter_mut().for_each(|(stitch_name, stitch)| {
-            let current_location = Address::Validated(AddressKind::Location {
-                knot: knot_name.clone(),
-                stitch: stitch_name.clone(),
-            });
+    // Calls between functions are evaluated to placeholder values, since the functions
+    // cannot be called before they are validated
+    validation_data.follow_data.functions = Arc::new(get_placeholder_functions(knots));
 
-            stitch.root.validate(
-                &mut error,
-                log,
-                &current_location,
-                &stitch.meta_data,
-                &validation_data,
-            );
-        })
-    });
+    knots
+        .iter_mut()
+        .filter(|(_, knot)| knot.is_function)
+        .for_each(|(knot_name, knot)| {
+            let mut function_error = ValidationError::new();
+
+            validate_knot(knot_name, knot, &mut function_error, log, &validation_data);
+
+            error
+                .invalid_address_errors
+                .extend(function_error.invalid_address_errors);
+        });
+
+    // External functions are not bound while the story is validated, so calls to them are
+    // still evaluated to placeholder values
+    let mut functions = get_function_set(knots);
+
+    for (name, placeholder) in get_placeholder_functions(knots) {
+        functions.entry(name).or_insert(placeholder);
+    }
+
+    validation_data.follow_data.functions = Arc::new(functions);
+
+    knots
+        .iter_mut()
+        .filter(|(_, knot)| !knot.is_function)
+        .for_each(|(knot_name, knot)| {
+            validate_knot(knot_name, knot, &mut error, log, &validation_data)
+        });
 
     if let Err(name_space_errors) = validate_story_name_spaces(&validation_data) {
         error.name_space_errors = name_space_errors;
     }
 
+    #[cfg(feature = "lints")]
+    check_for_unread_variables(&validation_data, log);
+
     if error.is_empty() {
         Ok(())
     } else {
@@ -165,6 +267,120 @@ pub fn validate_story_content(
     }
 }
 
+/// Validate the content of every stitch in a knot.
+///
+/// The parameters of the knot are declared as temporary variables in its default stitch,
+/// with placeholder values. Since the types of the parameters are not known until the
+/// knot is diverted to, errors from the types of variables are not collected from its
+/// default stitch if it has parameters.
+fn validate_knot(
+    knot_name: &str,
+    knot: &mut Knot,
+    error: &mut ValidationError,
+    log: &mut Logger,
+    validation_data: &ValidationData,
+) {
+    knot.stitches.iter_mut().for_each(|(stitch_name, stitch)| {
+        let current_location = Address::Validated(AddressKind::Location {
+            knot: knot_name.to_string(),
+            stitch: stitch_name.clone(),
+        });
+
+        let mut temporary_variables = validation_data.temporary_variables.borrow_mut();
+        temporary_variables.clear();
+
+        if stitch_name == &knot.default_stitch {
+            for parameter in knot.parameters.iter() {
+                temporary_variables.insert(parameter.clone(), Variable::Int(0));
+            }
+        }
+
+        drop(temporary_variables);
+
+        if stitch_name == &knot.default_stitch && !knot.parameters.is_empty() {
+            let mut stitch_error = ValidationError::new();
+
+            stitch.root.validate(
+                &mut stitch_error,
+                log,
+                &current_location,
+                &stitch.meta_data,
+                validation_data,
+            );
+
+            error
+                .invalid_address_errors
+                .extend(stitch_error.invalid_address_errors);
+        } else {
+            stitch.root.validate(
+                error,
+                log,
+                &current_location,
+                &stitch.meta_data,
+                validation_data,
+            );
+        }
+    })
+}
+
+/// Get functions which return a placeholder value for every function in a set of knots.
+fn get_placeholder_functions(knots: &KnotSet) -> FunctionSet {
+    knots
+        .iter()
+        .filter(|(_, knot)| knot.is_function)
+        .map(|(knot_name, knot)| {
+            let root = RootNode {
+                address: Address::Validated(AddressKind::Location {
+                    knot: knot_name.clone(),
+                    stitch: knot.default_stitch.clone(),
+                }),
+                items: vec![NodeItem::Return(Return {
+                    value: Some(Expression {
+                        head: Operand::Variable(Variable::Int(0)),
+                        tail: Vec::new(),
+                    }),
+                    meta_data: knot.meta_data.clone(),
+                })],
+            };
+
+            let function = InkFunction {
+                parameters: knot.parameters.clone(),
+                root,
+            };
+
+            (knot_name.clone(), function)
+        })
+        .collect()
+}
+
+/// Log a warning for every global variable which is never read in the story.
+///
+/// Variables which are set but never used are usually a sign of a typo where they should
+/// have been read.
+#[cfg(feature = "lints")]
+fn check_for_unread_variables(data: &ValidationData, log: &mut Logger) {
+    let read_variables = data.read_variables.borrow();
+
+    let mut unread_variables = data
+        .follow_data
+        .variables
+        .iter()
+        .filter(|(name, info)| !info.is_host_constant && !read_variables.contains(name.as_str()))
+        .collect::<Vec<_>>();
+
+    unread_variables.sort_by_key(|(_, info)| info.meta_data.line());
+
+    for (name, info) in unread_variables {
+        log.add_warning(
+            Warning::UnreadVariable {
+                name: name.clone(),
+                is_const: info.is_const,
+            },
+            &info.meta_data,
+        );
+    }
+}
+
 #[cfg(test)]
 pub(super) mod tests {
     use super::*;
@@ -181,6 +397,9 @@ pub(super) mod tests {
         },
     };
 
+    #[cfg(all(feature = "lints", feature = "logging"))]
+    use crate::log::{MessageKind, Warning};
+
     pub fn construct_knots(data: &[(&str, &[&str])]) -> KnotSet {
         let mut line_index = 0;
 
@@ -199,6 +418,8 @@ pub(super) mod tests {
                         let stitch = Stitch {
                             root,
                             stack: Vec::new(),
+                            labels: HashMap::new(),
+                            tags: Vec::new(),
                             meta_data: line_index.into(),
                         };
 
@@ -213,6 +434,9 @@ pub(super) mod tests {
                     stitches,
                     tags: Vec::new(),
                     meta_data: knot_line_index.into(),
+                    is_function: false,
+                    parameters: Vec::new(),
+                    is_external: false,
                 };
 
                 (knot_name.to_string(), knot)
@@ -250,6 +474,15 @@ pub(super) mod tests {
         validate_story_content(&mut knots, &data, &mut log)
     }
 
+    fn get_validation_log_from_string(content: &str) -> Logger {
+        let (mut knots, data) = get_validation_data_from_string(content);
+        let mut log = Logger::default();
+
+        validate_story_content(&mut knots, &data, &mut log).unwrap();
+
+        log
+    }
+
     fn get_validation_error_from_string(content: &str) -> ValidationError {
         let (mut knots, data) = get_validation_data_from_string(content);
         let mut log = Logger::default();
@@ -333,6 +566,7 @@ pub(super) mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn validating_story_raises_error_if_expression_has_non_matching_types() {
         let content = "
 
@@ -346,6 +580,7 @@ pub(super) mod tests {
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn validating_story_raises_error_if_condition_has_invalid_types_in_comparison() {
         let content = "
 
@@ -377,6 +612,7 @@ VAR int = 0
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn all_expressions_in_conditions_are_validated() {
         let content = "
 
@@ -480,6 +716,7 @@ VAR variable = true
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn encountered_invalid_address_errors_stop_expressions_from_trying_to_evaluate() {
         let content = "
 
@@ -494,6 +731,7 @@ VAR variable = true
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn encountered_invalid_address_errors_stop_conditions_from_trying_to_evaluate() {
         let content = "
 
@@ -552,6 +790,7 @@ VAR variable = true
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn expression_validation_is_done_in_first_displayed_text_of_branching_choice() {
         let content = "
 
@@ -614,6 +853,7 @@ Line
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn items_inside_true_parts_of_conditions_are_validated() {
         let content = "
 
@@ -628,6 +868,7 @@ Line
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn items_inside_false_parts_of_conditions_are_validated() {
         let content = "
 
@@ -642,6 +883,7 @@ Line
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn items_inside_parts_of_alternative_sequences_are_validated() {
         let content = "
 
@@ -655,6 +897,7 @@ Line
     }
 
     #[test]
+    #[cfg(feature = "math")]
     fn expressions_add_one_error_for_errors_in_nested_parts() {
         let content = "{1 + (2 + (3 + true))}";
 
@@ -662,4 +905,111 @@ Line
 
         assert_eq!(error.variable_errors.len(), 1);
     }
+    #[test]
+    #[cfg(all(feature = "lints", feature = "logging"))]
+    fn choices_with_identical_selection_text_in_the_same_set_yield_warning() {
+        let content = "
+
+*   Go to the kitchen.
+*   Go to the bedroom.
+*   Go to the [kitchen.]dining room.
+
+";
+
+        let log = get_validation_log_from_string(content);
+
+        assert_eq!(log.warnings.len(), 1);
+
+        match &log.warnings[0].message {
+            MessageKind::Warning(Warning::DuplicateChoiceText {
+                text,
+                prev_meta_data,
+            }) => {
+                assert_eq!(text, "Go to the kitchen.");
+                assert_eq!(prev_meta_data.line(), 3);
+                assert_eq!(log.warnings[0].meta_data.line(), 5);
+            }
+            other => panic!(
+                "expected `Warning::DuplicateChoiceText` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn choices_with_identical_text_in_different_sets_or_with_different_conditions_are_fine() {
+        let content = "
+
+VAR visited = false
+
+*   {visited} Go to the kitchen.
+*   {not visited} Go to the kitchen.
+-   *   Go to the bedroom.
+-   *   Go to the bedroom.
+*   Go to the {visited: kitchen|bedroom}.
+*   Go to the bedroom.
+
+";
+
+        let log = get_validation_log_from_string(content);
+
+        assert!(log.warnings.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "lints", feature = "logging", feature = "math"))]
+    fn variables_which_are_never_read_yield_warnings_in_order_of_declaration() {
+        let content = "
+
+VAR in_condition = true
+VAR unused = 0
+VAR in_text = 1
+CONST unused_constant = 2
+VAR in_expression = 3
+VAR destination = -> knot
+
+{in_condition: {in_text}}
+{in_expression + 1}
+-> destination
+
+== knot
+-> END
+
+";
+
+        let log = get_validation_log_from_string(content);
+
+        let unread = log
+            .warnings
+            .iter()
+            .map(|message| match &message.message {
+                MessageKind::Warning(Warning::UnreadVariable { name, is_const }) => {
+                    (name.as_str(), *is_const, message.meta_data.line())
+                }
+                other => panic!("expected `Warning::UnreadVariable` but got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            &unread,
+            &[("unused", false, 4), ("unused_constant", true, 6)]
+        );
+    }
+
+    #[test]
+    fn variables_read_in_choices_do_not_yield_warnings() {
+        let content = "
+
+VAR in_condition = true
+VAR in_selection_text = 1
+VAR in_display_text = 2
+
+*   {in_condition} {in_selection_text} [] {in_display_text}
+
+";
+
+        let log = get_validation_log_from_string(content);
+
+        assert!(log.warnings.is_empty());
+    }
 }
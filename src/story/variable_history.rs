@@ -0,0 +1,251 @@
+//! Record of the changes to selected global variables while following a story, and
+//! observers which the host is notified of them with.
+
+use crate::{error::utils::MetaData, line::Variable, story::types::Location};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// What changed the value of a variable.
+pub enum ChangeOrigin {
+    /// The variable was assigned to by a line in the story.
+    Story {
+        /// Information about the origin of the line which assigned to the variable.
+        meta_data: MetaData,
+    },
+    /// The variable was set by the host with
+    /// [`set_variable`][crate::story::Story::set_variable()].
+    Host,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Single change to the value of a variable.
+pub struct VariableChange {
+    /// Name of the variable.
+    pub name: String,
+    /// Value of the variable before the change, or `None` if the change created it.
+    pub old: Option<Variable>,
+    /// Value of the variable after the change.
+    pub new: Variable,
+    /// Knot or stitch that the story was at when the variable changed.
+    pub location: Location,
+    /// What changed the variable.
+    pub origin: ChangeOrigin,
+    /// Turn that the variable changed on.
+    ///
+    /// Turns are counted as the number of choices that had been made when the change happened.
+    pub turn: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// History of every change to a selected set of variables, in order.
+///
+/// The history is only recorded after it has been enabled with
+/// [`enable_variable_history`][crate::story::Story::enable_variable_history()] and only
+/// for the variables that it was enabled for. It is saved along with the story state.
+///
+/// # Example
+/// ```
+/// # use inkling::{read_story_from_string, ChangeOrigin, Location, Variable};
+/// let content = "\
+/// VAR trust = 0
+/// -> interrogation
+///
+/// === interrogation ===
+/// The detective leaned back. Trust stood at {trust}.
+/// *   [Lie about the alibi] -> cell
+///
+/// === cell ===
+/// The door locked behind you.
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// story.enable_variable_history(&["trust"]);
+///
+/// let mut line_buffer = Vec::new();
+///
+/// story.resume(&mut line_buffer).unwrap();
+/// story.make_choice(0).unwrap();
+/// story.resume(&mut line_buffer).unwrap();
+///
+/// story.set_variable("trust", -3).unwrap();
+///
+/// let history = story.get_variable_history().unwrap();
+/// let change = history.get_last_change("trust").unwrap();
+///
+/// assert_eq!(change.old, Some(Variable::Int(0)));
+/// assert_eq!(change.new, Variable::Int(-3));
+/// assert_eq!(change.location, Location::from("cell"));
+/// assert_eq!(change.origin, ChangeOrigin::Host);
+/// assert_eq!(change.turn, 1);
+/// ```
+pub struct VariableHistory {
+    /// Names of variables whose changes are recorded.
+    watched: HashSet<String>,
+    /// Changes in the order that they happened.
+    changes: Vec<VariableChange>,
+}
+
+impl VariableHistory {
+    /// Get all recorded changes in the order that they happened.
+    pub fn changes(&self) -> &[VariableChange] {
+        &self.changes
+    }
+
+    /// Get all changes to a variable in the order that they happened.
+    pub fn get_changes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a VariableChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.name == name)
+    }
+
+    /// Get the most recent change to a variable, if it has changed.
+    pub fn get_last_change(&self, name: &str) -> Option<&VariableChange> {
+        self.changes.iter().rev().find(|change| change.name == name)
+    }
+
+    /// Get whether changes to a variable are recorded.
+    pub fn is_watched(&self, name: &str) -> bool {
+        self.watched.contains(name)
+    }
+
+    /// Get the names of all variables whose changes are recorded, in arbitrary order.
+    pub fn watched(&self) -> impl Iterator<Item = &str> {
+        self.watched.iter().map(|name| name.as_str())
+    }
+
+    /// Begin recording changes to a variable.
+    pub(crate) fn watch(&mut self, name: &str) {
+        self.watched.insert(name.to_string());
+    }
+
+    /// Add a change to the history if the variable is watched.
+    pub(crate) fn record(&mut self, change: VariableChange) {
+        if self.is_watched(&change.name) {
+            self.changes.push(change);
+        }
+    }
+}
+
+/// Function which is called with the changes to an observed variable.
+pub type VariableObserver = Arc<dyn Fn(&VariableChange) + Send + Sync>;
+
+#[derive(Clone, Default)]
+/// Functions which are called when observed variables change.
+///
+/// Observers are set with [`observe_variable`][crate::story::Story::observe_variable()]
+/// and are not saved with the story state.
+pub struct VariableObservers {
+    observers: HashMap<String, Vec<VariableObserver>>,
+}
+
+impl VariableObservers {
+    /// Add an observer of a variable, after the observers which it already has.
+    pub fn add<F>(&mut self, name: &str, observer: F)
+    where
+        F: Fn(&VariableChange) + Send + Sync + 'static,
+    {
+        self.observers
+            .entry(name.to_string())
+            .or_default()
+            .push(Arc::new(observer));
+    }
+
+    /// Get whether a variable has any observers.
+    pub fn is_observed(&self, name: &str) -> bool {
+        self.observers.contains_key(name)
+    }
+
+    /// Call the observers of the changed variable in the order that they were added.
+    pub fn notify(&self, change: &VariableChange) {
+        for observer in self.observers.get(&change.name).into_iter().flatten() {
+            observer(change);
+        }
+    }
+}
+
+impl fmt::Debug for VariableObservers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = self.observers.keys().collect::<Vec<_>>();
+        names.sort();
+
+        write!(f, "VariableObservers({:?})", names)
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for VariableObservers {
+    fn eq(&self, other: &Self) -> bool {
+        self.observers.len() == other.observers.len()
+            && self.observers.iter().all(|(name, lhs)| {
+                other.observers.get(name).is_some_and(|rhs| {
+                    lhs.len() == rhs.len()
+                        && lhs.iter().zip(rhs).all(|(lhs, rhs)| Arc::ptr_eq(lhs, rhs))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_change(name: &str, new: i32, turn: u32) -> VariableChange {
+        VariableChange {
+            name: name.to_string(),
+            old: None,
+            new: Variable::Int(new),
+            location: Location::from("knot"),
+            origin: ChangeOrigin::Host,
+            turn,
+        }
+    }
+
+    #[test]
+    fn only_changes_to_watched_variables_are_recorded() {
+        let mut history = VariableHistory::default();
+        history.watch("trust");
+
+        history.record(mock_change("trust", 1, 0));
+        history.record(mock_change("suspicion", 1, 0));
+        history.record(mock_change("trust", -3, 2));
+
+        assert_eq!(history.changes().len(), 2);
+        assert_eq!(history.get_changes("trust").count(), 2);
+        assert_eq!(history.get_last_change("trust").unwrap().turn, 2);
+        assert!(history.get_last_change("suspicion").is_none());
+    }
+
+    #[test]
+    fn observers_of_a_variable_are_called_in_the_order_they_were_added() {
+        use std::sync::Mutex;
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut observers = VariableObservers::default();
+
+        for i in 0..2 {
+            let calls = calls.clone();
+            observers.add("trust", move |change| {
+                calls.lock().unwrap().push((i, change.turn));
+            });
+        }
+
+        assert!(observers.is_observed("trust"));
+        assert!(!observers.is_observed("suspicion"));
+
+        observers.notify(&mock_change("trust", 1, 3));
+        observers.notify(&mock_change("suspicion", 1, 4));
+
+        assert_eq!(*calls.lock().unwrap(), vec![(0, 3), (1, 3)]);
+    }
+}
@@ -14,3 +14,103 @@ pub enum OrderingDerive {
     Less,
     Greater,
 }
+
+/// Get the names from a set of candidates which are close to a name.
+///
+/// Used to suggest what a misspelled name might have been intended to be. Candidates are
+/// compared by their edit distance to the name, ignoring case. Close matches are returned
+/// in order of increasing distance, with at most three matches returned.
+pub fn get_close_matches<'a, I>(name: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    const MAX_MATCHES: usize = 3;
+
+    let name_lower = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut matches = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            let distance = get_edit_distance(&name_lower, &candidate.to_lowercase());
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+
+    matches.sort();
+    matches.dedup();
+
+    matches
+        .into_iter()
+        .take(MAX_MATCHES)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Get the Levenshtein distance between two strings.
+///
+/// This is the number of single character insertions, deletions or substitutions required
+/// to change one string into the other.
+fn get_edit_distance(from: &str, to: &str) -> usize {
+    let to_chars = to.chars().collect::<Vec<_>>();
+    let mut distances = (0..=to_chars.len()).collect::<Vec<_>>();
+
+    for (i, from_char) in from.chars().enumerate() {
+        let mut prev_diagonal = distances[0];
+        distances[0] = i + 1;
+
+        for (j, &to_char) in to_chars.iter().enumerate() {
+            let substitution = prev_diagonal + if from_char == to_char { 0 } else { 1 };
+            let insertion = distances[j] + 1;
+            let deletion = distances[j + 1] + 1;
+
+            prev_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(insertion).min(deletion);
+        }
+    }
+
+    distances[to_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_insertions_deletions_and_substitutions() {
+        assert_eq!(get_edit_distance("kitten", "kitten"), 0);
+        assert_eq!(get_edit_distance("kitten", "sitting"), 3);
+        assert_eq!(get_edit_distance("", "abc"), 3);
+        assert_eq!(get_edit_distance("abc", ""), 3);
+        assert_eq!(get_edit_distance("château", "chateau"), 1);
+    }
+
+    #[test]
+    fn close_matches_are_sorted_by_distance_and_ignore_case() {
+        let candidates = vec!["tripol", "Tripoli", "addis_ababa", "tripoli_two"];
+
+        assert_eq!(
+            get_close_matches("tripoli", candidates),
+            &["Tripoli", "tripol"]
+        );
+    }
+
+    #[test]
+    fn distant_candidates_and_the_name_itself_are_not_close_matches() {
+        let candidates = vec!["cinema", "tripoli", "knot"];
+
+        assert!(get_close_matches("tripoli", candidates.clone())
+            .iter()
+            .all(|name| name != "tripoli"));
+        assert!(get_close_matches("addis_ababa", candidates).is_empty());
+    }
+
+    #[test]
+    fn at_most_three_close_matches_are_returned() {
+        let candidates = vec!["knot1", "knot2", "knot3", "knot4"];
+
+        assert_eq!(get_close_matches("knot", candidates).len(), 3);
+    }
+}
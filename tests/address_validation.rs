@@ -56,6 +56,44 @@ Welcome to Duck Burg!
     }
 }
 
+#[test]
+fn diverts_are_validated_to_give_arguments_for_every_parameter_of_their_knot() {
+    let content = "
+
+-> duckburg(\"Scrooge\")
+
+== duckburg(owner, amount)
+{owner} counts {amount} coins.
+-> money_bin(amount)
+
+= money_bin
+-> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(error)) => {
+            let mut arguments = error
+                .invalid_address_errors
+                .iter()
+                .map(|err| match &err.kind {
+                    error::parse::address::InvalidAddressErrorKind::InvalidDivertArguments {
+                        expected,
+                        found,
+                        ..
+                    } => (*expected, *found),
+                    other => panic!("expected `InvalidDivertArguments` but got {:?}", other),
+                })
+                .collect::<Vec<_>>();
+
+            arguments.sort();
+
+            assert_eq!(arguments, &[(0, 1), (2, 1)]);
+        }
+        _ => panic!(),
+    }
+}
+
 #[test]
 fn diverts_in_choice_text_are_validated() {
     let content = "
@@ -152,3 +190,57 @@ Empty knot.
 
     assert!(!buffer.contains("Raw("));
 }
+
+#[test]
+fn host_addresses_can_be_validated_before_they_are_used() {
+    let content = "
+
+== station
+= platform
+The train was late.
+
+== town
+The streets were empty.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let platform = story.parse_location("station . platform").unwrap();
+    assert_eq!(platform, Location::with_stitch("station", "platform"));
+    assert_eq!(story.get_num_visited(&platform), Some(0));
+
+    let town = story.parse_location("town").unwrap();
+    assert_eq!(town, Location::from("town"));
+    assert!(story.move_to(&town).is_ok());
+
+    for address in &["", "station.", "station.waiting_room", "city", ".platform"] {
+        match story.parse_location(address) {
+            Err(InklingError::InvalidAddress { .. }) => (),
+            other => panic!("expected an invalid address for '{}': {:?}", address, other),
+        }
+    }
+}
+
+#[test]
+fn full_label_addresses_in_conditions_are_validated_part_by_part() {
+    let content = "
+
+{duckburg.money_bin.vault: You have been in the vault.}
+
+== duckburg
+= money_bin
+-> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => {
+            let error = &err.invalid_address_errors[0];
+
+            assert!(format!("{}", error)
+                .contains("no label with name 'vault' in stitch 'money_bin' of knot 'duckburg'"));
+        }
+        _ => panic!(),
+    }
+}
@@ -0,0 +1,187 @@
+use inkling::*;
+
+#[test]
+fn turns_counts_the_choices_made_and_turns_since_counts_from_the_last_visit() {
+    let content = "\
+-> hub
+
+=== hub ===
+Turns: {TURNS()}, since the hub: {TURNS_SINCE(-> hub)}, since the study: {TURNS_SINCE(-> study)}.
++   [Enter the study] -> study
++   [Stay] -> hub
+
+=== study ===
+{TURNS_SINCE(-> hub) == 1: You just came from the hub.}
+-> hub
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Turns: 0, since the hub: 0, since the study: -1.\n"
+    );
+
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You just came from the hub.\n");
+    assert_eq!(
+        &line_buffer[1].text,
+        "Turns: 1, since the hub: 0, since the study: 0.\n"
+    );
+
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Turns: 2, since the hub: 0, since the study: 1.\n"
+    );
+}
+
+#[test]
+fn turns_since_can_be_used_in_choice_conditions() {
+    let content = "\
+- (opts)
++   {TURNS_SINCE(-> opts) > 0} [Wait again] -> opts
++   [Wait] -> opts
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Wait");
+}
+
+#[test]
+fn turns_since_must_be_given_a_divert_target() {
+    assert!(read_story_from_string("{TURNS_SINCE(2)}").is_err());
+    assert!(read_story_from_string("{TURNS_SINCE()}").is_err());
+    assert!(read_story_from_string("{TURNS(-> knot)}\n=== knot ===\n-> END").is_err());
+}
+
+#[test]
+fn random_numbers_are_within_their_bounds_and_repeat_with_the_same_seed() {
+    let content = "\
+The dealer seeds the deck.{SEED_RANDOM(1859)}
+-> roll
+
+=== roll ===
+You roll {RANDOM(1, 6)}.
++   [Roll again] -> roll
+";
+
+    let roll = || {
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        for _ in 0..10 {
+            story.resume(&mut line_buffer).unwrap();
+            story.make_choice(0).unwrap();
+        }
+
+        line_buffer
+    };
+
+    let lines = roll();
+
+    assert_eq!(&lines[0].text, "The dealer seeds the deck.\n");
+    assert!(lines[1..].iter().all(|line| {
+        let value = line
+            .text
+            .trim_start_matches("You roll ")
+            .trim_end_matches(".\n");
+        (1..=6).contains(&value.parse::<i32>().unwrap())
+    }));
+
+    assert_eq!(lines, roll());
+}
+
+#[test]
+#[cfg(all(feature = "logging", not(feature = "random")))]
+fn random_logs_a_warning_and_returns_its_lower_bound_without_the_random_feature() {
+    use inkling::log::{MessageKind, Warning};
+
+    let mut story = read_story_from_string("You roll {RANDOM(1, 6)}.").unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You roll 1.\n");
+    assert_eq!(story.log.warnings.len(), 1);
+    assert!(matches!(
+        story.log.warnings[0].message,
+        MessageKind::Warning(Warning::RandomFunctionNoRandom)
+    ));
+}
+
+#[test]
+fn random_must_be_given_numbers_as_bounds() {
+    let content = "\
+You roll {RANDOM(\"one\", 6)}.
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn numeric_functions_can_be_used_in_text_and_calculations() {
+    let content = "\
+VAR level = 3
+VAR gold = 10.0
+You need {POW(2, level)} experience to reach the next level.
+You split the gold into {FLOOR(gold / 3)} and {INT(CEILING(gold / 3))} coins.
+{ABS(level - 5) == 2: You are two levels from the top.}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "You need 8 experience to reach the next level.\n"
+    );
+    assert_eq!(
+        &line_buffer[1].text,
+        "You split the gold into 3 and 4 coins.\n"
+    );
+    assert_eq!(&line_buffer[2].text, "You are two levels from the top.\n");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn min_and_max_clamp_values_in_calculations() {
+    let content = "\
+VAR health = 8
+VAR damage = 2.5
+~ health = MIN(health + 5, 10)
+You have {health} health.
+You take {MAX(damage - 5, 0)} damage, at most {MAX(damage, 1)}.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You have 10 health.\n");
+    assert_eq!(&line_buffer[1].text, "You take 0 damage, at most 2.5.\n");
+}
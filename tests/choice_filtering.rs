@@ -518,3 +518,99 @@ A crossing! Which path do you take?
 
     assert_eq!(&line_buffer[0].text, "This chamber used to hold a torch. ");
 }
+
+#[test]
+fn prompts_from_identical_stories_are_equal_and_can_be_hashed() {
+    let content = "
+
+The lift doors opened. # lift
+*   Step inside.
++   Wait for the next one.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut other = read_story_from_string(content).unwrap();
+
+    let (mut line_buffer, mut other_buffer) = (Vec::new(), Vec::new());
+
+    let prompt = story.resume(&mut line_buffer).unwrap();
+    let other_prompt = other.resume(&mut other_buffer).unwrap();
+
+    assert_eq!(prompt, other_prompt);
+    assert_eq!(line_buffer, other_buffer);
+    assert!(!matches!(prompt, Prompt::Done(..)));
+
+    let choices = prompt.get_choices().unwrap();
+    let unique = choices
+        .iter()
+        .chain(other_prompt.get_choices().unwrap().iter())
+        .cloned()
+        .collect::<std::collections::HashSet<_>>();
+
+    assert_eq!(unique.len(), 2);
+}
+
+#[test]
+fn choices_have_weights_from_their_weight_tags() {
+    let content = "
+
+The vendor held out two skewers.
+*   Take the chicken. # weight: 3
+*   Take the squid. # spicy
+*   Take both. # weight: 0
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+    let weights = choices
+        .iter()
+        .map(|choice| choice.weight)
+        .collect::<Vec<_>>();
+
+    assert_eq!(weights, &[3, 1, 0]);
+    assert_eq!(&choices[0].tags, &["weight: 3".to_string()]);
+}
+
+#[test]
+fn choices_with_several_conditions_are_only_presented_if_all_are_fulfilled() {
+    let content = "\
+VAR torch = true
+VAR dark_room = 1
+- (opts)
+*   {torch} {dark_room > 1} [Light the way] You light the way.
+*   {torch}{not (dark_room > 1)} {dark_room == 1} [Wait] You wait.
+    ~ dark_room = 2
+    -> opts
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Wait");
+
+    story.make_choice(0).unwrap();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Light the way");
+}
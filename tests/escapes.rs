@@ -0,0 +1,67 @@
+use inkling::*;
+
+#[test]
+fn escaped_characters_are_printed_without_their_backslashes() {
+    let content = r"
+You see \{curly\} braces, a pipe \| and a \#hashtag. # real tag
+Arrows \-> and glue \<> are kept, as is a backslash \\.
+\* This line is not a choice.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(
+        &line_buffer[0].text,
+        "You see {curly} braces, a pipe | and a #hashtag.\n"
+    );
+    assert_eq!(&line_buffer[0].tags, &["real tag".to_string()]);
+    assert_eq!(
+        &line_buffer[1].text,
+        "Arrows -> and glue <> are kept, as is a backslash \\.\n"
+    );
+    assert_eq!(&line_buffer[2].text, "* This line is not a choice.\n");
+}
+
+#[test]
+fn choices_can_contain_escaped_braces_and_brackets() {
+    let content = r"
+*   \{Shout\} \[loudly\] [now] at the door
+    -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&choices[0].text, "{Shout} [loudly] now");
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "{Shout} [loudly] at the door\n");
+}
+
+#[test]
+fn escaped_comment_markers_do_not_begin_comments() {
+    let content = r"
+A \// B // a comment
+C \/* D */ E /* another comment */
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "A // B\n");
+    assert_eq!(&line_buffer[1].text, "C /* D */ E\n");
+}
@@ -0,0 +1,211 @@
+use inkling::error::parse::address::InvalidAddressErrorKind;
+use inkling::*;
+
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[cfg(feature = "math")]
+fn bound_external_functions_are_called_from_text_and_logic_lines() {
+    let content = "\
+EXTERNAL add(a, b)
+
+VAR total = 0
+
+One and two is {add(1, 2)}.
+~ total = add(total, 10) * 2
+The total is {total}.
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .bind_external_function("add", |arguments: &[Variable]| match arguments {
+            [Variable::Int(a), Variable::Int(b)] => Some(Variable::Int(a + b)),
+            _ => None,
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "One and two is 3.\n");
+    assert_eq!(&line_buffer[1].text, "The total is 20.\n");
+}
+
+#[test]
+fn external_functions_can_be_declared_after_the_prelude_and_call_into_the_host() {
+    let content = "\
+-> cave
+
+=== cave ===
+EXTERNAL play_sound(name)
+{play_sound(\"drip\")} Water dripped from the ceiling.
+-> END
+";
+
+    let played = Arc::new(Mutex::new(Vec::new()));
+    let played_sounds = played.clone();
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .bind_external_function("play_sound", move |arguments: &[Variable]| {
+            played_sounds.lock().unwrap().extend(arguments.to_vec());
+            Some(Variable::from(""))
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&*played.lock().unwrap(), &[Variable::from("drip")]);
+}
+
+#[test]
+fn calling_an_unbound_external_function_yields_an_error() {
+    let content = "\
+EXTERNAL play_sound(name)
+
+{play_sound(\"drip\")}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let error = story.resume(&mut line_buffer).unwrap_err();
+
+    assert!(format!("{}", error).contains("'play_sound' which has not been bound"));
+}
+
+#[test]
+fn only_declared_external_functions_can_be_bound() {
+    let content = "\
+EXTERNAL play_sound(name)
+
+=== function max(a, b) ===
+~ return b
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(story
+        .bind_external_function("play_sound", |_: &[Variable]| None)
+        .is_ok());
+    assert!(story
+        .bind_external_function("max", |_: &[Variable]| None)
+        .is_err());
+    assert!(story
+        .bind_external_function("stop_sound", |_: &[Variable]| None)
+        .is_err());
+}
+
+#[test]
+fn external_functions_are_validated_to_be_called_with_their_parameters() {
+    let content = "\
+EXTERNAL play_sound(name)
+
+{play_sound()}
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn unbound_external_functions_are_validation_errors_if_checked() {
+    let content = "\
+EXTERNAL play_sound(name)
+EXTERNAL stop_sound()
+
+{play_sound(\"drip\")}
+";
+
+    let mut options = ReadOptions {
+        check_external_functions: true,
+        ..ReadOptions::default()
+    };
+
+    options
+        .external_functions
+        .bind("stop_sound", |_: &[Variable]| None);
+
+    match read_story_from_string_with_options(content, &options) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.invalid_address_errors.len(), 1);
+
+            match &error.invalid_address_errors[0].kind {
+                InvalidAddressErrorKind::UnboundExternalFunction { name } => {
+                    assert_eq!(name, "play_sound")
+                }
+                other => panic!("expected `UnboundExternalFunction` but got {:?}", other),
+            }
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+
+    options
+        .external_functions
+        .bind("play_sound", |_: &[Variable]| Some(Variable::from("")));
+
+    assert!(read_story_from_string_with_options(content, &options).is_ok());
+}
+
+#[test]
+fn functions_with_the_name_of_an_external_function_are_called_until_it_is_bound() {
+    let content = "\
+EXTERNAL describe(name)
+
++   [Look] {describe(\"door\")}
+    -> END
+
+=== function describe(name) ===
+{ name == \"door\":
+    ~ return \"a plain door\"
+}
+~ return name
+";
+
+    let mut options = ReadOptions {
+        check_external_functions: true,
+        ..ReadOptions::default()
+    };
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "a plain door\n");
+
+    options
+        .external_functions
+        .bind("describe", |_: &[Variable]| {
+            Some(Variable::from("an oak door"))
+        });
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "an oak door\n");
+}
+
+#[test]
+fn knots_cannot_have_the_name_of_an_external_function() {
+    let content = "\
+EXTERNAL play_sound(name)
+
+=== play_sound ===
+Clang!
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
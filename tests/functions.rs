@@ -0,0 +1,320 @@
+use inkling::error::parse::print_read_error;
+use inkling::*;
+
+#[test]
+#[cfg(feature = "math")]
+fn functions_are_called_from_text_and_logic_lines() {
+    let content = "\
+VAR x = 3
+VAR y = 7
+
+The highest is {max(x, y)}.
+~ temp lowest = min(x, y) * 10
+The lowest is {lowest}.
+-> END
+
+=== function max(a, b) ===
+{ a > b:
+    ~ return a
+}
+~ return b
+
+=== function min(a, b) ===
+{ a < b:
+    ~ return a
+- else:
+    ~ return b
+}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The highest is 7.\n");
+    assert_eq!(&line_buffer[1].text, "The lowest is 30.\n");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn functions_can_call_themselves_and_other_functions() {
+    let content = "\
+{factorial(5)} and {double_factorial(3)}
+
+=== function factorial(n) ===
+{ n <= 1:
+    ~ return 1
+}
+~ return n * factorial(n - 1)
+
+=== function double_factorial(n) ===
+~ temp value = factorial(n)
+~ return value * 2
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "120 and 12\n");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn text_written_by_functions_called_in_lines_is_spliced_before_their_value() {
+    let content = "\
+VAR mood = 1
+
+You look {describe(mood)} today.
+Hi {greet()} and {greet() + 1}.
+-> END
+
+=== function describe(x) ===
+{x > 0: happy|sad}
+~ return \"!\"
+
+=== function greet ===
+hello
+~ return 1
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "You look happy! today.\n");
+    assert_eq!(&line_buffer[1].text, "Hi hello1 and hello2.\n");
+}
+
+#[test]
+fn sequences_in_functions_continue_from_where_they_were_in_the_last_call() {
+    let content = "\
+{count()}
+{count()}
+{count()}
+-> END
+
+=== function count ===
+{&one|two|three}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "one\n");
+    assert_eq!(&line_buffer[1].text, "two\n");
+    assert_eq!(&line_buffer[2].text, "three\n");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn functions_called_in_lines_keep_their_changes_and_need_not_return_a_value() {
+    let content = "\
+VAR coins = 0
+
+You find {add_coins(3)} coins and have {coins}.
+{add_coins(2)} more. You have {coins} coins.
+-> END
+
+=== function add_coins(n) ===
+~ coins = coins + n
+{n}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You find 3 coins and have 3.\n");
+    assert_eq!(&line_buffer[1].text, "2 more. You have 5 coins.\n");
+    assert_eq!(story.get_variable("coins"), Some(Variable::Int(5)));
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn functions_called_in_expressions_keep_their_changes() {
+    let content = "\
+VAR counter = 0
+
+~ temp a = bump()
+~ temp b = bump()
+The counts were {a} and {b}.
+-> END
+
+=== function bump() ===
+~ counter = counter + 1
+~ return counter
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The counts were 1 and 2.\n");
+    assert_eq!(story.get_variable("counter"), Some(Variable::Int(2)));
+}
+
+#[test]
+fn calls_to_functions_can_be_used_as_conditions() {
+    let content = "\
+VAR keys = 1
+
+The door is {is_unlocked(): open|locked}.
+*   {has_keys()} [Unlock it] -> END
+*   {not has_keys()} [Leave] -> END
+
+=== function is_unlocked() ===
+{ keys > 1:
+    ~ return true
+}
+~ return false
+
+=== function has_keys() ===
+~ return keys
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The door is locked.\n");
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Unlock it");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn functions_which_recurse_without_end_yield_an_error() {
+    let content = "\
+VAR count = 0
+
++   [Count] {forever(count)}
+    -> END
+
+=== function forever(n) ===
+{ n >= 0:
+    ~ return forever(n + 1)
+}
+~ return n
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 1);
+            assert!(format!("{}", error.variable_errors[0]).contains("forever"));
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+fn calls_to_unknown_functions_or_with_wrong_arguments_yield_validation_errors() {
+    let content = "\
+{mux(1, 2)}
+{max(1)}
+
+=== function max(a, b) ===
+~ return b
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.invalid_address_errors.len(), 2);
+
+            match &error.invalid_address_errors[0].kind {
+                error::parse::address::InvalidAddressErrorKind::UnknownFunction {
+                    name,
+                    suggestions,
+                } => {
+                    assert_eq!(name, "mux");
+                    assert_eq!(suggestions, &["max"]);
+                }
+                other => panic!("expected `UnknownFunction` but got {:?}", other),
+            }
+
+            match &error.invalid_address_errors[1].kind {
+                error::parse::address::InvalidAddressErrorKind::InvalidNumberOfArguments {
+                    expected,
+                    found,
+                    ..
+                } => assert_eq!((*expected, *found), (2, 1)),
+                other => panic!("expected `InvalidNumberOfArguments` but got {:?}", other),
+            }
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+fn functions_cannot_be_diverted_to_and_only_functions_can_return() {
+    let content = "\
+-> max
+
+=== function max(a, b) ===
+~ return b
+";
+
+    assert!(read_story_from_string(content).is_err());
+
+    let content = "\
+Hello.
+~ return 5
+";
+
+    let error = read_story_from_string(content).unwrap_err();
+
+    assert!(print_read_error(&error)
+        .unwrap()
+        .contains("can only return from functions"));
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn logic_lines_can_call_functions_for_their_changes_and_text() {
+    let content = "\
+VAR coins = 0
+~ reward(3)
+~ reward(2)
+You have {coins} coins.
+~ nothing()
+-> END
+
+=== function reward(amount) ===
+You find {amount} coins.
+~ coins = coins + amount
+~ return coins
+
+=== function nothing() ===
+~ return
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "You find 3 coins.\n");
+    assert_eq!(&line_buffer[1].text, "You find 2 coins.\n");
+    assert_eq!(&line_buffer[2].text, "You have 5 coins.\n");
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "includes")]
+
+use inkling::*;
+
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+/// Write a set of files into a new directory and return its path.
+fn write_files(directory: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!(
+        "inkling-includes-{}-{}",
+        directory,
+        std::process::id()
+    ));
+
+    for (name, content) in files {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    root
+}
+
+#[test]
+fn stories_read_from_paths_include_files_relative_to_the_including_file() {
+    let root = write_files(
+        "relative",
+        &[
+            (
+                "main.ink",
+                "\
+INCLUDE chapters/harbor.ink
+VAR coins = 3
+You arrive in town with {coins} coins.
+-> harbor
+",
+            ),
+            (
+                "chapters/harbor.ink",
+                "\
+INCLUDE ships.ink
+=== harbor ===
+The harbor is busy.
+-> ships
+",
+            ),
+            (
+                "chapters/ships.ink",
+                "\
+=== ships ===
+A ship is about to leave.
+-> END
+",
+            ),
+        ],
+    );
+
+    let mut story = read_story_from_path(root.join("main.ink"), &ReadOptions::default()).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "You arrive in town with 3 coins.\n");
+    assert_eq!(&line_buffer[1].text, "The harbor is busy.\n");
+    assert_eq!(&line_buffer[2].text, "A ship is about to leave.\n");
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn errors_in_included_files_print_the_name_of_the_file() {
+    let root = write_files(
+        "errors",
+        &[
+            ("main.ink", "INCLUDE harbor.ink\n-> harbor\n"),
+            ("harbor.ink", "=== harbor ===\n-> docks\n"),
+        ],
+    );
+
+    let error = read_story_from_path(root.join("main.ink"), &ReadOptions::default()).unwrap_err();
+    let printed = error::parse::print_read_error(&error).unwrap();
+
+    assert!(printed.contains("harbor.ink:2"), "{}", printed);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn stories_can_be_read_through_a_custom_resolver() {
+    let mut files = HashMap::new();
+    files.insert("bundle/main.ink", "INCLUDE lighthouse.ink\n-> lighthouse");
+    files.insert(
+        "bundle/lighthouse.ink",
+        "=== lighthouse ===\nThe keeper climbs the stairs.\n-> END",
+    );
+
+    let resolver = |name: &str| {
+        files
+            .get(name)
+            .map(|content| content.to_string())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    };
+
+    let mut story =
+        read_story_with_resolver("bundle/main.ink", &resolver, &ReadOptions::default()).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The keeper climbs the stairs.\n");
+}
+
+#[test]
+fn missing_included_files_yield_include_errors() {
+    struct Archive;
+
+    impl IncludeResolver for Archive {
+        fn resolve_name(&self, name: &str, _: &str) -> String {
+            format!("archive://{}", name)
+        }
+
+        fn read_file(&self, name: &str) -> io::Result<String> {
+            match name {
+                "main.ink" => Ok("INCLUDE missing.ink\nHello!".to_string()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+    }
+
+    match read_story_with_resolver("main.ink", &Archive, &ReadOptions::default()) {
+        Err(error::ReadError::IncludeError(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(&errors[0].name, "missing.ink");
+            assert_eq!(&errors[0].resolved_name, "archive://missing.ink");
+            assert_eq!(errors[0].meta_data.file(), Some("main.ink"));
+        }
+        other => panic!(
+            "expected `ReadError::IncludeError` but got {:?}",
+            other.err()
+        ),
+    }
+}
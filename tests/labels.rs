@@ -0,0 +1,368 @@
+use inkling::error::parse::address::InvalidAddressErrorKind;
+use inkling::*;
+
+#[test]
+#[cfg(feature = "math")]
+fn diverts_to_labeled_gathers_continue_from_the_gather() {
+    let content = "\
+~ temp knocks = 0
+- (knock) You knock on the door.
+~ knocks = knocks + 1
+{knocks < 3: -> knock}
+Nobody answers after {knocks} knocks.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 4);
+    assert_eq!(&line_buffer[0].text, "You knock on the door.\n");
+    assert_eq!(&line_buffer[2].text, "You knock on the door.\n");
+    assert_eq!(&line_buffer[3].text, "Nobody answers after 3 knocks.\n");
+}
+
+#[test]
+fn labeled_choices_can_be_diverted_to_and_loop_back_into_the_weave() {
+    let content = "\
+- (opts) What do you do?
+*   [Look around] You look around.
+    -> opts
+*   (wait) [Wait] You wait.
+*   [Leave] -> wait
+- The train departs.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You look around.\n");
+    assert_eq!(&line_buffer[1].text, "What do you do?\n");
+    assert_eq!(choices.len(), 2);
+
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You wait.\n");
+    assert_eq!(&line_buffer[1].text, "The train departs.\n");
+}
+
+#[test]
+fn labels_in_other_knots_and_stitches_can_be_diverted_to_with_their_full_address() {
+    let content = "\
+-> station.platform.boarding
+
+=== station ===
+- (entrance) You enter the station.
+-> END
+
+= platform
+You wait on the platform.
+- (boarding) You board the train.
+-> carriage
+
+= carriage
+You find a seat.
+-> station.entrance
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "You board the train.\n");
+    assert_eq!(&line_buffer[1].text, "You find a seat.\n");
+    assert_eq!(&line_buffer[2].text, "You enter the station.\n");
+}
+
+#[test]
+fn labels_in_the_current_stitch_shadow_stitches_and_labels_of_the_knot() {
+    let content = "\
+-> station.platform
+
+=== station ===
+- (waiting) You wait in the hall.
+-> END
+
+= platform
+-> waiting
+- (waiting) You wait on the platform.
+-> END
+
+= waiting
+You wait in the waiting room.
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You wait on the platform.\n");
+}
+
+#[test]
+fn diverts_to_unknown_labels_yield_errors_with_suggestions() {
+    let content = "\
+-> station.platform.bording
+
+=== station ===
+= platform
+- (boarding) You board the train.
+-> END
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.invalid_address_errors.len(), 1);
+
+            match &error.invalid_address_errors[0].kind {
+                InvalidAddressErrorKind::UnknownLabel {
+                    label_name,
+                    suggestions,
+                    ..
+                } => {
+                    assert_eq!(label_name, "bording");
+                    assert_eq!(suggestions, &vec!["boarding".to_string()]);
+                }
+                other => panic!("expected `UnknownLabel` but got {:?}", other),
+            }
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+fn labels_evaluate_to_their_number_of_visits_in_conditions_and_text() {
+    let content = "\
+-> room
+=== room ===
+- (opts)
+*   (saw_clue) [Search the desk] You find a letter.
+*   {saw_clue} [Read the letter] It is addressed to you.
+*   {not saw_clue} [Leave] -> END
+- You have found the clue {saw_clue} time and looked {opts} times.
+-> opts
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 2);
+
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You find a letter.\n");
+    assert_eq!(
+        &line_buffer[1].text,
+        "You have found the clue 1 time and looked 1 times.\n"
+    );
+
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Read the letter");
+}
+
+#[test]
+fn labeled_choices_count_the_number_of_times_they_were_selected() {
+    let content = "\
+-> room
+=== room ===
+- (opts)
++   (knock) [Knock] You knock.
++   [Leave] You knocked {knock} times. -> END
+- -> opts
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You knocked 2 times.\n");
+}
+
+#[test]
+fn labels_in_other_knots_are_read_with_their_full_address() {
+    let content = "\
+-> hallway
+=== hallway ===
+- (lights) The lights flicker.
+-> study
+
+=== study ===
+The lights in the hallway have flickered {hallway.lights} time.
+{hallway.lights > 0: You remember them.}
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[1].text,
+        "The lights in the hallway have flickered 1 time.\n"
+    );
+    assert_eq!(&line_buffer[2].text, "You remember them.\n");
+}
+
+#[test]
+fn the_story_can_be_moved_to_labels_with_their_full_address() {
+    let content = "\
+=== station ===
+- (entrance) You enter the station.
+-> platform
+
+= platform
+You wait on the platform.
+- (boarding) You board the train.
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story
+        .move_to_address("station.platform.boarding", true)
+        .unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "You board the train.\n");
+
+    line_buffer.clear();
+    story.move_to_address("station.entrance", true).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You enter the station.\n");
+    assert_eq!(&line_buffer[1].text, "You wait on the platform.\n");
+}
+
+#[test]
+fn moves_to_addresses_without_counting_the_visit_leave_later_visits_counted() {
+    let content = "\
+=== hall ===
+The hall has been visited {hall} times.
++   [Wait] -> hall
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.move_to_address("hall", false).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The hall has been visited 0 times.\n");
+    assert_eq!(story.get_num_visited(&"hall".into()), Some(0));
+
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The hall has been visited 1 times.\n");
+    assert_eq!(story.get_num_visited(&"hall".into()), Some(1));
+}
+
+#[test]
+fn moving_the_story_to_an_unknown_label_yields_an_error() {
+    let content = "\
+=== station ===
+= platform
+- (boarding) You board the train.
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    match story.move_to_address("station.platform.bording", true) {
+        Err(InklingError::InvalidLabel { location, label }) => {
+            assert_eq!(location, Location::with_stitch("station", "platform"));
+            assert_eq!(&label, "bording");
+        }
+        other => panic!("expected `InklingError::InvalidLabel` but got {:?}", other),
+    }
+
+    assert!(story
+        .move_to_address("station.platform.boarding", true)
+        .is_ok());
+}
+
+#[test]
+fn visits_to_labels_and_stitches_can_be_queried_with_their_full_address() {
+    let content = "\
+-> room
+=== room ===
+- (opts)
++   (knock) [Knock] You knock.
++   [Leave] -> END
+- -> opts
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert!(!story.has_visited("room.knock").unwrap());
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert!(story.has_visited("room.knock").unwrap());
+    assert!(story.has_visited("room").unwrap());
+    assert_eq!(story.times_visited(" room.opts ").unwrap(), 2);
+
+    assert!(matches!(
+        story.times_visited("room.knok"),
+        Err(InklingError::InvalidAddress { .. })
+    ));
+    assert!(matches!(
+        story.has_visited("hallway"),
+        Err(InklingError::InvalidAddress { .. })
+    ));
+}
@@ -61,6 +61,92 @@ from Nantucket. {|||||We're besties.}
     );
 }
 
+#[test]
+fn variant_sequences_and_conditions_can_be_nested_inside_each_other() {
+    let content = "
+
+VAR lantern = true
+
+-> start
+
+== start
+{first {a|the} {lantern: lit {lamp|torch}|dark room}|second {lantern: {&glow|flicker}}}.
+
++   [Continue] -> start
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    for _ in 0..4 {
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+    }
+
+    assert_eq!(&line_buffer[0].text, "first a lit lamp.\n");
+    assert_eq!(&line_buffer[1].text, "second glow.\n");
+    assert_eq!(&line_buffer[2].text, "second flicker.\n");
+    assert_eq!(&line_buffer[3].text, "second glow.\n");
+}
+
+#[test]
+fn unmatched_braces_in_nested_variants_yield_errors_with_their_position() {
+    let content = "\
+Hello!
+{first {a|b|second} time.
+*   {first {a|b}|second} [Choice] {
+";
+
+    let error = read_story_from_string(content).unwrap_err();
+    let printed = error::parse::print_read_error(&error).unwrap();
+
+    assert!(
+        printed.contains("(line 2) line has unmatched curly '{}' braces (at character 1)"),
+        "{}",
+        printed
+    );
+    assert!(
+        printed.contains("(line 3) line has unmatched curly '{}' braces (at character 35)"),
+        "{}",
+        printed
+    );
+}
+
+#[test]
+fn shuffled_variants_deal_every_item_once_before_reshuffling() {
+    let content = "
+
+-> deal
+
+== deal
+{~Ace|King|Queen}
++   [Draw again] -> deal
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    story.set_seed(1859);
+
+    let mut line_buffer = Vec::new();
+
+    for _ in 0..6 {
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+    }
+
+    for round in line_buffer.chunks(3) {
+        let mut cards = round
+            .iter()
+            .map(|line| line.text.trim().to_string())
+            .collect::<Vec<_>>();
+
+        cards.sort();
+
+        assert_eq!(cards, &["Ace", "King", "Queen"]);
+    }
+}
+
 #[test]
 fn choices_can_have_variants_in_selection_text() {
     let content = "
@@ -110,6 +196,48 @@ You meet with Aaron.
     assert_eq!(&choices[1].text, "Sorry, I want some me-time right now");
 }
 
+#[test]
+fn choices_can_begin_with_conditional_content_and_variants() {
+    let content = "
+
+VAR met_anna = false
+
+-> square
+
+== square
++   {met_anna: Greet Anna|Introduce yourself}[.] to the woman.
+    ~ met_anna = true
+    -> square
++   {met_anna} {Ask about the weather|Ask about the harbor}
+    -> square
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 1);
+    assert_eq!(&choices[0].text, "Introduce yourself.");
+
+    story.make_choice(0).unwrap();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Introduce yourself to the woman.\n");
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Greet Anna.");
+    assert_eq!(&choices[1].text, "Ask about the weather");
+}
+
 #[test]
 fn lines_can_have_conditional_content() {
     let content = "
@@ -169,6 +297,7 @@ I {nantucket: {nantucket > 1: {nantucket > 2: many times | twice } | once } | ha
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn mathematical_expressions_can_be_used_in_lines() {
     let content = "
 
@@ -198,6 +327,7 @@ Strings can be added, too: <>
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn variables_can_be_used_in_mathematical_operations() {
     let content = "
 
@@ -227,6 +357,7 @@ Float calculation works better:
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn variable_expressions_always_use_updated_variables() {
     let content = "
 
@@ -264,6 +395,7 @@ VAR b = 5
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn conditions_may_use_expressions_on_left_or_right_hand_side() {
     let content = "
 
@@ -287,3 +419,339 @@ VAR b = 5.0
     assert_eq!(&line_buffer[2].text, "False\n");
     assert_eq!(&line_buffer[3].text, "True\n");
 }
+
+#[test]
+fn proofreading_mode_writes_every_variant_while_following_the_story_as_usual() {
+    let content = "
+
+-> start
+
+== start
+The bell rang {once|twice|three times}.
+{start > 1: The hall was empty.}
++   [Ring again] -> start
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.set_proofreading(true);
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "The bell rang {sequence: once | twice | three times}.\n"
+    );
+    assert_eq!(
+        &line_buffer[1].text,
+        "{if start > 1: The hall was empty.}\n"
+    );
+
+    story.set_proofreading(false);
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[2].text, "The bell rang twice.\n");
+    assert_eq!(&line_buffer[3].text, "The hall was empty.\n");
+}
+
+#[test]
+fn multi_line_blocks_select_a_group_of_lines_on_every_visit() {
+    let content = "
+
+-> harbour
+
+== harbour
+{ stopping:
+    -   The ferry was late.
+        Gulls circled overhead.
+    -   The ferry was on time.
+    -   The ferry had left already.
+}
+{ cycle:
+    - It was raining.
+    - It was sunny.
+}
++   [Wait] -> harbour
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    for _ in 0..3 {
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+    }
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = line_buffer
+        .iter()
+        .map(|line| line.text.trim())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        &text,
+        &[
+            "The ferry was late.",
+            "Gulls circled overhead.",
+            "It was raining.",
+            "The ferry was on time.",
+            "It was sunny.",
+            "The ferry had left already.",
+            "It was raining.",
+            "The ferry had left already.",
+            "It was sunny.",
+        ]
+    );
+}
+
+#[test]
+fn multi_line_blocks_can_divert_from_their_groups() {
+    let content = "
+
+-> cellar
+
+== cellar
+{ once:
+    -   A draft came from the north wall.
+        -> tunnel
+    -   The cellar was quiet.
+}
+The lamp flickered.
++   [Look around] -> cellar
+
+== tunnel
+You found a hidden tunnel.
++   [Go back] -> cellar
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[1].text, "You found a hidden tunnel.\n");
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[2].text, "The cellar was quiet.\n");
+    assert_eq!(&line_buffer[3].text, "The lamp flickered.\n");
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[4].text, "The lamp flickered.\n");
+}
+
+#[test]
+fn choices_in_multi_line_blocks_yield_errors() {
+    let content = "
+
+{ shuffle:
+    -   * [Take the left road]
+    -   * [Take the right road]
+}
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn multi_line_conditional_blocks_follow_the_branch_of_their_condition() {
+    let content = "
+
+VAR has_lantern = true
+
+The cave opened up before you.
+{ has_lantern:
+    The lantern lit up the walls.
+    *   [Go deeper]
+        You climbed down into the dark.
+    *   [Turn back]
+        You left the cave.
+    -   The lantern flickered.
+- else:
+    It was too dark to go on.
+}
+Water dripped somewhere.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => assert_eq!(choices.len(), 2),
+        other => panic!("expected a choice but got {:?}", other),
+    }
+
+    assert_eq!(&line_buffer[1].text, "The lantern lit up the walls.\n");
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = line_buffer
+        .iter()
+        .skip(2)
+        .map(|line| line.text.trim())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        &text,
+        &[
+            "You climbed down into the dark.",
+            "The lantern flickered.",
+            "Water dripped somewhere.",
+        ]
+    );
+
+    let mut story = read_story_from_string(content).unwrap();
+    story.set_variable("has_lantern", false).unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[1].text, "It was too dark to go on.\n");
+    assert_eq!(&line_buffer[2].text, "Water dripped somewhere.\n");
+}
+
+#[test]
+fn multi_line_conditional_blocks_can_be_nested_in_branches_and_blocks() {
+    let content = "
+
+VAR coins = 0
+
+-> square
+
+== square
++   [Search the fountain]
+    { coins == 0:
+        { stopping:
+            -   You found a coin.
+            -   { coins > 5:
+                    Too many coins.
+                - else:
+                    You found another coin.
+                }
+        }
+    }
+    -> square
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    for _ in 0..2 {
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+    }
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You found a coin.\n");
+    assert_eq!(&line_buffer[1].text, "You found another coin.\n");
+}
+
+#[test]
+fn choices_in_multi_line_conditional_blocks_can_be_made_after_saving() {
+    let content = "
+
+{ true:
+    *   [Open the door] The hinges creaked.
+    *   [Knock] Nobody answered.
+}
+The hallway was still.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let mut story = story.clone();
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Nobody answered.\n");
+    assert_eq!(&line_buffer[1].text, "The hallway was still.\n");
+}
+
+#[test]
+fn multi_line_conditional_blocks_follow_the_first_branch_whose_condition_is_fulfilled() {
+    let content = "
+
+VAR coins = 3
+
+{
+    - coins > 10: You were rich.
+    - coins > 0:
+        You had a few coins.
+        Enough for bread.
+    - else: You were broke.
+}
+{ coins:
+    - 1: One coin.
+    - 3: Three coins.
+    - else: Some coins.
+}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = line_buffer
+        .iter()
+        .map(|line| line.text.trim())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        &text,
+        &["You had a few coins.", "Enough for bread.", "Three coins."]
+    );
+
+    let mut story = read_story_from_string(content).unwrap();
+    story.set_variable("coins", 0).unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You were broke.\n");
+    assert_eq!(&line_buffer[1].text, "Some coins.\n");
+}
+
+#[test]
+fn branches_without_conditions_in_multi_line_conditional_blocks_yield_errors() {
+    let content = "
+
+VAR coins = 3
+
+{
+    - coins > 10: You were rich.
+    - You had a few coins.
+}
+
+";
+
+    match read_story_from_string(content) {
+        Err(error @ error::ReadError::ParseError(..)) => {
+            let message = error::parse::print_read_error(&error).unwrap();
+            assert!(message.starts_with("(line 7) expected a branch"));
+        }
+        other => panic!(
+            "expected a `ReadError::ParseError` but got {:?}",
+            other.err()
+        ),
+    }
+}
@@ -0,0 +1,217 @@
+use inkling::*;
+
+#[test]
+fn lists_are_declared_with_their_initial_items_and_print_as_item_names() {
+    let content = "\
+LIST inventory = sword, (torch), (key)
+
+You carry: {inventory}.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You carry: torch, key.\n");
+
+    let expected: List = vec![
+        ListItem::new("inventory", "torch", 2),
+        ListItem::new("inventory", "key", 3),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        story.get_variable("inventory"),
+        Some(Variable::List(expected))
+    );
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn items_are_added_to_and_removed_from_lists() {
+    let content = "\
+LIST inventory = sword, (torch), key
+LIST moods = (calm), angry
+
+~ inventory += sword
+~ inventory = inventory + key + moods.angry
+~ inventory -= torch
+~ inventory -= torch
+You carry: {inventory}.
+{inventory: Not empty.}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You carry: sword, angry, key.\n");
+    assert_eq!(&line_buffer[1].text, "Not empty.\n");
+}
+
+#[test]
+fn list_items_can_be_given_values_which_order_them() {
+    let content = "\
+LIST volume = (loud) = 5, quiet = 1, (medium)
+
+{volume}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "medium, loud\n");
+
+    match story.get_variable("volume") {
+        Some(Variable::List(list)) => {
+            let values = list.items().map(|item| item.value).collect::<Vec<_>>();
+            assert_eq!(values, &[2, 5]);
+        }
+        other => panic!("expected a list variable but got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_list_declarations_yield_errors() {
+    assert!(read_story_from_string("LIST inventory = sword, (torch").is_err());
+    assert!(read_story_from_string("LIST inventory = sword, sword").is_err());
+    assert!(read_story_from_string("LIST inventory = big sword").is_err());
+    assert!(read_story_from_string("LIST = sword").is_err());
+}
+
+#[test]
+fn lists_cannot_be_assigned_other_types() {
+    let content = "\
+LIST inventory = sword, (torch)
+
+~ inventory = 5
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 1)
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn list_query_functions_can_be_used_in_conditions_and_text() {
+    let content = "\
+LIST inventory = sword, (torch), (key), lamp
+
+{LIST_COUNT(inventory) > 1:You carry many things.|You travel light.}
+Your most valuable item is the {LIST_MAX(inventory)} and your least the {LIST_MIN(inventory)}.
+You could carry {LIST_ALL(inventory)}.
+{LIST_RANGE(LIST_ALL(inventory), 2, 3) == inventory:You carry the middle items.}
+~ inventory -= LIST_ALL(inventory)
+{LIST_COUNT(inventory) > 1:You carry many things.|You travel light.}
+You could still carry {LIST_ALL(inventory)}.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let lines = line_buffer
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        &[
+            "You carry many things.\n",
+            "Your most valuable item is the key and your least the torch.\n",
+            "You could carry sword, torch, key, lamp.\n",
+            "You carry the middle items.\n",
+            "You travel light.\n",
+            "You could still carry sword, torch, key, lamp.\n",
+        ]
+    );
+}
+
+#[test]
+fn list_query_functions_yield_errors_for_other_types() {
+    let content = "\
+VAR gold = 10
+
+{LIST_COUNT(gold) > 1:You are rich.}
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 1);
+            assert!(format!("{}", error.variable_errors[0]).contains("LIST_COUNT"));
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+
+    assert!(read_story_from_string("{LIST_RANGE(inventory)}").is_err());
+}
+
+#[test]
+fn conditions_can_check_whether_lists_contain_items() {
+    let content = "\
+LIST inventory = sword, (torch), (key), lamp
+
+{inventory ? torch:You have a torch.}
+{inventory ? sword:You have a sword.}
+{inventory !? sword:You have no sword.}
+{inventory ? (torch, key):You have a torch and a key.}
+{inventory ? (torch, lamp):You have a torch and a lamp.}
+{not (inventory ? lamp) and inventory !? (sword, lamp):You have neither a sword nor a lamp.}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let lines = line_buffer
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        &[
+            "You have a torch.\n",
+            "You have no sword.\n",
+            "You have a torch and a key.\n",
+            "You have neither a sword nor a lamp.\n",
+        ]
+    );
+}
+
+#[test]
+fn contains_conditions_on_variables_which_are_not_lists_yield_errors() {
+    let content = "\
+VAR gold = 10
+
+{gold ? 5:You have gold.}
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 1)
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
@@ -28,6 +28,7 @@ Let's add a couple more errors.
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn all_address_validation_errors_are_returned() {
     let content = "\
 
@@ -83,6 +84,7 @@ Line one.
 }
 
 #[test]
+#[cfg(feature = "math")]
 fn invalid_expression_and_condition_errors_are_yielded() {
     let content = "\
 
@@ -112,3 +114,147 @@ And in all knots! {2 + true}.
 
     assert_eq!(error_lines.len(), 11);
 }
+
+#[test]
+fn validating_story_returns_diagnostic_for_every_error_without_constructing_story() {
+    let content = "
+
+VAR = 0 // no variable name
+VAR variable = 10 // good variable to assert number of errors
+VAR bad_variable 0 // no assignment operator
+
+-> root
+
+== root
+Let's add a couple more errors.
+
+*+  Choices cannot have both stick and non-sticky markers
+*   Nor can they have[] unmatched braces ]
+
+";
+
+    let diagnostics = validate_story_from_string(content);
+
+    assert_eq!(diagnostics.len(), 4);
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.is_error()));
+
+    let lines = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.meta_data.as_ref().unwrap().line())
+        .collect::<Vec<_>>();
+
+    assert_eq!(&lines, &[3, 5, 12, 13]);
+}
+
+#[test]
+fn validating_valid_story_returns_no_errors() {
+    let content = "
+VAR variable = 10
+
+-> root
+
+== root
+Variable is {variable}.
+";
+
+    assert!(validate_story_from_string(content).is_empty());
+}
+
+#[test]
+fn misspelled_variables_in_expressions_and_conditions_get_suggestions() {
+    let content = "
+VAR num_tickets = 2
+
+{num_tickts > 1: We have enough tickets.}
+We have {num_tikets} tickets.
+";
+
+    let error = read_story_from_string(content).unwrap_err();
+    let error_string = print_read_error(&error).unwrap();
+
+    assert_eq!(
+        error_string
+            .lines()
+            .filter(|line| line.contains("(did you mean 'num_tickets'?)"))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn diagnostics_name_the_knot_and_stitch_they_occur_in() {
+    let content = "\
+-> tripoli
+
+== tripoli
+{unknown_one}
+= cinema
+{unknown_two}
+";
+
+    let diagnostics = validate_story_from_string(content);
+
+    let has_message = |location: &str| {
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains(location))
+    };
+
+    assert_eq!(diagnostics.len(), 2);
+    assert!(has_message("(line 4 in 'tripoli')"));
+    assert!(has_message("(line 6 in 'tripoli.cinema')"));
+}
+
+#[test]
+fn diagnostics_print_file_name_and_line_if_file_name_is_given() {
+    let content = "\
+-> tripoli
+
+== tripoli
+{unknown}
+";
+
+    let options = ReadOptions {
+        file_name: Some("tripoli.ink".to_string()),
+        ..Default::default()
+    };
+
+    let diagnostics = validate_story_from_string_with_options(content, &options);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]
+        .message
+        .contains("(tripoli.ink:4 in 'tripoli')"));
+}
+
+#[test]
+fn reading_story_from_reader_with_invalid_text_yields_io_error() {
+    let content: &[u8] = b"He drifted off.\n\xff\xfe\n";
+
+    match read_story_from_reader(content, &ReadOptions::default()) {
+        Err(error::ReadError::IoError { kind, .. }) => {
+            assert_eq!(kind, std::io::ErrorKind::InvalidData)
+        }
+        other => panic!("expected `ReadError::IoError` but got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn reading_story_from_reader_reports_errors_on_the_same_lines_as_strings() {
+    let content = "\
+-> tripoli
+
+== tripoli
+{unknown}
+";
+
+    let options = ReadOptions::default();
+
+    let from_string = read_story_from_string_with_options(content, &options).unwrap_err();
+    let from_reader = read_story_from_reader(content.as_bytes(), &options).unwrap_err();
+
+    assert_eq!(
+        print_read_error(&from_reader).unwrap(),
+        print_read_error(&from_string).unwrap()
+    );
+}
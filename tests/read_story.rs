@@ -15,7 +15,7 @@ He befriended thousands of climbers and children sightseeing in Switzerland.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 2);
         }
         _ => panic!("error while reading a flat story from string"),
@@ -42,7 +42,7 @@ He just woke from a nightmare.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 5);
             assert_eq!(&line_buffer[4].text, "He just woke from a nightmare.\n");
         }
@@ -65,7 +65,7 @@ He befriended thousands of climbers and children sightseeing in Switzerland.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(
                 &line_buffer[0].text,
                 "Mont Blanc was a world-renowned mountain guide.\n"
@@ -75,6 +75,121 @@ He befriended thousands of climbers and children sightseeing in Switzerland.
     }
 }
 
+#[test]
+fn block_comments_are_removed_and_errors_after_them_have_their_line_numbers() {
+    let content = "
+/* The opening of the story,
+   which is still being written. */
+It was a dark and stormy /* and cold */ night.
+-> DONE
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "It was a dark and stormy night.\n");
+
+    let mut story = read_story_from_reader(content.as_bytes(), &ReadOptions::default()).unwrap();
+    line_buffer.clear();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "It was a dark and stormy night.\n");
+
+    let error = read_story_from_string("/*\n*/\n-> nowhere").unwrap_err();
+
+    assert!(format!("{:?}", error).contains("line_index: 2"));
+}
+
+#[test]
+fn block_comments_which_are_never_closed_yield_an_error_with_the_line_they_begin_on() {
+    let content = "\
+The train pulled in.
+/* Left open
+-> platform
+
+=== platform ===
+It was empty.
+";
+
+    for result in [
+        read_story_from_string(content),
+        read_story_from_reader(content.as_bytes(), &ReadOptions::default()),
+    ] {
+        match result {
+            Err(error @ error::ReadError::UnterminatedComment { .. }) => {
+                assert_eq!(
+                    format!("{}", error),
+                    "Could not parse story: the block comment which begins at line 2 \
+                     is never closed"
+                );
+            }
+            other => panic!("expected an unterminated comment error but got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn block_comments_are_kept_if_comments_are_preserved() {
+    let content = "\
+/* The opening of the story,
+   which is still being written. */
+It was a dark and stormy /* and cold */ night.
+";
+
+    let options = ReadOptions {
+        preserve_comments: true,
+        ..Default::default()
+    };
+
+    let story = read_story_from_string_with_options(content, &options).unwrap();
+    let comments = story.get_comments();
+
+    assert_eq!(comments.len(), 2);
+    assert!(comments
+        .iter()
+        .all(|comment| comment.kind == CommentKind::Block));
+    assert_eq!(
+        &comments[0].text,
+        "/* The opening of the story,\n   which is still being written. */"
+    );
+    assert_eq!(&comments[1].text, "/* and cold */");
+    assert_eq!(comments[1].element.as_ref().unwrap().line(), 3);
+
+    let story = read_story_from_reader(content.as_bytes(), &options).unwrap();
+
+    assert_eq!(story.get_comments(), comments);
+}
+
+#[test]
+fn prompts_report_whether_the_story_reached_end_done_or_ran_out_of_content() {
+    let content = "\
+*   [Leave] -> END
+*   [Rest] -> DONE
+*   [Wait] You wait.
+";
+
+    for (choice, ending) in [EndingKind::End, EndingKind::Done, EndingKind::DeadEnd]
+        .into_iter()
+        .enumerate()
+    {
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(choice).unwrap();
+
+        assert_eq!(
+            story.resume(&mut line_buffer).unwrap(),
+            Prompt::Done(ending)
+        );
+        assert_eq!(story.get_ending(), Some(ending));
+    }
+}
+
 #[test]
 fn story_can_start_with_named_knot() {
     let content = "
@@ -93,7 +208,7 @@ He just woke from a nightmare.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 3);
             assert_eq!(&line_buffer[2].text, "He just woke from a nightmare.\n");
         }
@@ -131,7 +246,7 @@ He identifies himself as being from Europol and passes the barrier.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 7);
             assert_eq!(
                 &line_buffer[6].text,
@@ -168,7 +283,7 @@ He just woke from a nightmare.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 4);
             assert_eq!(&line_buffer[3].text, "He just woke from a nightmare.\n");
         }
@@ -176,6 +291,57 @@ He just woke from a nightmare.
     }
 }
 
+#[test]
+#[cfg(feature = "math")]
+fn diverts_set_their_arguments_as_the_parameters_of_knots() {
+    let content = "
+VAR visitors = 2
+
+-> meet(\"Barry\", visitors + 1)
+
+== meet(who, count) ==
+{who} meets {count} people at the tavern.
+{count > 1: -> meet(\"Jean\", count - 2)}
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(
+        &line_buffer[0].text,
+        "Barry meets 3 people at the tavern.\n"
+    );
+    assert_eq!(&line_buffer[1].text, "Jean meets 1 people at the tavern.\n");
+}
+
+#[test]
+fn divert_targets_can_be_given_as_arguments_and_diverted_to() {
+    let content = "
+-> greet(\"Barry\", -> farewell)
+
+== greet(who, next) ==
+Hello, {who}!
+-> next
+
+== farewell
+Goodbye!
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "Hello, Barry!\n");
+    assert_eq!(&line_buffer[1].text, "Goodbye!\n");
+}
+
 #[test]
 fn stitches_can_be_diverted_to_inside_a_knot_without_the_full_address() {
     let content = "
@@ -208,7 +374,7 @@ He just woke from a nightmare.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 6);
             assert_eq!(
                 &line_buffer[5].text,
@@ -587,3 +753,331 @@ An old castle heaves in front of you. -> gates # description
     assert_eq!(&choices[0].text, "Enter it.");
     assert_eq!(&choices[0].tags, &["action".to_string()]);
 }
+
+#[test]
+fn choice_tags_inside_brackets_are_only_set_to_the_choice() {
+    let content = "
+*   Draw [the sword # icon: sword] and charge. # sound: battle
+*   Flee # icon: boots
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&choices[0].tags, &["icon: sword".to_string()]);
+    assert_eq!(&choices[1].tags, &["icon: boots".to_string()]);
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Draw and charge.\n");
+    assert_eq!(&line_buffer[0].tags, &["sound: battle".to_string()]);
+}
+
+#[test]
+fn tags_written_on_their_own_line_are_set_to_the_next_line_of_text() {
+    let content = "
+Hello there. # mood: happy
+# mood: sad
+# portrait: frown
+I hate goodbyes.
+Goodbye.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].tags, &["mood: happy".to_string()]);
+    assert_eq!(
+        &line_buffer[1].tags,
+        &["mood: sad".to_string(), "portrait: frown".to_string()]
+    );
+    assert!(line_buffer[2].tags.is_empty());
+}
+
+#[test]
+fn tags_with_expressions_are_evaluated_when_their_line_is_produced() {
+    let content = "
+VAR speaker = \"guard\"
+VAR angry = false
+Halt! # portrait_{speaker} # {angry: mood: angry}
+~ speaker = \"captain\"
+~ angry = true
+Who goes there? # portrait_{speaker} # {angry: mood: angry}
+*   Run # sound_{speaker}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].tags, &["portrait_guard".to_string()]);
+    assert_eq!(
+        &line_buffer[1].tags,
+        &["portrait_captain".to_string(), "mood: angry".to_string()]
+    );
+    assert_eq!(&choices[0].tags, &["sound_captain".to_string()]);
+}
+
+#[test]
+fn lines_with_registered_directive_prefixes_do_not_produce_text() {
+    let content = "
+
+The curtain rose.
+@cue lights down
+*   Applaud
+    @cue lights up
+    The lights came back on.
+
+";
+
+    let mut options = ReadOptions::default();
+    options.directives.insert("@cue".to_string(), |content| {
+        Ok(vec![Variable::String(content.to_string())])
+    });
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        &text,
+        "The curtain rose.\nApplaud\nThe lights came back on.\n"
+    );
+}
+
+#[test]
+fn directives_are_delivered_as_items_between_lines_of_text() {
+    let content = "
+
+The curtain rose.
+@cue lights down
+*   Applaud
+    @cue lights up
+    The lights came back on.
+
+";
+
+    let mut options = ReadOptions::default();
+    options.directives.insert("@cue".to_string(), |content| {
+        Ok(vec![Variable::String(content.to_string())])
+    });
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut buffer = Vec::new();
+
+    story.resume_with_directives(&mut buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume_with_directives(&mut buffer).unwrap();
+
+    let directives = buffer
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| match item {
+            StoryItem::Directive(directive) => Some((i, directive.arguments.clone())),
+            StoryItem::Line(..) => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(buffer.len(), 5);
+    assert_eq!(
+        directives,
+        vec![
+            (1, vec![Variable::String("lights down".to_string())]),
+            (3, vec![Variable::String("lights up".to_string())])
+        ]
+    );
+}
+
+#[test]
+fn host_constants_are_used_in_conditions_without_shifting_line_numbers() {
+    let content = "\
+{PLATFORM == \"mobile\": Tap to continue.|Press any key.}
+{unknown}
+";
+
+    let mut options = ReadOptions::default();
+    options.constants.insert(
+        "PLATFORM".to_string(),
+        Variable::String("mobile".to_string()),
+    );
+
+    let diagnostics = validate_story_from_string_with_options(content, &options);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].meta_data.as_ref().unwrap().line(), 2);
+
+    let content = "\
+{PLATFORM == \"mobile\": Tap to continue.|Press any key.}
+";
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Tap to continue.\n");
+    assert!(story.set_variable("PLATFORM", "desktop").is_err());
+}
+
+#[test]
+fn flagged_regions_are_read_only_when_their_flags_are_set() {
+    let content = "\
+The ferry arrived at the island.
+# ifdef DEMO
+Thank you for playing the demo!
+# else
+-> island
+
+=== island ===
+# ifndef DEMO
+The harbour was quiet.
+# endif
+# endif
+";
+
+    let mut options = ReadOptions::default();
+    options.flags.insert("DEMO".to_string());
+
+    for story in [
+        read_story_from_string_with_options(content, &options).unwrap(),
+        read_story_from_reader(content.as_bytes(), &options).unwrap(),
+    ] {
+        let mut story = story;
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            &copy_lines_into_string(&line_buffer),
+            "The ferry arrived at the island.\nThank you for playing the demo!\n"
+        );
+        assert!(story.parse_location("island").is_err());
+    }
+
+    let options = ReadOptions::default();
+
+    let mut story = read_story_from_reader(content.as_bytes(), &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &copy_lines_into_string(&line_buffer),
+        "The ferry arrived at the island.\nThe harbour was quiet.\n"
+    );
+}
+
+#[test]
+fn unbalanced_flagged_regions_yield_errors_even_when_excluded() {
+    let content = "\
+Line.
+# ifdef FULL_GAME
+Full game.
+# else
+# else
+Demo.
+";
+
+    for result in [
+        read_story_from_string(content),
+        read_story_from_reader(content.as_bytes(), &ReadOptions::default()),
+    ] {
+        match result {
+            Err(error::ReadError::FlagError(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert_eq!(
+                    errors[0].kind,
+                    error::parse::flag::FlagErrorKind::DuplicateElse
+                );
+                assert_eq!(errors[0].meta_data.line(), 5);
+                assert_eq!(errors[1].meta_data.line(), 2);
+            }
+            other => panic!("expected flag errors but got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn variables_can_be_declared_after_the_prelude_and_inside_knots() {
+    let content = "\
+VAR coins = 3
+The market was busy.
+VAR price = 1.5
+VARIOUS stalls lined the street.
+-> stall
+
+=== stall ===
+VAR open = true
+CONST KEEPER = \"Hilda\"
+VAR next = -> stall
+{KEEPER} counted {coins} coins.
+";
+
+    for story in [
+        read_story_from_string(content).unwrap(),
+        read_story_from_reader(content.as_bytes(), &ReadOptions::default()).unwrap(),
+    ] {
+        let mut story = story;
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            &copy_lines_into_string(&line_buffer),
+            "The market was busy.\nVARIOUS stalls lined the street.\nHilda counted 3 coins.\n"
+        );
+
+        assert_eq!(story.get_variable("price").unwrap(), Variable::Float(1.5));
+        assert_eq!(story.get_variable("open").unwrap(), Variable::Bool(true));
+        assert!(matches!(
+            story.get_variable("next").unwrap(),
+            Variable::Divert(..)
+        ));
+        assert!(story.set_variable("KEEPER", "Olga").is_err());
+    }
+}
+
+#[test]
+fn variables_declared_twice_anywhere_in_the_story_yield_errors() {
+    let content = "\
+VAR coins = 3
+-> stall
+
+=== stall ===
+VAR coins = 5
+Line.
+";
+
+    for result in [
+        read_story_from_string(content),
+        read_story_from_reader(content.as_bytes(), &ReadOptions::default()),
+    ] {
+        match result {
+            Err(error::ReadError::ParseError(error)) => {
+                assert_eq!(error.prelude_errors.len(), 1);
+                assert_eq!(error.prelude_errors[0].meta_data.line(), 5);
+            }
+            other => panic!("expected a parse error but got {:?}", other),
+        }
+    }
+}
@@ -71,4 +71,389 @@ You head back.
         assert_eq!(choices_without_torch.len(), 1);
         assert_eq!(choices_with_torch.len(), 2);
     }
+
+    #[test]
+    fn serialization_skips_knots_which_have_not_been_visited() {
+        let content = "
+
+-> visited
+
+== visited ==
+You have been here.
+-> END
+
+== never_visited_knot ==
+You have not.
+-> END
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let state = serde_json::to_value(&story).unwrap();
+
+        let visit_counts = state["content"]["data"]["knot_visit_counts"]
+            .as_object()
+            .unwrap();
+        assert!(visit_counts.contains_key("visited"));
+        assert!(!visit_counts.contains_key("never_visited_knot"));
+
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(loaded.get_num_visited(&"visited".into()), Some(1));
+        assert_eq!(
+            loaded.get_num_visited(&"never_visited_knot".into()),
+            Some(0)
+        );
+
+        loaded.move_to(&"never_visited_knot".into()).unwrap();
+        loaded.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            loaded.get_num_visited(&"never_visited_knot".into()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn serialization_saves_values_of_only_the_variables_which_have_changed() {
+        let content = "\
+VAR coins = 0
+VAR name = \"Ada\"
+VAR lamp_lit = false
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+
+        story.set_variable("coins", 5).unwrap();
+        story.set_variable("lamp_lit", true).unwrap();
+        story.set_variable("lamp_lit", false).unwrap();
+
+        let state = serde_json::to_value(&story).unwrap();
+
+        let changed = state["content"]["data"]["variables"].as_object().unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed["coins"], serde_json::json!({ "Int": 5 }));
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(loaded.get_variable("coins"), Some(Variable::Int(5)));
+        assert_eq!(
+            loaded.get_variable("name"),
+            Some(Variable::String("Ada".to_string()))
+        );
+        assert_eq!(loaded.get_variable("lamp_lit"), Some(Variable::Bool(false)));
+
+        loaded.set_variable("coins", 0).unwrap();
+        let state = serde_json::to_value(&loaded).unwrap();
+
+        assert!(state["content"]["data"]["variables"]
+            .as_object()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn unchanged_variables_are_absent_from_the_saved_state() {
+        let content = "\
+VAR coins = 0
+VAR name = \"Ada\"
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        story.set_variable("coins", 5).unwrap();
+
+        let state = serde_json::to_value(&story).unwrap();
+        let saved_state = serde_json::to_string(&state["content"]["data"]).unwrap();
+
+        assert!(saved_state.contains("coins"));
+        assert!(!saved_state.contains("name"));
+        assert!(!saved_state.contains("Ada"));
+    }
+
+    #[test]
+    fn transient_variables_are_reset_when_loading_a_saved_state() {
+        let content = "\
+VAR debug_mode = false
+VAR coins = 0
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+
+        story.mark_variable_as_transient("debug_mode").unwrap();
+        story.set_variable("debug_mode", true).unwrap();
+        story.set_variable("coins", 5).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            loaded.get_variable("debug_mode"),
+            Some(Variable::Bool(false))
+        );
+        assert_eq!(loaded.get_variable("coins"), Some(Variable::Int(5)));
+
+        loaded.set_variable("debug_mode", true).unwrap();
+        let loaded_again: Story =
+            serde_json::from_str(&serde_json::to_string(&loaded).unwrap()).unwrap();
+
+        assert_eq!(
+            loaded_again.get_variable("debug_mode"),
+            Some(Variable::Bool(false))
+        );
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn shuffled_variants_continue_in_the_same_order_after_loading_a_saved_state() {
+        let content = "
+
+-> deal
+
+== deal
+{~Ace|King|Queen|Jack|Ten}
++   [Draw again] -> deal
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        story.set_seed(1859);
+
+        let mut line_buffer = Vec::new();
+
+        for _ in 0..3 {
+            story.resume(&mut line_buffer).unwrap();
+            story.make_choice(0).unwrap();
+        }
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        let mut original_lines = Vec::new();
+        let mut loaded_lines = Vec::new();
+
+        for _ in 0..12 {
+            story.resume(&mut original_lines).unwrap();
+            story.make_choice(0).unwrap();
+
+            loaded.resume(&mut loaded_lines).unwrap();
+            loaded.make_choice(0).unwrap();
+        }
+
+        assert_eq!(original_lines, loaded_lines);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn random_numbers_continue_in_the_same_order_after_loading_a_saved_state() {
+        let content = "
+
+The dealer seeds the deck.{SEED_RANDOM(1859)}
+-> roll
+
+== roll
+You roll {RANDOM(1, 1000)}.
++   [Roll again] -> roll
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        for _ in 0..3 {
+            story.resume(&mut line_buffer).unwrap();
+            story.make_choice(0).unwrap();
+        }
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        let mut original_lines = Vec::new();
+        let mut loaded_lines = Vec::new();
+
+        for _ in 0..12 {
+            story.resume(&mut original_lines).unwrap();
+            story.make_choice(0).unwrap();
+
+            loaded.resume(&mut loaded_lines).unwrap();
+            loaded.make_choice(0).unwrap();
+        }
+
+        assert_eq!(original_lines, loaded_lines);
+    }
+
+    #[test]
+    fn serialization_writes_repeated_strings_once() {
+        let content = "\
+-> gallery
+
+== gallery ==
+= north_wing
+A portrait. -> gallery.south_wing
+
+= south_wing
+A landscape. -> END
+";
+
+        let story = read_story_from_string(content).unwrap();
+        let state = serde_json::to_value(&story).unwrap();
+
+        let strings = state["strings"].as_array().unwrap();
+        let num_gallery = strings
+            .iter()
+            .filter(|string| string.as_str() == Some("gallery"))
+            .count();
+
+        assert_eq!(num_gallery, 1);
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        let mut line_buffer = Vec::new();
+        loaded.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[0].text, "A portrait. ");
+        assert_eq!(&line_buffer[1].text, "A landscape.\n");
+    }
+
+    #[test]
+    #[cfg(feature = "compressed_saves")]
+    fn compressed_saves_are_smaller_than_json_and_detect_corruption() {
+        let content = "
+-> corridor
+
+== corridor ==
+The corridor stretched on.
++   [Keep walking] -> corridor
++   [Turn back] -> END
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        for _ in 0..20 {
+            story.resume(&mut line_buffer).unwrap();
+            story.make_choice(0).unwrap();
+        }
+
+        let json = serde_json::to_vec(&story).unwrap();
+        let mut bytes = save_story_to_bytes(&story).unwrap();
+
+        assert!(bytes.len() < json.len());
+
+        let loaded = load_story_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            loaded.get_num_visited(&Location::from("corridor")),
+            Some(20)
+        );
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        assert!(load_story_from_bytes(&bytes).is_err());
+
+        match load_story_from_bytes(b"not a save") {
+            Err(error::SaveError::InvalidHeader) => (),
+            other => panic!(
+                "expected an `InvalidHeader` error but got {:?}",
+                other.err()
+            ),
+        }
+    }
+
+    #[test]
+    fn list_variables_are_serialized_with_their_items() {
+        let content = "\
+LIST inventory = sword, (torch), key
+
+~ inventory += key
+You carry: {inventory}.
+*   [Drop the torch] -> drop
+
+=== drop ===
+~ inventory -= torch
+You carry: {inventory}.
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            loaded.get_variable("inventory"),
+            story.get_variable("inventory")
+        );
+
+        line_buffer.clear();
+
+        loaded.make_choice(0).unwrap();
+        loaded.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer.last().unwrap().text, "You carry: key.\n");
+    }
+
+    #[test]
+    fn loaded_stories_can_be_inspected_without_resuming_them() {
+        let content = "\
+The guard blocked the gate.
+*   [Bribe him] He let you pass.
+*   [Turn back] -> END
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert!(!loaded.can_continue());
+        assert_eq!(loaded.current_choices().unwrap().len(), 2);
+
+        loaded.make_choice(1).unwrap();
+        loaded.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&loaded).unwrap();
+        let loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert!(!loaded.can_continue());
+        assert!(loaded.current_choices().is_none());
+    }
+
+    #[test]
+    fn lines_which_are_left_from_stepping_are_kept_in_loaded_stories() {
+        let content = "\
+The rain fell.
+The streets emptied.
+*   [Wait] -> END
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        assert_eq!(
+            story.resume_step(&mut line_buffer).unwrap(),
+            Prompt::Continue
+        );
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut loaded: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert!(loaded.can_continue());
+
+        let prompt = loaded.resume_step(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[1].text, "The streets emptied.\n");
+        assert_eq!(prompt.get_choices().unwrap().len(), 1);
+    }
 }
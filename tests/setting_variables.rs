@@ -128,3 +128,695 @@ The latest measurement is {value} {unit}. {not is_hazardous: Not terrible, not g
         "The latest measurement is 15000 Röntgen. Oh no.\n"
     );
 }
+
+#[test]
+fn variable_defaults_replace_declared_values_before_the_story_starts() {
+    let content = "\
+VAR difficulty = \"normal\"
+VAR lives = 3
+
+{difficulty == \"hard\": The storm has already begun.}
+You have {lives} lives.
+";
+
+    let mut options = ReadOptions::default();
+    options.variable_defaults.insert(
+        "difficulty".to_string(),
+        Variable::String("hard".to_string()),
+    );
+    options
+        .variable_defaults
+        .insert("lives".to_string(), Variable::Int(1));
+
+    let mut story = read_story_from_string_with_options(content, &options).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The storm has already begun.\n");
+    assert_eq!(&line_buffer[1].text, "You have 1 lives.\n");
+}
+
+#[test]
+fn invalid_variable_defaults_yield_errors_when_reading_the_story() {
+    let content = "\
+VAR lives = 3
+CONST MAX_LIVES = 5
+You have {lives} of {MAX_LIVES} lives.
+";
+
+    let read_with_default = |name: &str, value: Variable| {
+        let mut options = ReadOptions::default();
+        options.variable_defaults.insert(name.to_string(), value);
+
+        read_story_from_string_with_options(content, &options)
+    };
+
+    let is_default_error =
+        |result: Result<Story, error::ReadError>, expected: &'static str| match result {
+            Err(error::ReadError::InvalidVariableDefault { name, .. }) => {
+                assert_eq!(name, expected)
+            }
+            other => panic!(
+                "expected `ReadError::InvalidVariableDefault` but got {:?}",
+                other.err()
+            ),
+        };
+
+    is_default_error(read_with_default("lives", Variable::Bool(true)), "lives");
+    is_default_error(
+        read_with_default("MAX_LIVES", Variable::Int(9)),
+        "MAX_LIVES",
+    );
+    is_default_error(
+        read_with_default("continues", Variable::Int(1)),
+        "continues",
+    );
+}
+
+#[test]
+fn undeclared_variables_set_by_the_host_are_created_only_if_the_policy_allows_it() {
+    let content = "
+
+VAR torches = 2
+
+{torches} torches lit the hall.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    match story.set_variable("torchs", 3) {
+        Err(InklingError::InvalidVariable { name }) => assert_eq!(&name, "torchs"),
+        other => panic!("expected an `InvalidVariable` error but got {:?}", other),
+    }
+
+    story.set_undeclared_variable_policy(UndeclaredVariablePolicy::Create);
+    story.set_variable("torchs", 3).unwrap();
+
+    assert_eq!(story.get_variable("torchs"), Some(Variable::Int(3)));
+    assert_eq!(story.get_variable("torches"), Some(Variable::Int(2)));
+    assert!(story.log.warnings.is_empty());
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "2 torches lit the hall.\n");
+}
+
+#[test]
+fn variable_history_records_successful_host_changes_to_watched_variables() {
+    let content = "\
+VAR trust = 0
+VAR suspicion = 0
+CONST max_trust = 10
+Trust: {trust}, suspicion: {suspicion}, at most {max_trust}.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    story.set_undeclared_variable_policy(UndeclaredVariablePolicy::Create);
+    story.enable_variable_history(&["trust", "max_trust", "mood"]);
+
+    story.set_variable("trust", 2).unwrap();
+    story.set_variable("suspicion", 5).unwrap();
+    assert!(story.set_variable("trust", "high").is_err());
+    assert!(story.set_variable("max_trust", 5).is_err());
+    story.set_variable("mood", "wary").unwrap();
+    story.set_variable("trust", -3).unwrap();
+
+    let history = story.get_variable_history().unwrap();
+
+    let trust = history
+        .get_changes("trust")
+        .map(|change| (change.old.clone(), change.new.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        trust,
+        &[
+            (Some(Variable::Int(0)), Variable::Int(2)),
+            (Some(Variable::Int(2)), Variable::Int(-3)),
+        ]
+    );
+
+    let mood = history.get_last_change("mood").unwrap();
+
+    assert_eq!(mood.old, None);
+    assert_eq!(mood.new, Variable::String("wary".to_string()));
+    assert_eq!(history.changes().len(), 3);
+}
+
+#[test]
+fn constants_are_substituted_and_assigning_to_them_yields_errors_at_their_declaration() {
+    let content = "\
+VAR lives = 3
+-> storm
+
+=== storm ===
+CONST MAX_LIVES = 5
+{lives < MAX_LIVES: You have {lives} of {MAX_LIVES} lives.}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You have 3 of 5 lives.\n");
+
+    match story.set_variable("MAX_LIVES", 9) {
+        Err(InklingError::AssignedToConst { name, meta_data }) => {
+            assert_eq!(&name, "MAX_LIVES");
+            assert_eq!(meta_data.unwrap().line(), 5);
+        }
+        other => panic!("expected an `AssignedToConst` error but got {:?}", other),
+    }
+
+    assert_eq!(story.get_variable("MAX_LIVES"), Some(Variable::Int(5)));
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn temporary_variables_can_be_used_in_the_knot_that_declares_them() {
+    let content = "\
+VAR strength = 3
+-> arena
+
+=== arena ===
+~ temp damage = strength * 2
+You deal {damage} damage.{damage > 5: It is a mighty blow.}
+*   [Strike again]
+    ~ temp total = damage + damage
+    You have dealt {total} damage in total.
+    -> aftermath
+
+=== aftermath ===
+~ temp damage = \"no\"
+The crowd cheers. You take {damage} damage.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "You deal 6 damage. It is a mighty blow.\n"
+    );
+
+    story.make_choice(0).unwrap();
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You have dealt 12 damage in total.\n");
+    assert_eq!(
+        &line_buffer[1].text,
+        "The crowd cheers. You take no damage.\n"
+    );
+}
+
+#[test]
+fn temporary_variables_cannot_be_used_outside_of_the_knot_that_declares_them() {
+    let content = "\
+-> arena
+
+=== arena ===
+~ temp damage = 6
+-> aftermath
+
+=== aftermath ===
+You took {damage} damage.
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.invalid_address_errors.len(), 1)
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+
+    assert!(read_story_from_string("~ damage = 6").is_err());
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn logic_lines_assign_to_variables_when_they_are_reached() {
+    let content = "\
+VAR visits = 0
+VAR name = \"Mara\"
+VAR nickname = \"none\"
+
+-> market
+
+=== market ===
+~ visits = visits + 1
+~ name = \"Anna\"
+~ nickname = name
+~ temp price = 3
+~ price = price * visits + 2
+{name} ({nickname}) has been to the market {visits} times and pays {price} coins.
++   [Return] -> market
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Anna (Anna) has been to the market 1 times and pays 5 coins.\n"
+    );
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer.last().unwrap().text,
+        "Anna (Anna) has been to the market 2 times and pays 8 coins.\n"
+    );
+    assert_eq!(story.get_variable("visits"), Some(Variable::Int(2)));
+}
+
+#[test]
+fn invalid_assignments_yield_errors_when_the_story_is_read() {
+    let content = "\
+CONST max_health = 10
+VAR health = 10
+
+~ max_health = 20
+~ health = \"full\"
+~ helth = 5
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 2);
+            assert_eq!(error.invalid_address_errors.len(), 1);
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn assignments_from_the_story_are_recorded_in_the_variable_history() {
+    let content = "\
+VAR trust = 0
+
+-> interrogation
+
+=== interrogation ===
+~ trust = trust - 3
+The detective did not believe you.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    story.enable_variable_history(&["trust"]);
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    let history = story.get_variable_history().unwrap();
+    let change = history.get_last_change("trust").unwrap();
+
+    assert_eq!(change.old, Some(Variable::Int(0)));
+    assert_eq!(change.new, Variable::Int(-3));
+    assert_eq!(change.location, Location::from("interrogation"));
+
+    assert!(matches!(change.origin, ChangeOrigin::Story { .. }));
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn compound_assignments_add_to_and_subtract_from_variables() {
+    let content = "\
+VAR gold = 10
+VAR cost = 3
+VAR title = \"Sir\"
+
+~ gold += 5
+~ gold -= cost + 1
+~ title += \" Anna\"
+~ temp rounds = 1
+~ rounds += gold
+{title} has {gold} gold after {rounds} rounds.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Sir Anna has 11 gold after 12 rounds.\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn compound_assignments_on_incompatible_types_yield_assignment_errors() {
+    let content = "\
+VAR gold = 10
+VAR is_rich = false
+
+~ is_rich += 5
+~ gold -= \"coins\"
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 2);
+
+            for err in error.variable_errors.iter() {
+                assert!(format!("{}", err).contains("Invalid assignment"));
+            }
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn remainders_can_be_taken_with_mod_or_percent_in_text_and_conditions() {
+    let content = "\
+VAR count = 7
+{count mod 2 == 1: The count is odd.}
+{count % 4} is left after dividing {count} by 4.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The count is odd.\n");
+    assert_eq!(&line_buffer[1].text, "3 is left after dividing 7 by 4.\n");
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn remainders_of_divisions_by_zero_yield_errors_when_they_are_evaluated() {
+    let content = "\
+VAR divisor = 2
+~ divisor = divisor - 2
+{10 mod divisor} is left.
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer) {
+        Err(InklingError::VariableError(error)) => {
+            assert!(matches!(
+                error.kind,
+                error::variable::VariableErrorKind::DividedByZero { operator: '%', .. }
+            ));
+            assert!(error.to_string().contains("remainder of a division by 0"));
+        }
+        other => panic!("expected `InklingError::VariableError` but got {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn choice_conditions_which_divide_by_zero_yield_errors_when_they_are_evaluated() {
+    let content = "\
+VAR divisor = 2
+~ divisor = 0
+*   {3 mod divisor == 1} Open the door
+*   Leave -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer) {
+        Err(InklingError::VariableError(error)) => {
+            assert!(matches!(
+                error.kind,
+                error::variable::VariableErrorKind::DividedByZero { operator: '%', .. }
+            ));
+        }
+        other => panic!("expected `InklingError::VariableError` but got {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn conditions_can_be_negated_and_compare_negated_values() {
+    let content = "\
+VAR has_key = false
+VAR debt = 5
+{!has_key: You have no key.}
+{not(has_key or debt > 10): You are not in trouble yet.}
+{-debt < -3: You owe more than 3 coins.}
+{debt * -2 == -10: Twice the debt is 10 coins.}
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 4);
+    assert_eq!(&line_buffer[0].text, "You have no key.\n");
+    assert_eq!(&line_buffer[1].text, "You are not in trouble yet.\n");
+    assert_eq!(&line_buffer[2].text, "You owe more than 3 coins.\n");
+    assert_eq!(&line_buffer[3].text, "Twice the debt is 10 coins.\n");
+}
+
+#[test]
+fn strings_can_be_compared_in_conditions() {
+    let content = "\
+VAR name = \"Anna\"
+VAR title = \"Captain\"
+{name == \"Anna\": You greet Anna.}
+{\"Bert\" != name: You are not Bert.}
+{name < \"Bert\": Anna comes before Bert.}
+{title >= \"Captain\": The title is at least Captain.}
+{name > title: This is not printed.}
+-> greet(name)
+
+=== greet(person) ===
+{person == \"Anna\": Welcome back, {person}.}
+-> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 5);
+    assert_eq!(&line_buffer[0].text, "You greet Anna.\n");
+    assert_eq!(&line_buffer[1].text, "You are not Bert.\n");
+    assert_eq!(&line_buffer[2].text, "Anna comes before Bert.\n");
+    assert_eq!(&line_buffer[3].text, "The title is at least Captain.\n");
+    assert_eq!(&line_buffer[4].text, "Welcome back, Anna.\n");
+}
+
+#[test]
+fn comparisons_between_strings_and_numbers_yield_errors_with_their_line() {
+    let content = "\
+VAR name = \"Anna\"
+
+{name == 5: Hello!}
+{name >= 2.5: Hello!}
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.variable_errors.len(), 2);
+            assert_eq!(error.variable_errors[0].meta_data.line(), 3);
+            assert_eq!(error.variable_errors[1].meta_data.line(), 4);
+
+            let message = error.variable_errors[1].to_string();
+            assert!(message.contains("'String' to 'Float'"), "{}", message);
+            assert!(message.contains("'<' operator"), "{}", message);
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+#[cfg(feature = "math")]
+fn observers_are_called_with_changes_from_the_story_and_the_host() {
+    let content = "\
+VAR gold = 0
+VAR health = 10
+-> market
+
+=== market ===
+~ gold = 5
+~ gold = 5
+~ health = 8
+*   [Buy a sword] -> buy
+
+=== buy ===
+~ gold = gold - 3
+-> END
+";
+
+    let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let observed = changes.clone();
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .observe_variable("gold", move |change| {
+            observed.lock().unwrap().push(change.clone());
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    story.set_variable("gold", 10).unwrap();
+    story.set_variable("gold", 10).unwrap();
+
+    let changes = changes.lock().unwrap();
+
+    let values = changes
+        .iter()
+        .map(|change| (change.old.clone(), change.new.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        values,
+        vec![
+            (Some(Variable::Int(0)), Variable::Int(5)),
+            (Some(Variable::Int(5)), Variable::Int(2)),
+            (Some(Variable::Int(2)), Variable::Int(10)),
+        ]
+    );
+
+    assert_eq!(changes[1].location, Location::from("buy"));
+    assert_eq!(changes[1].turn, 1);
+    assert_eq!(changes[2].origin, ChangeOrigin::Host);
+}
+
+#[test]
+fn only_declared_variables_can_be_observed() {
+    let mut story = read_story_from_string("VAR gold = 0").unwrap();
+
+    match story.observe_variable("glod", |_| ()) {
+        Err(InklingError::InvalidVariable { name }) => assert_eq!(&name, "glod"),
+        other => panic!(
+            "expected `InklingError::InvalidVariable` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn variables_can_be_read_as_values_of_their_type() {
+    let content = "\
+VAR coins = 12
+VAR weight = 2.5
+VAR name = \"Anna\"
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert_eq!(story.get_variable_as::<i32>("coins").unwrap(), 12);
+    assert_eq!(story.get_variable_as::<f32>("coins").unwrap(), 12.0);
+    assert_eq!(story.get_variable_as::<f32>("weight").unwrap(), 2.5);
+    assert_eq!(&story.get_variable_as::<String>("name").unwrap(), "Anna");
+
+    story.set_variable("coins", 3).unwrap();
+    assert_eq!(story.get_variable_as::<i32>("coins").unwrap(), 3);
+
+    match story.get_variable_as::<i32>("weight") {
+        Err(InklingError::VariableError(error)) => {
+            assert!(error.to_string().contains("'Float' into 'i32'"))
+        }
+        other => panic!("expected `InklingError::VariableError` but got {:?}", other),
+    }
+
+    assert!(matches!(
+        story.get_variable_as::<i32>("coin"),
+        Err(InklingError::InvalidVariable { .. })
+    ));
+}
+
+#[test]
+fn snapshots_of_all_variables_can_be_set_back_into_the_story() {
+    let content = "\
+VAR coins = 3
+VAR name = \"Anna\"
+CONST max_coins = 99
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let snapshot = story
+        .variables()
+        .map(|(name, variable)| (name.to_string(), variable))
+        .collect::<Vec<_>>();
+
+    assert_eq!(snapshot.len(), 3);
+
+    story.set_variable("coins", 10).unwrap();
+    story.set_variable("name", "Bert").unwrap();
+
+    story.set_variables(snapshot).unwrap();
+
+    assert_eq!(story.get_variable("coins"), Some(Variable::Int(3)));
+    assert_eq!(story.get_variable("name"), Some(Variable::from("Anna")));
+}
+
+#[test]
+fn no_variables_are_set_if_any_value_in_a_bulk_import_is_invalid() {
+    let content = "\
+VAR coins = 3
+CONST max_coins = 99
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(matches!(
+        story.set_variables(vec![
+            ("coins", Variable::Int(5)),
+            ("coins", Variable::Bool(true))
+        ]),
+        Err(InklingError::VariableError(..))
+    ));
+
+    assert!(matches!(
+        story.set_variables(vec![
+            ("coins", Variable::Int(5)),
+            ("max_coins", Variable::Int(5))
+        ]),
+        Err(InklingError::AssignedToConst { .. })
+    ));
+
+    assert!(matches!(
+        story.set_variables(vec![
+            ("coins", Variable::Int(5)),
+            ("gems", Variable::Int(5))
+        ]),
+        Err(InklingError::InvalidVariable { .. })
+    ));
+
+    assert_eq!(story.get_variable("coins"), Some(Variable::Int(3)));
+}
@@ -21,7 +21,7 @@ He befriended thousands of climbers and children sightseeing in Switzerland.
     let mut line_buffer = Vec::new();
 
     match story.resume(&mut line_buffer) {
-        Ok(Prompt::Done) => {
+        Ok(Prompt::Done(..)) => {
             assert_eq!(line_buffer.len(), 2);
         }
         _ => panic!("error while reading a flat story from string"),
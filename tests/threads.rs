@@ -0,0 +1,244 @@
+use inkling::error::parse::address::InvalidAddressErrorKind;
+use inkling::*;
+
+#[test]
+fn threads_gather_text_and_choices_from_other_knots_into_the_story() {
+    let content = "\
+-> hallway
+
+=== hallway ===
+You stand in the hallway.
+<- guard
+The lights flicker.
+*   [Leave] You leave the hallway.
+    -> END
+
+=== guard ===
+A guard watches you.
+*   [Greet the guard] You greet the guard.
+    -> END
+*   [Bribe the guard] You bribe the guard.
+    -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You stand in the hallway.\n");
+    assert_eq!(&line_buffer[1].text, "A guard watches you.\n");
+    assert_eq!(&line_buffer[2].text, "The lights flicker.\n");
+
+    assert_eq!(choices.len(), 3);
+    assert_eq!(&choices[0].text, "Greet the guard");
+    assert_eq!(&choices[1].text, "Bribe the guard");
+    assert_eq!(&choices[2].text, "Leave");
+}
+
+#[test]
+fn choices_from_threads_continue_the_story_from_where_they_were_gathered() {
+    let content = "\
+-> hallway
+
+=== hallway ===
+<- guard
+*   [Leave] You leave the hallway.
+    -> END
+
+=== guard ===
+*   [Greet the guard] You greet the guard.
+    The guard nods.
+    -> END
+*   [Bribe the guard] You bribe the guard.
+    -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    let prompt = story.resume(&mut line_buffer).unwrap();
+
+    assert!(prompt.get_choices().is_none());
+    assert_eq!(&line_buffer[0].text, "You bribe the guard.\n");
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(2).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You leave the hallway.\n");
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You greet the guard.\n");
+    assert_eq!(&line_buffer[1].text, "The guard nods.\n");
+}
+
+#[test]
+fn threads_can_be_given_arguments_and_keep_the_temporary_variables_of_the_story() {
+    let content = "\
+~ temp place = \"the hallway\"
+<- visitor(\"Ada\")
+You are in {place}.
+*   [Wait] -> END
+
+=== visitor(name) ===
+{name} walks in.
+*   [Talk to {name}] You talk to {name}.
+    -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Ada walks in.\n");
+    assert_eq!(&line_buffer[1].text, "You are in the hallway.\n");
+
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Talk to Ada");
+    assert_eq!(&choices[1].text, "Wait");
+
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You talk to Ada.\n");
+}
+
+#[test]
+fn choices_from_threads_are_presented_when_the_story_runs_out_of_content() {
+    let content = "\
+You look around.
+<- window
+<- door
+
+=== window ===
+*   [Open the window] The window creaks open.
+    -> END
+
+=== door ===
+*   [Open the door] The door swings open.
+    -> END
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Open the window");
+    assert_eq!(&choices[1].text, "Open the door");
+
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The door swings open.\n");
+    assert_eq!(story.get_ending(), Some(EndingKind::End));
+}
+
+#[test]
+fn threads_to_unknown_knots_or_inside_alternatives_yield_errors() {
+    let content = "\
+<- gaurd
+-> END
+
+=== guard ===
+*   [Greet the guard] -> END
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(error)) => {
+            assert_eq!(error.invalid_address_errors.len(), 1);
+
+            match &error.invalid_address_errors[0].kind {
+                InvalidAddressErrorKind::UnknownAddress { name, .. } => assert_eq!(name, "gaurd"),
+                other => panic!("expected `UnknownAddress` but got {:?}", other),
+            }
+        }
+        other => panic!(
+            "expected `ReadError::ValidationError` but got {:?}",
+            other.err()
+        ),
+    }
+
+    let content = "\
+{&
+    - <- guard
+    - Nothing happens.
+}
+
+=== guard ===
+*   [Greet the guard] -> END
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn threads_which_divert_to_done_only_end_the_thread_while_end_ends_the_story() {
+    let content = "\
+<- lamp
+You wait in the dark.
+*   [Leave] -> END
+
+=== lamp ===
+The lamp flickers.
+-> DONE
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The lamp flickers.\n");
+    assert_eq!(&line_buffer[1].text, "You wait in the dark.\n");
+    assert_eq!(choices.len(), 1);
+
+    let content = content.replace("-> DONE", "-> END");
+
+    let mut story = read_story_from_string(&content).unwrap();
+    line_buffer.clear();
+
+    let prompt = story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(prompt, Prompt::Done(EndingKind::End));
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "The lamp flickers.\n");
+}